@@ -83,7 +83,10 @@ impl GuestFunction {
     /// See also [CallFromGuest] and [CallFromHost]. The latter is implemented
     /// for [GuestFunction] using this method.
     pub fn call(self, env: &mut Environment) {
-        log_dbg!("Begin call to guest function {:?}", self);
+        log_dbg!(
+            "Begin call to guest function {}",
+            env.symbolicate(self.addr_with_thumb_bit())
+        );
 
         let (old_pc, old_lr) = env
             .cpu
@@ -112,7 +115,10 @@ impl GuestFunction {
         regs[Cpu::SP] = old_sp;
         regs[FRAME_POINTER] = old_fp;
 
-        log_dbg!("End call to guest function {:?}", self);
+        log_dbg!(
+            "End call to guest function {}",
+            env.symbolicate(self.addr_with_thumb_bit())
+        );
     }
 
     /// Like [Self::call], but doesn't push a new guest stack frame. This is not
@@ -123,8 +129,8 @@ impl GuestFunction {
     /// pass-through.
     pub fn call_without_pushing_stack_frame(self, env: &mut Environment) {
         log_dbg!(
-            "Begin call to guest function {:?} (no new stack frame)",
-            self
+            "Begin call to guest function {} (no new stack frame)",
+            env.symbolicate(self.addr_with_thumb_bit())
         );
 
         let (old_pc, old_lr) = env
@@ -137,8 +143,8 @@ impl GuestFunction {
         env.cpu.regs_mut()[Cpu::LR] = old_lr.addr_with_thumb_bit();
 
         log_dbg!(
-            "End call to guest function {:?} (no stack frame popped)",
-            self
+            "End call to guest function {} (no stack frame popped)",
+            env.symbolicate(self.addr_with_thumb_bit())
         );
     }
 }
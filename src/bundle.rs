@@ -16,7 +16,7 @@ use plist::dictionary::Dictionary;
 use plist::Value;
 use std::io::Cursor;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Bundle {
     path: GuestPathBuf,
     plist: Dictionary,
@@ -0,0 +1,308 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! The guest's clock, and control of the emulation speed.
+//!
+//! Everything that tells the app what time it is, or waits for some time to
+//! pass on its behalf (`mach_absolute_time`, `gettimeofday`, `NSTimer`,
+//! [crate::Environment::sleep] etc), should use this rather than the host's
+//! clock, so that the app's sense of time can be scaled: sped up for
+//! fast-forward, slowed down, or stopped entirely while emulation is paused.
+//!
+//! Guest time is represented with host types: [Clock::now] returns an
+//! [Instant] which moves at the emulation speed, and [Clock::system_time]
+//! similarly for [SystemTime]. Durations measured in guest time must be
+//! converted with [Clock::host_duration] before the host waits for them.
+
+use std::time::{Duration, Instant, SystemTime};
+
+/// The host's monotonic clock. Tests use a fake one instead, so they don't
+/// depend on how long things take to run.
+#[cfg(not(test))]
+fn host_now() -> Instant {
+    Instant::now()
+}
+#[cfg(test)]
+use tests::host_now;
+
+/// Speed used while fast-forward is on.
+const FAST_FORWARD_SPEED: f64 = 4.0;
+/// Speed used while slow motion is on.
+const SLOW_MOTION_SPEED: f64 = 0.5;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum SpeedMode {
+    /// The speed from the `--speed=` option.
+    Normal,
+    FastForward,
+    SlowMotion,
+}
+
+pub struct Clock {
+    /// Host time at startup, i.e. the value of [Self::now] at startup.
+    startup_instant: Instant,
+    /// Host wall-clock time at startup.
+    startup_system_time: SystemTime,
+    /// Host time as of the last change of speed.
+    host_anchor: Instant,
+    /// Guest time elapsed since startup as of [Self::host_anchor].
+    guest_anchor: Duration,
+    /// Speed when no hotkeys have been used (see `--speed=`).
+    normal_speed: f64,
+    mode: SpeedMode,
+    paused: bool,
+    /// Set when advancing a single frame: emulation is paused again once the
+    /// app presents a frame.
+    pause_after_frame: bool,
+}
+impl Clock {
+    pub fn new(normal_speed: f64) -> Clock {
+        let startup_instant = host_now();
+        if normal_speed != 1.0 {
+            echo!("Emulation speed: {}×.", normal_speed);
+        }
+        Clock {
+            startup_instant,
+            startup_system_time: SystemTime::now(),
+            host_anchor: startup_instant,
+            guest_anchor: Duration::ZERO,
+            normal_speed,
+            mode: SpeedMode::Normal,
+            paused: false,
+            pause_after_frame: false,
+        }
+    }
+
+    fn speed(&self) -> f64 {
+        match self.mode {
+            SpeedMode::Normal => self.normal_speed,
+            SpeedMode::FastForward => FAST_FORWARD_SPEED,
+            SpeedMode::SlowMotion => SLOW_MOTION_SPEED,
+        }
+    }
+
+    /// Guest time elapsed since startup.
+    pub fn elapsed(&self) -> Duration {
+        if self.paused {
+            return self.guest_anchor;
+        }
+        let host_elapsed = host_now().saturating_duration_since(self.host_anchor);
+        self.guest_anchor + host_elapsed.mul_f64(self.speed())
+    }
+
+    /// Current guest time, as a monotonic clock.
+    pub fn now(&self) -> Instant {
+        self.startup_instant + self.elapsed()
+    }
+
+    /// Current guest time, as a wall clock.
+    pub fn system_time(&self) -> SystemTime {
+        self.startup_system_time + self.elapsed()
+    }
+
+    /// Convert a duration in guest time to how long the host would have to
+    /// wait for that much guest time to pass.
+    pub fn host_duration(&self, guest_duration: Duration) -> Duration {
+        guest_duration.div_f64(self.speed())
+    }
+
+    /// Returns [true] if guest time isn't passing at the same rate as real
+    /// time, i.e. the speed has been changed or emulation is paused.
+    pub fn is_time_scaled(&self) -> bool {
+        self.paused || self.speed() != 1.0
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Change the speed or pause state. This must be used for all such changes
+    /// so that guest time is continuous.
+    fn update(&mut self, mode: SpeedMode, paused: bool) {
+        self.guest_anchor = self.elapsed();
+        self.host_anchor = host_now();
+        self.mode = mode;
+        self.paused = paused;
+        if paused {
+            echo!("Emulation paused (speed: {}×).", self.speed());
+        } else {
+            echo!("Emulation speed: {}×.", self.speed());
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.pause_after_frame = false;
+        self.update(self.mode, !self.paused);
+    }
+
+    /// Resume emulation if it is paused, e.g. so the app can handle a request
+    /// to quit.
+    pub fn resume(&mut self) {
+        self.pause_after_frame = false;
+        if self.paused {
+            self.update(self.mode, false);
+        }
+    }
+
+    /// Run until the app presents its next frame, then pause.
+    pub fn advance_frame(&mut self) {
+        self.pause_after_frame = true;
+        if self.paused {
+            self.update(self.mode, false);
+        }
+    }
+
+    pub fn toggle_fast_forward(&mut self) {
+        let mode = match self.mode {
+            SpeedMode::FastForward => SpeedMode::Normal,
+            _ => SpeedMode::FastForward,
+        };
+        self.update(mode, self.paused);
+    }
+
+    pub fn toggle_slow_motion(&mut self) {
+        let mode = match self.mode {
+            SpeedMode::SlowMotion => SpeedMode::Normal,
+            _ => SpeedMode::SlowMotion,
+        };
+        self.update(mode, self.paused);
+    }
+
+    /// Jump to a guest time previously returned by [Self::elapsed], when a save
+    /// state is loaded (see [crate::snapshot]). The speed and pause state are
+    /// kept.
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.guest_anchor = elapsed;
+        self.host_anchor = host_now();
+    }
+
+    /// Call this each time the app presents a frame.
+    pub fn frame_presented(&mut self) {
+        if self.pause_after_frame {
+            self.pause_after_frame = false;
+            self.update(self.mode, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        /// Fake host time for [host_now]. Each test runs on its own thread, so
+        /// each gets its own clock.
+        static FAKE_HOST_NOW: Cell<Option<Instant>> = const { Cell::new(None) };
+    }
+
+    pub(super) fn host_now() -> Instant {
+        FAKE_HOST_NOW.with(|now| {
+            let instant = now.get().unwrap_or_else(Instant::now);
+            now.set(Some(instant));
+            instant
+        })
+    }
+
+    fn advance_host_time(duration: Duration) {
+        FAKE_HOST_NOW.with(|now| now.set(Some(host_now() + duration)));
+    }
+
+    #[test]
+    fn elapsed_under_scaling() {
+        let mut clock = Clock::new(1.0);
+        let second = Duration::from_secs(1);
+        advance_host_time(second);
+        assert_eq!(clock.elapsed(), second);
+
+        // Changing the speed mustn't make time jump.
+        clock.toggle_fast_forward();
+        assert_eq!(clock.elapsed(), second);
+        advance_host_time(second);
+        let elapsed = second + second.mul_f64(FAST_FORWARD_SPEED);
+        assert_eq!(clock.elapsed(), elapsed);
+
+        clock.toggle_slow_motion();
+        assert_eq!(clock.elapsed(), elapsed);
+        advance_host_time(second);
+        let elapsed = elapsed + second.mul_f64(SLOW_MOTION_SPEED);
+        assert_eq!(clock.elapsed(), elapsed);
+
+        // now() and system_time() move with elapsed().
+        assert_eq!(clock.now(), clock.startup_instant + elapsed);
+        assert_eq!(clock.system_time(), clock.startup_system_time + elapsed);
+    }
+
+    #[test]
+    fn pause() {
+        let mut clock = Clock::new(1.0);
+        advance_host_time(Duration::from_secs(1));
+        clock.toggle_pause();
+        assert!(clock.is_paused());
+        assert!(clock.is_time_scaled());
+        let elapsed = clock.elapsed();
+        let now = clock.now();
+        advance_host_time(Duration::from_secs(60));
+        assert_eq!(clock.elapsed(), elapsed);
+        assert_eq!(clock.now(), now);
+
+        clock.toggle_pause();
+        assert!(!clock.is_paused());
+        assert!(!clock.is_time_scaled());
+        assert_eq!(clock.elapsed(), elapsed);
+        advance_host_time(Duration::from_secs(1));
+        assert_eq!(clock.elapsed(), elapsed + Duration::from_secs(1));
+    }
+
+    #[test]
+    fn advance_frame() {
+        let mut clock = Clock::new(1.0);
+        clock.toggle_pause();
+        let elapsed = clock.elapsed();
+
+        clock.advance_frame();
+        assert!(!clock.is_paused());
+        advance_host_time(Duration::from_millis(16));
+        clock.frame_presented();
+        assert!(clock.is_paused());
+        let elapsed_after_frame = clock.elapsed();
+        assert_eq!(elapsed_after_frame, elapsed + Duration::from_millis(16));
+
+        // Only a single frame is advanced.
+        advance_host_time(Duration::from_millis(16));
+        clock.frame_presented();
+        assert!(clock.is_paused());
+        assert_eq!(clock.elapsed(), elapsed_after_frame);
+
+        // Unpausing cancels it.
+        clock.advance_frame();
+        clock.toggle_pause();
+        assert!(clock.is_paused());
+        clock.toggle_pause();
+        clock.frame_presented();
+        assert!(!clock.is_paused());
+    }
+
+    #[test]
+    fn host_duration() {
+        let mut clock = Clock::new(1.0);
+        let second = Duration::from_secs(1);
+        assert_eq!(clock.host_duration(second), second);
+        clock.toggle_fast_forward();
+        assert_eq!(
+            clock.host_duration(second),
+            second.div_f64(FAST_FORWARD_SPEED)
+        );
+        clock.toggle_slow_motion();
+        assert_eq!(
+            clock.host_duration(second),
+            second.div_f64(SLOW_MOTION_SPEED)
+        );
+
+        let clock = Clock::new(2.0);
+        assert!(clock.is_time_scaled());
+        assert_eq!(clock.host_duration(second), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Static compatibility report (`--compat-report`).
+//!
+//! This loads and links the app binary without running it, and checks which
+//! of the functions, constants, Objective-C classes and selectors it
+//! references are implemented by touchHLE (or by the dylibs bundled with it).
+//! The result is printed to stdout as JSON, so that it can be processed by
+//! scripts, e.g. to triage a whole folder of apps.
+//!
+//! Keep in mind that this is only an approximation: having an implementation
+//! of everything doesn't mean an app will work, and apps often reference
+//! things they never use.
+
+use crate::bundle::Bundle;
+use crate::dyld::{self, Dyld};
+use crate::fs::Fs;
+use crate::mach_o::{MachO, SectionType};
+use crate::mem::{ConstPtr, Mem, Ptr};
+use crate::objc::ObjC;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Used for symbols that don't say which library they're from.
+const UNKNOWN_LIBRARY: &str = "(unknown)";
+
+#[derive(Default)]
+struct Items {
+    implemented: BTreeSet<String>,
+    missing: BTreeSet<String>,
+}
+impl Items {
+    fn add(&mut self, name: &str, implemented: bool) {
+        if implemented {
+            self.implemented.insert(name.to_string());
+        } else {
+            self.missing.insert(name.to_string());
+        }
+    }
+}
+
+/// Items imported from a particular library (usually a framework).
+#[derive(Default)]
+struct Library {
+    functions: Items,
+    /// Constants and other non-function symbols.
+    data: Items,
+    classes: Items,
+}
+
+/// Produce the report and print it to stdout.
+pub fn print_report(bundle: &Bundle, fs: &Fs) -> Result<(), String> {
+    let mut mem = Mem::new();
+    let bins = dyld::load_bins(&bundle.executable_path(), fs, &mut mem)?;
+
+    // Linking registers the app's selectors and classes, which is needed to
+    // know which selectors the app implements itself.
+    let mut objc = ObjC::new();
+    Dyld::new(/* stub_unimplemented: */ false).do_initial_linking(&bins, &mut mem, &mut objc);
+
+    let app = &bins[0];
+    let dylibs = &bins[1..];
+    let exported_by_dylib = |symbol: &str| -> bool {
+        dylibs
+            .iter()
+            .any(|dylib| dylib.exported_symbols.contains_key(symbol))
+    };
+
+    let mut libraries: BTreeMap<&str, Library> = BTreeMap::new();
+
+    // Functions are called via stubs, which are linked lazily.
+    for section in &app.sections {
+        if section.type_ != SectionType::SymbolStubs {
+            continue;
+        }
+        let info = section.dyld_indirect_symbol_info.as_ref().unwrap();
+        for symbol in info.indirect_undef_symbols.iter().flatten() {
+            let implemented = dyld::has_host_function(symbol) || exported_by_dylib(symbol);
+            library_for(&mut libraries, app, symbol)
+                .functions
+                .add(symbol, implemented);
+        }
+    }
+
+    // Constants etc are linked upfront, see Dyld::do_non_lazy_linking.
+    if let Some(section) = app.get_section(SectionType::NonLazySymbolPointers) {
+        let info = section.dyld_indirect_symbol_info.as_ref().unwrap();
+        for symbol in info.indirect_undef_symbols.iter().flatten() {
+            let implemented = dyld::has_host_constant(symbol) || exported_by_dylib(symbol);
+            library_for(&mut libraries, app, symbol)
+                .data
+                .add(symbol, implemented);
+        }
+    }
+    for (_, symbol) in &app.external_relocations {
+        if let Some(class) = symbol
+            .strip_prefix("_OBJC_CLASS_$_")
+            .or_else(|| symbol.strip_prefix("_OBJC_METACLASS_$_"))
+        {
+            library_for(&mut libraries, app, symbol)
+                .classes
+                .add(class, ObjC::has_host_class(class));
+        } else {
+            // This is the only other kind of external relocation that is
+            // currently handled.
+            let implemented = symbol == "___CFConstantStringClassReference";
+            library_for(&mut libraries, app, symbol)
+                .data
+                .add(symbol, implemented);
+        }
+    }
+
+    let mut selectors = Items::default();
+    if let Some(selrefs) = app.get_section("__objc_selrefs") {
+        let base: ConstPtr<ConstPtr<u8>> = Ptr::from_bits(selrefs.addr);
+        for i in 0..(selrefs.size / 4) {
+            let selector = mem.cstr_at_utf8(mem.read(base + i)).unwrap();
+            selectors.add(selector, objc.has_method_for_selector(selector, &mem));
+        }
+    }
+
+    println!("{}", to_json(bundle, &libraries, &selectors));
+    Ok(())
+}
+
+fn library_for<'a, 'b>(
+    libraries: &'b mut BTreeMap<&'a str, Library>,
+    app: &'a MachO,
+    symbol: &str,
+) -> &'b mut Library {
+    let library = app
+        .symbol_libraries
+        .get(symbol)
+        .map_or(UNKNOWN_LIBRARY, |library| library.as_str());
+    libraries.entry(library).or_default()
+}
+
+fn json_string(string: &str) -> String {
+    let mut res = String::with_capacity(string.len() + 2);
+    res.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(res, "\\u{:04x}", c as u32).unwrap(),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn json_string_list<'a>(strings: impl Iterator<Item = &'a String>, indent: &str) -> String {
+    let strings: Vec<String> = strings
+        .map(|string| format!("{}  {}", indent, json_string(string)))
+        .collect();
+    if strings.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n{}]", strings.join(",\n"), indent)
+    }
+}
+
+fn items_to_json(items: &Items, indent: &str) -> String {
+    let inner_indent = format!("{}  ", indent);
+    format!(
+        "{{\n{i}\"implemented\": {},\n{i}\"missing\": {}\n{}}}",
+        json_string_list(items.implemented.iter(), &inner_indent),
+        json_string_list(items.missing.iter(), &inner_indent),
+        indent,
+        i = inner_indent,
+    )
+}
+
+fn to_json(bundle: &Bundle, libraries: &BTreeMap<&str, Library>, selectors: &Items) -> String {
+    let (mut implemented_count, mut missing_count) = (0, 0);
+    let mut count = |items: &Items| {
+        implemented_count += items.implemented.len();
+        missing_count += items.missing.len();
+    };
+
+    let mut libraries_json = Vec::new();
+    for (&name, library) in libraries {
+        count(&library.functions);
+        count(&library.data);
+        count(&library.classes);
+        libraries_json.push(format!(
+            "    {}: {{\n      \"functions\": {},\n      \"data\": {},\n      \"classes\": {}\n    }}",
+            json_string(name),
+            items_to_json(&library.functions, "      "),
+            items_to_json(&library.data, "      "),
+            items_to_json(&library.classes, "      "),
+        ));
+    }
+    count(selectors);
+
+    let mut json = String::new();
+    writeln!(json, "{{").unwrap();
+    writeln!(json, "  \"app\": {{").unwrap();
+    writeln!(
+        json,
+        "    \"name\": {},",
+        json_string(bundle.display_name())
+    )
+    .unwrap();
+    writeln!(
+        json,
+        "    \"identifier\": {},",
+        json_string(bundle.bundle_identifier())
+    )
+    .unwrap();
+    writeln!(
+        json,
+        "    \"version\": {}",
+        json_string(bundle.bundle_version())
+    )
+    .unwrap();
+    writeln!(json, "  }},").unwrap();
+    writeln!(json, "  \"summary\": {{").unwrap();
+    writeln!(json, "    \"implemented\": {},", implemented_count).unwrap();
+    writeln!(json, "    \"missing\": {}", missing_count).unwrap();
+    writeln!(json, "  }},").unwrap();
+    if libraries_json.is_empty() {
+        writeln!(json, "  \"libraries\": {{}},").unwrap();
+    } else {
+        writeln!(
+            json,
+            "  \"libraries\": {{\n{}\n  }},",
+            libraries_json.join(",\n")
+        )
+        .unwrap();
+    }
+    writeln!(json, "  \"selectors\": {}", items_to_json(selectors, "  ")).unwrap();
+    write!(json, "}}").unwrap();
+    json
+}
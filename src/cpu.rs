@@ -96,6 +96,9 @@ pub struct Cpu {
     /// Copy of the direct memory access pointer used to check it has not
     /// changed. If this is null, direct memory access is not in use.
     direct_memory_access_ptr: *const std::ffi::c_void,
+    /// Total number of ticks expended by [Self::run_or_step] so far, across all
+    /// threads. See [Self::elapsed_ticks].
+    elapsed_ticks: u64,
 }
 
 impl Drop for Cpu {
@@ -115,6 +118,12 @@ impl CpuContext {
         CpuContext { context }
     }
 }
+impl Clone for CpuContext {
+    fn clone(&self) -> Self {
+        let context = unsafe { touchHLE_DynarmicWrapper_Context_clone(self.context) };
+        CpuContext { context }
+    }
+}
 impl Drop for CpuContext {
     fn drop(&mut self) {
         unsafe { touchHLE_DynarmicWrapper_Context_delete(self.context) }
@@ -176,6 +185,7 @@ impl Cpu {
         Cpu {
             dynarmic_wrapper,
             direct_memory_access_ptr,
+            elapsed_ticks: 0,
         }
     }
 
@@ -226,6 +236,21 @@ impl Cpu {
         unsafe { touchHLE_DynarmicWrapper_swap_context(self.dynarmic_wrapper, context.context) }
     }
 
+    /// Get a copy of the current state of the CPU (registers etc).
+    pub fn save_context(&mut self) -> CpuContext {
+        let mut context = CpuContext::new();
+        self.swap_context(&mut context);
+        let copy = context.clone();
+        self.swap_context(&mut context);
+        copy
+    }
+
+    /// Replace the current state of the CPU (registers etc) with a copy of the
+    /// state stored in the context object.
+    pub fn load_context(&mut self, context: &CpuContext) {
+        self.swap_context(&mut context.clone());
+    }
+
     /// Get PC with the Thumb bit appropriately set.
     pub fn pc_with_thumb_bit(&self) -> GuestFunction {
         let pc = self.regs()[Self::PC];
@@ -264,6 +289,14 @@ impl Cpu {
         }
     }
 
+    /// Get the total number of ticks the CPU has expended so far. This is an
+    /// abstract measure of emulated time: unlike the host's clock, it only
+    /// advances when guest code is executed, so it can be used to reproduce
+    /// the timing of input events (see [crate::window::Window::pop_event]).
+    pub fn elapsed_ticks(&self) -> u64 {
+        self.elapsed_ticks
+    }
+
     /// Start CPU execution.
     ///
     /// If `ticks` is [Some], it is used as an abstract time limit. The value
@@ -275,19 +308,25 @@ impl Cpu {
     /// This will return either because the CPU ran out of time, or because
     /// something else happened which requires attention from the host.
     #[must_use]
-    pub fn run_or_step(&mut self, mem: &mut Mem, ticks: Option<&mut u64>) -> CpuState {
+    pub fn run_or_step(&mut self, mem: &mut Mem, mut ticks: Option<&mut u64>) -> CpuState {
         // See ::new() for why this is done.
         if !self.direct_memory_access_ptr.is_null() {
             assert!(self.direct_memory_access_ptr == unsafe { mem.direct_memory_access_ptr() });
         }
 
+        let ticks_before = ticks.as_deref().copied();
         let res = unsafe {
             touchHLE_DynarmicWrapper_run_or_step(
                 self.dynarmic_wrapper,
                 mem as *mut Mem as *mut touchHLE_Mem,
-                ticks,
+                ticks.as_deref_mut(),
             )
         };
+        self.elapsed_ticks += match (ticks_before, ticks) {
+            (Some(before), Some(after)) => before - *after,
+            // Stepping executes a single instruction.
+            _ => 1,
+        };
         match res {
             -1 => CpuState::Normal,
             -2 => CpuState::Error(CpuError::MemoryError),
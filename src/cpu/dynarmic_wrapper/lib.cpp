@@ -254,6 +254,10 @@ std::int32_t touchHLE_DynarmicWrapper_run_or_step(DynarmicWrapper *cpu,
 void *touchHLE_DynarmicWrapper_Context_new() {
   return (void *)new Dynarmic::A32::Context();
 }
+void *touchHLE_DynarmicWrapper_Context_clone(const void *context) {
+  return (void *)new Dynarmic::A32::Context(
+      *(const Dynarmic::A32::Context *)context);
+}
 void touchHLE_DynarmicWrapper_Context_delete(void *context) {
   delete (Dynarmic::A32::Context *)context;
 }
@@ -50,5 +50,8 @@ extern "C" {
     ) -> i32;
 
     pub fn touchHLE_DynarmicWrapper_Context_new() -> *mut Dynarmic_A32_Context;
+    pub fn touchHLE_DynarmicWrapper_Context_clone(
+        context: *const Dynarmic_A32_Context,
+    ) -> *mut Dynarmic_A32_Context;
     pub fn touchHLE_DynarmicWrapper_Context_delete(context: *mut Dynarmic_A32_Context);
 }
@@ -26,11 +26,12 @@ mod function_lists;
 use crate::abi::{CallFromGuest, GuestFunction};
 use crate::cpu::Cpu;
 use crate::frameworks::foundation::ns_string;
+use crate::fs::{Fs, GuestPath};
 use crate::mach_o::{MachO, SectionType};
 use crate::mem::{ConstVoidPtr, GuestUSize, Mem, MutPtr, Ptr};
 use crate::objc::{nil, ObjC};
 use crate::Environment;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 type HostFunction = &'static dyn CallFromGuest;
 
@@ -126,6 +127,60 @@ pub fn search_lists<T>(
         .find(|&(sym, _)| *sym == symbol)
 }
 
+/// Load the app binary and the dynamic libraries it depends on, other than
+/// those we have host implementations of. The app binary is always the first
+/// item in the result.
+pub fn load_bins(
+    executable_path: &GuestPath,
+    fs: &Fs,
+    mem: &mut Mem,
+) -> Result<Vec<MachO>, String> {
+    let executable = MachO::load_from_file(executable_path, fs, mem)
+        .map_err(|e| format!("Could not load executable: {}", e))?;
+
+    let mut dylibs = Vec::new();
+    for dylib in &executable.dynamic_libraries {
+        if dylib == "/usr/lib/libSystem.B.dylib" || dylib == "/usr/lib/libobjc.A.dylib" {
+            // We have host implementations of these
+            continue;
+        }
+
+        // There are some Free Software libraries bundled with touchHLE and
+        // exposed via the guest file system (see Fs::new()).
+        if fs.is_file(GuestPath::new(dylib)) {
+            let dylib = MachO::load_from_file(GuestPath::new(dylib), fs, mem)
+                .map_err(|e| format!("Could not load bundled dylib: {}", e))?;
+            dylibs.push(dylib);
+        } else {
+            // System frameworks will have host implementations.
+            // TODO: warn about unimplemented frameworks?
+            if !dylib.starts_with("/System/Library/Frameworks/") {
+                log!(
+                    "Warning: app binary depends on unexpected dylib \"{}\"",
+                    dylib
+                );
+            }
+            continue;
+        };
+    }
+
+    let mut bins = dylibs;
+    bins.insert(0, executable);
+    Ok(bins)
+}
+
+/// For use by [crate::compat_report]: is there a host implementation of this
+/// function? The name must be the mangled symbol name.
+pub fn has_host_function(symbol: &str) -> bool {
+    search_lists(function_lists::FUNCTION_LISTS, symbol).is_some()
+}
+
+/// For use by [crate::compat_report]: is there a host implementation of this
+/// constant? The name must be the mangled symbol name.
+pub fn has_host_constant(symbol: &str) -> bool {
+    search_lists(constant_lists::CONSTANT_LISTS, symbol).is_some()
+}
+
 fn encode_a32_svc(imm: u32) -> u32 {
     assert!(imm & 0xff000000 == 0);
     imm | 0xef000000
@@ -145,7 +200,44 @@ pub struct Dyld {
     linked_host_functions: Vec<(&'static str, HostFunction)>,
     return_to_host_routine: Option<GuestFunction>,
     constants_to_link_later: Vec<(MutPtr<ConstVoidPtr>, &'static HostConstant)>,
+    /// If [true], calls to functions we don't have an implementation of are
+    /// linked to [unimplemented_function_stub] rather than causing a panic.
+    /// See [crate::options::Options::stub_unimplemented].
+    stub_unimplemented: bool,
+    /// Unimplemented functions that have already been called and logged, see
+    /// [unimplemented_function_stub].
+    stubs_called: HashSet<&'static str>,
+}
+
+/// Host function used in place of functions we don't have an implementation
+/// of (see [Dyld::stub_unimplemented]). It behaves as if the function returned
+/// zero, and logs the first call.
+fn unimplemented_function_stub(env: &mut Environment) {
+    // This is shared by all unimplemented functions, so the SVC ID is the only
+    // way to know which one this is.
+    let svc_pc = env.cpu.regs()[Cpu::PC] - 4;
+    let svc = env.mem.read(Ptr::<u32, false>::from_bits(svc_pc)) & 0xffffff;
+    let idx = (svc - Dyld::SVC_LINKED_FUNCTIONS_BASE) as usize;
+    let (symbol, _) = env.dyld.linked_host_functions[idx];
+
+    if env.dyld.stubs_called.insert(symbol) {
+        let regs = env.cpu.regs();
+        log!(
+            "Warning: call to unimplemented function {} from {}, r0-r3: {:#x}, {:#x}, {:#x}, {:#x}. Returning 0.",
+            symbol,
+            env.symbolicate(regs[Cpu::LR]),
+            regs[0],
+            regs[1],
+            regs[2],
+            regs[3],
+        );
+    }
+
+    // Two registers, in case it returns a 64-bit value.
+    env.cpu.regs_mut()[0..2].fill(0);
 }
+const UNIMPLEMENTED_FUNCTION_STUB: HostFunction =
+    &(unimplemented_function_stub as fn(&mut Environment));
 
 impl Dyld {
     /// We reserve this SVC ID for invoking the lazy linker.
@@ -159,11 +251,13 @@ impl Dyld {
     const SYMBOL_STUB_INSTRUCTIONS: [u32; 2] = [0xe59fc000, 0xe59cf000];
     const PIC_SYMBOL_STUB_INSTRUCTIONS: [u32; 3] = [0xe59fc004, 0xe08fc00c, 0xe59cf000];
 
-    pub fn new() -> Dyld {
+    pub fn new(stub_unimplemented: bool) -> Dyld {
         Dyld {
             linked_host_functions: Vec::new(),
             return_to_host_routine: None,
             constants_to_link_later: Vec::new(),
+            stub_unimplemented,
+            stubs_called: HashSet::new(),
         }
     }
 
@@ -415,27 +509,7 @@ impl Dyld {
         let symbol = info.indirect_undef_symbols[idx].as_deref().unwrap();
 
         if let Some(&(symbol, f)) = search_lists(function_lists::FUNCTION_LISTS, symbol) {
-            // Allocate an SVC ID for this host function
-            let idx: u32 = self.linked_host_functions.len().try_into().unwrap();
-            let svc = idx + Self::SVC_LINKED_FUNCTIONS_BASE;
-            self.linked_host_functions.push((symbol, f));
-
-            // Rewrite stub function to call this host function
-            let stub_function_ptr: MutPtr<u32> = Ptr::from_bits(svc_pc);
-            mem.write(stub_function_ptr, encode_a32_svc(svc));
-            assert!(mem.read(stub_function_ptr + 1) == encode_a32_ret());
-
-            cpu.invalidate_cache_range(stub_function_ptr.to_bits(), 4);
-
-            log_dbg!(
-                "Linked {} at {:?} to host implementation",
-                symbol,
-                stub_function_ptr
-            );
-
-            // Return the host function so that we can call it now that we're
-            // done.
-            return Some(f);
+            return Some(self.link_stub_to_host_function(mem, cpu, svc_pc, symbol, f));
         }
 
         for dylib in &bins[1..] {
@@ -482,9 +556,52 @@ impl Dyld {
             }
         }
 
+        if self.stub_unimplemented {
+            // The name has to live as long as the stub does.
+            let symbol: &'static str = Box::leak(symbol.to_string().into_boxed_str());
+            return Some(self.link_stub_to_host_function(
+                mem,
+                cpu,
+                svc_pc,
+                symbol,
+                UNIMPLEMENTED_FUNCTION_STUB,
+            ));
+        }
+
         panic!("Call to unimplemented function {}", symbol);
     }
 
+    /// Rewrite the stub function at `svc_pc` so it calls a host function, and
+    /// return that function so that it can be called now.
+    fn link_stub_to_host_function(
+        &mut self,
+        mem: &mut Mem,
+        cpu: &mut Cpu,
+        svc_pc: u32,
+        symbol: &'static str,
+        f: HostFunction,
+    ) -> HostFunction {
+        // Allocate an SVC ID for this host function
+        let idx: u32 = self.linked_host_functions.len().try_into().unwrap();
+        let svc = idx + Self::SVC_LINKED_FUNCTIONS_BASE;
+        self.linked_host_functions.push((symbol, f));
+
+        // Rewrite stub function to call this host function
+        let stub_function_ptr: MutPtr<u32> = Ptr::from_bits(svc_pc);
+        mem.write(stub_function_ptr, encode_a32_svc(svc));
+        assert!(mem.read(stub_function_ptr + 1) == encode_a32_ret());
+
+        cpu.invalidate_cache_range(stub_function_ptr.to_bits(), 4);
+
+        log_dbg!(
+            "Linked {} at {:?} to host implementation",
+            symbol,
+            stub_function_ptr
+        );
+
+        f
+    }
+
     /// Creates a guest function that will call a host function with the name
     /// `symbol`. This can be used to implement "get proc address" functions.
     /// Note that no attempt is made to deduplicate or deallocate these, so
@@ -12,10 +12,16 @@ use crate::libc;
 /// All the lists of constants that the linker should search through.
 pub const CONSTANT_LISTS: &[super::ConstantExports] = &[
     libc::ctype::CONSTANTS,
+    libc::semaphore::CONSTANTS,
     core_foundation::cf_allocator::CONSTANTS,
     core_foundation::cf_run_loop::CONSTANTS,
+    core_foundation::time::CONSTANTS,
     core_graphics::cg_affine_transform::CONSTANTS,
     core_graphics::cg_color_space::CONSTANTS,
+    foundation::ns_calendar::CONSTANTS,
+    foundation::ns_exception::CONSTANTS,
+    foundation::ns_keyed_archiver::CONSTANTS,
+    foundation::ns_keyed_unarchiver::CONSTANTS,
     foundation::ns_run_loop::CONSTANTS,
     media_player::movie_player::CONSTANTS,
     opengles::eagl::CONSTANTS,
@@ -16,16 +16,19 @@ pub const FUNCTION_LISTS: &[super::FunctionExports] = &[
     libc::ctype::FUNCTIONS,
     libc::cxxabi::FUNCTIONS,
     libc::dlfcn::FUNCTIONS,
+    libc::errno::FUNCTIONS,
     libc::keymgr::FUNCTIONS,
     libc::mach_thread_info::FUNCTIONS,
     libc::mach_time::FUNCTIONS,
     libc::math::FUNCTIONS,
     libc::posix_io::FUNCTIONS,
     libc::posix_io::stat::FUNCTIONS,
+    libc::pthread::cond::FUNCTIONS,
     libc::pthread::key::FUNCTIONS,
     libc::pthread::mutex::FUNCTIONS,
     libc::pthread::once::FUNCTIONS,
     libc::pthread::thread::FUNCTIONS,
+    libc::semaphore::FUNCTIONS,
     libc::setjmp::FUNCTIONS,
     libc::stdio::FUNCTIONS,
     libc::stdio::printf::FUNCTIONS,
@@ -43,6 +46,7 @@ pub const FUNCTION_LISTS: &[super::FunctionExports] = &[
     audio_toolbox::audio_session::FUNCTIONS,
     core_foundation::cf_array::FUNCTIONS,
     core_foundation::cf_bundle::FUNCTIONS,
+    core_foundation::cf_property_list::FUNCTIONS,
     core_foundation::cf_run_loop::FUNCTIONS,
     core_foundation::cf_string::FUNCTIONS,
     core_foundation::cf_type::FUNCTIONS,
@@ -50,9 +54,11 @@ pub const FUNCTION_LISTS: &[super::FunctionExports] = &[
     core_foundation::time::FUNCTIONS,
     core_graphics::cg_affine_transform::FUNCTIONS,
     core_graphics::cg_bitmap_context::FUNCTIONS,
+    core_graphics::cg_color::FUNCTIONS,
     core_graphics::cg_color_space::FUNCTIONS,
     core_graphics::cg_context::FUNCTIONS,
     core_graphics::cg_image::FUNCTIONS,
+    foundation::ns_exception::FUNCTIONS,
     foundation::ns_file_manager::FUNCTIONS,
     foundation::ns_log::FUNCTIONS,
     foundation::ns_util::FUNCTIONS,
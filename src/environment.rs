@@ -9,21 +9,55 @@
 //! via the re-exports one level up.
 
 use crate::{
-    abi, bundle, cpu, dyld, frameworks, fs, gdb, image, libc, mach_o, mem, objc, options, stack,
-    window,
+    abi, bundle, clock, cpu, dyld, frameworks, fs, gdb, image, libc, mach_o, mem, objc, options,
+    snapshot, stack, window,
 };
 use std::net::TcpListener;
 use std::time::{Duration, Instant};
 
+/// How often to check for input from the host while emulation is paused.
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(1000 / 60);
+
 /// Index into the [Vec] of threads. Thread 0 is always the main thread.
 pub type ThreadID = usize;
 
+/// Something a thread can be blocked waiting for. See [Environment::block].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThreadBlock {
+    /// Waiting to lock a mutex (`pthread_mutex_lock`).
+    Mutex(libc::pthread::mutex::MutexPtr),
+    /// Waiting to be signalled on a condition variable, after which the mutex
+    /// must be re-locked (`pthread_cond_wait`).
+    Condition(libc::pthread::cond::CondPtr, libc::pthread::mutex::MutexPtr),
+    /// Waiting for another thread to finish, after which its return value is
+    /// written to the pointer, if it isn't null (`pthread_join`).
+    Join(ThreadID, mem::MutPtr<mem::MutVoidPtr>),
+    /// Waiting to decrement a semaphore (`sem_wait`, `semaphore_wait`).
+    Semaphore(libc::semaphore::SemaphoreID),
+}
+
 /// Bookkeeping for a thread.
+#[derive(Clone)]
 pub struct Thread {
     /// Once a thread finishes, this is set to false.
     pub active: bool,
-    /// If this is not [None], the thread is sleeping until the specified time.
+    /// If this is not [None], the thread is sleeping until the specified guest
+    /// time (see [clock::Clock::now]).
     sleeping_until: Option<Instant>,
+    /// If this is not [None], the thread is blocked until the specified thing
+    /// happens. See [Environment::block].
+    pub blocked_by: Option<ThreadBlock>,
+    /// If this is not [None], the thread stops being blocked at the specified
+    /// guest time even if what it was waiting for didn't happen. The second
+    /// value is what the blocking function should return in that case.
+    pub blocked_until: Option<(Instant, u32)>,
+    /// If this is not [None], the thread's `r0` will be overwritten with this
+    /// value when it next runs, i.e. this overrides the return value of the
+    /// blocking function.
+    pending_return_value: Option<u32>,
+    /// The value returned by the thread's startup routine, once it has
+    /// finished.
+    return_value: Option<u32>,
     /// Set to [true] when a thread is running its startup routine (i.e. the
     /// function pointer passed to `pthread_create`). When it returns to the
     /// host, it should become inactive.
@@ -44,6 +78,12 @@ pub struct Thread {
     /// frame" of the thread is a host function, not whether there are any host
     /// functions at all.
     in_host_function: bool,
+    /// For each host-to-guest call in progress on this thread (see
+    /// [Environment::run_call]), the guest stack pointer at the start of the
+    /// call. If the stack pointer is ever above the last of these, the guest
+    /// code has done a non-local jump (`longjmp` or a C++ exception) past the
+    /// host function that made the call. See [UnwindHostFunction].
+    call_stack_pointers: Vec<u32>,
     /// Context object containing the CPU state for this thread.
     ///
     /// There should always be `(threads.len() - 1)` contexts in existence.
@@ -56,11 +96,22 @@ pub struct Thread {
     stack: Option<std::ops::RangeInclusive<u32>>,
 }
 
+/// Panic payload used to unwind the host stack when the execution of a host
+/// function is abandoned. This happens when the guest has jumped past a host
+/// function that called into guest code (see [Thread::call_stack_pointers]),
+/// or when host code does a non-local jump itself (see
+/// [Environment::abandon_host_function]).
+///
+/// This is raised with [std::panic::resume_unwind] so that it doesn't invoke
+/// the panic hook, and it is caught in [Environment::handle_cpu_state].
+struct UnwindHostFunction;
+
 /// The struct containing the entire emulator state. Methods are provided for
 /// execution and management of threads.
 pub struct Environment {
-    /// Reference point for various timing functions.
-    pub startup_time: Instant,
+    /// The guest's clock. Use this rather than the host's clock for anything
+    /// the app can observe.
+    pub clock: clock::Clock,
     pub bundle: bundle::Bundle,
     pub fs: fs::Fs,
     pub window: window::Window,
@@ -76,6 +127,8 @@ pub struct Environment {
     pub libc_state: libc::State,
     pub framework_state: frameworks::State,
     pub options: options::Options,
+    /// The save state slot, see [crate::snapshot].
+    pub saved_state: Option<snapshot::Snapshot>,
     gdb_server: Option<gdb::GdbServer>,
 }
 
@@ -98,7 +151,7 @@ impl Environment {
         fs: fs::Fs,
         options: options::Options,
     ) -> Result<Environment, String> {
-        let startup_time = Instant::now();
+        let clock = clock::Clock::new(options.speed);
 
         let icon = fs
             .read(bundle.icon_path())
@@ -137,36 +190,9 @@ impl Environment {
 
         let mut mem = mem::Mem::new();
 
-        let executable = mach_o::MachO::load_from_file(bundle.executable_path(), &fs, &mut mem)
-            .map_err(|e| format!("Could not load executable: {}", e))?;
-
-        let mut dylibs = Vec::new();
-        for dylib in &executable.dynamic_libraries {
-            if dylib == "/usr/lib/libSystem.B.dylib" || dylib == "/usr/lib/libobjc.A.dylib" {
-                // We have host implementations of these
-                continue;
-            }
-
-            // There are some Free Software libraries bundled with touchHLE and
-            // exposed via the guest file system (see Fs::new()).
-            if fs.is_file(fs::GuestPath::new(dylib)) {
-                let dylib = mach_o::MachO::load_from_file(fs::GuestPath::new(dylib), &fs, &mut mem)
-                    .map_err(|e| format!("Could not load bundled dylib: {}", e))?;
-                dylibs.push(dylib);
-            } else {
-                // System frameworks will have host implementations.
-                // TODO: warn about unimplemented frameworks?
-                if !dylib.starts_with("/System/Library/Frameworks/") {
-                    log!(
-                        "Warning: app binary depends on unexpected dylib \"{}\"",
-                        dylib
-                    );
-                }
-                continue;
-            };
-        }
+        let bins = dyld::load_bins(&bundle.executable_path(), &fs, &mut mem)?;
 
-        let entry_point_addr = executable.entry_point_pc.ok_or_else(|| {
+        let entry_point_addr = bins[0].entry_point_pc.ok_or_else(|| {
             "Mach-O file does not specify an entry point PC, perhaps it is not an executable?"
                 .to_string()
         })?;
@@ -174,12 +200,9 @@ impl Environment {
 
         log_dbg!("Address of start function: {:?}", entry_point_addr);
 
-        let mut bins = dylibs;
-        bins.insert(0, executable);
-
         let mut objc = objc::ObjC::new();
 
-        let mut dyld = dyld::Dyld::new();
+        let mut dyld = dyld::Dyld::new(options.stub_unimplemented);
         dyld.do_initial_linking(&bins, &mut mem, &mut objc);
 
         let cpu = cpu::Cpu::new(match options.direct_memory_access {
@@ -190,14 +213,19 @@ impl Environment {
         let main_thread = Thread {
             active: true,
             sleeping_until: None,
+            blocked_by: None,
+            blocked_until: None,
+            pending_return_value: None,
+            return_value: None,
             in_start_routine: false, // main thread never terminates
             in_host_function: false,
+            call_stack_pointers: Vec::new(),
             context: None,
             stack: Some(mem::Mem::MAIN_THREAD_STACK_LOW_END..=0u32.wrapping_sub(1)),
         };
 
         let mut env = Environment {
-            startup_time,
+            clock,
             bundle,
             fs,
             window,
@@ -211,11 +239,16 @@ impl Environment {
             libc_state: Default::default(),
             framework_state: Default::default(),
             options,
+            saved_state: None,
             gdb_server: None,
         };
 
         dyld::Dyld::do_late_linking(&mut env);
 
+        if env.clock.is_time_scaled() {
+            frameworks::set_audio_muted(&mut env, true);
+        }
+
         {
             let bin_path = env.bundle.executable_path();
             let bin_path_apple_key = format!("executable_path={}", bin_path.as_str());
@@ -243,9 +276,8 @@ impl Environment {
                 .accept()
                 .map_err(|e| format!("Could not accept connection: {}", e))?;
             echo!("Debugger client connected on {}.", client_addr);
-            let mut gdb_server = gdb::GdbServer::new(client);
-            gdb_server.wait_for_debugger(None, &mut env.cpu, &mut env.mem);
-            env.gdb_server = Some(gdb_server);
+            env.gdb_server = Some(gdb::GdbServer::new(client));
+            env.wait_for_debugger(None);
         }
 
         echo!("CPU emulation begins now.");
@@ -279,7 +311,41 @@ impl Environment {
         Ok(env)
     }
 
-    fn stack_trace(&self) {
+    /// Format a guest code address for human consumption, including the name
+    /// of the function or Objective-C method it belongs to if that can be
+    /// found, e.g. `0x2f5c (_main + 0x40)`.
+    pub fn symbolicate(&self, addr: u32) -> String {
+        let addr_without_thumb_bit = addr & !abi::GuestFunction::THUMB_BIT;
+        let Some((bin, section)) = self.bins.iter().find_map(|bin| {
+            bin.section_containing(addr_without_thumb_bit)
+                .map(|section| (bin, section))
+        }) else {
+            return format!("{:#x}", addr);
+        };
+
+        let mut nearest = mach_o::nearest_symbol(&bin.symbols, addr_without_thumb_bit);
+        // Method implementations are only known for the app binary.
+        if std::ptr::eq(bin, &self.bins[0]) {
+            let method = self.objc.nearest_method_symbol(addr_without_thumb_bit);
+            nearest = match (nearest, method) {
+                (Some(symbol), Some(method)) if symbol.0 >= method.0 => Some(symbol),
+                (symbol, None) => symbol,
+                (_, method) => method,
+            };
+        }
+
+        match nearest {
+            Some((sym_addr, name)) if sym_addr >= section.addr => format!(
+                "{:#x} ({} + {:#x})",
+                addr,
+                name,
+                addr_without_thumb_bit - sym_addr
+            ),
+            _ => format!("{:#x}", addr),
+        }
+    }
+
+    pub fn stack_trace(&self) {
         if self.current_thread == 0 {
             echo!("Attempting to produce stack trace for main thread:");
         } else {
@@ -290,8 +356,8 @@ impl Environment {
         }
         let stack_range = self.threads[self.current_thread].stack.clone().unwrap();
         echo!(
-            " 0. {:#x} (PC)",
-            self.cpu.pc_with_thumb_bit().addr_with_thumb_bit()
+            " 0. {} (PC)",
+            self.symbolicate(self.cpu.pc_with_thumb_bit().addr_with_thumb_bit())
         );
         let regs = self.cpu.regs();
         let mut lr = regs[cpu::Cpu::LR];
@@ -299,7 +365,7 @@ impl Environment {
         if lr == return_to_host_routine_addr {
             echo!(" 1. [host function] (LR)");
         } else {
-            echo!(" 1. {:#x} (LR)", lr);
+            echo!(" 1. {} (LR)", self.symbolicate(lr));
         }
         let mut i = 2;
         let mut fp: mem::ConstPtr<u8> = mem::Ptr::from_bits(regs[abi::FRAME_POINTER]);
@@ -313,7 +379,7 @@ impl Environment {
             if lr == return_to_host_routine_addr {
                 echo!("{:2}. [host function]", i);
             } else {
-                echo!("{:2}. {:#x}", i, lr);
+                echo!("{:2}. {}", i, self.symbolicate(lr));
             }
             i += 1;
         }
@@ -335,8 +401,13 @@ impl Environment {
         self.threads.push(Thread {
             active: true,
             sleeping_until: None,
+            blocked_by: None,
+            blocked_until: None,
+            pending_return_value: None,
+            return_value: None,
             in_start_routine: true,
             in_host_function: false,
+            call_stack_pointers: Vec::new(),
             context: Some(cpu::CpuContext::new()),
             stack: Some(stack_alloc.to_bits()..=(stack_high_addr - 1)),
         });
@@ -360,7 +431,66 @@ impl Environment {
         new_thread_id
     }
 
-    /// Put the current thread to sleep for some duration.
+    /// Poll for events from the host, see [window::Window::poll_for_events],
+    /// and apply any changes to the emulation speed that were requested.
+    ///
+    /// While emulation is paused, this doesn't return until it is resumed.
+    pub fn poll_for_events(&mut self) {
+        loop {
+            let was_time_scaled = self.clock.is_time_scaled();
+            self.window
+                .poll_for_events(&self.options, &mut self.clock, self.cpu.elapsed_ticks());
+            // Audio can't be slowed down or sped up along with everything
+            // else, so it's muted instead.
+            let is_time_scaled = self.clock.is_time_scaled();
+            if is_time_scaled != was_time_scaled {
+                frameworks::set_audio_muted(self, is_time_scaled);
+            }
+
+            if !self.clock.is_paused() {
+                break;
+            }
+            std::thread::sleep(PAUSED_POLL_INTERVAL);
+        }
+    }
+
+    /// For use by [crate::snapshot]: check that the host isn't in the middle of
+    /// anything a save state can't capture. Only the host stack of the main
+    /// thread's outermost run loop is known to be the same when a state is
+    /// loaded as when it was saved.
+    fn check_threads_snapshot_supported(&self) -> Result<(), String> {
+        if self.current_thread != 0 || !self.threads[0].call_stack_pointers.is_empty() {
+            return Err("the main thread isn't in its outermost run loop".to_string());
+        }
+        for (i, thread) in self.threads.iter().enumerate().skip(1) {
+            if thread.in_host_function || !thread.call_stack_pointers.is_empty() {
+                return Err(format!("thread {} is in the middle of a host function", i));
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the threads for a save state (see [crate::snapshot]), including the
+    /// CPU state of the main thread.
+    pub fn snapshot_threads(&mut self) -> Result<Vec<Thread>, String> {
+        self.check_threads_snapshot_supported()?;
+        let mut threads = self.threads.clone();
+        threads[0].context = Some(self.cpu.save_context());
+        Ok(threads)
+    }
+
+    /// Replace the threads with copies of those from a save state (see
+    /// [crate::snapshot]), including the CPU state of the main thread.
+    pub fn restore_threads(&mut self, saved: &[Thread]) -> Result<(), String> {
+        self.check_threads_snapshot_supported()?;
+        let mut threads = saved.to_vec();
+        let context = threads[0].context.take().unwrap();
+        self.cpu.load_context(&context);
+        self.threads = threads;
+        Ok(())
+    }
+
+    /// Put the current thread to sleep for some duration of guest time.
     /// Note that this only take effect once returning to [Self::run] or
     /// [Self::run_call], so do this just before a host function returns.
     pub fn sleep(&mut self, duration: Duration) {
@@ -371,10 +501,89 @@ impl Environment {
             self.current_thread,
             duration
         );
-        let until = Instant::now().checked_add(duration).unwrap();
+        let until = self.clock.now().checked_add(duration).unwrap();
         self.threads[self.current_thread].sleeping_until = Some(until);
     }
 
+    /// Block the current thread until something happens, or, if `timeout` is
+    /// not [None], until the specified guest time (see [clock::Clock::now]) has
+    /// been reached. In the latter case, the second value is returned from the
+    /// blocking function instead of whatever it returned originally.
+    ///
+    /// Like [Self::sleep], this only takes effect once returning to [Self::run]
+    /// or [Self::run_call], so do this just before a host function returns.
+    pub fn block(&mut self, block: ThreadBlock, timeout: Option<(Instant, u32)>) {
+        let thread = &mut self.threads[self.current_thread];
+        assert!(thread.blocked_by.is_none());
+
+        log_dbg!(
+            "Thread {} is blocking on {:?} (timeout: {:?}).",
+            self.current_thread,
+            block,
+            timeout
+        );
+        thread.blocked_by = Some(block);
+        thread.blocked_until = timeout;
+    }
+
+    /// Try to unblock a blocked thread: check if what it was waiting for has
+    /// happened (and if so, do whatever the blocking function promised, e.g.
+    /// locking a mutex), or if it has timed out. Returns [true] if the thread
+    /// is no longer blocked.
+    fn try_unblock(&mut self, thread: ThreadID) -> bool {
+        let block = self.threads[thread].blocked_by.unwrap();
+        let unblocked = match block {
+            ThreadBlock::Mutex(mutex) => {
+                libc::pthread::mutex::try_lock_for_blocked_thread(self, mutex, thread)
+            }
+            // Only pthread_cond_signal() and pthread_cond_broadcast() can end
+            // this kind of block, aside from a timeout.
+            ThreadBlock::Condition(..) => false,
+            ThreadBlock::Join(other_thread, value_ptr) => {
+                if self.threads[other_thread].active {
+                    false
+                } else {
+                    if !value_ptr.is_null() {
+                        let value = self.threads[other_thread].return_value.unwrap_or(0);
+                        self.mem.write(value_ptr, mem::Ptr::from_bits(value));
+                    }
+                    true
+                }
+            }
+            ThreadBlock::Semaphore(semaphore) => {
+                // Semaphores can't be destroyed while threads are waiting on
+                // them, see semaphore_destroy().
+                libc::semaphore::try_decrement(self, semaphore).unwrap()
+            }
+        };
+
+        if unblocked {
+            log_dbg!("Thread {} is no longer blocked on {:?}.", thread, block);
+            self.threads[thread].blocked_by = None;
+            self.threads[thread].blocked_until = None;
+            return true;
+        }
+
+        let Some((until, timeout_value)) = self.threads[thread].blocked_until else {
+            return false;
+        };
+        if until > self.clock.now() {
+            return false;
+        }
+
+        log_dbg!("Thread {} timed out while blocked on {:?}.", thread, block);
+        self.threads[thread].blocked_until = None;
+        self.threads[thread].pending_return_value = Some(timeout_value);
+        if let ThreadBlock::Condition(_cond, mutex) = block {
+            // The mutex has to be re-locked even if the wait timed out.
+            self.threads[thread].blocked_by = Some(ThreadBlock::Mutex(mutex));
+            self.try_unblock(thread)
+        } else {
+            self.threads[thread].blocked_by = None;
+            true
+        }
+    }
+
     /// Run the emulator. This is the main loop and won't return until app exit.
     /// Only `main.rs` should call this.
     pub fn run(&mut self) {
@@ -397,7 +606,12 @@ impl Environment {
     pub fn run_call(&mut self) {
         let was_in_host_function = self.threads[self.current_thread].in_host_function;
         self.threads[self.current_thread].in_host_function = false;
+        let sp = self.cpu.regs()[cpu::Cpu::SP];
+        self.threads[self.current_thread]
+            .call_stack_pointers
+            .push(sp);
         self.run_inner(false);
+        self.threads[self.current_thread].call_stack_pointers.pop();
         self.threads[self.current_thread].in_host_function = was_in_host_function;
     }
 
@@ -445,10 +659,28 @@ impl Environment {
         // GDB doesn't seem to manage to produce a useful stack trace, so
         // let's print our own.
         self.stack_trace();
+        self.wait_for_debugger(Some(error))
+    }
+
+    /// Hand control to the connected debugger. See
+    /// [gdb::GdbServer::wait_for_debugger].
+    fn wait_for_debugger(&mut self, stop_reason: Option<cpu::CpuError>) -> bool {
+        let threads = gdb::DebugThreads {
+            current: self.current_thread,
+            contexts: self
+                .threads
+                .iter_mut()
+                .map(|thread| match thread.active {
+                    true => thread.context.as_mut(),
+                    false => None,
+                })
+                .collect(),
+        };
         self.gdb_server.as_mut().unwrap().wait_for_debugger(
-            Some(error),
+            stop_reason,
             &mut self.cpu,
             &mut self.mem,
+            threads,
         )
     }
 
@@ -468,6 +700,17 @@ impl Environment {
                 // instruction after the SVC, but we want the
                 // address of the SVC itself
                 let svc_pc = self.cpu.regs()[cpu::Cpu::PC] - 4;
+                // Check if the guest has jumped past the host function that
+                // is waiting for the current guest call to return. This needs
+                // to be done before handling the SVC, since the SVC needs to
+                // be handled by the right call to this function.
+                if let Some(&call_sp) = self.threads[self.current_thread].call_stack_pointers.last()
+                {
+                    if self.cpu.regs()[cpu::Cpu::SP] > call_sp {
+                        self.unwind_host_function(svc_pc, initial_thread, root);
+                    }
+                }
+
                 if svc == dyld::Dyld::SVC_RETURN_TO_HOST {
                     assert!(svc_pc == self.dyld.return_to_host_routine().addr_without_thumb_bit());
                     assert!(!root);
@@ -485,6 +728,7 @@ impl Environment {
                             self.current_thread
                         );
                         self.threads[self.current_thread].active = false;
+                        self.threads[self.current_thread].return_value = Some(self.cpu.regs()[0]);
                         let stack = self.threads[self.current_thread].stack.take().unwrap();
                         let stack: mem::MutVoidPtr = mem::Ptr::from_bits(*stack.start());
                         log_dbg!("Freeing thread {} stack {:?}", self.current_thread, stack);
@@ -500,13 +744,37 @@ impl Environment {
                         .get_svc_handler(&self.bins, &mut self.mem, &mut self.cpu, svc_pc, svc)
                 {
                     let was_in_host_function = self.threads[self.current_thread].in_host_function;
+                    let call_count = self.threads[self.current_thread].call_stack_pointers.len();
                     self.threads[self.current_thread].in_host_function = true;
-                    f.call_from_guest(self);
+                    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        f.call_from_guest(self)
+                    }));
                     self.threads[self.current_thread].in_host_function = was_in_host_function;
+                    if let Err(e) = res {
+                        if !e.is::<UnwindHostFunction>() {
+                            std::panic::resume_unwind(e);
+                        }
+                        // The host function was abandoned, so any guest call
+                        // stack it was waiting on is gone too. If the PC was
+                        // rewound, the SVC that triggered this will be handled
+                        // again, at this level.
+                        self.threads[self.current_thread]
+                            .call_stack_pointers
+                            .truncate(call_count);
+                        log_dbg!(
+                            "Thread {} abandoned a host function after a non-local jump.",
+                            self.current_thread
+                        );
+                        return ThreadNextAction::Continue;
+                    }
                     // Host function might have put the thread to sleep.
                     if self.threads[self.current_thread].sleeping_until.is_some() {
                         log_dbg!("Yielding: thread {} is asleep.", self.current_thread);
                         ThreadNextAction::Yield
+                    // Host function might have blocked the thread.
+                    } else if self.threads[self.current_thread].blocked_by.is_some() {
+                        log_dbg!("Yielding: thread {} is blocked.", self.current_thread);
+                        ThreadNextAction::Yield
                     } else {
                         ThreadNextAction::Continue
                     }
@@ -519,6 +787,42 @@ impl Environment {
         }
     }
 
+    #[cold]
+    /// Unwind the host stack up to the host function that is waiting for the
+    /// current guest call to return, after the guest has jumped past it. The
+    /// SVC at `svc_pc` will be handled again once that's done. See
+    /// [UnwindHostFunction].
+    fn unwind_host_function(&mut self, svc_pc: u32, initial_thread: ThreadID, root: bool) -> ! {
+        // The innermost host-to-guest call must be on this thread, otherwise
+        // we would be unwinding host functions belonging to other threads.
+        if root || self.current_thread != initial_thread {
+            panic!(
+                "Thread {} did a non-local jump past a host function, but the innermost host function is on another thread. This is not supported.",
+                self.current_thread
+            );
+        }
+        log_dbg!(
+            "Thread {} did a non-local jump past a host function (SP {:#x}), unwinding.",
+            self.current_thread,
+            self.cpu.regs()[cpu::Cpu::SP]
+        );
+        self.cpu.regs_mut()[cpu::Cpu::PC] = svc_pc;
+        std::panic::resume_unwind(Box::new(UnwindHostFunction))
+    }
+
+    /// Abandon the execution of the innermost host function called by the
+    /// guest, as if it returned, but without writing a return value. This is
+    /// for host code that has set up the CPU registers for a non-local jump,
+    /// e.g. throwing an Objective-C exception.
+    pub fn abandon_host_function(&mut self) -> ! {
+        log_dbg!(
+            "Thread {} is abandoning a host function, jumping to {}.",
+            self.current_thread,
+            self.symbolicate(self.cpu.regs()[cpu::Cpu::LR])
+        );
+        std::panic::resume_unwind(Box::new(UnwindHostFunction))
+    }
+
     fn run_inner(&mut self, root: bool) {
         let initial_thread = self.current_thread;
         assert!(self.threads[initial_thread].active);
@@ -529,7 +833,7 @@ impl Environment {
             // that the host OS doesn't consider touchHLE unresponsive.
             // This is not free so we should avoid doing it too often.
             // 100,000 ticks is an arbitrary number.
-            self.window.poll_for_events(&self.options);
+            self.poll_for_events();
 
             let mut ticks = 100_000;
             let mut step_and_debug = false;
@@ -545,11 +849,7 @@ impl Environment {
                 match self.handle_cpu_state(state, initial_thread, root) {
                     ThreadNextAction::Continue => {
                         if step_and_debug {
-                            step_and_debug = self.gdb_server.as_mut().unwrap().wait_for_debugger(
-                                None,
-                                &mut self.cpu,
-                                &mut self.mem,
-                            );
+                            step_and_debug = self.wait_for_debugger(None);
                         }
                     }
                     ThreadNextAction::Yield => break,
@@ -565,16 +865,29 @@ impl Environment {
                 // following the one currently executing.
                 let mut suitable_thread: Option<ThreadID> = None;
                 let mut next_awakening: Option<Instant> = None;
+                let mut any_blocked = false;
                 for i in 0..self.threads.len() {
                     let i = (self.current_thread + 1 + i) % self.threads.len();
-                    let candidate = &mut self.threads[i];
 
-                    if !candidate.active || candidate.in_host_function {
+                    if !self.threads[i].active || self.threads[i].in_host_function {
+                        continue;
+                    }
+
+                    if self.threads[i].blocked_by.is_some() && !self.try_unblock(i) {
+                        any_blocked = true;
+                        if let Some((blocked_until, _)) = self.threads[i].blocked_until {
+                            next_awakening = match next_awakening {
+                                None => Some(blocked_until),
+                                Some(other) => Some(other.min(blocked_until)),
+                            };
+                        }
                         continue;
                     }
 
+                    let candidate = &mut self.threads[i];
+
                     if let Some(sleeping_until) = candidate.sleeping_until {
-                        if sleeping_until <= Instant::now() {
+                        if sleeping_until <= self.clock.now() {
                             log_dbg!("Thread {} finished sleeping.", i);
                             candidate.sleeping_until = None;
                         } else {
@@ -595,16 +908,22 @@ impl Environment {
                     if suitable_thread != self.current_thread {
                         self.switch_thread(suitable_thread);
                     }
+                    if let Some(value) = self.threads[suitable_thread].pending_return_value.take() {
+                        self.cpu.regs_mut()[0] = value;
+                    }
                     break;
                 // All suitable threads are asleep. Sleep until one of them
                 // wakes up.
                 } else if let Some(next_awakening) = next_awakening {
-                    let duration = next_awakening.duration_since(Instant::now());
+                    let duration = next_awakening.duration_since(self.clock.now());
                     log_dbg!("All threads asleep, sleeping for {:?}.", duration);
-                    std::thread::sleep(duration);
+                    std::thread::sleep(self.clock.host_duration(duration));
                     // Try again, there should be some thread awake now (or
                     // there will be soon, since timing is approximate).
                     continue;
+                } else if any_blocked {
+                    // The app must have a bug, or our implementation does.
+                    panic!("Deadlock: all active threads are blocked!");
                 } else {
                     // This should never happen!
                     panic!("No active threads?!");
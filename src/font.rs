@@ -17,6 +17,7 @@ use crate::fs::files_prefix;
 use rusttype::{Point, Rect, Scale};
 use std::cmp;
 
+#[derive(Clone)]
 pub struct Font {
     font: rusttype::Font<'static>,
 }
@@ -36,9 +36,48 @@ pub mod uikit;
 #[derive(Default)]
 pub struct State {
     audio_toolbox: audio_toolbox::State,
+    core_animation: core_animation::State,
     foundation: foundation::State,
     media_player: media_player::State,
     openal: openal::State,
     opengles: opengles::State,
     uikit: uikit::State,
 }
+impl State {
+    /// See [crate::snapshot]. Audio is refused for now.
+    pub fn check_snapshot_supported(&self) -> Result<(), String> {
+        self.audio_toolbox.check_snapshot_supported()?;
+        self.openal.check_snapshot_supported()
+    }
+
+    /// See [crate::snapshot]. Audio and Core Animation state belongs to the
+    /// host, so it isn't part of the copy and is kept as-is by [Self::restore].
+    pub fn snapshot(&self) -> Result<State, String> {
+        self.check_snapshot_supported()?;
+        Ok(State {
+            audio_toolbox: Default::default(),
+            core_animation: Default::default(),
+            foundation: self.foundation.clone(),
+            media_player: self.media_player.clone(),
+            openal: Default::default(),
+            opengles: self.opengles.clone(),
+            uikit: self.uikit.clone(),
+        })
+    }
+
+    /// See [crate::snapshot].
+    pub fn restore(&mut self, saved: &State) {
+        self.foundation = saved.foundation.clone();
+        self.media_player = saved.media_player.clone();
+        self.opengles = saved.opengles.clone();
+        self.uikit = saved.uikit.clone();
+        self.core_animation.reset_schedule();
+    }
+}
+
+/// Mute or unmute all audio output by the app, e.g. while the emulation speed
+/// is changed (see [crate::clock]).
+pub fn set_audio_muted(env: &mut crate::Environment, muted: bool) {
+    openal::set_muted(env, muted);
+    audio_toolbox::audio_queue::set_muted(env, muted);
+}
@@ -31,3 +31,10 @@ pub struct State {
     audio_file: audio_file::State,
     audio_queue: audio_queue::State,
 }
+impl State {
+    /// See [crate::snapshot].
+    pub fn check_snapshot_supported(&self) -> Result<(), String> {
+        self.audio_file.check_snapshot_supported()?;
+        self.audio_queue.check_snapshot_supported()
+    }
+}
@@ -27,6 +27,16 @@ impl State {
     pub fn get(framework_state: &mut crate::frameworks::State) -> &mut Self {
         &mut framework_state.audio_toolbox.audio_file
     }
+
+    /// For use by [crate::snapshot]: audio files are host resources that can't
+    /// be copied yet, so save states are refused while any are open.
+    pub(super) fn check_snapshot_supported(&self) -> Result<(), String> {
+        if self.audio_files.is_empty() {
+            Ok(())
+        } else {
+            Err("the app has open audio files, and audio isn't supported yet".to_string())
+        }
+    }
 }
 
 struct AudioFileHostObject {
@@ -33,11 +33,22 @@ use std::collections::{HashMap, VecDeque};
 pub struct State {
     audio_queues: HashMap<AudioQueueRef, AudioQueueHostObject>,
     al_device_and_context: Option<(*mut ALCdevice, *mut ALCcontext)>,
+    /// See [set_muted].
+    muted: bool,
 }
 impl State {
     fn get(framework_state: &mut crate::frameworks::State) -> &mut Self {
         &mut framework_state.audio_toolbox.audio_queue
     }
+    /// For use by [crate::snapshot]: audio queues own OpenAL sources, which
+    /// can't be copied yet, so save states are refused while any exist.
+    pub(super) fn check_snapshot_supported(&self) -> Result<(), String> {
+        if self.audio_queues.is_empty() {
+            Ok(())
+        } else {
+            Err("the app has audio queues, and audio isn't supported yet".to_string())
+        }
+    }
     fn make_al_context_current(&mut self) -> ContextManager {
         if self.al_device_and_context.is_none() {
             let device = unsafe { al::alcOpenDevice(std::ptr::null()) };
@@ -56,7 +67,12 @@ impl State {
 
         // This object will make sure the existing context, which will belong
         // to the guest app, is restored once we're done.
-        ContextManager::make_active(context)
+        let manager = ContextManager::make_active(context);
+        // The listener gain is never changed otherwise, so it's fine to set it
+        // every time.
+        let gain = if self.muted { 0.0 } else { 1.0 };
+        unsafe { al::alListenerf(al::AL_GAIN, gain) };
+        manager
     }
 }
 
@@ -493,6 +509,16 @@ fn unqueue_buffers<F: FnMut(ALuint)>(al_source: ALuint, mut callback: F) {
     }
 }
 
+/// Mute or unmute all audio queues. See [crate::frameworks::set_audio_muted].
+pub fn set_muted(env: &mut Environment, muted: bool) {
+    let state = State::get(&mut env.framework_state);
+    state.muted = muted;
+    if state.al_device_and_context.is_some() {
+        // This applies the new gain.
+        let _context_manager = state.make_al_context_current();
+    }
+}
+
 /// For use by `NSRunLoop`: check the status of an audio queue, recycle buffers,
 /// call callbacks, push new buffers etc.
 pub fn handle_audio_queue(env: &mut Environment, in_aq: AudioQueueRef) {
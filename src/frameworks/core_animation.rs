@@ -7,3 +7,15 @@
 
 pub mod ca_eagl_layer;
 pub mod ca_layer;
+pub mod composition;
+
+#[derive(Default)]
+pub struct State {
+    composition: composition::State,
+}
+impl State {
+    /// See [crate::snapshot].
+    pub fn reset_schedule(&mut self) {
+        self.composition.reset_schedule();
+    }
+}
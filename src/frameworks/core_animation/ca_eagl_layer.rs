@@ -7,6 +7,7 @@
 
 use super::ca_layer::CALayerHostObject;
 use crate::objc::{id, msg, objc_classes, ClassExports};
+use crate::Environment;
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -29,3 +30,19 @@ pub const CLASSES: ClassExports = objc_classes! {
 @end
 
 };
+
+/// For use by `EAGLContext` when the compositor is in use (see
+/// [super::composition]): store a frame presented by the app so it can be
+/// composited with the other layers. The pixels must be 8 bits per channel
+/// RGBA, with rows in top-to-bottom order.
+pub fn set_presented_pixels(
+    env: &mut Environment,
+    layer: id,
+    pixels: Vec<u8>,
+    dimensions: (u32, u32),
+) {
+    let (width, height) = dimensions;
+    env.objc
+        .borrow_mut::<CALayerHostObject>(layer)
+        .presented_pixels = Some((pixels, width, height));
+}
@@ -4,18 +4,70 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `CALayer`.
+//!
+//! Layers are drawn to the screen by the compositor, see [super::composition].
 
-use crate::frameworks::core_graphics::{CGPoint, CGRect};
-use crate::objc::{id, msg, nil, objc_classes, release, ClassExports, HostObject};
+use crate::frameworks::core_graphics::cg_affine_transform::{
+    CGAffineTransform, CGAffineTransformIdentity,
+};
+use crate::frameworks::core_graphics::cg_bitmap_context::{
+    CGBitmapContextCreate, CGBitmapContextGetData,
+};
+use crate::frameworks::core_graphics::cg_color::{CGColorRef, CGColorRelease, CGColorRetain};
+use crate::frameworks::core_graphics::cg_color_space::{
+    CGColorSpaceCreateDeviceRGB, CGColorSpaceRelease,
+};
+use crate::frameworks::core_graphics::cg_context::{
+    CGContextRef, CGContextRelease, CGContextTranslateCTM,
+};
+use crate::frameworks::core_graphics::cg_image::kCGImageAlphaPremultipliedLast;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::{ns_array, NSUInteger};
+use crate::mem::GuestUSize;
+use crate::objc::{
+    autorelease, id, msg, nil, objc_classes, release, responds_to_selector, retain, AnyHostObject,
+    ClassExports, HostObject, ObjC,
+};
+use crate::Environment;
 
+#[derive(Clone)]
 pub(super) struct CALayerHostObject {
     /// Possibly nil, usually a UIView. This is a weak reference.
     delegate: id,
-    opaque: bool,
+    /// Sublayers in back-to-front order. These are strong references.
+    pub(super) sublayers: Vec<id>,
+    /// The superlayer. This is a weak reference.
+    pub(super) superlayer: id,
+    pub(super) bounds: CGRect,
+    pub(super) position: CGPoint,
+    pub(super) anchor_point: CGPoint,
+    pub(super) affine_transform: CGAffineTransform,
+    pub(super) opacity: CGFloat,
+    pub(super) hidden: bool,
+    pub(super) opaque: bool,
+    /// Whether sublayers are clipped to the layer's bounds.
+    pub(super) masks_to_bounds: bool,
+    /// `CGColorRef`, possibly nil. This is a strong reference.
+    pub(super) background_color: CGColorRef,
+    /// `CGImageRef`, possibly nil. This is a strong reference.
+    pub(super) contents: id,
+    pub(super) needs_display: bool,
+    /// Backing store used by `display`, if the layer has been displayed. The
+    /// rows of its pixel data are in bottom-to-top order, so that the origin
+    /// is in the top-left corner, as UIKit expects.
+    pub(super) cg_context: Option<CGContextRef>,
     /// For CAEAGLLayer only
     pub(super) drawable_properties: id,
+    /// For CAEAGLLayer only: the last frame presented by the app, if it was
+    /// presented while the compositor was in use. The pixels are 8 bits per
+    /// channel RGBA, with rows in top-to-bottom order.
+    pub(super) presented_pixels: Option<(Vec<u8>, u32, u32)>,
+}
+impl HostObject for CALayerHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
 }
-impl HostObject for CALayerHostObject {}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -26,22 +78,55 @@ pub const CLASSES: ClassExports = objc_classes! {
 + (id)alloc {
     let host_object = Box::new(CALayerHostObject {
         delegate: nil,
+        sublayers: Vec::new(),
+        superlayer: nil,
+        bounds: CGRect {
+            origin: CGPoint { x: 0.0, y: 0.0 },
+            size: CGSize { width: 0.0, height: 0.0 },
+        },
+        position: CGPoint { x: 0.0, y: 0.0 },
+        anchor_point: CGPoint { x: 0.5, y: 0.5 },
+        affine_transform: CGAffineTransformIdentity,
+        opacity: 1.0,
+        hidden: false,
         opaque: false,
+        masks_to_bounds: false,
+        background_color: nil,
+        contents: nil,
+        needs_display: false,
+        cg_context: None,
         drawable_properties: nil,
+        presented_pixels: None,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
 + (id)layer {
     let new_layer: id = msg![env; this alloc];
-    msg![env; new_layer init]
+    let new_layer: id = msg![env; new_layer init];
+    autorelease(env, new_layer)
 }
 
 - (())dealloc {
-    let &CALayerHostObject { drawable_properties, .. } = env.objc.borrow(this);
-    if drawable_properties != nil {
-        release(env, drawable_properties);
+    let host_object = env.objc.borrow_mut::<CALayerHostObject>(this);
+    let sublayers = std::mem::take(&mut host_object.sublayers);
+    let background_color = host_object.background_color;
+    let contents = host_object.contents;
+    let cg_context = host_object.cg_context.take();
+    let drawable_properties = host_object.drawable_properties;
+
+    for sublayer in sublayers {
+        env.objc.borrow_mut::<CALayerHostObject>(sublayer).superlayer = nil;
+        release(env, sublayer);
     }
+    CGColorRelease(env, background_color);
+    release(env, contents);
+    if let Some(cg_context) = cg_context {
+        free_backing_store(env, cg_context);
+    }
+    release(env, drawable_properties);
+
+    env.objc.dealloc_object(this, &mut env.mem)
 }
 
 - (id)delegate {
@@ -51,6 +136,125 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow_mut::<CALayerHostObject>(this).delegate = delegate;
 }
 
+- (id)superlayer {
+    env.objc.borrow::<CALayerHostObject>(this).superlayer
+}
+- (id)sublayers {
+    let sublayers = env.objc.borrow::<CALayerHostObject>(this).sublayers.clone();
+    for &sublayer in &sublayers {
+        retain(env, sublayer);
+    }
+    let array = ns_array::from_vec(env, sublayers);
+    autorelease(env, array)
+}
+
+- (())addSublayer:(id)layer { // CALayer*
+    let index = env.objc.borrow::<CALayerHostObject>(this).sublayers.len();
+    let index: NSUInteger = index.try_into().unwrap();
+    msg![env; this insertSublayer:layer atIndex:index]
+}
+- (())insertSublayer:(id)layer // CALayer*
+             atIndex:(NSUInteger)index {
+    // The layer might already be a sublayer of this layer, so it has to be
+    // retained before removing it.
+    retain(env, layer);
+    () = msg![env; layer removeFromSuperlayer];
+
+    let host_object = env.objc.borrow_mut::<CALayerHostObject>(this);
+    let index = (index as usize).min(host_object.sublayers.len());
+    host_object.sublayers.insert(index, layer);
+    env.objc.borrow_mut::<CALayerHostObject>(layer).superlayer = this;
+}
+- (())insertSublayer:(id)layer // CALayer*
+               below:(id)sibling { // CALayer*
+    let index = sublayer_index(env, this, sibling).unwrap_or(0);
+    let index: NSUInteger = index.try_into().unwrap();
+    msg![env; this insertSublayer:layer atIndex:index]
+}
+- (())insertSublayer:(id)layer // CALayer*
+               above:(id)sibling { // CALayer*
+    let index = match sublayer_index(env, this, sibling) {
+        Some(index) => index + 1,
+        None => env.objc.borrow::<CALayerHostObject>(this).sublayers.len(),
+    };
+    let index: NSUInteger = index.try_into().unwrap();
+    msg![env; this insertSublayer:layer atIndex:index]
+}
+- (())removeFromSuperlayer {
+    let superlayer = std::mem::replace(
+        &mut env.objc.borrow_mut::<CALayerHostObject>(this).superlayer,
+        nil,
+    );
+    if superlayer == nil {
+        return;
+    }
+    let index = sublayer_index(env, superlayer, this).unwrap();
+    env.objc.borrow_mut::<CALayerHostObject>(superlayer).sublayers.remove(index);
+    release(env, this);
+}
+
+- (CGRect)bounds {
+    env.objc.borrow::<CALayerHostObject>(this).bounds
+}
+- (())setBounds:(CGRect)bounds {
+    env.objc.borrow_mut::<CALayerHostObject>(this).bounds = bounds;
+}
+- (CGPoint)position {
+    env.objc.borrow::<CALayerHostObject>(this).position
+}
+- (())setPosition:(CGPoint)position {
+    env.objc.borrow_mut::<CALayerHostObject>(this).position = position;
+}
+- (CGPoint)anchorPoint {
+    env.objc.borrow::<CALayerHostObject>(this).anchor_point
+}
+- (())setAnchorPoint:(CGPoint)anchor_point {
+    env.objc.borrow_mut::<CALayerHostObject>(this).anchor_point = anchor_point;
+}
+
+// The frame is not stored, it's derived from the bounds, position and anchor
+// point. Like on the real iPhone OS, the transform is ignored.
+- (CGRect)frame {
+    let &CALayerHostObject { bounds, position, anchor_point, .. } = env.objc.borrow(this);
+    CGRect {
+        origin: CGPoint {
+            x: position.x - bounds.size.width * anchor_point.x,
+            y: position.y - bounds.size.height * anchor_point.y,
+        },
+        size: bounds.size,
+    }
+}
+- (())setFrame:(CGRect)frame {
+    let host_object = env.objc.borrow_mut::<CALayerHostObject>(this);
+    let anchor_point = host_object.anchor_point;
+    host_object.bounds.size = frame.size;
+    host_object.position = CGPoint {
+        x: frame.origin.x + frame.size.width * anchor_point.x,
+        y: frame.origin.y + frame.size.height * anchor_point.y,
+    };
+}
+
+- (CGAffineTransform)affineTransform {
+    env.objc.borrow::<CALayerHostObject>(this).affine_transform
+}
+- (())setAffineTransform:(CGAffineTransform)transform {
+    env.objc.borrow_mut::<CALayerHostObject>(this).affine_transform = transform;
+}
+
+- (CGFloat)opacity {
+    env.objc.borrow::<CALayerHostObject>(this).opacity
+}
+- (())setOpacity:(CGFloat)opacity {
+    env.objc.borrow_mut::<CALayerHostObject>(this).opacity = opacity;
+}
+
+- (bool)isHidden {
+    env.objc.borrow::<CALayerHostObject>(this).hidden
+}
+- (())setHidden:(bool)hidden {
+    env.objc.borrow_mut::<CALayerHostObject>(this).hidden = hidden;
+}
+
 - (bool)isOpaque {
     env.objc.borrow::<CALayerHostObject>(this).opaque
 }
@@ -58,22 +262,151 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow_mut::<CALayerHostObject>(this).opaque = opaque;
 }
 
-// FIXME: should these pass through from UIView, vice-versa, or neither?
-// (See similar comment in ui_view.rs)
-- (CGRect)bounds {
-    let view: id = msg![env; this delegate];
-    msg![env; view bounds]
+- (bool)masksToBounds {
+    env.objc.borrow::<CALayerHostObject>(this).masks_to_bounds
 }
-- (CGPoint)position {
-    let view: id = msg![env; this delegate];
-    msg![env; view center]
+- (())setMasksToBounds:(bool)masks_to_bounds {
+    env.objc.borrow_mut::<CALayerHostObject>(this).masks_to_bounds = masks_to_bounds;
 }
-- (CGPoint)anchorPoint {
-    CGPoint { x: 0.5, y: 0.5 }
+
+- (CGColorRef)backgroundColor {
+    env.objc.borrow::<CALayerHostObject>(this).background_color
+}
+- (())setBackgroundColor:(CGColorRef)color {
+    CGColorRetain(env, color);
+    let old = std::mem::replace(
+        &mut env.objc.borrow_mut::<CALayerHostObject>(this).background_color,
+        color,
+    );
+    CGColorRelease(env, old);
 }
 
-// TODO
+- (id)contents {
+    env.objc.borrow::<CALayerHostObject>(this).contents
+}
+- (())setContents:(id)contents { // usually a CGImageRef
+    retain(env, contents);
+    let old = std::mem::replace(
+        &mut env.objc.borrow_mut::<CALayerHostObject>(this).contents,
+        contents,
+    );
+    release(env, old);
+}
+
+- (bool)needsDisplay {
+    env.objc.borrow::<CALayerHostObject>(this).needs_display
+}
+- (())setNeedsDisplay {
+    env.objc.borrow_mut::<CALayerHostObject>(this).needs_display = true;
+}
+- (())setNeedsDisplayInRect:(CGRect)_rect {
+    // TODO: only redraw the invalidated part
+    msg![env; this setNeedsDisplay]
+}
+- (())displayIfNeeded {
+    let host_object = env.objc.borrow_mut::<CALayerHostObject>(this);
+    if host_object.needs_display {
+        host_object.needs_display = false;
+        () = msg![env; this display];
+    }
+}
+
+- (())display {
+    let delegate = env.objc.borrow::<CALayerHostObject>(this).delegate;
+    if delegate != nil && responds_to_selector(env, delegate, "displayLayer:") {
+        () = msg![env; delegate displayLayer:this];
+        return;
+    }
+
+    let context = prepare_backing_store(env, this);
+    () = msg![env; this drawInContext:context];
+}
+- (())drawInContext:(CGContextRef)context {
+    let delegate = env.objc.borrow::<CALayerHostObject>(this).delegate;
+    if delegate != nil && responds_to_selector(env, delegate, "drawLayer:inContext:") {
+        () = msg![env; delegate drawLayer:this inContext:context];
+    }
+}
 
 @end
 
 };
+
+fn sublayer_index(env: &mut Environment, layer: id, sublayer: id) -> Option<usize> {
+    env.objc
+        .borrow::<CALayerHostObject>(layer)
+        .sublayers
+        .iter()
+        .position(|&candidate| candidate == sublayer)
+}
+
+/// Get the transform from a layer's co-ordinate space (that of its bounds) to
+/// that of its superlayer.
+pub fn transform_to_superlayer(objc: &ObjC, layer: id) -> CGAffineTransform {
+    let &CALayerHostObject {
+        bounds: CGRect { origin, size },
+        position,
+        anchor_point,
+        affine_transform,
+        ..
+    } = objc.borrow(layer);
+    CGAffineTransform::make_translation(
+        -(origin.x + anchor_point.x * size.width),
+        -(origin.y + anchor_point.y * size.height),
+    )
+    .concat(affine_transform)
+    .concat(CGAffineTransform::make_translation(position.x, position.y))
+}
+
+/// Get the transform from a layer's co-ordinate space to that of the root of
+/// its layer tree. For a window's layer, that is the screen.
+pub fn transform_to_root(objc: &ObjC, layer: id) -> CGAffineTransform {
+    let mut transform = CGAffineTransformIdentity;
+    let mut layer = layer;
+    while layer != nil {
+        transform = transform.concat(transform_to_superlayer(objc, layer));
+        layer = objc.borrow::<CALayerHostObject>(layer).superlayer;
+    }
+    transform
+}
+
+/// Create a new, cleared bitmap context the size of the layer's bounds for
+/// `display`, replacing the old one.
+fn prepare_backing_store(env: &mut Environment, layer: id) -> CGContextRef {
+    let host_object = env.objc.borrow_mut::<CALayerHostObject>(layer);
+    let bounds = host_object.bounds;
+    if let Some(old_context) = host_object.cg_context.take() {
+        free_backing_store(env, old_context);
+    }
+
+    let width = (bounds.size.width.round() as GuestUSize).max(1);
+    let height = (bounds.size.height.round() as GuestUSize).max(1);
+    let bytes_per_row = width.checked_mul(4).unwrap();
+    let size = bytes_per_row.checked_mul(height).unwrap();
+
+    let data = env.mem.alloc(size);
+    env.mem.bytes_at_mut(data.cast(), size).fill(0);
+    let color_space = CGColorSpaceCreateDeviceRGB(env);
+    let context = CGBitmapContextCreate(
+        env,
+        data,
+        width,
+        height,
+        8,
+        bytes_per_row,
+        color_space,
+        kCGImageAlphaPremultipliedLast,
+    );
+    CGColorSpaceRelease(env, color_space);
+    // Drawing happens in the co-ordinate space of the bounds.
+    CGContextTranslateCTM(env, context, -bounds.origin.x, -bounds.origin.y);
+
+    env.objc.borrow_mut::<CALayerHostObject>(layer).cg_context = Some(context);
+    context
+}
+
+fn free_backing_store(env: &mut Environment, context: CGContextRef) {
+    let data = CGBitmapContextGetData(env, context);
+    CGContextRelease(env, context);
+    env.mem.free(data);
+}
@@ -0,0 +1,560 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! The compositor, which draws the layer tree to the screen.
+//!
+//! On the real iPhone OS, layers are composited by the system, so any
+//! combination of layers can be on screen at once. Most games that touchHLE
+//! supports only ever show a single, fullscreen `CAEAGLLayer`, so in that case
+//! the compositor stays out of the way and the app's frames are presented
+//! directly by `EAGLContext` (see [crate::frameworks::opengles::eagl]).
+//! Otherwise, the visible windows' layers are drawn in software, once per 60th
+//! of a second, with frames presented by `CAEAGLLayer`s drawn like any other
+//! layer content.
+//!
+//! Drawing in software is slow, but it is simple, it produces the same result
+//! everywhere, and it keeps working in headless mode, where frames can still be
+//! captured (see [crate::window::Window::frame_presented]).
+
+use super::ca_layer::{transform_to_superlayer, CALayerHostObject};
+use crate::frameworks::core_graphics::cg_affine_transform::CGAffineTransform;
+use crate::frameworks::core_graphics::cg_bitmap_context::{
+    CGBitmapContextGetBytesPerRow, CGBitmapContextGetData, CGBitmapContextGetHeight,
+    CGBitmapContextGetWidth,
+};
+use crate::frameworks::core_graphics::cg_image::{self, CGImageRef};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::opengles::{self, eagl, GLES};
+use crate::frameworks::uikit::{ui_color, ui_window};
+use crate::mem::{ConstPtr, GuestUSize, Mem};
+use crate::objc::{id, msg, msg_class, nil, release, Class, ObjC};
+use crate::Environment;
+use std::time::{Duration, Instant};
+
+/// Interval between composited frames.
+const FRAME_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+#[derive(Default)]
+pub struct State {
+    /// OpenGL ES context used to present composited frames. This is separate
+    /// from the app's contexts and is only created when first needed.
+    gles_ctx: Option<Box<dyn GLES>>,
+    /// The composited frame, 8 bits per channel RGBA with rows in
+    /// top-to-bottom order. This is kept between frames to avoid reallocating.
+    framebuffer: Vec<u8>,
+    /// When the next frame should be composited, if the compositor is in use.
+    recomposite_next: Option<Instant>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut Self {
+        &mut env.framework_state.core_animation.composition
+    }
+
+    /// For use by [crate::snapshot]: the schedule is in guest time, which
+    /// jumps when a state is loaded, so the next frame is composited as soon
+    /// as possible instead.
+    pub(super) fn reset_schedule(&mut self) {
+        self.recomposite_next = None;
+    }
+}
+
+/// A layer that has something to draw.
+struct DrawItem {
+    layer: id,
+    /// Maps from the co-ordinate space of the layer's bounds to pixels in the
+    /// framebuffer.
+    transform: CGAffineTransform,
+    /// The layer's opacity multiplied by that of its superlayers.
+    opacity: CGFloat,
+    /// The area of the framebuffer the layer is clipped to by superlayers that
+    /// mask to their bounds, if any.
+    clip: Option<CGRect>,
+    is_eagl_layer: bool,
+}
+
+/// Layers to be drawn in back-to-front order, and whether drawing them is
+/// actually necessary.
+struct Plan {
+    items: Vec<DrawItem>,
+    needs_compositing: bool,
+    framebuffer_size: (u32, u32),
+}
+
+fn plan(env: &mut Environment) -> Plan {
+    let (fb_width, fb_height) = env.window.size_unrotated_scalehacked();
+    let (screen_width, _) = env.window.size_unrotated_unscaled();
+    // The layer tree uses points, but the framebuffer might be bigger if the
+    // scale hack is in use.
+    let scale = fb_width as CGFloat / screen_width as CGFloat;
+    let root_transform = CGAffineTransform::make_scale(scale, scale);
+
+    let eagl_layer_class = env.objc.get_known_class("CAEAGLLayer", &mut env.mem);
+
+    let mut items = Vec::new();
+    for window in ui_window::visible_windows(env) {
+        let layer: id = msg![env; window layer];
+        collect_items(
+            &env.objc,
+            &env.mem,
+            eagl_layer_class,
+            layer,
+            root_transform,
+            1.0,
+            None,
+            &mut items,
+        );
+    }
+
+    // If a fullscreen, fully opaque CAEAGLLayer is on top, it's the only thing
+    // visible and its frames can be presented directly. This is the common
+    // case for games, and avoids an expensive and redundant copy.
+    let topmost_fullscreen_eagl_layer = items.iter().rposition(|item| {
+        item.is_eagl_layer && item.opacity >= 1.0 && {
+            let bounds = env.objc.borrow::<CALayerHostObject>(item.layer).bounds;
+            covers_framebuffer(item.transform, bounds, (fb_width, fb_height))
+        }
+    });
+    let needs_compositing = match topmost_fullscreen_eagl_layer {
+        Some(index) => index + 1 < items.len(),
+        None => !items.is_empty(),
+    };
+
+    Plan {
+        items,
+        needs_compositing,
+        framebuffer_size: (fb_width, fb_height),
+    }
+}
+
+/// Recursively find the layers with something to draw, in back-to-front order.
+fn collect_items(
+    objc: &ObjC,
+    mem: &Mem,
+    eagl_layer_class: Class,
+    layer: id,
+    parent_transform: CGAffineTransform,
+    parent_opacity: CGFloat,
+    parent_clip: Option<CGRect>,
+    items: &mut Vec<DrawItem>,
+) {
+    let host_object = objc.borrow::<CALayerHostObject>(layer);
+    let opacity = host_object.opacity * parent_opacity;
+    if host_object.hidden || opacity <= 0.0 {
+        return;
+    }
+
+    let transform = transform_to_superlayer(objc, layer).concat(parent_transform);
+
+    let is_eagl_layer = objc.class_is_subclass_of(ObjC::read_isa(layer, mem), eagl_layer_class);
+    if is_eagl_layer
+        || host_object.background_color != nil
+        || host_object.contents != nil
+        || host_object.cg_context.is_some()
+        || host_object.needs_display
+    {
+        items.push(DrawItem {
+            layer,
+            transform,
+            opacity,
+            clip: parent_clip,
+            is_eagl_layer,
+        });
+    }
+
+    // Clipping is done with the bounding box of the layer's bounds in the
+    // framebuffer, which is only exact if the layer isn't rotated.
+    let clip = if host_object.masks_to_bounds {
+        let bounds = bounding_box(transform, host_object.bounds);
+        Some(match parent_clip {
+            Some(parent_clip) => intersection(parent_clip, bounds),
+            None => bounds,
+        })
+    } else {
+        parent_clip
+    };
+
+    for &sublayer in &host_object.sublayers {
+        collect_items(
+            objc,
+            mem,
+            eagl_layer_class,
+            sublayer,
+            transform,
+            opacity,
+            clip,
+            items,
+        );
+    }
+}
+
+/// Get the smallest axis-aligned rectangle containing a transformed rectangle.
+fn bounding_box(transform: CGAffineTransform, rect: CGRect) -> CGRect {
+    let corners = [
+        (0.0, 0.0),
+        (rect.size.width, 0.0),
+        (0.0, rect.size.height),
+        (rect.size.width, rect.size.height),
+    ]
+    .map(|(x, y)| {
+        transform.apply_to_point(CGPoint {
+            x: rect.origin.x + x,
+            y: rect.origin.y + y,
+        })
+    });
+    let (mut min_x, mut min_y) = (CGFloat::INFINITY, CGFloat::INFINITY);
+    let (mut max_x, mut max_y) = (CGFloat::NEG_INFINITY, CGFloat::NEG_INFINITY);
+    for CGPoint { x, y } in corners {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    CGRect {
+        origin: CGPoint { x: min_x, y: min_y },
+        size: CGSize {
+            width: max_x - min_x,
+            height: max_y - min_y,
+        },
+    }
+}
+
+/// Get the intersection of two rectangles, which is empty if they don't
+/// overlap.
+fn intersection(a: CGRect, b: CGRect) -> CGRect {
+    let min_x = a.origin.x.max(b.origin.x);
+    let min_y = a.origin.y.max(b.origin.y);
+    let max_x = (a.origin.x + a.size.width).min(b.origin.x + b.size.width);
+    let max_y = (a.origin.y + a.size.height).min(b.origin.y + b.size.height);
+    CGRect {
+        origin: CGPoint { x: min_x, y: min_y },
+        size: CGSize {
+            width: (max_x - min_x).max(0.0),
+            height: (max_y - min_y).max(0.0),
+        },
+    }
+}
+
+/// Check if a layer's bounds exactly cover the framebuffer, without any
+/// rotation or flipping.
+fn covers_framebuffer(
+    transform: CGAffineTransform,
+    bounds: CGRect,
+    framebuffer_size: (u32, u32),
+) -> bool {
+    if transform.b != 0.0 || transform.c != 0.0 || transform.a <= 0.0 || transform.d <= 0.0 {
+        return false;
+    }
+    let top_left = transform.apply_to_point(bounds.origin);
+    let bottom_right = transform.apply_to_point(CGPoint {
+        x: bounds.origin.x + bounds.size.width,
+        y: bounds.origin.y + bounds.size.height,
+    });
+    let close = |a: CGFloat, b: u32| (a - b as CGFloat).abs() < 0.5;
+    close(top_left.x, 0)
+        && close(top_left.y, 0)
+        && close(bottom_right.x, framebuffer_size.0)
+        && close(bottom_right.y, framebuffer_size.1)
+}
+
+/// For use by `EAGLContext`: check if the app's frames need to go through the
+/// compositor, rather than being presented directly.
+pub fn compositor_in_use(env: &mut Environment) -> bool {
+    plan(env).needs_compositing
+}
+
+/// For use by `NSRunLoop`: composite and present a new frame, if the compositor
+/// is in use and it's time for one. Returns the time the next frame is due, if
+/// any.
+pub fn recomposite_if_necessary(env: &mut Environment) -> Option<Instant> {
+    let now = env.clock.now();
+    if let Some(recomposite_next) = State::get(env).recomposite_next {
+        if recomposite_next > now {
+            return Some(recomposite_next);
+        }
+    }
+
+    let plan = plan(env);
+    if !plan.needs_compositing {
+        State::get(env).recomposite_next = None;
+        return None;
+    }
+
+    display_layers(env, &plan);
+    composite(env, &plan);
+    present(env, plan.framebuffer_size);
+
+    let recomposite_next = now + FRAME_INTERVAL;
+    State::get(env).recomposite_next = Some(recomposite_next);
+    Some(recomposite_next)
+}
+
+/// Make layers that need it (re-)draw their content, e.g. by calling
+/// `-[UIView drawRect:]`.
+fn display_layers(env: &mut Environment, plan: &Plan) {
+    let pool: id = msg_class![env; NSAutoreleasePool new];
+    for item in &plan.items {
+        if env
+            .objc
+            .borrow::<CALayerHostObject>(item.layer)
+            .needs_display
+        {
+            let layer = item.layer;
+            () = msg![env; layer displayIfNeeded];
+        }
+    }
+    release(env, pool);
+}
+
+/// Where a layer's content comes from, see [Content].
+enum ContentSource {
+    None,
+    Image(CGImageRef),
+    BackingStore {
+        data: ConstPtr<u8>,
+        width: GuestUSize,
+        height: GuestUSize,
+        bytes_per_row: GuestUSize,
+    },
+    EAGLFrame,
+}
+
+/// Pixel data for a layer's content.
+enum Content<'a> {
+    None,
+    /// 8 bits per channel RGBA, with straight alpha and rows in top-to-bottom
+    /// order (a `CGImage` or a frame presented by a `CAEAGLLayer`).
+    Straight {
+        pixels: &'a [u8],
+        width: u32,
+        height: u32,
+    },
+    /// 8 bits per channel RGBA, with premultiplied alpha and rows in
+    /// bottom-to-top order (a layer's backing store, see `-[CALayer display]`).
+    BackingStore {
+        pixels: &'a [u8],
+        width: u32,
+        height: u32,
+        bytes_per_row: u32,
+    },
+}
+
+fn composite(env: &mut Environment, plan: &Plan) {
+    // Getting the backing store details needs the environment, so this has to
+    // be done before borrowing everything needed for drawing.
+    let sources: Vec<ContentSource> = plan
+        .items
+        .iter()
+        .map(|item| {
+            let &CALayerHostObject {
+                contents,
+                cg_context,
+                ..
+            } = env.objc.borrow(item.layer);
+            if item.is_eagl_layer {
+                ContentSource::EAGLFrame
+            } else if contents != nil {
+                ContentSource::Image(contents)
+            } else if let Some(context) = cg_context {
+                ContentSource::BackingStore {
+                    data: CGBitmapContextGetData(env, context).cast().cast_const(),
+                    width: CGBitmapContextGetWidth(env, context),
+                    height: CGBitmapContextGetHeight(env, context),
+                    bytes_per_row: CGBitmapContextGetBytesPerRow(env, context),
+                }
+            } else {
+                ContentSource::None
+            }
+        })
+        .collect();
+
+    let (width, height) = plan.framebuffer_size;
+    let mut framebuffer = std::mem::take(&mut State::get(env).framebuffer);
+    framebuffer.clear();
+    framebuffer.resize(width as usize * height as usize * 4, 0);
+    for pixel in framebuffer.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+
+    let objc = &env.objc;
+    let mem = &env.mem;
+    for (item, source) in plan.items.iter().zip(sources) {
+        let host_object = objc.borrow::<CALayerHostObject>(item.layer);
+        let background_color = if host_object.background_color != nil {
+            Some(ui_color::get_rgba(objc, host_object.background_color))
+        } else {
+            None
+        };
+        let content = match source {
+            ContentSource::None => Content::None,
+            ContentSource::Image(image) => {
+                let image = cg_image::borrow_image(objc, image);
+                match image.dimensions() {
+                    (0, _) | (_, 0) => Content::None,
+                    (width, height) => Content::Straight {
+                        pixels: image.pixels(),
+                        width,
+                        height,
+                    },
+                }
+            }
+            ContentSource::BackingStore {
+                data,
+                width,
+                height,
+                bytes_per_row,
+            } => Content::BackingStore {
+                pixels: mem.bytes_at(data, bytes_per_row * height),
+                width,
+                height,
+                bytes_per_row,
+            },
+            ContentSource::EAGLFrame => match host_object.presented_pixels {
+                Some((ref pixels, width, height)) => Content::Straight {
+                    pixels,
+                    width,
+                    height,
+                },
+                None => Content::None,
+            },
+        };
+        draw_layer(
+            &mut framebuffer,
+            (width, height),
+            item,
+            host_object.bounds,
+            background_color,
+            &content,
+            host_object.opaque,
+        );
+    }
+
+    State::get(env).framebuffer = framebuffer;
+}
+
+/// Draw a layer's background color and content over the framebuffer.
+fn draw_layer(
+    framebuffer: &mut [u8],
+    framebuffer_size: (u32, u32),
+    item: &DrawItem,
+    bounds: CGRect,
+    background_color: Option<(CGFloat, CGFloat, CGFloat, CGFloat)>,
+    content: &Content,
+    opaque: bool,
+) {
+    let Some(inverse) = item.transform.invert() else {
+        return;
+    };
+    if bounds.size.width <= 0.0 || bounds.size.height <= 0.0 {
+        return;
+    }
+
+    // Only the pixels within the transformed bounds (and the clip, if any)
+    // need to be visited.
+    let mut area = bounding_box(item.transform, bounds);
+    if let Some(clip) = item.clip {
+        area = intersection(area, clip);
+    }
+    let (fb_width, fb_height) = framebuffer_size;
+    let min_x = area.origin.x;
+    let min_y = area.origin.y;
+    let max_x = area.origin.x + area.size.width;
+    let max_y = area.origin.y + area.size.height;
+    let x_range = (min_x.floor().max(0.0) as u32)..(max_x.ceil().min(fb_width as CGFloat) as u32);
+    let y_range = (min_y.floor().max(0.0) as u32)..(max_y.ceil().min(fb_height as CGFloat) as u32);
+
+    for y in y_range {
+        for x in x_range.clone() {
+            // Sample at the center of the pixel.
+            let CGPoint { x: u, y: v } = inverse.apply_to_point(CGPoint {
+                x: x as CGFloat + 0.5,
+                y: y as CGFloat + 0.5,
+            });
+            let u = (u - bounds.origin.x) / bounds.size.width;
+            let v = (v - bounds.origin.y) / bounds.size.height;
+            if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                continue;
+            }
+
+            let offset = (y as usize * fb_width as usize + x as usize) * 4;
+            let pixel = &mut framebuffer[offset..offset + 4];
+
+            if let Some((r, g, b, a)) = background_color {
+                blend(pixel, (r * a, g * a, b * a, a), item.opacity);
+            }
+
+            let premultiplied = match *content {
+                Content::None => continue,
+                Content::Straight {
+                    pixels,
+                    width,
+                    height,
+                } => {
+                    let sx = ((u * width as CGFloat) as usize).min(width as usize - 1);
+                    let sy = ((v * height as CGFloat) as usize).min(height as usize - 1);
+                    let offset = (sy * width as usize + sx) * 4;
+                    let [r, g, b, a] = unpack(&pixels[offset..offset + 4]);
+                    (r * a, g * a, b * a, a)
+                }
+                Content::BackingStore {
+                    pixels,
+                    width,
+                    height,
+                    bytes_per_row,
+                } => {
+                    let sx = ((u * width as CGFloat) as usize).min(width as usize - 1);
+                    let sy = ((v * height as CGFloat) as usize).min(height as usize - 1);
+                    // Flip the rows so the origin is in the top-left corner.
+                    let sy = height as usize - 1 - sy;
+                    let offset = sy * bytes_per_row as usize + sx * 4;
+                    let [r, g, b, a] = unpack(&pixels[offset..offset + 4]);
+                    // An opaque layer's backing store has no alpha channel.
+                    (r, g, b, if opaque { 1.0 } else { a })
+                }
+            };
+            blend(pixel, premultiplied, item.opacity);
+        }
+    }
+}
+
+fn unpack(pixel: &[u8]) -> [CGFloat; 4] {
+    [0, 1, 2, 3].map(|i| pixel[i] as CGFloat / 255.0)
+}
+
+/// Draw a color with premultiplied alpha over an opaque pixel.
+fn blend(pixel: &mut [u8], color: (CGFloat, CGFloat, CGFloat, CGFloat), opacity: CGFloat) {
+    let (r, g, b, a) = color;
+    let (r, g, b, a) = (r * opacity, g * opacity, b * opacity, a * opacity);
+    for (channel, value) in pixel[..3].iter_mut().zip([r, g, b]) {
+        let result = value + (*channel as CGFloat / 255.0) * (1.0 - a);
+        *channel = (result.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+fn present(env: &mut Environment, framebuffer_size: (u32, u32)) {
+    let state = &mut env.framework_state.core_animation.composition;
+
+    // Capture the frame for a screenshot or --dump-frames=, if needed.
+    env.window
+        .frame_presented(env.cpu.elapsed_ticks(), framebuffer_size, || {
+            state.framebuffer.clone()
+        });
+
+    let gles = state
+        .gles_ctx
+        .get_or_insert_with(|| opengles::create_gles1_ctx(&mut env.window, &env.options));
+    gles.make_current(&mut env.window);
+    // The app's context has to be restored before its next OpenGL ES call.
+    env.window.set_app_gl_ctx_no_longer_current();
+    unsafe {
+        eagl::present_pixels(
+            gles.as_mut(),
+            &mut env.window,
+            &state.framebuffer,
+            framebuffer_size,
+        );
+    }
+
+    env.clock.frame_presented();
+}
@@ -0,0 +1,161 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `CFPropertyList`.
+//!
+//! Property list types are toll-free bridged to their Foundation equivalents in
+//! Apple's implementation, so these functions are implemented with the same
+//! code as `NSPropertyListSerialization`.
+
+use super::cf_allocator::{kCFAllocatorDefault, CFAllocatorRef};
+use super::CFTypeRef;
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::frameworks::foundation::ns_data;
+use crate::frameworks::foundation::ns_property_list_serialization::{
+    deserialize, object_to_value, serialize_object, value_to_object, NSPropertyListFormat,
+    NSPropertyListMutabilityOptions, NSPropertyListOpenStepFormat, NSPropertyListXMLFormat_v1_0,
+};
+use crate::frameworks::foundation::ns_string::from_rust_string;
+use crate::mem::MutPtr;
+use crate::objc::{id, nil};
+use crate::Environment;
+
+pub type CFPropertyListRef = CFTypeRef;
+/// `CFData` is toll-free bridged to `NSData`.
+pub type CFDataRef = CFTypeRef;
+
+pub type CFPropertyListFormat = NSPropertyListFormat;
+pub type CFOptionFlags = u32;
+
+fn CFPropertyListCreateFromXMLData(
+    env: &mut Environment,
+    allocator: CFAllocatorRef,
+    xml_data: CFDataRef,
+    mutability_option: CFOptionFlags,
+    error_string: MutPtr<id>, // CFStringRef*
+) -> CFPropertyListRef {
+    assert!(allocator == kCFAllocatorDefault); // unimplemented
+
+    // Despite the name, this accepts any format.
+    let bytes = ns_data::to_vec(env, xml_data);
+    match deserialize(&bytes, mutability_option as NSPropertyListMutabilityOptions) {
+        Ok((value, _format)) => {
+            if !error_string.is_null() {
+                env.mem.write(error_string, nil);
+            }
+            value_to_object(env, &value)
+        }
+        Err(e) => {
+            log!("Warning: CFPropertyListCreateFromXMLData() failed: {}", e);
+            if !error_string.is_null() {
+                let error = from_rust_string(env, e);
+                env.mem.write(error_string, error);
+            }
+            nil
+        }
+    }
+}
+
+fn CFPropertyListCreateWithData(
+    env: &mut Environment,
+    allocator: CFAllocatorRef,
+    data: CFDataRef,
+    options: CFOptionFlags,
+    format: MutPtr<CFPropertyListFormat>,
+    error: MutPtr<id>, // CFErrorRef*
+) -> CFPropertyListRef {
+    assert!(allocator == kCFAllocatorDefault); // unimplemented
+
+    // TODO: CFError
+    if !error.is_null() {
+        env.mem.write(error, nil);
+    }
+    let bytes = ns_data::to_vec(env, data);
+    match deserialize(&bytes, options as NSPropertyListMutabilityOptions) {
+        Ok((value, actual_format)) => {
+            if !format.is_null() {
+                env.mem.write(format, actual_format);
+            }
+            value_to_object(env, &value)
+        }
+        Err(e) => {
+            log!("Warning: CFPropertyListCreateWithData() failed: {}", e);
+            nil
+        }
+    }
+}
+
+fn CFPropertyListCreateXMLData(
+    env: &mut Environment,
+    allocator: CFAllocatorRef,
+    property_list: CFPropertyListRef,
+) -> CFDataRef {
+    CFPropertyListCreateData(
+        env,
+        allocator,
+        property_list,
+        NSPropertyListXMLFormat_v1_0,
+        0,
+        MutPtr::null(),
+    )
+}
+
+fn CFPropertyListCreateData(
+    env: &mut Environment,
+    allocator: CFAllocatorRef,
+    property_list: CFPropertyListRef,
+    format: CFPropertyListFormat,
+    _options: CFOptionFlags, // reserved
+    error: MutPtr<id>,       // CFErrorRef*
+) -> CFDataRef {
+    assert!(allocator == kCFAllocatorDefault); // unimplemented
+
+    // TODO: CFError
+    if !error.is_null() {
+        env.mem.write(error, nil);
+    }
+    match serialize_object(env, property_list, format) {
+        Ok(bytes) => ns_data::from_slice(env, &bytes),
+        Err(e) => {
+            log!("Warning: CFPropertyListCreateData() failed: {}", e);
+            nil
+        }
+    }
+}
+
+fn CFPropertyListCreateDeepCopy(
+    env: &mut Environment,
+    allocator: CFAllocatorRef,
+    property_list: CFPropertyListRef,
+    mutability_option: CFOptionFlags,
+) -> CFPropertyListRef {
+    assert!(allocator == kCFAllocatorDefault); // unimplemented
+    if mutability_option != 0 {
+        // TODO: mutable containers (needs NSMutableDictionary)
+        log!(
+            "TODO: mutability option {} for CFPropertyListCreateDeepCopy() is unsupported, using immutable objects",
+            mutability_option
+        );
+    }
+    let value = object_to_value(env, property_list).unwrap();
+    value_to_object(env, &value)
+}
+
+fn CFPropertyListIsValid(
+    env: &mut Environment,
+    property_list: CFPropertyListRef,
+    format: CFPropertyListFormat,
+) -> bool {
+    format != NSPropertyListOpenStepFormat && object_to_value(env, property_list).is_some()
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(CFPropertyListCreateFromXMLData(_, _, _, _)),
+    export_c_func!(CFPropertyListCreateWithData(_, _, _, _, _)),
+    export_c_func!(CFPropertyListCreateXMLData(_, _)),
+    export_c_func!(CFPropertyListCreateData(_, _, _, _, _)),
+    export_c_func!(CFPropertyListCreateDeepCopy(_, _, _)),
+    export_c_func!(CFPropertyListIsValid(_, _)),
+];
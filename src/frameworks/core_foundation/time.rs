@@ -5,17 +5,58 @@
  */
 //! Time things including `CFAbsoluteTime`.
 
-use crate::dyld::{export_c_func, FunctionExports};
+use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
 use crate::frameworks::foundation::NSTimeInterval;
-use crate::objc::msg_class;
+use crate::mem::{ConstVoidPtr, Mem};
 use crate::Environment;
+use std::time::{Duration, SystemTime};
 
 pub type CFTimeInterval = NSTimeInterval;
-type CFAbsoluteTime = CFTimeInterval;
+/// Seconds since the reference date, 2001-01-01 00:00:00 UTC. `NSDate` uses
+/// the same reference date.
+pub type CFAbsoluteTime = CFTimeInterval;
+
+/// Seconds between the UNIX epoch and the reference date.
+pub const kCFAbsoluteTimeIntervalSince1970: CFTimeInterval = 978307200.0;
+
+/// Convert a host wall-clock time to an absolute time.
+pub fn absolute_time_from_system_time(time: SystemTime) -> CFAbsoluteTime {
+    let since_unix_epoch = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    };
+    since_unix_epoch - kCFAbsoluteTimeIntervalSince1970
+}
+
+/// Convert an absolute time to a host wall-clock time.
+pub fn system_time_from_absolute_time(time: CFAbsoluteTime) -> SystemTime {
+    let since_unix_epoch = time + kCFAbsoluteTimeIntervalSince1970;
+    // Duration can't be constructed from non-finite values.
+    let since_unix_epoch = if since_unix_epoch.is_finite() {
+        since_unix_epoch
+    } else {
+        0.0
+    };
+    if since_unix_epoch >= 0.0 {
+        SystemTime::UNIX_EPOCH + Duration::from_secs_f64(since_unix_epoch)
+    } else {
+        SystemTime::UNIX_EPOCH - Duration::from_secs_f64(-since_unix_epoch)
+    }
+}
 
 fn CFAbsoluteTimeGetCurrent(env: &mut Environment) -> CFAbsoluteTime {
-    let time: NSTimeInterval = msg_class![env; NSProcessInfo systemUptime];
-    time
+    absolute_time_from_system_time(env.clock.system_time())
+}
+
+fn get_interval_since_1970(mem: &mut Mem) -> ConstVoidPtr {
+    mem.alloc_and_write(kCFAbsoluteTimeIntervalSince1970)
+        .cast()
+        .cast_const()
 }
 
+pub const CONSTANTS: ConstantExports = &[(
+    "_kCFAbsoluteTimeIntervalSince1970",
+    HostConstant::Custom(get_interval_since_1970),
+)];
+
 pub const FUNCTIONS: FunctionExports = &[export_c_func!(CFAbsoluteTimeGetCurrent())];
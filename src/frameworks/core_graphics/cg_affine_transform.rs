@@ -5,8 +5,8 @@
  */
 //! `CGAffineTransform.h`
 
-use super::CGFloat;
-use crate::abi::GuestArg;
+use super::{CGFloat, CGPoint};
+use crate::abi::{impl_GuestRet_for_large_struct, GuestArg};
 use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
 use crate::mem::SafeRead;
 use crate::Environment;
@@ -24,6 +24,7 @@ pub struct CGAffineTransform {
     pub ty: CGFloat,
 }
 unsafe impl SafeRead for CGAffineTransform {}
+impl_GuestRet_for_large_struct!(CGAffineTransform);
 impl GuestArg for CGAffineTransform {
     const REG_COUNT: usize = 6;
 
@@ -54,6 +55,69 @@ pub const CGAffineTransformIdentity: CGAffineTransform = CGAffineTransform {
     // 0.0, 0.0, 1.0,
 };
 
+impl CGAffineTransform {
+    pub fn make_translation(tx: CGFloat, ty: CGFloat) -> Self {
+        CGAffineTransform {
+            tx,
+            ty,
+            ..CGAffineTransformIdentity
+        }
+    }
+    pub fn make_scale(sx: CGFloat, sy: CGFloat) -> Self {
+        CGAffineTransform {
+            a: sx,
+            d: sy,
+            ..CGAffineTransformIdentity
+        }
+    }
+    pub fn make_rotation(angle: CGFloat) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        CGAffineTransform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            tx: 0.0,
+            ty: 0.0,
+        }
+    }
+
+    /// Get a transform that applies `self` and then `other`.
+    pub fn concat(self, other: Self) -> Self {
+        CGAffineTransform {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            tx: self.tx * other.a + self.ty * other.c + other.tx,
+            ty: self.tx * other.b + self.ty * other.d + other.ty,
+        }
+    }
+
+    /// Get the inverse of the transform, or [None] if it can't be inverted.
+    pub fn invert(self) -> Option<Self> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 {
+            return None;
+        }
+        Some(CGAffineTransform {
+            a: self.d / det,
+            b: -self.b / det,
+            c: -self.c / det,
+            d: self.a / det,
+            tx: (self.c * self.ty - self.d * self.tx) / det,
+            ty: (self.b * self.tx - self.a * self.ty) / det,
+        })
+    }
+
+    pub fn apply_to_point(self, point: CGPoint) -> CGPoint {
+        CGPoint {
+            x: self.a * point.x + self.c * point.y + self.tx,
+            y: self.b * point.x + self.d * point.y + self.ty,
+        }
+    }
+}
+
 pub const CONSTANTS: ConstantExports = &[(
     "_CGAffineTransformIdentity",
     HostConstant::Custom(|mem| {
@@ -67,4 +131,91 @@ fn CGAffineTransformIsIdentity(_env: &mut Environment, transform: CGAffineTransf
     transform == CGAffineTransformIdentity
 }
 
-pub const FUNCTIONS: FunctionExports = &[export_c_func!(CGAffineTransformIsIdentity(_))];
+fn CGAffineTransformMake(
+    _env: &mut Environment,
+    a: CGFloat,
+    b: CGFloat,
+    c: CGFloat,
+    d: CGFloat,
+    tx: CGFloat,
+    ty: CGFloat,
+) -> CGAffineTransform {
+    CGAffineTransform { a, b, c, d, tx, ty }
+}
+fn CGAffineTransformMakeTranslation(
+    _env: &mut Environment,
+    tx: CGFloat,
+    ty: CGFloat,
+) -> CGAffineTransform {
+    CGAffineTransform::make_translation(tx, ty)
+}
+fn CGAffineTransformMakeScale(
+    _env: &mut Environment,
+    sx: CGFloat,
+    sy: CGFloat,
+) -> CGAffineTransform {
+    CGAffineTransform::make_scale(sx, sy)
+}
+fn CGAffineTransformMakeRotation(_env: &mut Environment, angle: CGFloat) -> CGAffineTransform {
+    CGAffineTransform::make_rotation(angle)
+}
+
+fn CGAffineTransformTranslate(
+    _env: &mut Environment,
+    transform: CGAffineTransform,
+    tx: CGFloat,
+    ty: CGFloat,
+) -> CGAffineTransform {
+    CGAffineTransform::make_translation(tx, ty).concat(transform)
+}
+fn CGAffineTransformScale(
+    _env: &mut Environment,
+    transform: CGAffineTransform,
+    sx: CGFloat,
+    sy: CGFloat,
+) -> CGAffineTransform {
+    CGAffineTransform::make_scale(sx, sy).concat(transform)
+}
+fn CGAffineTransformRotate(
+    _env: &mut Environment,
+    transform: CGAffineTransform,
+    angle: CGFloat,
+) -> CGAffineTransform {
+    CGAffineTransform::make_rotation(angle).concat(transform)
+}
+fn CGAffineTransformConcat(
+    _env: &mut Environment,
+    t1: CGAffineTransform,
+    t2: CGAffineTransform,
+) -> CGAffineTransform {
+    t1.concat(t2)
+}
+fn CGAffineTransformInvert(
+    _env: &mut Environment,
+    transform: CGAffineTransform,
+) -> CGAffineTransform {
+    // If the transform can't be inverted, it's returned unchanged.
+    transform.invert().unwrap_or(transform)
+}
+
+fn CGPointApplyAffineTransform(
+    _env: &mut Environment,
+    point: CGPoint,
+    transform: CGAffineTransform,
+) -> CGPoint {
+    transform.apply_to_point(point)
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(CGAffineTransformIsIdentity(_)),
+    export_c_func!(CGAffineTransformMake(_, _, _, _, _, _)),
+    export_c_func!(CGAffineTransformMakeTranslation(_, _)),
+    export_c_func!(CGAffineTransformMakeScale(_, _)),
+    export_c_func!(CGAffineTransformMakeRotation(_)),
+    export_c_func!(CGAffineTransformTranslate(_, _, _)),
+    export_c_func!(CGAffineTransformScale(_, _, _)),
+    export_c_func!(CGAffineTransformRotate(_, _)),
+    export_c_func!(CGAffineTransformConcat(_, _)),
+    export_c_func!(CGAffineTransformInvert(_)),
+    export_c_func!(CGPointApplyAffineTransform(_, _)),
+];
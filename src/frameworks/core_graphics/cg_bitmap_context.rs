@@ -31,7 +31,7 @@ pub(super) struct CGBitmapContextData {
     alpha_info: CGImageAlphaInfo,
 }
 
-fn CGBitmapContextCreate(
+pub fn CGBitmapContextCreate(
     env: &mut Environment,
     data: MutVoidPtr,
     width: GuestUSize,
@@ -70,6 +70,27 @@ fn CGBitmapContextCreate(
         .alloc_object(isa, Box::new(host_object), &mut env.mem)
 }
 
+fn borrow_bitmap_data(env: &Environment, context: CGContextRef) -> CGBitmapContextData {
+    let &CGContextHostObject {
+        subclass: CGContextSubclass::CGBitmapContext(data),
+        ..
+    } = env.objc.borrow(context);
+    data
+}
+
+pub fn CGBitmapContextGetData(env: &mut Environment, context: CGContextRef) -> MutVoidPtr {
+    borrow_bitmap_data(env, context).data
+}
+pub fn CGBitmapContextGetWidth(env: &mut Environment, context: CGContextRef) -> GuestUSize {
+    borrow_bitmap_data(env, context).width
+}
+pub fn CGBitmapContextGetHeight(env: &mut Environment, context: CGContextRef) -> GuestUSize {
+    borrow_bitmap_data(env, context).height
+}
+pub fn CGBitmapContextGetBytesPerRow(env: &mut Environment, context: CGContextRef) -> GuestUSize {
+    borrow_bitmap_data(env, context).bytes_per_row
+}
+
 fn components_for_rgb(bitmap_info: CGBitmapInfo) -> Result<GuestUSize, ()> {
     let byte_order = bitmap_info & kCGBitmapByteOrderMask;
     if byte_order != kCGImageByteOrderDefault && byte_order != kCGImageByteOrder32Big {
@@ -360,6 +381,28 @@ pub(super) fn draw_image(
     context: CGContextRef,
     rect: CGRect,
     image: CGImageRef,
+) {
+    draw_image_inner(env, context, rect, image, /* flipped: */ false)
+}
+
+/// Like `CGContextDrawImage`, but for UIKit's co-ordinate system, which is
+/// flipped relative to Core Graphics'. `UIImage`'s drawing methods use this so
+/// the image is the right way up.
+pub fn draw_image_flipped(
+    env: &mut Environment,
+    context: CGContextRef,
+    rect: CGRect,
+    image: CGImageRef,
+) {
+    draw_image_inner(env, context, rect, image, /* flipped: */ true)
+}
+
+fn draw_image_inner(
+    env: &mut Environment,
+    context: CGContextRef,
+    rect: CGRect,
+    image: CGImageRef,
+    flipped: bool,
 ) {
     let image = cg_image::borrow_image(&env.objc, image);
 
@@ -394,7 +437,8 @@ pub(super) fn draw_image(
             let texel_y = (0.5 + (y - y_start) as f32) / dest_height as f32;
             let texel_x = (image_width as f32 * texel_x) as i32;
             // Image is in top-to-bottom order, but the bitmap is bottom-to-top
-            let texel_y = (image_height as f32 * (1.0 - texel_y)) as i32;
+            let texel_y = if flipped { texel_y } else { 1.0 - texel_y };
+            let texel_y = (image_height as f32 * texel_y) as i32;
             if let Some(color) = image.get_pixel((texel_x, texel_y)) {
                 drawer.put_pixel((x, y), color)
             }
@@ -404,5 +448,10 @@ pub(super) fn draw_image(
     // let _ = std::fs::write(format!("bitmap-{:?}-{:?}-after.data", (image as *const _ as *const ()), (drawer.width(), drawer.height())), &drawer.pixels);
 }
 
-pub const FUNCTIONS: FunctionExports =
-    &[export_c_func!(CGBitmapContextCreate(_, _, _, _, _, _, _))];
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(CGBitmapContextCreate(_, _, _, _, _, _, _)),
+    export_c_func!(CGBitmapContextGetData(_)),
+    export_c_func!(CGBitmapContextGetWidth(_)),
+    export_c_func!(CGBitmapContextGetHeight(_)),
+    export_c_func!(CGBitmapContextGetBytesPerRow(_)),
+];
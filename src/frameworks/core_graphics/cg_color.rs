@@ -0,0 +1,34 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `CGColor.h`
+//!
+//! The only way to get a `CGColorRef` at the moment is `-[UIColor CGColor]`,
+//! and in our implementation that just returns the `UIColor` itself, so this
+//! type is the same object as the `UIColor` it came from.
+
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::frameworks::core_foundation::{CFRelease, CFRetain, CFTypeRef};
+use crate::Environment;
+
+pub type CGColorRef = CFTypeRef;
+
+pub fn CGColorRelease(env: &mut Environment, c: CGColorRef) {
+    if !c.is_null() {
+        CFRelease(env, c);
+    }
+}
+pub fn CGColorRetain(env: &mut Environment, c: CGColorRef) -> CGColorRef {
+    if !c.is_null() {
+        CFRetain(env, c)
+    } else {
+        c
+    }
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(CGColorRelease(_)),
+    export_c_func!(CGColorRetain(_)),
+];
@@ -9,7 +9,7 @@ use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant}
 use crate::frameworks::core_foundation::cf_string::CFStringRef;
 use crate::frameworks::core_foundation::{CFRelease, CFRetain, CFTypeRef};
 use crate::frameworks::foundation::ns_string;
-use crate::objc::{msg, objc_classes, ClassExports, HostObject};
+use crate::objc::{msg, objc_classes, AnyHostObject, ClassExports, HostObject};
 use crate::Environment;
 
 pub const CLASSES: ClassExports = objc_classes! {
@@ -24,10 +24,15 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
+#[derive(Clone)]
 pub(super) struct CGColorSpaceHostObject {
     pub(super) name: &'static str,
 }
-impl HostObject for CGColorSpaceHostObject {}
+impl HostObject for CGColorSpaceHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub type CGColorSpaceRef = CFTypeRef;
 
@@ -9,7 +9,7 @@ use super::cg_image::CGImageRef;
 use super::{cg_bitmap_context, CGFloat, CGRect};
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::frameworks::core_foundation::{CFRelease, CFRetain, CFTypeRef};
-use crate::objc::{objc_classes, ClassExports, HostObject};
+use crate::objc::{objc_classes, AnyHostObject, ClassExports, HostObject};
 use crate::Environment;
 
 pub const CLASSES: ClassExports = objc_classes! {
@@ -24,14 +24,20 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
+#[derive(Clone)]
 pub(super) struct CGContextHostObject {
     pub(super) subclass: CGContextSubclass,
     pub(super) rgb_fill_color: (CGFloat, CGFloat, CGFloat, CGFloat),
     /// Current translation. TODO: replace this with a transformation matrix.
     pub(super) translation: (CGFloat, CGFloat),
 }
-impl HostObject for CGContextHostObject {}
+impl HostObject for CGContextHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
+#[derive(Clone)]
 pub(super) enum CGContextSubclass {
     CGBitmapContext(cg_bitmap_context::CGBitmapContextData),
 }
@@ -51,7 +57,7 @@ pub fn CGContextRetain(env: &mut Environment, c: CGContextRef) -> CGContextRef {
     }
 }
 
-fn CGContextSetRGBFillColor(
+pub fn CGContextSetRGBFillColor(
     env: &mut Environment,
     context: CGContextRef,
     red: CGFloat,
@@ -65,7 +71,7 @@ fn CGContextSetRGBFillColor(
         .rgb_fill_color = color;
 }
 
-fn CGContextFillRect(env: &mut Environment, context: CGContextRef, rect: CGRect) {
+pub fn CGContextFillRect(env: &mut Environment, context: CGContextRef, rect: CGRect) {
     cg_bitmap_context::fill_rect(env, context, rect, /* clear: */ false);
 }
 
@@ -73,7 +79,12 @@ fn CGContextClearRect(env: &mut Environment, context: CGContextRef, rect: CGRect
     cg_bitmap_context::fill_rect(env, context, rect, /* clear: */ true);
 }
 
-fn CGContextTranslateCTM(env: &mut Environment, context: CGContextRef, tx: CGFloat, ty: CGFloat) {
+pub fn CGContextTranslateCTM(
+    env: &mut Environment,
+    context: CGContextRef,
+    tx: CGFloat,
+    ty: CGFloat,
+) {
     let context = env.objc.borrow_mut::<CGContextHostObject>(context);
     context.translation.0 += tx;
     context.translation.1 += ty;
@@ -11,8 +11,9 @@ use crate::frameworks::core_foundation::{CFRelease, CFRetain, CFTypeRef};
 use crate::frameworks::foundation::ns_string;
 use crate::image::Image;
 use crate::mem::GuestUSize;
-use crate::objc::{objc_classes, ClassExports, HostObject, ObjC};
+use crate::objc::{objc_classes, AnyHostObject, ClassExports, HostObject, ObjC};
 use crate::Environment;
+use std::rc::Rc;
 
 pub type CGImageAlphaInfo = u32;
 pub const kCGImageAlphaNone: CGImageAlphaInfo = 0;
@@ -52,10 +53,16 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
+#[derive(Clone)]
 struct CGImageHostObject {
-    image: Image,
+    /// Images are immutable, so copies of the host object can share this.
+    image: Rc<Image>,
+}
+impl HostObject for CGImageHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
 }
-impl HostObject for CGImageHostObject {}
 
 // TODO: CGImageCreate family. Currently the accessor on UIImage is the only way
 //       to create this type.
@@ -77,7 +84,9 @@ pub fn CGImageRetain(env: &mut Environment, c: CGImageRef) -> CGImageRef {
 /// Shortcut for use by `UIImage`: directly construct a `CGImage` instance from
 /// an [Image] instance.
 pub fn from_image(env: &mut Environment, image: Image) -> CGImageRef {
-    let host_obj = Box::new(CGImageHostObject { image });
+    let host_obj = Box::new(CGImageHostObject {
+        image: Rc::new(image),
+    });
     let class = env.objc.get_known_class("_touchHLE_CGImage", &mut env.mem);
     env.objc.alloc_object(class, host_obj, &mut env.mem)
 }
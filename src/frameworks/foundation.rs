@@ -14,12 +14,18 @@
 pub mod ns_array;
 pub mod ns_autorelease_pool;
 pub mod ns_bundle;
+pub mod ns_calendar;
 pub mod ns_character_set;
 pub mod ns_coder;
 pub mod ns_data;
+pub mod ns_date;
+pub mod ns_date_formatter;
 pub mod ns_dictionary;
 pub mod ns_enumerator;
+pub mod ns_exception;
 pub mod ns_file_manager;
+pub mod ns_index_path;
+pub mod ns_keyed_archiver;
 pub mod ns_keyed_unarchiver;
 pub mod ns_locale;
 pub mod ns_log;
@@ -28,26 +34,32 @@ pub mod ns_notification_center;
 pub mod ns_null;
 pub mod ns_object;
 pub mod ns_process_info;
+pub mod ns_property_list_serialization;
 pub mod ns_run_loop;
 pub mod ns_set;
 pub mod ns_string;
 pub mod ns_thread;
+pub mod ns_time_zone;
 pub mod ns_timer;
 pub mod ns_url;
 pub mod ns_user_defaults;
 pub mod ns_util;
 pub mod ns_value;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     ns_autorelease_pool: ns_autorelease_pool::State,
     ns_bundle: ns_bundle::State,
+    ns_exception: ns_exception::State,
     ns_file_manager: ns_file_manager::State,
+    ns_keyed_archiver: ns_keyed_archiver::State,
+    ns_keyed_unarchiver: ns_keyed_unarchiver::State,
     ns_locale: ns_locale::State,
     ns_notification_center: ns_notification_center::State,
     ns_null: ns_null::State,
     ns_run_loop: ns_run_loop::State,
     ns_string: ns_string::State,
+    ns_time_zone: ns_time_zone::State,
     ns_user_defaults: ns_user_defaults::State,
 }
 
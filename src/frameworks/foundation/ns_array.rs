@@ -5,17 +5,26 @@
  */
 //! The `NSArray` class cluster, including `NSMutableArray`.
 
-use super::{ns_keyed_unarchiver, NSUInteger};
+use super::ns_exception::{raise, NSRangeException};
+use super::ns_property_list_serialization::{read_plist_file, value_to_object, write_plist_file};
+use super::{ns_keyed_archiver, ns_keyed_unarchiver, NSUInteger};
 use crate::objc::{
-    id, msg_class, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class,
+    ClassExports, HostObject, NSZonePtr,
 };
 use crate::Environment;
+use plist::Value;
 
 /// Belongs to _touchHLE_NSArray
+#[derive(Clone)]
 struct ArrayHostObject {
     array: Vec<id>,
 }
-impl HostObject for ArrayHostObject {}
+impl HostObject for ArrayHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -35,12 +44,38 @@ pub const CLASSES: ClassExports = objc_classes! {
     msg_class![env; _touchHLE_NSArray allocWithZone:zone]
 }
 
++ (id)arrayWithContentsOfFile:(id)path { // NSString*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithContentsOfFile:path];
+    autorelease(env, new)
+}
+
 // NSCopying implementation
 - (id)copyWithZone:(NSZonePtr)_zone {
     // TODO: override this once we have NSMutableArray!
     retain(env, this)
 }
 
+// NSCoding implementation
+- (())encodeWithCoder:(id)coder {
+    // See `[_touchHLE_NSArray initWithCoder:]` for the format.
+    let count: NSUInteger = msg![env; this count];
+    let objects: Vec<id> = (0..count)
+        .map(|i| msg![env; this objectAtIndex:i])
+        .collect();
+    ns_keyed_archiver::encode_current_array(env, coder, "NS.objects", &objects);
+}
+
+- (Class)classForCoder {
+    // Our private subclasses shouldn't appear in archives.
+    env.objc.get_known_class("NSArray", &mut env.mem)
+}
+
+- (bool)writeToFile:(id)path // NSString*
+         atomically:(bool)use_aux_file {
+    write_plist_file(env, this, path, use_aux_file)
+}
+
 @end
 
 // NSMutableArray is an abstract class. A subclass must provide everything
@@ -66,6 +101,10 @@ pub const CLASSES: ClassExports = objc_classes! {
     todo!(); // TODO: this should produce an immutable copy
 }
 
+- (Class)classForCoder {
+    env.objc.get_known_class("NSMutableArray", &mut env.mem)
+}
+
 @end
 
 // Our private subclass that is the single implementation of NSArray for the
@@ -94,13 +133,17 @@ pub const CLASSES: ClassExports = objc_classes! {
     // allocating an NSString here would be inconvenient, so let's just take a
     // shortcut.
     // FIXME: What if it's not an NSKeyedUnarchiver?
-    let objects = ns_keyed_unarchiver::decode_current_array(env, coder);
+    let objects = ns_keyed_unarchiver::decode_current_array(env, coder, "NS.objects");
     let host_object: &mut ArrayHostObject = env.objc.borrow_mut(this);
     assert!(host_object.array.is_empty());
     host_object.array = objects; // objects are already retained
     this
 }
 
+- (id)initWithContentsOfFile:(id)path { // NSString*
+    init_with_contents_of_file(env, this, path)
+}
+
 - (())dealloc {
     let host_object: &mut ArrayHostObject = env.objc.borrow_mut(this);
     let array = std::mem::take(&mut host_object.array);
@@ -118,7 +161,7 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow::<ArrayHostObject>(this).array.len().try_into().unwrap()
 }
 - (id)objectAtIndex:(NSUInteger)index {
-    // TODO: throw real exception rather than panic if out-of-bounds?
+    check_index(env, this, "objectAtIndex:", index);
     env.objc.borrow::<ArrayHostObject>(this).array[index as usize]
 }
 
@@ -134,6 +177,20 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    // See `[_touchHLE_NSArray initWithCoder:]`.
+    let objects = ns_keyed_unarchiver::decode_current_array(env, coder, "NS.objects");
+    let host_object: &mut ArrayHostObject = env.objc.borrow_mut(this);
+    assert!(host_object.array.is_empty());
+    host_object.array = objects; // objects are already retained
+    this
+}
+
+- (id)initWithContentsOfFile:(id)path { // NSString*
+    init_with_contents_of_file(env, this, path)
+}
+
 - (())dealloc {
     let host_object: &mut ArrayHostObject = env.objc.borrow_mut(this);
     let array = std::mem::take(&mut host_object.array);
@@ -152,7 +209,7 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow::<ArrayHostObject>(this).array.len().try_into().unwrap()
 }
 - (id)objectAtIndex:(NSUInteger)index {
-    // TODO: throw real exception rather than panic if out-of-bounds?
+    check_index(env, this, "objectAtIndex:", index);
     env.objc.borrow::<ArrayHostObject>(this).array[index as usize]
 }
 
@@ -164,6 +221,7 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (())removeObjectAtIndex:(NSUInteger)index {
+    check_index(env, this, "removeObjectAtIndex:", index);
     let object = env.objc.borrow_mut::<ArrayHostObject>(this).array.remove(index as usize);
     release(env, object)
 }
@@ -183,6 +241,7 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (())removeObjectAtIndex:(NSUInteger)index {
+    check_index(env, this, "removeObjectAtIndex:", index);
     env.objc.borrow_mut::<ArrayHostObject>(this).array.remove(index as usize);
 }
 
@@ -190,6 +249,42 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
+/// Raise `NSRangeException` if the index is out of bounds, like Apple's
+/// implementation does.
+fn check_index(env: &mut Environment, this: id, method: &str, index: NSUInteger) {
+    let len = env.objc.borrow::<ArrayHostObject>(this).array.len();
+    if (index as usize) < len {
+        return;
+    }
+    let bounds = if len == 0 {
+        "for empty array".to_string()
+    } else {
+        format!("beyond bounds [0 .. {}]", len - 1)
+    };
+    raise(
+        env,
+        NSRangeException,
+        format!("*** -[NSArray {}]: index {} {}", method, index, bounds),
+    );
+}
+
+/// Shared implementation of `initWithContentsOfFile:` for our subclasses.
+fn init_with_contents_of_file(env: &mut Environment, this: id, path: id) -> id {
+    let Some(Value::Array(values)) = read_plist_file(env, path) else {
+        release(env, this);
+        return nil;
+    };
+    let objects = values
+        .iter()
+        .map(|value| value_to_object(env, value))
+        .filter(|&object| object != nil)
+        .collect();
+    let host_object: &mut ArrayHostObject = env.objc.borrow_mut(this);
+    assert!(host_object.array.is_empty());
+    host_object.array = objects; // objects are already retained
+    this
+}
+
 /// Shortcut for host code, roughly equivalent to
 /// `[[NSArray alloc] initWithObjects:count]` but without copying.
 /// The elements should already be "retained by" the `Vec`.
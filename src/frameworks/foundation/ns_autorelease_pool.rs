@@ -5,10 +5,13 @@
  */
 //! `NSAutoreleasePool`.
 
-use crate::objc::{id, msg, objc_classes, release, ClassExports, HostObject, NSZonePtr};
+use super::ns_exception::{raise, NSInvalidArgumentException};
+use crate::objc::{
+    id, msg, objc_classes, release, AnyHostObject, ClassExports, HostObject, NSZonePtr,
+};
 use crate::Environment;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     pool_stack: Vec<id>,
 }
@@ -18,11 +21,16 @@ impl State {
     }
 }
 
+#[derive(Clone)]
 struct NSAutoreleasePoolHostObject {
     /// This is allowed to contain duplicates, which get released several times!
     objects: Vec<id>,
 }
-impl HostObject for NSAutoreleasePoolHostObject {}
+impl HostObject for NSAutoreleasePoolHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -57,12 +65,19 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (id)retain {
-    // TODO: throw proper exception?
-    panic!("NSAutoreleasePool can't be retained!");
+    raise(
+        env,
+        NSInvalidArgumentException,
+        "*** -[NSAutoreleasePool retain]: Cannot retain an autorelease pool".to_string(),
+    );
 }
 - (id)autorelease {
-    // TODO: throw proper exception?
-    panic!("NSAutoreleasePool can't be autoreleased!");
+    raise(
+        env,
+        NSInvalidArgumentException,
+        "*** -[NSAutoreleasePool autorelease]: Cannot autorelease an autorelease pool"
+            .to_string(),
+    );
 }
 
 - (())drain {
@@ -8,14 +8,16 @@
 use super::ns_string;
 use crate::bundle::Bundle;
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, AnyHostObject, ClassExports,
+    HostObject,
 };
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     main_bundle: Option<id>,
 }
 
+#[derive(Clone)]
 struct NSBundleHostObject {
     /// If this is [None], this is the main bundle's NSBundle instance and the
     /// [Bundle] is stored in [crate::Environment], not here.
@@ -25,7 +27,11 @@ struct NSBundleHostObject {
     /// NSURL with bundle path. [None] if not created yet.
     bundle_url: Option<id>,
 }
-impl HostObject for NSBundleHostObject {}
+impl HostObject for NSBundleHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -0,0 +1,462 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSCalendar` and `NSDateComponents`.
+//!
+//! Only the Gregorian calendar is supported.
+
+use super::ns_string::{get_static_str, to_rust_string};
+use super::{ns_date, ns_time_zone, NSInteger, NSUInteger};
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::frameworks::core_foundation::time::kCFAbsoluteTimeIntervalSince1970;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject,
+    ClassExports, HostObject, NSZonePtr,
+};
+use crate::time_zone::{
+    days_in_month, seconds_from_civil, DateTime, TimeZone, DAY_SECONDS, HOUR_SECONDS,
+    MINUTE_SECONDS,
+};
+use crate::Environment;
+
+pub const NSGregorianCalendar: &str = "gregorian";
+
+pub const CONSTANTS: ConstantExports = &[(
+    "_NSGregorianCalendar",
+    HostConstant::NSString(NSGregorianCalendar),
+)];
+
+pub type NSCalendarUnit = NSUInteger;
+pub const NSEraCalendarUnit: NSCalendarUnit = 1 << 1;
+pub const NSYearCalendarUnit: NSCalendarUnit = 1 << 2;
+pub const NSMonthCalendarUnit: NSCalendarUnit = 1 << 3;
+pub const NSDayCalendarUnit: NSCalendarUnit = 1 << 4;
+pub const NSHourCalendarUnit: NSCalendarUnit = 1 << 5;
+pub const NSMinuteCalendarUnit: NSCalendarUnit = 1 << 6;
+pub const NSSecondCalendarUnit: NSCalendarUnit = 1 << 7;
+pub const NSWeekCalendarUnit: NSCalendarUnit = 1 << 8;
+pub const NSWeekdayCalendarUnit: NSCalendarUnit = 1 << 9;
+pub const NSWeekdayOrdinalCalendarUnit: NSCalendarUnit = 1 << 10;
+
+/// Value of an `NSDateComponents` field that hasn't been set.
+pub const NSUndefinedDateComponent: NSInteger = NSInteger::MAX;
+
+#[derive(Clone)]
+struct NSCalendarHostObject {
+    /// `NSTimeZone*`
+    time_zone: id,
+    /// 1 = Sunday
+    first_weekday: NSUInteger,
+}
+impl HostObject for NSCalendarHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[derive(Clone)]
+struct NSDateComponentsHostObject {
+    era: NSInteger,
+    year: NSInteger,
+    month: NSInteger,
+    day: NSInteger,
+    hour: NSInteger,
+    minute: NSInteger,
+    second: NSInteger,
+    week: NSInteger,
+    /// 1 = Sunday
+    weekday: NSInteger,
+    weekday_ordinal: NSInteger,
+}
+impl HostObject for NSDateComponentsHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+impl Default for NSDateComponentsHostObject {
+    fn default() -> Self {
+        NSDateComponentsHostObject {
+            era: NSUndefinedDateComponent,
+            year: NSUndefinedDateComponent,
+            month: NSUndefinedDateComponent,
+            day: NSUndefinedDateComponent,
+            hour: NSUndefinedDateComponent,
+            minute: NSUndefinedDateComponent,
+            second: NSUndefinedDateComponent,
+            week: NSUndefinedDateComponent,
+            weekday: NSUndefinedDateComponent,
+            weekday_ordinal: NSUndefinedDateComponent,
+        }
+    }
+}
+impl NSDateComponentsHostObject {
+    /// Returns [None] (and logs a warning) for units with no field.
+    fn field_mut(&mut self, unit: NSCalendarUnit) -> Option<&mut NSInteger> {
+        Some(match unit {
+            NSEraCalendarUnit => &mut self.era,
+            NSYearCalendarUnit => &mut self.year,
+            NSMonthCalendarUnit => &mut self.month,
+            NSDayCalendarUnit => &mut self.day,
+            NSHourCalendarUnit => &mut self.hour,
+            NSMinuteCalendarUnit => &mut self.minute,
+            NSSecondCalendarUnit => &mut self.second,
+            NSWeekCalendarUnit => &mut self.week,
+            NSWeekdayCalendarUnit => &mut self.weekday,
+            NSWeekdayOrdinalCalendarUnit => &mut self.weekday_ordinal,
+            _ => {
+                log!("Warning: unknown calendar unit {:#x}, ignoring it", unit);
+                return None;
+            }
+        })
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSCalendar: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSCalendarHostObject {
+        time_zone: nil,
+        first_weekday: 1,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)currentCalendar {
+    let identifier = get_static_str(env, NSGregorianCalendar);
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithCalendarIdentifier:identifier];
+    autorelease(env, new)
+}
++ (id)autoupdatingCurrentCalendar {
+    msg![env; this currentCalendar]
+}
+
+- (id)initWithCalendarIdentifier:(id)identifier { // NSString*
+    let identifier = to_rust_string(env, identifier);
+    if identifier != NSGregorianCalendar {
+        log!("TODO: Calendar {:?} is not supported, returning nil", identifier);
+        release(env, this);
+        return nil;
+    }
+    let time_zone: id = msg_class![env; NSTimeZone defaultTimeZone];
+    retain(env, time_zone);
+    env.objc.borrow_mut::<NSCalendarHostObject>(this).time_zone = time_zone;
+    this
+}
+
+- (())dealloc {
+    let time_zone = env.objc.borrow::<NSCalendarHostObject>(this).time_zone;
+    release(env, time_zone);
+    env.objc.dealloc_object(this, &mut env.mem);
+}
+
+- (id)calendarIdentifier {
+    get_static_str(env, NSGregorianCalendar)
+}
+
+- (id)timeZone {
+    env.objc.borrow::<NSCalendarHostObject>(this).time_zone
+}
+- (())setTimeZone:(id)time_zone { // NSTimeZone*
+    let time_zone: id = if time_zone == nil {
+        msg_class![env; NSTimeZone defaultTimeZone]
+    } else {
+        time_zone
+    };
+    retain(env, time_zone);
+    let host_object = env.objc.borrow_mut::<NSCalendarHostObject>(this);
+    let old = std::mem::replace(&mut host_object.time_zone, time_zone);
+    release(env, old);
+}
+
+- (NSUInteger)firstWeekday {
+    env.objc.borrow::<NSCalendarHostObject>(this).first_weekday
+}
+- (())setFirstWeekday:(NSUInteger)weekday {
+    env.objc.borrow_mut::<NSCalendarHostObject>(this).first_weekday = weekday;
+}
+- (NSUInteger)minimumDaysInFirstWeek {
+    1
+}
+
+- (id)components:(NSCalendarUnit)units
+        fromDate:(id)date { // NSDate*
+    let time_zone = time_zone(env, this);
+    let first_weekday = env.objc.borrow::<NSCalendarHostObject>(this).first_weekday;
+    let date_time = time_zone.date_time(date_to_time(env, date));
+
+    let mut components = NSDateComponentsHostObject::default();
+    for (unit, value) in [
+        // TODO: dates before the common era
+        (NSEraCalendarUnit, 1),
+        (NSYearCalendarUnit, date_time.year),
+        (NSMonthCalendarUnit, date_time.month),
+        (NSDayCalendarUnit, date_time.day),
+        (NSHourCalendarUnit, date_time.hour),
+        (NSMinuteCalendarUnit, date_time.minute),
+        (NSSecondCalendarUnit, date_time.second),
+        (NSWeekCalendarUnit, week_of_year(&date_time, first_weekday.into())),
+        (NSWeekdayCalendarUnit, date_time.weekday + 1),
+        (NSWeekdayOrdinalCalendarUnit, (date_time.day - 1) / 7 + 1),
+    ] {
+        if units & unit == 0 {
+            continue;
+        }
+        if let Some(field) = components.field_mut(unit) {
+            *field = value as NSInteger;
+        }
+    }
+    let new = components_from_host_object(env, components);
+    autorelease(env, new)
+}
+
+- (id)dateFromComponents:(id)components { // NSDateComponents*
+    let time_zone = time_zone(env, this);
+    let components = env.objc.borrow::<NSDateComponentsHostObject>(components).clone();
+    let value = |value: NSInteger, default: i64| {
+        if value == NSUndefinedDateComponent {
+            default
+        } else {
+            value.into()
+        }
+    };
+    if components.week != NSUndefinedDateComponent
+        || components.weekday != NSUndefinedDateComponent
+        || components.weekday_ordinal != NSUndefinedDateComponent
+    {
+        log!("TODO: Week-based date components are ignored by dateFromComponents:");
+    }
+    let local_time = seconds_from_civil(
+        value(components.year, 1),
+        value(components.month, 1),
+        value(components.day, 1),
+        value(components.hour, 0),
+        value(components.minute, 0),
+        value(components.second, 0),
+    );
+    let time = local_time - i64::from(time_zone.utc_offset_for_local_time(local_time, None));
+    time_to_date(env, time as f64)
+}
+
+- (id)dateByAddingComponents:(id)components // NSDateComponents*
+                      toDate:(id)date // NSDate*
+                     options:(NSUInteger)_options {
+    let time_zone = time_zone(env, this);
+    let components = env.objc.borrow::<NSDateComponentsHostObject>(components).clone();
+    let time = date_to_time(env, date);
+    let time = add_components(&time_zone, time, &components);
+    time_to_date(env, time)
+}
+
+- (id)components:(NSCalendarUnit)units
+        fromDate:(id)start_date // NSDate*
+          toDate:(id)end_date // NSDate*
+         options:(NSUInteger)_options {
+    let time_zone = time_zone(env, this);
+    let start = date_to_time(env, start_date);
+    let end = date_to_time(env, end_date);
+    let sign: i64 = if end >= start { 1 } else { -1 };
+    let fits = |time: f64| if sign > 0 { time <= end } else { time >= end };
+
+    // Each unit, from largest to smallest, is given the largest count that
+    // doesn't overshoot the end date.
+    let mut components = NSDateComponentsHostObject::default();
+    let mut offsets = NSDateComponentsHostObject::default();
+    for (unit, approximate_seconds) in [
+        (NSYearCalendarUnit, 365.2425 * DAY_SECONDS as f64),
+        (NSMonthCalendarUnit, 30.436875 * DAY_SECONDS as f64),
+        (NSWeekCalendarUnit, 7.0 * DAY_SECONDS as f64),
+        (NSDayCalendarUnit, DAY_SECONDS as f64),
+        (NSHourCalendarUnit, HOUR_SECONDS as f64),
+        (NSMinuteCalendarUnit, MINUTE_SECONDS as f64),
+        (NSSecondCalendarUnit, 1.0),
+    ] {
+        if units & unit == 0 {
+            continue;
+        }
+        let current = add_components(&time_zone, start, &offsets);
+        let mut count = ((end - current) / approximate_seconds).trunc() as i64;
+        let mut try_count = |count: i64| {
+            if let Some(field) = offsets.field_mut(unit) {
+                *field = count as NSInteger;
+            }
+            fits(add_components(&time_zone, start, &offsets))
+        };
+        while count != 0 && !try_count(count) {
+            count -= sign;
+        }
+        while try_count(count + sign) {
+            count += sign;
+        }
+        if let Some(field) = offsets.field_mut(unit) {
+            *field = count as NSInteger;
+        }
+        if let Some(field) = components.field_mut(unit) {
+            *field = count as NSInteger;
+        }
+    }
+    if units & (NSEraCalendarUnit | NSWeekdayCalendarUnit | NSWeekdayOrdinalCalendarUnit) != 0 {
+        log!(
+            "TODO: Era and weekday units aren't supported by components:fromDate:toDate:options:, ignoring them"
+        );
+    }
+
+    let new = components_from_host_object(env, components);
+    autorelease(env, new)
+}
+
+@end
+
+@implementation NSDateComponents: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<NSDateComponentsHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    let host_object = env.objc.borrow::<NSDateComponentsHostObject>(this).clone();
+    components_from_host_object(env, host_object)
+}
+
+- (NSInteger)era {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).era
+}
+- (())setEra:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).era = value;
+}
+- (NSInteger)year {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).year
+}
+- (())setYear:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).year = value;
+}
+- (NSInteger)month {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).month
+}
+- (())setMonth:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).month = value;
+}
+- (NSInteger)day {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).day
+}
+- (())setDay:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).day = value;
+}
+- (NSInteger)hour {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).hour
+}
+- (())setHour:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).hour = value;
+}
+- (NSInteger)minute {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).minute
+}
+- (())setMinute:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).minute = value;
+}
+- (NSInteger)second {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).second
+}
+- (())setSecond:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).second = value;
+}
+- (NSInteger)week {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).week
+}
+- (())setWeek:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).week = value;
+}
+- (NSInteger)weekday {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).weekday
+}
+- (())setWeekday:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).weekday = value;
+}
+- (NSInteger)weekdayOrdinal {
+    env.objc.borrow::<NSDateComponentsHostObject>(this).weekday_ordinal
+}
+- (())setWeekdayOrdinal:(NSInteger)value {
+    env.objc.borrow_mut::<NSDateComponentsHostObject>(this).weekday_ordinal = value;
+}
+
+@end
+
+};
+
+fn components_from_host_object(
+    env: &mut Environment,
+    host_object: NSDateComponentsHostObject,
+) -> id {
+    let new: id = msg_class![env; NSDateComponents alloc];
+    *env.objc.borrow_mut(new) = host_object;
+    new
+}
+
+fn time_zone(env: &mut Environment, calendar: id) -> std::rc::Rc<TimeZone> {
+    let time_zone = env.objc.borrow::<NSCalendarHostObject>(calendar).time_zone;
+    ns_time_zone::to_time_zone(env, time_zone)
+}
+
+/// Convert an `NSDate` to seconds since the UNIX epoch.
+fn date_to_time(env: &mut Environment, date: id) -> f64 {
+    ns_date::to_time_interval(env, date) + kCFAbsoluteTimeIntervalSince1970
+}
+
+/// Convert seconds since the UNIX epoch to an autoreleased `NSDate`.
+fn time_to_date(env: &mut Environment, time: f64) -> id {
+    let date = ns_date::from_time_interval(env, time - kCFAbsoluteTimeIntervalSince1970);
+    autorelease(env, date)
+}
+
+/// Week of the year, where weeks start on `first_weekday` (1 = Sunday) and
+/// week 1 is the one containing January 1st.
+fn week_of_year(date_time: &DateTime, first_weekday: i64) -> i64 {
+    let january_1st_weekday = (date_time.weekday - date_time.day_of_year).rem_euclid(7);
+    let offset = (january_1st_weekday - (first_weekday - 1)).rem_euclid(7);
+    (date_time.day_of_year + offset) / 7 + 1
+}
+
+/// Add date components to a time (seconds since the UNIX epoch). Years, months,
+/// weeks and days are added to the local date, clamping the day of the month
+/// if necessary (January 31st plus one month is February 28th or 29th), and
+/// then hours, minutes and seconds are added as elapsed time.
+fn add_components(time_zone: &TimeZone, time: f64, components: &NSDateComponentsHostObject) -> f64 {
+    let value = |value: NSInteger| {
+        if value == NSUndefinedDateComponent {
+            0
+        } else {
+            i64::from(value)
+        }
+    };
+    let date_time = time_zone.date_time(time);
+    let months = date_time.year * 12
+        + (date_time.month - 1)
+        + value(components.year) * 12
+        + value(components.month);
+    let (year, month) = (months.div_euclid(12), months.rem_euclid(12) + 1);
+    let day = date_time.day.min(days_in_month(year, month))
+        + value(components.week) * 7
+        + value(components.day);
+    let local_time = seconds_from_civil(
+        year,
+        month,
+        day,
+        date_time.hour,
+        date_time.minute,
+        date_time.second,
+    );
+    let is_dst = Some(date_time.local_time_type.is_dst);
+    let time = local_time - i64::from(time_zone.utc_offset_for_local_time(local_time, is_dst));
+    let elapsed = value(components.hour) * HOUR_SECONDS
+        + value(components.minute) * MINUTE_SECONDS
+        + value(components.second);
+    (time + elapsed) as f64 + date_time.fraction
+}
@@ -7,15 +7,21 @@
 
 use super::ns_string;
 use crate::objc::{
-    autorelease, id, msg, msg_class, objc_classes, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, objc_classes, retain, AnyHostObject, ClassExports, HostObject,
+    NSZonePtr,
 };
 use std::collections::HashSet;
 
 /// Belongs to _touchHLE_NSCharacterSet
+#[derive(Clone)]
 struct CharacterSetHostObject {
     set: HashSet<u16>,
 }
-impl HostObject for CharacterSetHostObject {}
+impl HostObject for CharacterSetHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -13,6 +13,11 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @implementation NSCoder: NSObject
 // This is an abstract class
+
+- (bool)allowsKeyedCoding {
+    false
+}
+
 @end
 
 };
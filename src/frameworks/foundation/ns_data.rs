@@ -8,16 +8,23 @@
 use super::ns_string::to_rust_string;
 use super::NSUInteger;
 use crate::fs::GuestPath;
-use crate::mem::{ConstVoidPtr, MutVoidPtr, Ptr};
+use crate::mem::{ConstVoidPtr, MutPtr, MutVoidPtr, Ptr};
 use crate::objc::{
-    autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject,
+    ClassExports, HostObject, NSZonePtr,
 };
+use crate::Environment;
 
+#[derive(Clone)]
 struct NSDataHostObject {
     bytes: MutVoidPtr,
     length: NSUInteger,
 }
-impl HostObject for NSDataHostObject {}
+impl HostObject for NSDataHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -34,6 +41,12 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
++ (id)data {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new init];
+    autorelease(env, new)
+}
+
 + (id)dataWithBytesNoCopy:(MutVoidPtr)bytes
                    length:(NSUInteger)length {
     let new: id = msg![env; this alloc];
@@ -127,4 +140,111 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @end
 
+@implementation NSMutableData: NSData
+
++ (id)dataWithCapacity:(NSUInteger)capacity {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithCapacity:capacity];
+    autorelease(env, new)
+}
+
+- (id)initWithCapacity:(NSUInteger)_capacity {
+    // The capacity is only a hint, so it can be ignored.
+    this
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    let bytes = to_vec(env, this);
+    from_slice(env, &bytes)
+}
+
+- (MutVoidPtr)mutableBytes {
+    env.objc.borrow::<NSDataHostObject>(this).bytes
+}
+
+- (())setLength:(NSUInteger)length {
+    set_length(env, this, length);
+}
+- (())increaseLengthBy:(NSUInteger)extra_length {
+    let length = env.objc.borrow::<NSDataHostObject>(this).length;
+    set_length(env, this, length.checked_add(extra_length).unwrap());
+}
+
+- (())appendBytes:(ConstVoidPtr)bytes
+           length:(NSUInteger)length {
+    let old_length = env.objc.borrow::<NSDataHostObject>(this).length;
+    set_length(env, this, old_length.checked_add(length).unwrap());
+    let new_bytes: MutPtr<u8> = env.objc.borrow::<NSDataHostObject>(this).bytes.cast();
+    env.mem.memmove((new_bytes + old_length).cast(), bytes, length);
+}
+- (())appendData:(id)other { // NSData*
+    let bytes: ConstVoidPtr = msg![env; other bytes];
+    let length: NSUInteger = msg![env; other length];
+    msg![env; this appendBytes:bytes length:length]
+}
+
+@end
+
 };
+
+/// Change the length of an `NSMutableData`, keeping its contents (up to the
+/// new length) and zero-filling any new bytes. The bytes may move.
+fn set_length(env: &mut Environment, data: id, new_length: NSUInteger) {
+    let &NSDataHostObject {
+        bytes: old_bytes,
+        length: old_length,
+    } = env.objc.borrow(data);
+    if new_length == old_length {
+        return;
+    }
+    let new_bytes = if new_length == 0 {
+        Ptr::null()
+    } else {
+        // Memory from the allocator is always zeroed.
+        let new_bytes = env.mem.alloc(new_length);
+        env.mem.memmove(
+            new_bytes,
+            old_bytes.cast_const(),
+            old_length.min(new_length),
+        );
+        new_bytes
+    };
+    if !old_bytes.is_null() {
+        env.mem.free(old_bytes);
+    }
+    let host_object = env.objc.borrow_mut::<NSDataHostObject>(data);
+    host_object.bytes = new_bytes;
+    host_object.length = new_length;
+}
+
+/// Shortcut for host code: create a new `NSData` (not autoreleased) with a
+/// copy of some bytes.
+pub fn from_slice(env: &mut Environment, bytes: &[u8]) -> id {
+    let new: id = msg_class![env; NSData alloc];
+    if bytes.is_empty() {
+        return new;
+    }
+    let size = bytes.len().try_into().unwrap();
+    let alloc = env.mem.alloc(size);
+    env.mem
+        .bytes_at_mut(alloc.cast(), size)
+        .copy_from_slice(bytes);
+    let host_object = env.objc.borrow_mut::<NSDataHostObject>(new);
+    host_object.bytes = alloc;
+    host_object.length = size;
+    new
+}
+
+/// Shortcut for host code: get a copy of the bytes of an `NSData`.
+pub fn to_vec(env: &mut Environment, data: id) -> Vec<u8> {
+    let bytes: ConstVoidPtr = msg![env; data bytes];
+    let length: NSUInteger = msg![env; data length];
+    // Mem::bytes_at() panics when the pointer is NULL, but NSData's pointer can
+    // be NULL if the length is 0.
+    if length == 0 {
+        Vec::new()
+    } else {
+        env.mem.bytes_at(bytes.cast(), length).to_vec()
+    }
+}
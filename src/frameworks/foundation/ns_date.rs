@@ -0,0 +1,234 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSDate`.
+//!
+//! Dates are stored as the number of seconds since the reference date,
+//! 2001-01-01 00:00:00 UTC, just like `CFAbsoluteTime`.
+
+use super::ns_string::{from_rust_string, get_static_str};
+use super::{
+    NSComparisonResult, NSOrderedAscending, NSOrderedDescending, NSOrderedSame, NSTimeInterval,
+    NSUInteger,
+};
+use crate::frameworks::core_foundation::time::{
+    absolute_time_from_system_time, kCFAbsoluteTimeIntervalSince1970,
+};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, retain, AnyHostObject, Class, ClassExports,
+    HostObject, NSZonePtr,
+};
+use crate::time_zone::TimeZone;
+use crate::Environment;
+
+/// `distantFuture` and `distantPast` are about 2000 years away from the
+/// reference date.
+const DISTANT_INTERVAL: NSTimeInterval = 63113904000.0;
+
+#[derive(Clone)]
+struct NSDateHostObject {
+    /// Seconds since the reference date.
+    time_interval: NSTimeInterval,
+}
+impl HostObject for NSDateHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSDate: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSDateHostObject { time_interval: 0.0 });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)date {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new init];
+    autorelease(env, new)
+}
++ (id)dateWithTimeIntervalSinceNow:(NSTimeInterval)interval {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithTimeIntervalSinceNow:interval];
+    autorelease(env, new)
+}
++ (id)dateWithTimeIntervalSince1970:(NSTimeInterval)interval {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithTimeIntervalSince1970:interval];
+    autorelease(env, new)
+}
++ (id)dateWithTimeIntervalSinceReferenceDate:(NSTimeInterval)interval {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithTimeIntervalSinceReferenceDate:interval];
+    autorelease(env, new)
+}
++ (id)dateWithTimeInterval:(NSTimeInterval)interval
+                 sinceDate:(id)date {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithTimeInterval:interval sinceDate:date];
+    autorelease(env, new)
+}
++ (id)distantFuture {
+    msg![env; this dateWithTimeIntervalSinceReferenceDate:DISTANT_INTERVAL]
+}
++ (id)distantPast {
+    msg![env; this dateWithTimeIntervalSinceReferenceDate:(-DISTANT_INTERVAL)]
+}
+
++ (NSTimeInterval)timeIntervalSinceReferenceDate {
+    now(env)
+}
+
+- (id)init {
+    let interval = now(env);
+    msg![env; this initWithTimeIntervalSinceReferenceDate:interval]
+}
+- (id)initWithTimeIntervalSinceNow:(NSTimeInterval)interval {
+    let interval = now(env) + interval;
+    msg![env; this initWithTimeIntervalSinceReferenceDate:interval]
+}
+- (id)initWithTimeIntervalSince1970:(NSTimeInterval)interval {
+    let interval = interval - kCFAbsoluteTimeIntervalSince1970;
+    msg![env; this initWithTimeIntervalSinceReferenceDate:interval]
+}
+- (id)initWithTimeInterval:(NSTimeInterval)interval
+                 sinceDate:(id)date {
+    let interval = to_time_interval(env, date) + interval;
+    msg![env; this initWithTimeIntervalSinceReferenceDate:interval]
+}
+// This is the designated initializer.
+- (id)initWithTimeIntervalSinceReferenceDate:(NSTimeInterval)interval {
+    env.objc.borrow_mut::<NSDateHostObject>(this).time_interval = interval;
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let key = get_static_str(env, "NS.time");
+    let interval: NSTimeInterval = msg![env; coder decodeDoubleForKey:key];
+    msg![env; this initWithTimeIntervalSinceReferenceDate:interval]
+}
+- (())encodeWithCoder:(id)coder {
+    let interval = to_time_interval(env, this);
+    let key = get_static_str(env, "NS.time");
+    msg![env; coder encodeDouble:interval forKey:key]
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Dates are immutable.
+    retain(env, this)
+}
+
+- (NSTimeInterval)timeIntervalSinceReferenceDate {
+    env.objc.borrow::<NSDateHostObject>(this).time_interval
+}
+- (NSTimeInterval)timeIntervalSince1970 {
+    to_time_interval(env, this) + kCFAbsoluteTimeIntervalSince1970
+}
+- (NSTimeInterval)timeIntervalSinceNow {
+    to_time_interval(env, this) - now(env)
+}
+- (NSTimeInterval)timeIntervalSinceDate:(id)other {
+    to_time_interval(env, this) - to_time_interval(env, other)
+}
+
+- (id)dateByAddingTimeInterval:(NSTimeInterval)interval {
+    let class: Class = msg![env; this class];
+    msg![env; class dateWithTimeInterval:interval sinceDate:this]
+}
+- (id)addTimeInterval:(NSTimeInterval)interval {
+    // Deprecated name for the above.
+    msg![env; this dateByAddingTimeInterval:interval]
+}
+
+- (NSComparisonResult)compare:(id)other {
+    let a = to_time_interval(env, this);
+    let b = to_time_interval(env, other);
+    if a < b {
+        NSOrderedAscending
+    } else if a > b {
+        NSOrderedDescending
+    } else {
+        NSOrderedSame
+    }
+}
+- (bool)isEqualToDate:(id)other {
+    if other == nil {
+        return false;
+    }
+    to_time_interval(env, this) == to_time_interval(env, other)
+}
+- (bool)isEqual:(id)other {
+    if this == other {
+        return true;
+    }
+    let class: Class = msg_class![env; NSDate class];
+    if !msg![env; other isKindOfClass:class] {
+        return false;
+    }
+    msg![env; this isEqualToDate:other]
+}
+- (NSUInteger)hash {
+    super::hash_helper(&to_time_interval(env, this).to_bits())
+}
+- (id)earlierDate:(id)other {
+    if to_time_interval(env, other) < to_time_interval(env, this) {
+        other
+    } else {
+        this
+    }
+}
+- (id)laterDate:(id)other {
+    if to_time_interval(env, other) > to_time_interval(env, this) {
+        other
+    } else {
+        this
+    }
+}
+
+- (id)description {
+    // e.g. "2001-01-01 00:00:00 +0000"
+    let time = to_time_interval(env, this) + kCFAbsoluteTimeIntervalSince1970;
+    let date_time = TimeZone::utc().date_time(time);
+    let description = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} +0000",
+        date_time.year,
+        date_time.month,
+        date_time.day,
+        date_time.hour,
+        date_time.minute,
+        date_time.second,
+    );
+    let description = from_rust_string(env, description);
+    autorelease(env, description)
+}
+
+@end
+
+};
+
+/// Current time, in seconds since the reference date.
+fn now(env: &mut Environment) -> NSTimeInterval {
+    absolute_time_from_system_time(env.clock.system_time())
+}
+
+/// Shortcut for host code: create a new `NSDate` (not autoreleased) for a
+/// number of seconds since the reference date.
+pub fn from_time_interval(env: &mut Environment, interval: NSTimeInterval) -> id {
+    let new: id = msg_class![env; NSDate alloc];
+    msg![env; new initWithTimeIntervalSinceReferenceDate:interval]
+}
+
+/// Shortcut for host code: get the number of seconds since the reference date
+/// for an `NSDate`.
+pub fn to_time_interval(env: &mut Environment, date: id) -> NSTimeInterval {
+    msg![env; date timeIntervalSinceReferenceDate]
+}
@@ -0,0 +1,852 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSFormatter` and `NSDateFormatter`.
+//!
+//! Date formats use the patterns from
+//! [Unicode Technical Standard #35](https://unicode.org/reports/tr35/tr35-dates.html#Date_Field_Symbol_Table),
+//! like Apple's implementation. Month and weekday names are always in English,
+//! whatever the locale, and the date and time styles are those of the `en_US`
+//! locale.
+
+use super::ns_string::{from_rust_string, to_rust_string};
+use super::{ns_date, ns_time_zone, NSUInteger};
+use crate::frameworks::core_foundation::time::kCFAbsoluteTimeIntervalSince1970;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject,
+    ClassExports, HostObject, NSZonePtr,
+};
+use crate::time_zone::{days_in_month, seconds_from_civil, DateTime, TimeZone};
+use crate::Environment;
+
+pub type NSDateFormatterStyle = NSUInteger;
+pub const NSDateFormatterNoStyle: NSDateFormatterStyle = 0;
+pub const NSDateFormatterShortStyle: NSDateFormatterStyle = 1;
+pub const NSDateFormatterMediumStyle: NSDateFormatterStyle = 2;
+pub const NSDateFormatterLongStyle: NSDateFormatterStyle = 3;
+pub const NSDateFormatterFullStyle: NSDateFormatterStyle = 4;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+#[derive(Clone)]
+struct NSDateFormatterHostObject {
+    /// `NSString*`, or `nil` if the styles should be used.
+    date_format: id,
+    date_style: NSDateFormatterStyle,
+    time_style: NSDateFormatterStyle,
+    /// `NSTimeZone*`, or `nil` for the default time zone.
+    time_zone: id,
+    /// `NSLocale*`
+    locale: id,
+}
+impl HostObject for NSDateFormatterHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+// NSFormatter is an abstract class. NSDateFormatter is the only subclass here.
+@implementation NSFormatter: NSObject
+@end
+
+@implementation NSDateFormatter: NSFormatter
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSDateFormatterHostObject {
+        date_format: nil,
+        date_style: NSDateFormatterNoStyle,
+        time_style: NSDateFormatterNoStyle,
+        time_zone: nil,
+        locale: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)localizedStringFromDate:(id)date // NSDate*
+                    dateStyle:(NSDateFormatterStyle)date_style
+                    timeStyle:(NSDateFormatterStyle)time_style {
+    let formatter: id = msg![env; this new];
+    let _: () = msg![env; formatter setDateStyle:date_style];
+    let _: () = msg![env; formatter setTimeStyle:time_style];
+    let string: id = msg![env; formatter stringFromDate:date];
+    release(env, formatter);
+    string
+}
+
+- (())dealloc {
+    let &NSDateFormatterHostObject {
+        date_format,
+        time_zone,
+        locale,
+        ..
+    } = env.objc.borrow(this);
+    release(env, date_format);
+    release(env, time_zone);
+    release(env, locale);
+    env.objc.dealloc_object(this, &mut env.mem);
+}
+
+- (())setFormatterBehavior:(NSUInteger)_behavior {
+    // Only the modern (10.4+) behavior is supported, which is the default.
+}
+
+- (id)dateFormat {
+    let date_format = env.objc.borrow::<NSDateFormatterHostObject>(this).date_format;
+    if date_format != nil {
+        return date_format;
+    }
+    let pattern = pattern(env, this);
+    let pattern = from_rust_string(env, pattern);
+    autorelease(env, pattern)
+}
+- (())setDateFormat:(id)date_format { // NSString*
+    let date_format: id = msg![env; date_format copy];
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    let old = std::mem::replace(&mut host_object.date_format, date_format);
+    release(env, old);
+}
+
+- (NSDateFormatterStyle)dateStyle {
+    env.objc.borrow::<NSDateFormatterHostObject>(this).date_style
+}
+- (())setDateStyle:(NSDateFormatterStyle)style {
+    assert!(style <= NSDateFormatterFullStyle);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    host_object.date_style = style;
+    // Setting a style replaces the format.
+    let old = std::mem::replace(&mut host_object.date_format, nil);
+    release(env, old);
+}
+- (NSDateFormatterStyle)timeStyle {
+    env.objc.borrow::<NSDateFormatterHostObject>(this).time_style
+}
+- (())setTimeStyle:(NSDateFormatterStyle)style {
+    assert!(style <= NSDateFormatterFullStyle);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    host_object.time_style = style;
+    let old = std::mem::replace(&mut host_object.date_format, nil);
+    release(env, old);
+}
+
+- (id)timeZone {
+    let time_zone = env.objc.borrow::<NSDateFormatterHostObject>(this).time_zone;
+    if time_zone != nil {
+        time_zone
+    } else {
+        msg_class![env; NSTimeZone defaultTimeZone]
+    }
+}
+- (())setTimeZone:(id)time_zone { // NSTimeZone*
+    retain(env, time_zone);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    let old = std::mem::replace(&mut host_object.time_zone, time_zone);
+    release(env, old);
+}
+
+- (id)locale {
+    let locale = env.objc.borrow::<NSDateFormatterHostObject>(this).locale;
+    if locale != nil {
+        locale
+    } else {
+        msg_class![env; NSLocale currentLocale]
+    }
+}
+- (())setLocale:(id)locale { // NSLocale*
+    // The locale is only remembered, see the module documentation.
+    retain(env, locale);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    let old = std::mem::replace(&mut host_object.locale, locale);
+    release(env, old);
+}
+
+- (id)stringFromDate:(id)date { // NSDate*
+    if date == nil {
+        return nil;
+    }
+    let items = parse_pattern(&pattern(env, this));
+    let time_zone = time_zone(env, this);
+    let time = ns_date::to_time_interval(env, date) + kCFAbsoluteTimeIntervalSince1970;
+    let string = format_date(&items, &time_zone.date_time(time));
+    let string = from_rust_string(env, string);
+    autorelease(env, string)
+}
+
+- (id)dateFromString:(id)string { // NSString*
+    if string == nil {
+        return nil;
+    }
+    let items = parse_pattern(&pattern(env, this));
+    let time_zone = time_zone(env, this);
+    let string = to_rust_string(env, string);
+    let Some(time) = parse_date(&items, &string, &time_zone) else {
+        return nil;
+    };
+    let date = ns_date::from_time_interval(env, time - kCFAbsoluteTimeIntervalSince1970);
+    autorelease(env, date)
+}
+
+@end
+
+};
+
+/// Get the pattern a formatter uses, either the one set with `setDateFormat:`
+/// or one for its date and time styles.
+fn pattern(env: &mut Environment, formatter: id) -> String {
+    let &NSDateFormatterHostObject {
+        date_format,
+        date_style,
+        time_style,
+        ..
+    } = env.objc.borrow(formatter);
+    if date_format != nil {
+        return to_rust_string(env, date_format).into_owned();
+    }
+    let date_pattern = match date_style {
+        NSDateFormatterNoStyle => "",
+        NSDateFormatterShortStyle => "M/d/yy",
+        NSDateFormatterMediumStyle => "MMM d, y",
+        NSDateFormatterLongStyle => "MMMM d, y",
+        _ => "EEEE, MMMM d, y",
+    };
+    let time_pattern = match time_style {
+        NSDateFormatterNoStyle => "",
+        NSDateFormatterShortStyle => "h:mm a",
+        NSDateFormatterMediumStyle => "h:mm:ss a",
+        NSDateFormatterLongStyle => "h:mm:ss a z",
+        _ => "h:mm:ss a zzzz",
+    };
+    if date_pattern.is_empty() || time_pattern.is_empty() {
+        format!("{}{}", date_pattern, time_pattern)
+    } else if date_style >= NSDateFormatterLongStyle {
+        format!("{} 'at' {}", date_pattern, time_pattern)
+    } else {
+        format!("{}, {}", date_pattern, time_pattern)
+    }
+}
+
+fn time_zone(env: &mut Environment, formatter: id) -> std::rc::Rc<TimeZone> {
+    let time_zone = env
+        .objc
+        .borrow::<NSDateFormatterHostObject>(formatter)
+        .time_zone;
+    if time_zone != nil {
+        ns_time_zone::to_time_zone(env, time_zone)
+    } else {
+        ns_time_zone::default_time_zone(env)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PatternItem {
+    Literal(String),
+    /// A pattern letter and how many times it is repeated.
+    Field(char, usize),
+}
+
+/// Split a pattern into literal text and fields. ASCII letters are fields,
+/// text in single quotes is literal, and `''` is a literal single quote.
+fn parse_pattern(pattern: &str) -> Vec<PatternItem> {
+    let mut items = Vec::new();
+    let mut literal = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            if chars.next_if_eq(&'\'').is_some() {
+                literal.push('\'');
+                continue;
+            }
+            while let Some(c) = chars.next() {
+                if c != '\'' {
+                    literal.push(c);
+                } else if chars.next_if_eq(&'\'').is_some() {
+                    literal.push('\'');
+                } else {
+                    break;
+                }
+            }
+        } else if c.is_ascii_alphabetic() {
+            let mut count = 1;
+            while chars.next_if_eq(&c).is_some() {
+                count += 1;
+            }
+            if !literal.is_empty() {
+                items.push(PatternItem::Literal(std::mem::take(&mut literal)));
+            }
+            items.push(PatternItem::Field(c, count));
+        } else {
+            literal.push(c);
+        }
+    }
+    if !literal.is_empty() {
+        items.push(PatternItem::Literal(literal));
+    }
+    items
+}
+
+/// Format a name (month, weekday etc) for a field with `count` letters: 1 to
+/// 3 letters is abbreviated, 4 is in full and 5 is a single letter.
+fn format_name(name: &str, count: usize) -> &str {
+    match count {
+        4 => name,
+        5 => &name[..1],
+        _ => &name[..3],
+    }
+}
+
+/// Format an offset from UTC like `+0100` (`colon == false`) or `+01:00`.
+fn format_utc_offset(utc_offset: i32, colon: bool) -> String {
+    let sign = if utc_offset < 0 { '-' } else { '+' };
+    let minutes = utc_offset.unsigned_abs() / 60;
+    let separator = if colon { ":" } else { "" };
+    format!(
+        "{}{:02}{}{:02}",
+        sign,
+        minutes / 60,
+        separator,
+        minutes % 60
+    )
+}
+
+fn format_date(items: &[PatternItem], date_time: &DateTime) -> String {
+    let mut string = String::new();
+    for item in items {
+        let (letter, count) = match item {
+            PatternItem::Literal(literal) => {
+                string.push_str(literal);
+                continue;
+            }
+            &PatternItem::Field(letter, count) => (letter, count),
+        };
+        let number = |value: i64| format!("{:01$}", value, count);
+        let utc_offset = date_time.local_time_type.utc_offset;
+        let field = match letter {
+            'G' => {
+                let (short, long) = if date_time.year > 0 {
+                    ("AD", "Anno Domini")
+                } else {
+                    ("BC", "Before Christ")
+                };
+                match count {
+                    4 => long.to_string(),
+                    5 => short[..1].to_string(),
+                    _ => short.to_string(),
+                }
+            }
+            'y' | 'Y' | 'u' => {
+                // Years of the era count backwards from 1 BC, but `u` is the
+                // extended year, where 1 BC is 0.
+                let year = if letter != 'u' && date_time.year <= 0 {
+                    1 - date_time.year
+                } else {
+                    date_time.year
+                };
+                match count {
+                    2 => format!("{:02}", year.rem_euclid(100)),
+                    _ => number(year),
+                }
+            }
+            'M' | 'L' if count <= 2 => number(date_time.month),
+            'M' | 'L' => format_name(MONTHS[date_time.month as usize - 1], count).to_string(),
+            'd' => number(date_time.day),
+            'D' => number(date_time.day_of_year + 1),
+            'e' | 'c' if count <= 2 => number(date_time.weekday + 1),
+            'E' | 'e' | 'c' => format_name(WEEKDAYS[date_time.weekday as usize], count).to_string(),
+            'a' if date_time.hour < 12 => "AM".to_string(),
+            'a' => "PM".to_string(),
+            'h' => number((date_time.hour + 11) % 12 + 1),
+            'H' => number(date_time.hour),
+            'K' => number(date_time.hour % 12),
+            'k' if date_time.hour == 0 => number(24),
+            'k' => number(date_time.hour),
+            'm' => number(date_time.minute),
+            's' => number(date_time.second),
+            'S' => {
+                let digits = (date_time.fraction * 10f64.powi(count as i32)).floor();
+                format!("{:01$}", digits as i64, count)
+            }
+            'z' if count < 4 => date_time.local_time_type.abbreviation.clone(),
+            'z' | 'Z' if count == 4 && utc_offset == 0 => "GMT".to_string(),
+            'z' | 'Z' if count == 4 => format!("GMT{}", format_utc_offset(utc_offset, true)),
+            'Z' if count < 4 => format_utc_offset(utc_offset, false),
+            'Z' | 'X' if utc_offset == 0 => "Z".to_string(),
+            'Z' | 'X' | 'x' if count == 1 => format_utc_offset(utc_offset, false)[..3].to_string(),
+            'Z' | 'X' | 'x' => format_utc_offset(utc_offset, count != 2 && count != 4),
+            _ => {
+                log!(
+                    "TODO: Date format field {:?} is not supported, ignoring it",
+                    letter.to_string().repeat(count)
+                );
+                String::new()
+            }
+        };
+        string.push_str(&field);
+    }
+    string
+}
+
+/// Parse a number of at most `max_digits` digits at the start of a string.
+/// Returns the number and how many bytes it used.
+fn parse_number(string: &str, max_digits: usize) -> Option<(i64, usize)> {
+    let len = string
+        .bytes()
+        .take(max_digits)
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    Some((string[..len].parse().ok()?, len))
+}
+
+/// Parse a name from a list (month, weekday etc), in full or abbreviated, at
+/// the start of a string. Returns the index in the list and how many bytes the
+/// name used.
+fn parse_name(string: &str, names: &[&str]) -> Option<(usize, usize)> {
+    let starts_with = |prefix: &str| {
+        string
+            .get(..prefix.len())
+            .map_or(false, |start| start.eq_ignore_ascii_case(prefix))
+    };
+    names
+        .iter()
+        .position(|name| starts_with(name))
+        .map(|index| (index, names[index].len()))
+        .or_else(|| {
+            names
+                .iter()
+                .position(|name| starts_with(&name[..3]))
+                .map(|index| (index, 3))
+        })
+}
+
+/// Parse a time zone at the start of a string: an offset from UTC like `Z`,
+/// `+0100`, `+01:00` or `GMT+01:00`, or an abbreviation like `CET`. Returns the
+/// time zone and how many bytes it used.
+fn parse_time_zone(string: &str, default: &TimeZone) -> Option<(TimeZone, usize)> {
+    if string.starts_with('Z') {
+        return Some((TimeZone::utc(), 1));
+    }
+    let (prefix_len, offset) = if string.starts_with("GMT") || string.starts_with("UTC") {
+        (3, &string[3..])
+    } else {
+        (0, string)
+    };
+    if let Some(sign @ ('+' | '-')) = offset.chars().next() {
+        let (hours, hours_len) = parse_number(&offset[1..], 2)?;
+        let mut len = 1 + hours_len;
+        let mut minutes = 0;
+        if hours_len == 2 {
+            let colon = offset[len..].starts_with(':') as usize;
+            if let Some((value, minutes_len)) = parse_number(&offset[len + colon..], 2) {
+                if minutes_len == 2 {
+                    minutes = value;
+                    len += colon + minutes_len;
+                }
+            }
+        }
+        let utc_offset = (hours * 60 + minutes) * 60 * if sign == '-' { -1 } else { 1 };
+        return Some((TimeZone::with_offset(utc_offset as i32), prefix_len + len));
+    }
+    if prefix_len != 0 {
+        return Some((TimeZone::with_offset(0), prefix_len));
+    }
+
+    let len = string
+        .bytes()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let abbreviation = &string[..len];
+    if len == 0 {
+        return None;
+    }
+    let time_zone =
+        ns_time_zone::time_zone_for_abbreviation(abbreviation).unwrap_or_else(|| default.clone());
+    Some((time_zone, len))
+}
+
+/// Parse a date according to a pattern. Returns seconds since the UNIX epoch,
+/// or [None] if the string doesn't match the pattern.
+fn parse_date(items: &[PatternItem], string: &str, default_time_zone: &TimeZone) -> Option<f64> {
+    let mut rest = string;
+    // Unspecified fields default to the UNIX epoch, like Apple's
+    // implementation.
+    let mut year = 1970;
+    let mut month = 1;
+    let mut day = 1;
+    let mut day_of_year = None;
+    let mut hour = 0;
+    let mut is_12_hour = false;
+    let mut is_pm = None;
+    let mut minute = 0;
+    let mut second = 0;
+    let mut fraction = 0.0;
+    let mut is_bc = false;
+    let mut time_zone = None;
+
+    for (i, item) in items.iter().enumerate() {
+        let (letter, count) = match item {
+            PatternItem::Literal(literal) => {
+                rest = rest.strip_prefix(literal.as_str())?;
+                continue;
+            }
+            &PatternItem::Field(letter, count) => (letter, count),
+        };
+        // If a number is followed directly by another number, like in
+        // "yyyyMMdd", it must be exactly as long as its field.
+        let max_digits = match items.get(i + 1) {
+            Some(&PatternItem::Field(next_letter, next_count))
+                if "yYuMLdDHhKkmsS".contains(next_letter)
+                    && !("ML".contains(next_letter) && next_count > 2) =>
+            {
+                count
+            }
+            _ => usize::MAX,
+        };
+        let mut number = || {
+            let (value, len) = parse_number(rest, max_digits)?;
+            rest = &rest[len..];
+            Some((value, len))
+        };
+        match letter {
+            'y' | 'Y' | 'u' => {
+                let (value, len) = number()?;
+                year = if count == 2 && len == 2 {
+                    // Two-digit years are assumed to be in 1950–2049.
+                    if value < 50 {
+                        2000 + value
+                    } else {
+                        1900 + value
+                    }
+                } else {
+                    value
+                };
+            }
+            'M' | 'L' if count <= 2 => month = number()?.0,
+            'M' | 'L' => {
+                let (index, len) = parse_name(rest, &MONTHS)?;
+                rest = &rest[len..];
+                month = index as i64 + 1;
+            }
+            'd' => day = number()?.0,
+            'D' => day_of_year = Some(number()?.0),
+            'e' | 'c' if count <= 2 => {
+                number()?;
+            }
+            'E' | 'e' | 'c' => {
+                // The weekday is redundant, so it's only checked for syntax.
+                let (_, len) = parse_name(rest, &WEEKDAYS)?;
+                rest = &rest[len..];
+            }
+            'a' => {
+                let am_pm = rest.get(..2)?;
+                if am_pm.eq_ignore_ascii_case("AM") {
+                    is_pm = Some(false);
+                } else if am_pm.eq_ignore_ascii_case("PM") {
+                    is_pm = Some(true);
+                } else {
+                    return None;
+                }
+                rest = &rest[2..];
+            }
+            'h' | 'K' => {
+                hour = number()?.0;
+                is_12_hour = true;
+            }
+            'H' => hour = number()?.0,
+            'k' => hour = number()?.0 % 24,
+            'm' => minute = number()?.0,
+            's' => second = number()?.0,
+            'S' => {
+                let (value, len) = number()?;
+                fraction = value as f64 / 10f64.powi(len as i32);
+            }
+            'z' | 'Z' | 'X' | 'x' => {
+                let (parsed, len) = parse_time_zone(rest, default_time_zone)?;
+                rest = &rest[len..];
+                time_zone = Some(parsed);
+            }
+            'G' => {
+                if let Some(after) = rest.strip_prefix("AD") {
+                    rest = after;
+                } else if let Some(after) = rest.strip_prefix("BC") {
+                    rest = after;
+                    is_bc = true;
+                } else {
+                    return None;
+                }
+            }
+            _ => {
+                log!(
+                    "TODO: Date format field {:?} is not supported for parsing",
+                    letter.to_string().repeat(count)
+                );
+                return None;
+            }
+        }
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+
+    if is_bc {
+        year = 1 - year;
+    }
+    if is_12_hour {
+        if !(0..=12).contains(&hour) {
+            return None;
+        }
+        hour %= 12;
+    }
+    if is_pm == Some(true) && hour < 12 {
+        hour += 12;
+    }
+    if let Some(day_of_year) = day_of_year {
+        month = 1;
+        day = day_of_year;
+    } else if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let local_time = seconds_from_civil(year, month, day, hour, minute, second);
+    let time_zone = time_zone.as_ref().unwrap_or(default_time_zone);
+    let utc_offset = time_zone.utc_offset_for_local_time(local_time, None);
+    Some((local_time - i64::from(utc_offset)) as f64 + fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(pattern: &str, time: f64, time_zone: &TimeZone) -> String {
+        format_date(&parse_pattern(pattern), &time_zone.date_time(time))
+    }
+
+    fn parse(pattern: &str, string: &str, time_zone: &TimeZone) -> Option<f64> {
+        parse_date(&parse_pattern(pattern), string, time_zone)
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        use PatternItem::{Field, Literal};
+        assert_eq!(
+            parse_pattern("yyyy-MM-dd'T'HH:mm"),
+            [
+                Field('y', 4),
+                Literal("-".to_string()),
+                Field('M', 2),
+                Literal("-".to_string()),
+                Field('d', 2),
+                Literal("T".to_string()),
+                Field('H', 2),
+                Literal(":".to_string()),
+                Field('m', 2),
+            ]
+        );
+        assert_eq!(
+            parse_pattern("h 'o''clock' a"),
+            [
+                Field('h', 1),
+                Literal(" o'clock ".to_string()),
+                Field('a', 1),
+            ]
+        );
+        assert_eq!(
+            parse_pattern("''EEE''"),
+            [
+                Literal("'".to_string()),
+                Field('E', 3),
+                Literal("'".to_string()),
+            ]
+        );
+        // An unterminated quote runs to the end.
+        assert_eq!(
+            parse_pattern("s 'sec"),
+            [Field('s', 1), Literal(" sec".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_format_date() {
+        let utc = TimeZone::utc();
+        let time = 1234567890.0; // 2009-02-13 23:31:30 UTC
+        assert_eq!(
+            format("yyyy-MM-dd HH:mm:ss zzz", time, &utc),
+            "2009-02-13 23:31:30 UTC"
+        );
+        assert_eq!(
+            format(
+                "yyyy-MM-dd HH:mm:ss zzz",
+                time,
+                &TimeZone::with_offset(3600)
+            ),
+            "2009-02-14 00:31:30 GMT+0100"
+        );
+        assert_eq!(
+            format(
+                "EEE, d MMM yy h:mm a Z",
+                time,
+                &TimeZone::with_offset(-5 * 3600)
+            ),
+            "Fri, 13 Feb 09 6:31 PM -0500"
+        );
+        assert_eq!(
+            format("EEEE, MMMM d 'at' HH:mm:ss.SSS ZZZZ", time + 0.5, &utc),
+            "Friday, February 13 at 23:31:30.500 GMT"
+        );
+        assert_eq!(
+            format(
+                "yyyy-MM-dd'T'HH:mm:ssZZZZZ",
+                time,
+                &TimeZone::with_offset(-3600)
+            ),
+            "2009-02-13T22:31:30-01:00"
+        );
+        assert_eq!(
+            format("yyyy-MM-dd'T'HH:mm:ssXXX", time, &utc),
+            "2009-02-13T23:31:30Z"
+        );
+        assert_eq!(
+            format("'Day' D', week day' e", time, &utc),
+            "Day 44, week day 6"
+        );
+        // 0001-01-01 00:00:00 UTC is the first second AD.
+        let time = -62135596800.0;
+        assert_eq!(format("G yyyy", time, &utc), "AD 0001");
+        assert_eq!(format("G yyyy", time - 1.0, &utc), "BC 0001");
+        assert_eq!(format("u", time - 1.0, &utc), "0");
+        assert_eq!(
+            parse("G yyyy", "BC 0001", &utc),
+            Some(time - 366.0 * 86400.0)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let time_zones = [
+            TimeZone::utc(),
+            TimeZone::with_offset(0),
+            TimeZone::with_offset(3600),
+            TimeZone::with_offset(-(3 * 3600 + 30 * 60)),
+            TimeZone::with_offset(13 * 3600),
+        ];
+        let patterns = [
+            "yyyy-MM-dd HH:mm:ss zzz",
+            "yyyy-MM-dd HH:mm:ss Z",
+            "yyyy-MM-dd'T'HH:mm:ssZZZZZ",
+            "yyyy-MM-dd'T'HH:mm:ss.SSSXXX",
+            "EEE, dd MMM yyyy HH:mm:ss ZZZZ",
+            "EEEE, MMMM d, yyyy 'at' h:mm:ss a zzz",
+            "'Today is' D 'of' yyyy', and it''s' HH:mm:ss z",
+            "yyyyMMddHHmmssZ",
+        ];
+        // 2009-02-13 23:31:30, 1970-01-01 00:00:00, the last second of a leap
+        // year, and a time with a fraction of a second.
+        let times = [1234567890.0, 0.0, 1230767999.0, 1700000000.25];
+        for time_zone in &time_zones {
+            for pattern in patterns {
+                for time in times {
+                    // The default time zone mustn't be what makes this work.
+                    let other = TimeZone::with_offset(-7 * 3600);
+                    let string = format(pattern, time, time_zone);
+                    let expected = if pattern.contains('S') {
+                        time
+                    } else {
+                        time.floor()
+                    };
+                    assert_eq!(
+                        parse(pattern, &string, &other),
+                        Some(expected),
+                        "{:?} {:?}",
+                        pattern,
+                        string
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let utc = TimeZone::utc();
+        let offset = TimeZone::with_offset(3600);
+        let pattern = "yyyy-MM-dd HH:mm:ss";
+        // Without a time zone in the string, the formatter's is used.
+        assert_eq!(
+            parse(pattern, "2009-02-13 23:31:30", &utc),
+            Some(1234567890.0)
+        );
+        assert_eq!(
+            parse(pattern, "2009-02-14 00:31:30", &offset),
+            Some(1234567890.0)
+        );
+        let pattern = "yyyy-MM-dd HH:mm:ss zzz";
+        assert_eq!(
+            parse(pattern, "2009-02-13 23:31:30 GMT", &offset),
+            Some(1234567890.0)
+        );
+        assert_eq!(
+            parse(pattern, "2009-02-13 23:31:30 Z", &offset),
+            Some(1234567890.0)
+        );
+        assert_eq!(
+            parse(pattern, "2009-02-13 18:31:30 UTC-05:00", &offset),
+            Some(1234567890.0)
+        );
+        assert_eq!(
+            parse(pattern, "2009-02-14 00:31:30 +01", &utc),
+            Some(1234567890.0)
+        );
+        // Unspecified fields default to the UNIX epoch.
+        assert_eq!(parse("HH:mm", "01:02", &utc), Some(3720.0));
+        assert_eq!(
+            parse("yy-MM-dd", "49-01-01", &utc),
+            parse("yyyy-MM-dd", "2049-01-01", &utc)
+        );
+        assert_eq!(
+            parse("yy-MM-dd", "50-01-01", &utc),
+            parse("yyyy-MM-dd", "1950-01-01", &utc)
+        );
+        assert_eq!(parse("h a", "12 AM", &utc), Some(0.0));
+        assert_eq!(parse("h a", "12 pm", &utc), Some(12.0 * 3600.0));
+
+        // Literals, including quoted ones, must match exactly.
+        let pattern = "'Date:' yyyy-MM-dd";
+        assert_eq!(parse(pattern, "Date: 1970-01-02", &utc), Some(86400.0));
+        assert_eq!(parse(pattern, "date: 1970-01-02", &utc), None);
+        assert_eq!(parse(pattern, "Date: 1970-01-02 ", &utc), None);
+        assert_eq!(parse(pattern, "Date: 1970/01/02", &utc), None);
+        assert_eq!(parse("yyyy-MM-dd", "2023-02-29", &utc), None);
+        assert_eq!(parse("yyyy-MM-dd", "2024-13-01", &utc), None);
+        assert_eq!(parse("HH:mm", "24:00", &utc), None);
+        assert_eq!(parse("h a", "13 PM", &utc), None);
+        assert_eq!(parse("yyyy", "", &utc), None);
+    }
+}
@@ -5,20 +5,24 @@
  */
 //! The `NSDictionary` class cluster, including `NSMutableDictionary`.
 
-use super::NSUInteger;
+use super::ns_exception::{raise, NSInvalidArgumentException};
+use super::ns_property_list_serialization::{read_plist_file, value_to_object, write_plist_file};
+use super::ns_string::from_rust_string;
+use super::{ns_keyed_archiver, ns_keyed_unarchiver, NSUInteger};
 
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class,
+    ClassExports, HostObject, NSZonePtr,
 };
 use crate::Environment;
+use plist::Value;
 use std::collections::HashMap;
 
 /// Alias for the return type of the `hash` method of the `NSObject` protocol.
 type Hash = NSUInteger;
 
 /// Belongs to _touchHLE_NSDictionary, also used by _touchHLE_NSSet
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(super) struct DictionaryHostObject {
     /// Since we need custom hashing and custom equality, and these both need a
     /// `&mut Environment`, we can't just use a `HashMap<id, id>`.
@@ -29,7 +33,11 @@ pub(super) struct DictionaryHostObject {
     map: HashMap<Hash, Vec<(id, id)>>,
     pub(super) count: NSUInteger,
 }
-impl HostObject for DictionaryHostObject {}
+impl HostObject for DictionaryHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 impl DictionaryHostObject {
     pub(super) fn lookup(&self, env: &mut Environment, key: id) -> id {
         let hash: Hash = msg![env; key hash];
@@ -107,6 +115,12 @@ pub const CLASSES: ClassExports = objc_classes! {
     autorelease(env, new_dict)
 }
 
++ (id)dictionaryWithContentsOfFile:(id)path { // NSString*
+    let new_dict: id = msg![env; this alloc];
+    let new_dict: id = msg![env; new_dict initWithContentsOfFile:path];
+    autorelease(env, new_dict)
+}
+
 + (id)dictionaryWithObjectsAndKeys:(id)first_object /*, ...*/ {
     // This passes on the va_args by creative abuse of untyped function calls.
     // I should be ashamed, and you should be careful.
@@ -126,6 +140,16 @@ pub const CLASSES: ClassExports = objc_classes! {
     retain(env, this)
 }
 
+- (Class)classForCoder {
+    // Our private subclass shouldn't appear in archives.
+    env.objc.get_known_class("NSDictionary", &mut env.mem)
+}
+
+- (bool)writeToFile:(id)path // NSString*
+         atomically:(bool)use_aux_file {
+    write_plist_file(env, this, path, use_aux_file)
+}
+
 // TODO
 
 @end
@@ -145,10 +169,55 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.dealloc_object(this, &mut env.mem)
 }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    // Dictionaries in keyed archives look like:
+    // {
+    //   "$class" => (uid of NSDictionary class goes here),
+    //   "NS.keys" => [
+    //     // keys here
+    //   ],
+    //   "NS.objects" => [
+    //     // objects here, in the same order as the keys
+    //   ]
+    // }
+    // See `[_touchHLE_NSArray initWithCoder:]` for why this takes a shortcut.
+    let keys = ns_keyed_unarchiver::decode_current_array(env, coder, "NS.keys");
+    let objects = ns_keyed_unarchiver::decode_current_array(env, coder, "NS.objects");
+    assert_eq!(keys.len(), objects.len());
+
+    let mut host_object = <DictionaryHostObject as Default>::default();
+    for (key, object) in keys.into_iter().zip(objects) {
+        host_object.insert(env, key, object, /* copy_key: */ true);
+        release(env, key);
+        release(env, object);
+    }
+    *env.objc.borrow_mut(this) = host_object;
+
+    this
+}
+- (())encodeWithCoder:(id)coder {
+    let host_object: DictionaryHostObject = std::mem::take(env.objc.borrow_mut(this));
+    let keys: Vec<id> = host_object.iter_keys().collect();
+    let objects: Vec<id> = keys.iter().map(|&key| host_object.lookup(env, key)).collect();
+    *env.objc.borrow_mut(this) = host_object;
+
+    ns_keyed_archiver::encode_current_array(env, coder, "NS.keys", &keys);
+    ns_keyed_archiver::encode_current_array(env, coder, "NS.objects", &objects);
+}
+
 - (id)initWithObjectsAndKeys:(id)first_object, ...dots {
     let mut va_args = dots.start();
     let first_key: id = va_args.next(env);
-    assert!(first_key != nil); // TODO: raise proper exception
+    if first_key == nil {
+        // The receiver is abandoned, since the method won't return.
+        release(env, this);
+        raise(
+            env,
+            NSInvalidArgumentException,
+            "*** -[NSDictionary initWithObjectsAndKeys:]: nil key".to_string(),
+        );
+    }
 
     let mut host_object = <DictionaryHostObject as Default>::default();
     host_object.insert(env, first_key, first_object, /* copy_key: */ true);
@@ -159,7 +228,15 @@ pub const CLASSES: ClassExports = objc_classes! {
             break;
         }
         let key: id = va_args.next(env);
-        assert!(key != nil); // TODO: raise proper exception
+        if key == nil {
+            host_object.release(env);
+            release(env, this);
+            raise(
+                env,
+                NSInvalidArgumentException,
+                "*** -[NSDictionary initWithObjectsAndKeys:]: nil key".to_string(),
+            );
+        }
         host_object.insert(env, key, object, /* copy_key: */ true);
     }
 
@@ -173,6 +250,28 @@ pub const CLASSES: ClassExports = objc_classes! {
     this
 }
 
+- (id)initWithContentsOfFile:(id)path { // NSString*
+    let Some(Value::Dictionary(dictionary)) = read_plist_file(env, path) else {
+        release(env, this);
+        return nil;
+    };
+
+    let mut host_object = <DictionaryHostObject as Default>::default();
+    for (key, value) in dictionary {
+        let object = value_to_object(env, &value);
+        if object == nil {
+            continue;
+        }
+        let key = from_rust_string(env, key);
+        host_object.insert(env, key, object, /* copy_key: */ true);
+        release(env, key);
+        release(env, object);
+    }
+    *env.objc.borrow_mut(this) = host_object;
+
+    this
+}
+
 // TODO: enumeration, more init methods, etc
 
 - (NSUInteger)count {
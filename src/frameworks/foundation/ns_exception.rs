@@ -0,0 +1,221 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSException`.
+//!
+//! The runtime side of exceptions (`@throw` etc) is implemented in the
+//! `crate::objc` module, see [crate::objc::throw].
+
+use super::ns_string::{from_rust_string, get_static_str, to_rust_string, with_format};
+use crate::abi::{CallFromHost, GuestFunction};
+use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
+use crate::mem::{ConstVoidPtr, Ptr};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, throw, AnyHostObject,
+    ClassExports, HostObject, NSZonePtr,
+};
+use crate::Environment;
+
+/// `NSString*`
+pub type NSExceptionName = id;
+
+pub const NSGenericException: &str = "NSGenericException";
+pub const NSRangeException: &str = "NSRangeException";
+pub const NSInvalidArgumentException: &str = "NSInvalidArgumentException";
+pub const NSInternalInconsistencyException: &str = "NSInternalInconsistencyException";
+
+pub const CONSTANTS: ConstantExports = &[
+    (
+        "_NSGenericException",
+        HostConstant::NSString(NSGenericException),
+    ),
+    (
+        "_NSRangeException",
+        HostConstant::NSString(NSRangeException),
+    ),
+    (
+        "_NSInvalidArgumentException",
+        HostConstant::NSString(NSInvalidArgumentException),
+    ),
+    (
+        "_NSInternalInconsistencyException",
+        HostConstant::NSString(NSInternalInconsistencyException),
+    ),
+];
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Set by `NSSetUncaughtExceptionHandler`.
+    uncaught_exception_handler: Option<GuestFunction>,
+}
+
+#[derive(Clone)]
+struct NSExceptionHostObject {
+    name: id,
+    reason: id,
+    user_info: id,
+}
+impl HostObject for NSExceptionHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSException: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSExceptionHostObject {
+        name: nil,
+        reason: nil,
+        user_info: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)exceptionWithName:(NSExceptionName)name
+                 reason:(id)reason // NSString*
+               userInfo:(id)user_info { // NSDictionary*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithName:name reason:reason userInfo:user_info];
+    autorelease(env, new)
+}
+
++ (())raise:(NSExceptionName)name
+     format:(id)format, // NSString*
+            ...args {
+    let reason = with_format(env, format, args.start());
+    let reason = autorelease(env, reason);
+    let exception: id = msg![env; this exceptionWithName:name reason:reason userInfo:nil];
+    throw(env, exception)
+}
+
+- (id)initWithName:(NSExceptionName)name
+            reason:(id)reason // NSString*
+          userInfo:(id)user_info { // NSDictionary*
+    let name: id = msg![env; name copy];
+    let reason: id = msg![env; reason copy];
+    retain(env, user_info);
+    *env.objc.borrow_mut(this) = NSExceptionHostObject { name, reason, user_info };
+    this
+}
+
+- (())dealloc {
+    let &NSExceptionHostObject { name, reason, user_info } = env.objc.borrow(this);
+    release(env, name);
+    release(env, reason);
+    release(env, user_info);
+    env.objc.dealloc_object(this, &mut env.mem);
+}
+
+- (id)name {
+    env.objc.borrow::<NSExceptionHostObject>(this).name
+}
+- (id)reason {
+    env.objc.borrow::<NSExceptionHostObject>(this).reason
+}
+- (id)userInfo {
+    env.objc.borrow::<NSExceptionHostObject>(this).user_info
+}
+
+- (id)description {
+    env.objc.borrow::<NSExceptionHostObject>(this).reason
+}
+
+- (())raise {
+    throw(env, this)
+}
+
+@end
+
+};
+
+/// Shortcut for host code: throw an `NSException` with the given name and
+/// reason, e.g. when a method is used incorrectly. Keep in mind that the
+/// calling host function won't return (see [throw]).
+pub fn raise(env: &mut Environment, name: &'static str, reason: String) -> ! {
+    log_dbg!("Raising {}: {}", name, reason);
+    let name = get_static_str(env, name);
+    let reason = from_rust_string(env, reason);
+    let reason = autorelease(env, reason);
+    let exception: id = msg_class![env; NSException exceptionWithName:name
+                                                               reason:reason
+                                                             userInfo:nil];
+    throw(env, exception)
+}
+
+/// For use by [throw]: there's no handler for the exception, so call the
+/// app's uncaught exception handler, if any, then print what happened and
+/// exit.
+pub fn handle_uncaught_exception(env: &mut Environment, exception: id) -> ! {
+    if let Some(handler) = env
+        .framework_state
+        .foundation
+        .ns_exception
+        .uncaught_exception_handler
+    {
+        log_dbg!("Calling uncaught exception handler {:?}", handler);
+        let () = handler.call_from_host(env, (exception,));
+    }
+
+    // The exception might not be an NSException, though it almost always is.
+    let name: id = msg![env; exception name];
+    let reason: id = msg![env; exception reason];
+    let name = if name == nil {
+        "(no name)".into()
+    } else {
+        to_rust_string(env, name)
+    };
+    let reason = if reason == nil {
+        "(no reason)".into()
+    } else {
+        to_rust_string(env, reason)
+    };
+    echo!(
+        "Uncaught Objective-C exception on thread {}: {}: {}",
+        env.current_thread,
+        name,
+        reason
+    );
+    env.cpu.dump_regs();
+    env.stack_trace();
+    echo!("The app has been terminated due to the uncaught exception.");
+    std::process::exit(1);
+}
+
+/// `void (*)(NSException *)`. This is a plain pointer rather than a
+/// [GuestFunction] so that it can be null.
+type NSUncaughtExceptionHandler = ConstVoidPtr;
+
+fn NSGetUncaughtExceptionHandler(env: &mut Environment) -> NSUncaughtExceptionHandler {
+    let handler = env
+        .framework_state
+        .foundation
+        .ns_exception
+        .uncaught_exception_handler;
+    handler.map_or(Ptr::null(), |handler| {
+        Ptr::from_bits(handler.addr_with_thumb_bit())
+    })
+}
+
+fn NSSetUncaughtExceptionHandler(env: &mut Environment, handler: NSUncaughtExceptionHandler) {
+    let handler = if handler.is_null() {
+        None
+    } else {
+        Some(GuestFunction::from_addr_with_thumb_bit(handler.to_bits()))
+    };
+    env.framework_state
+        .foundation
+        .ns_exception
+        .uncaught_exception_handler = handler;
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(NSGetUncaughtExceptionHandler()),
+    export_c_func!(NSSetUncaughtExceptionHandler(_)),
+];
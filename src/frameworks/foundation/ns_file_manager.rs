@@ -9,12 +9,15 @@ use super::{ns_array, ns_string, NSUInteger};
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::fs::{GuestPath, GuestPathBuf};
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, AnyHostObject, ClassExports,
+    HostObject,
 };
 use crate::Environment;
 
 type NSSearchPathDirectory = NSUInteger;
+const NSLibraryDirectory: NSSearchPathDirectory = 5;
 const NSDocumentDirectory: NSSearchPathDirectory = 9;
+const NSCachesDirectory: NSSearchPathDirectory = 13;
 
 type NSSearchPathDomainMask = NSUInteger;
 const NSUserDomainMask: NSSearchPathDomainMask = 1;
@@ -26,11 +29,19 @@ fn NSSearchPathForDirectoriesInDomains(
     expand_tilde: bool,
 ) -> id {
     // TODO: other cases not implemented
-    assert!(directory == NSDocumentDirectory);
     assert!(domain_mask == NSUserDomainMask);
     assert!(expand_tilde);
 
-    let dir = env.fs.home_directory().join("Documents");
+    let dir = match directory {
+        NSLibraryDirectory => "Library",
+        NSDocumentDirectory => "Documents",
+        NSCachesDirectory => "Library/Caches",
+        _ => unimplemented!(
+            "NSSearchPathForDirectoriesInDomains() for directory {}",
+            directory
+        ),
+    };
+    let dir = env.fs.home_directory().join(dir);
     let dir = ns_string::from_rust_string(env, String::from(dir));
     let dir_list = ns_array::from_vec(env, vec![dir]);
     autorelease(env, dir_list)
@@ -42,20 +53,34 @@ fn NSHomeDirectory(env: &mut Environment) -> id {
     autorelease(env, dir)
 }
 
+fn NSTemporaryDirectory(env: &mut Environment) -> id {
+    // The real path has a trailing slash, and some apps rely on that when
+    // appending a file name.
+    let dir = format!("{}/", env.fs.home_directory().join("tmp").as_str());
+    let dir = ns_string::from_rust_string(env, dir);
+    autorelease(env, dir)
+}
+
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(NSHomeDirectory()),
     export_c_func!(NSSearchPathForDirectoriesInDomains(_, _, _)),
+    export_c_func!(NSTemporaryDirectory()),
 ];
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     default_manager: Option<id>,
 }
 
+#[derive(Clone)]
 struct NSDirectoryEnumeratorHostObject {
     iterator: std::vec::IntoIter<GuestPathBuf>,
 }
-impl HostObject for NSDirectoryEnumeratorHostObject {}
+impl HostObject for NSDirectoryEnumeratorHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -0,0 +1,147 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSIndexPath`, including the UIKit additions for table views.
+
+use super::{NSComparisonResult, NSOrderedAscending, NSOrderedDescending, NSOrderedSame};
+use super::{NSInteger, NSUInteger};
+use crate::mem::ConstPtr;
+use crate::objc::{
+    autorelease, id, msg, msg_class, objc_classes, retain, AnyHostObject, Class, ClassExports,
+    HostObject, NSZonePtr,
+};
+use crate::Environment;
+
+#[derive(Clone)]
+struct NSIndexPathHostObject {
+    indexes: Vec<NSUInteger>,
+}
+impl HostObject for NSIndexPathHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Shortcut for host code: create a new `NSIndexPath` for a table view row
+/// (not autoreleased).
+pub fn from_section_and_row(env: &mut Environment, section: NSUInteger, row: NSUInteger) -> id {
+    let new: id = msg_class![env; NSIndexPath alloc];
+    env.objc.borrow_mut::<NSIndexPathHostObject>(new).indexes = vec![section, row];
+    new
+}
+
+/// Shortcut for host code: get the section and row of a table view index path.
+pub fn to_section_and_row(env: &mut Environment, index_path: id) -> (NSUInteger, NSUInteger) {
+    let indexes = &env.objc.borrow::<NSIndexPathHostObject>(index_path).indexes;
+    (indexes[0], indexes[1])
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSIndexPath: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSIndexPathHostObject {
+        indexes: Vec::new(),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)indexPathWithIndex:(NSUInteger)index {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithIndex:index];
+    autorelease(env, new)
+}
++ (id)indexPathWithIndexes:(ConstPtr<NSUInteger>)indexes
+                    length:(NSUInteger)length {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithIndexes:indexes length:length];
+    autorelease(env, new)
+}
+
+// UIKit addition
++ (id)indexPathForRow:(NSUInteger)row
+            inSection:(NSUInteger)section {
+    let new = from_section_and_row(env, section, row);
+    autorelease(env, new)
+}
+
+- (id)initWithIndex:(NSUInteger)index {
+    env.objc.borrow_mut::<NSIndexPathHostObject>(this).indexes = vec![index];
+    this
+}
+- (id)initWithIndexes:(ConstPtr<NSUInteger>)indexes
+               length:(NSUInteger)length {
+    let indexes = (0..length).map(|i| env.mem.read(indexes + i)).collect();
+    env.objc.borrow_mut::<NSIndexPathHostObject>(this).indexes = indexes;
+    this
+}
+
+- (id)indexPathByAddingIndex:(NSUInteger)index {
+    let mut indexes = env.objc.borrow::<NSIndexPathHostObject>(this).indexes.clone();
+    indexes.push(index);
+    let class: Class = msg![env; this class];
+    let new: id = msg![env; class alloc];
+    env.objc.borrow_mut::<NSIndexPathHostObject>(new).indexes = indexes;
+    autorelease(env, new)
+}
+
+- (NSUInteger)length {
+    let indexes = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    indexes.len().try_into().unwrap()
+}
+- (NSUInteger)indexAtPosition:(NSUInteger)position {
+    let indexes = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    // Apple's implementation returns NSNotFound for out-of-range positions.
+    indexes
+        .get(position as usize)
+        .copied()
+        .unwrap_or(NSInteger::MAX as NSUInteger)
+}
+
+// UIKit additions
+- (NSUInteger)section {
+    msg![env; this indexAtPosition:0u32]
+}
+- (NSUInteger)row {
+    msg![env; this indexAtPosition:1u32]
+}
+
+- (NSComparisonResult)compare:(id)other { // NSIndexPath*
+    let a = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    let b = &env.objc.borrow::<NSIndexPathHostObject>(other).indexes;
+    match a.cmp(b) {
+        std::cmp::Ordering::Less => NSOrderedAscending,
+        std::cmp::Ordering::Equal => NSOrderedSame,
+        std::cmp::Ordering::Greater => NSOrderedDescending,
+    }
+}
+
+- (NSUInteger)hash {
+    super::hash_helper(&env.objc.borrow::<NSIndexPathHostObject>(this).indexes)
+}
+- (bool)isEqualTo:(id)other {
+    if this == other {
+        return true;
+    }
+    let class: Class = msg_class![env; NSIndexPath class];
+    if !msg![env; other isKindOfClass:class] {
+        return false;
+    }
+    let a = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    let b = &env.objc.borrow::<NSIndexPathHostObject>(other).indexes;
+    a == b
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    retain(env, this)
+}
+
+@end
+
+};
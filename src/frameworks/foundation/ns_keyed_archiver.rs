@@ -0,0 +1,447 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSKeyedArchiver` and serialization of object graphs to its format.
+//!
+//! The output is meant to be identical in structure to what Apple's
+//! implementation produces, so that archives can be exchanged with real
+//! devices. It looks like this:
+//!
+//! ```text
+//! {
+//!   "$version" => 100000,
+//!   "$archiver" => "NSKeyedArchiver",
+//!   "$top" => {
+//!     "root" => (uid of the root object),
+//!   },
+//!   "$objects" => [
+//!     "$null", // uid 0 always means nil
+//!     // Strings, numbers and data are stored directly:
+//!     "some string",
+//!     // Other objects are dictionaries of the keys they encoded:
+//!     {
+//!       "$class" => (uid of a class description),
+//!       "NS.objects" => [(uid), (uid), ...],
+//!     },
+//!     // Class descriptions:
+//!     {
+//!       "$classes" => ["NSArray", "NSObject"],
+//!       "$classname" => "NSArray",
+//!     },
+//!   ],
+//! }
+//! ```
+//!
+//! See [super::ns_keyed_unarchiver] for the other direction.
+//!
+//! Resources:
+//! - Apple's [Archives and Serializations Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/Archiving/Articles/archives.html)
+
+use super::ns_exception::raise;
+use super::ns_property_list_serialization::{
+    is_kind_of, object_to_value, NSPropertyListBinaryFormat_v1_0, NSPropertyListFormat,
+    NSPropertyListXMLFormat_v1_0,
+};
+use super::ns_string::{from_rust_string, get_static_str, to_rust_string};
+use super::{ns_data, NSInteger, NSUInteger};
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::mem::ConstPtr;
+use crate::objc::{
+    autorelease, class_getName_inner, id, msg, msg_class, nil, objc_classes, release, retain,
+    AnyHostObject, Class, ClassExports, HostObject, NSZonePtr,
+};
+use crate::Environment;
+use plist::{Dictionary, Uid, Value};
+use std::collections::HashMap;
+
+pub const NSInvalidArchiveOperationException: &str = "NSInvalidArchiveOperationException";
+
+pub const CONSTANTS: ConstantExports = &[(
+    "_NSInvalidArchiveOperationException",
+    HostConstant::NSString(NSInvalidArchiveOperationException),
+)];
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Class name substitutions from `+[NSKeyedArchiver setClassName:forClass:]`.
+    class_names: HashMap<Class, String>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut State {
+        &mut env.framework_state.foundation.ns_keyed_archiver
+    }
+}
+
+#[derive(Clone)]
+struct NSKeyedArchiverHostObject {
+    /// `NSMutableData*` that the archive is appended to by `finishEncoding`.
+    data: id,
+    output_format: NSPropertyListFormat,
+    /// Keys encoded at the top level, i.e. not by an object's
+    /// `encodeWithCoder:`.
+    top: Dictionary,
+    /// The archive's items so far, indexed by uid.
+    objects: Vec<Value>,
+    /// Objects that have already been encoded, so that they are only encoded
+    /// once no matter how many times (or how cyclically) they are referenced.
+    /// They are retained so their addresses can't be reused in the meantime.
+    object_uids: HashMap<id, Uid>,
+    /// Class descriptions that have already been encoded.
+    class_uids: HashMap<Class, Uid>,
+    /// The object currently being encoded, if any.
+    current_uid: Option<Uid>,
+    /// Class name substitutions from `-setClassName:forClass:`.
+    class_names: HashMap<Class, String>,
+    finished: bool,
+}
+impl HostObject for NSKeyedArchiverHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSKeyedArchiver: NSCoder
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let archiver = Box::new(NSKeyedArchiverHostObject {
+        data: nil,
+        output_format: NSPropertyListBinaryFormat_v1_0,
+        top: Dictionary::new(),
+        objects: vec![Value::String("$null".to_string())],
+        object_uids: HashMap::new(),
+        class_uids: HashMap::new(),
+        current_uid: None,
+        class_names: HashMap::new(),
+        finished: false,
+    });
+    env.objc.alloc_object(this, archiver, &mut env.mem)
+}
+
++ (id)archivedDataWithRootObject:(id)root_object {
+    let data: id = msg_class![env; NSMutableData data];
+    let archiver: id = msg![env; this alloc];
+    let archiver: id = msg![env; archiver initForWritingWithMutableData:data];
+    let key = get_static_str(env, "root");
+    let _: () = msg![env; archiver encodeObject:root_object forKey:key];
+    let _: () = msg![env; archiver finishEncoding];
+    release(env, archiver);
+    data
+}
+
++ (bool)archiveRootObject:(id)root_object
+                   toFile:(id)path { // NSString*
+    let data: id = msg![env; this archivedDataWithRootObject:root_object];
+    msg![env; data writeToFile:path atomically:true]
+}
+
++ (())setClassName:(id)name // NSString*
+          forClass:(Class)class {
+    if name == nil {
+        State::get(env).class_names.remove(&class);
+    } else {
+        let name = to_rust_string(env, name).into_owned();
+        State::get(env).class_names.insert(class, name);
+    }
+}
++ (id)classNameForClass:(Class)class {
+    let Some(name) = State::get(env).class_names.get(&class).cloned() else {
+        return nil;
+    };
+    let name = from_rust_string(env, name);
+    autorelease(env, name)
+}
+
+- (id)initForWritingWithMutableData:(id)data { // NSMutableData*
+    retain(env, data);
+    borrow_host_obj(env, this).data = data;
+    this
+}
+
+- (())dealloc {
+    let host_obj = borrow_host_obj(env, this);
+    let data = std::mem::replace(&mut host_obj.data, nil);
+    let object_uids = std::mem::take(&mut host_obj.object_uids);
+
+    release(env, data);
+    for &object in object_uids.keys() {
+        release(env, object);
+    }
+
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (NSPropertyListFormat)outputFormat {
+    borrow_host_obj(env, this).output_format
+}
+- (())setOutputFormat:(NSPropertyListFormat)format {
+    assert!(
+        format == NSPropertyListXMLFormat_v1_0 || format == NSPropertyListBinaryFormat_v1_0,
+        "Unsupported output format: {}",
+        format
+    );
+    borrow_host_obj(env, this).output_format = format;
+}
+
+- (())setClassName:(id)name // NSString*
+          forClass:(Class)class {
+    if name == nil {
+        borrow_host_obj(env, this).class_names.remove(&class);
+    } else {
+        let name = to_rust_string(env, name).into_owned();
+        borrow_host_obj(env, this).class_names.insert(class, name);
+    }
+}
+- (id)classNameForClass:(Class)class {
+    let Some(name) = borrow_host_obj(env, this).class_names.get(&class).cloned() else {
+        return nil;
+    };
+    let name = from_rust_string(env, name);
+    autorelease(env, name)
+}
+
+- (bool)allowsKeyedCoding {
+    true
+}
+
+// These methods are from the NSCoder abstract class. They get called by the
+// code that initiates the archival to encode top-level objects, and by objects
+// being archived (from `encodeWithCoder:`) to encode their contents.
+
+- (())encodeObject:(id)object
+            forKey:(id)key { // NSString*
+    let uid = encode_object(env, this, object);
+    encode_value(env, this, key, Value::Uid(uid));
+}
+- (())encodeConditionalObject:(id)object
+                       forKey:(id)key { // NSString*
+    // TODO: Only encode the object if something else encodes it
+    // unconditionally. Encoding it anyway is wasteful but still correct.
+    let uid = encode_object(env, this, object);
+    encode_value(env, this, key, Value::Uid(uid));
+}
+
+- (())encodeBool:(bool)value
+          forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Boolean(value));
+}
+- (())encodeInt:(i32)value
+         forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer((value as i64).into()));
+}
+- (())encodeInteger:(NSInteger)value
+             forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer((value as i64).into()));
+}
+- (())encodeInt32:(i32)value
+           forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer((value as i64).into()));
+}
+- (())encodeInt64:(i64)value
+           forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer(value.into()));
+}
+- (())encodeFloat:(f32)value
+           forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Real(value as f64));
+}
+- (())encodeDouble:(f64)value
+            forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Real(value));
+}
+- (())encodeBytes:(ConstPtr<u8>)bytes
+           length:(NSUInteger)length
+           forKey:(id)key { // NSString*
+    let bytes = if length == 0 {
+        Vec::new()
+    } else {
+        env.mem.bytes_at(bytes, length).to_vec()
+    };
+    encode_value(env, this, key, Value::Data(bytes));
+}
+
+- (())finishEncoding {
+    let host_obj = borrow_host_obj(env, this);
+    if host_obj.finished {
+        return;
+    }
+    host_obj.finished = true;
+
+    let mut plist = Dictionary::new();
+    plist.insert("$version".to_string(), Value::Integer(100000u64.into()));
+    plist.insert("$archiver".to_string(), Value::String("NSKeyedArchiver".to_string()));
+    plist.insert("$top".to_string(), Value::Dictionary(std::mem::take(&mut host_obj.top)));
+    plist.insert("$objects".to_string(), Value::Array(std::mem::take(&mut host_obj.objects)));
+
+    let mut bytes = Vec::new();
+    let plist = Value::Dictionary(plist);
+    if host_obj.output_format == NSPropertyListXMLFormat_v1_0 {
+        plist.to_writer_xml(&mut bytes).unwrap();
+    } else {
+        plist.to_writer_binary(&mut bytes).unwrap();
+    }
+
+    let data = host_obj.data;
+    if data != nil {
+        let new_data = ns_data::from_slice(env, &bytes);
+        let _: () = msg![env; data appendData:new_data];
+        release(env, new_data);
+    }
+}
+
+@end
+
+};
+
+fn borrow_host_obj(env: &mut Environment, archiver: id) -> &mut NSKeyedArchiverHostObject {
+    env.objc.borrow_mut(archiver)
+}
+
+/// Keys beginning with `$` are reserved for the archive's own structure, so
+/// keys chosen by objects that begin with `$` get another `$` prepended.
+pub(super) fn escape_key(key: &str) -> String {
+    if key.starts_with('$') {
+        format!("${}", key)
+    } else {
+        key.to_string()
+    }
+}
+
+/// Add a key to the dictionary keys are currently being encoded into: the top
+/// level, or the object that is being archived.
+fn insert_into_current_scope(env: &mut Environment, archiver: id, key: String, value: Value) {
+    if borrow_host_obj(env, archiver).finished {
+        raise(
+            env,
+            NSInvalidArchiveOperationException,
+            format!(
+                "*** -[NSKeyedArchiver encode...]: archive already finished, cannot encode anything more (key: {})",
+                key
+            ),
+        );
+    }
+    let host_obj = borrow_host_obj(env, archiver);
+    let scope = match host_obj.current_uid {
+        Some(current_uid) => host_obj.objects[current_uid.get() as usize]
+            .as_dictionary_mut()
+            .unwrap(),
+        None => &mut host_obj.top,
+    };
+    scope.insert(key, value);
+}
+
+fn encode_value(env: &mut Environment, archiver: id, key: id, value: Value) {
+    let key = escape_key(&to_rust_string(env, key));
+    insert_into_current_scope(env, archiver, key, value);
+}
+
+/// The core of the implementation: add an object to the archive, if it isn't
+/// there already, and return its uid.
+///
+/// This is recursive in practice: the `encodeWithCoder:` message sent by this
+/// function will be received by an object which will then send
+/// `encodeXXX:forKey:` messages back to the archiver, which may then call this
+/// function (and so on).
+fn encode_object(env: &mut Environment, archiver: id, object: id) -> Uid {
+    if object == nil {
+        return Uid::new(0);
+    }
+    if let Some(&existing) = borrow_host_obj(env, archiver).object_uids.get(&object) {
+        return existing;
+    }
+
+    retain(env, object);
+
+    // Strings, numbers and data are the only objects stored directly.
+    let inline_value = if is_kind_of(env, object, "NSString")
+        || is_kind_of(env, object, "NSNumber")
+        || is_kind_of(env, object, "NSData")
+    {
+        object_to_value(env, object)
+    } else {
+        None
+    };
+    if let Some(value) = inline_value {
+        let host_obj = borrow_host_obj(env, archiver);
+        let uid = Uid::new(host_obj.objects.len() as u64);
+        host_obj.objects.push(value);
+        host_obj.object_uids.insert(object, uid);
+        return uid;
+    }
+
+    let class: Class = msg![env; object classForKeyedArchiver];
+    let class_uid = encode_class(env, archiver, class);
+
+    let mut dict = Dictionary::new();
+    dict.insert("$class".to_string(), Value::Uid(class_uid));
+
+    let host_obj = borrow_host_obj(env, archiver);
+    let uid = Uid::new(host_obj.objects.len() as u64);
+    host_obj.objects.push(Value::Dictionary(dict));
+    // This must be done before encoding the object's contents, in case they
+    // refer back to the object.
+    host_obj.object_uids.insert(object, uid);
+    let old_current_uid = host_obj.current_uid;
+    host_obj.current_uid = Some(uid);
+
+    let _: () = msg![env; object encodeWithCoder:archiver];
+
+    borrow_host_obj(env, archiver).current_uid = old_current_uid;
+
+    uid
+}
+
+/// Add the description of a class to the archive, if it isn't there already,
+/// and return its uid.
+fn encode_class(env: &mut Environment, archiver: id, class: Class) -> Uid {
+    if let Some(&existing) = borrow_host_obj(env, archiver).class_uids.get(&class) {
+        return existing;
+    }
+
+    let mut class_names = Vec::new();
+    let mut next = class;
+    while next != nil {
+        class_names.push(Value::String(class_name_for_class(env, archiver, next)));
+        next = env.objc.get_superclass(next);
+    }
+
+    let mut dict = Dictionary::new();
+    dict.insert("$classname".to_string(), class_names[0].clone());
+    dict.insert("$classes".to_string(), Value::Array(class_names));
+
+    let host_obj = borrow_host_obj(env, archiver);
+    let uid = Uid::new(host_obj.objects.len() as u64);
+    host_obj.objects.push(Value::Dictionary(dict));
+    host_obj.class_uids.insert(class, uid);
+    uid
+}
+
+/// The name a class is stored under, which can be changed with
+/// `setClassName:forClass:` on either the archiver or the archiver class.
+fn class_name_for_class(env: &mut Environment, archiver: id, class: Class) -> String {
+    if let Some(name) = borrow_host_obj(env, archiver).class_names.get(&class) {
+        return name.clone();
+    }
+    if let Some(name) = State::get(env).class_names.get(&class) {
+        return name.clone();
+    }
+    class_getName_inner(&mut env.objc, class).to_string()
+}
+
+/// Shortcut for use by the `encodeWithCoder:` implementations of collection
+/// classes, the counterpart of
+/// [super::ns_keyed_unarchiver::decode_current_array]. The array is stored
+/// directly in the object's dictionary, rather than as an `NSArray` object.
+pub fn encode_current_array(env: &mut Environment, archiver: id, key: &str, objects: &[id]) {
+    // FIXME: What if it's not an NSKeyedArchiver?
+    let uids = objects
+        .iter()
+        .map(|&object| Value::Uid(encode_object(env, archiver, object)))
+        .collect();
+    insert_into_current_scope(env, archiver, key.to_string(), Value::Array(uids));
+}
@@ -11,21 +11,59 @@
 //!   plists, e.g. `plutil -p` or `println!("{:#?}", plist::Value::...);`.
 //! - Apple's [Archives and Serializations Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/Archiving/Articles/archives.html)
 
-use super::ns_string::{from_rust_string, to_rust_string};
+use super::ns_data;
+use super::ns_exception::{raise, NSInvalidArgumentException};
+use super::ns_keyed_archiver::escape_key;
+use super::ns_property_list_serialization::value_to_object;
+use super::ns_string::{from_rust_string, get_static_str, to_rust_string};
+use super::{NSInteger, NSUInteger};
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::fs::GuestPath;
+use crate::mem::{ConstPtr, MutPtr, Ptr};
 use crate::objc::{
-    autorelease, id, msg, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, nil, objc_classes, release, retain, AnyHostObject, Class, ClassExports,
+    HostObject, NSZonePtr,
 };
 use crate::Environment;
 use plist::{Dictionary, Uid, Value};
+use std::collections::HashMap;
 use std::io::Cursor;
 
+pub const NSInvalidUnarchiveOperationException: &str = "NSInvalidUnarchiveOperationException";
+
+pub const CONSTANTS: ConstantExports = &[(
+    "_NSInvalidUnarchiveOperationException",
+    HostConstant::NSString(NSInvalidUnarchiveOperationException),
+)];
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Class substitutions from `+[NSKeyedUnarchiver setClass:forClassName:]`.
+    classes: HashMap<String, Class>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut State {
+        &mut env.framework_state.foundation.ns_keyed_unarchiver
+    }
+}
+
+#[derive(Clone)]
 struct NSKeyedUnarchiverHostObject {
     plist: Dictionary,
     current_key: Option<Uid>,
     /// linear map of Uid => id
     already_unarchived: Vec<Option<id>>,
+    /// Class substitutions from `-setClass:forClassName:`.
+    classes: HashMap<String, Class>,
+    /// Buffers returned by `decodeBytesForKey:returnedLength:`, which must
+    /// live as long as the unarchiver.
+    decoded_bytes: Vec<MutPtr<u8>>,
+}
+impl HostObject for NSKeyedUnarchiverHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
 }
-impl HostObject for NSKeyedUnarchiverHostObject {}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -38,24 +76,110 @@ pub const CLASSES: ClassExports = objc_classes! {
         plist: Dictionary::new(),
         current_key: None,
         already_unarchived: Vec::new(),
+        classes: HashMap::new(),
+        decoded_bytes: Vec::new(),
     });
     env.objc.alloc_object(this, unarchiver, &mut env.mem)
 }
 
-// TODO: real init methods. This is currently only initialized by the shortcut
-// function below.
++ (id)unarchiveObjectWithData:(id)data { // NSData*
+    let unarchiver: id = msg![env; this alloc];
+    let unarchiver: id = msg![env; unarchiver initForReadingWithData:data];
+    let key = get_static_str(env, "root");
+    // This is retained and autoreleased on our behalf, so it will outlive the
+    // unarchiver.
+    let object: id = msg![env; unarchiver decodeObjectForKey:key];
+    let _: () = msg![env; unarchiver finishDecoding];
+    release(env, unarchiver);
+    object
+}
+
++ (id)unarchiveObjectWithFile:(id)path { // NSString*
+    let path = to_rust_string(env, path);
+    log_dbg!("[NSKeyedUnarchiver unarchiveObjectWithFile:{:?}]", path);
+    let Ok(bytes) = env.fs.read(GuestPath::new(&path)) else {
+        return nil;
+    };
+    let data = ns_data::from_slice(env, &bytes);
+    let object: id = msg![env; this unarchiveObjectWithData:data];
+    release(env, data);
+    object
+}
+
++ (())setClass:(Class)class
+  forClassName:(id)name { // NSString*
+    let name = to_rust_string(env, name).into_owned();
+    if class == nil {
+        State::get(env).classes.remove(&name);
+    } else {
+        State::get(env).classes.insert(name, class);
+    }
+}
++ (Class)classForClassName:(id)name { // NSString*
+    let name = to_rust_string(env, name);
+    State::get(env).classes.get(&*name).copied().unwrap_or(nil)
+}
+
+- (id)initForReadingWithData:(id)data { // NSData*
+    let bytes = ns_data::to_vec(env, data);
+    match parse_archive(&bytes) {
+        Ok(plist) => {
+            init_with_plist(env, this, plist);
+            this
+        }
+        Err(e) => {
+            release(env, this);
+            raise(
+                env,
+                NSInvalidArgumentException,
+                format!(
+                    "*** -[NSKeyedUnarchiver initForReadingWithData:]: incomprehensible archive ({})",
+                    e
+                ),
+            );
+        }
+    }
+}
 
 - (())dealloc {
     let host_obj = borrow_host_obj(env, this);
     let already_unarchived = std::mem::take(&mut host_obj.already_unarchived);
+    let decoded_bytes = std::mem::take(&mut host_obj.decoded_bytes);
 
     for &object in already_unarchived.iter().flatten() {
         release(env, object);
     }
+    for bytes in decoded_bytes {
+        env.mem.free(bytes.cast());
+    }
 
     env.objc.dealloc_object(this, &mut env.mem)
 }
 
+- (())finishDecoding {
+    // Nothing to do.
+}
+
+- (())setClass:(Class)class
+  forClassName:(id)name { // NSString*
+    let name = to_rust_string(env, name).into_owned();
+    let host_obj = borrow_host_obj(env, this);
+    if class == nil {
+        host_obj.classes.remove(&name);
+    } else {
+        host_obj.classes.insert(name, class);
+    }
+}
+- (Class)classForClassName:(id)name { // NSString*
+    let name = to_rust_string(env, name);
+    let host_obj = borrow_host_obj(env, this);
+    host_obj.classes.get(&*name).copied().unwrap_or(nil)
+}
+
+- (bool)allowsKeyedCoding {
+    true
+}
+
 // These methods drive most of the decoding. They get called in two cases:
 // - By the code that initiates the unarchival, e.g. UINib, to retrieve
 //   top-level objects.
@@ -64,18 +188,14 @@ pub const CLASSES: ClassExports = objc_classes! {
 // They are all from the NSCoder abstract class and they return default values
 // if the key is unknown.
 
+- (bool)containsValueForKey:(id)key { // NSString*
+    lookup_key(env, this, key).is_some()
+}
+
 - (id)decodeObjectForKey:(id)key { // NSString*
-    let key = to_rust_string(env, key); // TODO: avoid copying string
-    let host_obj = borrow_host_obj(env, this);
-    let scope = match host_obj.current_key {
-        Some(current_uid) => {
-            &host_obj.plist["$objects"].as_array().unwrap()[current_uid.get() as usize]
-        },
-        None => {
-            &host_obj.plist["$top"]
-        }
-    }.as_dictionary().unwrap();
-    let next_uid = scope[&key].as_uid().copied().unwrap();
+    let Some(Value::Uid(next_uid)) = lookup_key(env, this, key) else {
+        return nil;
+    };
     let object = unarchive_key(env, this, next_uid);
 
     // on behalf of the caller
@@ -83,7 +203,50 @@ pub const CLASSES: ClassExports = objc_classes! {
     autorelease(env, object)
 }
 
-// TODO: add more decode methods
+- (bool)decodeBoolForKey:(id)key { // NSString*
+    match lookup_key(env, this, key) {
+        Some(Value::Boolean(value)) => value,
+        Some(value) => value_to_i64(&value) != 0,
+        None => false,
+    }
+}
+- (i32)decodeIntForKey:(id)key { // NSString*
+    lookup_key(env, this, key).map_or(0, |value| value_to_i64(&value) as i32)
+}
+- (NSInteger)decodeIntegerForKey:(id)key { // NSString*
+    lookup_key(env, this, key).map_or(0, |value| value_to_i64(&value) as NSInteger)
+}
+- (i32)decodeInt32ForKey:(id)key { // NSString*
+    lookup_key(env, this, key).map_or(0, |value| value_to_i64(&value) as i32)
+}
+- (i64)decodeInt64ForKey:(id)key { // NSString*
+    lookup_key(env, this, key).map_or(0, |value| value_to_i64(&value))
+}
+- (f32)decodeFloatForKey:(id)key { // NSString*
+    lookup_key(env, this, key).map_or(0.0, |value| value_to_f64(&value) as f32)
+}
+- (f64)decodeDoubleForKey:(id)key { // NSString*
+    lookup_key(env, this, key).map_or(0.0, |value| value_to_f64(&value))
+}
+
+- (ConstPtr<u8>)decodeBytesForKey:(id)key // NSString*
+                   returnedLength:(MutPtr<NSUInteger>)length_ptr {
+    let Some(Value::Data(bytes)) = lookup_key(env, this, key) else {
+        if !length_ptr.is_null() {
+            env.mem.write(length_ptr, 0);
+        }
+        return Ptr::null();
+    };
+    let length: NSUInteger = bytes.len().try_into().unwrap();
+    if !length_ptr.is_null() {
+        env.mem.write(length_ptr, length);
+    }
+    // The allocator can't make empty allocations.
+    let alloc: MutPtr<u8> = env.mem.alloc(length.max(1)).cast();
+    env.mem.bytes_at_mut(alloc, length).copy_from_slice(&bytes);
+    borrow_host_obj(env, this).decoded_bytes.push(alloc);
+    alloc.cast_const()
+}
 
 @end
 
@@ -93,27 +256,137 @@ fn borrow_host_obj(env: &mut Environment, unarchiver: id) -> &mut NSKeyedUnarchi
     env.objc.borrow_mut(unarchiver)
 }
 
-/// Shortcut for use by [crate::frameworks::uikit::ui_nib::load_main_nib_file].
-///
-/// This is equivalent to calling `initForReadingWithData:` in the proper API.
-pub fn init_for_reading_with_data(env: &mut Environment, unarchiver: id, data: &[u8]) {
+/// The dictionary keys are currently being decoded from: the top level, or the
+/// object that is being unarchived.
+fn current_scope(host_obj: &NSKeyedUnarchiverHostObject) -> &Dictionary {
+    match host_obj.current_key {
+        Some(current_uid) => {
+            &host_obj.plist["$objects"].as_array().unwrap()[current_uid.get() as usize]
+        }
+        None => &host_obj.plist["$top"],
+    }
+    .as_dictionary()
+    .unwrap()
+}
+
+/// Get a copy of the value for a key in the current scope, if there is one.
+fn lookup_key(env: &mut Environment, unarchiver: id, key: id) -> Option<Value> {
+    let key = escape_key(&to_rust_string(env, key));
+    let host_obj = borrow_host_obj(env, unarchiver);
+    current_scope(host_obj).get(&key).cloned()
+}
+
+/// Numeric interpretation of a value, as used by the integer decode methods.
+fn value_to_i64(value: &Value) -> i64 {
+    match value {
+        Value::Boolean(value) => *value as i64,
+        Value::Integer(integer) => integer
+            .as_signed()
+            .unwrap_or_else(|| integer.as_unsigned().unwrap() as i64),
+        Value::Real(real) => *real as i64,
+        _ => 0,
+    }
+}
+
+/// Numeric interpretation of a value, as used by the floating-point decode
+/// methods.
+fn value_to_f64(value: &Value) -> f64 {
+    match value {
+        Value::Real(real) => *real,
+        _ => value_to_i64(value) as f64,
+    }
+}
+
+/// Check that some data is a keyed archive we can read, and parse it.
+fn parse_archive(data: &[u8]) -> Result<Dictionary, String> {
+    let plist = Value::from_reader(Cursor::new(data)).map_err(|e| e.to_string())?;
+    let plist = plist
+        .into_dictionary()
+        .ok_or_else(|| "not a dictionary".to_string())?;
+    if plist.get("$version").and_then(Value::as_unsigned_integer) != Some(100000) {
+        return Err(format!("unsupported version {:?}", plist.get("$version")));
+    }
+    if plist.get("$archiver").and_then(Value::as_string) != Some("NSKeyedArchiver") {
+        return Err(format!("unsupported archiver {:?}", plist.get("$archiver")));
+    }
+    if plist.get("$top").and_then(Value::as_dictionary).is_none() {
+        return Err("no $top dictionary".to_string());
+    }
+    if plist.get("$objects").and_then(Value::as_array).is_none() {
+        return Err("no $objects array".to_string());
+    }
+    Ok(plist)
+}
+
+fn init_with_plist(env: &mut Environment, unarchiver: id, plist: Dictionary) {
     // Should have already been alloc'd the proper way.
     let host_obj = borrow_host_obj(env, unarchiver);
     assert!(host_obj.already_unarchived.is_empty());
     assert!(host_obj.current_key.is_none());
     assert!(host_obj.plist.is_empty());
 
-    let plist = Value::from_reader(Cursor::new(data)).unwrap();
-    let plist = plist.into_dictionary().unwrap();
-    assert!(plist["$version"].as_unsigned_integer() == Some(100000));
-    assert!(plist["$archiver"].as_string() == Some("NSKeyedArchiver"));
-
     let key_count = plist["$objects"].as_array().unwrap().len();
 
     host_obj.already_unarchived = vec![None; key_count];
     host_obj.plist = plist;
 }
 
+/// Shortcut for use by [crate::frameworks::uikit::ui_nib::load_main_nib_file].
+///
+/// This is equivalent to calling `initForReadingWithData:` in the proper API,
+/// except that an invalid archive is reported by returning an error rather
+/// than by raising an exception. The unarchiver isn't released in that case.
+pub fn init_for_reading_with_data(
+    env: &mut Environment,
+    unarchiver: id,
+    data: &[u8],
+) -> Result<(), String> {
+    let plist = parse_archive(data)?;
+    init_with_plist(env, unarchiver, plist);
+    Ok(())
+}
+
+/// Find the class to use for a class name in the archive, taking substitutions
+/// from `setClass:forClassName:` into account.
+fn class_for_class_name(env: &mut Environment, unarchiver: id, name: &str) -> Option<Class> {
+    if let Some(&class) = borrow_host_obj(env, unarchiver).classes.get(name) {
+        return Some(class);
+    }
+    if let Some(&class) = State::get(env).classes.get(name) {
+        return Some(class);
+    }
+    env.objc.find_class(name, &mut env.mem)
+}
+
+/// Find the uid and name of the class of an object in the archive.
+fn class_of_item(objects: &[Value], item: &Dictionary) -> Result<(Uid, String), String> {
+    let class_key = item
+        .get("$class")
+        .and_then(Value::as_uid)
+        .copied()
+        .ok_or_else(|| "no $class uid".to_string())?;
+    let class_name = objects
+        .get(class_key.get() as usize)
+        .and_then(Value::as_dictionary)
+        .and_then(|class_dict| class_dict.get("$classname"))
+        .and_then(Value::as_string)
+        .ok_or_else(|| format!("class {} has no $classname", class_key.get()))?;
+    Ok((class_key, class_name.to_string()))
+}
+
+/// Raise the exception for an archive that doesn't have the expected
+/// structure, e.g. because it is truncated or corrupt.
+fn invalid_archive(env: &mut Environment, problem: String) -> ! {
+    raise(
+        env,
+        NSInvalidUnarchiveOperationException,
+        format!(
+            "*** -[NSKeyedUnarchiver decodeObjectForKey:]: incomprehensible archive ({})",
+            problem
+        ),
+    )
+}
+
 /// The core of the implementation: unarchive something by its uid.
 ///
 /// This is recursive in practice: the `initWithCoder:` messages sent by this
@@ -124,8 +397,16 @@ pub fn init_for_reading_with_data(env: &mut Environment, unarchiver: id, data: &
 /// The object returned is retained only by the archiver. Remember to retain and
 /// possibly autorelease it as appropriate.
 fn unarchive_key(env: &mut Environment, unarchiver: id, key: Uid) -> id {
+    // Uid 0 is always "$null", which represents nil.
+    if key.get() == 0 {
+        return nil;
+    }
+
     let host_obj = borrow_host_obj(env, unarchiver);
-    if let Some(existing) = host_obj.already_unarchived[key.get() as usize] {
+    let Some(&already_unarchived) = host_obj.already_unarchived.get(key.get() as usize) else {
+        invalid_archive(env, format!("uid {} is out of range", key.get()));
+    };
+    if let Some(existing) = already_unarchived {
         return existing;
     }
 
@@ -136,33 +417,41 @@ fn unarchive_key(env: &mut Environment, unarchiver: id, key: Uid) -> id {
         // The most general kind of item: a dictionary that contains the info
         // needed to invoke `initWithCoder:` on a class implementing NSCoding.
         Value::Dictionary(dict) => {
-            let class_key = dict["$class"].as_uid().copied().unwrap();
+            let (class_key, class_name) = match class_of_item(objects, dict) {
+                Ok(class_info) => class_info,
+                Err(e) => invalid_archive(env, format!("object {}: {}", key.get(), e)),
+            };
+            let host_obj = borrow_host_obj(env, unarchiver); // reborrow
             let class;
             if let Some(existing) = host_obj.already_unarchived[class_key.get() as usize] {
                 class = existing;
             } else {
-                let class_dict = &objects[class_key.get() as usize];
-                let class_dict = class_dict.as_dictionary().unwrap();
-
-                let class_name = class_dict["$classname"].as_string().unwrap();
-
-                class = {
-                    // get_known_class needs &mut ObjC, so we can't call it
-                    // while holding a reference to the class name, since it
-                    // is ultimately owned by ObjC via the host object
-                    let class_name = class_name.to_string();
-                    env.objc.get_known_class(&class_name, &mut env.mem)
-                };
+                class = class_for_class_name(env, unarchiver, &class_name).unwrap_or_else(|| {
+                    raise(
+                        env,
+                        NSInvalidUnarchiveOperationException,
+                        format!(
+                            "*** -[NSKeyedUnarchiver decodeObjectForKey:]: cannot decode object of class ({})",
+                            class_name
+                        ),
+                    )
+                });
                 let host_obj = borrow_host_obj(env, unarchiver); // reborrow
 
                 host_obj.already_unarchived[class_key.get() as usize] = Some(class);
             };
 
+            let new_object: id = msg![env; class alloc];
+
+            // The object must be known before it is initialized, in case it
+            // contains references back to itself.
             let host_obj = borrow_host_obj(env, unarchiver); // reborrow
+            host_obj.already_unarchived[key.get() as usize] = Some(new_object);
             let old_current_key = host_obj.current_key;
             host_obj.current_key = Some(key);
 
-            let new_object: id = msg![env; class alloc];
+            // initWithCoder: may return a different object, in which case it
+            // has released the original one.
             let new_object: id = msg![env; new_object initWithCoder:unarchiver];
 
             let host_obj = borrow_host_obj(env, unarchiver); // reborrow
@@ -174,7 +463,18 @@ fn unarchive_key(env: &mut Environment, unarchiver: id, key: Uid) -> id {
             let s = s.to_string();
             from_rust_string(env, s)
         }
-        _ => unimplemented!("Unarchive: {:#?}", item),
+        // Numbers and data are stored directly, like strings.
+        Value::Boolean(_) | Value::Integer(_) | Value::Real(_) | Value::Data(_) => {
+            let item = item.clone();
+            value_to_object(env, &item)
+        }
+        _ => {
+            log!(
+                "TODO: Unarchive: {:#?} (unsupported kind of item), using nil",
+                item
+            );
+            nil
+        }
     };
 
     let host_obj = borrow_host_obj(env, unarchiver); // reborrow
@@ -182,20 +482,28 @@ fn unarchive_key(env: &mut Environment, unarchiver: id, key: Uid) -> id {
     new_object
 }
 
-/// Shortcut for use by `[_touchHLE_NSArray initWithCoder:]`.
+/// Shortcut for use by the `initWithCoder:` implementations of collection
+/// classes, e.g. `[_touchHLE_NSArray initWithCoder:]`, to decode arrays like
+/// `NS.objects` that are stored directly in the object's dictionary.
 ///
 /// The objects are to be considered retained by the `Vec`.
-pub fn decode_current_array(env: &mut Environment, unarchiver: id) -> Vec<id> {
-    let keys: Vec<Uid> = {
+pub fn decode_current_array(env: &mut Environment, unarchiver: id, key: &str) -> Vec<id> {
+    let keys = {
         let host_obj = borrow_host_obj(env, unarchiver);
         let objects = host_obj.plist["$objects"].as_array().unwrap();
         let item = &objects[host_obj.current_key.unwrap().get() as usize];
-        let keys = item.as_dictionary().unwrap()["NS.objects"]
-            .as_array()
-            .unwrap();
-        keys.iter()
-            .map(|value| value.as_uid().copied().unwrap())
-            .collect()
+        item.as_dictionary()
+            .unwrap()
+            .get(key)
+            .and_then(Value::as_array)
+            .and_then(|keys| {
+                keys.iter()
+                    .map(|value| value.as_uid().copied())
+                    .collect::<Option<Vec<Uid>>>()
+            })
+    };
+    let Some(keys) = keys else {
+        invalid_archive(env, format!("{} is not an array of uids", key));
     };
 
     keys.into_iter()
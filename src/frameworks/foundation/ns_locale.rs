@@ -6,12 +6,15 @@
 //! `NSLocale`.
 
 use super::{ns_array, ns_string};
-use crate::objc::{id, objc_classes, ClassExports};
+use crate::objc::{
+    autorelease, id, msg, objc_classes, release, AnyHostObject, ClassExports, HostObject, NSZonePtr,
+};
 use crate::Environment;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     preferred_languages: Option<id>,
+    current_locale: Option<id>,
 }
 impl State {
     fn get(env: &mut Environment) -> &mut State {
@@ -19,12 +22,30 @@ impl State {
     }
 }
 
+#[derive(Clone)]
+struct NSLocaleHostObject {
+    /// `NSString*`
+    identifier: id,
+}
+impl HostObject for NSLocaleHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation NSLocale: NSObject
 
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSLocaleHostObject {
+        identifier: ns_string::get_static_str(env, ""),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
 // The documentation isn't clear about what the format of the strings should be,
 // but Super Monkey Ball does `isEqualToString:` against "fr", "es", "de", "it"
 // and "ja", and its locale detection works properly, so presumably they do not
@@ -53,7 +74,51 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
-// TODO: constructors, more accessors
++ (id)currentLocale {
+    if let Some(existing) = State::get(env).current_locale {
+        return existing;
+    }
+    // turn e.g. "sv_SE.UTF-8" into just "sv_SE"
+    let identifier = std::env::var("LANG")
+        .ok()
+        .and_then(|lang| lang.split('.').next().map(str::to_string))
+        .filter(|identifier| !identifier.is_empty() && identifier != "C")
+        .unwrap_or_else(|| "en_US".to_string());
+    let identifier = ns_string::from_rust_string(env, identifier);
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithLocaleIdentifier:identifier];
+    release(env, identifier);
+    State::get(env).current_locale = Some(new);
+    new
+}
++ (id)autoupdatingCurrentLocale {
+    msg![env; this currentLocale]
+}
+
+- (id)initWithLocaleIdentifier:(id)identifier { // NSString*
+    let identifier: id = msg![env; identifier copy];
+    env.objc.borrow_mut::<NSLocaleHostObject>(this).identifier = identifier;
+    this
+}
+
+- (())dealloc {
+    let identifier = env.objc.borrow::<NSLocaleHostObject>(this).identifier;
+    release(env, identifier);
+    env.objc.dealloc_object(this, &mut env.mem);
+}
+
+- (id)localeIdentifier {
+    env.objc.borrow::<NSLocaleHostObject>(this).identifier
+}
+
+- (id)description {
+    let identifier = env.objc.borrow::<NSLocaleHostObject>(this).identifier;
+    let identifier = ns_string::to_rust_string(env, identifier);
+    let description = ns_string::from_rust_string(env, format!("<NSLocale {}>", identifier));
+    autorelease(env, description)
+}
+
+// TODO: more accessors
 
 @end
 
@@ -6,18 +6,24 @@
 //! `NSNotification`.
 
 use crate::objc::{
-    autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, nil, objc_classes, release, retain, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr,
 };
 
 /// `NSString*`
 pub type NSNotificationName = id;
 
+#[derive(Clone)]
 struct NSNotificationHostObject {
     name: id,
     object: id,
     user_info: id,
 }
-impl HostObject for NSNotificationHostObject {}
+impl HostObject for NSNotificationHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
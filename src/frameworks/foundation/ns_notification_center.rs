@@ -9,13 +9,13 @@ use super::ns_notification::NSNotificationName;
 use super::ns_string;
 
 use crate::objc::{
-    id, msg, msg_class, msg_send, nil, objc_classes, release, retain, ClassExports, HostObject,
-    NSZonePtr, SEL,
+    id, msg, msg_class, msg_send, nil, objc_classes, release, retain, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr, SEL,
 };
 use std::borrow::Cow;
 use std::collections::HashMap;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     default_center: Option<id>,
 }
@@ -27,10 +27,15 @@ struct Observer {
     object: id,
 }
 
+#[derive(Clone)]
 struct NSNotificationCenterHostObject {
     observers: HashMap<Cow<'static, str>, Vec<Observer>>,
 }
-impl HostObject for NSNotificationCenterHostObject {}
+impl HostObject for NSNotificationCenterHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
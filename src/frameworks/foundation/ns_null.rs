@@ -5,9 +5,9 @@
  */
 //! `NSNull`.
 
-use crate::objc::{id, objc_classes, ClassExports, TrivialHostObject};
+use crate::objc::{id, msg_class, objc_classes, ClassExports, TrivialHostObject};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     null: Option<id>,
 }
@@ -38,6 +38,17 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (())release {}
 - (id)autorelease { this }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)_coder {
+    // Since this is a singleton, the unarchiver's new instance isn't needed.
+    // It can't be released normally, because of the override above.
+    env.objc.dealloc_object(this, &mut env.mem);
+    msg_class![env; NSNull null]
+}
+- (())encodeWithCoder:(id)_coder {
+    // Nothing to encode.
+}
+
 @end
 
 };
@@ -116,6 +116,15 @@ pub const CLASSES: ClassExports = objc_classes! {
     msg![env; this copyWithZone:(MutVoidPtr::null())]
 }
 
+// NSCoding helpers. Class clusters override these so that their private
+// subclasses aren't mentioned in archives.
+- (Class)classForCoder {
+    msg![env; this class]
+}
+- (Class)classForKeyedArchiver {
+    msg![env; this classForCoder]
+}
+
 
 // NSKeyValueCoding
 - (())setValue:(id)value
@@ -0,0 +1,385 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSPropertyListSerialization`, and conversion between property lists and
+//! Foundation objects for host code.
+//!
+//! Property lists are represented on the host side with the `plist` crate's
+//! [Value] type, which handles the XML and binary formats for us.
+
+use super::ns_dictionary::{dict_from_keys_and_objects, DictionaryHostObject};
+use super::ns_string::{from_rust_string, to_rust_string};
+use super::ns_value::NSNumberHostObject;
+use super::{ns_array, ns_data, ns_date, ns_value, NSUInteger};
+use crate::frameworks::core_foundation::time::{
+    absolute_time_from_system_time, system_time_from_absolute_time,
+};
+use crate::fs::GuestPath;
+use crate::mem::MutPtr;
+use crate::objc::{autorelease, id, msg, nil, objc_classes, release, Class, ClassExports};
+use crate::Environment;
+use plist::{Dictionary, Value};
+use std::io::Cursor;
+
+pub type NSPropertyListFormat = NSUInteger;
+pub const NSPropertyListOpenStepFormat: NSPropertyListFormat = 1;
+pub const NSPropertyListXMLFormat_v1_0: NSPropertyListFormat = 100;
+pub const NSPropertyListBinaryFormat_v1_0: NSPropertyListFormat = 200;
+
+pub type NSPropertyListMutabilityOptions = NSUInteger;
+pub const NSPropertyListImmutable: NSPropertyListMutabilityOptions = 0;
+pub const NSPropertyListMutableContainers: NSPropertyListMutabilityOptions = 1;
+pub const NSPropertyListMutableContainersAndLeaves: NSPropertyListMutabilityOptions = 2;
+
+/// Magic number at the start of binary property lists.
+const BINARY_MAGIC: &[u8] = b"bplist00";
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSPropertyListSerialization: NSObject
+
++ (id)propertyListFromData:(id)data // NSData*
+          mutabilityOption:(NSPropertyListMutabilityOptions)options
+                    format:(MutPtr<NSPropertyListFormat>)format_ptr
+          errorDescription:(MutPtr<id>)error_ptr { // NSString**
+    let bytes = ns_data::to_vec(env, data);
+    match deserialize(&bytes, options) {
+        Ok((value, format)) => {
+            if !format_ptr.is_null() {
+                env.mem.write(format_ptr, format);
+            }
+            if !error_ptr.is_null() {
+                env.mem.write(error_ptr, nil);
+            }
+            let object = value_to_object(env, &value);
+            autorelease(env, object)
+        }
+        Err(e) => {
+            log!(
+                "Warning: [NSPropertyListSerialization propertyListFromData:...] failed: {}",
+                e
+            );
+            if !error_ptr.is_null() {
+                // The caller is responsible for releasing this.
+                let error = from_rust_string(env, e);
+                env.mem.write(error_ptr, error);
+            }
+            nil
+        }
+    }
+}
+
++ (id)propertyListWithData:(id)data // NSData*
+                   options:(NSPropertyListMutabilityOptions)options
+                    format:(MutPtr<NSPropertyListFormat>)format_ptr
+                     error:(MutPtr<id>)error_ptr { // NSError**
+    let bytes = ns_data::to_vec(env, data);
+    // TODO: NSError
+    if !error_ptr.is_null() {
+        env.mem.write(error_ptr, nil);
+    }
+    match deserialize(&bytes, options) {
+        Ok((value, format)) => {
+            if !format_ptr.is_null() {
+                env.mem.write(format_ptr, format);
+            }
+            let object = value_to_object(env, &value);
+            autorelease(env, object)
+        }
+        Err(e) => {
+            log!(
+                "Warning: [NSPropertyListSerialization propertyListWithData:...] failed: {}",
+                e
+            );
+            nil
+        }
+    }
+}
+
++ (id)dataFromPropertyList:(id)plist
+                    format:(NSPropertyListFormat)format
+          errorDescription:(MutPtr<id>)error_ptr { // NSString**
+    match serialize_object(env, plist, format) {
+        Ok(bytes) => {
+            if !error_ptr.is_null() {
+                env.mem.write(error_ptr, nil);
+            }
+            let data = ns_data::from_slice(env, &bytes);
+            autorelease(env, data)
+        }
+        Err(e) => {
+            log!(
+                "Warning: [NSPropertyListSerialization dataFromPropertyList:...] failed: {}",
+                e
+            );
+            if !error_ptr.is_null() {
+                // The caller is responsible for releasing this.
+                let error = from_rust_string(env, e);
+                env.mem.write(error_ptr, error);
+            }
+            nil
+        }
+    }
+}
+
++ (id)dataWithPropertyList:(id)plist
+                    format:(NSPropertyListFormat)format
+                   options:(NSUInteger)_options // reserved
+                     error:(MutPtr<id>)error_ptr { // NSError**
+    // TODO: NSError
+    if !error_ptr.is_null() {
+        env.mem.write(error_ptr, nil);
+    }
+    match serialize_object(env, plist, format) {
+        Ok(bytes) => {
+            let data = ns_data::from_slice(env, &bytes);
+            autorelease(env, data)
+        }
+        Err(e) => {
+            log!(
+                "Warning: [NSPropertyListSerialization dataWithPropertyList:...] failed: {}",
+                e
+            );
+            nil
+        }
+    }
+}
+
++ (bool)propertyList:(id)plist
+    isValidForFormat:(NSPropertyListFormat)format {
+    format != NSPropertyListOpenStepFormat && object_to_value(env, plist).is_some()
+}
+
+@end
+
+};
+
+/// Parse a property list in XML or binary format, and return it with its
+/// format.
+pub fn deserialize(
+    bytes: &[u8],
+    options: NSPropertyListMutabilityOptions,
+) -> Result<(Value, NSPropertyListFormat), String> {
+    match options {
+        NSPropertyListImmutable => (),
+        // TODO: mutable containers and leaves (needs NSMutableDictionary and
+        // NSMutableString). Apps that modify the result will probably crash,
+        // so this shouldn't be silent.
+        NSPropertyListMutableContainers | NSPropertyListMutableContainersAndLeaves => log!(
+            "TODO: mutability option {} for property list is unsupported, using immutable objects",
+            options
+        ),
+        _ => log!(
+            "Warning: unknown mutability option {} for property list, using immutable objects",
+            options
+        ),
+    }
+    let format = if bytes.starts_with(BINARY_MAGIC) {
+        NSPropertyListBinaryFormat_v1_0
+    } else {
+        NSPropertyListXMLFormat_v1_0
+    };
+    let value = Value::from_reader(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    Ok((value, format))
+}
+
+/// Convert a Foundation object to a property list in XML or binary format.
+pub fn serialize_object(
+    env: &mut Environment,
+    object: id,
+    format: NSPropertyListFormat,
+) -> Result<Vec<u8>, String> {
+    let Some(value) = object_to_value(env, object) else {
+        return Err(format!("{:?} is not a property list object", object));
+    };
+    let mut bytes = Vec::new();
+    match format {
+        NSPropertyListXMLFormat_v1_0 => value.to_writer_xml(&mut bytes),
+        NSPropertyListBinaryFormat_v1_0 => value.to_writer_binary(&mut bytes),
+        _ => return Err(format!("Unsupported property list format {}", format)),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Shortcut for the `...WithContentsOfFile:` methods of collection classes:
+/// read and parse a property list file. Returns [None] if the file can't be
+/// read or parsed.
+pub fn read_plist_file(env: &mut Environment, path: id) -> Option<Value> {
+    if path == nil {
+        return None;
+    }
+    let path = to_rust_string(env, path);
+    let Ok(bytes) = env.fs.read(GuestPath::new(&path)) else {
+        log_dbg!("Couldn't read property list file {:?}", path);
+        return None;
+    };
+    match deserialize(&bytes, NSPropertyListImmutable) {
+        Ok((value, _format)) => Some(value),
+        Err(e) => {
+            log!(
+                "Warning: Couldn't parse property list file {:?}: {}",
+                path,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Shortcut for the `writeToFile:atomically:` methods of collection classes:
+/// write an object to a file as an XML property list. Returns [false] on
+/// failure.
+///
+/// If `atomically` is [true], the file is written under a temporary name first
+/// and then renamed, so that the original file is never left half-written.
+pub fn write_plist_file(env: &mut Environment, object: id, path: id, atomically: bool) -> bool {
+    let bytes = match serialize_object(env, object, NSPropertyListXMLFormat_v1_0) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log!("Warning: Couldn't write {:?} to a file: {}", object, e);
+            return false;
+        }
+    };
+    let path = to_rust_string(env, path);
+    if !atomically {
+        return env.fs.write(GuestPath::new(&path), &bytes).is_ok();
+    }
+    let tmp_path = format!("{}.tmp", path);
+    if env.fs.write(GuestPath::new(&tmp_path), &bytes).is_err() {
+        return false;
+    }
+    if env
+        .fs
+        .rename(GuestPath::new(&tmp_path), GuestPath::new(&path))
+        .is_err()
+    {
+        let _ = env.fs.remove(GuestPath::new(&tmp_path));
+        return false;
+    }
+    true
+}
+
+/// Create a new Foundation object (not autoreleased) equivalent to a property
+/// list value. Arrays and dictionaries are converted recursively.
+///
+/// Values with no supported equivalent (e.g. UIDs) are converted to `nil`, and
+/// omitted if they are inside a container.
+pub fn value_to_object(env: &mut Environment, value: &Value) -> id {
+    match value {
+        Value::String(string) => from_rust_string(env, string.clone()),
+        Value::Boolean(value) => ns_value::from_host_object(env, NSNumberHostObject::Bool(*value)),
+        Value::Integer(integer) => {
+            let integer = integer.as_signed().unwrap_or_else(|| {
+                // Values this big can only be unsigned.
+                integer.as_unsigned().unwrap() as i64
+            });
+            let number = match i32::try_from(integer) {
+                Ok(integer) => NSNumberHostObject::Int(integer),
+                Err(_) => NSNumberHostObject::LongLong(integer),
+            };
+            ns_value::from_host_object(env, number)
+        }
+        Value::Real(real) => ns_value::from_host_object(env, NSNumberHostObject::Double(*real)),
+        Value::Data(data) => ns_data::from_slice(env, data),
+        Value::Date(date) => {
+            let interval = absolute_time_from_system_time((*date).into());
+            ns_date::from_time_interval(env, interval)
+        }
+        Value::Array(array) => {
+            let objects = array
+                .iter()
+                .map(|value| value_to_object(env, value))
+                .filter(|&object| object != nil)
+                .collect();
+            ns_array::from_vec(env, objects)
+        }
+        Value::Dictionary(dictionary) => {
+            let mut keys_and_objects = Vec::with_capacity(dictionary.len());
+            for (key, value) in dictionary {
+                let object = value_to_object(env, value);
+                if object == nil {
+                    continue;
+                }
+                let key = from_rust_string(env, key.clone());
+                keys_and_objects.push((key, object));
+            }
+            let dict = dict_from_keys_and_objects(env, &keys_and_objects);
+            for (key, object) in keys_and_objects {
+                release(env, key);
+                release(env, object);
+            }
+            dict
+        }
+        _ => {
+            log!(
+                "TODO: Property list value {:?} can't be converted to an object, using nil",
+                value
+            );
+            nil
+        }
+    }
+}
+
+/// Convert a Foundation object to the equivalent property list value.
+/// Arrays and dictionaries are converted recursively.
+///
+/// Returns [None] if the object, or an object inside it, is not a property
+/// list object.
+pub fn object_to_value(env: &mut Environment, object: id) -> Option<Value> {
+    if object == nil {
+        return None;
+    }
+
+    if is_kind_of(env, object, "NSString") {
+        Some(Value::String(to_rust_string(env, object).into_owned()))
+    } else if is_kind_of(env, object, "NSNumber") {
+        Some(match ns_value::to_host_object(env, object) {
+            NSNumberHostObject::Bool(value) => Value::Boolean(value),
+            NSNumberHostObject::Int(value) => Value::Integer((value as i64).into()),
+            NSNumberHostObject::LongLong(value) => Value::Integer(value.into()),
+            NSNumberHostObject::Float(value) => Value::Real(value as f64),
+            NSNumberHostObject::Double(value) => Value::Real(value),
+        })
+    } else if is_kind_of(env, object, "NSData") {
+        Some(Value::Data(ns_data::to_vec(env, object)))
+    } else if is_kind_of(env, object, "NSDate") {
+        let interval = ns_date::to_time_interval(env, object);
+        Some(Value::Date(system_time_from_absolute_time(interval).into()))
+    } else if is_kind_of(env, object, "NSArray") {
+        let count: NSUInteger = msg![env; object count];
+        let mut array = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let element: id = msg![env; object objectAtIndex:i];
+            array.push(object_to_value(env, element)?);
+        }
+        Some(Value::Array(array))
+    } else if is_kind_of(env, object, "NSDictionary") {
+        let keys: Vec<id> = env
+            .objc
+            .borrow::<DictionaryHostObject>(object)
+            .iter_keys()
+            .collect();
+        let mut dictionary = Dictionary::new();
+        for key in keys {
+            // Property list dictionary keys are always strings.
+            let Some(Value::String(key_string)) = object_to_value(env, key) else {
+                return None;
+            };
+            let value: id = msg![env; object objectForKey:key];
+            dictionary.insert(key_string, object_to_value(env, value)?);
+        }
+        Some(Value::Dictionary(dictionary))
+    } else {
+        None
+    }
+}
+
+pub(super) fn is_kind_of(env: &mut Environment, object: id, class_name: &str) -> bool {
+    let class: Class = env.objc.get_known_class(class_name, &mut env.mem);
+    msg![env; object isKindOfClass:class]
+}
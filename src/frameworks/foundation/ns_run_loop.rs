@@ -14,8 +14,11 @@ use crate::frameworks::audio_toolbox::audio_queue::{handle_audio_queue, AudioQue
 use crate::frameworks::core_foundation::cf_run_loop::{
     kCFRunLoopCommonModes, kCFRunLoopDefaultMode, CFRunLoopRef,
 };
-use crate::frameworks::{media_player, uikit};
-use crate::objc::{id, msg, objc_classes, release, retain, ClassExports, HostObject};
+use crate::frameworks::{core_animation, media_player, uikit};
+use crate::objc::{
+    id, msg, objc_classes, release, retain, AnyHostObject, ClassExports, HostObject,
+};
+use crate::snapshot;
 use crate::Environment;
 use std::time::{Duration, Instant};
 
@@ -36,11 +39,12 @@ pub const CONSTANTS: ConstantExports = &[
     ),
 ];
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     main_thread_run_loop: Option<id>,
 }
 
+#[derive(Clone)]
 struct NSRunLoopHostObject {
     /// Weak reference. Audio queue must remove itself when destroyed (TODO).
     /// They are in no particular order.
@@ -49,7 +53,11 @@ struct NSRunLoopHostObject {
     /// by the run loop. The timer must remove itself when invalidated.
     timers: Vec<id>,
 }
-impl HostObject for NSRunLoopHostObject {}
+impl HostObject for NSRunLoopHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -170,11 +178,17 @@ fn run_run_loop(env: &mut Environment, run_loop: id) {
     loop {
         let mut sleep_until = None;
 
-        env.window.poll_for_events(&env.options);
+        env.poll_for_events();
+
+        // The host stack is only the same each time at the top of the loop.
+        snapshot::handle_request(env, run_loop);
 
         let next_due = uikit::handle_events(env);
         limit_sleep_time(&mut sleep_until, next_due);
 
+        let next_due = core_animation::composition::recomposite_if_necessary(env);
+        limit_sleep_time(&mut sleep_until, next_due);
+
         assert!(timers_tmp.is_empty());
         timers_tmp.extend_from_slice(&env.objc.borrow::<NSRunLoopHostObject>(run_loop).timers);
 
@@ -213,8 +227,10 @@ fn run_run_loop(env: &mut Environment, run_loop: id) {
         //
         // FIXME: Run the app's other threads if they are active.
         let limit = Duration::from_millis(1000 / 60);
-        std::thread::sleep(
-            sleep_until.map_or(limit, |i| i.duration_since(Instant::now()).min(limit)),
-        );
+        std::thread::sleep(sleep_until.map_or(limit, |i| {
+            env.clock
+                .host_duration(i.duration_since(env.clock.now()))
+                .min(limit)
+        }));
     }
 }
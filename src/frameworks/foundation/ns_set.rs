@@ -8,17 +8,24 @@
 use super::ns_array;
 use super::ns_dictionary::DictionaryHostObject;
 use super::ns_enumerator::NSFastEnumerationState;
-use super::NSUInteger;
+use super::{ns_keyed_archiver, ns_keyed_unarchiver, NSUInteger};
 use crate::mem::MutPtr;
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class,
+    ClassExports, HostObject, NSZonePtr,
 };
+use crate::Environment;
 
 /// Belongs to _touchHLE_NSSet
+#[derive(Clone)]
 struct SetHostObject {
     dict: DictionaryHostObject,
 }
-impl HostObject for SetHostObject {}
+impl HostObject for SetHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -52,6 +59,11 @@ pub const CLASSES: ClassExports = objc_classes! {
     retain(env, this)
 }
 
+- (Class)classForCoder {
+    // Our private subclass shouldn't appear in archives.
+    env.objc.get_known_class("NSSet", &mut env.mem)
+}
+
 @end
 
 // Our private subclass that is the single implementation of NSSet for the
@@ -81,9 +93,52 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.dealloc_object(this, &mut env.mem)
 }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    // Sets in keyed archives have the same format as arrays, see
+    // `[_touchHLE_NSArray initWithCoder:]`.
+    let objects = ns_keyed_unarchiver::decode_current_array(env, coder, "NS.objects");
+    let null: id = msg_class![env; NSNull null];
+
+    let mut dict = <DictionaryHostObject as Default>::default();
+    for object in objects {
+        dict.insert(env, object, null, /* copy_key: */ false);
+        release(env, object);
+    }
+
+    env.objc.borrow_mut::<SetHostObject>(this).dict = dict;
+
+    this
+}
+- (())encodeWithCoder:(id)coder {
+    let objects: Vec<id> = env.objc.borrow::<SetHostObject>(this).dict.iter_keys().collect();
+    ns_keyed_archiver::encode_current_array(env, coder, "NS.objects", &objects);
+}
+
 // TODO: more init methods, etc
 
-// TODO: accessors
+- (NSUInteger)count {
+    env.objc.borrow::<SetHostObject>(this).dict.count
+}
+
+- (id)member:(id)object {
+    let null: id = msg_class![env; NSNull null];
+    let dict = std::mem::take(&mut env.objc.borrow_mut::<SetHostObject>(this).dict);
+    let found = dict.lookup(env, object) == null;
+    env.objc.borrow_mut::<SetHostObject>(this).dict = dict;
+    if found {
+        object
+    } else {
+        nil
+    }
+}
+
+- (bool)containsObject:(id)object {
+    let member: id = msg![env; this member:object];
+    member != nil
+}
+
+// TODO: more accessors
 
 - (id)anyObject {
     let object_or_none = env.objc.borrow_mut::<SetHostObject>(this).dict.iter_keys().next();
@@ -102,42 +157,58 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (NSUInteger)countByEnumeratingWithState:(MutPtr<NSFastEnumerationState>)state
                                   objects:(MutPtr<id>)stackbuf
                                     count:(NSUInteger)len {
-    let host_object = env.objc.borrow::<SetHostObject>(this);
-
-    if host_object.dict.count == 0 {
-        return 0;
-    }
-
-    // TODO: handle size > 1
-    assert!(host_object.dict.count == 1);
-    assert!(len >= host_object.dict.count);
-
+    // The state is the number of objects returned so far. The iteration
+    // order is consistent as long as the set is not modified.
     let NSFastEnumerationState {
-        state: is_first_round,
+        state: returned_count,
         ..
     } = env.mem.read(state);
 
-    match is_first_round {
-        0 => {
-            let object = host_object.dict.iter_keys().next().unwrap();
-            env.mem.write(stackbuf, object);
-            env.mem.write(state, NSFastEnumerationState {
-                state: 1,
-                items_ptr: stackbuf,
-                // can be anything as long as it's dereferenceable and the same
-                // each iteration
-                mutations_ptr: stackbuf.cast(),
-                extra: Default::default(),
-            });
-            1 // returned object count
-        },
-        1 => {
-            0 // end of iteration
-        },
-        _ => panic!(), // app failed to initialize the buffer?
+    let host_object = env.objc.borrow::<SetHostObject>(this);
+    assert!(returned_count <= host_object.dict.count); // app failed to initialize the buffer?
+    let objects: Vec<id> = host_object
+        .dict
+        .iter_keys()
+        .skip(returned_count as usize)
+        .take(len as usize)
+        .collect();
+
+    if objects.is_empty() {
+        return 0; // end of iteration
+    }
+
+    let batch_count = objects.len() as NSUInteger;
+    for (i, object) in objects.into_iter().enumerate() {
+        env.mem.write(stackbuf + i as NSUInteger, object);
     }
+    env.mem.write(state, NSFastEnumerationState {
+        state: returned_count + batch_count,
+        items_ptr: stackbuf,
+        // can be anything as long as it's dereferenceable and the same
+        // each iteration
+        mutations_ptr: stackbuf.cast(),
+        extra: Default::default(),
+    });
+    batch_count // returned object count
 }
 
 @end
 
 };
+
+/// Shortcut for host code, roughly equivalent to
+/// `[[NSSet alloc] initWithObjects:count:]`.
+/// The elements should already be "retained by" the `Vec`.
+pub fn from_vec(env: &mut Environment, objects: Vec<id>) -> id {
+    let null: id = msg_class![env; NSNull null];
+
+    let mut dict = <DictionaryHostObject as Default>::default();
+    for object in objects {
+        dict.insert(env, object, null, /* copy_key: */ false);
+        release(env, object);
+    }
+
+    let set: id = msg_class![env; NSSet alloc];
+    env.objc.borrow_mut::<SetHostObject>(set).dict = dict;
+    set
+}
@@ -8,9 +8,11 @@
 mod path_algorithms;
 
 use super::ns_array;
+use super::ns_exception::{raise, NSInvalidArgumentException, NSRangeException};
 use super::{
     NSComparisonResult, NSOrderedAscending, NSOrderedDescending, NSOrderedSame, NSUInteger,
 };
+use crate::abi::VaList;
 use crate::frameworks::core_graphics::{CGRect, CGSize};
 use crate::frameworks::uikit::ui_font::{
     self, UILineBreakMode, UILineBreakModeWordWrap, UITextAlignment, UITextAlignmentLeft,
@@ -19,8 +21,8 @@ use crate::fs::GuestPath;
 use crate::mach_o::MachO;
 use crate::mem::{guest_size_of, ConstPtr, Mem, MutPtr, Ptr, SafeRead};
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, retain, Class, ClassExports, HostObject,
-    NSZonePtr, ObjC,
+    autorelease, id, msg, msg_class, nil, objc_classes, retain, AnyHostObject, Class, ClassExports,
+    HostObject, NSZonePtr, ObjC,
 };
 use crate::Environment;
 use std::borrow::Cow;
@@ -41,7 +43,7 @@ const C_STRING_FRIENDLY_ENCODINGS: &[NSStringEncoding] =
 
 pub const NSMaximumStringLength: NSUInteger = (i32::MAX - 1) as _;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     static_str_pool: HashMap<&'static str, id>,
 }
@@ -65,12 +67,17 @@ unsafe impl SafeRead for cfstringStruct {}
 type Utf16String = Vec<u16>;
 
 /// Belongs to _touchHLE_NSString.
+#[derive(Clone)]
 enum StringHostObject {
     Utf8(Cow<'static, str>),
     /// Not necessarily well-formed UTF-16: might contain unpaired surrogates.
     Utf16(Utf16String),
 }
-impl HostObject for StringHostObject {}
+impl HostObject for StringHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 impl StringHostObject {
     fn decode(bytes: Cow<[u8]>, encoding: NSStringEncoding) -> StringHostObject {
         if bytes.len() == 0 {
@@ -248,24 +255,8 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 + (id)stringWithFormat:(id)format, // NSString*
                        ...args {
-    // TODO: avoid copy
-    let format_string = to_rust_string(env, format);
-
-    log_dbg!("[NSString stringWithFormat:{:?} ({:?}), ...]", format, format_string);
-
-    let res = crate::libc::stdio::printf::printf_inner::<true, _>(
-        env,
-        |_, idx| {
-            if idx as usize == format_string.len() {
-                b'\0'
-            } else {
-                format_string.as_bytes()[idx as usize]
-            }
-        },
-        args.start(),
-    );
-    // TODO: what if it's not valid UTF-8?
-    let res = from_rust_string(env, String::from_utf8(res).unwrap());
+    log_dbg!("[NSString stringWithFormat:{:?}, ...]", format);
+    let res = with_format(env, format, args.start());
     autorelease(env, res)
 }
 
@@ -299,8 +290,18 @@ pub const CLASSES: ClassExports = objc_classes! {
         log_dbg!("[{:?} characterAtIndex:{:?}]: converted string to UTF-16", this, index);
     }
 
-    // TODO: raise exception instead of panicking?
-    utf16[index as usize]
+    let Some(character) = utf16.get(index as usize).copied() else {
+        let len = utf16.len();
+        raise(
+            env,
+            NSRangeException,
+            format!(
+                "*** -[NSString characterAtIndex:]: index {} out of bounds for length {}",
+                index, len
+            ),
+        );
+    };
+    character
 }
 
 - (id)description {
@@ -530,7 +531,13 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (id)stringByAppendingString:(id)other { // NSString*
-    assert!(other != nil); // TODO: raise exception
+    if other == nil {
+        raise(
+            env,
+            NSInvalidArgumentException,
+            "*** -[NSString stringByAppendingString:]: nil argument".to_string(),
+        );
+    }
 
     // TODO: ideally, don't convert to UTF-16 here
     let this_len: NSUInteger = msg![env; this length];
@@ -822,6 +829,29 @@ pub fn from_rust_string(env: &mut Environment, from: String) -> id {
     string
 }
 
+/// Shortcut for host code, roughly equivalent to
+/// `[[NSString alloc] initWithFormat:arguments:]` in the proper API.
+pub fn with_format(env: &mut Environment, format: id, args: VaList) -> id {
+    // TODO: avoid copy
+    let format_string = to_rust_string(env, format);
+
+    log_dbg!("Formatting {:?} ({:?})", format, format_string);
+
+    let res = crate::libc::stdio::printf::printf_inner::<true, _>(
+        env,
+        |_, idx| {
+            if idx as usize == format_string.len() {
+                b'\0'
+            } else {
+                format_string.as_bytes()[idx as usize]
+            }
+        },
+        args,
+    );
+    // TODO: what if it's not valid UTF-8?
+    from_rust_string(env, String::from_utf8(res).unwrap())
+}
+
 /// Shortcut for host code, provides a view of a string in UTF-8.
 /// Warning: This may panic if the string is not valid UTF-16!
 ///
@@ -0,0 +1,277 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSTimeZone`.
+//!
+//! The rules come from the host, see [crate::time_zone].
+
+use super::ns_string::{from_rust_string, to_rust_string};
+use super::{ns_date, NSInteger, NSTimeInterval, NSUInteger};
+use crate::frameworks::core_foundation::time::kCFAbsoluteTimeIntervalSince1970;
+use crate::libc::time::local_time_zone;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class,
+    ClassExports, HostObject, NSZonePtr,
+};
+use crate::time_zone::{LocalTimeType, TimeZone};
+use crate::Environment;
+use std::rc::Rc;
+
+/// Time zone names for some common abbreviations, for
+/// `timeZoneWithAbbreviation:`. Abbreviations are ambiguous in general, so this
+/// is only a subset of what Apple's list has.
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("BST", "Europe/London"),
+    ("CDT", "America/Chicago"),
+    ("CEST", "Europe/Paris"),
+    ("CET", "Europe/Paris"),
+    ("CST", "America/Chicago"),
+    ("EDT", "America/New_York"),
+    ("EEST", "Europe/Athens"),
+    ("EET", "Europe/Athens"),
+    ("EST", "America/New_York"),
+    ("GMT", "GMT"),
+    ("JST", "Asia/Tokyo"),
+    ("MDT", "America/Denver"),
+    ("MST", "America/Denver"),
+    ("PDT", "America/Los_Angeles"),
+    ("PST", "America/Los_Angeles"),
+    ("UTC", "UTC"),
+    ("WEST", "Europe/Lisbon"),
+    ("WET", "Europe/Lisbon"),
+];
+
+#[derive(Default, Clone)]
+pub struct State {
+    system_time_zone: Option<id>,
+    default_time_zone: Option<id>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut State {
+        &mut env.framework_state.foundation.ns_time_zone
+    }
+}
+
+#[derive(Clone)]
+struct NSTimeZoneHostObject {
+    time_zone: Rc<TimeZone>,
+}
+impl HostObject for NSTimeZoneHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSTimeZone: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSTimeZoneHostObject {
+        time_zone: Rc::new(TimeZone::utc()),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)systemTimeZone {
+    if let Some(existing) = State::get(env).system_time_zone {
+        return existing;
+    }
+    let time_zone = local_time_zone(env).clone();
+    let new = from_time_zone(env, time_zone);
+    State::get(env).system_time_zone = Some(new);
+    new
+}
++ (())resetSystemTimeZone {
+    // The host's time zone is only read once, so this just drops the cached
+    // object.
+    if let Some(existing) = State::get(env).system_time_zone.take() {
+        release(env, existing);
+    }
+}
++ (id)defaultTimeZone {
+    if let Some(existing) = State::get(env).default_time_zone {
+        existing
+    } else {
+        msg![env; this systemTimeZone]
+    }
+}
++ (())setDefaultTimeZone:(id)time_zone { // NSTimeZone*
+    retain(env, time_zone);
+    if let Some(old) = State::get(env).default_time_zone.take() {
+        release(env, old);
+    }
+    State::get(env).default_time_zone = (time_zone != nil).then_some(time_zone);
+}
++ (id)localTimeZone {
+    // This is meant to be a proxy that always forwards to the current default
+    // time zone, but it's unlikely to matter that this is a snapshot.
+    msg![env; this defaultTimeZone]
+}
+
++ (id)timeZoneWithName:(id)name { // NSString*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithName:name];
+    autorelease(env, new)
+}
++ (id)timeZoneWithAbbreviation:(id)abbreviation { // NSString*
+    let abbreviation = to_rust_string(env, abbreviation);
+    let Some(time_zone) = time_zone_for_abbreviation(&abbreviation) else {
+        log!("Unknown time zone abbreviation {:?}, returning nil", abbreviation);
+        return nil;
+    };
+    let new = from_time_zone(env, time_zone);
+    autorelease(env, new)
+}
++ (id)timeZoneForSecondsFromGMT:(NSInteger)seconds {
+    let new = from_time_zone(env, TimeZone::with_offset(seconds));
+    autorelease(env, new)
+}
+
+- (id)initWithName:(id)name { // NSString*
+    let name = to_rust_string(env, name);
+    match TimeZone::load(&name) {
+        Ok(time_zone) => {
+            env.objc.borrow_mut::<NSTimeZoneHostObject>(this).time_zone = Rc::new(time_zone);
+            this
+        }
+        Err(e) => {
+            log!("{}, returning nil", e);
+            release(env, this);
+            nil
+        }
+    }
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Time zones are immutable.
+    retain(env, this)
+}
+
+- (id)name {
+    let name = to_time_zone(env, this).name().to_string();
+    let name = from_rust_string(env, name);
+    autorelease(env, name)
+}
+
+- (id)abbreviation {
+    let date: id = msg_class![env; NSDate date];
+    msg![env; this abbreviationForDate:date]
+}
+- (id)abbreviationForDate:(id)date { // NSDate*
+    let abbreviation = local_time_type(env, this, date).abbreviation;
+    let abbreviation = from_rust_string(env, abbreviation);
+    autorelease(env, abbreviation)
+}
+
+- (NSInteger)secondsFromGMT {
+    let date: id = msg_class![env; NSDate date];
+    msg![env; this secondsFromGMTForDate:date]
+}
+- (NSInteger)secondsFromGMTForDate:(id)date { // NSDate*
+    local_time_type(env, this, date).utc_offset
+}
+
+- (bool)isDaylightSavingTime {
+    let date: id = msg_class![env; NSDate date];
+    msg![env; this isDaylightSavingTimeForDate:date]
+}
+- (bool)isDaylightSavingTimeForDate:(id)date { // NSDate*
+    local_time_type(env, this, date).is_dst
+}
+- (NSTimeInterval)daylightSavingTimeOffset {
+    let date: id = msg_class![env; NSDate date];
+    let now = local_time_type(env, this, date);
+    if !now.is_dst {
+        return 0.0;
+    }
+    // Assume the standard time offset is the one from half a year away.
+    let interval = ns_date::to_time_interval(env, date) + 182.0 * 24.0 * 60.0 * 60.0;
+    let other_date = ns_date::from_time_interval(env, interval);
+    let other = local_time_type(env, this, other_date);
+    release(env, other_date);
+    (now.utc_offset - other.utc_offset).into()
+}
+
+- (bool)isEqualToTimeZone:(id)other { // NSTimeZone*
+    if other == nil {
+        return false;
+    }
+    to_time_zone(env, this).name() == to_time_zone(env, other).name()
+}
+- (bool)isEqual:(id)other {
+    if this == other {
+        return true;
+    }
+    let class: Class = msg_class![env; NSTimeZone class];
+    if !msg![env; other isKindOfClass:class] {
+        return false;
+    }
+    msg![env; this isEqualToTimeZone:other]
+}
+- (NSUInteger)hash {
+    super::hash_helper(&to_time_zone(env, this).name())
+}
+
+- (id)description {
+    // e.g. "Europe/Stockholm (CEST) offset 7200 (Daylight)"
+    let date: id = msg_class![env; NSDate date];
+    let local_time_type = local_time_type(env, this, date);
+    let description = format!(
+        "{} ({}) offset {}{}",
+        to_time_zone(env, this).name(),
+        local_time_type.abbreviation,
+        local_time_type.utc_offset,
+        if local_time_type.is_dst { " (Daylight)" } else { "" },
+    );
+    let description = from_rust_string(env, description);
+    autorelease(env, description)
+}
+
+@end
+
+};
+
+/// Shortcut for host code: create a new `NSTimeZone` (not autoreleased).
+pub fn from_time_zone(env: &mut Environment, time_zone: TimeZone) -> id {
+    let new: id = msg_class![env; NSTimeZone alloc];
+    env.objc.borrow_mut::<NSTimeZoneHostObject>(new).time_zone = Rc::new(time_zone);
+    new
+}
+
+/// Shortcut for host code: get the rules of an `NSTimeZone`.
+pub fn to_time_zone(env: &mut Environment, time_zone: id) -> Rc<TimeZone> {
+    env.objc
+        .borrow::<NSTimeZoneHostObject>(time_zone)
+        .time_zone
+        .clone()
+}
+
+/// Shortcut for host code: get the rules of the default time zone (see
+/// `+[NSTimeZone defaultTimeZone]`).
+pub fn default_time_zone(env: &mut Environment) -> Rc<TimeZone> {
+    let time_zone: id = msg_class![env; NSTimeZone defaultTimeZone];
+    to_time_zone(env, time_zone)
+}
+
+/// Find the time zone for an abbreviation like `CET`, if it's a known one.
+pub(super) fn time_zone_for_abbreviation(abbreviation: &str) -> Option<TimeZone> {
+    let &(_, name) = ABBREVIATIONS
+        .iter()
+        .find(|&&(candidate, _)| candidate == abbreviation)?;
+    TimeZone::load(name)
+        .map_err(|e| log!("Warning: {}", e))
+        .ok()
+}
+
+fn local_time_type(env: &mut Environment, time_zone: id, date: id) -> LocalTimeType {
+    let time = ns_date::to_time_interval(env, date) + kCFAbsoluteTimeIntervalSince1970;
+    to_time_zone(env, time_zone)
+        .local_time_type(time.floor() as i64)
+        .clone()
+}
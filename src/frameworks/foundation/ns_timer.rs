@@ -9,12 +9,13 @@ use super::ns_run_loop::NSDefaultRunLoopMode;
 use super::NSTimeInterval;
 use super::{ns_run_loop, ns_string};
 use crate::objc::{
-    autorelease, id, msg, msg_class, msg_send, nil, objc_classes, release, retain, ClassExports,
-    HostObject, SEL,
+    autorelease, id, msg, msg_class, msg_send, nil, objc_classes, release, retain, AnyHostObject,
+    ClassExports, HostObject, SEL,
 };
 use crate::Environment;
 use std::time::{Duration, Instant};
 
+#[derive(Clone)]
 struct NSTimerHostObject {
     ns_interval: NSTimeInterval,
     /// Copy of `ns_interval` in Rust's type for time intervals. Keep in sync!
@@ -29,7 +30,11 @@ struct NSTimerHostObject {
     /// Weak reference
     run_loop: id,
 }
-impl HostObject for NSTimerHostObject {}
+impl HostObject for NSTimerHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -56,7 +61,7 @@ pub const CLASSES: ClassExports = objc_classes! {
         selector,
         user_info,
         repeats,
-        due_by: Some(Instant::now().checked_add(rust_interval).unwrap()),
+        due_by: Some(env.clock.now().checked_add(rust_interval).unwrap()),
         run_loop: nil,
     });
     let new = env.objc.alloc_object(this, host_object, &mut env.mem);
@@ -159,7 +164,7 @@ pub(super) fn handle_timer(env: &mut Environment, timer: id) -> Option<Instant>
     // invalidated timers should have already been removed from the run loop
     let due_by = due_by.unwrap();
 
-    let now = Instant::now();
+    let now = env.clock.now();
 
     if due_by > now {
         return Some(due_by);
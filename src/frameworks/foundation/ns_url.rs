@@ -10,13 +10,15 @@ use super::NSUInteger;
 use crate::fs::GuestPath;
 use crate::mem::MutPtr;
 use crate::objc::{
-    autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, nil, objc_classes, release, retain, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr,
 };
 use crate::Environment;
 use std::borrow::Cow;
 
 /// It seems like there's two kinds of NSURLs: ones for file paths, and others.
 /// So far only the former is implemented (TODO).
+#[derive(Clone)]
 enum NSURLHostObject {
     /// This is a file URL. The NSString is a system path (no `file:///`).
     ///
@@ -26,7 +28,11 @@ enum NSURLHostObject {
     /// Non-file URL.
     OtherURL { ns_string: id },
 }
-impl HostObject for NSURLHostObject {}
+impl HostObject for NSURLHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -5,17 +5,29 @@
  */
 //! `NSUserDefaults`.
 //!
+//! The app's defaults are saved to `Library/Preferences/<bundle id>.plist` in
+//! its sandbox, as a binary property list, just like on a real device. This
+//! means saves can be copied between touchHLE and real hardware.
+//!
 //! References:
 //! - Apple's [Preferences and Settings Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/UserDefaults/AboutPreferenceDomains/AboutPreferenceDomains.html).
 
-use super::ns_dictionary::dict_from_keys_and_objects;
-use super::ns_string;
-use crate::objc::{id, msg_class, objc_classes, ClassExports};
+use super::ns_exception::{raise, NSInvalidArgumentException};
+use super::ns_property_list_serialization::{object_to_value, value_to_object};
+use super::ns_string::{from_rust_string, to_rust_string};
+use super::NSInteger;
+use crate::fs::GuestPathBuf;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, AnyHostObject, ClassExports, HostObject,
+    NSZonePtr,
+};
 use crate::Environment;
+use plist::{Dictionary, Value};
+use std::io::Cursor;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
-    /// `NSDictionary*`
+    /// `NSUserDefaults*`
     standard_defaults: Option<id>,
 }
 impl State {
@@ -24,27 +36,284 @@ impl State {
     }
 }
 
+#[derive(Default, Clone)]
+struct NSUserDefaultsHostObject {
+    /// The app's own defaults, which are persistent.
+    app_domain: Dictionary,
+    /// Fallback values, from `registerDefaults:` and the system. These are
+    /// never saved.
+    registration_domain: Dictionary,
+    /// Where the app's defaults are saved, if anywhere.
+    path: Option<GuestPathBuf>,
+    /// Whether [Self::app_domain] has changed since it was last saved.
+    dirty: bool,
+}
+impl HostObject for NSUserDefaultsHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation NSUserDefaults: NSObject
 
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<NSUserDefaultsHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
 + (id)standardUserDefaults {
     if let Some(existing) = State::get(env).standard_defaults {
-        existing
-    } else {
-        // TODO: Are there other default keys we need to set?
-        let langs_value: id = msg_class![env; NSLocale preferredLanguages];
-        let langs_key: id = ns_string::get_static_str(env, "AppleLanguages");
-        let new = dict_from_keys_and_objects(env, &[(langs_key, langs_value)]);
-        State::get(env).standard_defaults = Some(new);
-        new
+        return existing;
+    }
+
+    let path = env.fs.home_directory().join(format!(
+        "Library/Preferences/{}.plist",
+        env.bundle.bundle_identifier()
+    ));
+    let app_domain = match env.fs.read(&path) {
+        Ok(bytes) => {
+            match Value::from_reader(Cursor::new(bytes)).ok().and_then(Value::into_dictionary) {
+                Some(dictionary) => dictionary,
+                None => {
+                    log!("Warning: Could not parse {:?}, ignoring it.", path);
+                    Dictionary::new()
+                }
+            }
+        }
+        Err(()) => Dictionary::new(),
+    };
+    log_dbg!("Loaded user defaults from {:?}: {:?}", path, app_domain);
+
+    // TODO: Are there other default keys we need to set?
+    let mut registration_domain = Dictionary::new();
+    let langs: id = msg_class![env; NSLocale preferredLanguages];
+    registration_domain.insert("AppleLanguages".to_string(), object_to_value(env, langs).unwrap());
+
+    let new: id = msg![env; this alloc];
+    *env.objc.borrow_mut(new) = NSUserDefaultsHostObject {
+        app_domain,
+        registration_domain,
+        path: Some(path),
+        dirty: false,
+    };
+    State::get(env).standard_defaults = Some(new);
+    new
+}
+
+- (())registerDefaults:(id)defaults { // NSDictionary*
+    let Some(Value::Dictionary(defaults)) = object_to_value(env, defaults) else {
+        raise(
+            env,
+            NSInvalidArgumentException,
+            format!(
+                "Attempt to register {:?}, which isn't a property list dictionary, as defaults",
+                defaults
+            ),
+        );
+    };
+    log_dbg!("[(NSUserDefaults*){:?} registerDefaults:{:?}]", this, defaults);
+    let host_object = env.objc.borrow_mut::<NSUserDefaultsHostObject>(this);
+    for (key, value) in defaults {
+        host_object.registration_domain.insert(key, value);
+    }
+}
+
+- (id)dictionaryRepresentation {
+    let host_object = env.objc.borrow::<NSUserDefaultsHostObject>(this);
+    let mut dictionary = host_object.registration_domain.clone();
+    for (key, value) in &host_object.app_domain {
+        dictionary.insert(key.clone(), value.clone());
+    }
+    let dictionary = value_to_object(env, &Value::Dictionary(dictionary));
+    autorelease(env, dictionary)
+}
+
+- (id)objectForKey:(id)key { // NSString*
+    let Some(value) = lookup(env, this, key) else {
+        return nil;
+    };
+    let object = value_to_object(env, &value);
+    autorelease(env, object)
+}
+- (id)stringForKey:(id)key { // NSString*
+    let string = match lookup(env, this, key) {
+        Some(Value::String(string)) => string,
+        // Numbers are converted, other types are not.
+        Some(Value::Integer(integer)) => integer.to_string(),
+        Some(Value::Real(real)) => real.to_string(),
+        _ => return nil,
+    };
+    let string = from_rust_string(env, string);
+    autorelease(env, string)
+}
+- (id)arrayForKey:(id)key { // NSString*
+    let Some(value @ Value::Array(_)) = lookup(env, this, key) else {
+        return nil;
+    };
+    let array = value_to_object(env, &value);
+    autorelease(env, array)
+}
+- (id)dictionaryForKey:(id)key { // NSString*
+    let Some(value @ Value::Dictionary(_)) = lookup(env, this, key) else {
+        return nil;
+    };
+    let dictionary = value_to_object(env, &value);
+    autorelease(env, dictionary)
+}
+- (id)dataForKey:(id)key { // NSString*
+    let Some(value @ Value::Data(_)) = lookup(env, this, key) else {
+        return nil;
+    };
+    let data = value_to_object(env, &value);
+    autorelease(env, data)
+}
+- (bool)boolForKey:(id)key { // NSString*
+    match lookup(env, this, key) {
+        Some(Value::String(string))
+            if string.eq_ignore_ascii_case("yes") || string.eq_ignore_ascii_case("true") =>
+        {
+            true
+        }
+        Some(value) => value_to_f64(&value) != 0.0,
+        None => false,
+    }
+}
+- (NSInteger)integerForKey:(id)key { // NSString*
+    lookup(env, this, key).map_or(0, |value| value_to_f64(&value) as NSInteger)
+}
+- (f32)floatForKey:(id)key { // NSString*
+    lookup(env, this, key).map_or(0.0, |value| value_to_f64(&value) as f32)
+}
+- (f64)doubleForKey:(id)key { // NSString*
+    lookup(env, this, key).map_or(0.0, |value| value_to_f64(&value))
+}
+
+- (())setObject:(id)object
+         forKey:(id)key { // NSString*
+    if object == nil {
+        set(env, this, key, None);
+        return;
     }
+    let Some(value) = object_to_value(env, object) else {
+        let key = to_rust_string(env, key);
+        raise(
+            env,
+            NSInvalidArgumentException,
+            format!(
+                "Attempt to insert non-property list object {:?} for key {}",
+                object, key
+            ),
+        );
+    };
+    set(env, this, key, Some(value));
+}
+- (())setBool:(bool)value
+       forKey:(id)key { // NSString*
+    set(env, this, key, Some(Value::Boolean(value)));
+}
+- (())setInteger:(NSInteger)value
+          forKey:(id)key { // NSString*
+    set(env, this, key, Some(Value::Integer((value as i64).into())));
+}
+- (())setFloat:(f32)value
+        forKey:(id)key { // NSString*
+    set(env, this, key, Some(Value::Real(value as f64)));
+}
+- (())setDouble:(f64)value
+         forKey:(id)key { // NSString*
+    set(env, this, key, Some(Value::Real(value)));
+}
+- (())removeObjectForKey:(id)key { // NSString*
+    set(env, this, key, None);
 }
 
-// TODO: plist methods etc
+- (bool)synchronize {
+    save(env, this)
+}
 
 @end
 
 };
+
+/// Look up a key in the app's defaults, falling back to the registered
+/// defaults.
+fn lookup(env: &mut Environment, defaults: id, key: id) -> Option<Value> {
+    let key = to_rust_string(env, key);
+    let host_object = env.objc.borrow::<NSUserDefaultsHostObject>(defaults);
+    host_object
+        .app_domain
+        .get(&*key)
+        .or_else(|| host_object.registration_domain.get(&*key))
+        .cloned()
+}
+
+/// Set or (if `value` is [None]) remove a key in the app's defaults.
+fn set(env: &mut Environment, defaults: id, key: id, value: Option<Value>) {
+    let key = to_rust_string(env, key).into_owned();
+    log_dbg!(
+        "Setting user default {:?} to {:?} in {:?}",
+        key,
+        value,
+        defaults
+    );
+    let host_object = env.objc.borrow_mut::<NSUserDefaultsHostObject>(defaults);
+    match value {
+        Some(value) => {
+            host_object.app_domain.insert(key, value);
+        }
+        None => {
+            host_object.app_domain.remove(&key);
+        }
+    }
+    host_object.dirty = true;
+}
+
+/// Save the app's defaults if they have changed. Returns [false] on failure.
+fn save(env: &mut Environment, defaults: id) -> bool {
+    let host_object = env.objc.borrow_mut::<NSUserDefaultsHostObject>(defaults);
+    if !host_object.dirty {
+        return true;
+    }
+    let Some(path) = host_object.path.clone() else {
+        return true;
+    };
+    let mut bytes = Vec::new();
+    Value::Dictionary(host_object.app_domain.clone())
+        .to_writer_binary(&mut bytes)
+        .unwrap();
+    if env.fs.write(&path, &bytes).is_err() {
+        log!("Warning: Could not save user defaults to {:?}.", path);
+        return false;
+    }
+    log_dbg!("Saved user defaults to {:?}.", path);
+    env.objc
+        .borrow_mut::<NSUserDefaultsHostObject>(defaults)
+        .dirty = false;
+    true
+}
+
+/// Save the standard user defaults, if they have been used. This should be
+/// called when the app exits, since apps don't always call `synchronize`.
+pub fn synchronize_standard_defaults(env: &mut Environment) {
+    if let Some(defaults) = State::get(env).standard_defaults {
+        save(env, defaults);
+    }
+}
+
+/// Numeric interpretation of a defaults value, as used by the typed getters.
+fn value_to_f64(value: &Value) -> f64 {
+    match value {
+        Value::Boolean(value) => *value as i32 as f64,
+        Value::Integer(integer) => integer.as_signed().map_or_else(
+            || integer.as_unsigned().unwrap() as f64,
+            |integer| integer as f64,
+        ),
+        Value::Real(real) => *real,
+        Value::String(string) => string.trim().parse().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
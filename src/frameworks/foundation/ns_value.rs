@@ -5,16 +5,65 @@
  */
 //! The `NSValue` class cluster, including `NSNumber`.
 
-use super::NSUInteger;
+use super::ns_string::from_rust_string;
+use super::{NSInteger, NSUInteger};
 use crate::objc::{
-    autorelease, id, msg, msg_class, objc_classes, retain, Class, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, id, msg, msg_class, objc_classes, retain, AnyHostObject, Class, ClassExports,
+    HostObject, NSZonePtr,
 };
+use crate::Environment;
 
-enum NSNumberHostObject {
+#[derive(Copy, Clone)]
+pub(super) enum NSNumberHostObject {
     Bool(bool),
+    Int(i32),
+    LongLong(i64),
+    Float(f32),
+    Double(f64),
+}
+impl HostObject for NSNumberHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+impl NSNumberHostObject {
+    fn as_i64(self) -> i64 {
+        match self {
+            NSNumberHostObject::Bool(value) => value as i64,
+            NSNumberHostObject::Int(value) => value as i64,
+            NSNumberHostObject::LongLong(value) => value,
+            NSNumberHostObject::Float(value) => value as i64,
+            NSNumberHostObject::Double(value) => value as i64,
+        }
+    }
+    fn as_f64(self) -> f64 {
+        match self {
+            NSNumberHostObject::Bool(value) => value as i64 as f64,
+            NSNumberHostObject::Int(value) => value as f64,
+            NSNumberHostObject::LongLong(value) => value as f64,
+            NSNumberHostObject::Float(value) => value as f64,
+            NSNumberHostObject::Double(value) => value,
+        }
+    }
+    fn is_float(self) -> bool {
+        matches!(
+            self,
+            NSNumberHostObject::Float(_) | NSNumberHostObject::Double(_)
+        )
+    }
+}
+
+/// Shortcut for host code: create a new `NSNumber` (not autoreleased).
+pub(super) fn from_host_object(env: &mut Environment, value: NSNumberHostObject) -> id {
+    let new: id = msg_class![env; NSNumber alloc];
+    *env.objc.borrow_mut(new) = value;
+    new
+}
+
+/// Shortcut for host code: get the value of an `NSNumber`.
+pub(super) fn to_host_object(env: &mut Environment, number: id) -> NSNumberHostObject {
+    *env.objc.borrow(number)
 }
-impl HostObject for NSNumberHostObject {}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -39,15 +88,40 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
-+ (id)numberWithBool:(bool)value {
-    // TODO: for greater efficiency we could return a static-lifetime value
+// TODO: for greater efficiency we could return static-lifetime values
 
++ (id)numberWithBool:(bool)value {
     let new: id = msg![env; this alloc];
     let new: id = msg![env; new initWithBool:value];
     autorelease(env, new)
 }
++ (id)numberWithInt:(i32)value {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithInt:value];
+    autorelease(env, new)
+}
++ (id)numberWithInteger:(NSInteger)value {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithInteger:value];
+    autorelease(env, new)
+}
++ (id)numberWithLongLong:(i64)value {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithLongLong:value];
+    autorelease(env, new)
+}
++ (id)numberWithFloat:(f32)value {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithFloat:value];
+    autorelease(env, new)
+}
++ (id)numberWithDouble:(f64)value {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithDouble:value];
+    autorelease(env, new)
+}
 
-// TODO: types other than booleans
+// TODO: unsigned types, chars and shorts
 
 - (id)initWithBool:(bool)value {
     *env.objc.borrow_mut::<NSNumberHostObject>(this) = NSNumberHostObject::Bool(
@@ -55,10 +129,56 @@ pub const CLASSES: ClassExports = objc_classes! {
     );
     this
 }
+- (id)initWithInt:(i32)value {
+    *env.objc.borrow_mut::<NSNumberHostObject>(this) = NSNumberHostObject::Int(value);
+    this
+}
+- (id)initWithInteger:(NSInteger)value {
+    *env.objc.borrow_mut::<NSNumberHostObject>(this) = NSNumberHostObject::Int(value);
+    this
+}
+- (id)initWithLongLong:(i64)value {
+    *env.objc.borrow_mut::<NSNumberHostObject>(this) = NSNumberHostObject::LongLong(
+        value,
+    );
+    this
+}
+- (id)initWithFloat:(f32)value {
+    *env.objc.borrow_mut::<NSNumberHostObject>(this) = NSNumberHostObject::Float(value);
+    this
+}
+- (id)initWithDouble:(f64)value {
+    *env.objc.borrow_mut::<NSNumberHostObject>(this) = NSNumberHostObject::Double(value);
+    this
+}
+
+- (bool)boolValue {
+    let value = to_host_object(env, this);
+    if value.is_float() {
+        value.as_f64() != 0.0
+    } else {
+        value.as_i64() != 0
+    }
+}
+- (i32)intValue {
+    to_host_object(env, this).as_i64() as i32
+}
+- (NSInteger)integerValue {
+    to_host_object(env, this).as_i64() as NSInteger
+}
+- (i64)longLongValue {
+    to_host_object(env, this).as_i64()
+}
+- (f32)floatValue {
+    to_host_object(env, this).as_f64() as f32
+}
+- (f64)doubleValue {
+    to_host_object(env, this).as_f64()
+}
 
 - (NSUInteger)hash {
-    let &NSNumberHostObject::Bool(value) = env.objc.borrow(this);
-    super::hash_helper(&value)
+    // Equal numbers must have equal hashes regardless of their types.
+    super::hash_helper(&to_host_object(env, this).as_f64().to_bits())
 }
 - (bool)isEqualTo:(id)other {
     if this == other {
@@ -68,12 +188,28 @@ pub const CLASSES: ClassExports = objc_classes! {
     if !msg![env; other isKindOfClass:class] {
         return false;
     }
-    let &NSNumberHostObject::Bool(a) = env.objc.borrow(this);
-    let &NSNumberHostObject::Bool(b) = env.objc.borrow(other);
-    a == b
+    let a = to_host_object(env, this);
+    let b = to_host_object(env, other);
+    if a.is_float() || b.is_float() {
+        a.as_f64() == b.as_f64()
+    } else {
+        a.as_i64() == b.as_i64()
+    }
+}
+
+- (id)description {
+    let description = match to_host_object(env, this) {
+        NSNumberHostObject::Bool(value) => (value as i32).to_string(),
+        NSNumberHostObject::Int(value) => value.to_string(),
+        NSNumberHostObject::LongLong(value) => value.to_string(),
+        NSNumberHostObject::Float(value) => value.to_string(),
+        NSNumberHostObject::Double(value) => value.to_string(),
+    };
+    let description = from_rust_string(env, description);
+    autorelease(env, description)
 }
 
-// TODO: accessors etc
+// TODO: more accessors etc
 
 @end
 
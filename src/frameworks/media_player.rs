@@ -7,7 +7,7 @@
 
 pub mod movie_player;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     movie_player: movie_player::State,
 }
@@ -11,7 +11,7 @@ use crate::objc::{id, msg, msg_class, objc_classes, release, retain, ClassExport
 use crate::Environment;
 use std::collections::VecDeque;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     active_player: Option<id>,
     /// Various apps (e.g. Crash Bandicoot Nitro Kart 3D and Spore Origins)
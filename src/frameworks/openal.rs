@@ -23,11 +23,27 @@ use std::collections::HashMap;
 pub struct State {
     devices: HashMap<MutPtr<GuestALCdevice>, *mut ALCdevice>,
     contexts: HashMap<MutPtr<GuestALCcontext>, *mut ALCcontext>,
+    /// Listener gain (`AL_GAIN`) set by the app for each context, if it has
+    /// set one. This is needed to restore it when unmuting.
+    listener_gains: HashMap<*mut ALCcontext, ALfloat>,
+    /// See [set_muted].
+    muted: bool,
 }
 impl State {
     fn get(env: &mut Environment) -> &mut Self {
         &mut env.framework_state.openal
     }
+
+    /// For use by [crate::snapshot]: OpenAL devices and contexts are host
+    /// resources that can't be copied yet, so save states are refused while
+    /// the app has any.
+    pub fn check_snapshot_supported(&self) -> Result<(), String> {
+        if self.devices.is_empty() && self.contexts.is_empty() {
+            Ok(())
+        } else {
+            Err("the app is using OpenAL, and audio isn't supported yet".to_string())
+        }
+    }
 }
 
 /// Opaque type in guest memory standing in for [ALCdevice] in host memory.
@@ -91,6 +107,10 @@ fn alcCreateContext(
         return Ptr::null();
     }
 
+    if State::get(env).muted {
+        unsafe { set_listener_gain(res, 0.0) };
+    }
+
     let guest_res = env.mem.alloc_and_write(GuestALCcontext { _filler: 0 });
     State::get(env).contexts.insert(guest_res, res);
     log_dbg!(
@@ -103,6 +123,7 @@ fn alcCreateContext(
 }
 fn alcDestroyContext(env: &mut Environment, context: MutPtr<GuestALCcontext>) {
     let host_context = State::get(env).contexts.remove(&context).unwrap();
+    State::get(env).listener_gains.remove(&host_context);
     env.mem.free(context.cast());
     unsafe { al::alcDestroyContext(host_context) };
     log_dbg!("alcDestroyContext({:?})", context);
@@ -164,7 +185,15 @@ fn alDistanceModel(_env: &mut Environment, value: ALenum) {
     unsafe { al::alDistanceModel(value) };
 }
 
-fn alListenerf(_env: &mut Environment, param: ALenum, value: ALfloat) {
+fn alListenerf(env: &mut Environment, param: ALenum, value: ALfloat) {
+    if param == al::AL_GAIN {
+        let state = State::get(env);
+        let host_context = unsafe { al::alcGetCurrentContext() };
+        state.listener_gains.insert(host_context, value);
+        if state.muted {
+            return;
+        }
+    }
     unsafe { al::alListenerf(param, value) };
 }
 fn alListener3f(
@@ -306,6 +335,35 @@ fn alBufferDataStatic(
     alBufferData(env, buffer, format, data, size, samplerate);
 }
 
+/// Mute or unmute all of the app's OpenAL contexts. The app's own listener
+/// gain is preserved. See [crate::frameworks::set_audio_muted].
+pub fn set_muted(env: &mut Environment, muted: bool) {
+    let state = State::get(env);
+    if state.muted == muted {
+        return;
+    }
+    state.muted = muted;
+    for &host_context in state.contexts.values() {
+        let gain = match muted {
+            true => 0.0,
+            false => state
+                .listener_gains
+                .get(&host_context)
+                .copied()
+                .unwrap_or(1.0),
+        };
+        unsafe { set_listener_gain(host_context, gain) };
+    }
+}
+
+/// Set the listener gain of a context that might not be current.
+unsafe fn set_listener_gain(host_context: *mut ALCcontext, gain: ALfloat) {
+    let old_context = al::alcGetCurrentContext();
+    assert!(al::alcMakeContextCurrent(host_context) == al::ALC_TRUE);
+    al::alListenerf(al::AL_GAIN, gain);
+    assert!(al::alcMakeContextCurrent(old_context) == al::ALC_TRUE);
+}
+
 // Apple-specific extension to OpenAL
 fn alcMacOSXMixerOutputRate(_env: &mut Environment, value: ALdouble) {
     log!("App wants to set mixer output sample rate to {} Hz", value);
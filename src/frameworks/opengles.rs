@@ -13,6 +13,7 @@
 //!   - `gles1_native` passes through native OpenGL ES 1.1.
 //!   - `gles1_on_gl2` provides an implementation of OpenGL ES 1.1 using OpenGL
 //!     2.1 compatibility profile.
+//!   - `gles_null` discards everything, for use in headless mode.
 //!   - There might be more in future.
 //!
 //! Useful resources for OpenGL ES 1.1:
@@ -39,12 +40,14 @@ mod gles1_native;
 mod gles1_on_gl2;
 mod gles_generic;
 mod gles_guest;
+mod gles_null;
 mod util;
 
 use gles1_native::GLES1Native;
 use gles1_on_gl2::GLES1OnGL2;
-use gles_generic::GLES;
+pub use gles_generic::GLES;
 pub use gles_guest::FUNCTIONS;
+use gles_null::GLES1Null;
 
 /// Labels for [GLES] implementations and an abstraction for constructing them.
 #[derive(Copy, Clone)]
@@ -53,6 +56,9 @@ pub enum GLESImplementation {
     GLES1Native,
     /// [GLES1OnGL2].
     GLES1OnGL2,
+    /// [GLES1Null]. This is only used in headless mode and is deliberately not
+    /// in [Self::GLES1_IMPLEMENTATIONS].
+    GLES1Null,
 }
 impl GLESImplementation {
     /// List of OpenGL ES 1.1 implementations in order of preference.
@@ -71,6 +77,7 @@ impl GLESImplementation {
         match self {
             Self::GLES1Native => GLES1Native::description(),
             Self::GLES1OnGL2 => GLES1OnGL2::description(),
+            Self::GLES1Null => GLES1Null::description(),
         }
     }
     /// See [GLES::new].
@@ -81,11 +88,44 @@ impl GLESImplementation {
         match self {
             Self::GLES1Native => GLES1Native::new(window).map(boxer),
             Self::GLES1OnGL2 => GLES1OnGL2::new(window).map(boxer),
+            Self::GLES1Null => GLES1Null::new(window).map(boxer),
         }
     }
 }
 
-#[derive(Default)]
+/// Create an OpenGL ES 1.1 context, using the first implementation that works
+/// (or the one chosen by the user), see [GLESImplementation].
+///
+/// The new context might be made current, so if this isn't done on behalf of
+/// the app, the caller has to make sure the app's context gets restored.
+pub fn create_gles1_ctx(
+    window: &mut crate::window::Window,
+    options: &crate::options::Options,
+) -> Box<dyn GLES> {
+    log!("Creating an OpenGL ES 1.1 context:");
+    let list = if options.headless {
+        std::slice::from_ref(&GLESImplementation::GLES1Null)
+    } else if let Some(ref preference) = options.gles1_implementation {
+        std::slice::from_ref(preference)
+    } else {
+        GLESImplementation::GLES1_IMPLEMENTATIONS
+    };
+    for implementation in list {
+        log!("Trying: {}", implementation.description());
+        match implementation.construct(window) {
+            Ok(ctx) => {
+                log!("=> Success!");
+                return ctx;
+            }
+            Err(err) => {
+                log!("=> Failed: {}.", err);
+            }
+        }
+    }
+    panic!("Couldn't create OpenGL ES 1.1 context!");
+}
+
+#[derive(Default, Clone)]
 pub struct State {
     /// Current EAGLContext for each thread
     current_ctxs: std::collections::HashMap<crate::ThreadID, Option<crate::objc::id>>,
@@ -5,13 +5,16 @@
  */
 //! EAGL.
 
-use super::{GLESImplementation, GLES};
+use super::GLES;
 use crate::dyld::{ConstantExports, HostConstant};
+use crate::frameworks::core_animation::{ca_eagl_layer, composition};
 use crate::frameworks::foundation::ns_string::get_static_str;
 use crate::frameworks::foundation::NSUInteger;
 use crate::objc::{id, msg, nil, objc_classes, release, retain, ClassExports, HostObject};
 use crate::window::gles11;
+use crate::window::gles11::types::*;
 use crate::window::{Matrix, Window};
+use std::collections::HashMap;
 
 // These are used by the EAGLDrawable protocol implemented by CAEAGLayer.
 // Since these have the ABI of constant symbols rather than literal constants,
@@ -49,6 +52,9 @@ const kEAGLRenderingAPIOpenGLES3: EAGLRenderingAPI = 3;
 
 pub(super) struct EAGLContextHostObject {
     pub(super) gles_ctx: Option<Box<dyn GLES>>,
+    /// The drawable (`CAEAGLLayer*`) each renderbuffer's storage came from.
+    /// These are strong references.
+    renderbuffer_drawables: HashMap<GLuint, id>,
 }
 impl HostObject for EAGLContextHostObject {}
 
@@ -59,7 +65,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 @implementation EAGLContext: NSObject
 
 + (id)alloc {
-    let host_object = Box::new(EAGLContextHostObject { gles_ctx: None });
+    let host_object = Box::new(EAGLContextHostObject {
+        gles_ctx: None,
+        renderbuffer_drawables: HashMap::new(),
+    });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
@@ -95,27 +104,7 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (id)initWithAPI:(EAGLRenderingAPI)api {
     assert!(api == kEAGLRenderingAPIOpenGLES1);
 
-    log!("Creating an OpenGL ES 1.1 context:");
-    let list = if let Some(ref preference) = env.options.gles1_implementation {
-        std::slice::from_ref(preference)
-    } else {
-        GLESImplementation::GLES1_IMPLEMENTATIONS
-    };
-    let mut gles1_ctx = None;
-    for implementation in list {
-        log!("Trying: {}", implementation.description());
-        match implementation.construct(&mut env.window) {
-            Ok(ctx) => {
-                log!("=> Success!");
-                gles1_ctx = Some(ctx);
-                break;
-            },
-            Err(err) => {
-                log!("=> Failed: {}.", err);
-            }
-        }
-    }
-    let gles1_ctx = gles1_ctx.expect("Couldn't create OpenGL ES 1.1 context!");
+    let gles1_ctx = super::create_gles1_ctx(&mut env.window, &env.options);
 
     // Make the context current so we can get driver info from it.
     // initWithAPI: is not supposed to make the new context current (the app
@@ -126,13 +115,20 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.framework_state.opengles.current_ctx_thread = None;
     log!("Driver info: {}", unsafe { gles1_ctx.driver_description() });
 
-    *env.objc.borrow_mut(this) = EAGLContextHostObject {
-        gles_ctx: Some(gles1_ctx),
-    };
+    env.objc.borrow_mut::<EAGLContextHostObject>(this).gles_ctx = Some(gles1_ctx);
 
     this
 }
 
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<EAGLContextHostObject>(this);
+    let drawables = std::mem::take(&mut host_obj.renderbuffer_drawables);
+    for (_, drawable) in drawables {
+        release(env, drawable);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
 - (bool)renderbufferStorage:(NSUInteger)target
                fromDrawable:(id)drawable { // EAGLDrawable (always CAEAGLayer*)
     assert!(target == gles11::RENDERBUFFER_OES);
@@ -163,8 +159,17 @@ pub const CLASSES: ClassExports = objc_classes! {
     // Unclear from documentation if this method requires an appropriate context
     // to already be active, but that seems to be the case in practice?
     let gles = super::sync_context(&mut env.framework_state.opengles, &mut env.objc, &mut env.window, env.current_thread);
-    unsafe {
-        gles.RenderbufferStorageOES(target, internalformat, width.try_into().unwrap(), height.try_into().unwrap())
+    let renderbuffer: GLuint = unsafe {
+        gles.RenderbufferStorageOES(target, internalformat, width.try_into().unwrap(), height.try_into().unwrap());
+        get_int(gles, gles11::RENDERBUFFER_BINDING_OES) as _
+    };
+
+    // Remember the drawable so the frames presented from this renderbuffer can
+    // be composited with the right layer.
+    retain(env, drawable);
+    let old = env.objc.borrow_mut::<EAGLContextHostObject>(this).renderbuffer_drawables.insert(renderbuffer, drawable);
+    if let Some(old) = old {
+        release(env, old);
     }
 
     true
@@ -176,9 +181,24 @@ pub const CLASSES: ClassExports = objc_classes! {
     // Unclear from documentation if this method requires an appropriate context
     // to already be active, but that seems to be the case in practice?
     let gles = super::sync_context(&mut env.framework_state.opengles, &mut env.objc, &mut env.window, env.current_thread);
+    let renderbuffer: GLuint = unsafe { get_int(gles, gles11::RENDERBUFFER_BINDING_OES) as _ };
+    let layer = env.objc.borrow::<EAGLContextHostObject>(this).renderbuffer_drawables.get(&renderbuffer).copied();
+
+    // If there's more than just this layer on screen, the frame has to be
+    // composited with the other layers rather than being presented directly.
+    if let Some(layer) = layer.filter(|_| composition::compositor_in_use(env)) {
+        let gles = super::sync_context(&mut env.framework_state.opengles, &mut env.objc, &mut env.window, env.current_thread);
+        let (pixels, dimensions) = unsafe { read_renderbuffer(gles) };
+        ca_eagl_layer::set_presented_pixels(env, layer, pixels, dimensions);
+        return true;
+    }
+
+    let guest_ticks = env.cpu.elapsed_ticks();
+    let gles = super::sync_context(&mut env.framework_state.opengles, &mut env.objc, &mut env.window, env.current_thread);
     unsafe {
-        present_renderbuffer(gles, &mut env.window);
+        present_renderbuffer(gles, &mut env.window, guest_ticks);
     }
+    env.clock.frame_presented();
 
     true
 }
@@ -187,45 +207,35 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
-/// Copies the renderbuffer provided by the app to the window's framebuffer,
-/// rotated if necessary, and presents that framebuffer.
-unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
-    use gles11::types::*;
-
-    // These helper functions make the state backup code easier to read, but
-    // more importantly, they make it free of mutable variables that wouldn't
-    // get caught by Rust's unused variable warnings, which are useful to check
-    // we actually restore the stuff we back up.
-
-    unsafe fn get_ptr(gles: &mut dyn GLES, pname: GLenum) -> *const GLvoid {
-        let mut ptr = std::ptr::null();
-        gles.GetPointerv(pname, &mut ptr);
-        ptr
-    }
-    // Safety: caller's responsibility to use appropriate N.
-    unsafe fn get_ints<const N: usize>(gles: &mut dyn GLES, pname: GLenum) -> [GLint; N] {
-        let mut res = [0; N];
-        gles.GetIntegerv(pname, res.as_mut_ptr());
-        res
-    }
-    // Safety: caller's responsibility to only use this for scalars.
-    unsafe fn get_int(gles: &mut dyn GLES, pname: GLenum) -> GLint {
-        get_ints::<1>(gles, pname)[0]
-    }
-    // Safety: caller's responsibility to use appropriate N.
-    unsafe fn get_floats<const N: usize>(gles: &mut dyn GLES, pname: GLenum) -> [GLfloat; N] {
-        let mut res = [0.0; N];
-        gles.GetFloatv(pname, res.as_mut_ptr());
-        res
-    }
+// These helper functions make the state backup code easier to read, but more
+// importantly, they make it free of mutable variables that wouldn't get caught
+// by Rust's unused variable warnings, which are useful to check we actually
+// restore the stuff we back up.
 
-    // We can't directly copy the content of the renderbuffer to the default
-    // framebuffer (the window), but if we attach it to a framebuffer object, we
-    // can use glCopyTexImage2D() to copy it to a texture, which we can then
-    // draw to the default framebuffer via a textured quad, which can be
-    // rotated, scaled or letterboxed as appropriate.
+unsafe fn get_ptr(gles: &mut dyn GLES, pname: GLenum) -> *const GLvoid {
+    let mut ptr = std::ptr::null();
+    gles.GetPointerv(pname, &mut ptr);
+    ptr
+}
+// Safety: caller's responsibility to use appropriate N.
+unsafe fn get_ints<const N: usize>(gles: &mut dyn GLES, pname: GLenum) -> [GLint; N] {
+    let mut res = [0; N];
+    gles.GetIntegerv(pname, res.as_mut_ptr());
+    res
+}
+// Safety: caller's responsibility to only use this for scalars.
+unsafe fn get_int(gles: &mut dyn GLES, pname: GLenum) -> GLint {
+    get_ints::<1>(gles, pname)[0]
+}
+// Safety: caller's responsibility to use appropriate N.
+unsafe fn get_floats<const N: usize>(gles: &mut dyn GLES, pname: GLenum) -> [GLfloat; N] {
+    let mut res = [0.0; N];
+    gles.GetFloatv(pname, res.as_mut_ptr());
+    res
+}
 
-    let renderbuffer: GLuint = get_int(gles, gles11::RENDERBUFFER_BINDING_OES) as _;
+/// Gets the size of the currently bound renderbuffer.
+unsafe fn get_renderbuffer_size(gles: &mut dyn GLES) -> (GLint, GLint) {
     let mut width: GLint = 0;
     let mut height: GLint = 0;
     gles.GetRenderbufferParameterivOES(
@@ -238,6 +248,184 @@ unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
         gles11::RENDERBUFFER_HEIGHT_OES,
         &mut height,
     );
+    (width, height)
+}
+
+/// Reads the pixels of the currently bound framebuffer, as 8 bits per channel
+/// RGBA with rows in top-to-bottom order.
+unsafe fn read_pixels(gles: &mut dyn GLES, width: GLint, height: GLint) -> Vec<u8> {
+    let old_pack_alignment: GLint = get_int(gles, gles11::PACK_ALIGNMENT);
+    gles.PixelStorei(gles11::PACK_ALIGNMENT, 1);
+    let row_size = width as usize * 4;
+    let mut pixels = vec![0u8; row_size * height as usize];
+    gles.ReadPixels(
+        0,
+        0,
+        width,
+        height,
+        gles11::RGBA,
+        gles11::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut GLvoid,
+    );
+    gles.PixelStorei(gles11::PACK_ALIGNMENT, old_pack_alignment);
+    // OpenGL's rows are bottom-to-top. The alpha channel is meaningless
+    // because the layer is displayed as opaque, see the CopyTexImage2D
+    // call in present_renderbuffer.
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks_exact(row_size).rev() {
+        flipped.extend_from_slice(row);
+    }
+    for pixel in flipped.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    flipped
+}
+
+/// Copies the content of the currently bound renderbuffer so it can be
+/// composited with other layers (see [composition]). The pixels are in the
+/// same format as for [read_pixels].
+unsafe fn read_renderbuffer(gles: &mut dyn GLES) -> (Vec<u8>, (u32, u32)) {
+    let renderbuffer: GLuint = get_int(gles, gles11::RENDERBUFFER_BINDING_OES) as _;
+    let (width, height) = get_renderbuffer_size(gles);
+
+    let old_framebuffer: GLuint = get_int(gles, gles11::FRAMEBUFFER_BINDING_OES) as _;
+
+    let mut src_framebuffer = 0;
+    gles.GenFramebuffersOES(1, &mut src_framebuffer);
+    gles.BindFramebufferOES(gles11::FRAMEBUFFER_OES, src_framebuffer);
+    gles.FramebufferRenderbufferOES(
+        gles11::FRAMEBUFFER_OES,
+        gles11::COLOR_ATTACHMENT0_OES,
+        gles11::RENDERBUFFER_OES,
+        renderbuffer,
+    );
+    let pixels = read_pixels(gles, width, height);
+    gles.DeleteFramebuffersOES(1, &src_framebuffer);
+
+    gles.BindFramebufferOES(gles11::FRAMEBUFFER_OES, old_framebuffer);
+
+    (pixels, (width as u32, height as u32))
+}
+
+/// Presents a frame composited by touchHLE (see [composition]). This must only
+/// be used with a context that doesn't belong to the app, because it doesn't
+/// restore any state. `pixels` must be 8 bits per channel RGBA with rows in
+/// top-to-bottom order.
+pub unsafe fn present_pixels(
+    gles: &mut dyn GLES,
+    window: &mut Window,
+    pixels: &[u8],
+    dimensions: (u32, u32),
+) {
+    let (width, height) = dimensions;
+
+    // OpenGL's rows are bottom-to-top.
+    let row_size = width as usize * 4;
+    let mut flipped = Vec::with_capacity(pixels.len());
+    for row in pixels.chunks_exact(row_size).rev() {
+        flipped.extend_from_slice(row);
+    }
+
+    let mut texture: GLuint = 0;
+    gles.GenTextures(1, &mut texture);
+    gles.BindTexture(gles11::TEXTURE_2D, texture);
+    gles.TexImage2D(
+        gles11::TEXTURE_2D,
+        0,
+        gles11::RGBA as _,
+        width as _,
+        height as _,
+        0,
+        gles11::RGBA,
+        gles11::UNSIGNED_BYTE,
+        flipped.as_ptr() as *const GLvoid,
+    );
+    gles.TexParameteri(
+        gles11::TEXTURE_2D,
+        gles11::TEXTURE_MIN_FILTER,
+        gles11::LINEAR as _,
+    );
+
+    // Drawing the virtual cursors leaves some state behind.
+    for mode in [gles11::MODELVIEW, gles11::PROJECTION] {
+        gles.MatrixMode(mode);
+        gles.LoadIdentity();
+    }
+    gles.Disable(gles11::BLEND);
+    gles.Color4f(1.0, 1.0, 1.0, 1.0);
+
+    draw_to_window(gles, window);
+
+    gles.DeleteTextures(1, &texture);
+
+    window.swap_window();
+}
+
+/// Draws the currently bound texture to the window, rotated, scaled or
+/// letterboxed as appropriate, along with any virtual cursors. Most state that
+/// could affect this must be reset beforehand.
+unsafe fn draw_to_window(gles: &mut dyn GLES, window: &Window) {
+    let viewport = window.viewport();
+    gles.Viewport(
+        viewport.0 as _,
+        viewport.1 as _,
+        viewport.2 as _,
+        viewport.3 as _,
+    );
+    gles.ClearColor(0.0, 0.0, 0.0, 1.0);
+    gles.Clear(gles11::COLOR_BUFFER_BIT | gles11::DEPTH_BUFFER_BIT | gles11::STENCIL_BUFFER_BIT);
+    gles.BindBuffer(gles11::ARRAY_BUFFER, 0);
+    let vertices: [f32; 12] = [
+        -1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0,
+    ];
+    gles.EnableClientState(gles11::VERTEX_ARRAY);
+    gles.VertexPointer(2, gles11::FLOAT, 0, vertices.as_ptr() as *const GLvoid);
+    let tex_coords: [f32; 12] = [0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+    gles.EnableClientState(gles11::TEXTURE_COORD_ARRAY);
+    gles.TexCoordPointer(2, gles11::FLOAT, 0, tex_coords.as_ptr() as *const GLvoid);
+    let matrix = Matrix::<4>::from(&window.output_rotation_matrix());
+    gles.MatrixMode(gles11::TEXTURE);
+    gles.LoadMatrixf(matrix.columns().as_ptr() as *const _);
+    gles.Enable(gles11::TEXTURE_2D);
+    gles.DrawArrays(gles11::TRIANGLES, 0, 6);
+
+    // Display virtual cursors
+    for (x, y, pressed) in window.virtual_cursors_visible_at() {
+        let (vx, vy, vw, vh) = viewport;
+        let x = x - vx as f32;
+        let y = y - vy as f32;
+
+        gles.DisableClientState(gles11::TEXTURE_COORD_ARRAY);
+        gles.Disable(gles11::TEXTURE_2D);
+
+        gles.Enable(gles11::BLEND);
+        gles.BlendFunc(gles11::ONE, gles11::ONE_MINUS_SRC_ALPHA);
+        gles.Color4f(0.0, 0.0, 0.0, if pressed { 2.0 / 3.0 } else { 1.0 / 3.0 });
+
+        let radius = 10.0;
+
+        let mut vertices = vertices;
+        for i in (0..vertices.len()).step_by(2) {
+            vertices[i] = (vertices[i] * radius + x) / (vw as f32 / 2.0) - 1.0;
+            vertices[i + 1] = 1.0 - (vertices[i + 1] * radius + y) / (vh as f32 / 2.0);
+        }
+        gles.VertexPointer(2, gles11::FLOAT, 0, vertices.as_ptr() as *const GLvoid);
+        gles.DrawArrays(gles11::TRIANGLES, 0, 6);
+    }
+}
+
+/// Copies the renderbuffer provided by the app to the window's framebuffer,
+/// rotated if necessary, and presents that framebuffer. The renderbuffer is
+/// also captured if the window wants it (see [Window::frame_presented]).
+unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window, guest_ticks: u64) {
+    // We can't directly copy the content of the renderbuffer to the default
+    // framebuffer (the window), but if we attach it to a framebuffer object, we
+    // can use glCopyTexImage2D() to copy it to a texture, which we can then
+    // draw to the default framebuffer via a textured quad, which can be
+    // rotated, scaled or letterboxed as appropriate.
+
+    let renderbuffer: GLuint = get_int(gles, gles11::RENDERBUFFER_BINDING_OES) as _;
+    let (width, height) = get_renderbuffer_size(gles);
 
     // To avoid confusing the guest app, we need to be able to undo any
     // state changes we make.
@@ -277,6 +465,12 @@ unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
         gles11::LINEAR as _,
     );
 
+    // Capture the frame for a screenshot or --dump-frames=, if needed. This
+    // has to happen while the renderbuffer is bound for reading.
+    window.frame_presented(guest_ticks, (width as u32, height as u32), || {
+        read_pixels(gles, width, height)
+    });
+
     // Clean up the framebuffer object since we no longer need it.
     // This also sets the framebuffer bindings back to zero, so rendering
     // will go to the default framebuffer (the window).
@@ -339,54 +533,7 @@ unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
     let old_blend_sfactor: GLenum = get_int(gles, gles11::BLEND_SRC) as _;
     let old_blend_dfactor: GLenum = get_int(gles, gles11::BLEND_DST) as _;
 
-    // Draw the quad
-    let viewport = window.viewport();
-    gles.Viewport(
-        viewport.0 as _,
-        viewport.1 as _,
-        viewport.2 as _,
-        viewport.3 as _,
-    );
-    gles.ClearColor(0.0, 0.0, 0.0, 1.0);
-    gles.Clear(gles11::COLOR_BUFFER_BIT | gles11::DEPTH_BUFFER_BIT | gles11::STENCIL_BUFFER_BIT);
-    gles.BindBuffer(gles11::ARRAY_BUFFER, 0);
-    let vertices: [f32; 12] = [
-        -1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0,
-    ];
-    gles.EnableClientState(gles11::VERTEX_ARRAY);
-    gles.VertexPointer(2, gles11::FLOAT, 0, vertices.as_ptr() as *const GLvoid);
-    let tex_coords: [f32; 12] = [0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
-    gles.EnableClientState(gles11::TEXTURE_COORD_ARRAY);
-    gles.TexCoordPointer(2, gles11::FLOAT, 0, tex_coords.as_ptr() as *const GLvoid);
-    let matrix = Matrix::<4>::from(&window.output_rotation_matrix());
-    gles.MatrixMode(gles11::TEXTURE);
-    gles.LoadMatrixf(matrix.columns().as_ptr() as *const _);
-    gles.Enable(gles11::TEXTURE_2D);
-    gles.DrawArrays(gles11::TRIANGLES, 0, 6);
-
-    // Display virtual cursor
-    if let Some((x, y, pressed)) = window.virtual_cursor_visible_at() {
-        let (vx, vy, vw, vh) = viewport;
-        let x = x - vx as f32;
-        let y = y - vy as f32;
-
-        gles.DisableClientState(gles11::TEXTURE_COORD_ARRAY);
-        gles.Disable(gles11::TEXTURE_2D);
-
-        gles.Enable(gles11::BLEND);
-        gles.BlendFunc(gles11::ONE, gles11::ONE_MINUS_SRC_ALPHA);
-        gles.Color4f(0.0, 0.0, 0.0, if pressed { 2.0 / 3.0 } else { 1.0 / 3.0 });
-
-        let radius = 10.0;
-
-        let mut vertices = vertices;
-        for i in (0..vertices.len()).step_by(2) {
-            vertices[i] = (vertices[i] * radius + x) / (vw as f32 / 2.0) - 1.0;
-            vertices[i + 1] = 1.0 - (vertices[i + 1] * radius + y) / (vh as f32 / 2.0);
-        }
-        gles.VertexPointer(2, gles11::FLOAT, 0, vertices.as_ptr() as *const GLvoid);
-        gles.DrawArrays(gles11::TRIANGLES, 0, 6);
-    }
+    draw_to_window(gles, window);
 
     // Clean up the texture
     gles.DeleteTextures(1, &texture);
@@ -272,6 +272,20 @@ impl GLES for GLES1Native {
         gles11::ClearStencil(s)
     }
 
+    // Reading pixels
+    unsafe fn ReadPixels(
+        &mut self,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *mut GLvoid,
+    ) {
+        gles11::ReadPixels(x, y, width, height, format, type_, pixels)
+    }
+
     // Textures
     unsafe fn PixelStorei(&mut self, pname: GLenum, param: GLint) {
         gles11::PixelStorei(pname, param)
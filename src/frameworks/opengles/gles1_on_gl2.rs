@@ -1031,6 +1031,22 @@ impl GLES for GLES1OnGL2 {
         gl21::ClearStencil(s)
     }
 
+    // Reading pixels
+    unsafe fn ReadPixels(
+        &mut self,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *mut GLvoid,
+    ) {
+        // This is the only combination OpenGL ES 1.1 guarantees support for.
+        assert!(format == gl21::RGBA && type_ == gl21::UNSIGNED_BYTE);
+        gl21::ReadPixels(x, y, width, height, format, type_, pixels)
+    }
+
     // Textures
     unsafe fn PixelStorei(&mut self, pname: GLenum, param: GLint) {
         assert!(pname == gl21::PACK_ALIGNMENT || pname == gl21::UNPACK_ALIGNMENT);
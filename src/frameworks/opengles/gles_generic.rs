@@ -149,6 +149,18 @@ pub trait GLES {
     unsafe fn ClearDepthx(&mut self, depth: GLclampx);
     unsafe fn ClearStencil(&mut self, s: GLint);
 
+    // Reading pixels
+    unsafe fn ReadPixels(
+        &mut self,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *mut GLvoid,
+    );
+
     // Textures
     unsafe fn PixelStorei(&mut self, pname: GLenum, param: GLint);
     unsafe fn GenTextures(&mut self, n: GLsizei, textures: *mut GLuint);
@@ -14,7 +14,7 @@
 
 use super::GLES;
 use crate::dyld::{export_c_func, FunctionExports};
-use crate::mem::{ConstPtr, ConstVoidPtr, GuestUSize, Mem, MutPtr};
+use crate::mem::{ConstPtr, ConstVoidPtr, GuestUSize, Mem, MutPtr, MutVoidPtr};
 use crate::window::gles11;
 use crate::window::gles11::types::*;
 use crate::Environment;
@@ -392,6 +392,29 @@ fn glClearStencil(env: &mut Environment, s: GLint) {
     with_ctx_and_mem(env, |gles, _mem| unsafe { gles.ClearStencil(s) });
 }
 
+// Reading pixels
+fn glReadPixels(
+    env: &mut Environment,
+    x: GLint,
+    y: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    type_: GLenum,
+    pixels: MutVoidPtr,
+) {
+    with_ctx_and_mem(env, |gles, mem| unsafe {
+        // This is the only combination OpenGL ES 1.1 guarantees support for.
+        assert!(format == gles11::RGBA && type_ == gles11::UNSIGNED_BYTE);
+        let pixel_count: GuestUSize = width.checked_mul(height).unwrap().try_into().unwrap();
+        // This is approximate, it doesn't account for alignment.
+        let pixels = mem
+            .ptr_at_mut(pixels.cast::<u8>(), pixel_count * 4)
+            .cast::<GLvoid>();
+        gles.ReadPixels(x, y, width, height, format, type_, pixels)
+    })
+}
+
 // Matrix stack operations
 fn glMatrixMode(env: &mut Environment, mode: GLenum) {
     with_ctx_and_mem(env, |gles, _mem| {
@@ -850,6 +873,8 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(glClearDepthf(_)),
     export_c_func!(glClearDepthx(_)),
     export_c_func!(glClearStencil(_)),
+    // Reading pixels
+    export_c_func!(glReadPixels(_, _, _, _, _, _, _)),
     // Matrix stack operations
     export_c_func!(glMatrixMode(_)),
     export_c_func!(glLoadIdentity()),
@@ -0,0 +1,404 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Null implementation of OpenGL ES 1.1, used in headless mode.
+//!
+//! Every call is accepted and nothing is drawn. Just enough state is tracked
+//! (object names, bindings, renderbuffer sizes) that typical apps' setup code
+//! sees plausible results and doesn't bail out.
+
+use super::GLES;
+use crate::window::gles11;
+use crate::window::gles11::types::*;
+use crate::window::Window;
+use std::collections::HashMap;
+
+pub struct GLES1Null {
+    /// Next name to hand out from any of the `Gen*` functions. Names are never
+    /// reused, which is allowed by the specification.
+    next_name: GLuint,
+    bound_framebuffer: GLuint,
+    bound_renderbuffer: GLuint,
+    bound_texture_2d: GLuint,
+    renderbuffer_sizes: HashMap<GLuint, (GLsizei, GLsizei)>,
+}
+impl GLES1Null {
+    unsafe fn gen_names(&mut self, n: GLsizei, names: *mut GLuint) {
+        for i in 0..n.max(0) as usize {
+            names.add(i).write(self.next_name);
+            self.next_name += 1;
+        }
+    }
+}
+impl GLES for GLES1Null {
+    fn description() -> &'static str {
+        "Null OpenGL ES 1.1 (headless mode, nothing is drawn)"
+    }
+
+    fn new(_window: &mut Window) -> Result<Self, String> {
+        Ok(Self {
+            next_name: 1,
+            bound_framebuffer: 0,
+            bound_renderbuffer: 0,
+            bound_texture_2d: 0,
+            renderbuffer_sizes: HashMap::new(),
+        })
+    }
+
+    fn make_current(&self, _window: &mut Window) {}
+
+    unsafe fn driver_description(&self) -> String {
+        "touchHLE null renderer".to_string()
+    }
+
+    // Generic state manipulation
+    unsafe fn GetError(&mut self) -> GLenum {
+        gles11::NO_ERROR
+    }
+    unsafe fn Enable(&mut self, _cap: GLenum) {}
+    unsafe fn Disable(&mut self, _cap: GLenum) {}
+    unsafe fn ClientActiveTexture(&mut self, _texture: GLenum) {}
+    unsafe fn EnableClientState(&mut self, _array: GLenum) {}
+    unsafe fn DisableClientState(&mut self, _array: GLenum) {}
+    // The getters only ever write the first value, since the null renderer
+    // doesn't know how many values each parameter has. That's always safe.
+    unsafe fn GetBooleanv(&mut self, _pname: GLenum, params: *mut GLboolean) {
+        params.write(gles11::FALSE);
+    }
+    unsafe fn GetFloatv(&mut self, _pname: GLenum, params: *mut GLfloat) {
+        params.write(0.0);
+    }
+    unsafe fn GetIntegerv(&mut self, pname: GLenum, params: *mut GLint) {
+        params.write(match pname {
+            gles11::FRAMEBUFFER_BINDING_OES => self.bound_framebuffer as _,
+            gles11::RENDERBUFFER_BINDING_OES => self.bound_renderbuffer as _,
+            gles11::TEXTURE_BINDING_2D => self.bound_texture_2d as _,
+            // Values for the original iPhone's PowerVR MBX.
+            gles11::MAX_TEXTURE_SIZE => 1024,
+            gles11::MAX_TEXTURE_UNITS => 2,
+            _ => 0,
+        });
+    }
+    unsafe fn GetPointerv(&mut self, _pname: GLenum, params: *mut *const GLvoid) {
+        params.write(std::ptr::null());
+    }
+    unsafe fn Hint(&mut self, _target: GLenum, _mode: GLenum) {}
+
+    // Other state manipulation
+    unsafe fn AlphaFunc(&mut self, _func: GLenum, _ref_: GLclampf) {}
+    unsafe fn AlphaFuncx(&mut self, _func: GLenum, _ref_: GLclampx) {}
+    unsafe fn BlendFunc(&mut self, _sfactor: GLenum, _dfactor: GLenum) {}
+    unsafe fn ColorMask(
+        &mut self,
+        _red: GLboolean,
+        _green: GLboolean,
+        _blue: GLboolean,
+        _alpha: GLboolean,
+    ) {
+    }
+    unsafe fn CullFace(&mut self, _mode: GLenum) {}
+    unsafe fn DepthFunc(&mut self, _func: GLenum) {}
+    unsafe fn DepthMask(&mut self, _flag: GLboolean) {}
+    unsafe fn DepthRangef(&mut self, _near: GLclampf, _far: GLclampf) {}
+    unsafe fn DepthRangex(&mut self, _near: GLclampx, _far: GLclampx) {}
+    unsafe fn FrontFace(&mut self, _mode: GLenum) {}
+    unsafe fn ShadeModel(&mut self, _mode: GLenum) {}
+    unsafe fn Scissor(&mut self, _x: GLint, _y: GLint, _width: GLsizei, _height: GLsizei) {}
+    unsafe fn Viewport(&mut self, _x: GLint, _y: GLint, _width: GLsizei, _height: GLsizei) {}
+
+    // Lighting and materials
+    unsafe fn Fogf(&mut self, _pname: GLenum, _param: GLfloat) {}
+    unsafe fn Fogx(&mut self, _pname: GLenum, _param: GLfixed) {}
+    unsafe fn Fogfv(&mut self, _pname: GLenum, _params: *const GLfloat) {}
+    unsafe fn Fogxv(&mut self, _pname: GLenum, _params: *const GLfixed) {}
+    unsafe fn Lightf(&mut self, _light: GLenum, _pname: GLenum, _param: GLfloat) {}
+    unsafe fn Lightx(&mut self, _light: GLenum, _pname: GLenum, _param: GLfixed) {}
+    unsafe fn Lightfv(&mut self, _light: GLenum, _pname: GLenum, _params: *const GLfloat) {}
+    unsafe fn Lightxv(&mut self, _light: GLenum, _pname: GLenum, _params: *const GLfixed) {}
+    unsafe fn Materialf(&mut self, _face: GLenum, _pname: GLenum, _param: GLfloat) {}
+    unsafe fn Materialx(&mut self, _face: GLenum, _pname: GLenum, _param: GLfixed) {}
+    unsafe fn Materialfv(&mut self, _face: GLenum, _pname: GLenum, _params: *const GLfloat) {}
+    unsafe fn Materialxv(&mut self, _face: GLenum, _pname: GLenum, _params: *const GLfixed) {}
+
+    // Buffers
+    unsafe fn GenBuffers(&mut self, n: GLsizei, buffers: *mut GLuint) {
+        self.gen_names(n, buffers)
+    }
+    unsafe fn DeleteBuffers(&mut self, _n: GLsizei, _buffers: *const GLuint) {}
+    unsafe fn BindBuffer(&mut self, _target: GLenum, _buffer: GLuint) {}
+
+    // Non-pointers
+    unsafe fn Color4f(&mut self, _red: GLfloat, _green: GLfloat, _blue: GLfloat, _alpha: GLfloat) {}
+    unsafe fn Color4x(&mut self, _red: GLfixed, _green: GLfixed, _blue: GLfixed, _alpha: GLfixed) {}
+
+    // Pointers
+    unsafe fn ColorPointer(
+        &mut self,
+        _size: GLint,
+        _type_: GLenum,
+        _stride: GLsizei,
+        _pointer: *const GLvoid,
+    ) {
+    }
+    unsafe fn NormalPointer(&mut self, _type_: GLenum, _stride: GLsizei, _pointer: *const GLvoid) {}
+    unsafe fn TexCoordPointer(
+        &mut self,
+        _size: GLint,
+        _type_: GLenum,
+        _stride: GLsizei,
+        _pointer: *const GLvoid,
+    ) {
+    }
+    unsafe fn VertexPointer(
+        &mut self,
+        _size: GLint,
+        _type_: GLenum,
+        _stride: GLsizei,
+        _pointer: *const GLvoid,
+    ) {
+    }
+
+    // Drawing
+    unsafe fn DrawArrays(&mut self, _mode: GLenum, _first: GLint, _count: GLsizei) {}
+    unsafe fn DrawElements(
+        &mut self,
+        _mode: GLenum,
+        _count: GLsizei,
+        _type_: GLenum,
+        _indices: *const GLvoid,
+    ) {
+    }
+
+    // Clearing
+    unsafe fn Clear(&mut self, _mask: GLbitfield) {}
+    unsafe fn ClearColor(
+        &mut self,
+        _red: GLclampf,
+        _green: GLclampf,
+        _blue: GLclampf,
+        _alpha: GLclampf,
+    ) {
+    }
+    unsafe fn ClearColorx(
+        &mut self,
+        _red: GLclampx,
+        _green: GLclampx,
+        _blue: GLclampx,
+        _alpha: GLclampx,
+    ) {
+    }
+    unsafe fn ClearDepthf(&mut self, _depth: GLclampf) {}
+    unsafe fn ClearDepthx(&mut self, _depth: GLclampx) {}
+    unsafe fn ClearStencil(&mut self, _s: GLint) {}
+
+    // Reading pixels
+    unsafe fn ReadPixels(
+        &mut self,
+        _x: GLint,
+        _y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *mut GLvoid,
+    ) {
+        // Nothing is drawn, so the result is always zero (transparent black).
+        assert!(format == gles11::RGBA && type_ == gles11::UNSIGNED_BYTE);
+        let size = width.max(0) as usize * height.max(0) as usize * 4;
+        std::ptr::write_bytes(pixels.cast::<u8>(), 0, size);
+    }
+
+    // Textures
+    unsafe fn PixelStorei(&mut self, _pname: GLenum, _param: GLint) {}
+    unsafe fn GenTextures(&mut self, n: GLsizei, textures: *mut GLuint) {
+        self.gen_names(n, textures)
+    }
+    unsafe fn DeleteTextures(&mut self, _n: GLsizei, _textures: *const GLuint) {}
+    unsafe fn ActiveTexture(&mut self, _texture: GLenum) {}
+    unsafe fn BindTexture(&mut self, target: GLenum, texture: GLuint) {
+        if target == gles11::TEXTURE_2D {
+            self.bound_texture_2d = texture;
+        }
+    }
+    unsafe fn TexParameteri(&mut self, _target: GLenum, _pname: GLenum, _param: GLint) {}
+    unsafe fn TexParameterf(&mut self, _target: GLenum, _pname: GLenum, _param: GLfloat) {}
+    unsafe fn TexParameterx(&mut self, _target: GLenum, _pname: GLenum, _param: GLfixed) {}
+    unsafe fn TexImage2D(
+        &mut self,
+        _target: GLenum,
+        _level: GLint,
+        _internalformat: GLint,
+        _width: GLsizei,
+        _height: GLsizei,
+        _border: GLint,
+        _format: GLenum,
+        _type_: GLenum,
+        _pixels: *const GLvoid,
+    ) {
+    }
+    unsafe fn CompressedTexImage2D(
+        &mut self,
+        _target: GLenum,
+        _level: GLint,
+        _internalformat: GLenum,
+        _width: GLsizei,
+        _height: GLsizei,
+        _border: GLint,
+        _image_size: GLsizei,
+        _data: *const GLvoid,
+    ) {
+    }
+    unsafe fn CopyTexImage2D(
+        &mut self,
+        _target: GLenum,
+        _level: GLint,
+        _internalformat: GLenum,
+        _x: GLint,
+        _y: GLint,
+        _width: GLsizei,
+        _height: GLsizei,
+        _border: GLint,
+    ) {
+    }
+    unsafe fn TexEnvf(&mut self, _target: GLenum, _pname: GLenum, _param: GLfloat) {}
+    unsafe fn TexEnvx(&mut self, _target: GLenum, _pname: GLenum, _param: GLfixed) {}
+    unsafe fn TexEnvi(&mut self, _target: GLenum, _pname: GLenum, _param: GLint) {}
+    unsafe fn TexEnvfv(&mut self, _target: GLenum, _pname: GLenum, _params: *const GLfloat) {}
+    unsafe fn TexEnvxv(&mut self, _target: GLenum, _pname: GLenum, _params: *const GLfixed) {}
+    unsafe fn TexEnviv(&mut self, _target: GLenum, _pname: GLenum, _params: *const GLint) {}
+
+    // Matrix stack operations
+    unsafe fn MatrixMode(&mut self, _mode: GLenum) {}
+    unsafe fn LoadIdentity(&mut self) {}
+    unsafe fn LoadMatrixf(&mut self, _m: *const GLfloat) {}
+    unsafe fn LoadMatrixx(&mut self, _m: *const GLfixed) {}
+    unsafe fn MultMatrixf(&mut self, _m: *const GLfloat) {}
+    unsafe fn MultMatrixx(&mut self, _m: *const GLfixed) {}
+    unsafe fn PushMatrix(&mut self) {}
+    unsafe fn PopMatrix(&mut self) {}
+    unsafe fn Orthof(
+        &mut self,
+        _left: GLfloat,
+        _right: GLfloat,
+        _bottom: GLfloat,
+        _top: GLfloat,
+        _near: GLfloat,
+        _far: GLfloat,
+    ) {
+    }
+    unsafe fn Orthox(
+        &mut self,
+        _left: GLfixed,
+        _right: GLfixed,
+        _bottom: GLfixed,
+        _top: GLfixed,
+        _near: GLfixed,
+        _far: GLfixed,
+    ) {
+    }
+    unsafe fn Frustumf(
+        &mut self,
+        _left: GLfloat,
+        _right: GLfloat,
+        _bottom: GLfloat,
+        _top: GLfloat,
+        _near: GLfloat,
+        _far: GLfloat,
+    ) {
+    }
+    unsafe fn Frustumx(
+        &mut self,
+        _left: GLfixed,
+        _right: GLfixed,
+        _bottom: GLfixed,
+        _top: GLfixed,
+        _near: GLfixed,
+        _far: GLfixed,
+    ) {
+    }
+    unsafe fn Rotatef(&mut self, _angle: GLfloat, _x: GLfloat, _y: GLfloat, _z: GLfloat) {}
+    unsafe fn Rotatex(&mut self, _angle: GLfixed, _x: GLfixed, _y: GLfixed, _z: GLfixed) {}
+    unsafe fn Scalef(&mut self, _x: GLfloat, _y: GLfloat, _z: GLfloat) {}
+    unsafe fn Scalex(&mut self, _x: GLfixed, _y: GLfixed, _z: GLfixed) {}
+    unsafe fn Translatef(&mut self, _x: GLfloat, _y: GLfloat, _z: GLfloat) {}
+    unsafe fn Translatex(&mut self, _x: GLfixed, _y: GLfixed, _z: GLfixed) {}
+
+    // OES_framebuffer_object
+    unsafe fn GenFramebuffersOES(&mut self, n: GLsizei, framebuffers: *mut GLuint) {
+        self.gen_names(n, framebuffers)
+    }
+    unsafe fn GenRenderbuffersOES(&mut self, n: GLsizei, renderbuffers: *mut GLuint) {
+        self.gen_names(n, renderbuffers)
+    }
+    unsafe fn BindFramebufferOES(&mut self, _target: GLenum, framebuffer: GLuint) {
+        self.bound_framebuffer = framebuffer;
+    }
+    unsafe fn BindRenderbufferOES(&mut self, _target: GLenum, renderbuffer: GLuint) {
+        self.bound_renderbuffer = renderbuffer;
+    }
+    unsafe fn RenderbufferStorageOES(
+        &mut self,
+        _target: GLenum,
+        _internalformat: GLenum,
+        width: GLsizei,
+        height: GLsizei,
+    ) {
+        self.renderbuffer_sizes
+            .insert(self.bound_renderbuffer, (width, height));
+    }
+    unsafe fn FramebufferRenderbufferOES(
+        &mut self,
+        _target: GLenum,
+        _attachment: GLenum,
+        _renderbuffertarget: GLenum,
+        _renderbuffer: GLuint,
+    ) {
+    }
+    unsafe fn FramebufferTexture2DOES(
+        &mut self,
+        _target: GLenum,
+        _attachment: GLenum,
+        _textarget: GLenum,
+        _texture: GLuint,
+        _level: i32,
+    ) {
+    }
+    unsafe fn GetRenderbufferParameterivOES(
+        &mut self,
+        _target: GLenum,
+        pname: GLenum,
+        params: *mut GLint,
+    ) {
+        let (width, height) = self
+            .renderbuffer_sizes
+            .get(&self.bound_renderbuffer)
+            .copied()
+            .unwrap_or((0, 0));
+        params.write(match pname {
+            gles11::RENDERBUFFER_WIDTH_OES => width,
+            gles11::RENDERBUFFER_HEIGHT_OES => height,
+            _ => 0,
+        });
+    }
+    unsafe fn CheckFramebufferStatusOES(&mut self, _target: GLenum) -> GLenum {
+        gles11::FRAMEBUFFER_COMPLETE_OES
+    }
+    unsafe fn DeleteFramebuffersOES(&mut self, n: GLsizei, framebuffers: *const GLuint) {
+        for i in 0..n.max(0) as usize {
+            if framebuffers.add(i).read() == self.bound_framebuffer {
+                self.bound_framebuffer = 0;
+            }
+        }
+    }
+    unsafe fn DeleteRenderbuffersOES(&mut self, n: GLsizei, renderbuffers: *const GLuint) {
+        for i in 0..n.max(0) as usize {
+            let renderbuffer = renderbuffers.add(i).read();
+            self.renderbuffer_sizes.remove(&renderbuffer);
+            if renderbuffer == self.bound_renderbuffer {
+                self.bound_renderbuffer = 0;
+            }
+        }
+    }
+}
@@ -13,7 +13,11 @@ use crate::Environment;
 use std::time::Instant;
 
 pub mod ui_accelerometer;
+pub mod ui_action_sheet;
+pub mod ui_alert_view;
 pub mod ui_application;
+pub mod ui_bar_item;
+pub mod ui_button;
 pub mod ui_color;
 pub mod ui_control;
 pub mod ui_device;
@@ -23,26 +27,39 @@ pub mod ui_graphics;
 pub mod ui_image;
 pub mod ui_image_picker_controller;
 pub mod ui_image_view;
+pub mod ui_label;
+pub mod ui_navigation_bar;
+pub mod ui_navigation_controller;
 pub mod ui_nib;
 pub mod ui_responder;
 pub mod ui_screen;
+pub mod ui_scroll_view;
+pub mod ui_tab_bar;
+pub mod ui_tab_bar_controller;
+pub mod ui_table_view;
+pub mod ui_table_view_cell;
 pub mod ui_text_field;
 pub mod ui_touch;
 pub mod ui_view;
 pub mod ui_view_controller;
 pub mod ui_window;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     ui_accelerometer: ui_accelerometer::State,
+    ui_alert_view: ui_alert_view::State,
     ui_application: ui_application::State,
     ui_color: ui_color::State,
     ui_device: ui_device::State,
     ui_font: ui_font::State,
     ui_graphics: ui_graphics::State,
+    ui_nib: ui_nib::State,
     ui_screen: ui_screen::State,
+    ui_scroll_view: ui_scroll_view::State,
+    ui_table_view: ui_table_view::State,
     ui_touch: ui_touch::State,
     ui_view: ui_view::State,
+    ui_window: ui_window::State,
 }
 
 /// For use by `NSRunLoop`: handles any events that have queued up.
@@ -68,5 +85,14 @@ pub fn handle_events(env: &mut Environment) -> Option<Instant> {
         }
     }
 
-    ui_accelerometer::handle_accelerometer(env)
+    ui_alert_view::handle_alerts(env);
+
+    ui_table_view::load_pending_tables(env);
+
+    let scrolling_due = ui_scroll_view::animate_scroll_views(env);
+    let accelerometer_due = ui_accelerometer::handle_accelerometer(env);
+    [scrolling_due, accelerometer_due]
+        .into_iter()
+        .flatten()
+        .min()
 }
@@ -10,13 +10,13 @@
 
 use crate::frameworks::foundation::NSTimeInterval;
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
-    NSZonePtr, TrivialHostObject,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr, TrivialHostObject,
 };
 use crate::Environment;
 use std::time::{Duration, Instant};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     /// [UIAccelerometer sharedAccelerometer]
     shared_accelerometer: Option<id>,
@@ -28,13 +28,18 @@ pub struct State {
 
 type UIAccelerationValue = f64;
 
+#[derive(Clone)]
 struct UIAccelerationHostObject {
     x: UIAccelerationValue,
     y: UIAccelerationValue,
     z: UIAccelerationValue,
     timestamp: NSTimeInterval,
 }
-impl HostObject for UIAccelerationHostObject {}
+impl HostObject for UIAccelerationHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -132,7 +137,7 @@ pub(super) fn handle_accelerometer(env: &mut Environment) -> Option<Instant> {
     let ns_interval = state.update_interval.unwrap();
     let rust_interval = Duration::from_secs_f64(ns_interval);
 
-    let now = Instant::now();
+    let now = env.clock.now();
     let new_due_by = if let Some(due_by) = state.due_by {
         if due_by > now {
             return Some(due_by);
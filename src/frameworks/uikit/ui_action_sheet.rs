@@ -0,0 +1,118 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIActionSheet`.
+//!
+//! This is presented the same way as `UIAlertView`, see [super::ui_alert_view].
+
+use super::ui_alert_view::{
+    add_button, add_buttons_from_va_args, alert_state, button_title, dismiss, init_alert, show,
+    AlertKind,
+};
+use crate::frameworks::foundation::NSInteger;
+use crate::objc::{id, msg, nil, objc_classes, release, ClassExports};
+
+type UIActionSheetStyle = NSInteger;
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIActionSheet: UIView
+
+- (id)initWithTitle:(id)title // NSString*
+           delegate:(id)delegate
+  cancelButtonTitle:(id)cancel_button_title // NSString*
+destructiveButtonTitle:(id)destructive_button_title // NSString*
+  otherButtonTitles:(id)other_button_titles, // NSString*
+                    ...dots {
+    let this = init_alert(env, this, AlertKind::ActionSheet, title, nil, delegate);
+    // The destructive button comes first and the cancel button comes last.
+    if destructive_button_title != nil {
+        let index: NSInteger = msg![env; this addButtonWithTitle:destructive_button_title];
+        alert_state(&mut env.objc, this, AlertKind::ActionSheet).destructive_button_index = index;
+    }
+    if other_button_titles != nil {
+        let state = alert_state(&mut env.objc, this, AlertKind::ActionSheet);
+        state.first_other_button_index = state.button_titles.len().try_into().unwrap();
+    }
+    add_buttons_from_va_args(env, this, other_button_titles, dots);
+    if cancel_button_title != nil {
+        let index: NSInteger = msg![env; this addButtonWithTitle:cancel_button_title];
+        alert_state(&mut env.objc, this, AlertKind::ActionSheet).cancel_button_index = index;
+    }
+    this
+}
+
+- (id)title {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).title
+}
+- (())setTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    let state = alert_state(&mut env.objc, this, AlertKind::ActionSheet);
+    let old = std::mem::replace(&mut state.title, title);
+    release(env, old);
+}
+- (id)delegate {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).delegate
+}
+- (())setDelegate:(id)delegate {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).delegate = delegate;
+}
+
+- (UIActionSheetStyle)actionSheetStyle {
+    0 // UIActionSheetStyleDefault
+}
+- (())setActionSheetStyle:(UIActionSheetStyle)_style {
+    // The style has no effect on a host message box.
+}
+
+- (NSInteger)addButtonWithTitle:(id)title { // NSString*
+    add_button(env, this, AlertKind::ActionSheet, title)
+}
+- (id)buttonTitleAtIndex:(NSInteger)index {
+    button_title(env, this, AlertKind::ActionSheet, index)
+}
+- (NSInteger)numberOfButtons {
+    let state = alert_state(&mut env.objc, this, AlertKind::ActionSheet);
+    state.button_titles.len().try_into().unwrap()
+}
+- (NSInteger)cancelButtonIndex {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).cancel_button_index
+}
+- (())setCancelButtonIndex:(NSInteger)index {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).cancel_button_index = index;
+}
+- (NSInteger)destructiveButtonIndex {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).destructive_button_index
+}
+- (())setDestructiveButtonIndex:(NSInteger)index {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).destructive_button_index = index;
+}
+- (NSInteger)firstOtherButtonIndex {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).first_other_button_index
+}
+
+- (bool)isVisible {
+    alert_state(&mut env.objc, this, AlertKind::ActionSheet).visible
+}
+// The view an action sheet is shown from only affects where it appears.
+- (())showInView:(id)_view { // UIView*
+    show(env, this, AlertKind::ActionSheet);
+}
+- (())showFromToolbar:(id)_toolbar { // UIToolbar*
+    show(env, this, AlertKind::ActionSheet);
+}
+- (())showFromTabBar:(id)_tab_bar { // UITabBar*
+    show(env, this, AlertKind::ActionSheet);
+}
+- (())dismissWithClickedButtonIndex:(NSInteger)index
+                           animated:(bool)_animated {
+    dismiss(env, this, AlertKind::ActionSheet, index, /* clicked: */ false);
+}
+
+@end
+
+};
@@ -0,0 +1,426 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIAlertView`, and the parts of `UIActionSheet` that work the same way.
+//!
+//! Rather than being drawn in the app's window, alerts and action sheets are
+//! shown using the host OS's message box (see
+//! [crate::window::show_message_with_options]). This blocks emulation until the
+//! user picks a button, which is close enough to a modal alert. In headless
+//! mode, the cancel button (or the first button) is picked automatically.
+//!
+//! Like on the real iPhone OS, `show` doesn't block: the alert is presented
+//! and the delegate is notified later, on the main run loop.
+
+use super::ui_view::{UIViewHostObject, UIViewSubclass};
+use crate::abi::DotDotDot;
+use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::to_rust_string;
+use crate::frameworks::foundation::NSInteger;
+use crate::objc::{
+    id, msg, nil, objc_classes, release, responds_to_selector, retain, ClassExports, ObjC,
+};
+use crate::Environment;
+use std::collections::VecDeque;
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Alert views and action sheets that `show` has been called for, which
+    /// haven't been presented yet. These are weak references: the strong
+    /// reference is owned by the alert itself while it's visible.
+    pending: VecDeque<id>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum AlertKind {
+    AlertView,
+    ActionSheet,
+}
+
+#[derive(Clone)]
+pub(super) struct AlertState {
+    kind: AlertKind,
+    /// `NSString*`, possibly nil. This is a strong reference.
+    pub(super) title: id,
+    /// `NSString*`, possibly nil. Always nil for action sheets. This is a
+    /// strong reference.
+    pub(super) message: id,
+    /// This is a weak reference.
+    pub(super) delegate: id,
+    /// `NSString*`s. These are strong references.
+    pub(super) button_titles: Vec<id>,
+    pub(super) cancel_button_index: NSInteger,
+    pub(super) destructive_button_index: NSInteger,
+    pub(super) first_other_button_index: NSInteger,
+    /// Whether `show` has been called and the alert hasn't been dismissed yet.
+    /// While this is [true], the alert holds a strong reference to itself.
+    pub(super) visible: bool,
+}
+impl AlertState {
+    fn new(kind: AlertKind) -> Self {
+        AlertState {
+            kind,
+            title: nil,
+            message: nil,
+            delegate: nil,
+            button_titles: Vec::new(),
+            cancel_button_index: -1,
+            destructive_button_index: -1,
+            first_other_button_index: -1,
+            visible: false,
+        }
+    }
+
+    /// For use by `-[UIView dealloc]`.
+    pub(super) fn release(self, env: &mut Environment) {
+        release(env, self.title);
+        release(env, self.message);
+        for button_title in self.button_titles {
+            release(env, button_title);
+        }
+    }
+}
+
+/// Get the alert-specific state of a `UIAlertView` or `UIActionSheet`,
+/// initializing it if necessary (e.g. if the app used plain `init`).
+pub(super) fn alert_state(objc: &mut ObjC, alert: id, kind: AlertKind) -> &mut AlertState {
+    let host_object = objc.borrow_mut::<UIViewHostObject>(alert);
+    if !matches!(host_object.subclass, UIViewSubclass::Alert(_)) {
+        host_object.subclass = UIViewSubclass::Alert(AlertState::new(kind));
+    }
+    let UIViewSubclass::Alert(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    assert!(state.kind == kind);
+    state
+}
+
+/// Shared implementation of the initializers: set up the view, then store the
+/// title, message and delegate. The buttons are added by the caller.
+pub(super) fn init_alert(
+    env: &mut Environment,
+    this: id,
+    kind: AlertKind,
+    title: id,
+    message: id,
+    delegate: id,
+) -> id {
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: 0.0,
+            height: 0.0,
+        },
+    };
+    let this: id = msg![env; this initWithFrame:frame];
+
+    let title: id = msg![env; title copy];
+    let message: id = msg![env; message copy];
+    let state = alert_state(&mut env.objc, this, kind);
+    state.title = title;
+    state.message = message;
+    state.delegate = delegate;
+    this
+}
+
+/// Add the titles from a nil-terminated variable arguments list as buttons,
+/// starting with `first_title` (which may itself be nil).
+pub(super) fn add_buttons_from_va_args(
+    env: &mut Environment,
+    this: id,
+    first_title: id,
+    dots: DotDotDot,
+) {
+    let mut va_args = dots.start();
+    let mut title = first_title;
+    while title != nil {
+        let _: NSInteger = msg![env; this addButtonWithTitle:title];
+        title = va_args.next(env);
+    }
+}
+
+/// Shared implementation of `addButtonWithTitle:`.
+pub(super) fn add_button(env: &mut Environment, this: id, kind: AlertKind, title: id) -> NSInteger {
+    let title: id = msg![env; title copy];
+    let state = alert_state(&mut env.objc, this, kind);
+    state.button_titles.push(title);
+    (state.button_titles.len() - 1).try_into().unwrap()
+}
+
+/// Shared implementation of `buttonTitleAtIndex:`.
+pub(super) fn button_title(
+    env: &mut Environment,
+    this: id,
+    kind: AlertKind,
+    index: NSInteger,
+) -> id {
+    let state = alert_state(&mut env.objc, this, kind);
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| state.button_titles.get(index).copied())
+        .unwrap_or(nil)
+}
+
+/// Shared implementation of `show` and friends.
+pub(super) fn show(env: &mut Environment, this: id, kind: AlertKind) {
+    let state = alert_state(&mut env.objc, this, kind);
+    if state.visible {
+        return;
+    }
+    state.visible = true;
+    retain(env, this);
+    env.framework_state
+        .uikit
+        .ui_alert_view
+        .pending
+        .push_back(this);
+}
+
+/// Shared implementation of `dismissWithClickedButtonIndex:animated:`, also
+/// used when the user picks a button, in which case `clicked` is [true].
+pub(super) fn dismiss(
+    env: &mut Environment,
+    this: id,
+    kind: AlertKind,
+    button_index: NSInteger,
+    clicked: bool,
+) {
+    let state = alert_state(&mut env.objc, this, kind);
+    if !state.visible {
+        return;
+    }
+    state.visible = false;
+    let delegate = state.delegate;
+    env.framework_state
+        .uikit
+        .ui_alert_view
+        .pending
+        .retain(|&alert| alert != this);
+
+    if delegate != nil {
+        match kind {
+            AlertKind::AlertView => {
+                if clicked && responds_to_selector(env, delegate, "alertView:clickedButtonAtIndex:")
+                {
+                    () = msg![env; delegate alertView:this clickedButtonAtIndex:button_index];
+                }
+                if responds_to_selector(env, delegate, "alertView:willDismissWithButtonIndex:") {
+                    () = msg![env; delegate alertView:this willDismissWithButtonIndex:button_index];
+                }
+                if responds_to_selector(env, delegate, "alertView:didDismissWithButtonIndex:") {
+                    () = msg![env; delegate alertView:this didDismissWithButtonIndex:button_index];
+                }
+            }
+            AlertKind::ActionSheet => {
+                if clicked
+                    && responds_to_selector(env, delegate, "actionSheet:clickedButtonAtIndex:")
+                {
+                    () = msg![env; delegate actionSheet:this clickedButtonAtIndex:button_index];
+                }
+                if responds_to_selector(env, delegate, "actionSheet:willDismissWithButtonIndex:") {
+                    () = msg![env; delegate actionSheet:this willDismissWithButtonIndex:button_index];
+                }
+                if responds_to_selector(env, delegate, "actionSheet:didDismissWithButtonIndex:") {
+                    () =
+                        msg![env; delegate actionSheet:this didDismissWithButtonIndex:button_index];
+                }
+            }
+        }
+    }
+
+    release(env, this);
+}
+
+/// For use by [super::handle_events]: present any alerts and action sheets
+/// that have been shown since the last time this was called.
+pub(super) fn handle_alerts(env: &mut Environment) {
+    while let Some(alert) = env.framework_state.uikit.ui_alert_view.pending.pop_front() {
+        present(env, alert);
+    }
+}
+
+fn present(env: &mut Environment, alert: id) {
+    let UIViewSubclass::Alert(ref state) = env.objc.borrow::<UIViewHostObject>(alert).subclass
+    else {
+        panic!();
+    };
+    let kind = state.kind;
+    let delegate = state.delegate;
+    let title = state.title;
+    let message = state.message;
+    let button_titles = state.button_titles.clone();
+    let cancel_button_index = state.cancel_button_index;
+
+    // The delegate might dismiss the alert while it's being presented.
+    retain(env, alert);
+
+    if delegate != nil {
+        match kind {
+            AlertKind::AlertView => {
+                if responds_to_selector(env, delegate, "willPresentAlertView:") {
+                    () = msg![env; delegate willPresentAlertView:alert];
+                }
+                if responds_to_selector(env, delegate, "didPresentAlertView:") {
+                    () = msg![env; delegate didPresentAlertView:alert];
+                }
+            }
+            AlertKind::ActionSheet => {
+                if responds_to_selector(env, delegate, "willPresentActionSheet:") {
+                    () = msg![env; delegate willPresentActionSheet:alert];
+                }
+                if responds_to_selector(env, delegate, "didPresentActionSheet:") {
+                    () = msg![env; delegate didPresentActionSheet:alert];
+                }
+            }
+        }
+    }
+
+    let title = if title != nil {
+        to_rust_string(env, title).into_owned()
+    } else {
+        String::new()
+    };
+    let message = if message != nil {
+        to_rust_string(env, message).into_owned()
+    } else {
+        String::new()
+    };
+    let button_titles: Vec<String> = button_titles
+        .into_iter()
+        .map(|button_title| to_rust_string(env, button_title).into_owned())
+        .collect();
+    echo!(
+        "App shows {}: title {:?}, message {:?}, buttons {:?}",
+        match kind {
+            AlertKind::AlertView => "alert",
+            AlertKind::ActionSheet => "action sheet",
+        },
+        title,
+        message,
+        button_titles
+    );
+
+    let still_visible = matches!(
+        env.objc.borrow::<UIViewHostObject>(alert).subclass,
+        UIViewSubclass::Alert(AlertState { visible: true, .. })
+    );
+    // An alert without buttons can only be dismissed by the app, which is
+    // sometimes used for "please wait" messages.
+    if still_visible && !button_titles.is_empty() {
+        let default_index = if cancel_button_index >= 0 {
+            cancel_button_index
+        } else {
+            0
+        };
+        let button_index = if env.options.headless {
+            echo!(
+                "Headless mode, picking button {:?}",
+                button_titles[default_index as usize]
+            );
+            default_index
+        } else {
+            let options: Vec<(i32, &str)> = button_titles
+                .iter()
+                .enumerate()
+                .map(|(i, button_title)| (i as i32, button_title.as_str()))
+                .collect();
+            // Closing the message box counts as cancelling.
+            crate::window::show_message_with_options(&title, &message, false, &options)
+                .unwrap_or(default_index)
+        };
+        echo!(
+            "User picked button {:?}",
+            button_titles[button_index as usize]
+        );
+        dismiss(env, alert, kind, button_index, /* clicked: */ true);
+    }
+
+    release(env, alert);
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIAlertView: UIView
+
+- (id)initWithTitle:(id)title // NSString*
+            message:(id)message // NSString*
+           delegate:(id)delegate
+  cancelButtonTitle:(id)cancel_button_title // NSString*
+  otherButtonTitles:(id)other_button_titles, // NSString*
+                    ...dots {
+    let this = init_alert(env, this, AlertKind::AlertView, title, message, delegate);
+    if cancel_button_title != nil {
+        let index: NSInteger = msg![env; this addButtonWithTitle:cancel_button_title];
+        alert_state(&mut env.objc, this, AlertKind::AlertView).cancel_button_index = index;
+    }
+    add_buttons_from_va_args(env, this, other_button_titles, dots);
+    let state = alert_state(&mut env.objc, this, AlertKind::AlertView);
+    if other_button_titles != nil {
+        state.first_other_button_index = if cancel_button_title != nil { 1 } else { 0 };
+    }
+    this
+}
+
+- (id)title {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).title
+}
+- (())setTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    let state = alert_state(&mut env.objc, this, AlertKind::AlertView);
+    let old = std::mem::replace(&mut state.title, title);
+    release(env, old);
+}
+- (id)message {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).message
+}
+- (())setMessage:(id)message { // NSString*
+    let message: id = msg![env; message copy];
+    let state = alert_state(&mut env.objc, this, AlertKind::AlertView);
+    let old = std::mem::replace(&mut state.message, message);
+    release(env, old);
+}
+- (id)delegate {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).delegate
+}
+- (())setDelegate:(id)delegate {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).delegate = delegate;
+}
+
+- (NSInteger)addButtonWithTitle:(id)title { // NSString*
+    add_button(env, this, AlertKind::AlertView, title)
+}
+- (id)buttonTitleAtIndex:(NSInteger)index {
+    button_title(env, this, AlertKind::AlertView, index)
+}
+- (NSInteger)numberOfButtons {
+    let state = alert_state(&mut env.objc, this, AlertKind::AlertView);
+    state.button_titles.len().try_into().unwrap()
+}
+- (NSInteger)cancelButtonIndex {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).cancel_button_index
+}
+- (())setCancelButtonIndex:(NSInteger)index {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).cancel_button_index = index;
+}
+- (NSInteger)firstOtherButtonIndex {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).first_other_button_index
+}
+
+- (bool)isVisible {
+    alert_state(&mut env.objc, this, AlertKind::AlertView).visible
+}
+- (())show {
+    show(env, this, AlertKind::AlertView);
+}
+- (())dismissWithClickedButtonIndex:(NSInteger)index
+                           animated:(bool)_animated {
+    dismiss(env, this, AlertKind::AlertView, index, /* clicked: */ false);
+}
+
+@end
+
+};
@@ -7,29 +7,34 @@
 
 use super::ui_device::*;
 use crate::dyld::{export_c_func, FunctionExports};
-use crate::frameworks::foundation::ns_string;
+use crate::frameworks::foundation::{ns_string, ns_user_defaults};
 use crate::frameworks::uikit::ui_nib::load_main_nib_file;
 use crate::mem::MutPtr;
 use crate::objc::{
-    id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain, ClassExports,
-    HostObject, NSZonePtr,
+    id, msg, msg_class, msg_send, nil, objc_classes, release, responds_to_selector, retain,
+    AnyHostObject, ClassExports, HostObject, NSZonePtr, SEL,
 };
 use crate::window::DeviceOrientation;
 use crate::Environment;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     /// [UIApplication sharedApplication]
     shared_application: Option<id>,
 }
 
+#[derive(Clone)]
 struct UIApplicationHostObject {
     delegate: id,
     delegate_is_retained: bool,
 }
-impl HostObject for UIApplicationHostObject {}
+impl HostObject for UIApplicationHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
-type UIInterfaceOrientation = UIDeviceOrientation;
+pub type UIInterfaceOrientation = UIDeviceOrientation;
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -100,6 +105,10 @@ pub const CLASSES: ClassExports = objc_classes! {
     msg![env; this setStatusBarOrientation:orientation]
 }
 
+- (id)keyWindow {
+    env.framework_state.uikit.ui_window.key_window.unwrap_or(nil)
+}
+
 - (bool)idleTimerDisabled {
     !env.window.is_screen_saver_enabled()
 }
@@ -107,6 +116,52 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.window.set_screen_saver_enabled(!disabled);
 }
 
+- (bool)sendAction:(SEL)action
+                to:(id)target
+              from:(id)sender
+          forEvent:(id)event { // UIEvent*
+    let mut target = target;
+    if target == nil {
+        // The action should go to the first object in the responder chain
+        // that can handle it. There's no tracking of the first responder yet,
+        // so the search starts from the sender.
+        let mut responder = sender;
+        while responder != nil {
+            if msg![env; responder respondsToSelector:action] {
+                break;
+            }
+            responder = msg![env; responder nextResponder];
+        }
+        if responder == nil {
+            // The app delegate is the last resort.
+            let delegate: id = msg![env; this delegate];
+            if delegate != nil && msg![env; delegate respondsToSelector:action] {
+                responder = delegate;
+            }
+        }
+        target = responder;
+    }
+    if target == nil {
+        log!(
+            "Warning: no target for action {:?} from {:?}",
+            action.as_str(&env.mem),
+            sender
+        );
+        return false;
+    }
+
+    log_dbg!(
+        "Sending action {:?} to {:?} from {:?}",
+        action.as_str(&env.mem),
+        target,
+        sender
+    );
+    // The action method might take the sender and the event as arguments, or
+    // just the sender, or neither. Passing both is harmless.
+    let _: () = msg_send(env, (target, action, sender, event));
+    true
+}
+
 - (bool)openURL:(id)url { // NSURL
     let ns_string = msg![env; url absoluteURL];
     let url_string = ns_string::to_rust_string(env, ns_string);
@@ -233,6 +288,8 @@ pub(super) fn exit(env: &mut Environment) {
         let _: () = msg![env; pool drain];
     }
 
+    ns_user_defaults::synchronize_standard_defaults(env);
+
     std::process::exit(0);
 }
 
@@ -0,0 +1,421 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIBarItem` and its subclasses `UIBarButtonItem` and `UITabBarItem`.
+//!
+//! Bar items aren't views. The bar showing them (`UINavigationBar` or
+//! `UITabBar`) creates buttons for them, and lays itself out again when an
+//! item changes.
+//!
+//! Unlike on the real iPhone OS, system items are shown with a text title
+//! rather than an icon.
+
+use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
+use crate::frameworks::foundation::NSInteger;
+use crate::objc::{
+    id, msg, nil, objc_classes, release, retain, AnyHostObject, ClassExports, HostObject,
+    NSZonePtr, SEL,
+};
+use crate::Environment;
+
+pub type UIBarButtonItemStyle = NSInteger;
+pub const UIBarButtonItemStylePlain: UIBarButtonItemStyle = 0;
+#[allow(dead_code)]
+pub const UIBarButtonItemStyleBordered: UIBarButtonItemStyle = 1;
+pub const UIBarButtonItemStyleDone: UIBarButtonItemStyle = 2;
+
+pub type UIBarButtonSystemItem = NSInteger;
+pub const UIBarButtonSystemItemDone: UIBarButtonSystemItem = 0;
+
+pub type UITabBarSystemItem = NSInteger;
+
+#[derive(Clone)]
+struct UIBarItemHostObject {
+    /// `NSString*`, possibly nil. This is a strong reference.
+    title: id,
+    /// `UIImage*`, possibly nil. This is a strong reference.
+    image: id,
+    tag: NSInteger,
+    enabled: bool,
+    /// Only used by `UIBarButtonItem`.
+    style: UIBarButtonItemStyle,
+    /// Only used by `UIBarButtonItem`. This is a weak reference.
+    target: id,
+    /// Only used by `UIBarButtonItem`. This may be null.
+    action: SEL,
+    /// `UIView*`, possibly nil. Only used by `UIBarButtonItem`. This is a
+    /// strong reference.
+    custom_view: id,
+    /// `NSString*`, possibly nil. Only used by `UITabBarItem`. This is a
+    /// strong reference.
+    badge_value: id,
+    /// The bar showing this item, if any. This is a weak reference.
+    bar: id,
+}
+impl HostObject for UIBarItemHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// The title shown for a system item, if it has one.
+fn bar_button_system_item_title(item: UIBarButtonSystemItem) -> Option<&'static str> {
+    Some(match item {
+        UIBarButtonSystemItemDone => "Done",
+        1 => "Cancel",
+        2 => "Edit",
+        3 => "Save",
+        4 => "+",
+        // Flexible and fixed space
+        5 | 6 => return None,
+        7 => "Compose",
+        8 => "Reply",
+        9 => "Action",
+        10 => "Organize",
+        11 => "Bookmarks",
+        12 => "Search",
+        13 => "Refresh",
+        14 => "Stop",
+        15 => "Camera",
+        16 => "Trash",
+        17 => "Play",
+        18 => "Pause",
+        19 => "Rewind",
+        20 => "Fast Forward",
+        21 => "Undo",
+        22 => "Redo",
+        _ => {
+            log!("Warning: unknown UIBarButtonSystemItem {}", item);
+            return None;
+        }
+    })
+}
+
+/// The title shown for a system item.
+fn tab_bar_system_item_title(item: UITabBarSystemItem) -> &'static str {
+    match item {
+        0 => "More",
+        1 => "Favorites",
+        2 => "Featured",
+        3 => "Top Rated",
+        4 => "Recents",
+        5 => "Contacts",
+        6 => "History",
+        7 => "Bookmarks",
+        8 => "Search",
+        9 => "Downloads",
+        10 => "Most Recent",
+        11 => "Most Viewed",
+        _ => {
+            log!("Warning: unknown UITabBarSystemItem {}", item);
+            ""
+        }
+    }
+}
+
+/// Tell the bar showing the item, if any, that it has changed.
+fn item_changed(env: &mut Environment, item: id) {
+    let bar = env.objc.borrow::<UIBarItemHostObject>(item).bar;
+    if bar != nil {
+        () = msg![env; bar layoutSubviews];
+    }
+}
+
+/// Replace one of the item's strong references, retaining the new value and
+/// releasing the old one.
+fn set_field(
+    env: &mut Environment,
+    item: id,
+    value: id,
+    field: fn(&mut UIBarItemHostObject) -> &mut id,
+) {
+    retain(env, value);
+    let old = std::mem::replace(field(env.objc.borrow_mut(item)), value);
+    release(env, old);
+    item_changed(env, item);
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+// abstract class
+@implementation UIBarItem: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UIBarItemHostObject {
+        title: nil,
+        image: nil,
+        tag: 0,
+        enabled: true,
+        style: UIBarButtonItemStylePlain,
+        target: nil,
+        action: SEL::null(),
+        custom_view: nil,
+        badge_value: nil,
+        bar: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    init_with_coder(env, this, coder)
+}
+
+- (())dealloc {
+    let &UIBarItemHostObject {
+        title,
+        image,
+        custom_view,
+        badge_value,
+        ..
+    } = env.objc.borrow(this);
+    release(env, title);
+    release(env, image);
+    release(env, custom_view);
+    release(env, badge_value);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)title {
+    env.objc.borrow::<UIBarItemHostObject>(this).title
+}
+- (())setTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    set_field(env, this, title, |h| &mut h.title);
+    release(env, title);
+}
+
+- (id)image {
+    env.objc.borrow::<UIBarItemHostObject>(this).image
+}
+- (())setImage:(id)image { // UIImage*
+    set_field(env, this, image, |h| &mut h.image);
+}
+
+- (NSInteger)tag {
+    env.objc.borrow::<UIBarItemHostObject>(this).tag
+}
+- (())setTag:(NSInteger)tag {
+    env.objc.borrow_mut::<UIBarItemHostObject>(this).tag = tag;
+}
+
+- (bool)isEnabled {
+    env.objc.borrow::<UIBarItemHostObject>(this).enabled
+}
+- (())setEnabled:(bool)enabled {
+    env.objc.borrow_mut::<UIBarItemHostObject>(this).enabled = enabled;
+    item_changed(env, this);
+}
+
+@end
+
+@implementation UIBarButtonItem: UIBarItem
+
+- (id)initWithTitle:(id)title // NSString*
+              style:(UIBarButtonItemStyle)style
+             target:(id)target
+             action:(SEL)action {
+    let title: id = msg![env; title copy];
+    let host_object = env.objc.borrow_mut::<UIBarItemHostObject>(this);
+    host_object.title = title;
+    host_object.style = style;
+    host_object.target = target;
+    host_object.action = action;
+    this
+}
+
+- (id)initWithImage:(id)image // UIImage*
+              style:(UIBarButtonItemStyle)style
+             target:(id)target
+             action:(SEL)action {
+    retain(env, image);
+    let host_object = env.objc.borrow_mut::<UIBarItemHostObject>(this);
+    host_object.image = image;
+    host_object.style = style;
+    host_object.target = target;
+    host_object.action = action;
+    this
+}
+
+- (id)initWithBarButtonSystemItem:(UIBarButtonSystemItem)system_item
+                           target:(id)target
+                           action:(SEL)action {
+    let title = bar_button_system_item_title(system_item).map_or(nil, |title| {
+        get_static_str(env, title)
+    });
+    let style = if system_item == UIBarButtonSystemItemDone {
+        UIBarButtonItemStyleDone
+    } else {
+        UIBarButtonItemStylePlain
+    };
+    msg![env; this initWithTitle:title style:style target:target action:action]
+}
+
+- (id)initWithCustomView:(id)view { // UIView*
+    retain(env, view);
+    env.objc.borrow_mut::<UIBarItemHostObject>(this).custom_view = view;
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let key_ns_string = get_static_str(env, "UIIsSystemItem");
+    let is_system_item: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    if is_system_item {
+        let key_ns_string = get_static_str(env, "UISystemItemIdentifier");
+        let system_item: UIBarButtonSystemItem = msg![
+            env; coder decodeIntegerForKey:key_ns_string
+        ];
+        let this: id = msg![env; this initWithBarButtonSystemItem:system_item
+                                                           target:nil
+                                                           action:(SEL::null())];
+        // The tag still needs decoding.
+        let key_ns_string = get_static_str(env, "UITag");
+        let tag: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+        env.objc.borrow_mut::<UIBarItemHostObject>(this).tag = tag;
+        return this;
+    }
+
+    let this = init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIStyle");
+    let style: UIBarButtonItemStyle = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UICustomView");
+    let custom_view: id = msg![env; coder decodeObjectForKey:key_ns_string];
+
+    retain(env, custom_view);
+    let host_object = env.objc.borrow_mut::<UIBarItemHostObject>(this);
+    host_object.style = style;
+    host_object.custom_view = custom_view;
+    this
+}
+
+- (UIBarButtonItemStyle)style {
+    env.objc.borrow::<UIBarItemHostObject>(this).style
+}
+- (())setStyle:(UIBarButtonItemStyle)style {
+    env.objc.borrow_mut::<UIBarItemHostObject>(this).style = style;
+    item_changed(env, this);
+}
+
+- (id)target {
+    env.objc.borrow::<UIBarItemHostObject>(this).target
+}
+- (())setTarget:(id)target {
+    env.objc.borrow_mut::<UIBarItemHostObject>(this).target = target;
+}
+
+- (SEL)action {
+    env.objc.borrow::<UIBarItemHostObject>(this).action
+}
+- (())setAction:(SEL)action {
+    env.objc.borrow_mut::<UIBarItemHostObject>(this).action = action;
+}
+
+- (id)customView {
+    env.objc.borrow::<UIBarItemHostObject>(this).custom_view
+}
+- (())setCustomView:(id)view { // UIView*
+    set_field(env, this, view, |h| &mut h.custom_view);
+}
+
+@end
+
+@implementation UITabBarItem: UIBarItem
+
+- (id)initWithTitle:(id)title // NSString*
+              image:(id)image // UIImage*
+                tag:(NSInteger)tag {
+    let title: id = msg![env; title copy];
+    retain(env, image);
+    let host_object = env.objc.borrow_mut::<UIBarItemHostObject>(this);
+    host_object.title = title;
+    host_object.image = image;
+    host_object.tag = tag;
+    this
+}
+
+- (id)initWithTabBarSystemItem:(UITabBarSystemItem)system_item
+                           tag:(NSInteger)tag {
+    let title = get_static_str(env, tab_bar_system_item_title(system_item));
+    msg![env; this initWithTitle:title image:nil tag:tag]
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let key_ns_string = get_static_str(env, "UIIsSystemItem");
+    let is_system_item: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UITag");
+    let tag: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+    if is_system_item {
+        let key_ns_string = get_static_str(env, "UISystemItemIdentifier");
+        let system_item: UITabBarSystemItem = msg![
+            env; coder decodeIntegerForKey:key_ns_string
+        ];
+        return msg![env; this initWithTabBarSystemItem:system_item tag:tag];
+    }
+
+    let this = init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIBadgeValue");
+    let badge_value: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    () = msg![env; this setBadgeValue:badge_value];
+    this
+}
+
+- (id)badgeValue {
+    env.objc.borrow::<UIBarItemHostObject>(this).badge_value
+}
+- (())setBadgeValue:(id)value { // NSString*
+    if value != nil {
+        // TODO: draw badges
+        log_dbg!(
+            "TODO: [(UITabBarItem*){:?} setBadgeValue:{:?}] (badge won't be shown)",
+            this,
+            to_rust_string(env, value),
+        );
+    }
+    let value: id = msg![env; value copy];
+    set_field(env, this, value, |h| &mut h.badge_value);
+    release(env, value);
+}
+
+@end
+
+};
+
+/// The body of UIBarItem's `initWithCoder:`, for use by the subclasses that
+/// decode more properties.
+fn init_with_coder(env: &mut Environment, this: id, coder: id) -> id {
+    let key_ns_string = get_static_str(env, "UITitle");
+    let title: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIImage");
+    let image: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UITag");
+    let tag: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIEnabled");
+    let enabled: bool = if msg![env; coder containsValueForKey:key_ns_string] {
+        msg![env; coder decodeBoolForKey:key_ns_string]
+    } else {
+        true
+    };
+
+    let title: id = msg![env; title copy];
+    retain(env, image);
+    let host_object = env.objc.borrow_mut::<UIBarItemHostObject>(this);
+    host_object.title = title;
+    host_object.image = image;
+    host_object.tag = tag;
+    host_object.enabled = enabled;
+    this
+}
+
+/// For use by `UINavigationBar` and `UITabBar`: tell the item which bar is
+/// showing it, or that it's not being shown any more (if `bar` is nil).
+pub(super) fn set_bar(env: &mut Environment, item: id, bar: id) {
+    env.objc.borrow_mut::<UIBarItemHostObject>(item).bar = bar;
+}
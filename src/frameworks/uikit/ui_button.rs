@@ -0,0 +1,481 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIButton`.
+//!
+//! Unlike on the real iPhone OS, the button draws its title and image itself
+//! rather than using subviews. `titleLabel` is still provided so that the
+//! title's font can be changed.
+
+use super::ui_color;
+use super::ui_control::{
+    self, control_state, UIControlContentHorizontalAlignmentLeft,
+    UIControlContentHorizontalAlignmentRight, UIControlContentVerticalAlignmentBottom,
+    UIControlContentVerticalAlignmentTop, UIControlState, UIControlStateDisabled,
+    UIControlStateHighlighted, UIControlStateNormal, UIControlStateSelected, UIControlSubclass,
+};
+use super::ui_font::{self, UILineBreakModeWordWrap, UITextAlignmentLeft};
+use super::ui_graphics::UIGraphicsGetCurrentContext;
+use crate::frameworks::core_graphics::cg_context::{CGContextFillRect, CGContextSetRGBFillColor};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
+use crate::frameworks::foundation::NSInteger;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class,
+    ClassExports, HostObject, NSZonePtr, ObjC,
+};
+use crate::Environment;
+use std::collections::HashMap;
+
+pub type UIButtonType = NSInteger;
+pub const UIButtonTypeCustom: UIButtonType = 0;
+pub const UIButtonTypeRoundedRect: UIButtonType = 1;
+
+/// The content a button shows for a particular [UIControlState]. These are
+/// all strong references and are possibly nil.
+#[derive(Copy, Clone)]
+struct StateContent {
+    /// `NSString*`
+    title: id,
+    /// `UIColor*`
+    title_color: id,
+    /// `UIImage*`
+    image: id,
+    /// `UIImage*`
+    background_image: id,
+}
+impl Default for StateContent {
+    fn default() -> Self {
+        StateContent {
+            title: nil,
+            title_color: nil,
+            image: nil,
+            background_image: nil,
+        }
+    }
+}
+impl StateContent {
+    fn release(self, env: &mut Environment) {
+        release(env, self.title);
+        release(env, self.title_color);
+        release(env, self.image);
+        release(env, self.background_image);
+    }
+}
+
+/// State specific to `UIButton`, see [ui_control::ControlState].
+#[derive(Clone)]
+pub(super) struct ButtonState {
+    button_type: UIButtonType,
+    /// The content for each state it has been set for. Anything not set for
+    /// the current state is taken from [UIControlStateNormal].
+    content: HashMap<UIControlState, StateContent>,
+    /// `UILabel*`, nil until it's first needed. This is a strong reference.
+    title_label: id,
+}
+impl Default for ButtonState {
+    fn default() -> Self {
+        ButtonState {
+            button_type: UIButtonTypeCustom,
+            content: HashMap::new(),
+            title_label: nil,
+        }
+    }
+}
+impl ButtonState {
+    pub(super) fn release(self, env: &mut Environment) {
+        for (_, content) in self.content {
+            content.release(env);
+        }
+        release(env, self.title_label);
+    }
+}
+
+/// Get the button's state, creating it if this is the first time it's needed.
+fn button_state(objc: &mut ObjC, button: id) -> &mut ButtonState {
+    let state = control_state(objc, button);
+    if !matches!(state.subclass, UIControlSubclass::UIButton(_)) {
+        state.subclass = UIControlSubclass::UIButton(ButtonState::default());
+    }
+    let UIControlSubclass::UIButton(ref mut state) = state.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+/// Replace one of the button's pieces of content for a state, retaining the
+/// new value and releasing the old one.
+fn set_content(
+    env: &mut Environment,
+    button: id,
+    state: UIControlState,
+    value: id,
+    field: fn(&mut StateContent) -> &mut id,
+) {
+    retain(env, value);
+    let content = button_state(&mut env.objc, button)
+        .content
+        .entry(state)
+        .or_default();
+    let old = std::mem::replace(field(content), value);
+    release(env, old);
+    () = msg![env; button setNeedsDisplay];
+}
+
+/// Get one of the button's pieces of content for a state, falling back to
+/// the content for [UIControlStateNormal].
+fn get_content(
+    env: &mut Environment,
+    button: id,
+    state: UIControlState,
+    field: fn(&mut StateContent) -> &mut id,
+) -> id {
+    let content = &mut button_state(&mut env.objc, button).content;
+    let value = content.get_mut(&state).map_or(nil, |c| *field(c));
+    if value != nil {
+        return value;
+    }
+    content
+        .get_mut(&UIControlStateNormal)
+        .map_or(nil, |c| *field(c))
+}
+
+/// Default title colors, since buttons created in code don't have any.
+fn default_title_color(
+    button_type: UIButtonType,
+    state: UIControlState,
+) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+    if button_type == UIButtonTypeRoundedRect && state & UIControlStateHighlighted == 0 {
+        (0.196, 0.310, 0.522, 1.0)
+    } else {
+        (1.0, 1.0, 1.0, 1.0)
+    }
+}
+
+#[derive(Clone)]
+struct UIButtonContentHostObject {
+    content: StateContent,
+}
+impl HostObject for UIButtonContentHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIButton: UIControl
+
++ (id)buttonWithType:(UIButtonType)button_type {
+    let new: id = msg![env; this alloc];
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize { width: 0.0, height: 0.0 },
+    };
+    let new: id = msg![env; new initWithFrame:frame];
+    button_state(&mut env.objc, new).button_type = button_type;
+    autorelease(env, new)
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_control::init_with_coder(env, this, coder);
+
+    // Older nib files use a subclass rather than the button type for rounded
+    // rectangle buttons.
+    let key_ns_string = get_static_str(env, "UIButtonType");
+    let button_type: UIButtonType = if msg![env; coder containsValueForKey:key_ns_string] {
+        msg![env; coder decodeIntegerForKey:key_ns_string]
+    } else {
+        let class: Class = msg![env; this class];
+        let rounded_rect_class = env.objc.get_known_class("UIRoundedRectButton", &mut env.mem);
+        if env.objc.class_is_subclass_of(class, rounded_rect_class) {
+            UIButtonTypeRoundedRect
+        } else {
+            UIButtonTypeCustom
+        }
+    };
+    button_state(&mut env.objc, this).button_type = button_type;
+
+    let key_ns_string = get_static_str(env, "UIFont");
+    let font: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if font != nil {
+        let title_label: id = msg![env; this titleLabel];
+        () = msg![env; title_label setFont:font];
+    }
+
+    // This is a dictionary of UIButtonContent objects keyed by the state.
+    let key_ns_string = get_static_str(env, "UIButtonStatefulContent");
+    let stateful_content: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if stateful_content != nil {
+        // There are only a few combinations of states, so it's easiest to try
+        // each of them.
+        let all_states = UIControlStateHighlighted | UIControlStateDisabled | UIControlStateSelected;
+        for state in 0..=all_states {
+            let key: id = msg_class![env; NSNumber numberWithInteger:(state as NSInteger)];
+            let content: id = msg![env; stateful_content objectForKey:key];
+            if content == nil {
+                continue;
+            }
+            let StateContent {
+                title,
+                title_color,
+                image,
+                background_image,
+            } = env.objc.borrow::<UIButtonContentHostObject>(content).content;
+            () = msg![env; this setTitle:title forState:state];
+            () = msg![env; this setTitleColor:title_color forState:state];
+            () = msg![env; this setImage:image forState:state];
+            () = msg![env; this setBackgroundImage:background_image forState:state];
+        }
+    }
+
+    this
+}
+
+- (UIButtonType)buttonType {
+    button_state(&mut env.objc, this).button_type
+}
+
+- (id)titleLabel {
+    let existing = button_state(&mut env.objc, this).title_label;
+    if existing != nil {
+        return existing;
+    }
+    let title_label: id = msg_class![env; UILabel new];
+    let font: id = msg_class![env; UIFont boldSystemFontOfSize:(15.0 as CGFloat)];
+    () = msg![env; title_label setFont:font];
+    button_state(&mut env.objc, this).title_label = title_label;
+    title_label
+}
+
+- (())setTitle:(id)title // NSString*
+      forState:(UIControlState)state {
+    let title: id = msg![env; title copy];
+    set_content(env, this, state, title, |c| &mut c.title);
+    release(env, title);
+}
+- (id)titleForState:(UIControlState)state {
+    get_content(env, this, state, |c| &mut c.title)
+}
+- (id)currentTitle {
+    let state: UIControlState = msg![env; this state];
+    get_content(env, this, state, |c| &mut c.title)
+}
+
+- (())setTitleColor:(id)color // UIColor*
+           forState:(UIControlState)state {
+    set_content(env, this, state, color, |c| &mut c.title_color);
+}
+- (id)titleColorForState:(UIControlState)state {
+    get_content(env, this, state, |c| &mut c.title_color)
+}
+- (id)currentTitleColor {
+    let state: UIControlState = msg![env; this state];
+    get_content(env, this, state, |c| &mut c.title_color)
+}
+
+- (())setImage:(id)image // UIImage*
+      forState:(UIControlState)state {
+    set_content(env, this, state, image, |c| &mut c.image);
+}
+- (id)imageForState:(UIControlState)state {
+    get_content(env, this, state, |c| &mut c.image)
+}
+- (id)currentImage {
+    let state: UIControlState = msg![env; this state];
+    get_content(env, this, state, |c| &mut c.image)
+}
+
+- (())setBackgroundImage:(id)image // UIImage*
+                forState:(UIControlState)state {
+    set_content(env, this, state, image, |c| &mut c.background_image);
+}
+- (id)backgroundImageForState:(UIControlState)state {
+    get_content(env, this, state, |c| &mut c.background_image)
+}
+- (id)currentBackgroundImage {
+    let state: UIControlState = msg![env; this state];
+    get_content(env, this, state, |c| &mut c.background_image)
+}
+
+- (CGSize)sizeThatFits:(CGSize)_size {
+    let (image_size, title_size) = content_sizes(env, this);
+    CGSize {
+        width: image_size.width + title_size.width,
+        height: image_size.height.max(title_size.height),
+    }
+}
+
+- (())drawRect:(CGRect)rect {
+    let state: UIControlState = msg![env; this state];
+    let button_type = button_state(&mut env.objc, this).button_type;
+    let context = UIGraphicsGetCurrentContext(env);
+
+    let background_image = get_content(env, this, state, |c| &mut c.background_image);
+    if background_image != nil {
+        () = msg![env; background_image drawInRect:rect];
+    } else if button_type == UIButtonTypeRoundedRect {
+        // An approximation of the standard look: a white rectangle with a
+        // grey border, which turns blue when highlighted.
+        CGContextSetRGBFillColor(env, context, 0.6, 0.6, 0.6, 1.0);
+        CGContextFillRect(env, context, rect);
+        if state & UIControlStateHighlighted != 0 {
+            CGContextSetRGBFillColor(env, context, 0.1, 0.4, 0.9, 1.0);
+        } else {
+            CGContextSetRGBFillColor(env, context, 1.0, 1.0, 1.0, 1.0);
+        }
+        let inner = CGRect {
+            origin: CGPoint {
+                x: rect.origin.x + 1.0,
+                y: rect.origin.y + 1.0,
+            },
+            size: CGSize {
+                width: rect.size.width - 2.0,
+                height: rect.size.height - 2.0,
+            },
+        };
+        CGContextFillRect(env, context, inner);
+    }
+
+    // The image and the title are laid out side by side.
+    let (image_size, title_size) = content_sizes(env, this);
+    let content_width = image_size.width + title_size.width;
+    let horizontal: NSInteger = msg![env; this contentHorizontalAlignment];
+    let vertical: NSInteger = msg![env; this contentVerticalAlignment];
+    let x = match horizontal {
+        UIControlContentHorizontalAlignmentLeft => rect.origin.x,
+        UIControlContentHorizontalAlignmentRight => {
+            rect.origin.x + rect.size.width - content_width
+        }
+        _ => rect.origin.x + (rect.size.width - content_width) / 2.0,
+    };
+    let y_for_height = |height: CGFloat| match vertical {
+        UIControlContentVerticalAlignmentTop => rect.origin.y,
+        UIControlContentVerticalAlignmentBottom => rect.origin.y + rect.size.height - height,
+        _ => rect.origin.y + (rect.size.height - height) / 2.0,
+    };
+
+    let image = get_content(env, this, state, |c| &mut c.image);
+    if image != nil {
+        let image_rect = CGRect {
+            origin: CGPoint {
+                x,
+                y: y_for_height(image_size.height),
+            },
+            size: image_size,
+        };
+        () = msg![env; image drawInRect:image_rect];
+    }
+
+    let title = get_content(env, this, state, |c| &mut c.title);
+    if title != nil {
+        let title_color = get_content(env, this, state, |c| &mut c.title_color);
+        let (r, g, b, a) = if title_color != nil {
+            ui_color::get_rgba(&env.objc, title_color)
+        } else {
+            default_title_color(button_type, state)
+        };
+        CGContextSetRGBFillColor(env, context, r, g, b, a);
+        let title_label: id = msg![env; this titleLabel];
+        let font: id = msg![env; title_label font];
+        let title = to_rust_string(env, title);
+        let title_rect = CGRect {
+            origin: CGPoint {
+                x: x + image_size.width,
+                y: y_for_height(title_size.height),
+            },
+            // The width is rounded up so the title is never wrapped.
+            size: CGSize {
+                width: title_size.width.ceil() + 1.0,
+                height: title_size.height,
+            },
+        };
+        ui_font::draw_in_rect(
+            env,
+            font,
+            &title,
+            title_rect,
+            UILineBreakModeWordWrap,
+            UITextAlignmentLeft,
+        );
+    }
+}
+
+@end
+
+// An undocumented subclass that older nib files use for rounded rectangle
+// buttons.
+@implementation UIRoundedRectButton: UIButton
+@end
+
+// An undocumented type that nib files use for the content of a button for a
+// particular state.
+@implementation UIButtonContent: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UIButtonContentHostObject {
+        content: StateContent::default(),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let mut decode = |key| {
+        let key_ns_string = get_static_str(env, key);
+        let object: id = msg![env; coder decodeObjectForKey:key_ns_string];
+        retain(env, object)
+    };
+    let content = StateContent {
+        title: decode("UITitle"),
+        title_color: decode("UITitleColor"),
+        image: decode("UIImage"),
+        background_image: decode("UIBackgroundImage"),
+    };
+    env.objc.borrow_mut::<UIButtonContentHostObject>(this).content = content;
+    this
+}
+
+- (())dealloc {
+    let content = env.objc.borrow::<UIButtonContentHostObject>(this).content;
+    content.release(env);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+@end
+
+};
+
+/// Get the sizes of the current image and title, which are zero if there
+/// isn't one.
+fn content_sizes(env: &mut Environment, button: id) -> (CGSize, CGSize) {
+    let zero = CGSize {
+        width: 0.0,
+        height: 0.0,
+    };
+    let state: UIControlState = msg![env; button state];
+
+    let image = get_content(env, button, state, |c| &mut c.image);
+    let image_size = if image != nil {
+        msg![env; image size]
+    } else {
+        zero
+    };
+
+    let title = get_content(env, button, state, |c| &mut c.title);
+    let title_size = if title != nil {
+        let title_label: id = msg![env; button titleLabel];
+        let font: id = msg![env; title_label font];
+        let title = to_rust_string(env, title);
+        ui_font::size_with_font(env, font, &title, None)
+    } else {
+        zero
+    };
+
+    (image_size, title_size)
+}
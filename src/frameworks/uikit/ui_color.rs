@@ -5,15 +5,19 @@
  */
 //! `UIColor`.
 
+use crate::frameworks::core_graphics::cg_color::CGColorRef;
 use crate::frameworks::core_graphics::CGFloat;
-use crate::mem::MutPtr;
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::NSUInteger;
+use crate::mem::{guest_size_of, ConstPtr, MutPtr};
 use crate::objc::{
-    autorelease, id, msg, msg_class, objc_classes, ClassExports, HostObject, NSZonePtr, SEL,
+    autorelease, id, msg, msg_class, objc_classes, AnyHostObject, ClassExports, HostObject,
+    NSZonePtr, ObjC, SEL,
 };
 use crate::Environment;
 use std::collections::HashMap;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     standard_colors: HashMap<SEL, id>,
 }
@@ -40,10 +44,15 @@ fn get_standard_color(
     }
 }
 
+#[derive(Clone)]
 struct UIColorHostObject {
     rgba: (CGFloat, CGFloat, CGFloat, CGFloat),
 }
-impl HostObject for UIColorHostObject {}
+impl HostObject for UIColorHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -114,6 +123,61 @@ pub const CLASSES: ClassExports = objc_classes! {
     this
 }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let get_float = |env: &mut Environment, key: &'static str| -> Option<CGFloat> {
+        let key_ns_string = get_static_str(env, key);
+        if msg![env; coder containsValueForKey:key_ns_string] {
+            Some(msg![env; coder decodeFloatForKey:key_ns_string])
+        } else {
+            None
+        }
+    };
+    let alpha = get_float(env, "UIAlpha").unwrap_or(1.0);
+    if let Some(r) = get_float(env, "UIRed") {
+        let g = get_float(env, "UIGreen").unwrap_or(0.0);
+        let b = get_float(env, "UIBlue").unwrap_or(0.0);
+        return msg![env; this initWithRed:r green:g blue:b alpha:alpha];
+    }
+    if let Some(w) = get_float(env, "UIWhite") {
+        return msg![env; this initWithWhite:w alpha:alpha];
+    }
+
+    // Nib files compiled from Interface Builder documents might instead have
+    // the components as a string, using AppKit's NSColor keys.
+    for (key, component_count) in [("NSRGB", 3), ("NSWhite", 1)] {
+        let key_ns_string = get_static_str(env, key);
+        let length_ptr: MutPtr<NSUInteger> = env.mem.alloc(guest_size_of::<NSUInteger>()).cast();
+        let bytes: ConstPtr<u8> = msg![env; coder decodeBytesForKey:key_ns_string
+                                                     returnedLength:length_ptr];
+        let length = env.mem.read(length_ptr);
+        env.mem.free(length_ptr.cast());
+        if bytes.is_null() {
+            continue;
+        }
+        let string = String::from_utf8_lossy(env.mem.bytes_at(bytes, length)).into_owned();
+        let mut components: Vec<CGFloat> = string
+            .trim_end_matches('\0')
+            .split_whitespace()
+            .filter_map(|component| component.parse().ok())
+            .collect();
+        if components.len() < component_count {
+            continue;
+        }
+        components.resize(component_count + 1, 1.0);
+        let rgba = match components[..] {
+            [r, g, b, a] => (r, g, b, a),
+            [w, a] => (w, w, w, a),
+            _ => unreachable!(),
+        };
+        env.objc.borrow_mut::<UIColorHostObject>(this).rgba = rgba;
+        return this;
+    }
+
+    log!("Warning: couldn't decode UIColor {:?}, using black", this);
+    msg![env; this initWithWhite:0.0 alpha:1.0]
+}
+
 - (bool)getRed:(MutPtr<CGFloat>)r
          green:(MutPtr<CGFloat>)g
           blue:(MutPtr<CGFloat>)b
@@ -126,6 +190,17 @@ pub const CLASSES: ClassExports = objc_classes! {
     true
 }
 
+- (CGColorRef)CGColor {
+    // See cg_color.rs for why this is the same object.
+    this
+}
+
 @end
 
 };
+
+/// Shortcut for host code: get the components of a `UIColor` (or a
+/// `CGColorRef`).
+pub fn get_rgba(objc: &ObjC, color: id) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+    objc.borrow::<UIColorHostObject>(color).rgba
+}
@@ -4,8 +4,113 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `UIControl`.
+//!
+//! Controls track the touches they receive and turn them into control events,
+//! which are sent as actions to the registered targets via
+//! `-[UIApplication sendAction:to:from:forEvent:]`.
 
-use crate::objc::{objc_classes, ClassExports};
+use super::ui_button::ButtonState;
+use super::ui_view::{UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::{NSInteger, NSUInteger};
+use crate::objc::{id, msg, msg_class, nil, objc_classes, ClassExports, ObjC, SEL};
+use crate::Environment;
+
+pub type UIControlEvents = NSUInteger;
+pub const UIControlEventTouchDown: UIControlEvents = 1 << 0;
+#[allow(dead_code)]
+pub const UIControlEventTouchDownRepeat: UIControlEvents = 1 << 1;
+pub const UIControlEventTouchDragInside: UIControlEvents = 1 << 2;
+pub const UIControlEventTouchDragOutside: UIControlEvents = 1 << 3;
+pub const UIControlEventTouchDragEnter: UIControlEvents = 1 << 4;
+pub const UIControlEventTouchDragExit: UIControlEvents = 1 << 5;
+pub const UIControlEventTouchUpInside: UIControlEvents = 1 << 6;
+pub const UIControlEventTouchUpOutside: UIControlEvents = 1 << 7;
+#[allow(dead_code)]
+pub const UIControlEventTouchCancel: UIControlEvents = 1 << 8;
+#[allow(dead_code)]
+pub const UIControlEventValueChanged: UIControlEvents = 1 << 12;
+
+pub type UIControlState = NSUInteger;
+pub const UIControlStateNormal: UIControlState = 0;
+pub const UIControlStateHighlighted: UIControlState = 1 << 0;
+pub const UIControlStateDisabled: UIControlState = 1 << 1;
+pub const UIControlStateSelected: UIControlState = 1 << 2;
+
+pub type UIControlContentHorizontalAlignment = NSInteger;
+pub const UIControlContentHorizontalAlignmentCenter: UIControlContentHorizontalAlignment = 0;
+pub const UIControlContentHorizontalAlignmentLeft: UIControlContentHorizontalAlignment = 1;
+pub const UIControlContentHorizontalAlignmentRight: UIControlContentHorizontalAlignment = 2;
+
+pub type UIControlContentVerticalAlignment = NSInteger;
+pub const UIControlContentVerticalAlignmentCenter: UIControlContentVerticalAlignment = 0;
+pub const UIControlContentVerticalAlignmentTop: UIControlContentVerticalAlignment = 1;
+pub const UIControlContentVerticalAlignmentBottom: UIControlContentVerticalAlignment = 2;
+
+/// How far outside its bounds a touch that began in a control can go before
+/// it's considered to have left the control. UIKit is similarly forgiving.
+const TOUCH_TRACKING_MARGIN: CGFloat = 70.0;
+
+/// State specific to `UIControl`, see [UIViewSubclass].
+#[derive(Clone)]
+pub(super) struct ControlState {
+    /// Target-action pairs and the events they are sent for. The targets are
+    /// weak references.
+    targets: Vec<(id, SEL, UIControlEvents)>,
+    enabled: bool,
+    selected: bool,
+    highlighted: bool,
+    /// Whether a touch that began in the control is being tracked.
+    tracking: bool,
+    /// Whether the touch being tracked is inside the control.
+    touch_inside: bool,
+    content_horizontal_alignment: UIControlContentHorizontalAlignment,
+    content_vertical_alignment: UIControlContentVerticalAlignment,
+    pub(super) subclass: UIControlSubclass,
+}
+impl Default for ControlState {
+    fn default() -> Self {
+        ControlState {
+            targets: Vec::new(),
+            enabled: true,
+            selected: false,
+            highlighted: false,
+            tracking: false,
+            touch_inside: false,
+            content_horizontal_alignment: UIControlContentHorizontalAlignmentCenter,
+            content_vertical_alignment: UIControlContentVerticalAlignmentCenter,
+            subclass: UIControlSubclass::Other,
+        }
+    }
+}
+impl ControlState {
+    pub(super) fn release(self, env: &mut Environment) {
+        match self.subclass {
+            UIControlSubclass::Other => (),
+            UIControlSubclass::UIButton(button_state) => button_state.release(env),
+        }
+    }
+}
+
+/// State specific to the UIKit classes that inherit from `UIControl`.
+#[derive(Clone)]
+pub(super) enum UIControlSubclass {
+    Other,
+    UIButton(ButtonState),
+}
+
+/// Get the control's state, creating it if this is the first time it's needed.
+pub(super) fn control_state(objc: &mut ObjC, control: id) -> &mut ControlState {
+    let host_object = objc.borrow_mut::<UIViewHostObject>(control);
+    if !matches!(host_object.subclass, UIViewSubclass::UIControl(_)) {
+        host_object.subclass = UIViewSubclass::UIControl(ControlState::default());
+    }
+    let UIViewSubclass::UIControl(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    state
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -13,7 +118,205 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // abstract class
 @implementation UIControl: UIView
-// TODO
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    init_with_coder(env, this, coder)
+}
+
+- (())addTarget:(id)target
+         action:(SEL)action
+forControlEvents:(UIControlEvents)events {
+    let state = control_state(&mut env.objc, this);
+    if let Some(existing) = state
+        .targets
+        .iter_mut()
+        .find(|entry| entry.0 == target && entry.1 == action)
+    {
+        existing.2 |= events;
+    } else {
+        state.targets.push((target, action, events));
+    }
+}
+- (())removeTarget:(id)target
+            action:(SEL)action // possibly NULL
+  forControlEvents:(UIControlEvents)events {
+    let state = control_state(&mut env.objc, this);
+    // A nil target or NULL action means all of them.
+    for (t, a, e) in state.targets.iter_mut() {
+        if (target == nil || *t == target) && (action.is_null() || *a == action) {
+            *e &= !events;
+        }
+    }
+    state.targets.retain(|&(_, _, e)| e != 0);
+}
+
+- (())sendAction:(SEL)action
+               to:(id)target
+         forEvent:(id)event { // UIEvent*
+    let app: id = msg_class![env; UIApplication sharedApplication];
+    let _: bool = msg![env; app sendAction:action to:target from:this forEvent:event];
+}
+- (())sendActionsForControlEvents:(UIControlEvents)events {
+    send_actions(env, this, events, nil);
+}
+
+- (UIControlState)state {
+    let state = control_state(&mut env.objc, this);
+    let mut control_state = UIControlStateNormal;
+    if state.highlighted {
+        control_state |= UIControlStateHighlighted;
+    }
+    if !state.enabled {
+        control_state |= UIControlStateDisabled;
+    }
+    if state.selected {
+        control_state |= UIControlStateSelected;
+    }
+    control_state
+}
+
+// The appearance of subclasses depends on these, so they redraw when changed.
+- (bool)isEnabled {
+    control_state(&mut env.objc, this).enabled
+}
+- (())setEnabled:(bool)enabled {
+    control_state(&mut env.objc, this).enabled = enabled;
+    () = msg![env; this setNeedsDisplay];
+}
+- (bool)isSelected {
+    control_state(&mut env.objc, this).selected
+}
+- (())setSelected:(bool)selected {
+    control_state(&mut env.objc, this).selected = selected;
+    () = msg![env; this setNeedsDisplay];
+}
+- (bool)isHighlighted {
+    control_state(&mut env.objc, this).highlighted
+}
+- (())setHighlighted:(bool)highlighted {
+    control_state(&mut env.objc, this).highlighted = highlighted;
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (UIControlContentHorizontalAlignment)contentHorizontalAlignment {
+    control_state(&mut env.objc, this).content_horizontal_alignment
+}
+- (())setContentHorizontalAlignment:(UIControlContentHorizontalAlignment)alignment {
+    control_state(&mut env.objc, this).content_horizontal_alignment = alignment;
+    () = msg![env; this setNeedsDisplay];
+}
+- (UIControlContentVerticalAlignment)contentVerticalAlignment {
+    control_state(&mut env.objc, this).content_vertical_alignment
+}
+- (())setContentVerticalAlignment:(UIControlContentVerticalAlignment)alignment {
+    control_state(&mut env.objc, this).content_vertical_alignment = alignment;
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (bool)isTracking {
+    control_state(&mut env.objc, this).tracking
+}
+- (bool)isTouchInside {
+    control_state(&mut env.objc, this).touch_inside
+}
+
+// UIResponder implementation
+- (())touchesBegan:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let state = control_state(&mut env.objc, this);
+    if !state.enabled || state.tracking {
+        return;
+    }
+    state.tracking = true;
+    state.touch_inside = true;
+    () = msg![env; this setHighlighted:true];
+    send_actions(env, this, UIControlEventTouchDown, event);
+}
+- (())touchesMoved:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    if !control_state(&mut env.objc, this).tracking {
+        return;
+    }
+    let inside = touch_is_inside(env, this, touches);
+    let state = control_state(&mut env.objc, this);
+    let was_inside = std::mem::replace(&mut state.touch_inside, inside);
+    if inside != was_inside {
+        () = msg![env; this setHighlighted:inside];
+    }
+    let events = match (was_inside, inside) {
+        (false, true) => UIControlEventTouchDragEnter | UIControlEventTouchDragInside,
+        (true, true) => UIControlEventTouchDragInside,
+        (true, false) => UIControlEventTouchDragExit | UIControlEventTouchDragOutside,
+        (false, false) => UIControlEventTouchDragOutside,
+    };
+    send_actions(env, this, events, event);
+}
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    if !control_state(&mut env.objc, this).tracking {
+        return;
+    }
+    let inside = touch_is_inside(env, this, touches);
+    let state = control_state(&mut env.objc, this);
+    state.tracking = false;
+    state.touch_inside = false;
+    () = msg![env; this setHighlighted:false];
+    let events = if inside {
+        UIControlEventTouchUpInside
+    } else {
+        UIControlEventTouchUpOutside
+    };
+    send_actions(env, this, events, event);
+}
+
 @end
 
 };
+
+/// The body of UIControl's `initWithCoder:`, for use by the subclasses that
+/// decode more properties.
+pub(super) fn init_with_coder(env: &mut Environment, this: id, coder: id) -> id {
+    let this = super::ui_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIDisabled");
+    let disabled: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UISelected");
+    let selected: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIContentHorizontalAlignment");
+    let horizontal: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIContentVerticalAlignment");
+    let vertical: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+
+    let state = control_state(&mut env.objc, this);
+    state.enabled = !disabled;
+    state.selected = selected;
+    state.content_horizontal_alignment = horizontal;
+    state.content_vertical_alignment = vertical;
+
+    this
+}
+
+/// Check whether a touch the control is tracking is still inside it.
+fn touch_is_inside(env: &mut Environment, control: id, touches: id) -> bool {
+    let touch: id = msg![env; touches anyObject];
+    let point: CGPoint = msg![env; touch locationInView:control];
+    let bounds: CGRect = msg![env; control bounds];
+    point.x >= bounds.origin.x - TOUCH_TRACKING_MARGIN
+        && point.y >= bounds.origin.y - TOUCH_TRACKING_MARGIN
+        && point.x < bounds.origin.x + bounds.size.width + TOUCH_TRACKING_MARGIN
+        && point.y < bounds.origin.y + bounds.size.height + TOUCH_TRACKING_MARGIN
+}
+
+/// Send the actions registered for any of `events`.
+fn send_actions(env: &mut Environment, control: id, events: UIControlEvents, event: id) {
+    let actions: Vec<(id, SEL)> = control_state(&mut env.objc, control)
+        .targets
+        .iter()
+        .filter(|&&(_, _, e)| e & events != 0)
+        .map(|&(target, action, _)| (target, action))
+        .collect();
+    for (target, action) in actions {
+        () = msg![env; control sendAction:action to:target forEvent:event];
+    }
+}
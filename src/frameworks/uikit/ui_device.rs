@@ -22,7 +22,7 @@ pub const UIDeviceOrientationFaceUp: UIDeviceOrientation = 5;
 #[allow(dead_code)]
 pub const UIDeviceOrientationFaceDown: UIDeviceOrientation = 6;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     current_device: Option<id>,
 }
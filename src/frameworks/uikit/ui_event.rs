@@ -5,19 +5,26 @@
  */
 //! `UIEvent`.
 
-use crate::frameworks::core_graphics::CGPoint;
+use super::ui_touch;
+use crate::frameworks::foundation::{ns_set, NSTimeInterval};
 use crate::objc::{
-    id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg_class, objc_classes, release, retain, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr,
 };
 use crate::Environment;
 
+#[derive(Clone)]
 struct UIEventHostObject {
-    /// `NSSet<UITouch*>*`
-    touches: id,
-    /// `UIView*`
-    view: id,
+    /// Strong references to the `UITouch*`es for every finger touching the
+    /// screen, including ones that didn't change.
+    all_touches: Vec<id>,
+    timestamp: NSTimeInterval,
+}
+impl HostObject for UIEventHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
 }
-impl HostObject for UIEventHostObject {}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -27,26 +34,36 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 + (id)allocWithZone:(NSZonePtr)_zone {
     let host_object = Box::new(UIEventHostObject {
-        touches: nil,
-        view: nil,
+        all_touches: Vec::new(),
+        timestamp: 0.0,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
 - (())dealloc {
-    let &UIEventHostObject { touches, view } = env.objc.borrow(this);
-    release(env, touches);
-    release(env, view);
+    let all_touches = std::mem::take(&mut env.objc.borrow_mut::<UIEventHostObject>(this).all_touches);
+    for touch in all_touches {
+        release(env, touch);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)allTouches {
+    let all_touches = env.objc.borrow::<UIEventHostObject>(this).all_touches.clone();
+    touches_to_set(env, all_touches)
 }
 
 - (id)touchesForView:(id)view {
-    let &UIEventHostObject { touches, .. } = env.objc.borrow(this);
-    // TODO: broken for multi-touch
-    // FIXME: this will be wrong sometimes. locationInView: currently panics
-    // if it would be, at least.
-    let touch: id = msg![env; touches anyObject];
-    let _: CGPoint = msg![env; touch locationInView:view];
-    touches
+    let all_touches = env.objc.borrow::<UIEventHostObject>(this).all_touches.clone();
+    let touches = all_touches
+        .into_iter()
+        .filter(|&touch| ui_touch::view_for_touch(env, touch) == view)
+        .collect();
+    touches_to_set(env, touches)
+}
+
+- (NSTimeInterval)timestamp {
+    env.objc.borrow::<UIEventHostObject>(this).timestamp
 }
 
 // TODO: more accessors
@@ -55,14 +72,28 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
-/// For use by [super::ui_touch]: create a `UIEvent` with a set of `UITouch*`
-/// and the view it was originally sent to.
-pub(super) fn new_event(env: &mut Environment, touches: id, view: id) -> id {
+/// Create an autoreleased `NSSet*` of `UITouch*`es.
+fn touches_to_set(env: &mut Environment, touches: Vec<id>) -> id {
+    for &touch in &touches {
+        retain(env, touch);
+    }
+    let set = ns_set::from_vec(env, touches);
+    autorelease(env, set)
+}
+
+/// For use by [super::ui_touch]: create a `UIEvent` with the `UITouch*`es of
+/// every finger touching the screen.
+pub(super) fn new_event(
+    env: &mut Environment,
+    all_touches: Vec<id>,
+    timestamp: NSTimeInterval,
+) -> id {
     let event: id = msg_class![env; UIEvent alloc];
-    retain(env, touches);
-    retain(env, view);
+    for &touch in &all_touches {
+        retain(env, touch);
+    }
     let borrow = env.objc.borrow_mut::<UIEventHostObject>(event);
-    borrow.touches = touches;
-    borrow.view = view;
+    borrow.all_touches = all_touches;
+    borrow.timestamp = timestamp;
     event
 }
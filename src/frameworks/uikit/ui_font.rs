@@ -9,11 +9,15 @@ use super::ui_graphics::UIGraphicsGetCurrentContext;
 use crate::font::{Font, TextAlignment, WrapMode};
 use crate::frameworks::core_graphics::cg_bitmap_context::CGBitmapContextDrawer;
 use crate::frameworks::core_graphics::{CGFloat, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
 use crate::frameworks::foundation::NSInteger;
-use crate::objc::{autorelease, id, objc_classes, ClassExports, HostObject};
+use crate::objc::{
+    autorelease, id, msg, nil, objc_classes, AnyHostObject, Class, ClassExports, HostObject,
+    NSZonePtr, ObjC,
+};
 use crate::Environment;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub(super) struct State {
     regular: Option<Font>,
     bold: Option<Font>,
@@ -29,11 +33,16 @@ enum FontKind {
     Italic,
 }
 
+#[derive(Clone)]
 struct UIFontHostObject {
     size: CGFloat,
     kind: FontKind,
 }
-impl HostObject for UIFontHostObject {}
+impl HostObject for UIFontHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 /// Line break mode.
 ///
@@ -66,47 +75,93 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @implementation UIFont: NSObject
 
-+ (id)systemFontOfSize:(CGFloat)size {
-    // Cache for later use
-    if env.framework_state.uikit.ui_font.regular.is_none() {
-        env.framework_state.uikit.ui_font.regular = Some(Font::sans_regular());
-    }
-    let host_object = UIFontHostObject {
-        size,
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UIFontHostObject {
+        size: 0.0,
         kind: FontKind::Regular,
-    };
-    let new = env.objc.alloc_object(this, Box::new(host_object), &mut env.mem);
-    autorelease(env, new)
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)systemFontOfSize:(CGFloat)size {
+    new_font(env, this, size, FontKind::Regular)
 }
 + (id)boldSystemFontOfSize:(CGFloat)size {
-    // Cache for later use
-    if env.framework_state.uikit.ui_font.bold.is_none() {
-        env.framework_state.uikit.ui_font.bold = Some(Font::sans_bold());
-    }
-    let host_object = UIFontHostObject {
-        size,
-        kind: FontKind::Bold,
-    };
-    let new = env.objc.alloc_object(this, Box::new(host_object), &mut env.mem);
-    autorelease(env, new)
+    new_font(env, this, size, FontKind::Bold)
 }
 + (id)italicSystemFontOfSize:(CGFloat)size {
-    // Cache for later use
-    if env.framework_state.uikit.ui_font.italic.is_none() {
-        env.framework_state.uikit.ui_font.italic = Some(Font::sans_italic());
-    }
-    let host_object = UIFontHostObject {
-        size,
-        kind: FontKind::Italic,
+    new_font(env, this, size, FontKind::Italic)
+}
++ (id)fontWithName:(id)name // NSString*
+              size:(CGFloat)size {
+    let kind = kind_for_font_name(&to_rust_string(env, name));
+    new_font(env, this, size, kind)
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let key_ns_string = get_static_str(env, "UIFontName");
+    let name: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    let kind = if name != nil {
+        kind_for_font_name(&to_rust_string(env, name))
+    } else {
+        FontKind::Regular
     };
-    let new = env.objc.alloc_object(this, Box::new(host_object), &mut env.mem);
-    autorelease(env, new)
+    let key_ns_string = get_static_str(env, "UIFontPointSize");
+    let size: CGFloat = msg![env; coder decodeFloatForKey:key_ns_string];
+
+    load_font(&mut env.framework_state.uikit.ui_font, kind);
+    *env.objc.borrow_mut(this) = UIFontHostObject { size, kind };
+    this
+}
+
+- (CGFloat)pointSize {
+    env.objc.borrow::<UIFontHostObject>(this).size
+}
+- (id)fontWithSize:(CGFloat)size {
+    let class = ObjC::read_isa(this, &env.mem);
+    let kind = env.objc.borrow::<UIFontHostObject>(this).kind;
+    new_font(env, class, size, kind)
 }
 
 @end
 
 };
 
+/// Make sure the font for `kind` is cached for later use.
+fn load_font(state: &mut State, kind: FontKind) {
+    let (slot, constructor): (_, fn() -> Font) = match kind {
+        FontKind::Regular => (&mut state.regular, Font::sans_regular),
+        FontKind::Bold => (&mut state.bold, Font::sans_bold),
+        FontKind::Italic => (&mut state.italic, Font::sans_italic),
+    };
+    if slot.is_none() {
+        *slot = Some(constructor());
+    }
+}
+
+fn new_font(env: &mut Environment, class: Class, size: CGFloat, kind: FontKind) -> id {
+    load_font(&mut env.framework_state.uikit.ui_font, kind);
+    let host_object = UIFontHostObject { size, kind };
+    let new = env
+        .objc
+        .alloc_object(class, Box::new(host_object), &mut env.mem);
+    autorelease(env, new)
+}
+
+/// Pick the closest of the available fonts for a named font, e.g.
+/// `Helvetica-BoldOblique`. Every font is substituted by one of the system
+/// font's styles.
+fn kind_for_font_name(name: &str) -> FontKind {
+    if name.contains("Bold") {
+        FontKind::Bold
+    } else if name.contains("Italic") || name.contains("Oblique") {
+        FontKind::Italic
+    } else {
+        FontKind::Regular
+    }
+}
+
 fn convert_line_break_mode(ui_mode: UILineBreakMode) -> WrapMode {
     match ui_mode {
         UILineBreakModeWordWrap => WrapMode::Word,
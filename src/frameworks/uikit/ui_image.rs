@@ -5,20 +5,27 @@
  */
 //! `UIImage`.
 
+use super::ui_graphics::UIGraphicsGetCurrentContext;
+use crate::frameworks::core_graphics::cg_bitmap_context::draw_image_flipped;
 use crate::frameworks::core_graphics::cg_image::{self, CGImageRef, CGImageRelease};
-use crate::frameworks::core_graphics::CGSize;
+use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
 use crate::frameworks::foundation::{ns_string, NSInteger};
 use crate::fs::GuestPath;
 use crate::image::Image;
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr,
 };
 
+#[derive(Clone)]
 struct UIImageHostObject {
     cg_image: CGImageRef,
 }
-impl HostObject for UIImageHostObject {}
+impl HostObject for UIImageHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -67,6 +74,21 @@ pub const CLASSES: ClassExports = objc_classes! {
     this
 }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    // Nib files refer to images in the app bundle by name.
+    let key_ns_string = ns_string::get_static_str(env, "UIResourceName");
+    let name: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if name == nil {
+        log!("Warning: couldn't decode UIImage {:?}, returning nil", this);
+        release(env, this);
+        return nil;
+    }
+    let bundle: id = msg_class![env; NSBundle mainBundle];
+    let path: id = msg![env; bundle pathForResource:name ofType:nil];
+    msg![env; this initWithContentsOfFile:path]
+}
+
 // TODO: more init methods
 // TODO: more accessors
 
@@ -89,6 +111,22 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
+// These draw into the current UIKit graphics context.
+- (())drawAtPoint:(CGPoint)point {
+    let size: CGSize = msg![env; this size];
+    let rect = CGRect { origin: point, size };
+    msg![env; this drawInRect:rect]
+}
+- (())drawInRect:(CGRect)rect {
+    let cg_image = env.objc.borrow::<UIImageHostObject>(this).cg_image;
+    let context = UIGraphicsGetCurrentContext(env);
+    draw_image_flipped(env, context, rect, cg_image);
+}
+
+@end
+
+// An undocumented subclass that nib files use for images.
+@implementation UIImageNibPlaceholder: UIImage
 @end
 
 };
@@ -5,18 +5,28 @@
  */
 //! `UIImageView`.
 
+use super::ui_view::{self, UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::cg_image::CGImageRef;
 use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
-use crate::objc::{id, msg, objc_classes, ClassExports};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::objc::{id, msg, nil, objc_classes, release, retain, ClassExports};
 
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation UIImageView: UIView
-// TODO: actually display images etc. Currently touchHLE can't draw views, so
-// there's no point implementing that yet.
 
-// initWithCoder: intentionally not overridden yet
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view::init_with_coder(env, this, coder);
+    let key_ns_string = get_static_str(env, "UIImage");
+    let image: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if image != nil {
+        () = msg![env; this setImage:image];
+    }
+    this
+}
 
 - (id)initWithImage:(id)image { // UIImage*
     let size: CGSize = msg![env; image size];
@@ -24,7 +34,34 @@ pub const CLASSES: ClassExports = objc_classes! {
         origin: CGPoint { x: 0.0, y: 0.0 },
         size
     };
-    msg![env; this initWithFrame:frame]
+    let this: id = msg![env; this initWithFrame:frame];
+    () = msg![env; this setImage:image];
+    this
+}
+
+- (id)image {
+    match env.objc.borrow::<UIViewHostObject>(this).subclass {
+        UIViewSubclass::UIImageView { image } => image,
+        _ => nil,
+    }
+}
+- (())setImage:(id)image { // UIImage*
+    retain(env, image);
+    let host_object = env.objc.borrow_mut::<UIViewHostObject>(this);
+    let old = std::mem::replace(
+        &mut host_object.subclass,
+        UIViewSubclass::UIImageView { image },
+    );
+    if let UIViewSubclass::UIImageView { image: old_image } = old {
+        release(env, old_image);
+    }
+
+    // The image is displayed by the layer, see the compositor.
+    // TODO: support contentMode, the image is always stretched to fill the
+    //       view at the moment.
+    let cg_image: CGImageRef = msg![env; image CGImage];
+    let layer: id = msg![env; this layer];
+    () = msg![env; layer setContents:cg_image];
 }
 
 @end
@@ -0,0 +1,258 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UILabel`.
+
+use super::ui_color;
+use super::ui_font::{
+    self, UILineBreakMode, UILineBreakModeCharacterWrap, UILineBreakModeTailTruncation,
+    UILineBreakModeWordWrap, UITextAlignment, UITextAlignmentLeft,
+};
+use super::ui_graphics::UIGraphicsGetCurrentContext;
+use super::ui_view::{self, UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::cg_context::CGContextSetRGBFillColor;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
+use crate::frameworks::foundation::NSInteger;
+use crate::objc::{id, msg, msg_class, nil, objc_classes, release, retain, ClassExports};
+use crate::Environment;
+
+/// State specific to `UILabel`, see [UIViewSubclass].
+#[derive(Clone)]
+pub(super) struct LabelState {
+    /// `NSString*`, possibly nil. This is a strong reference.
+    text: id,
+    /// `UIFont*`. This is a strong reference.
+    font: id,
+    /// `UIColor*`. This is a strong reference.
+    text_color: id,
+    text_alignment: UITextAlignment,
+    /// 0 means there is no limit.
+    number_of_lines: NSInteger,
+    line_break_mode: UILineBreakMode,
+}
+impl LabelState {
+    pub(super) fn release(self, env: &mut Environment) {
+        release(env, self.text);
+        release(env, self.font);
+        release(env, self.text_color);
+    }
+}
+
+/// Get the label's state, creating it with the default font and color if this
+/// is the first time it's needed.
+pub(super) fn label_state(env: &mut Environment, label: id) -> &mut LabelState {
+    if !matches!(
+        env.objc.borrow::<UIViewHostObject>(label).subclass,
+        UIViewSubclass::UILabel(_)
+    ) {
+        let font: id = msg_class![env; UIFont systemFontOfSize:(17.0 as CGFloat)];
+        retain(env, font);
+        let text_color: id = msg_class![env; UIColor blackColor];
+        retain(env, text_color);
+        env.objc.borrow_mut::<UIViewHostObject>(label).subclass =
+            UIViewSubclass::UILabel(LabelState {
+                text: nil,
+                font,
+                text_color,
+                text_alignment: UITextAlignmentLeft,
+                number_of_lines: 1,
+                line_break_mode: UILineBreakModeTailTruncation,
+            });
+    }
+    let UIViewSubclass::UILabel(ref mut state) =
+        env.objc.borrow_mut::<UIViewHostObject>(label).subclass
+    else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UILabel: UIView
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIText");
+    let text: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    () = msg![env; this setText:text];
+
+    let key_ns_string = get_static_str(env, "UIFont");
+    let font: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if font != nil {
+        () = msg![env; this setFont:font];
+    }
+
+    let key_ns_string = get_static_str(env, "UITextColor");
+    let text_color: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if text_color != nil {
+        () = msg![env; this setTextColor:text_color];
+    }
+
+    let key_ns_string = get_static_str(env, "UITextAlignment");
+    let alignment: UITextAlignment = msg![env; coder decodeIntegerForKey:key_ns_string];
+    () = msg![env; this setTextAlignment:alignment];
+
+    let key_ns_string = get_static_str(env, "UINumberOfLines");
+    if msg![env; coder containsValueForKey:key_ns_string] {
+        let lines: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+        () = msg![env; this setNumberOfLines:lines];
+    }
+
+    let key_ns_string = get_static_str(env, "UILineBreakMode");
+    if msg![env; coder containsValueForKey:key_ns_string] {
+        let mode: UILineBreakMode = msg![env; coder decodeIntegerForKey:key_ns_string];
+        () = msg![env; this setLineBreakMode:mode];
+    }
+
+    this
+}
+
+- (id)text {
+    label_state(env, this).text
+}
+- (())setText:(id)text { // NSString*
+    let text: id = msg![env; text copy];
+    let old = std::mem::replace(&mut label_state(env, this).text, text);
+    release(env, old);
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (id)font {
+    label_state(env, this).font
+}
+- (())setFont:(id)font { // UIFont*
+    if font == nil {
+        // nil means the default font
+        let font: id = msg_class![env; UIFont systemFontOfSize:(17.0 as CGFloat)];
+        return msg![env; this setFont:font];
+    }
+    retain(env, font);
+    let old = std::mem::replace(&mut label_state(env, this).font, font);
+    release(env, old);
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (id)textColor {
+    label_state(env, this).text_color
+}
+- (())setTextColor:(id)color { // UIColor*
+    if color == nil {
+        // nil means the default color
+        let color: id = msg_class![env; UIColor blackColor];
+        return msg![env; this setTextColor:color];
+    }
+    retain(env, color);
+    let old = std::mem::replace(&mut label_state(env, this).text_color, color);
+    release(env, old);
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (UITextAlignment)textAlignment {
+    label_state(env, this).text_alignment
+}
+- (())setTextAlignment:(UITextAlignment)alignment {
+    label_state(env, this).text_alignment = alignment;
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (NSInteger)numberOfLines {
+    label_state(env, this).number_of_lines
+}
+- (())setNumberOfLines:(NSInteger)lines {
+    label_state(env, this).number_of_lines = lines;
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (UILineBreakMode)lineBreakMode {
+    label_state(env, this).line_break_mode
+}
+- (())setLineBreakMode:(UILineBreakMode)mode {
+    label_state(env, this).line_break_mode = mode;
+    () = msg![env; this setNeedsDisplay];
+}
+
+// TODO: support these
+- (())setAdjustsFontSizeToFitWidth:(bool)_adjusts {}
+- (())setMinimumFontSize:(CGFloat)_size {}
+- (())setShadowColor:(id)_color {} // UIColor*
+- (())setShadowOffset:(CGSize)_offset {}
+
+- (CGSize)sizeThatFits:(CGSize)size {
+    let &mut LabelState { text, font, number_of_lines, .. } = label_state(env, this);
+    if text == nil {
+        return CGSize { width: 0.0, height: 0.0 };
+    }
+    let text = to_rust_string(env, text);
+    if number_of_lines == 1 {
+        ui_font::size_with_font(env, font, &text, None)
+    } else {
+        ui_font::size_with_font(env, font, &text, Some((size, UILineBreakModeWordWrap)))
+    }
+}
+
+- (())drawTextInRect:(CGRect)rect {
+    let &mut LabelState {
+        text,
+        font,
+        text_color,
+        text_alignment,
+        number_of_lines,
+        line_break_mode,
+    } = label_state(env, this);
+    if text == nil {
+        return;
+    }
+    let text = to_rust_string(env, text);
+
+    let (r, g, b, a) = ui_color::get_rgba(&env.objc, text_color);
+    let context = UIGraphicsGetCurrentContext(env);
+    CGContextSetRGBFillColor(env, context, r, g, b, a);
+
+    // Only wrapping is supported when drawing text, so text that doesn't fit is
+    // clipped rather than truncated.
+    // TODO: truncation, limits on the number of lines other than 1
+    let line_break_mode = if line_break_mode == UILineBreakModeCharacterWrap {
+        UILineBreakModeCharacterWrap
+    } else {
+        UILineBreakModeWordWrap
+    };
+    let text_size = if number_of_lines == 1 {
+        ui_font::size_with_font(env, font, &text, None)
+    } else {
+        ui_font::size_with_font(env, font, &text, Some((rect.size, line_break_mode)))
+    };
+
+    // The text is vertically centered. A single line must not be wrapped even
+    // if it's too wide, so the width is adjusted to fit it.
+    let text_rect = CGRect {
+        origin: CGPoint {
+            x: rect.origin.x,
+            y: rect.origin.y + (rect.size.height - text_size.height) / 2.0,
+        },
+        size: CGSize {
+            width: if number_of_lines == 1 {
+                rect.size.width.max(text_size.width.ceil() + 1.0)
+            } else {
+                rect.size.width
+            },
+            height: text_size.height,
+        },
+    };
+    ui_font::draw_in_rect(env, font, &text, text_rect, line_break_mode, text_alignment);
+}
+
+- (())drawRect:(CGRect)rect {
+    msg![env; this drawTextInRect:rect]
+}
+
+@end
+
+};
@@ -0,0 +1,689 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UINavigationBar` and `UINavigationItem`.
+//!
+//! The bar shows the top item's title (or title view) and bar button items,
+//! and a back button for the item below it. These are subviews that are
+//! recreated whenever the bar is laid out.
+//!
+//! TODO: prompts, animated transitions, back button arrow shape.
+
+use super::ui_bar_item::{
+    self, UIBarButtonItemStyle, UIBarButtonItemStyleDone, UIBarButtonItemStylePlain,
+};
+use super::ui_button::UIButtonTypeCustom;
+use super::ui_control::{
+    UIControlEventTouchUpInside, UIControlStateHighlighted, UIControlStateNormal,
+};
+use super::ui_font::UITextAlignmentCenter;
+use super::ui_view::{self, UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain,
+    AnyHostObject, ClassExports, HostObject, NSZonePtr, ObjC, SEL,
+};
+use crate::Environment;
+
+pub type UIBarStyle = NSInteger;
+pub const UIBarStyleDefault: UIBarStyle = 0;
+#[allow(dead_code)]
+pub const UIBarStyleBlack: UIBarStyle = 1;
+
+pub const NAVIGATION_BAR_HEIGHT: CGFloat = 44.0;
+/// Space between the edges of the bar and the buttons.
+const BUTTON_MARGIN: CGFloat = 5.0;
+/// Space between a button's edges and its content.
+const BUTTON_PADDING: CGFloat = 10.0;
+const BUTTON_HEIGHT: CGFloat = 30.0;
+
+/// State specific to `UINavigationBar`, see [UIViewSubclass].
+#[derive(Clone)]
+pub(super) struct NavigationBarState {
+    /// `UINavigationItem*`s, bottom to top. These are strong references.
+    items: Vec<id>,
+    /// This is a weak reference.
+    delegate: id,
+    bar_style: UIBarStyle,
+    /// `UIColor*`, possibly nil. This is a strong reference.
+    tint_color: id,
+    /// The views shown for the top item and the back button, which are also
+    /// subviews. These are weak references.
+    item_views: Vec<id>,
+    /// The buttons (or custom views) for the top item's bar button items,
+    /// paired with the items. These are weak references.
+    buttons: Vec<(id, id)>,
+}
+impl Default for NavigationBarState {
+    fn default() -> Self {
+        NavigationBarState {
+            items: Vec::new(),
+            delegate: nil,
+            bar_style: UIBarStyleDefault,
+            tint_color: nil,
+            item_views: Vec::new(),
+            buttons: Vec::new(),
+        }
+    }
+}
+impl NavigationBarState {
+    pub(super) fn release(self, env: &mut Environment) {
+        for (_, item) in self.buttons {
+            ui_bar_item::set_bar(env, item, nil);
+        }
+        for item in self.items {
+            env.objc.borrow_mut::<UINavigationItemHostObject>(item).bar = nil;
+            release(env, item);
+        }
+        release(env, self.tint_color);
+    }
+}
+
+/// Get the bar's state, creating it if this is the first time it's needed.
+fn navigation_bar_state(objc: &mut ObjC, bar: id) -> &mut NavigationBarState {
+    let host_object = objc.borrow_mut::<UIViewHostObject>(bar);
+    if !matches!(host_object.subclass, UIViewSubclass::UINavigationBar(_)) {
+        host_object.subclass = UIViewSubclass::UINavigationBar(NavigationBarState::default());
+    }
+    let UIViewSubclass::UINavigationBar(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+#[derive(Clone)]
+struct UINavigationItemHostObject {
+    /// `NSString*`, possibly nil. This is a strong reference.
+    title: id,
+    /// `UIView*`, possibly nil. This is a strong reference.
+    title_view: id,
+    /// `NSString*`, possibly nil. This is a strong reference.
+    prompt: id,
+    /// `UIBarButtonItem*`, possibly nil. This is a strong reference.
+    back_bar_button_item: id,
+    /// `UIBarButtonItem*`, possibly nil. This is a strong reference.
+    left_bar_button_item: id,
+    /// `UIBarButtonItem*`, possibly nil. This is a strong reference.
+    right_bar_button_item: id,
+    hides_back_button: bool,
+    /// The bar this item has been pushed onto, if any. This is a weak
+    /// reference.
+    bar: id,
+}
+impl HostObject for UINavigationItemHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Replace one of the item's strong references, retaining the new value and
+/// releasing the old one, then update the bar showing it.
+fn set_item_field(
+    env: &mut Environment,
+    item: id,
+    value: id,
+    field: fn(&mut UINavigationItemHostObject) -> &mut id,
+) {
+    retain(env, value);
+    let old = std::mem::replace(field(env.objc.borrow_mut(item)), value);
+    release(env, old);
+    let bar = env.objc.borrow::<UINavigationItemHostObject>(item).bar;
+    if bar != nil {
+        () = msg![env; bar layoutSubviews];
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UINavigationBar: UIView
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIBarStyle");
+    let bar_style: UIBarStyle = msg![env; coder decodeIntegerForKey:key_ns_string];
+    navigation_bar_state(&mut env.objc, this).bar_style = bar_style;
+
+    let key_ns_string = get_static_str(env, "UIItems");
+    let items: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if items != nil {
+        () = msg![env; this setItems:items];
+    }
+
+    this
+}
+
+- (id)delegate {
+    navigation_bar_state(&mut env.objc, this).delegate
+}
+- (())setDelegate:(id)delegate {
+    navigation_bar_state(&mut env.objc, this).delegate = delegate;
+}
+
+- (UIBarStyle)barStyle {
+    navigation_bar_state(&mut env.objc, this).bar_style
+}
+- (())setBarStyle:(UIBarStyle)style {
+    navigation_bar_state(&mut env.objc, this).bar_style = style;
+    () = msg![env; this layoutSubviews];
+}
+
+- (id)tintColor {
+    navigation_bar_state(&mut env.objc, this).tint_color
+}
+- (())setTintColor:(id)color { // UIColor*
+    retain(env, color);
+    let old = std::mem::replace(&mut navigation_bar_state(&mut env.objc, this).tint_color, color);
+    release(env, old);
+    () = msg![env; this layoutSubviews];
+}
+
+- (id)items {
+    let items = navigation_bar_state(&mut env.objc, this).items.clone();
+    for &item in &items {
+        retain(env, item);
+    }
+    let array = ns_array::from_vec(env, items);
+    autorelease(env, array)
+}
+- (())setItems:(id)items { // NSArray* of UINavigationItem*
+    let count: NSUInteger = msg![env; items count];
+    let mut new_items = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let item: id = msg![env; items objectAtIndex:i];
+        new_items.push(retain(env, item));
+    }
+    let old_items = std::mem::take(&mut navigation_bar_state(&mut env.objc, this).items);
+    for &item in &old_items {
+        env.objc.borrow_mut::<UINavigationItemHostObject>(item).bar = nil;
+    }
+    for &item in &new_items {
+        env.objc.borrow_mut::<UINavigationItemHostObject>(item).bar = this;
+    }
+    navigation_bar_state(&mut env.objc, this).items = new_items;
+    for item in old_items {
+        release(env, item);
+    }
+    () = msg![env; this layoutSubviews];
+}
+- (())setItems:(id)items // NSArray* of UINavigationItem*
+      animated:(bool)_animated {
+    // TODO: animation
+    msg![env; this setItems:items]
+}
+
+- (id)topItem {
+    navigation_bar_state(&mut env.objc, this).items.last().copied().unwrap_or(nil)
+}
+- (id)backItem {
+    let items = &navigation_bar_state(&mut env.objc, this).items;
+    if items.len() >= 2 {
+        items[items.len() - 2]
+    } else {
+        nil
+    }
+}
+
+- (())pushNavigationItem:(id)item // UINavigationItem*
+                animated:(bool)_animated {
+    // TODO: animation
+    let delegate = navigation_bar_state(&mut env.objc, this).delegate;
+    if delegate != nil && responds_to_selector(env, delegate, "navigationBar:shouldPushItem:") {
+        let should_push: bool = msg![env; delegate navigationBar:this shouldPushItem:item];
+        if !should_push {
+            return;
+        }
+    }
+    retain(env, item);
+    env.objc.borrow_mut::<UINavigationItemHostObject>(item).bar = this;
+    navigation_bar_state(&mut env.objc, this).items.push(item);
+    () = msg![env; this layoutSubviews];
+    if delegate != nil && responds_to_selector(env, delegate, "navigationBar:didPushItem:") {
+        () = msg![env; delegate navigationBar:this didPushItem:item];
+    }
+}
+- (id)popNavigationItemAnimated:(bool)_animated {
+    // TODO: animation
+    let state = navigation_bar_state(&mut env.objc, this);
+    let delegate = state.delegate;
+    let Some(&item) = state.items.last() else {
+        return nil;
+    };
+    if delegate != nil && responds_to_selector(env, delegate, "navigationBar:shouldPopItem:") {
+        let should_pop: bool = msg![env; delegate navigationBar:this shouldPopItem:item];
+        if !should_pop {
+            return nil;
+        }
+    }
+    navigation_bar_state(&mut env.objc, this).items.pop();
+    env.objc.borrow_mut::<UINavigationItemHostObject>(item).bar = nil;
+    () = msg![env; this layoutSubviews];
+    if delegate != nil && responds_to_selector(env, delegate, "navigationBar:didPopItem:") {
+        () = msg![env; delegate navigationBar:this didPopItem:item];
+    }
+    autorelease(env, item)
+}
+
+- (CGSize)sizeThatFits:(CGSize)size {
+    CGSize {
+        width: size.width,
+        height: NAVIGATION_BAR_HEIGHT,
+    }
+}
+
+- (())layoutSubviews {
+    layout_items(env, this);
+}
+
+// Action for the back button.
+- (())_backButtonPressed:(id)_button {
+    // A navigation controller pops the view controller, which in turn pops
+    // the item.
+    let delegate = navigation_bar_state(&mut env.objc, this).delegate;
+    let navigation_controller_class = env.objc.get_known_class(
+        "UINavigationController",
+        &mut env.mem
+    );
+    if delegate != nil && msg![env; delegate isKindOfClass:navigation_controller_class] {
+        let _: id = msg![env; delegate popViewControllerAnimated:true];
+    } else {
+        let _: id = msg![env; this popNavigationItemAnimated:true];
+    }
+}
+
+// Action for the buttons created for bar button items.
+- (())_barButtonItemPressed:(id)button {
+    let buttons = &navigation_bar_state(&mut env.objc, this).buttons;
+    let Some(&(_, item)) = buttons.iter().find(|&&(b, _)| b == button) else {
+        return;
+    };
+    let target: id = msg![env; item target];
+    let action: SEL = msg![env; item action];
+    if action.is_null() {
+        return;
+    }
+    let application: id = msg_class![env; UIApplication sharedApplication];
+    let _: bool = msg![env; application sendAction:action to:target from:item forEvent:nil];
+}
+
+@end
+
+@implementation UINavigationItem: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UINavigationItemHostObject {
+        title: nil,
+        title_view: nil,
+        prompt: nil,
+        back_bar_button_item: nil,
+        left_bar_button_item: nil,
+        right_bar_button_item: nil,
+        hides_back_button: false,
+        bar: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    env.objc.borrow_mut::<UINavigationItemHostObject>(this).title = title;
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let mut decode = |key| {
+        let key_ns_string = get_static_str(env, key);
+        let object: id = msg![env; coder decodeObjectForKey:key_ns_string];
+        retain(env, object)
+    };
+    let title = decode("UITitle");
+    let title_view = decode("UITitleView");
+    let prompt = decode("UIPrompt");
+    let back_bar_button_item = decode("UIBackBarButtonItem");
+    let left_bar_button_item = decode("UILeftBarButtonItem");
+    let right_bar_button_item = decode("UIRightBarButtonItem");
+
+    let key_ns_string = get_static_str(env, "UIHidesBackButton");
+    let hides_back_button: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+
+    *env.objc.borrow_mut::<UINavigationItemHostObject>(this) = UINavigationItemHostObject {
+        title,
+        title_view,
+        prompt,
+        back_bar_button_item,
+        left_bar_button_item,
+        right_bar_button_item,
+        hides_back_button,
+        bar: nil,
+    };
+    this
+}
+
+- (())dealloc {
+    let &UINavigationItemHostObject {
+        title,
+        title_view,
+        prompt,
+        back_bar_button_item,
+        left_bar_button_item,
+        right_bar_button_item,
+        ..
+    } = env.objc.borrow(this);
+    release(env, title);
+    release(env, title_view);
+    release(env, prompt);
+    release(env, back_bar_button_item);
+    release(env, left_bar_button_item);
+    release(env, right_bar_button_item);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)title {
+    env.objc.borrow::<UINavigationItemHostObject>(this).title
+}
+- (())setTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    set_item_field(env, this, title, |h| &mut h.title);
+    release(env, title);
+}
+
+- (id)titleView {
+    env.objc.borrow::<UINavigationItemHostObject>(this).title_view
+}
+- (())setTitleView:(id)view { // UIView*
+    set_item_field(env, this, view, |h| &mut h.title_view);
+}
+
+- (id)prompt {
+    env.objc.borrow::<UINavigationItemHostObject>(this).prompt
+}
+- (())setPrompt:(id)prompt { // NSString*
+    // TODO: show the prompt
+    let prompt: id = msg![env; prompt copy];
+    set_item_field(env, this, prompt, |h| &mut h.prompt);
+    release(env, prompt);
+}
+
+- (id)backBarButtonItem {
+    env.objc.borrow::<UINavigationItemHostObject>(this).back_bar_button_item
+}
+- (())setBackBarButtonItem:(id)item { // UIBarButtonItem*
+    set_item_field(env, this, item, |h| &mut h.back_bar_button_item);
+}
+
+- (id)leftBarButtonItem {
+    env.objc.borrow::<UINavigationItemHostObject>(this).left_bar_button_item
+}
+- (())setLeftBarButtonItem:(id)item { // UIBarButtonItem*
+    set_item_field(env, this, item, |h| &mut h.left_bar_button_item);
+}
+- (())setLeftBarButtonItem:(id)item // UIBarButtonItem*
+                  animated:(bool)_animated {
+    // TODO: animation
+    msg![env; this setLeftBarButtonItem:item]
+}
+
+- (id)rightBarButtonItem {
+    env.objc.borrow::<UINavigationItemHostObject>(this).right_bar_button_item
+}
+- (())setRightBarButtonItem:(id)item { // UIBarButtonItem*
+    set_item_field(env, this, item, |h| &mut h.right_bar_button_item);
+}
+- (())setRightBarButtonItem:(id)item // UIBarButtonItem*
+                   animated:(bool)_animated {
+    // TODO: animation
+    msg![env; this setRightBarButtonItem:item]
+}
+
+- (bool)hidesBackButton {
+    env.objc.borrow::<UINavigationItemHostObject>(this).hides_back_button
+}
+- (())setHidesBackButton:(bool)hides {
+    env.objc.borrow_mut::<UINavigationItemHostObject>(this).hides_back_button = hides;
+    let bar = env.objc.borrow::<UINavigationItemHostObject>(this).bar;
+    if bar != nil {
+        () = msg![env; bar layoutSubviews];
+    }
+}
+- (())setHidesBackButton:(bool)hides
+                animated:(bool)_animated {
+    // TODO: animation
+    msg![env; this setHidesBackButton:hides]
+}
+
+@end
+
+};
+
+/// The bar's background color, which the buttons are based on.
+fn bar_color(env: &mut Environment, bar: id) -> (CGFloat, CGFloat, CGFloat) {
+    let state = navigation_bar_state(&mut env.objc, bar);
+    let (bar_style, tint_color) = (state.bar_style, state.tint_color);
+    if tint_color != nil {
+        let (r, g, b, _a) = super::ui_color::get_rgba(&env.objc, tint_color);
+        (r, g, b)
+    } else if bar_style == UIBarStyleDefault {
+        (0.427, 0.518, 0.635)
+    } else {
+        (0.1, 0.1, 0.1)
+    }
+}
+
+/// Create a button for the bar, with a background a little darker than the
+/// bar's.
+fn new_button(
+    env: &mut Environment,
+    bar: id,
+    title: id,
+    image: id,
+    style: UIBarButtonItemStyle,
+    action: &str,
+) -> id {
+    let button: id = msg_class![env; UIButton buttonWithType:UIButtonTypeCustom];
+    () = msg![env; button setTitle:title forState:UIControlStateNormal];
+    () = msg![env; button setImage:image forState:UIControlStateNormal];
+    let highlighted_color: id = msg_class![env; UIColor lightGrayColor];
+    () = msg![env; button setTitleColor:highlighted_color forState:UIControlStateHighlighted];
+    let title_label: id = msg![env; button titleLabel];
+    let font: id = msg_class![env; UIFont boldSystemFontOfSize:(12.0 as CGFloat)];
+    () = msg![env; title_label setFont:font];
+
+    let (r, g, b) = if style == UIBarButtonItemStyleDone {
+        (0.2, 0.4, 0.8)
+    } else {
+        let (r, g, b) = bar_color(env, bar);
+        (r * 0.8, g * 0.8, b * 0.8)
+    };
+    let background_color: id = msg_class![env; UIColor colorWithRed:r
+                                                               green:g
+                                                                blue:b
+                                                               alpha:(1.0 as CGFloat)];
+    () = msg![env; button setBackgroundColor:background_color];
+
+    let content_size: CGSize = msg![env; button sizeThatFits:(CGSize {
+        width: 0.0,
+        height: 0.0,
+    })];
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: content_size.width + BUTTON_PADDING * 2.0,
+            height: BUTTON_HEIGHT,
+        },
+    };
+    () = msg![env; button setFrame:frame];
+
+    let action = env.objc.lookup_selector(action).unwrap();
+    () = msg![env; button addTarget:bar
+                             action:action
+                   forControlEvents:UIControlEventTouchUpInside];
+    button
+}
+
+/// Create the view shown for a bar button item: its custom view, or a button.
+fn bar_button_item_view(env: &mut Environment, bar: id, item: id) -> id {
+    ui_bar_item::set_bar(env, item, bar);
+    let custom_view: id = msg![env; item customView];
+    let view = if custom_view != nil {
+        custom_view
+    } else {
+        let title: id = msg![env; item title];
+        let image: id = msg![env; item image];
+        let style: UIBarButtonItemStyle = msg![env; item style];
+        let enabled: bool = msg![env; item isEnabled];
+        let button = new_button(env, bar, title, image, style, "_barButtonItemPressed:");
+        () = msg![env; button setEnabled:enabled];
+        button
+    };
+    navigation_bar_state(&mut env.objc, bar)
+        .buttons
+        .push((view, item));
+    view
+}
+
+/// Replace the views shown for the items.
+fn layout_items(env: &mut Environment, bar: id) {
+    let state = navigation_bar_state(&mut env.objc, bar);
+    let old_views = std::mem::take(&mut state.item_views);
+    let old_buttons = std::mem::take(&mut state.buttons);
+    let items = state.items.clone();
+
+    for view in old_views {
+        // One of these might be the button whose action caused this, so it's
+        // kept alive until the event has been handled.
+        retain(env, view);
+        () = msg![env; view removeFromSuperview];
+        autorelease(env, view);
+    }
+    for (_, item) in old_buttons {
+        ui_bar_item::set_bar(env, item, nil);
+    }
+
+    let (r, g, b) = bar_color(env, bar);
+    let color: id = msg_class![env; UIColor colorWithRed:r
+                                                   green:g
+                                                    blue:b
+                                                   alpha:(1.0 as CGFloat)];
+    () = msg![env; bar setBackgroundColor:color];
+
+    let Some(&top_item) = items.last() else {
+        return;
+    };
+    let back_item = if items.len() >= 2 {
+        items[items.len() - 2]
+    } else {
+        nil
+    };
+
+    let mut new_views = Vec::new();
+
+    let left_item: id = msg![env; top_item leftBarButtonItem];
+    let hides_back_button: bool = msg![env; top_item hidesBackButton];
+    let left_view = if left_item != nil {
+        bar_button_item_view(env, bar, left_item)
+    } else if back_item != nil && !hides_back_button {
+        // The back button's title comes from the item it goes back to.
+        let back_bar_button_item: id = msg![env; back_item backBarButtonItem];
+        let mut title: id = if back_bar_button_item != nil {
+            msg![env; back_bar_button_item title]
+        } else {
+            msg![env; back_item title]
+        };
+        if title == nil {
+            title = get_static_str(env, "Back");
+        }
+        new_button(
+            env,
+            bar,
+            title,
+            nil,
+            UIBarButtonItemStylePlain,
+            "_backButtonPressed:",
+        )
+    } else {
+        nil
+    };
+
+    let right_item: id = msg![env; top_item rightBarButtonItem];
+    let right_view = if right_item != nil {
+        bar_button_item_view(env, bar, right_item)
+    } else {
+        nil
+    };
+
+    let bounds: CGRect = msg![env; bar bounds];
+    let mut left_inset: CGFloat = 0.0;
+    let mut right_inset: CGFloat = 0.0;
+    if left_view != nil {
+        let mut frame: CGRect = msg![env; left_view frame];
+        frame.origin = CGPoint {
+            x: bounds.origin.x + BUTTON_MARGIN,
+            y: bounds.origin.y + (bounds.size.height - frame.size.height) / 2.0,
+        };
+        () = msg![env; left_view setFrame:frame];
+        left_inset = frame.size.width + BUTTON_MARGIN * 2.0;
+        new_views.push(left_view);
+    }
+    if right_view != nil {
+        let mut frame: CGRect = msg![env; right_view frame];
+        frame.origin = CGPoint {
+            x: bounds.origin.x + bounds.size.width - BUTTON_MARGIN - frame.size.width,
+            y: bounds.origin.y + (bounds.size.height - frame.size.height) / 2.0,
+        };
+        () = msg![env; right_view setFrame:frame];
+        right_inset = frame.size.width + BUTTON_MARGIN * 2.0;
+        new_views.push(right_view);
+    }
+
+    let title_view: id = msg![env; top_item titleView];
+    let center = CGPoint {
+        x: bounds.origin.x + bounds.size.width / 2.0,
+        y: bounds.origin.y + bounds.size.height / 2.0,
+    };
+    if title_view != nil {
+        () = msg![env; title_view setCenter:center];
+        new_views.push(title_view);
+    } else {
+        let title: id = msg![env; top_item title];
+        // The title is centered on the bar, so it has to avoid the wider of
+        // the buttons on both sides.
+        let inset = left_inset.max(right_inset);
+        let label: id = msg_class![env; UILabel new];
+        let label = autorelease(env, label);
+        () = msg![env; label setText:title];
+        let font: id = msg_class![env; UIFont boldSystemFontOfSize:(20.0 as CGFloat)];
+        () = msg![env; label setFont:font];
+        let white: id = msg_class![env; UIColor whiteColor];
+        () = msg![env; label setTextColor:white];
+        let clear: id = msg_class![env; UIColor clearColor];
+        () = msg![env; label setBackgroundColor:clear];
+        () = msg![env; label setOpaque:false];
+        () = msg![env; label setTextAlignment:UITextAlignmentCenter];
+        let frame = CGRect {
+            origin: CGPoint {
+                x: bounds.origin.x + inset,
+                y: bounds.origin.y,
+            },
+            size: CGSize {
+                width: (bounds.size.width - inset * 2.0).max(0.0),
+                height: bounds.size.height,
+            },
+        };
+        () = msg![env; label setFrame:frame];
+        new_views.push(label);
+    }
+
+    // addSubview: would lay out the bar again.
+    for &view in &new_views {
+        () = msg![env; bar insertSubview:view atIndex:(NSInteger::MAX)];
+    }
+    navigation_bar_state(&mut env.objc, bar).item_views = new_views;
+}
@@ -0,0 +1,428 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UINavigationController`.
+//!
+//! The controller's view contains the navigation bar and the view of the view
+//! controller at the top of the stack. The bar's items are the view
+//! controllers' navigation items.
+//!
+//! TODO: toolbar, animated transitions.
+
+use super::ui_navigation_bar::NAVIGATION_BAR_HEIGHT;
+use super::ui_view_controller::{
+    self, set_parent, show_child, UIViewControllerHostObject, UIViewControllerSubclass,
+};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain,
+    ClassExports, ObjC,
+};
+use crate::Environment;
+
+/// State specific to `UINavigationController`, see
+/// [UIViewControllerSubclass].
+#[derive(Clone)]
+pub(super) struct NavigationControllerState {
+    /// The stack of view controllers, bottom to top. These are strong
+    /// references.
+    view_controllers: Vec<id>,
+    /// `UINavigationBar*`, nil until it's first needed. This is a strong
+    /// reference.
+    navigation_bar: id,
+    navigation_bar_hidden: bool,
+    /// This is a weak reference.
+    delegate: id,
+    /// The view controller whose view is shown, if any. This is a weak
+    /// reference.
+    shown: id,
+}
+impl Default for NavigationControllerState {
+    fn default() -> Self {
+        NavigationControllerState {
+            view_controllers: Vec::new(),
+            navigation_bar: nil,
+            navigation_bar_hidden: false,
+            delegate: nil,
+            shown: nil,
+        }
+    }
+}
+impl NavigationControllerState {
+    pub(super) fn release(self, env: &mut Environment) {
+        for view_controller in self.view_controllers {
+            set_parent(env, view_controller, nil);
+            release(env, view_controller);
+        }
+        if self.navigation_bar != nil {
+            () = msg![env; (self.navigation_bar) setDelegate:nil];
+            release(env, self.navigation_bar);
+        }
+    }
+}
+
+/// Get the controller's state, creating it if this is the first time it's
+/// needed.
+fn navigation_controller_state(objc: &mut ObjC, controller: id) -> &mut NavigationControllerState {
+    let host_object = objc.borrow_mut::<UIViewControllerHostObject>(controller);
+    if !matches!(
+        host_object.subclass,
+        UIViewControllerSubclass::UINavigationController(_)
+    ) {
+        host_object.subclass =
+            UIViewControllerSubclass::UINavigationController(NavigationControllerState::default());
+    }
+    let UIViewControllerSubclass::UINavigationController(ref mut state) = host_object.subclass
+    else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UINavigationController: UIViewController
+
+- (id)initWithRootViewController:(id)root_view_controller { // UIViewController*
+    let this: id = msg![env; this initWithNibName:nil bundle:nil];
+    set_view_controllers(env, this, vec![root_view_controller], false);
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view_controller::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UINavigationBar");
+    let navigation_bar: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if navigation_bar != nil {
+        retain(env, navigation_bar);
+        () = msg![env; navigation_bar setDelegate:this];
+        navigation_controller_state(&mut env.objc, this).navigation_bar = navigation_bar;
+    }
+
+    let view_controllers = ui_view_controller::decode_child_view_controllers(env, coder);
+    set_view_controllers(env, this, view_controllers, false);
+
+    this
+}
+
+- (id)delegate {
+    navigation_controller_state(&mut env.objc, this).delegate
+}
+- (())setDelegate:(id)delegate {
+    navigation_controller_state(&mut env.objc, this).delegate = delegate;
+}
+
+- (id)viewControllers {
+    let view_controllers = navigation_controller_state(&mut env.objc, this)
+        .view_controllers
+        .clone();
+    for &view_controller in &view_controllers {
+        retain(env, view_controller);
+    }
+    let array = ns_array::from_vec(env, view_controllers);
+    autorelease(env, array)
+}
+- (())setViewControllers:(id)view_controllers { // NSArray* of UIViewController*
+    msg![env; this setViewControllers:view_controllers animated:false]
+}
+- (())setViewControllers:(id)view_controllers // NSArray* of UIViewController*
+                animated:(bool)animated {
+    let count: NSUInteger = msg![env; view_controllers count];
+    let view_controllers = (0..count)
+        .map(|i| msg![env; view_controllers objectAtIndex:i])
+        .collect();
+    set_view_controllers(env, this, view_controllers, animated);
+}
+
+- (id)topViewController {
+    let state = navigation_controller_state(&mut env.objc, this);
+    state.view_controllers.last().copied().unwrap_or(nil)
+}
+- (id)visibleViewController {
+    let top: id = msg![env; this topViewController];
+    if top == nil {
+        return nil;
+    }
+    let modal: id = msg![env; top modalViewController];
+    if modal != nil {
+        modal
+    } else {
+        top
+    }
+}
+
+- (())pushViewController:(id)view_controller // UIViewController*
+                animated:(bool)animated {
+    let mut view_controllers = navigation_controller_state(&mut env.objc, this)
+        .view_controllers
+        .clone();
+    if view_controllers.contains(&view_controller) {
+        log!(
+            "Warning: (UIViewController*){:?} is already on the stack of (UINavigationController*){:?}, not pushing it again",
+            view_controller,
+            this
+        );
+        return;
+    }
+    view_controllers.push(view_controller);
+    set_view_controllers(env, this, view_controllers, animated);
+}
+
+- (id)popViewControllerAnimated:(bool)animated {
+    let mut view_controllers = navigation_controller_state(&mut env.objc, this)
+        .view_controllers
+        .clone();
+    // The root view controller can't be popped.
+    if view_controllers.len() <= 1 {
+        return nil;
+    }
+    let popped = view_controllers.pop().unwrap();
+    // Keep it alive for the caller.
+    retain(env, popped);
+    set_view_controllers(env, this, view_controllers, animated);
+    autorelease(env, popped)
+}
+- (id)popToRootViewControllerAnimated:(bool)animated {
+    let root: id = navigation_controller_state(&mut env.objc, this)
+        .view_controllers
+        .first()
+        .copied()
+        .unwrap_or(nil);
+    msg![env; this popToViewController:root animated:animated]
+}
+- (id)popToViewController:(id)view_controller // UIViewController*
+                 animated:(bool)animated {
+    let mut view_controllers = navigation_controller_state(&mut env.objc, this)
+        .view_controllers
+        .clone();
+    let Some(index) = view_controllers.iter().position(|&vc| vc == view_controller) else {
+        return nil;
+    };
+    let popped = view_controllers.split_off(index + 1);
+    // Keep them alive for the caller.
+    for &popped_view_controller in &popped {
+        retain(env, popped_view_controller);
+    }
+    set_view_controllers(env, this, view_controllers, animated);
+    let array = ns_array::from_vec(env, popped);
+    autorelease(env, array)
+}
+
+- (id)navigationBar {
+    navigation_bar(env, this)
+}
+
+- (bool)isNavigationBarHidden {
+    navigation_controller_state(&mut env.objc, this).navigation_bar_hidden
+}
+- (())setNavigationBarHidden:(bool)hidden {
+    msg![env; this setNavigationBarHidden:hidden animated:false]
+}
+- (())setNavigationBarHidden:(bool)hidden
+                    animated:(bool)_animated {
+    let state = navigation_controller_state(&mut env.objc, this);
+    state.navigation_bar_hidden = hidden;
+    let shown = state.shown;
+    if !msg![env; this isViewLoaded] {
+        return;
+    }
+    let navigation_bar = navigation_bar(env, this);
+    () = msg![env; navigation_bar setHidden:hidden];
+    if shown != nil {
+        let frame = content_frame(env, this);
+        let shown_view: id = msg![env; shown view];
+        () = msg![env; shown_view setFrame:frame];
+        () = msg![env; shown_view layoutSubviews];
+    }
+}
+
+- (())loadView {
+    let screen: id = msg_class![env; UIScreen mainScreen];
+    let frame: CGRect = msg![env; screen applicationFrame];
+    let view: id = msg_class![env; UIView alloc];
+    let view: id = msg![env; view initWithFrame:frame];
+    () = msg![env; this setView:view];
+    release(env, view);
+
+    let navigation_bar = navigation_bar(env, this);
+    let bar_frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: frame.size.width,
+            height: NAVIGATION_BAR_HEIGHT,
+        },
+    };
+    () = msg![env; navigation_bar setFrame:bar_frame];
+    let hidden = navigation_controller_state(&mut env.objc, this).navigation_bar_hidden;
+    () = msg![env; navigation_bar setHidden:hidden];
+    () = msg![env; view insertSubview:navigation_bar atIndex:(NSInteger::MAX)];
+    () = msg![env; navigation_bar layoutSubviews];
+
+    show_top(env, this, false);
+}
+
+// The appearance messages are forwarded to the view controller whose view is
+// shown.
+- (())viewWillAppear:(bool)animated {
+    let shown = navigation_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewWillAppear:animated]
+}
+- (())viewDidAppear:(bool)animated {
+    let shown = navigation_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewDidAppear:animated]
+}
+- (())viewWillDisappear:(bool)animated {
+    let shown = navigation_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewWillDisappear:animated]
+}
+- (())viewDidDisappear:(bool)animated {
+    let shown = navigation_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewDidDisappear:animated]
+}
+
+@end
+
+};
+
+/// Get the navigation bar, creating it if this is the first time it's needed.
+fn navigation_bar(env: &mut Environment, controller: id) -> id {
+    let existing = navigation_controller_state(&mut env.objc, controller).navigation_bar;
+    if existing != nil {
+        return existing;
+    }
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: 320.0,
+            height: NAVIGATION_BAR_HEIGHT,
+        },
+    };
+    let navigation_bar: id = msg_class![env; UINavigationBar alloc];
+    let navigation_bar: id = msg![env; navigation_bar initWithFrame:frame];
+    () = msg![env; navigation_bar setDelegate:controller];
+    navigation_controller_state(&mut env.objc, controller).navigation_bar = navigation_bar;
+    update_navigation_bar(env, controller);
+    navigation_bar
+}
+
+/// Make the navigation bar's items match the stack of view controllers.
+fn update_navigation_bar(env: &mut Environment, controller: id) {
+    let state = navigation_controller_state(&mut env.objc, controller);
+    let navigation_bar = state.navigation_bar;
+    if navigation_bar == nil {
+        // It'll be updated when it's created.
+        return;
+    }
+    let view_controllers = state.view_controllers.clone();
+    let items = view_controllers
+        .into_iter()
+        .map(|view_controller| {
+            let item: id = msg![env; view_controller navigationItem];
+            retain(env, item)
+        })
+        .collect();
+    let items = ns_array::from_vec(env, items);
+    () = msg![env; navigation_bar setItems:items];
+    release(env, items);
+}
+
+/// The frame for the view of the view controller at the top of the stack,
+/// which fills the controller's view apart from the navigation bar.
+fn content_frame(env: &mut Environment, controller: id) -> CGRect {
+    let view: id = msg![env; controller view];
+    let mut frame: CGRect = msg![env; view bounds];
+    if !navigation_controller_state(&mut env.objc, controller).navigation_bar_hidden {
+        frame.origin.y += NAVIGATION_BAR_HEIGHT;
+        frame.size.height = (frame.size.height - NAVIGATION_BAR_HEIGHT).max(0.0 as CGFloat);
+    }
+    frame
+}
+
+/// Replace the stack of view controllers, showing the new top one.
+fn set_view_controllers(
+    env: &mut Environment,
+    controller: id,
+    view_controllers: Vec<id>,
+    animated: bool,
+) {
+    for &view_controller in &view_controllers {
+        retain(env, view_controller);
+        set_parent(env, view_controller, controller);
+    }
+    let old_view_controllers = std::mem::replace(
+        &mut navigation_controller_state(&mut env.objc, controller).view_controllers,
+        view_controllers.clone(),
+    );
+    for &old_view_controller in &old_view_controllers {
+        if !view_controllers.contains(&old_view_controller) {
+            set_parent(env, old_view_controller, nil);
+        }
+    }
+
+    update_navigation_bar(env, controller);
+    show_top(env, controller, animated);
+
+    // Popped view controllers are only released once they're no longer shown.
+    for old_view_controller in old_view_controllers {
+        release(env, old_view_controller);
+    }
+}
+
+/// Show the view of the view controller at the top of the stack, if the
+/// controller's view is loaded. Otherwise, this happens when it's loaded.
+fn show_top(env: &mut Environment, controller: id, animated: bool) {
+    if !msg![env; controller isViewLoaded] {
+        return;
+    }
+    let state = navigation_controller_state(&mut env.objc, controller);
+    let top = state.view_controllers.last().copied().unwrap_or(nil);
+    let shown = state.shown;
+    let delegate = state.delegate;
+    if top == shown {
+        return;
+    }
+
+    log_dbg!(
+        "(UINavigationController*){:?} shows (UIViewController*){:?}",
+        controller,
+        top
+    );
+
+    if top != nil
+        && delegate != nil
+        && responds_to_selector(
+            env,
+            delegate,
+            "navigationController:willShowViewController:animated:",
+        )
+    {
+        () = msg![env; delegate navigationController:controller
+                              willShowViewController:top
+                                            animated:animated];
+    }
+
+    let frame = content_frame(env, controller);
+    navigation_controller_state(&mut env.objc, controller).shown = top;
+    show_child(env, controller, shown, top, frame, animated);
+
+    if top != nil
+        && delegate != nil
+        && responds_to_selector(
+            env,
+            delegate,
+            "navigationController:didShowViewController:animated:",
+        )
+    {
+        () = msg![env; delegate navigationController:controller
+                               didShowViewController:top
+                                            animated:animated];
+    }
+}
@@ -9,19 +9,47 @@
 //! - Apple's [Resource Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/LoadingResources/CocoaNibs/CocoaNibs.html) is very helpful.
 //! - GitHub user 0xced's [reverse-engineering of UIClassSwapper](https://gist.github.com/0xced/45daf79b62ad6a20be1c).
 
+use super::ui_control::UIControlEvents;
 use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
 use crate::frameworks::foundation::{ns_keyed_unarchiver, NSUInteger};
+use crate::fs::GuestPath;
 use crate::objc::{
-    id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class, ClassExports,
+    HostObject,
 };
 use crate::Environment;
 
+#[derive(Default, Clone)]
+pub struct State {
+    /// The owner of the nib file currently being loaded, if it isn't the
+    /// `UIApplication` instance. This is a weak reference.
+    files_owner: Option<id>,
+}
+
+#[derive(Clone)]
 struct UIRuntimeOutletConnectionHostObject {
     destination: id,
     label: id,
     source: id,
 }
-impl HostObject for UIRuntimeOutletConnectionHostObject {}
+impl HostObject for UIRuntimeOutletConnectionHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+#[derive(Clone)]
+struct UIRuntimeEventConnectionHostObject {
+    destination: id,
+    label: id,
+    source: id,
+    event_mask: UIControlEvents,
+}
+impl HostObject for UIRuntimeEventConnectionHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -41,25 +69,28 @@ pub const CLASSES: ClassExports = objc_classes! {
     let id = to_rust_string(env, id_nss);
 
     if id == "IBFilesOwner" {
-        // The file owner is usually the UIApplication instance.
+        // The file owner is the UIApplication instance for the main nib file,
+        // or the view controller for a view controller's nib file.
         // Replacing the proxy with that instance is important so that the
         // "delegate" outlet can be connected between it and the
-        // UIApplicationDelegate.
+        // UIApplicationDelegate, or the "view" outlet can be connected to the
+        // view controller.
         //
         // TODO: This is a bit of a hack. Eventually it would be good to fix:
         // - The name "UIProxyObject" implies that it might be intended to
         //   proxy messages to another object, rather than be replaced by it.
         //   Check what iPhone OS does?
-        // - If/when the UINib class is implemented and arbitrary nib files can
-        //   be deserialized, an app could pick some other object to be the nib
-        //   file owner, which this would need to handle.
         // - If this object is meant to be replaced, it's probably not meant to
         //   be done via `initWithCoder:`, but instead by providing a delegate
         //   to the NSKeyedUnarchiver. That might be needed to implement
-        //   replacement for objects other than the UIApplication instance.
+        //   replacement for objects other than the file owner.
 
         release(env, this);
-        msg_class![env; UIApplication sharedApplication]
+        if let Some(owner) = env.framework_state.uikit.ui_nib.files_owner {
+            owner
+        } else {
+            msg_class![env; UIApplication sharedApplication]
+        }
     } else {
         log!("TODO: UIProxyObject replacement for {}, instance {:?} left unreplaced", id, this);
         this
@@ -160,6 +191,85 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @end
 
+// Another undocumented type used by nib files. This one connects actions: the
+// source is a UIControl and the label is the name of the action method.
+@implementation UIRuntimeEventConnection: NSObject
+
++ (id)alloc {
+    let host_object = Box::new(UIRuntimeEventConnectionHostObject {
+        destination: nil,
+        label: nil,
+        source: nil,
+        event_mask: 0,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+
+    let destination_key = get_static_str(env, "UIDestination");
+    let destination: id = msg![env; coder decodeObjectForKey: destination_key];
+
+    let label_key = get_static_str(env, "UILabel");
+    let label: id = msg![env; coder decodeObjectForKey: label_key];
+
+    let source_key = get_static_str(env, "UISource");
+    let source: id = msg![env; coder decodeObjectForKey: source_key];
+
+    let event_mask_key = get_static_str(env, "UIEventMask");
+    let event_mask: i32 = msg![env; coder decodeIntForKey: event_mask_key];
+
+    retain(env, destination);
+    retain(env, source);
+    retain(env, label);
+    let host_obj = env.objc.borrow_mut::<UIRuntimeEventConnectionHostObject>(this);
+    host_obj.destination = destination;
+    host_obj.label = label;
+    host_obj.source = source;
+    host_obj.event_mask = event_mask as UIControlEvents;
+
+    this
+}
+
+- (())connect {
+    let &UIRuntimeEventConnectionHostObject {
+        destination,
+        label,
+        source,
+        event_mask,
+    } = env.objc.borrow(this);
+
+    let action = to_rust_string(env, label);
+    let action = env.objc.register_selector(&action, &mut env.mem);
+    // Bar button items aren't controls, they have a single target and action.
+    let bar_button_item_class: Class = msg_class![env; UIBarButtonItem class];
+    if msg![env; source isKindOfClass:bar_button_item_class] {
+        () = msg![env; source setTarget:destination];
+        () = msg![env; source setAction:action];
+        return;
+    }
+    () = msg![env; source addTarget:destination
+                             action:action
+                   forControlEvents:event_mask];
+}
+
+- (())dealloc {
+    let &UIRuntimeEventConnectionHostObject {
+        destination,
+        label,
+        source,
+        ..
+    } = env.objc.borrow(this);
+    release(env, destination);
+    release(env, label);
+    release(env, source);
+
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+@end
+
 };
 
 /// Shortcut for use by [super::ui_application::UIApplicationMain].
@@ -175,15 +285,53 @@ pub fn load_main_nib_file(env: &mut Environment, _ui_application: id) {
         return;
     };
 
-    let Ok(data) = env.fs.read(path) else {
+    if !load_nib_file(env, &path, None) {
         // Apparently it's permitted to specify the nib file key in the
         // Info.plist, yet not have it point to a valid nib file?!
         log!("Warning: couldn't load main nib file");
-        return;
+    }
+}
+
+/// Shortcut for use by `-[UIViewController loadView]`: load the nib file with
+/// a particular name from a bundle (the main bundle if nil), with the view
+/// controller as the file's owner. Returns [false] if the file couldn't be
+/// read or isn't a valid nib file.
+pub(super) fn load_nib_named(env: &mut Environment, name: id, bundle: id, owner: id) -> bool {
+    let bundle: id = if bundle == nil {
+        msg_class![env; NSBundle mainBundle]
+    } else {
+        bundle
+    };
+    let extension = get_static_str(env, "nib");
+    let path: id = msg![env; bundle pathForResource:name ofType:extension];
+    if path == nil {
+        return false;
+    }
+    let path = to_rust_string(env, path).into_owned();
+    load_nib_file(env, GuestPath::new(&path), Some(owner))
+}
+
+/// Deserialize a nib file, connect its outlets and actions, and show the
+/// windows that should be visible on load. The owner replaces the file's owner
+/// proxy object, see `UIProxyObject`; [None] means the `UIApplication`
+/// instance. Returns [false] if the file couldn't be read or isn't a valid nib
+/// file.
+fn load_nib_file(env: &mut Environment, path: &GuestPath, owner: Option<id>) -> bool {
+    let Ok(data) = env.fs.read(path) else {
+        return false;
     };
 
     let unarchiver = msg_class![env; NSKeyedUnarchiver alloc];
-    ns_keyed_unarchiver::init_for_reading_with_data(env, unarchiver, &data);
+    if let Err(e) = ns_keyed_unarchiver::init_for_reading_with_data(env, unarchiver, &data) {
+        log!("Warning: {:?} is not a valid nib file: {}", path, e);
+        release(env, unarchiver);
+        return false;
+    }
+
+    // Nib files can be loaded while another one is being loaded, e.g. by an
+    // object's initWithCoder:, so the previous owner is restored afterwards.
+    let previous_owner =
+        std::mem::replace(&mut env.framework_state.uikit.ui_nib.files_owner, owner);
 
     // The top-level keys in a nib file's keyed archive appear to be
     // UINibAccessibilityConfigurationsKey, UINibConnectionsKey,
@@ -195,14 +343,29 @@ pub fn load_main_nib_file(env: &mut Environment, _ui_application: id) {
     let objects_key = get_static_str(env, "UINibObjectsKey");
     let _objects: id = msg![env; unarchiver decodeObjectForKey:objects_key];
 
-    // Connect all the outlets with UIRuntimeOutletConnection
     let conns_key = get_static_str(env, "UINibConnectionsKey");
     let conns: id = msg![env; unarchiver decodeObjectForKey:conns_key];
+
+    env.framework_state.uikit.ui_nib.files_owner = previous_owner;
+
+    // Connect all the outlets and actions with UIRuntimeOutletConnection and
+    // UIRuntimeEventConnection
     let conns_count: NSUInteger = msg![env; conns count];
     for i in 0..conns_count {
         let conn: id = msg![env; conns objectAtIndex:i];
         () = msg![env; conn connect];
     }
 
+    // Show the windows that should be visible on launch
+    let visible_windows_key = get_static_str(env, "UINibVisibleWindowsKey");
+    let visible_windows: id = msg![env; unarchiver decodeObjectForKey:visible_windows_key];
+    let visible_windows_count: NSUInteger = msg![env; visible_windows count];
+    for i in 0..visible_windows_count {
+        let window: id = msg![env; visible_windows objectAtIndex:i];
+        () = msg![env; window makeKeyAndVisible];
+    }
+
     release(env, unarchiver);
+
+    true
 }
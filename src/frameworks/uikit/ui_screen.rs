@@ -6,9 +6,9 @@
 //! `UIScreen`.
 
 use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
-use crate::objc::{id, objc_classes, ClassExports, TrivialHostObject};
+use crate::objc::{id, msg, objc_classes, ClassExports, TrivialHostObject};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     main_screen: Option<id>,
 }
@@ -48,6 +48,11 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
+- (CGRect)applicationFrame {
+    // touchHLE doesn't draw a status bar, so the whole screen is available.
+    msg![env; this bounds]
+}
+
 @end
 
 };
@@ -0,0 +1,692 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIScrollView`.
+//!
+//! Like on the real iPhone OS, the content offset is the origin of the view's
+//! bounds, so scrolling moves the subviews without changing their frames.
+//!
+//! The real UIKit uses gesture recognizers to decide whether a touch is meant
+//! for the scroll view or for one of its subviews. Here, touches in a scroll
+//! view go to the scroll view itself, unless they hit a control or another
+//! scroll view, see `hitTest:withEvent:`.
+
+use super::ui_table_view::TableViewState;
+use super::ui_view::{self, UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::NSTimeInterval;
+use crate::objc::{
+    id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain, ClassExports,
+    ObjC,
+};
+use crate::Environment;
+use std::time::{Duration, Instant};
+
+/// How far (in points) a touch has to move before it's treated as a drag
+/// rather than a tap.
+const DRAG_THRESHOLD: CGFloat = 10.0;
+
+/// Speed (in points per second) above which a drag that ends is treated as a
+/// flick, which turns the page or makes the content keep scrolling.
+const FLICK_SPEED: CGFloat = 300.0;
+
+/// How long it takes for the content to stop scrolling after a flick.
+const DECELERATION_DURATION: Duration = Duration::from_millis(1000);
+
+/// How long it takes for the content to settle after a drag that isn't a flick,
+/// e.g. to bounce back or to snap to a page.
+const SETTLE_DURATION: Duration = Duration::from_millis(300);
+
+/// Interval between steps of scrolling animations.
+const ANIMATION_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Scroll views with a scrolling animation in progress. These are strong
+    /// references.
+    animating: Vec<id>,
+}
+
+/// An animated change of the content offset.
+#[derive(Copy, Clone)]
+struct Animation {
+    from: CGPoint,
+    to: CGPoint,
+    start: Instant,
+    duration: Duration,
+    /// Whether this is deceleration after a drag, rather than the result of
+    /// `setContentOffset:animated:`. This decides which delegate method is
+    /// called at the end.
+    decelerating: bool,
+}
+
+/// State specific to `UIScrollView`, see [UIViewSubclass].
+#[derive(Clone)]
+pub(super) struct ScrollViewState {
+    /// This is a weak reference.
+    pub(super) delegate: id,
+    content_size: CGSize,
+    scroll_enabled: bool,
+    paging_enabled: bool,
+    bounces: bool,
+    always_bounce_vertical: bool,
+    always_bounce_horizontal: bool,
+    shows_vertical_scroll_indicator: bool,
+    shows_horizontal_scroll_indicator: bool,
+    /// Whether a touch that began in the scroll view is being tracked.
+    tracking: bool,
+    /// Whether the touch being tracked has moved far enough to scroll.
+    dragging: bool,
+    /// Where the touch being tracked began (in the superview's co-ordinate
+    /// space) and the content offset at that time.
+    drag_start: (CGPoint, CGPoint),
+    /// How fast the content offset was changing the last time the touch moved,
+    /// in points per second.
+    velocity: CGPoint,
+    last_move_timestamp: NSTimeInterval,
+    animation: Option<Animation>,
+    pub(super) subclass: UIScrollViewSubclass,
+}
+impl Default for ScrollViewState {
+    fn default() -> Self {
+        ScrollViewState {
+            delegate: nil,
+            content_size: CGSize {
+                width: 0.0,
+                height: 0.0,
+            },
+            scroll_enabled: true,
+            paging_enabled: false,
+            bounces: true,
+            always_bounce_vertical: false,
+            always_bounce_horizontal: false,
+            shows_vertical_scroll_indicator: true,
+            shows_horizontal_scroll_indicator: true,
+            tracking: false,
+            dragging: false,
+            drag_start: (CGPoint { x: 0.0, y: 0.0 }, CGPoint { x: 0.0, y: 0.0 }),
+            velocity: CGPoint { x: 0.0, y: 0.0 },
+            last_move_timestamp: 0.0,
+            animation: None,
+            subclass: UIScrollViewSubclass::Other,
+        }
+    }
+}
+impl ScrollViewState {
+    pub(super) fn release(self, env: &mut Environment) {
+        match self.subclass {
+            UIScrollViewSubclass::Other => (),
+            UIScrollViewSubclass::UITableView(table_view_state) => table_view_state.release(env),
+        }
+    }
+}
+
+/// State specific to the UIKit classes that inherit from `UIScrollView`.
+#[derive(Clone)]
+pub(super) enum UIScrollViewSubclass {
+    Other,
+    UITableView(TableViewState),
+}
+
+/// Get the scroll view's state, creating it if this is the first time it's
+/// needed.
+pub(super) fn scroll_view_state(objc: &mut ObjC, scroll_view: id) -> &mut ScrollViewState {
+    let host_object = objc.borrow_mut::<UIViewHostObject>(scroll_view);
+    if !matches!(host_object.subclass, UIViewSubclass::UIScrollView(_)) {
+        host_object.subclass = UIViewSubclass::UIScrollView(ScrollViewState::default());
+    }
+    let UIViewSubclass::UIScrollView(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIScrollView: UIView
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    init_with_coder(env, this, coder)
+}
+
+- (id)delegate {
+    scroll_view_state(&mut env.objc, this).delegate
+}
+- (())setDelegate:(id)delegate {
+    scroll_view_state(&mut env.objc, this).delegate = delegate;
+}
+
+- (CGSize)contentSize {
+    scroll_view_state(&mut env.objc, this).content_size
+}
+- (())setContentSize:(CGSize)size {
+    scroll_view_state(&mut env.objc, this).content_size = size;
+}
+
+- (CGPoint)contentOffset {
+    let bounds: CGRect = msg![env; this bounds];
+    bounds.origin
+}
+- (())setContentOffset:(CGPoint)offset {
+    stop_animation(env, this);
+    set_content_offset(env, this, offset);
+}
+- (())setContentOffset:(CGPoint)offset
+              animated:(bool)animated {
+    if animated {
+        let from: CGPoint = msg![env; this contentOffset];
+        start_animation(env, this, from, offset, SETTLE_DURATION, false);
+    } else {
+        () = msg![env; this setContentOffset:offset];
+    }
+}
+- (())scrollRectToVisible:(CGRect)rect
+                 animated:(bool)animated {
+    let bounds: CGRect = msg![env; this bounds];
+    let max = max_content_offset(env, this);
+    let mut offset = bounds.origin;
+    // Scroll as little as possible to make the rect visible.
+    let scroll_axis = |offset: CGFloat,
+                       view_size: CGFloat,
+                       rect_origin: CGFloat,
+                       rect_size: CGFloat,
+                       max: CGFloat| {
+        let offset = if rect_origin + rect_size > offset + view_size {
+            rect_origin + rect_size - view_size
+        } else {
+            offset
+        };
+        let offset = if rect_origin < offset { rect_origin } else { offset };
+        CGFloat::clamp(offset, 0.0, max)
+    };
+    offset.x = scroll_axis(offset.x, bounds.size.width, rect.origin.x, rect.size.width, max.x);
+    offset.y = scroll_axis(offset.y, bounds.size.height, rect.origin.y, rect.size.height, max.y);
+    msg![env; this setContentOffset:offset animated:animated]
+}
+
+- (bool)isScrollEnabled {
+    scroll_view_state(&mut env.objc, this).scroll_enabled
+}
+- (())setScrollEnabled:(bool)enabled {
+    scroll_view_state(&mut env.objc, this).scroll_enabled = enabled;
+}
+- (bool)isPagingEnabled {
+    scroll_view_state(&mut env.objc, this).paging_enabled
+}
+- (())setPagingEnabled:(bool)enabled {
+    scroll_view_state(&mut env.objc, this).paging_enabled = enabled;
+}
+- (bool)bounces {
+    scroll_view_state(&mut env.objc, this).bounces
+}
+- (())setBounces:(bool)bounces {
+    scroll_view_state(&mut env.objc, this).bounces = bounces;
+}
+- (bool)alwaysBounceVertical {
+    scroll_view_state(&mut env.objc, this).always_bounce_vertical
+}
+- (())setAlwaysBounceVertical:(bool)always {
+    scroll_view_state(&mut env.objc, this).always_bounce_vertical = always;
+}
+- (bool)alwaysBounceHorizontal {
+    scroll_view_state(&mut env.objc, this).always_bounce_horizontal
+}
+- (())setAlwaysBounceHorizontal:(bool)always {
+    scroll_view_state(&mut env.objc, this).always_bounce_horizontal = always;
+}
+
+// TODO: draw scroll indicators
+- (bool)showsVerticalScrollIndicator {
+    scroll_view_state(&mut env.objc, this).shows_vertical_scroll_indicator
+}
+- (())setShowsVerticalScrollIndicator:(bool)shows {
+    scroll_view_state(&mut env.objc, this).shows_vertical_scroll_indicator = shows;
+}
+- (bool)showsHorizontalScrollIndicator {
+    scroll_view_state(&mut env.objc, this).shows_horizontal_scroll_indicator
+}
+- (())setShowsHorizontalScrollIndicator:(bool)shows {
+    scroll_view_state(&mut env.objc, this).shows_horizontal_scroll_indicator = shows;
+}
+- (())flashScrollIndicators {}
+
+// These only affect how touches are shared with subviews, which isn't
+// supported, see hitTest:withEvent:.
+- (())setDelaysContentTouches:(bool)_delays {}
+- (())setCanCancelContentTouches:(bool)_can_cancel {}
+// TODO: zooming
+- (())setMinimumZoomScale:(CGFloat)_scale {}
+- (())setMaximumZoomScale:(CGFloat)_scale {}
+- (())setBouncesZoom:(bool)_bounces {}
+
+- (bool)isTracking {
+    scroll_view_state(&mut env.objc, this).tracking
+}
+- (bool)isDragging {
+    scroll_view_state(&mut env.objc, this).dragging
+}
+- (bool)isDecelerating {
+    scroll_view_state(&mut env.objc, this)
+        .animation
+        .as_ref()
+        .map_or(false, |animation| animation.decelerating)
+}
+
+- (id)hitTest:(CGPoint)point
+    withEvent:(id)event { // UIEvent*
+    hit_test(env, this, point, event)
+}
+
+// UIResponder implementation
+- (())touchesBegan:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    touches_began(env, this, touches);
+}
+- (())touchesMoved:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    touches_moved(env, this, touches);
+}
+- (())touchesEnded:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    touches_ended(env, this);
+}
+
+@end
+
+};
+
+/// The body of UIScrollView's `initWithCoder:`, for use by the subclasses that
+/// decode more properties.
+pub(super) fn init_with_coder(env: &mut Environment, this: id, coder: id) -> id {
+    let this = ui_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIScrollDisabled");
+    let scroll_disabled: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIPagingEnabled");
+    let paging_enabled: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIBounceDisabled");
+    let bounce_disabled: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIAlwaysBounceVertical");
+    let always_bounce_vertical: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIAlwaysBounceHorizontal");
+    let always_bounce_horizontal: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+
+    let state = scroll_view_state(&mut env.objc, this);
+    state.scroll_enabled = !scroll_disabled;
+    state.paging_enabled = paging_enabled;
+    state.bounces = !bounce_disabled;
+    state.always_bounce_vertical = always_bounce_vertical;
+    state.always_bounce_horizontal = always_bounce_horizontal;
+
+    this
+}
+
+/// UIScrollView's `hitTest:withEvent:`, for use by subclasses.
+pub(super) fn hit_test(env: &mut Environment, this: id, point: CGPoint, event: id) -> id {
+    let hit = ui_view::hit_test(env, this, point, event);
+    if hit == nil || hit == this || !scroll_view_state(&mut env.objc, this).scroll_enabled {
+        return hit;
+    }
+    // Controls and nested scroll views handle their own touches, anything else
+    // is scrolled.
+    let control_class = env.objc.get_known_class("UIControl", &mut env.mem);
+    let scroll_view_class = env.objc.get_known_class("UIScrollView", &mut env.mem);
+    let is_control: bool = msg![env; hit isKindOfClass:control_class];
+    let is_scroll_view: bool = msg![env; hit isKindOfClass:scroll_view_class];
+    if is_control || is_scroll_view {
+        hit
+    } else {
+        this
+    }
+}
+
+/// UIScrollView's `touchesBegan:withEvent:`, for use by subclasses.
+pub(super) fn touches_began(env: &mut Environment, this: id, touches: id) {
+    if scroll_view_state(&mut env.objc, this).tracking {
+        return;
+    }
+    // Touching the content stops it from scrolling.
+    stop_animation(env, this);
+
+    let (point, timestamp) = touch_location(env, this, touches);
+    let offset: CGPoint = msg![env; this contentOffset];
+    let state = scroll_view_state(&mut env.objc, this);
+    state.tracking = true;
+    state.dragging = false;
+    state.drag_start = (point, offset);
+    state.velocity = CGPoint { x: 0.0, y: 0.0 };
+    state.last_move_timestamp = timestamp;
+}
+
+/// UIScrollView's `touchesMoved:withEvent:`, for use by subclasses.
+pub(super) fn touches_moved(env: &mut Environment, this: id, touches: id) {
+    let state = scroll_view_state(&mut env.objc, this);
+    if !state.tracking || !state.scroll_enabled {
+        return;
+    }
+    let (point, timestamp) = touch_location(env, this, touches);
+    let state = scroll_view_state(&mut env.objc, this);
+    let (start_point, start_offset) = state.drag_start;
+
+    if !state.dragging {
+        let distance = (point.x - start_point.x).hypot(point.y - start_point.y);
+        if distance < DRAG_THRESHOLD {
+            return;
+        }
+        // Start scrolling from here, so the content doesn't jump.
+        state.dragging = true;
+        state.drag_start = (point, start_offset);
+        state.last_move_timestamp = timestamp;
+        let delegate = state.delegate;
+        if delegate != nil && responds_to_selector(env, delegate, "scrollViewWillBeginDragging:") {
+            () = msg![env; delegate scrollViewWillBeginDragging:this];
+        }
+        return;
+    }
+
+    let bounces = state.bounces;
+    let (can_scroll_x, can_scroll_y) = scrollable_axes(env, this);
+    let max = max_content_offset(env, this);
+    // Content dragged past its edge only moves half as far, if bouncing is
+    // enabled.
+    let drag_axis = |start_offset: CGFloat, start_point: CGFloat, point: CGFloat, max: CGFloat| {
+        let offset = start_offset + (start_point - point);
+        if !bounces {
+            CGFloat::clamp(offset, 0.0, max)
+        } else if offset < 0.0 {
+            offset / 2.0
+        } else if offset > max {
+            max + (offset - max) / 2.0
+        } else {
+            offset
+        }
+    };
+    let old_offset: CGPoint = msg![env; this contentOffset];
+    let mut offset = old_offset;
+    if can_scroll_x {
+        offset.x = drag_axis(start_offset.x, start_point.x, point.x, max.x);
+    }
+    if can_scroll_y {
+        offset.y = drag_axis(start_offset.y, start_point.y, point.y, max.y);
+    }
+
+    let state = scroll_view_state(&mut env.objc, this);
+    let elapsed = timestamp - state.last_move_timestamp;
+    if elapsed > 0.0 {
+        state.velocity = CGPoint {
+            x: (offset.x - old_offset.x) / elapsed as CGFloat,
+            y: (offset.y - old_offset.y) / elapsed as CGFloat,
+        };
+    }
+    state.last_move_timestamp = timestamp;
+
+    set_content_offset(env, this, offset);
+}
+
+/// UIScrollView's `touchesEnded:withEvent:`, for use by subclasses. Returns
+/// [true] if the touch was a drag rather than a tap.
+pub(super) fn touches_ended(env: &mut Environment, this: id) -> bool {
+    let state = scroll_view_state(&mut env.objc, this);
+    if !state.tracking {
+        return false;
+    }
+    state.tracking = false;
+    let dragged = std::mem::take(&mut state.dragging);
+    // A tap can still leave the content out of place, if it interrupted an
+    // animation.
+    let velocity = if dragged {
+        state.velocity
+    } else {
+        CGPoint { x: 0.0, y: 0.0 }
+    };
+    let paging_enabled = state.paging_enabled;
+    let (_, start_offset) = state.drag_start;
+    let delegate = state.delegate;
+
+    let bounds: CGRect = msg![env; this bounds];
+    let offset = bounds.origin;
+    let max = max_content_offset(env, this);
+    let (can_scroll_x, can_scroll_y) = scrollable_axes(env, this);
+
+    let settle_axis = |offset: CGFloat,
+                       start_offset: CGFloat,
+                       velocity: CGFloat,
+                       page_size: CGFloat,
+                       max: CGFloat| {
+        let target = if paging_enabled && page_size > 0.0 {
+            // A flick turns the page even if the drag was short.
+            let start_page = (start_offset / page_size).round();
+            let page = if velocity >= FLICK_SPEED {
+                start_page + 1.0
+            } else if velocity <= -FLICK_SPEED {
+                start_page - 1.0
+            } else {
+                (offset / page_size).round()
+            };
+            page * page_size
+        } else if velocity.abs() >= FLICK_SPEED {
+            // With an ease-out animation, the initial speed is three times
+            // the average speed.
+            offset + velocity * DECELERATION_DURATION.as_secs_f32() / 3.0
+        } else {
+            offset
+        };
+        CGFloat::clamp(target, 0.0, max)
+    };
+    let mut target = offset;
+    if can_scroll_x {
+        target.x = settle_axis(
+            offset.x,
+            start_offset.x,
+            velocity.x,
+            bounds.size.width,
+            max.x,
+        );
+    }
+    if can_scroll_y {
+        target.y = settle_axis(
+            offset.y,
+            start_offset.y,
+            velocity.y,
+            bounds.size.height,
+            max.y,
+        );
+    }
+
+    let will_decelerate = target.x != offset.x || target.y != offset.y;
+    if dragged
+        && delegate != nil
+        && responds_to_selector(env, delegate, "scrollViewDidEndDragging:willDecelerate:")
+    {
+        () = msg![env; delegate scrollViewDidEndDragging:this willDecelerate:will_decelerate];
+    }
+    if will_decelerate {
+        if delegate != nil
+            && responds_to_selector(env, delegate, "scrollViewWillBeginDecelerating:")
+        {
+            () = msg![env; delegate scrollViewWillBeginDecelerating:this];
+        }
+        let flicked = velocity.x.abs() >= FLICK_SPEED || velocity.y.abs() >= FLICK_SPEED;
+        let duration = if flicked && !paging_enabled {
+            DECELERATION_DURATION
+        } else {
+            SETTLE_DURATION
+        };
+        start_animation(env, this, offset, target, duration, true);
+    }
+    dragged
+}
+
+/// Get the location of a touch in the scroll view's superview, whose
+/// co-ordinate space doesn't change when the scroll view scrolls, and its
+/// timestamp.
+fn touch_location(env: &mut Environment, this: id, touches: id) -> (CGPoint, NSTimeInterval) {
+    let touch: id = msg![env; touches anyObject];
+    let superview: id = msg![env; this superview];
+    let point: CGPoint = msg![env; touch locationInView:superview];
+    let timestamp: NSTimeInterval = msg![env; touch timestamp];
+    (point, timestamp)
+}
+
+/// Get the largest content offset at which the content still fills the view.
+fn max_content_offset(env: &mut Environment, this: id) -> CGPoint {
+    let bounds: CGRect = msg![env; this bounds];
+    let content_size = scroll_view_state(&mut env.objc, this).content_size;
+    CGPoint {
+        x: (content_size.width - bounds.size.width).max(0.0),
+        y: (content_size.height - bounds.size.height).max(0.0),
+    }
+}
+
+/// Check which axes the scroll view can be dragged along: those that the
+/// content doesn't fit in, and those that always bounce.
+fn scrollable_axes(env: &mut Environment, this: id) -> (bool, bool) {
+    let bounds: CGRect = msg![env; this bounds];
+    let state = scroll_view_state(&mut env.objc, this);
+    (
+        state.content_size.width > bounds.size.width || state.always_bounce_horizontal,
+        state.content_size.height > bounds.size.height || state.always_bounce_vertical,
+    )
+}
+
+fn set_content_offset(env: &mut Environment, this: id, offset: CGPoint) {
+    let mut bounds: CGRect = msg![env; this bounds];
+    bounds.origin = offset;
+    () = msg![env; this setBounds:bounds];
+    // FIXME: This should be done using setNeedsLayout. UITableView relies on
+    // it to update its cells.
+    () = msg![env; this layoutSubviews];
+
+    let delegate = scroll_view_state(&mut env.objc, this).delegate;
+    if delegate != nil && responds_to_selector(env, delegate, "scrollViewDidScroll:") {
+        () = msg![env; delegate scrollViewDidScroll:this];
+    }
+}
+
+fn start_animation(
+    env: &mut Environment,
+    this: id,
+    from: CGPoint,
+    to: CGPoint,
+    duration: Duration,
+    decelerating: bool,
+) {
+    let start = env.clock.now();
+    let state = scroll_view_state(&mut env.objc, this);
+    let already_animating = state.animation.is_some();
+    state.animation = Some(Animation {
+        from,
+        to,
+        start,
+        duration,
+        decelerating,
+    });
+    if !already_animating {
+        retain(env, this);
+        env.framework_state
+            .uikit
+            .ui_scroll_view
+            .animating
+            .push(this);
+    }
+}
+
+/// Stop a scrolling animation, leaving the content where it is.
+fn stop_animation(env: &mut Environment, this: id) {
+    if scroll_view_state(&mut env.objc, this)
+        .animation
+        .take()
+        .is_none()
+    {
+        return;
+    }
+    let animating = &mut env.framework_state.uikit.ui_scroll_view.animating;
+    let index = animating.iter().position(|&v| v == this).unwrap();
+    animating.remove(index);
+    release(env, this);
+}
+
+/// For use by [super::handle_events]: advance any scrolling animations.
+///
+/// Returns the time the next step is due, if any animations are in progress.
+pub(super) fn animate_scroll_views(env: &mut Environment) -> Option<Instant> {
+    if env
+        .framework_state
+        .uikit
+        .ui_scroll_view
+        .animating
+        .is_empty()
+    {
+        return None;
+    }
+
+    // UIKit creates and drains autorelease pools when handling events.
+    let pool: id = msg_class![env; NSAutoreleasePool new];
+
+    let now = env.clock.now();
+    let animating = env.framework_state.uikit.ui_scroll_view.animating.clone();
+    for scroll_view in animating {
+        let Some(Animation {
+            from,
+            to,
+            start,
+            duration,
+            decelerating,
+        }) = scroll_view_state(&mut env.objc, scroll_view).animation
+        else {
+            // The animation was stopped by an earlier delegate call.
+            continue;
+        };
+        let progress = if duration.is_zero() {
+            1.0
+        } else {
+            (now.duration_since(start).as_secs_f32() / duration.as_secs_f32()).min(1.0)
+        };
+        // Ease out, so the content slows down smoothly.
+        let eased = 1.0 - (1.0 - progress).powi(3);
+        let offset = CGPoint {
+            x: from.x + (to.x - from.x) * eased,
+            y: from.y + (to.y - from.y) * eased,
+        };
+        set_content_offset(env, scroll_view, offset);
+
+        if progress < 1.0 {
+            continue;
+        }
+        // Stopping the animation releases the scroll view, so it has to be
+        // kept alive until the delegate has been notified.
+        retain(env, scroll_view);
+        stop_animation(env, scroll_view);
+        let delegate = scroll_view_state(&mut env.objc, scroll_view).delegate;
+        if delegate != nil {
+            if decelerating {
+                if responds_to_selector(env, delegate, "scrollViewDidEndDecelerating:") {
+                    () = msg![env; delegate scrollViewDidEndDecelerating:scroll_view];
+                }
+            } else if responds_to_selector(env, delegate, "scrollViewDidEndScrollingAnimation:") {
+                () = msg![env; delegate scrollViewDidEndScrollingAnimation:scroll_view];
+            }
+        }
+        release(env, scroll_view);
+    }
+
+    release(env, pool);
+
+    if env
+        .framework_state
+        .uikit
+        .ui_scroll_view
+        .animating
+        .is_empty()
+    {
+        None
+    } else {
+        Some(now + ANIMATION_INTERVAL)
+    }
+}
@@ -0,0 +1,253 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UITabBar`.
+//!
+//! Each item is shown as a button. The buttons are subviews that are
+//! recreated whenever the bar is laid out.
+//!
+//! TODO: badges, customization, the "More" item for more than five items.
+
+use super::ui_bar_item;
+use super::ui_button::UIButtonTypeCustom;
+use super::ui_control::{
+    UIControlEventTouchUpInside, UIControlStateNormal, UIControlStateSelected,
+};
+use super::ui_view::{self, UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain,
+    ClassExports, ObjC,
+};
+use crate::Environment;
+
+pub const TAB_BAR_HEIGHT: CGFloat = 49.0;
+
+/// State specific to `UITabBar`, see [UIViewSubclass].
+#[derive(Clone)]
+pub(super) struct TabBarState {
+    /// `UITabBarItem*`s. These are strong references.
+    items: Vec<id>,
+    /// One of the items, or nil. This is a weak reference.
+    selected_item: id,
+    /// This is a weak reference.
+    delegate: id,
+    /// The buttons for the items, in the same order. These are also subviews.
+    /// These are weak references.
+    buttons: Vec<id>,
+}
+impl Default for TabBarState {
+    fn default() -> Self {
+        TabBarState {
+            items: Vec::new(),
+            selected_item: nil,
+            delegate: nil,
+            buttons: Vec::new(),
+        }
+    }
+}
+impl TabBarState {
+    pub(super) fn release(self, env: &mut Environment) {
+        for item in self.items {
+            ui_bar_item::set_bar(env, item, nil);
+            release(env, item);
+        }
+    }
+}
+
+/// Get the bar's state, creating it if this is the first time it's needed.
+fn tab_bar_state(objc: &mut ObjC, bar: id) -> &mut TabBarState {
+    let host_object = objc.borrow_mut::<UIViewHostObject>(bar);
+    if !matches!(host_object.subclass, UIViewSubclass::UITabBar(_)) {
+        host_object.subclass = UIViewSubclass::UITabBar(TabBarState::default());
+    }
+    let UIViewSubclass::UITabBar(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UITabBar: UIView
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIItems");
+    let items: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if items != nil {
+        () = msg![env; this setItems:items];
+    }
+
+    this
+}
+
+- (id)delegate {
+    tab_bar_state(&mut env.objc, this).delegate
+}
+- (())setDelegate:(id)delegate {
+    tab_bar_state(&mut env.objc, this).delegate = delegate;
+}
+
+- (id)items {
+    let items = tab_bar_state(&mut env.objc, this).items.clone();
+    for &item in &items {
+        retain(env, item);
+    }
+    let array = ns_array::from_vec(env, items);
+    autorelease(env, array)
+}
+- (())setItems:(id)items { // NSArray* of UITabBarItem*
+    let count: NSUInteger = msg![env; items count];
+    let mut new_items = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let item: id = msg![env; items objectAtIndex:i];
+        new_items.push(retain(env, item));
+    }
+    let state = tab_bar_state(&mut env.objc, this);
+    let old_items = std::mem::replace(&mut state.items, new_items.clone());
+    if !new_items.contains(&state.selected_item) {
+        state.selected_item = nil;
+    }
+    for &item in &old_items {
+        ui_bar_item::set_bar(env, item, nil);
+    }
+    for &item in &new_items {
+        ui_bar_item::set_bar(env, item, this);
+    }
+    for item in old_items {
+        release(env, item);
+    }
+    () = msg![env; this layoutSubviews];
+}
+- (())setItems:(id)items // NSArray* of UITabBarItem*
+      animated:(bool)_animated {
+    // TODO: animation
+    msg![env; this setItems:items]
+}
+
+- (id)selectedItem {
+    tab_bar_state(&mut env.objc, this).selected_item
+}
+- (())setSelectedItem:(id)item { // UITabBarItem*
+    tab_bar_state(&mut env.objc, this).selected_item = item;
+    update_selection(env, this);
+}
+
+- (CGSize)sizeThatFits:(CGSize)size {
+    CGSize {
+        width: size.width,
+        height: TAB_BAR_HEIGHT,
+    }
+}
+
+- (())layoutSubviews {
+    layout_items(env, this);
+}
+
+// Action for the item buttons.
+- (())_itemButtonPressed:(id)button {
+    let state = tab_bar_state(&mut env.objc, this);
+    let Some(index) = state.buttons.iter().position(|&b| b == button) else {
+        return;
+    };
+    let item = state.items[index];
+    state.selected_item = item;
+    let delegate = state.delegate;
+    update_selection(env, this);
+    if delegate != nil && responds_to_selector(env, delegate, "tabBar:didSelectItem:") {
+        () = msg![env; delegate tabBar:this didSelectItem:item];
+    }
+}
+
+@end
+
+};
+
+/// Make only the selected item's button appear selected.
+fn update_selection(env: &mut Environment, bar: id) {
+    let state = tab_bar_state(&mut env.objc, bar);
+    let selected_item = state.selected_item;
+    let buttons: Vec<(id, id)> = state
+        .buttons
+        .iter()
+        .copied()
+        .zip(state.items.iter().copied())
+        .collect();
+    for (button, item) in buttons {
+        () = msg![env; button setSelected:(item == selected_item)];
+    }
+}
+
+/// Replace the buttons shown for the items.
+fn layout_items(env: &mut Environment, bar: id) {
+    let state = tab_bar_state(&mut env.objc, bar);
+    let old_buttons = std::mem::take(&mut state.buttons);
+    let items = state.items.clone();
+
+    for button in old_buttons {
+        // One of these might be the button whose action caused this, so it's
+        // kept alive until the event has been handled.
+        retain(env, button);
+        () = msg![env; button removeFromSuperview];
+        autorelease(env, button);
+    }
+
+    let background_color: id = msg_class![env; UIColor colorWithWhite:(0.1 as CGFloat)
+                                                                alpha:(1.0 as CGFloat)];
+    () = msg![env; bar setBackgroundColor:background_color];
+
+    if items.is_empty() {
+        return;
+    }
+
+    let bounds: CGRect = msg![env; bar bounds];
+    let button_width = bounds.size.width / items.len() as CGFloat;
+    let normal_color: id = msg_class![env; UIColor grayColor];
+    let selected_color: id = msg_class![env; UIColor whiteColor];
+    let font: id = msg_class![env; UIFont boldSystemFontOfSize:(10.0 as CGFloat)];
+    let action = env.objc.lookup_selector("_itemButtonPressed:").unwrap();
+
+    let mut buttons = Vec::with_capacity(items.len());
+    for (i, &item) in items.iter().enumerate() {
+        let title: id = msg![env; item title];
+        let image: id = msg![env; item image];
+        let enabled: bool = msg![env; item isEnabled];
+
+        let button: id = msg_class![env; UIButton buttonWithType:UIButtonTypeCustom];
+        () = msg![env; button setTitle:title forState:UIControlStateNormal];
+        () = msg![env; button setImage:image forState:UIControlStateNormal];
+        () = msg![env; button setTitleColor:normal_color forState:UIControlStateNormal];
+        () = msg![env; button setTitleColor:selected_color forState:UIControlStateSelected];
+        let title_label: id = msg![env; button titleLabel];
+        () = msg![env; title_label setFont:font];
+        () = msg![env; button setEnabled:enabled];
+        let frame = CGRect {
+            origin: CGPoint {
+                x: bounds.origin.x + button_width * i as CGFloat,
+                y: bounds.origin.y,
+            },
+            size: CGSize {
+                width: button_width,
+                height: bounds.size.height,
+            },
+        };
+        () = msg![env; button setFrame:frame];
+        () = msg![env; button addTarget:bar
+                                 action:action
+                       forControlEvents:UIControlEventTouchUpInside];
+        // addSubview: would lay out the bar again.
+        () = msg![env; bar insertSubview:button atIndex:(NSInteger::MAX)];
+        buttons.push(button);
+    }
+    tab_bar_state(&mut env.objc, bar).buttons = buttons;
+    update_selection(env, bar);
+}
@@ -0,0 +1,390 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UITabBarController`.
+//!
+//! The controller's view contains the tab bar and the view of the selected
+//! view controller. The bar's items are the view controllers' tab bar items.
+//!
+//! TODO: the "More" navigation controller, customization.
+
+use super::ui_tab_bar::TAB_BAR_HEIGHT;
+use super::ui_view_controller::{
+    self, set_parent, show_child, UIViewControllerHostObject, UIViewControllerSubclass,
+};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain,
+    ClassExports, ObjC,
+};
+use crate::Environment;
+
+/// Returned by `selectedIndex` if there's no selected view controller.
+const NSNotFound: NSUInteger = NSInteger::MAX as NSUInteger;
+
+/// State specific to `UITabBarController`, see [UIViewControllerSubclass].
+#[derive(Clone)]
+pub(super) struct TabBarControllerState {
+    /// These are strong references.
+    view_controllers: Vec<id>,
+    /// Index into `view_controllers`.
+    selected_index: Option<usize>,
+    /// `UITabBar*`, nil until it's first needed. This is a strong reference.
+    tab_bar: id,
+    /// This is a weak reference.
+    delegate: id,
+    /// The view controller whose view is shown, if any. This is a weak
+    /// reference.
+    shown: id,
+}
+impl Default for TabBarControllerState {
+    fn default() -> Self {
+        TabBarControllerState {
+            view_controllers: Vec::new(),
+            selected_index: None,
+            tab_bar: nil,
+            delegate: nil,
+            shown: nil,
+        }
+    }
+}
+impl TabBarControllerState {
+    pub(super) fn release(self, env: &mut Environment) {
+        for view_controller in self.view_controllers {
+            set_parent(env, view_controller, nil);
+            release(env, view_controller);
+        }
+        if self.tab_bar != nil {
+            () = msg![env; (self.tab_bar) setDelegate:nil];
+            release(env, self.tab_bar);
+        }
+    }
+}
+
+/// Get the controller's state, creating it if this is the first time it's
+/// needed.
+fn tab_bar_controller_state(objc: &mut ObjC, controller: id) -> &mut TabBarControllerState {
+    let host_object = objc.borrow_mut::<UIViewControllerHostObject>(controller);
+    if !matches!(
+        host_object.subclass,
+        UIViewControllerSubclass::UITabBarController(_)
+    ) {
+        host_object.subclass =
+            UIViewControllerSubclass::UITabBarController(TabBarControllerState::default());
+    }
+    let UIViewControllerSubclass::UITabBarController(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UITabBarController: UIViewController
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view_controller::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UITabBar");
+    let tab_bar: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if tab_bar != nil {
+        retain(env, tab_bar);
+        () = msg![env; tab_bar setDelegate:this];
+        tab_bar_controller_state(&mut env.objc, this).tab_bar = tab_bar;
+    }
+
+    let view_controllers = ui_view_controller::decode_child_view_controllers(env, coder);
+    set_view_controllers(env, this, view_controllers);
+
+    this
+}
+
+- (id)delegate {
+    tab_bar_controller_state(&mut env.objc, this).delegate
+}
+- (())setDelegate:(id)delegate {
+    tab_bar_controller_state(&mut env.objc, this).delegate = delegate;
+}
+
+- (id)viewControllers {
+    let view_controllers = tab_bar_controller_state(&mut env.objc, this)
+        .view_controllers
+        .clone();
+    for &view_controller in &view_controllers {
+        retain(env, view_controller);
+    }
+    let array = ns_array::from_vec(env, view_controllers);
+    autorelease(env, array)
+}
+- (())setViewControllers:(id)view_controllers { // NSArray* of UIViewController*
+    let count: NSUInteger = msg![env; view_controllers count];
+    let view_controllers = (0..count)
+        .map(|i| msg![env; view_controllers objectAtIndex:i])
+        .collect();
+    set_view_controllers(env, this, view_controllers);
+}
+- (())setViewControllers:(id)view_controllers // NSArray* of UIViewController*
+                animated:(bool)_animated {
+    // TODO: animation
+    msg![env; this setViewControllers:view_controllers]
+}
+
+- (NSUInteger)selectedIndex {
+    let state = tab_bar_controller_state(&mut env.objc, this);
+    state.selected_index.map_or(NSNotFound, |index| index as NSUInteger)
+}
+- (())setSelectedIndex:(NSUInteger)index {
+    let count = tab_bar_controller_state(&mut env.objc, this).view_controllers.len();
+    if (index as usize) < count {
+        select(env, this, index as usize);
+    }
+}
+
+- (id)selectedViewController {
+    let state = tab_bar_controller_state(&mut env.objc, this);
+    state.selected_index.map_or(nil, |index| state.view_controllers[index])
+}
+- (())setSelectedViewController:(id)view_controller { // UIViewController*
+    let Some(index) = tab_bar_controller_state(&mut env.objc, this)
+        .view_controllers
+        .iter()
+        .position(|&vc| vc == view_controller)
+    else {
+        return;
+    };
+    select(env, this, index);
+}
+
+- (id)tabBar {
+    tab_bar(env, this)
+}
+
+- (())loadView {
+    let screen: id = msg_class![env; UIScreen mainScreen];
+    let frame: CGRect = msg![env; screen applicationFrame];
+    let view: id = msg_class![env; UIView alloc];
+    let view: id = msg![env; view initWithFrame:frame];
+    () = msg![env; this setView:view];
+    release(env, view);
+
+    let tab_bar = tab_bar(env, this);
+    let bar_frame = CGRect {
+        origin: CGPoint {
+            x: 0.0,
+            y: (frame.size.height - TAB_BAR_HEIGHT).max(0.0),
+        },
+        size: CGSize {
+            width: frame.size.width,
+            height: TAB_BAR_HEIGHT,
+        },
+    };
+    () = msg![env; tab_bar setFrame:bar_frame];
+    () = msg![env; view insertSubview:tab_bar atIndex:(NSInteger::MAX)];
+    () = msg![env; tab_bar layoutSubviews];
+
+    show_selected(env, this);
+}
+
+// The appearance messages are forwarded to the view controller whose view is
+// shown.
+- (())viewWillAppear:(bool)animated {
+    let shown = tab_bar_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewWillAppear:animated]
+}
+- (())viewDidAppear:(bool)animated {
+    let shown = tab_bar_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewDidAppear:animated]
+}
+- (())viewWillDisappear:(bool)animated {
+    let shown = tab_bar_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewWillDisappear:animated]
+}
+- (())viewDidDisappear:(bool)animated {
+    let shown = tab_bar_controller_state(&mut env.objc, this).shown;
+    msg![env; shown viewDidDisappear:animated]
+}
+
+// UITabBarDelegate implementation
+- (())tabBar:(id)tab_bar // UITabBar*
+didSelectItem:(id)item { // UITabBarItem*
+    let state = tab_bar_controller_state(&mut env.objc, this);
+    let view_controllers = state.view_controllers.clone();
+    let previous_index = state.selected_index;
+    let delegate = state.delegate;
+
+    let mut index = None;
+    for (i, &view_controller) in view_controllers.iter().enumerate() {
+        let view_controller_item: id = msg![env; view_controller tabBarItem];
+        if view_controller_item == item {
+            index = Some(i);
+            break;
+        }
+    }
+    let Some(index) = index else {
+        return;
+    };
+    let view_controller = view_controllers[index];
+
+    if delegate != nil
+        && responds_to_selector(env, delegate, "tabBarController:shouldSelectViewController:")
+    {
+        let should_select: bool = msg![env; delegate tabBarController:this
+                                           shouldSelectViewController:view_controller];
+        if !should_select {
+            // Undo the tab bar's change.
+            let previous_item: id = match previous_index {
+                Some(previous_index) => {
+                    let previous = view_controllers[previous_index];
+                    msg![env; previous tabBarItem]
+                }
+                None => nil,
+            };
+            () = msg![env; tab_bar setSelectedItem:previous_item];
+            return;
+        }
+    }
+
+    select(env, this, index);
+
+    if delegate != nil
+        && responds_to_selector(env, delegate, "tabBarController:didSelectViewController:")
+    {
+        () = msg![env; delegate tabBarController:this
+                          didSelectViewController:view_controller];
+    }
+}
+
+@end
+
+};
+
+/// Get the tab bar, creating it if this is the first time it's needed.
+fn tab_bar(env: &mut Environment, controller: id) -> id {
+    let existing = tab_bar_controller_state(&mut env.objc, controller).tab_bar;
+    if existing != nil {
+        return existing;
+    }
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: 320.0,
+            height: TAB_BAR_HEIGHT,
+        },
+    };
+    let tab_bar: id = msg_class![env; UITabBar alloc];
+    let tab_bar: id = msg![env; tab_bar initWithFrame:frame];
+    () = msg![env; tab_bar setDelegate:controller];
+    tab_bar_controller_state(&mut env.objc, controller).tab_bar = tab_bar;
+    update_tab_bar(env, controller);
+    tab_bar
+}
+
+/// Make the tab bar's items match the view controllers.
+fn update_tab_bar(env: &mut Environment, controller: id) {
+    let state = tab_bar_controller_state(&mut env.objc, controller);
+    let tab_bar = state.tab_bar;
+    if tab_bar == nil {
+        // It'll be updated when it's created.
+        return;
+    }
+    let view_controllers = state.view_controllers.clone();
+    let selected_index = state.selected_index;
+
+    let items: Vec<id> = view_controllers
+        .into_iter()
+        .map(|view_controller| {
+            let item: id = msg![env; view_controller tabBarItem];
+            retain(env, item)
+        })
+        .collect();
+    let selected_item = selected_index.map_or(nil, |index| items[index]);
+    let items = ns_array::from_vec(env, items);
+    () = msg![env; tab_bar setItems:items];
+    () = msg![env; tab_bar setSelectedItem:selected_item];
+    release(env, items);
+}
+
+/// The frame for the view of the selected view controller, which fills the
+/// controller's view apart from the tab bar.
+fn content_frame(env: &mut Environment, controller: id) -> CGRect {
+    let view: id = msg![env; controller view];
+    let mut frame: CGRect = msg![env; view bounds];
+    frame.size.height = (frame.size.height - TAB_BAR_HEIGHT).max(0.0 as CGFloat);
+    frame
+}
+
+/// Replace the view controllers. The selection is kept if the selected view
+/// controller is still there, otherwise the first one is selected.
+fn set_view_controllers(env: &mut Environment, controller: id, view_controllers: Vec<id>) {
+    for &view_controller in &view_controllers {
+        retain(env, view_controller);
+        set_parent(env, view_controller, controller);
+    }
+    let state = tab_bar_controller_state(&mut env.objc, controller);
+    let previously_selected = state
+        .selected_index
+        .map(|index| state.view_controllers[index]);
+    state.selected_index = previously_selected
+        .and_then(|selected| view_controllers.iter().position(|&vc| vc == selected))
+        .or(if view_controllers.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    let old_view_controllers = std::mem::replace(&mut state.view_controllers, view_controllers);
+    let view_controllers = state.view_controllers.clone();
+    for &old_view_controller in &old_view_controllers {
+        if !view_controllers.contains(&old_view_controller) {
+            set_parent(env, old_view_controller, nil);
+        }
+    }
+
+    update_tab_bar(env, controller);
+    show_selected(env, controller);
+
+    // Removed view controllers are only released once they're no longer
+    // shown.
+    for old_view_controller in old_view_controllers {
+        release(env, old_view_controller);
+    }
+}
+
+/// Select a view controller and show its view.
+fn select(env: &mut Environment, controller: id, index: usize) {
+    tab_bar_controller_state(&mut env.objc, controller).selected_index = Some(index);
+    update_tab_bar(env, controller);
+    show_selected(env, controller);
+}
+
+/// Show the view of the selected view controller, if the controller's view is
+/// loaded. Otherwise, this happens when it's loaded.
+fn show_selected(env: &mut Environment, controller: id) {
+    if !msg![env; controller isViewLoaded] {
+        return;
+    }
+    let state = tab_bar_controller_state(&mut env.objc, controller);
+    let selected = state
+        .selected_index
+        .map_or(nil, |index| state.view_controllers[index]);
+    let shown = state.shown;
+    if selected == shown {
+        return;
+    }
+
+    log_dbg!(
+        "(UITabBarController*){:?} shows (UIViewController*){:?}",
+        controller,
+        selected
+    );
+
+    let frame = content_frame(env, controller);
+    tab_bar_controller_state(&mut env.objc, controller).shown = selected;
+    show_child(env, controller, shown, selected, frame, false);
+}
@@ -0,0 +1,787 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UITableView`.
+//!
+//! The table only has cells for the rows that are visible. When a row scrolls
+//! out of view, its cell is removed and kept for reuse, so the data source can
+//! get it back with `dequeueReusableCellWithIdentifier:`.
+//!
+//! Like on the real iPhone OS, the data is loaded when the table is first laid
+//! out, which happens at the end of the current run loop iteration at the
+//! latest, so the data source is set up by then.
+//!
+//! TODO: grouped style appearance, section footers, index titles, editing,
+//! table header and footer views.
+
+use super::ui_scroll_view::{self, scroll_view_state, UIScrollViewSubclass};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_index_path::{from_section_and_row, to_section_and_row};
+use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
+use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, responds_to_selector, retain,
+    ClassExports, ObjC,
+};
+use crate::Environment;
+use std::collections::HashMap;
+
+pub type UITableViewStyle = NSInteger;
+pub const UITableViewStylePlain: UITableViewStyle = 0;
+#[allow(dead_code)]
+pub const UITableViewStyleGrouped: UITableViewStyle = 1;
+
+pub type UITableViewScrollPosition = NSInteger;
+#[allow(dead_code)]
+pub const UITableViewScrollPositionNone: UITableViewScrollPosition = 0;
+pub const UITableViewScrollPositionTop: UITableViewScrollPosition = 1;
+pub const UITableViewScrollPositionMiddle: UITableViewScrollPosition = 2;
+pub const UITableViewScrollPositionBottom: UITableViewScrollPosition = 3;
+
+const DEFAULT_ROW_HEIGHT: CGFloat = 44.0;
+const DEFAULT_SECTION_HEADER_HEIGHT: CGFloat = 22.0;
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Tables whose data source has been set but which may not have loaded
+    /// their data yet. These are strong references.
+    pending_loads: Vec<id>,
+}
+
+/// A row is identified by its section and its index within that section.
+type Row = (NSUInteger, NSUInteger);
+
+/// Where a section's header and rows are, as of the last `reloadData`.
+#[derive(Clone)]
+struct SectionLayout {
+    header_y: CGFloat,
+    header_height: CGFloat,
+    /// `UIView*`, possibly nil. This is a strong reference.
+    header_view: id,
+    /// The y co-ordinate and height of each row.
+    rows: Vec<(CGFloat, CGFloat)>,
+}
+
+/// State specific to `UITableView`, see [UIScrollViewSubclass].
+#[derive(Clone)]
+pub(super) struct TableViewState {
+    /// This is a weak reference.
+    data_source: id,
+    style: UITableViewStyle,
+    row_height: CGFloat,
+    allows_selection: bool,
+    /// [None] if the data hasn't been loaded yet.
+    sections: Option<Vec<SectionLayout>>,
+    /// The cells for the visible rows. These are subviews of the table, so
+    /// they are weak references.
+    visible_cells: Vec<(Row, id)>,
+    /// Cells that have been scrolled out of view, by reuse identifier. These
+    /// are strong references.
+    reusable_cells: HashMap<String, Vec<id>>,
+    selected_row: Option<Row>,
+    /// Set while the table is creating cells, so that it isn't re-entered.
+    tiling: bool,
+}
+impl Default for TableViewState {
+    fn default() -> Self {
+        TableViewState {
+            data_source: nil,
+            style: UITableViewStylePlain,
+            row_height: DEFAULT_ROW_HEIGHT,
+            allows_selection: true,
+            sections: None,
+            visible_cells: Vec::new(),
+            reusable_cells: HashMap::new(),
+            selected_row: None,
+            tiling: false,
+        }
+    }
+}
+impl TableViewState {
+    pub(super) fn release(self, env: &mut Environment) {
+        for section in self.sections.into_iter().flatten() {
+            release(env, section.header_view);
+        }
+        for cell in self.reusable_cells.into_values().flatten() {
+            release(env, cell);
+        }
+    }
+}
+
+/// Get the table view's state, creating it if this is the first time it's
+/// needed.
+fn table_view_state(objc: &mut ObjC, table_view: id) -> &mut TableViewState {
+    let state = scroll_view_state(objc, table_view);
+    if !matches!(state.subclass, UIScrollViewSubclass::UITableView(_)) {
+        state.subclass = UIScrollViewSubclass::UITableView(TableViewState::default());
+    }
+    let UIScrollViewSubclass::UITableView(ref mut state) = state.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UITableView: UIScrollView
+
+- (id)initWithFrame:(CGRect)frame
+              style:(UITableViewStyle)style {
+    let this: id = msg![env; this initWithFrame:frame];
+    table_view_state(&mut env.objc, this).style = style;
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_scroll_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIStyle");
+    let style: UITableViewStyle = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIRowHeight");
+    let row_height: CGFloat = if msg![env; coder containsValueForKey:key_ns_string] {
+        msg![env; coder decodeFloatForKey:key_ns_string]
+    } else {
+        DEFAULT_ROW_HEIGHT
+    };
+    let key_ns_string = get_static_str(env, "UIAllowsSelection");
+    let allows_selection: bool = if msg![env; coder containsValueForKey:key_ns_string] {
+        msg![env; coder decodeBoolForKey:key_ns_string]
+    } else {
+        true
+    };
+
+    let state = table_view_state(&mut env.objc, this);
+    state.style = style;
+    state.row_height = row_height;
+    state.allows_selection = allows_selection;
+
+    this
+}
+
+- (UITableViewStyle)style {
+    table_view_state(&mut env.objc, this).style
+}
+
+- (id)dataSource {
+    table_view_state(&mut env.objc, this).data_source
+}
+- (())setDataSource:(id)data_source {
+    table_view_state(&mut env.objc, this).data_source = data_source;
+    let pending_loads = &mut env.framework_state.uikit.ui_table_view.pending_loads;
+    if data_source != nil && !pending_loads.contains(&this) {
+        pending_loads.push(this);
+        retain(env, this);
+    }
+}
+
+- (CGFloat)rowHeight {
+    table_view_state(&mut env.objc, this).row_height
+}
+- (())setRowHeight:(CGFloat)height {
+    table_view_state(&mut env.objc, this).row_height = height;
+}
+
+- (bool)allowsSelection {
+    table_view_state(&mut env.objc, this).allows_selection
+}
+- (())setAllowsSelection:(bool)allows {
+    table_view_state(&mut env.objc, this).allows_selection = allows;
+}
+
+// TODO: draw separators
+- (())setSeparatorStyle:(NSInteger)_style {}
+- (())setSeparatorColor:(id)_color {} // UIColor*
+
+- (())reloadData {
+    reload_data(env, this);
+}
+// Changes to the rows are not animated, so the table is just reloaded.
+- (())beginUpdates {}
+- (())endUpdates {
+    reload_data(env, this);
+}
+- (())insertRowsAtIndexPaths:(id)_index_paths // NSArray* of NSIndexPath*
+            withRowAnimation:(NSInteger)_animation {
+    reload_data(env, this);
+}
+- (())deleteRowsAtIndexPaths:(id)_index_paths // NSArray* of NSIndexPath*
+            withRowAnimation:(NSInteger)_animation {
+    reload_data(env, this);
+}
+- (())reloadRowsAtIndexPaths:(id)_index_paths // NSArray* of NSIndexPath*
+            withRowAnimation:(NSInteger)_animation {
+    reload_data(env, this);
+}
+
+- (NSInteger)numberOfSections {
+    ensure_data_loaded(env, this);
+    let sections = table_view_state(&mut env.objc, this).sections.as_ref().unwrap();
+    sections.len().try_into().unwrap()
+}
+- (NSInteger)numberOfRowsInSection:(NSInteger)section {
+    ensure_data_loaded(env, this);
+    let sections = table_view_state(&mut env.objc, this).sections.as_ref().unwrap();
+    // A section that doesn't exist has no rows.
+    usize::try_from(section)
+        .ok()
+        .and_then(|section| sections.get(section))
+        .map_or(0, |section| section.rows.len().try_into().unwrap())
+}
+
+- (CGRect)rectForRowAtIndexPath:(id)index_path { // NSIndexPath*
+    let row = to_section_and_row(env, index_path);
+    ensure_data_loaded(env, this);
+    rect_for_row(env, this, row)
+}
+- (id)indexPathForRowAtPoint:(CGPoint)point {
+    ensure_data_loaded(env, this);
+    match row_at_point(env, this, point) {
+        Some((section, row)) => {
+            let index_path = from_section_and_row(env, section, row);
+            autorelease(env, index_path)
+        }
+        None => nil,
+    }
+}
+- (id)indexPathForCell:(id)cell { // UITableViewCell*
+    let state = table_view_state(&mut env.objc, this);
+    match state.visible_cells.iter().find(|&&(_, c)| c == cell) {
+        Some(&((section, row), _)) => {
+            let index_path = from_section_and_row(env, section, row);
+            autorelease(env, index_path)
+        }
+        None => nil,
+    }
+}
+- (id)cellForRowAtIndexPath:(id)index_path { // NSIndexPath*
+    let row = to_section_and_row(env, index_path);
+    let state = table_view_state(&mut env.objc, this);
+    match state.visible_cells.iter().find(|&&(r, _)| r == row) {
+        Some(&(_, cell)) => cell,
+        None => nil,
+    }
+}
+- (id)visibleCells {
+    let mut visible_cells = table_view_state(&mut env.objc, this).visible_cells.clone();
+    visible_cells.sort_by_key(|&(row, _)| row);
+    let cells: Vec<id> = visible_cells.into_iter().map(|(_, cell)| cell).collect();
+    for &cell in &cells {
+        retain(env, cell);
+    }
+    let array = ns_array::from_vec(env, cells);
+    autorelease(env, array)
+}
+- (id)indexPathsForVisibleRows {
+    let mut rows: Vec<Row> = table_view_state(&mut env.objc, this)
+        .visible_cells
+        .iter()
+        .map(|&(row, _)| row)
+        .collect();
+    rows.sort();
+    let index_paths = rows
+        .into_iter()
+        .map(|(section, row)| from_section_and_row(env, section, row))
+        .collect();
+    let array = ns_array::from_vec(env, index_paths);
+    autorelease(env, array)
+}
+
+- (id)dequeueReusableCellWithIdentifier:(id)identifier { // NSString*
+    let identifier = to_rust_string(env, identifier);
+    let state = table_view_state(&mut env.objc, this);
+    let Some(cell) = state
+        .reusable_cells
+        .get_mut(&*identifier)
+        .and_then(|cells| cells.pop())
+    else {
+        return nil;
+    };
+    () = msg![env; cell prepareForReuse];
+    autorelease(env, cell)
+}
+
+- (())scrollToRowAtIndexPath:(id)index_path // NSIndexPath*
+            atScrollPosition:(UITableViewScrollPosition)position
+                    animated:(bool)animated {
+    let row = to_section_and_row(env, index_path);
+    ensure_data_loaded(env, this);
+    scroll_to_row(env, this, row, position, animated);
+}
+
+- (id)indexPathForSelectedRow {
+    match table_view_state(&mut env.objc, this).selected_row {
+        Some((section, row)) => {
+            let index_path = from_section_and_row(env, section, row);
+            autorelease(env, index_path)
+        }
+        None => nil,
+    }
+}
+- (())selectRowAtIndexPath:(id)index_path // NSIndexPath*, possibly nil
+                  animated:(bool)animated
+            scrollPosition:(UITableViewScrollPosition)position {
+    let row = if index_path == nil {
+        None
+    } else {
+        Some(to_section_and_row(env, index_path))
+    };
+    set_selected_row(env, this, row);
+    if let Some(row) = row {
+        ensure_data_loaded(env, this);
+        scroll_to_row(env, this, row, position, animated);
+    }
+}
+- (())deselectRowAtIndexPath:(id)index_path // NSIndexPath*
+                    animated:(bool)_animated {
+    let row = to_section_and_row(env, index_path);
+    if table_view_state(&mut env.objc, this).selected_row == Some(row) {
+        set_selected_row(env, this, None);
+    }
+}
+
+- (())layoutSubviews {
+    let state = table_view_state(&mut env.objc, this);
+    if state.sections.is_none() {
+        if state.data_source != nil {
+            reload_data(env, this);
+        }
+    } else {
+        tile_cells(env, this);
+    }
+}
+
+// UIResponder implementation
+- (())touchesBegan:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    ui_scroll_view::touches_began(env, this, touches);
+}
+- (())touchesMoved:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    ui_scroll_view::touches_moved(env, this, touches);
+}
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    if ui_scroll_view::touches_ended(env, this) {
+        return;
+    }
+    // The touch was a tap, which selects the row under it.
+    let touch: id = msg![env; touches anyObject];
+    let point: CGPoint = msg![env; touch locationInView:this];
+    if let Some(row) = row_at_point(env, this, point) {
+        select_row_by_user(env, this, row);
+    }
+}
+
+@end
+
+};
+
+/// For use by [super::handle_events]: load the data of any tables that haven't
+/// been laid out since their data source was set.
+pub(super) fn load_pending_tables(env: &mut Environment) {
+    let pending_loads = std::mem::take(&mut env.framework_state.uikit.ui_table_view.pending_loads);
+    if pending_loads.is_empty() {
+        return;
+    }
+
+    // UIKit creates and drains autorelease pools when handling events.
+    let pool: id = msg_class![env; NSAutoreleasePool new];
+    for table_view in pending_loads {
+        ensure_data_loaded(env, table_view);
+        release(env, table_view);
+    }
+    release(env, pool);
+}
+
+fn ensure_data_loaded(env: &mut Environment, this: id) {
+    if table_view_state(&mut env.objc, this).sections.is_none() {
+        reload_data(env, this);
+    }
+}
+
+/// Ask the data source and the delegate for the sections and rows, then
+/// replace all the cells.
+fn reload_data(env: &mut Environment, this: id) {
+    // All the old cells can be reused for the new rows.
+    let state = table_view_state(&mut env.objc, this);
+    let old_cells = std::mem::take(&mut state.visible_cells);
+    let old_sections = state.sections.take();
+    for (_, cell) in old_cells {
+        recycle_cell(env, this, cell);
+    }
+    for section in old_sections.into_iter().flatten() {
+        let header_view = section.header_view;
+        if header_view != nil {
+            () = msg![env; header_view removeFromSuperview];
+            release(env, header_view);
+        }
+    }
+
+    let state = table_view_state(&mut env.objc, this);
+    let data_source = state.data_source;
+    let row_height = state.row_height;
+    let delegate = scroll_view_state(&mut env.objc, this).delegate;
+
+    let section_count: NSInteger = if data_source != nil
+        && responds_to_selector(env, data_source, "numberOfSectionsInTableView:")
+    {
+        msg![env; data_source numberOfSectionsInTableView:this]
+    } else {
+        1
+    };
+
+    let mut sections = Vec::new();
+    let mut y = 0.0;
+    for section in 0..section_count.max(0) {
+        let header_view = section_header_view(env, this, section);
+        let header_height: CGFloat = if delegate != nil
+            && responds_to_selector(env, delegate, "tableView:heightForHeaderInSection:")
+        {
+            msg![env; delegate tableView:this heightForHeaderInSection:section]
+        } else if header_view != nil {
+            DEFAULT_SECTION_HEADER_HEIGHT
+        } else {
+            0.0
+        };
+        let header_y = y;
+        y += header_height;
+
+        let row_count: NSInteger = if data_source != nil {
+            msg![env; data_source tableView:this numberOfRowsInSection:section]
+        } else {
+            0
+        };
+        let mut rows = Vec::new();
+        for row in 0..row_count.max(0) {
+            let height: CGFloat = if delegate != nil
+                && responds_to_selector(env, delegate, "tableView:heightForRowAtIndexPath:")
+            {
+                let index_path =
+                    from_section_and_row(env, section as NSUInteger, row as NSUInteger);
+                let height = msg![env; delegate tableView:this heightForRowAtIndexPath:index_path];
+                release(env, index_path);
+                height
+            } else {
+                row_height
+            };
+            rows.push((y, height));
+            y += height;
+        }
+
+        if header_view != nil {
+            // addSubview: would lay out the table again.
+            let index = NSInteger::MAX;
+            () = msg![env; this insertSubview:header_view atIndex:index];
+        }
+        sections.push(SectionLayout {
+            header_y,
+            header_height,
+            header_view,
+            rows,
+        });
+    }
+
+    log_dbg!(
+        "[(UITableView*){:?} reloadData]: {} sections, {} rows",
+        this,
+        sections.len(),
+        sections.iter().map(|s| s.rows.len()).sum::<usize>()
+    );
+
+    let state = table_view_state(&mut env.objc, this);
+    state.sections = Some(sections);
+    if let Some((section, row)) = state.selected_row {
+        if !row_exists(state, (section, row)) {
+            state.selected_row = None;
+        }
+    }
+
+    let bounds: CGRect = msg![env; this bounds];
+    let content_size = CGSize {
+        width: bounds.size.width,
+        height: y,
+    };
+    () = msg![env; this setContentSize:content_size];
+
+    tile_cells(env, this);
+}
+
+/// Get a section's header view from the delegate, or make one with the title
+/// from the data source. The result is retained.
+fn section_header_view(env: &mut Environment, this: id, section: NSInteger) -> id {
+    let delegate = scroll_view_state(&mut env.objc, this).delegate;
+    if delegate != nil && responds_to_selector(env, delegate, "tableView:viewForHeaderInSection:") {
+        let view: id = msg![env; delegate tableView:this viewForHeaderInSection:section];
+        if view != nil {
+            return retain(env, view);
+        }
+    }
+
+    let data_source = table_view_state(&mut env.objc, this).data_source;
+    if data_source == nil
+        || !responds_to_selector(env, data_source, "tableView:titleForHeaderInSection:")
+    {
+        return nil;
+    }
+    let title: id = msg![env; data_source tableView:this titleForHeaderInSection:section];
+    if title == nil {
+        return nil;
+    }
+    let label: id = msg_class![env; UILabel new];
+    () = msg![env; label setText:title];
+    let font: id = msg_class![env; UIFont boldSystemFontOfSize:(18.0 as CGFloat)];
+    () = msg![env; label setFont:font];
+    let white: id = msg_class![env; UIColor whiteColor];
+    () = msg![env; label setTextColor:white];
+    let gray: id = msg_class![env; UIColor colorWithRed:(0.55 as CGFloat)
+                                                   green:(0.6 as CGFloat)
+                                                    blue:(0.65 as CGFloat)
+                                                   alpha:(0.9 as CGFloat)];
+    () = msg![env; label setBackgroundColor:gray];
+    label
+}
+
+fn row_exists(state: &TableViewState, (section, row): Row) -> bool {
+    state
+        .sections
+        .as_ref()
+        .and_then(|sections| sections.get(section as usize))
+        .map_or(false, |section| (row as usize) < section.rows.len())
+}
+
+fn rect_for_row(env: &mut Environment, this: id, (section, row): Row) -> CGRect {
+    let bounds: CGRect = msg![env; this bounds];
+    let state = table_view_state(&mut env.objc, this);
+    let (y, height) = state
+        .sections
+        .as_ref()
+        .and_then(|sections| sections.get(section as usize))
+        .and_then(|section| section.rows.get(row as usize))
+        .copied()
+        .unwrap_or((0.0, 0.0));
+    CGRect {
+        origin: CGPoint { x: 0.0, y },
+        size: CGSize {
+            width: bounds.size.width,
+            height,
+        },
+    }
+}
+
+fn row_at_point(env: &mut Environment, this: id, point: CGPoint) -> Option<Row> {
+    let sections = table_view_state(&mut env.objc, this).sections.as_ref()?;
+    for (section_index, section) in sections.iter().enumerate() {
+        for (row_index, &(y, height)) in section.rows.iter().enumerate() {
+            if point.y >= y && point.y < y + height {
+                return Some((section_index as NSUInteger, row_index as NSUInteger));
+            }
+        }
+    }
+    None
+}
+
+/// Remove a cell from the table and keep it for reuse, if it has a reuse
+/// identifier.
+fn recycle_cell(env: &mut Environment, this: id, cell: id) {
+    retain(env, cell);
+    () = msg![env; cell removeFromSuperview];
+    let identifier: id = msg![env; cell reuseIdentifier];
+    if identifier == nil {
+        release(env, cell);
+        return;
+    }
+    let identifier = to_rust_string(env, identifier).to_string();
+    table_view_state(&mut env.objc, this)
+        .reusable_cells
+        .entry(identifier)
+        .or_default()
+        .push(cell);
+}
+
+/// Make sure the visible rows, and only those, have cells, and position them
+/// and the section headers.
+fn tile_cells(env: &mut Environment, this: id) {
+    let state = table_view_state(&mut env.objc, this);
+    if state.tiling || state.sections.is_none() {
+        return;
+    }
+    state.tiling = true;
+
+    let bounds: CGRect = msg![env; this bounds];
+    let visible_top = bounds.origin.y;
+    let visible_bottom = bounds.origin.y + bounds.size.height;
+
+    let state = table_view_state(&mut env.objc, this);
+    let sections = state.sections.as_ref().unwrap();
+    let mut visible_rows = Vec::new();
+    let mut headers = Vec::new();
+    for (section_index, section) in sections.iter().enumerate() {
+        for (row_index, &(y, height)) in section.rows.iter().enumerate() {
+            if y + height > visible_top && y < visible_bottom {
+                let row = (section_index as NSUInteger, row_index as NSUInteger);
+                visible_rows.push((row, y, height));
+            }
+        }
+        if section.header_view != nil {
+            headers.push((section.header_view, section.header_y, section.header_height));
+        }
+    }
+
+    // Recycle the cells that have gone out of view first, so the data source
+    // can reuse them for the rows that have come into view.
+    let old_cells = std::mem::take(&mut state.visible_cells);
+    let mut kept_cells = Vec::new();
+    for (row, cell) in old_cells {
+        if visible_rows.iter().any(|&(r, _, _)| r == row) {
+            kept_cells.push((row, cell));
+        } else {
+            recycle_cell(env, this, cell);
+        }
+    }
+    table_view_state(&mut env.objc, this).visible_cells = kept_cells;
+
+    let data_source = table_view_state(&mut env.objc, this).data_source;
+    for (row, y, height) in visible_rows {
+        let state = table_view_state(&mut env.objc, this);
+        let existing = state
+            .visible_cells
+            .iter()
+            .find(|&&(r, _)| r == row)
+            .map(|&(_, cell)| cell);
+        let selected = state.selected_row == Some(row);
+        let cell = match existing {
+            Some(cell) => cell,
+            None => {
+                let index_path = from_section_and_row(env, row.0, row.1);
+                let cell: id =
+                    msg![env; data_source tableView:this cellForRowAtIndexPath:index_path];
+                release(env, index_path);
+                if cell == nil {
+                    log!(
+                        "Warning: data source {:?} returned nil cell for row {:?} of table {:?}",
+                        data_source,
+                        row,
+                        this
+                    );
+                    continue;
+                }
+                // addSubview: would lay out the table again.
+                let index: NSInteger = 0;
+                () = msg![env; this insertSubview:cell atIndex:index];
+                () = msg![env; cell setSelected:selected];
+                table_view_state(&mut env.objc, this)
+                    .visible_cells
+                    .push((row, cell));
+                cell
+            }
+        };
+        let frame = CGRect {
+            origin: CGPoint { x: 0.0, y },
+            size: CGSize {
+                width: bounds.size.width,
+                height,
+            },
+        };
+        () = msg![env; cell setFrame:frame];
+        () = msg![env; cell layoutSubviews];
+    }
+
+    // In a plain table, the header of the section at the top sticks to the
+    // top of the view until the next section pushes it away.
+    let style = table_view_state(&mut env.objc, this).style;
+    for (i, &(header_view, header_y, header_height)) in headers.iter().enumerate() {
+        let mut y = header_y;
+        if style == UITableViewStylePlain {
+            let next_header_y = headers.get(i + 1).map_or(CGFloat::INFINITY, |&(_, y, _)| y);
+            y = y
+                .max(visible_top)
+                .min(next_header_y - header_height)
+                .max(header_y);
+        }
+        let frame = CGRect {
+            origin: CGPoint { x: 0.0, y },
+            size: CGSize {
+                width: bounds.size.width,
+                height: header_height,
+            },
+        };
+        () = msg![env; header_view setFrame:frame];
+        () = msg![env; this bringSubviewToFront:header_view];
+    }
+
+    table_view_state(&mut env.objc, this).tiling = false;
+}
+
+/// Scroll so that a row is at the requested position, or just visible.
+fn scroll_to_row(
+    env: &mut Environment,
+    this: id,
+    row: Row,
+    position: UITableViewScrollPosition,
+    animated: bool,
+) {
+    let rect = rect_for_row(env, this, row);
+    let bounds: CGRect = msg![env; this bounds];
+    let content_size: CGSize = msg![env; this contentSize];
+    let max_y = (content_size.height - bounds.size.height).max(0.0);
+    let y = match position {
+        UITableViewScrollPositionTop => rect.origin.y,
+        UITableViewScrollPositionMiddle => {
+            rect.origin.y - (bounds.size.height - rect.size.height) / 2.0
+        }
+        UITableViewScrollPositionBottom => rect.origin.y + rect.size.height - bounds.size.height,
+        _ => return msg![env; this scrollRectToVisible:rect animated:animated],
+    };
+    let offset = CGPoint {
+        x: bounds.origin.x,
+        y: y.clamp(0.0, max_y),
+    };
+    () = msg![env; this setContentOffset:offset animated:animated];
+}
+
+fn set_selected_row(env: &mut Environment, this: id, row: Option<Row>) {
+    let state = table_view_state(&mut env.objc, this);
+    let old_row = std::mem::replace(&mut state.selected_row, row);
+    let cells: Vec<(Row, id)> = state.visible_cells.clone();
+    for (cell_row, cell) in cells {
+        if Some(cell_row) == old_row && old_row != row {
+            () = msg![env; cell setSelected:false];
+        } else if Some(cell_row) == row {
+            () = msg![env; cell setSelected:true];
+        }
+    }
+}
+
+/// Select a row the user tapped, letting the delegate decide what happens.
+fn select_row_by_user(env: &mut Environment, this: id, row: Row) {
+    if !table_view_state(&mut env.objc, this).allows_selection {
+        return;
+    }
+    let delegate = scroll_view_state(&mut env.objc, this).delegate;
+
+    let index_path = from_section_and_row(env, row.0, row.1);
+    autorelease(env, index_path);
+    let index_path: id = if delegate != nil
+        && responds_to_selector(env, delegate, "tableView:willSelectRowAtIndexPath:")
+    {
+        msg![env; delegate tableView:this willSelectRowAtIndexPath:index_path]
+    } else {
+        index_path
+    };
+    if index_path == nil {
+        return;
+    }
+    let row = to_section_and_row(env, index_path);
+
+    log_dbg!("[(UITableView*){:?}] user selected row {:?}", this, row);
+    set_selected_row(env, this, Some(row));
+    if delegate != nil && responds_to_selector(env, delegate, "tableView:didSelectRowAtIndexPath:")
+    {
+        () = msg![env; delegate tableView:this didSelectRowAtIndexPath:index_path];
+    }
+}
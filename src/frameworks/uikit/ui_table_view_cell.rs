@@ -0,0 +1,542 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UITableViewCell`.
+//!
+//! The cell's subviews (content view, labels, image view and selection
+//! background) are only created when first needed.
+//!
+//! TODO: drawing accessories, white text when selected.
+
+use super::ui_font::{UITextAlignmentLeft, UITextAlignmentRight};
+use super::ui_view::{self, UIViewHostObject, UIViewSubclass};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::NSInteger;
+use crate::objc::{id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, ObjC};
+use crate::Environment;
+
+pub type UITableViewCellStyle = NSInteger;
+pub const UITableViewCellStyleDefault: UITableViewCellStyle = 0;
+pub const UITableViewCellStyleValue1: UITableViewCellStyle = 1;
+pub const UITableViewCellStyleValue2: UITableViewCellStyle = 2;
+pub const UITableViewCellStyleSubtitle: UITableViewCellStyle = 3;
+
+pub type UITableViewCellSelectionStyle = NSInteger;
+pub const UITableViewCellSelectionStyleNone: UITableViewCellSelectionStyle = 0;
+pub const UITableViewCellSelectionStyleBlue: UITableViewCellSelectionStyle = 1;
+#[allow(dead_code)]
+pub const UITableViewCellSelectionStyleGray: UITableViewCellSelectionStyle = 2;
+
+pub type UITableViewCellAccessoryType = NSInteger;
+pub const UITableViewCellAccessoryNone: UITableViewCellAccessoryType = 0;
+
+/// Default size of a cell, as on the real iPhone OS.
+const DEFAULT_SIZE: CGSize = CGSize {
+    width: 320.0,
+    height: 44.0,
+};
+
+/// Horizontal space between the edges of the cell and its content.
+const MARGIN: CGFloat = 10.0;
+
+/// State specific to `UITableViewCell`, see [UIViewSubclass].
+#[derive(Clone)]
+pub(super) struct TableViewCellState {
+    /// `NSString*`, possibly nil. This is a strong reference.
+    reuse_identifier: id,
+    style: UITableViewCellStyle,
+    // The following views are subviews of the cell or its content view, so
+    // they are weak references. They are nil until they are first needed.
+    content_view: id,
+    text_label: id,
+    detail_text_label: id,
+    image_view: id,
+    selection_view: id,
+    /// `UIView*`, possibly nil. This is a strong reference.
+    accessory_view: id,
+    accessory_type: UITableViewCellAccessoryType,
+    selection_style: UITableViewCellSelectionStyle,
+    selected: bool,
+    highlighted: bool,
+}
+impl Default for TableViewCellState {
+    fn default() -> Self {
+        TableViewCellState {
+            reuse_identifier: nil,
+            style: UITableViewCellStyleDefault,
+            content_view: nil,
+            text_label: nil,
+            detail_text_label: nil,
+            image_view: nil,
+            selection_view: nil,
+            accessory_view: nil,
+            accessory_type: UITableViewCellAccessoryNone,
+            selection_style: UITableViewCellSelectionStyleBlue,
+            selected: false,
+            highlighted: false,
+        }
+    }
+}
+impl TableViewCellState {
+    pub(super) fn release(self, env: &mut Environment) {
+        release(env, self.reuse_identifier);
+        release(env, self.accessory_view);
+    }
+}
+
+/// Get the cell's state, creating it if this is the first time it's needed.
+fn cell_state(objc: &mut ObjC, cell: id) -> &mut TableViewCellState {
+    let host_object = objc.borrow_mut::<UIViewHostObject>(cell);
+    if !matches!(host_object.subclass, UIViewSubclass::UITableViewCell(_)) {
+        host_object.subclass = UIViewSubclass::UITableViewCell(TableViewCellState::default());
+    }
+    let UIViewSubclass::UITableViewCell(ref mut state) = host_object.subclass else {
+        unreachable!();
+    };
+    state
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UITableViewCell: UIView
+
+- (id)initWithStyle:(UITableViewCellStyle)style
+    reuseIdentifier:(id)reuse_identifier { // NSString*
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: DEFAULT_SIZE,
+    };
+    let this: id = msg![env; this initWithFrame:frame];
+    let reuse_identifier: id = msg![env; reuse_identifier copy];
+    let state = cell_state(&mut env.objc, this);
+    state.style = style;
+    state.reuse_identifier = reuse_identifier;
+    this
+}
+// Deprecated in iPhone OS 3.0
+- (id)initWithFrame:(CGRect)frame
+    reuseIdentifier:(id)reuse_identifier { // NSString*
+    let this: id = msg![env; this initWithFrame:frame];
+    let reuse_identifier: id = msg![env; reuse_identifier copy];
+    cell_state(&mut env.objc, this).reuse_identifier = reuse_identifier;
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let this = ui_view::init_with_coder(env, this, coder);
+
+    let key_ns_string = get_static_str(env, "UIReuseIdentifier");
+    let reuse_identifier: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    let reuse_identifier: id = msg![env; reuse_identifier copy];
+    let key_ns_string = get_static_str(env, "UIStyle");
+    let style: UITableViewCellStyle = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UISelectionStyle");
+    let selection_style: UITableViewCellSelectionStyle =
+        if msg![env; coder containsValueForKey:key_ns_string] {
+            msg![env; coder decodeIntegerForKey:key_ns_string]
+        } else {
+            UITableViewCellSelectionStyleBlue
+        };
+    let key_ns_string = get_static_str(env, "UIAccessoryType");
+    let accessory_type: UITableViewCellAccessoryType =
+        msg![env; coder decodeIntegerForKey:key_ns_string];
+    // The content view was already added as a subview.
+    let key_ns_string = get_static_str(env, "UIContentView");
+    let content_view: id = msg![env; coder decodeObjectForKey:key_ns_string];
+
+    let state = cell_state(&mut env.objc, this);
+    state.reuse_identifier = reuse_identifier;
+    state.style = style;
+    state.selection_style = selection_style;
+    state.accessory_type = accessory_type;
+    state.content_view = content_view;
+
+    this
+}
+
+- (id)reuseIdentifier {
+    cell_state(&mut env.objc, this).reuse_identifier
+}
+- (())prepareForReuse {
+    // Subclasses can override this to reset their content.
+}
+
+- (id)contentView {
+    content_view(env, this)
+}
+- (id)textLabel {
+    text_label(env, this)
+}
+- (id)detailTextLabel {
+    detail_text_label(env, this)
+}
+- (id)imageView {
+    image_view(env, this)
+}
+
+// Deprecated in iPhone OS 3.0, these forward to the subviews.
+- (id)text {
+    let label = text_label(env, this);
+    msg![env; label text]
+}
+- (())setText:(id)text { // NSString*
+    let label = text_label(env, this);
+    msg![env; label setText:text]
+}
+- (id)font {
+    let label = text_label(env, this);
+    msg![env; label font]
+}
+- (())setFont:(id)font { // UIFont*
+    let label = text_label(env, this);
+    msg![env; label setFont:font]
+}
+- (id)textColor {
+    let label = text_label(env, this);
+    msg![env; label textColor]
+}
+- (())setTextColor:(id)color { // UIColor*
+    let label = text_label(env, this);
+    msg![env; label setTextColor:color]
+}
+- (NSInteger)textAlignment {
+    let label = text_label(env, this);
+    msg![env; label textAlignment]
+}
+- (())setTextAlignment:(NSInteger)alignment {
+    let label = text_label(env, this);
+    msg![env; label setTextAlignment:alignment]
+}
+- (id)image {
+    let image_view = image_view(env, this);
+    msg![env; image_view image]
+}
+- (())setImage:(id)image { // UIImage*
+    let image_view = image_view(env, this);
+    () = msg![env; image_view setImage:image];
+    layout_subviews(env, this);
+}
+
+- (UITableViewCellAccessoryType)accessoryType {
+    cell_state(&mut env.objc, this).accessory_type
+}
+- (())setAccessoryType:(UITableViewCellAccessoryType)accessory_type {
+    cell_state(&mut env.objc, this).accessory_type = accessory_type;
+}
+- (id)accessoryView {
+    cell_state(&mut env.objc, this).accessory_view
+}
+- (())setAccessoryView:(id)view { // UIView*
+    retain(env, view);
+    let old = std::mem::replace(&mut cell_state(&mut env.objc, this).accessory_view, view);
+    if old != nil {
+        () = msg![env; old removeFromSuperview];
+        release(env, old);
+    }
+    if view != nil {
+        () = msg![env; this addSubview:view];
+    }
+}
+
+- (UITableViewCellSelectionStyle)selectionStyle {
+    cell_state(&mut env.objc, this).selection_style
+}
+- (())setSelectionStyle:(UITableViewCellSelectionStyle)style {
+    cell_state(&mut env.objc, this).selection_style = style;
+    update_selection_view(env, this);
+}
+
+- (bool)isSelected {
+    cell_state(&mut env.objc, this).selected
+}
+- (())setSelected:(bool)selected {
+    msg![env; this setSelected:selected animated:false]
+}
+- (())setSelected:(bool)selected
+         animated:(bool)_animated {
+    cell_state(&mut env.objc, this).selected = selected;
+    update_selection_view(env, this);
+}
+- (bool)isHighlighted {
+    cell_state(&mut env.objc, this).highlighted
+}
+- (())setHighlighted:(bool)highlighted {
+    msg![env; this setHighlighted:highlighted animated:false]
+}
+- (())setHighlighted:(bool)highlighted
+            animated:(bool)_animated {
+    cell_state(&mut env.objc, this).highlighted = highlighted;
+    update_selection_view(env, this);
+}
+
+// The standard layout for the cell's style.
+- (())layoutSubviews {
+    layout_subviews(env, this);
+}
+
+@end
+
+// The class of the content view, which is private.
+@implementation UITableViewCellContentView: UIView
+@end
+
+};
+
+fn content_view(env: &mut Environment, this: id) -> id {
+    let content_view = cell_state(&mut env.objc, this).content_view;
+    if content_view != nil {
+        return content_view;
+    }
+    let content_view: id = msg_class![env; UITableViewCellContentView new];
+    let bounds: CGRect = msg![env; this bounds];
+    () = msg![env; content_view setFrame:bounds];
+    // It has to be in the state before it's added, because adding it lays out
+    // the cell.
+    cell_state(&mut env.objc, this).content_view = content_view;
+    () = msg![env; this addSubview:content_view];
+    release(env, content_view);
+    content_view
+}
+
+/// Create a label for the cell with the right font and color for its style.
+fn new_label(env: &mut Environment, this: id, font_size: CGFloat, bold: bool, gray: bool) -> id {
+    let label: id = msg_class![env; UILabel new];
+    let font: id = if bold {
+        msg_class![env; UIFont boldSystemFontOfSize:font_size]
+    } else {
+        msg_class![env; UIFont systemFontOfSize:font_size]
+    };
+    () = msg![env; label setFont:font];
+    if gray {
+        let color: id = msg_class![env; UIColor grayColor];
+        () = msg![env; label setTextColor:color];
+    }
+    let clear: id = msg_class![env; UIColor clearColor];
+    () = msg![env; label setBackgroundColor:clear];
+    () = msg![env; label setOpaque:false];
+    let content_view = content_view(env, this);
+    () = msg![env; content_view addSubview:label];
+    release(env, label);
+    label
+}
+
+fn text_label(env: &mut Environment, this: id) -> id {
+    let state = cell_state(&mut env.objc, this);
+    if state.text_label != nil {
+        return state.text_label;
+    }
+    let style = state.style;
+    let (font_size, bold) = match style {
+        UITableViewCellStyleDefault => (20.0, true),
+        UITableViewCellStyleValue2 => (12.0, true),
+        _ => (18.0, true),
+    };
+    let label = new_label(env, this, font_size, bold, false);
+    cell_state(&mut env.objc, this).text_label = label;
+    if style == UITableViewCellStyleValue2 {
+        let color: id = msg_class![env; UIColor colorWithRed:(0.32 as CGFloat)
+                                                        green:(0.4 as CGFloat)
+                                                         blue:(0.57 as CGFloat)
+                                                        alpha:(1.0 as CGFloat)];
+        () = msg![env; label setTextColor:color];
+    }
+    label
+}
+
+/// Cells with the default style don't have a detail label.
+fn detail_text_label(env: &mut Environment, this: id) -> id {
+    let state = cell_state(&mut env.objc, this);
+    if state.detail_text_label != nil || state.style == UITableViewCellStyleDefault {
+        return state.detail_text_label;
+    }
+    let (font_size, bold, gray) = match state.style {
+        UITableViewCellStyleValue1 => (18.0, false, true),
+        UITableViewCellStyleValue2 => (15.0, true, false),
+        _ => (14.0, false, true),
+    };
+    let label = new_label(env, this, font_size, bold, gray);
+    cell_state(&mut env.objc, this).detail_text_label = label;
+    label
+}
+
+fn image_view(env: &mut Environment, this: id) -> id {
+    let image_view = cell_state(&mut env.objc, this).image_view;
+    if image_view != nil {
+        return image_view;
+    }
+    let image_view: id = msg_class![env; UIImageView new];
+    cell_state(&mut env.objc, this).image_view = image_view;
+    let content_view = content_view(env, this);
+    () = msg![env; content_view addSubview:image_view];
+    release(env, image_view);
+    image_view
+}
+
+/// Show the selection background behind the content if the cell is selected
+/// or highlighted.
+fn update_selection_view(env: &mut Environment, this: id) {
+    let state = cell_state(&mut env.objc, this);
+    let show = (state.selected || state.highlighted)
+        && state.selection_style != UITableViewCellSelectionStyleNone;
+    let selection_style = state.selection_style;
+    let mut selection_view = state.selection_view;
+    if selection_view == nil {
+        if !show {
+            return;
+        }
+        selection_view = msg_class![env; UIView new];
+        let bounds: CGRect = msg![env; this bounds];
+        () = msg![env; selection_view setFrame:bounds];
+        cell_state(&mut env.objc, this).selection_view = selection_view;
+        let index: NSInteger = 0;
+        () = msg![env; this insertSubview:selection_view atIndex:index];
+        release(env, selection_view);
+    }
+    let color: id = if selection_style == UITableViewCellSelectionStyleBlue {
+        msg_class![env; UIColor colorWithRed:(0.02 as CGFloat)
+                                       green:(0.55 as CGFloat)
+                                        blue:(0.96 as CGFloat)
+                                       alpha:(1.0 as CGFloat)]
+    } else {
+        msg_class![env; UIColor colorWithWhite:(0.75 as CGFloat) alpha:(1.0 as CGFloat)]
+    };
+    () = msg![env; selection_view setBackgroundColor:color];
+    () = msg![env; selection_view setHidden:(!show)];
+}
+
+fn layout_subviews(env: &mut Environment, this: id) {
+    let bounds: CGRect = msg![env; this bounds];
+    let state = cell_state(&mut env.objc, this);
+    let &mut TableViewCellState {
+        style,
+        content_view,
+        text_label,
+        detail_text_label,
+        image_view,
+        selection_view,
+        accessory_view,
+        ..
+    } = state;
+
+    if selection_view != nil {
+        () = msg![env; selection_view setFrame:bounds];
+    }
+
+    // The accessory view is on the right, and the content view fills the rest
+    // of the cell.
+    let mut content_frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: bounds.size,
+    };
+    if accessory_view != nil {
+        let accessory_frame: CGRect = msg![env; accessory_view frame];
+        let accessory_size = accessory_frame.size;
+        let frame = CGRect {
+            origin: CGPoint {
+                x: bounds.size.width - MARGIN - accessory_size.width,
+                y: (bounds.size.height - accessory_size.height) / 2.0,
+            },
+            size: accessory_size,
+        };
+        () = msg![env; accessory_view setFrame:frame];
+        content_frame.size.width = frame.origin.x;
+    }
+    if content_view == nil {
+        return;
+    }
+    () = msg![env; content_view setFrame:content_frame];
+    let CGSize { width, height } = content_frame.size;
+
+    let mut text_x = MARGIN;
+    if image_view != nil {
+        let image: id = msg![env; image_view image];
+        let image_size = if image == nil {
+            CGSize {
+                width: 0.0,
+                height: 0.0,
+            }
+        } else {
+            msg![env; image size]
+        };
+        let frame = CGRect {
+            origin: CGPoint {
+                x: MARGIN,
+                y: (height - image_size.height) / 2.0,
+            },
+            size: image_size,
+        };
+        () = msg![env; image_view setFrame:frame];
+        if image != nil {
+            text_x = MARGIN * 2.0 + image_size.width;
+        }
+    }
+
+    let text_width = (width - text_x - MARGIN).max(0.0);
+    let row = |x: CGFloat, y: CGFloat, width: CGFloat, height: CGFloat| CGRect {
+        origin: CGPoint { x, y },
+        size: CGSize { width, height },
+    };
+    match style {
+        UITableViewCellStyleSubtitle if detail_text_label != nil => {
+            // The labels are stacked and centered vertically together.
+            let text_height = label_height(env, text_label, text_width);
+            let detail_height = label_height(env, detail_text_label, text_width);
+            let top = (height - text_height - detail_height) / 2.0;
+            if text_label != nil {
+                let frame = row(text_x, top, text_width, text_height);
+                () = msg![env; text_label setFrame:frame];
+            }
+            let frame = row(text_x, top + text_height, text_width, detail_height);
+            () = msg![env; detail_text_label setFrame:frame];
+        }
+        UITableViewCellStyleValue1 | UITableViewCellStyleValue2 if detail_text_label != nil => {
+            // The labels share the row. For Value1 the detail is on the
+            // right, for Value2 the text is in a narrow column on the left.
+            let (text_width, detail_x) = if style == UITableViewCellStyleValue1 {
+                (text_width / 2.0, text_x + text_width / 2.0)
+            } else {
+                (67.0, text_x + 67.0 + MARGIN / 2.0)
+            };
+            if text_label != nil {
+                let frame = row(text_x, 0.0, text_width, height);
+                () = msg![env; text_label setFrame:frame];
+            }
+            let detail_width = (width - detail_x - MARGIN).max(0.0);
+            let frame = row(detail_x, 0.0, detail_width, height);
+            () = msg![env; detail_text_label setFrame:frame];
+            let (text_alignment, detail_alignment) = if style == UITableViewCellStyleValue1 {
+                (UITextAlignmentLeft, UITextAlignmentRight)
+            } else {
+                (UITextAlignmentRight, UITextAlignmentLeft)
+            };
+            if text_label != nil {
+                () = msg![env; text_label setTextAlignment:text_alignment];
+            }
+            () = msg![env; detail_text_label setTextAlignment:detail_alignment];
+        }
+        _ => {
+            if text_label != nil {
+                let frame = row(text_x, 0.0, text_width, height);
+                () = msg![env; text_label setFrame:frame];
+            }
+        }
+    }
+}
+
+fn label_height(env: &mut Environment, label: id, width: CGFloat) -> CGFloat {
+    if label == nil {
+        return 0.0;
+    }
+    let size = CGSize {
+        width,
+        height: CGFloat::INFINITY,
+    };
+    let size: CGSize = msg![env; label sizeThatFits:size];
+    size.height
+}
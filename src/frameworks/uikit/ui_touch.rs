@@ -7,20 +7,32 @@
 
 use super::ui_event;
 use super::ui_view::UIViewHostObject;
-use crate::frameworks::core_graphics::{CGFloat, CGPoint};
-use crate::frameworks::foundation::{NSTimeInterval, NSUInteger};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect};
+use crate::frameworks::foundation::{NSInteger, NSTimeInterval, NSUInteger};
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject,
+    ClassExports, HostObject, NSZonePtr,
 };
-use crate::window::Event;
+use crate::window::{Event, FingerId};
 use crate::Environment;
-
-#[derive(Default)]
+use std::collections::HashMap;
+
+pub type UITouchPhase = NSInteger;
+pub const UITouchPhaseBegan: UITouchPhase = 0;
+pub const UITouchPhaseMoved: UITouchPhase = 1;
+#[allow(dead_code)]
+pub const UITouchPhaseStationary: UITouchPhase = 2;
+pub const UITouchPhaseEnded: UITouchPhase = 3;
+#[allow(dead_code)]
+pub const UITouchPhaseCancelled: UITouchPhase = 4;
+
+#[derive(Default, Clone)]
 pub struct State {
-    current_touch: Option<id>,
+    /// The `UITouch*` for each finger currently touching the screen.
+    current_touches: HashMap<FingerId, id>,
 }
 
+#[derive(Clone)]
 struct UITouchHostObject {
     /// Strong reference to the `UIView`
     view: id,
@@ -29,8 +41,13 @@ struct UITouchHostObject {
     /// Relative to screen
     previous_location: CGPoint,
     timestamp: NSTimeInterval,
+    phase: UITouchPhase,
+}
+impl HostObject for UITouchHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
 }
-impl HostObject for UITouchHostObject {}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -44,6 +61,7 @@ pub const CLASSES: ClassExports = objc_classes! {
         location: CGPoint { x: 0.0, y: 0.0 },
         previous_location: CGPoint { x: 0.0, y: 0.0 },
         timestamp: 0.0,
+        phase: UITouchPhaseBegan,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
@@ -59,9 +77,7 @@ pub const CLASSES: ClassExports = objc_classes! {
     if that_view == nil {
         location
     } else {
-        // FIXME, see below
-        // Note: also change touchesForView: on UIEvent
-        resolve_point_in_view(env, that_view, location).unwrap()
+        msg![env; that_view convertPoint:location fromView:nil]
     }
 }
 - (CGPoint)previousLocationInView:(id)that_view { // UIView*
@@ -69,9 +85,7 @@ pub const CLASSES: ClassExports = objc_classes! {
     if that_view == nil {
         previous_location
     } else {
-        // FIXME, see below
-        // Note: also change touchesForView: on UIEvent
-        resolve_point_in_view(env, that_view, previous_location).unwrap()
+        msg![env; that_view convertPoint:previous_location fromView:nil]
     }
 }
 
@@ -83,6 +97,10 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow::<UITouchHostObject>(this).timestamp
 }
 
+- (UITouchPhase)phase {
+    env.objc.borrow::<UITouchHostObject>(this).phase
+}
+
 - (NSUInteger)tapCount {
     1 // TODO: support double-taps etc
 }
@@ -91,12 +109,15 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
-pub fn resolve_point_in_view(env: &mut Environment, view: id, point: CGPoint) -> Option<CGPoint> {
+/// Check if a view is fullscreen, and if so, get the point in its co-ordinate
+/// space. See [find_view_for_touch].
+fn resolve_point_in_view(env: &mut Environment, view: id, point: CGPoint) -> Option<CGPoint> {
     let (expected_width, expected_height) = env.window.size_unrotated_unscaled();
     let expected_width = expected_width as CGFloat;
     let expected_height = expected_height as CGFloat;
 
-    let &UIViewHostObject { bounds, center, .. } = env.objc.borrow(view);
+    let bounds: CGRect = msg![env; view bounds];
+    let center: CGPoint = msg![env; view center];
 
     if bounds.size.width != expected_width || bounds.size.height != expected_height {
         return None;
@@ -112,11 +133,27 @@ pub fn resolve_point_in_view(env: &mut Environment, view: id, point: CGPoint) ->
 }
 
 fn find_view_for_touch(env: &mut Environment, point: CGPoint) -> Option<id> {
-    // FIXME: This is a massive hack that is only going to work for apps that
-    // have a single view which handles all touch inputs. We should eventually
-    // implement the proper responder chain.
-
+    // The touch goes to the front-most view under it in the front-most window.
     let ui_window_class = env.objc.get_known_class("UIWindow", &mut env.mem);
+    for window in super::ui_window::visible_windows(env).into_iter().rev() {
+        let window_point: CGPoint = msg![env; window convertPoint:point fromView:nil];
+        let view: id = msg![env; window hitTest:window_point withEvent:nil];
+        if view == nil {
+            continue;
+        }
+        // Apps don't usually expect a window to handle touches, so if the
+        // window itself was hit, the fallback below is tried instead.
+        let is_window: bool = msg![env; view isKindOfClass:ui_window_class];
+        if !is_window {
+            log_dbg!("Hit test picked view {:?} for touch event", view);
+            return Some(view);
+        }
+    }
+
+    // FIXME: This is a massive hack for apps whose views aren't in a visible
+    // window. It is only going to work for apps that have a single view which
+    // handles all touch inputs.
+
     // TODO: Can we avoid copying this somehow?
     let views = env.framework_state.uikit.ui_view.views.clone();
     for view in views {
@@ -141,16 +178,27 @@ fn find_view_for_touch(env: &mut Environment, point: CGPoint) -> Option<id> {
     None
 }
 
+/// For use by [super::ui_event]: get the view a `UITouch*` belongs to.
+pub(super) fn view_for_touch(env: &mut Environment, touch: id) -> id {
+    env.objc.borrow::<UITouchHostObject>(touch).view
+}
+
 /// [super::handle_events] will forward touch events to this function.
 pub fn handle_event(env: &mut Environment, event: Event) {
     match event {
-        Event::TouchDown(coords) => {
-            if env.framework_state.uikit.ui_touch.current_touch.is_some() {
+        Event::TouchDown(finger, coords) => {
+            if env
+                .framework_state
+                .uikit
+                .ui_touch
+                .current_touches
+                .contains_key(&finger)
+            {
                 log!("Warning: New touch initiated but current touch did not end yet, treating as movement.");
-                return handle_event(env, Event::TouchMove(coords));
+                return handle_event(env, Event::TouchMove(finger, coords));
             }
 
-            log_dbg!("Touch down: {:?}", coords);
+            log_dbg!("Touch down (finger {}): {:?}", finger, coords);
 
             let location = CGPoint {
                 x: coords.0,
@@ -161,6 +209,34 @@ pub fn handle_event(env: &mut Environment, event: Event) {
                 return;
             };
 
+            // A view that doesn't have multi-touch enabled only gets the first
+            // of several simultaneous touches.
+            let multiple_touch_enabled = env
+                .objc
+                .borrow::<UIViewHostObject>(view)
+                .multiple_touch_enabled;
+            if !multiple_touch_enabled {
+                let touches: Vec<id> = env
+                    .framework_state
+                    .uikit
+                    .ui_touch
+                    .current_touches
+                    .values()
+                    .copied()
+                    .collect();
+                if touches
+                    .into_iter()
+                    .any(|touch| view_for_touch(env, touch) == view)
+                {
+                    log_dbg!(
+                        "Ignoring touch for finger {}, {:?} does not have multi-touch enabled",
+                        finger,
+                        view
+                    );
+                    return;
+                }
+            }
+
             // UIKit creates and drains autorelease pools when handling events.
             let pool: id = msg_class![env; NSAutoreleasePool new];
 
@@ -177,33 +253,43 @@ pub fn handle_event(env: &mut Environment, event: Event) {
                 location,
                 previous_location: location,
                 timestamp,
+                phase: UITouchPhaseBegan,
             };
-            autorelease(env, new_touch);
 
-            env.framework_state.uikit.ui_touch.current_touch = Some(new_touch);
-            retain(env, new_touch);
+            // The map now owns the touch.
+            env.framework_state
+                .uikit
+                .ui_touch
+                .current_touches
+                .insert(finger, new_touch);
 
-            let touches: id = msg_class![env; NSSet setWithObject:new_touch];
-            let event = ui_event::new_event(env, touches, view);
-            autorelease(env, event);
-
-            log_dbg!(
-                "Sending [{:?} touchesBegan:{:?} withEvent:{:?}]",
-                view,
-                touches,
-                event
-            );
-            let _: () = msg![env; view touchesBegan:touches withEvent:event];
+            send_touch(env, new_touch, timestamp);
 
             release(env, pool);
         }
-        Event::TouchMove(coords) => {
-            let Some(touch) = env.framework_state.uikit.ui_touch.current_touch else {
-                log!("Warning: Touch move event received but no current touch, ignoring.");
+        Event::TouchMove(finger, coords) | Event::TouchUp(finger, coords) => {
+            let Some(&touch) = env
+                .framework_state
+                .uikit
+                .ui_touch
+                .current_touches
+                .get(&finger)
+            else {
+                // This is expected if the touch was ignored when it began.
+                log_dbg!(
+                    "Ignoring touch move/up for finger {} that isn't touching the screen",
+                    finger
+                );
                 return;
             };
 
-            log_dbg!("Touch move: {:?}", coords);
+            let ended = matches!(event, Event::TouchUp(..));
+            log_dbg!(
+                "Touch {} (finger {}): {:?}",
+                if ended { "up" } else { "move" },
+                finger,
+                coords
+            );
 
             let location = CGPoint {
                 x: coords.0,
@@ -212,18 +298,67 @@ pub fn handle_event(env: &mut Environment, event: Event) {
 
             let timestamp: NSTimeInterval = msg_class![env; NSProcessInfo systemUptime];
 
-            let view = env.objc.borrow::<UITouchHostObject>(touch).view;
             let host_object = env.objc.borrow_mut::<UITouchHostObject>(touch);
             host_object.previous_location = host_object.location;
             host_object.location = location;
             host_object.timestamp = timestamp;
+            host_object.phase = if ended {
+                UITouchPhaseEnded
+            } else {
+                UITouchPhaseMoved
+            };
 
             let pool: id = msg_class![env; NSAutoreleasePool new];
 
-            let touches: id = msg_class![env; NSSet setWithObject:touch];
-            let event = ui_event::new_event(env, touches, view);
-            autorelease(env, event);
+            send_touch(env, touch, timestamp);
+
+            if ended {
+                // The touch stays in the event's allTouches set until the event
+                // is done with, so it's only removed afterwards.
+                env.framework_state
+                    .uikit
+                    .ui_touch
+                    .current_touches
+                    .remove(&finger);
+                release(env, touch);
+            }
+
+            release(env, pool);
+        }
+        _ => unreachable!(),
+    }
+}
 
+/// Send `touchesBegan:withEvent:`, `touchesMoved:withEvent:` or
+/// `touchesEnded:withEvent:` (depending on its phase) for a touch that has
+/// changed to its view. The event's `allTouches` also includes the other
+/// fingers currently touching the screen.
+fn send_touch(env: &mut Environment, touch: id, timestamp: NSTimeInterval) {
+    let &UITouchHostObject { view, phase, .. } = env.objc.borrow(touch);
+
+    let touches: id = msg_class![env; NSSet setWithObject:touch];
+    let all_touches = env
+        .framework_state
+        .uikit
+        .ui_touch
+        .current_touches
+        .values()
+        .copied()
+        .collect();
+    let event = ui_event::new_event(env, all_touches, timestamp);
+    autorelease(env, event);
+
+    match phase {
+        UITouchPhaseBegan => {
+            log_dbg!(
+                "Sending [{:?} touchesBegan:{:?} withEvent:{:?}]",
+                view,
+                touches,
+                event
+            );
+            let _: () = msg![env; view touchesBegan:touches withEvent:event];
+        }
+        UITouchPhaseMoved => {
             log_dbg!(
                 "Sending [{:?} touchesMoved:{:?} withEvent:{:?}]",
                 view,
@@ -231,39 +366,8 @@ pub fn handle_event(env: &mut Environment, event: Event) {
                 event
             );
             let _: () = msg![env; view touchesMoved:touches withEvent:event];
-
-            release(env, pool);
         }
-        Event::TouchUp(coords) => {
-            let Some(touch) = env.framework_state.uikit.ui_touch.current_touch else {
-                log!("Warning: Touch up event received but no current touch, ignoring.");
-                return;
-            };
-
-            log_dbg!("Touch up: {:?}", coords);
-
-            let location = CGPoint {
-                x: coords.0,
-                y: coords.1,
-            };
-
-            let timestamp: NSTimeInterval = msg_class![env; NSProcessInfo systemUptime];
-
-            let view = env.objc.borrow::<UITouchHostObject>(touch).view;
-            let host_object = env.objc.borrow_mut::<UITouchHostObject>(touch);
-            host_object.previous_location = host_object.location;
-            host_object.location = location;
-            host_object.timestamp = timestamp;
-
-            let pool: id = msg_class![env; NSAutoreleasePool new];
-
-            let touches: id = msg_class![env; NSSet setWithObject:touch];
-            let event = ui_event::new_event(env, touches, view);
-            autorelease(env, event);
-
-            env.framework_state.uikit.ui_touch.current_touch = None;
-            release(env, touch); // only owner now should be the NSSet
-
+        UITouchPhaseEnded => {
             log_dbg!(
                 "Sending [{:?} touchesEnded:{:?} withEvent:{:?}]",
                 view,
@@ -271,8 +375,6 @@ pub fn handle_event(env: &mut Environment, event: Event) {
                 event
             );
             let _: () = msg![env; view touchesEnded:touches withEvent:event];
-
-            release(env, pool);
         }
         _ => unreachable!(),
     }
@@ -5,24 +5,81 @@
  */
 //! `UIView`.
 
+use super::ui_alert_view::AlertState;
+use super::ui_control::ControlState;
+use super::ui_graphics::{UIGraphicsPopContext, UIGraphicsPushContext};
+use super::ui_label::LabelState;
+use super::ui_navigation_bar::NavigationBarState;
+use super::ui_scroll_view::ScrollViewState;
+use super::ui_tab_bar::TabBarState;
+use super::ui_table_view_cell::TableViewCellState;
+use crate::frameworks::core_animation::ca_layer;
+use crate::frameworks::core_graphics::cg_affine_transform::{
+    CGAffineTransform, CGAffineTransformIdentity,
+};
+use crate::frameworks::core_graphics::cg_color::CGColorRef;
+use crate::frameworks::core_graphics::cg_context::CGContextRef;
 use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
 use crate::frameworks::foundation::ns_string::{get_static_str, to_rust_string};
+use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
 use crate::objc::{
-    id, msg, nil, objc_classes, release, Class, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, Class,
+    ClassExports, HostObject, NSZonePtr, ObjC,
 };
+use crate::Environment;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     pub(super) views: Vec<id>,
 }
 
+/// State specific to the UIKit classes that inherit from `UIView`, which have
+/// to share its host object.
+#[derive(Clone)]
+pub(super) enum UIViewSubclass {
+    Other,
+    UIImageView {
+        /// `UIImage*`, possibly nil. This is a strong reference.
+        image: id,
+    },
+    /// `UIAlertView` or `UIActionSheet`
+    Alert(AlertState),
+    UILabel(LabelState),
+    /// `UIControl` and its subclasses
+    UIControl(ControlState),
+    /// `UIScrollView` and its subclasses
+    UIScrollView(ScrollViewState),
+    UITableViewCell(TableViewCellState),
+    UINavigationBar(NavigationBarState),
+    UITabBar(TabBarState),
+}
+
+#[derive(Clone)]
 pub(super) struct UIViewHostObject {
-    pub(super) bounds: CGRect,
-    pub(super) center: CGPoint,
-    /// CALayer or subclass.
+    /// CALayer or subclass. This is a strong reference. The view's geometry,
+    /// visibility and background color are all stored in the layer.
     layer: id,
+    /// Subviews in back-to-front order. These are strong references.
+    subviews: Vec<id>,
+    /// The superview. This is a weak reference.
+    superview: id,
+    /// The view controller whose view this is, if any. This is a weak
+    /// reference.
+    pub(super) view_controller: id,
+    /// If this is [false], the view only receives the first of several
+    /// simultaneous touches.
+    pub(super) multiple_touch_enabled: bool,
+    /// If this is [false], the view and its subviews are ignored by hit
+    /// testing, so touches go to the views behind them.
+    user_interaction_enabled: bool,
+    tag: NSInteger,
+    pub(super) subclass: UIViewSubclass,
+}
+impl HostObject for UIViewHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
 }
-impl HostObject for UIViewHostObject {}
 
 fn parse_tuple(string: &str) -> Option<(f32, f32)> {
     let (a, b) = string.split_once(", ")?;
@@ -51,49 +108,47 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 + (id)allocWithZone:(NSZonePtr)_zone {
     let layer_class: Class = msg![env; this layerClass];
-    let layer: id = msg![env; layer_class layer];
+    let layer: id = msg![env; layer_class new];
+    // UIView's default is different from CALayer's.
+    () = msg![env; layer setOpaque:true];
 
     let host_object = Box::new(UIViewHostObject {
-        bounds: CGRect {
-            origin: CGPoint { x: 0.0, y: 0.0 },
-            size: CGSize { width: 0.0, height: 0.0 }
-        },
-        center: CGPoint { x: 0.0, y: 0.0 },
         layer,
+        subviews: Vec::new(),
+        superview: nil,
+        view_controller: nil,
+        multiple_touch_enabled: false,
+        user_interaction_enabled: true,
+        tag: 0,
+        subclass: UIViewSubclass::Other,
     });
-    env.objc.alloc_object(this, host_object, &mut env.mem)
+    let new = env.objc.alloc_object(this, host_object, &mut env.mem);
+    set_subclass_defaults(env, this, new);
+    new
 }
 
 + (Class)layerClass {
     env.objc.get_known_class("CALayer", &mut env.mem)
 }
 
-// TODO: accessors etc
-
-- (id)initWithFrame:(CGRect)frame {
-    let center = CGPoint {
-        x: frame.origin.x + frame.size.width / 2.0,
-        y: frame.origin.y + frame.size.height / 2.0,
-    };
-    let bounds = CGRect {
+- (id)init {
+    let frame = CGRect {
         origin: CGPoint { x: 0.0, y: 0.0 },
-        size: frame.size,
+        size: CGSize { width: 0.0, height: 0.0 },
     };
+    msg![env; this initWithFrame:frame]
+}
 
-    let host_object: &mut UIViewHostObject = env.objc.borrow_mut(this);
-    host_object.bounds = bounds;
-    host_object.center = center;
+- (id)initWithFrame:(CGRect)frame {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    () = msg![env; layer setDelegate:this];
+    () = msg![env; layer setFrame:frame];
 
-    log_dbg!(
-        "[(UIView*){:?} initWithFrame:{:?}] => bounds {:?}, center {:?}",
-        this,
-        frame,
-        bounds,
-        center,
-    );
+    log_dbg!("[(UIView*){:?} initWithFrame:{:?}]", this, frame);
 
-    let layer = host_object.layer;
-    () = msg![env; layer setDelegate:this];
+    if overrides_draw_rect(env, this) {
+        () = msg![env; layer setNeedsDisplay];
+    }
 
     env.framework_state.uikit.ui_view.views.push(this);
 
@@ -102,74 +157,179 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // NSCoding implementation
 - (id)initWithCoder:(id)coder {
-    // TODO: there's a category on NSCoder for decoding CGRect and CGPoint, we
-    //       should implement and use that
-    // TODO: avoid copying strings
-    // TODO: decode the various other UIView properties
-
-    let key_ns_string = get_static_str(env, "UIBounds");
-    let value = msg![env; coder decodeObjectForKey:key_ns_string];
-    let bounds = parse_rect(&to_rust_string(env, value)).unwrap();
-
-    let key_ns_string = get_static_str(env, "UICenter");
-    let value = msg![env; coder decodeObjectForKey:key_ns_string];
-    let center = parse_point(&to_rust_string(env, value)).unwrap();
-
-    let host_object: &mut UIViewHostObject = env.objc.borrow_mut(this);
-    host_object.bounds = bounds;
-    host_object.center = center;
-
-    log_dbg!(
-        "[(UIView*){:?} initWithCoder:{:?}] => bounds {:?}, center {:?}",
-        this,
-        coder,
-        bounds,
-        center
-    );
-
-    let layer = host_object.layer;
-    () = msg![env; layer setDelegate:this];
+    init_with_coder(env, this, coder)
+}
 
-    env.framework_state.uikit.ui_view.views.push(this);
+- (bool)isMultipleTouchEnabled {
+    env.objc.borrow::<UIViewHostObject>(this).multiple_touch_enabled
+}
+- (())setMultipleTouchEnabled:(bool)enabled {
+    env.objc.borrow_mut::<UIViewHostObject>(this).multiple_touch_enabled = enabled;
+}
 
-    this
+- (NSInteger)tag {
+    env.objc.borrow::<UIViewHostObject>(this).tag
+}
+- (())setTag:(NSInteger)tag {
+    env.objc.borrow_mut::<UIViewHostObject>(this).tag = tag;
+}
+- (id)viewWithTag:(NSInteger)tag {
+    let host_object = env.objc.borrow::<UIViewHostObject>(this);
+    if host_object.tag == tag {
+        return this;
+    }
+    let subviews = host_object.subviews.clone();
+    for subview in subviews {
+        let found: id = msg![env; subview viewWithTag:tag];
+        if found != nil {
+            return found;
+        }
+    }
+    nil
 }
 
-// TODO: setMultipleTouchEnabled
-- (())setMultipleTouchEnabled:(bool)_enabled {
-    // TODO: enable multitouch
+- (bool)isUserInteractionEnabled {
+    env.objc.borrow::<UIViewHostObject>(this).user_interaction_enabled
+}
+- (())setUserInteractionEnabled:(bool)enabled {
+    env.objc.borrow_mut::<UIViewHostObject>(this).user_interaction_enabled = enabled;
 }
 
 - (())layoutSubviews {
     // On iOS 5.1 and earlier, the default implementation of this method does nothing.
 }
 
+- (id)superview {
+    env.objc.borrow::<UIViewHostObject>(this).superview
+}
+// UIResponder implementation
+- (id)nextResponder {
+    // A view controller comes between its view and the superview.
+    let host_object = env.objc.borrow::<UIViewHostObject>(this);
+    if host_object.view_controller != nil {
+        host_object.view_controller
+    } else {
+        host_object.superview
+    }
+}
+- (id)subviews {
+    let subviews = env.objc.borrow::<UIViewHostObject>(this).subviews.clone();
+    for &subview in &subviews {
+        retain(env, subview);
+    }
+    let array = ns_array::from_vec(env, subviews);
+    autorelease(env, array)
+}
+
 - (())addSubview:(id)view {
-    // FIXME: there should be an actual hierarchy that retains the view
-    log!("TODO: [(UIView*){:?} addSubview:{:?}]", this, view);
+    log_dbg!("[(UIView*){:?} addSubview:{:?}]", this, view);
+    let index = env.objc.borrow::<UIViewHostObject>(this).subviews.len();
+    let index: NSInteger = index.try_into().unwrap();
+    () = msg![env; this insertSubview:view atIndex:index];
     // FIXME: These should be called systematically using setNeedsLayout: and
     //        layoutIfNeeded.
     let _: () = msg![env; this layoutSubviews];
     let _: () = msg![env; view layoutSubviews];
+
+    let view_controller = env.objc.borrow::<UIViewHostObject>(view).view_controller;
+    if view_controller != nil {
+        super::ui_view_controller::view_added_to_superview(env, view_controller, this);
+    }
+}
+- (())insertSubview:(id)view atIndex:(NSInteger)index {
+    // The view might already be a subview of this view, so it has to be
+    // retained before removing it.
+    retain(env, view);
+    () = msg![env; view removeFromSuperview];
+
+    let host_object = env.objc.borrow_mut::<UIViewHostObject>(this);
+    let index = (index.max(0) as usize).min(host_object.subviews.len());
+    host_object.subviews.insert(index, view);
+    let layer = host_object.layer;
+    // The layer might have sublayers that don't belong to views, so the view's
+    // layer is placed relative to its new sibling rather than by index.
+    let next_sibling = host_object.subviews.get(index + 1).copied();
+
+    let view_host_object = env.objc.borrow_mut::<UIViewHostObject>(view);
+    view_host_object.superview = this;
+    let view_layer = view_host_object.layer;
+
+    if let Some(next_sibling) = next_sibling {
+        let sibling_layer = env.objc.borrow::<UIViewHostObject>(next_sibling).layer;
+        () = msg![env; layer insertSublayer:view_layer below:sibling_layer];
+    } else {
+        () = msg![env; layer addSublayer:view_layer];
+    }
 }
 
 - (())bringSubviewToFront:(id)view {
-    log_dbg!("TODO: [(UIView*){:?} bringSubviewToFront:{:?}]", this, view);
+    let host_object = env.objc.borrow_mut::<UIViewHostObject>(this);
+    let Some(index) = host_object.subviews.iter().position(|&v| v == view) else {
+        return;
+    };
+    host_object.subviews.remove(index);
+    host_object.subviews.push(view);
+    let layer = host_object.layer;
+    let view_layer = env.objc.borrow::<UIViewHostObject>(view).layer;
+    () = msg![env; layer addSublayer:view_layer];
+}
+- (())sendSubviewToBack:(id)view {
+    let host_object = env.objc.borrow_mut::<UIViewHostObject>(this);
+    let Some(index) = host_object.subviews.iter().position(|&v| v == view) else {
+        return;
+    };
+    host_object.subviews.remove(index);
+    host_object.subviews.insert(0, view);
+    let layer = host_object.layer;
+    let view_layer = env.objc.borrow::<UIViewHostObject>(view).layer;
+    let index: NSUInteger = 0;
+    () = msg![env; layer insertSublayer:view_layer atIndex:index];
 }
 
 - (())removeFromSuperview {
-    // FIXME: this should actually remove the view from some hierarchy and
-    //        release it
-    log!("TODO: [(UIView*){:?} removeFromSuperview]", this);
+    let host_object = env.objc.borrow_mut::<UIViewHostObject>(this);
+    let superview = std::mem::replace(&mut host_object.superview, nil);
+    if superview == nil {
+        return;
+    }
+    let layer = host_object.layer;
+
+    let subviews = &mut env.objc.borrow_mut::<UIViewHostObject>(superview).subviews;
+    let index = subviews.iter().position(|&v| v == this).unwrap();
+    subviews.remove(index);
+    () = msg![env; layer removeFromSuperlayer];
+
+    release(env, this);
 }
 
 - (())dealloc {
-    let &mut UIViewHostObject { layer, .. } = env.objc.borrow_mut(this);
+    let host_object = env.objc.borrow_mut::<UIViewHostObject>(this);
+    let layer = host_object.layer;
+    let subviews = std::mem::take(&mut host_object.subviews);
+    let subclass = std::mem::replace(&mut host_object.subclass, UIViewSubclass::Other);
+
+    for subview in subviews {
+        env.objc.borrow_mut::<UIViewHostObject>(subview).superview = nil;
+        release(env, subview);
+    }
+    match subclass {
+        UIViewSubclass::Other => (),
+        UIViewSubclass::UIImageView { image } => release(env, image),
+        UIViewSubclass::Alert(alert_state) => alert_state.release(env),
+        UIViewSubclass::UILabel(label_state) => label_state.release(env),
+        UIViewSubclass::UIControl(control_state) => control_state.release(env),
+        UIViewSubclass::UIScrollView(scroll_view_state) => scroll_view_state.release(env),
+        UIViewSubclass::UITableViewCell(cell_state) => cell_state.release(env),
+        UIViewSubclass::UINavigationBar(bar_state) => bar_state.release(env),
+        UIViewSubclass::UITabBar(bar_state) => bar_state.release(env),
+    }
+    () = msg![env; layer setDelegate:nil];
     release(env, layer);
 
     env.framework_state.uikit.ui_view.views.swap_remove(
         env.framework_state.uikit.ui_view.views.iter().position(|&v| v == this).unwrap()
     );
+    super::ui_window::forget_window(env, this);
 
     env.objc.dealloc_object(this, &mut env.mem);
 }
@@ -178,64 +338,376 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow_mut::<UIViewHostObject>(this).layer
 }
 
+// Geometry and visibility are stored in the layer, like on the real iPhone OS.
+
+- (CGRect)bounds {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer bounds]
+}
+- (())setBounds:(CGRect)bounds {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setBounds:bounds]
+}
+- (CGPoint)center {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer position]
+}
+- (())setCenter:(CGPoint)center {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setPosition:center]
+}
+- (CGRect)frame {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer frame]
+}
+- (())setFrame:(CGRect)frame {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setFrame:frame]
+}
+- (CGAffineTransform)transform {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer affineTransform]
+}
+- (())setTransform:(CGAffineTransform)transform {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setAffineTransform:transform]
+}
+
+- (CGSize)sizeThatFits:(CGSize)_size {
+    let bounds: CGRect = msg![env; this bounds];
+    bounds.size
+}
+- (())sizeToFit {
+    let mut frame: CGRect = msg![env; this frame];
+    frame.size = msg![env; this sizeThatFits:(frame.size)];
+    () = msg![env; this setFrame:frame];
+}
+
+// Co-ordinate conversion. A nil view means the window's co-ordinate space,
+// which is the same as the screen's, since windows are always fullscreen.
+- (CGPoint)convertPoint:(CGPoint)point
+                 toView:(id)view { // UIView*
+    convert_point(env, point, this, view)
+}
+- (CGPoint)convertPoint:(CGPoint)point
+               fromView:(id)view { // UIView*
+    convert_point(env, point, view, this)
+}
+- (CGRect)convertRect:(CGRect)rect
+               toView:(id)view { // UIView*
+    convert_rect(env, rect, this, view)
+}
+- (CGRect)convertRect:(CGRect)rect
+             fromView:(id)view { // UIView*
+    convert_rect(env, rect, view, this)
+}
+
+// Hit testing, used to find the view a touch belongs to.
+- (bool)pointInside:(CGPoint)point
+          withEvent:(id)_event { // UIEvent*
+    let bounds: CGRect = msg![env; this bounds];
+    point.x >= bounds.origin.x
+        && point.y >= bounds.origin.y
+        && point.x < bounds.origin.x + bounds.size.width
+        && point.y < bounds.origin.y + bounds.size.height
+}
+- (id)hitTest:(CGPoint)point
+    withEvent:(id)event { // UIEvent*
+    hit_test(env, this, point, event)
+}
+
+- (bool)isHidden {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer isHidden]
+}
+- (())setHidden:(bool)hidden {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setHidden:hidden]
+}
+
+- (bool)clipsToBounds {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer masksToBounds]
+}
+- (())setClipsToBounds:(bool)clips {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setMasksToBounds:clips]
+}
+
+- (bool)isOpaque {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer isOpaque]
+}
 - (bool)opaque {
-    true
+    msg![env; this isOpaque]
 }
 - (())setOpaque:(bool)opaque {
-    // TODO: implement this once views are actually rendered
-    assert!(opaque);
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setOpaque:opaque]
 }
 
 - (CGFloat)alpha {
-    1.0
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer opacity]
 }
-- (())setAlpha:(CGFloat)_alpha {
-    // TODO: implement this once views are actually rendered
+- (())setAlpha:(CGFloat)alpha {
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setOpacity:alpha]
 }
 
 - (id)backgroundColor {
-    nil // this is the actual default (equivalent to transparency)
+    // The CGColorRef is the UIColor, see cg_color.rs
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer backgroundColor]
 }
-- (())setBackgroundColor:(id)_color { // UIColor*
-    // TODO: implement this once views are actually rendered
+- (())setBackgroundColor:(id)color { // UIColor*
+    let color: CGColorRef = msg![env; color CGColor];
+    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+    msg![env; layer setBackgroundColor:color]
 }
 
-// FIXME: should these pass through from CALayer, vice-versa, or neither?
-// (See similar comment in ca_layer.rs)
-- (CGRect)bounds {
-    env.objc.borrow::<UIViewHostObject>(this).bounds
+// Drawing. The layer's backing store is only needed if drawRect: has been
+// overridden.
+
+- (())setNeedsDisplay {
+    if overrides_draw_rect(env, this) {
+        let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+        () = msg![env; layer setNeedsDisplay];
+    }
 }
-- (CGPoint)center {
-    env.objc.borrow::<UIViewHostObject>(this).center
+- (())setNeedsDisplayInRect:(CGRect)rect {
+    if overrides_draw_rect(env, this) {
+        let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
+        () = msg![env; layer setNeedsDisplayInRect:rect];
+    }
+}
+- (())drawRect:(CGRect)_rect {
+    // Nothing to draw.
+}
+
+// CALayerDelegate implementation
+- (())drawLayer:(id)_layer // CALayer*
+      inContext:(CGContextRef)context {
+    let bounds: CGRect = msg![env; this bounds];
+    UIGraphicsPushContext(env, context);
+    () = msg![env; this drawRect:bounds];
+    UIGraphicsPopContext(env);
 }
 
 @end
 
-@implementation UIAlertView: UIView
-- (id)initWithTitle:(id)title
-                      message:(id)message
-                     delegate:(id)delegate
-            cancelButtonTitle:(id)cancelButtonTitle
-            otherButtonTitles:(id)otherButtonTitles {
+};
+
+/// Check if the view's class has its own `drawRect:`. UIView's implementation
+/// draws nothing, so views that don't override it don't need a backing store.
+fn overrides_draw_rect(env: &mut Environment, view: id) -> bool {
+    let class = ObjC::read_isa(view, &env.mem);
+    let ui_view_class = env.objc.get_known_class("UIView", &mut env.mem);
+    let sel = env.objc.lookup_selector("drawRect:").unwrap();
+    env.objc.class_overrides_method(class, ui_view_class, sel)
+}
+
+/// UIView's `hitTest:withEvent:`, for use by subclasses.
+pub(super) fn hit_test(env: &mut Environment, this: id, point: CGPoint, event: id) -> id {
+    let hidden: bool = msg![env; this isHidden];
+    let alpha: CGFloat = msg![env; this alpha];
+    let host_object = env.objc.borrow::<UIViewHostObject>(this);
+    if hidden || alpha < 0.01 || !host_object.user_interaction_enabled {
+        return nil;
+    }
+    let subviews = host_object.subviews.clone();
+    let inside: bool = msg![env; this pointInside:point withEvent:event];
+    if !inside {
+        return nil;
+    }
+    // Front-most subviews get the first chance.
+    for subview in subviews.into_iter().rev() {
+        let subview_point: CGPoint = msg![env; this convertPoint:point toView:subview];
+        let hit: id = msg![env; subview hitTest:subview_point withEvent:event];
+        if hit != nil {
+            return hit;
+        }
+    }
+    this
+}
+
+/// The body of UIView's `initWithCoder:`, for use by the subclasses that
+/// decode more properties.
+pub(super) fn init_with_coder(env: &mut Environment, this: id, coder: id) -> id {
+    // TODO: there's a category on NSCoder for decoding CGRect and CGPoint, we
+    //       should implement and use that
+    // TODO: avoid copying strings
+    // TODO: decode the various other UIView properties
+
+    let key_ns_string = get_static_str(env, "UIBounds");
+    let value = msg![env; coder decodeObjectForKey:key_ns_string];
+    let bounds = parse_rect(&to_rust_string(env, value)).unwrap();
+
+    let key_ns_string = get_static_str(env, "UICenter");
+    let value = msg![env; coder decodeObjectForKey:key_ns_string];
+    let center = parse_point(&to_rust_string(env, value)).unwrap();
+
+    let key_ns_string = get_static_str(env, "UIMultipleTouchEnabled");
+    let multiple_touch_enabled: bool = msg![env; coder decodeBoolForKey:key_ns_string];
 
-    log!("TODO: [(UIAlertView*){:?} initWithTitle:{:?} message:{:?} delegate:{:?} cancelButtonTitle:{:?} otherButtonTitles:{:?}]", this, title, message, delegate, cancelButtonTitle, otherButtonTitles);
+    let key_ns_string = get_static_str(env, "UIUserInteractionDisabled");
+    let user_interaction_disabled: bool = msg![env; coder decodeBoolForKey:key_ns_string];
 
-    let msg = to_rust_string(env, message);
-    let title = to_rust_string(env, title);
+    let key_ns_string = get_static_str(env, "UITag");
+    let tag: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
 
-    log!("UIAlertView: title: {:?}, message: {:?}", title, msg);
+    let key_ns_string = get_static_str(env, "UIHidden");
+    let hidden: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+
+    let key_ns_string = get_static_str(env, "UIAlpha");
+    let alpha: CGFloat = if msg![env; coder containsValueForKey:key_ns_string] {
+        msg![env; coder decodeFloatForKey:key_ns_string]
+    } else {
+        1.0
+    };
+
+    let key_ns_string = get_static_str(env, "UIOpaque");
+    let opaque: bool = if msg![env; coder containsValueForKey:key_ns_string] {
+        msg![env; coder decodeBoolForKey:key_ns_string]
+    } else {
+        true
+    };
+
+    let key_ns_string = get_static_str(env, "UIClipsToBounds");
+    let clips_to_bounds: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+
+    // This is nil if there's no background color, which replaces the default
+    // some subclasses have.
+    let key_ns_string = get_static_str(env, "UIBackgroundColor");
+    let background_color: id = msg![env; coder decodeObjectForKey:key_ns_string];
 
     let host_object: &mut UIViewHostObject = env.objc.borrow_mut(this);
+    host_object.multiple_touch_enabled = multiple_touch_enabled;
+    host_object.user_interaction_enabled = !user_interaction_disabled;
+    host_object.tag = tag;
     let layer = host_object.layer;
+
+    log_dbg!(
+        "[(UIView*){:?} initWithCoder:{:?}] => bounds {:?}, center {:?}",
+        this,
+        coder,
+        bounds,
+        center
+    );
+
     () = msg![env; layer setDelegate:this];
+    () = msg![env; layer setBounds:bounds];
+    () = msg![env; layer setPosition:center];
+    () = msg![env; layer setHidden:hidden];
+    () = msg![env; layer setOpacity:alpha];
+    () = msg![env; layer setOpaque:opaque];
+    () = msg![env; layer setMasksToBounds:clips_to_bounds];
+    () = msg![env; layer setBackgroundColor:background_color];
+
+    if overrides_draw_rect(env, this) {
+        () = msg![env; layer setNeedsDisplay];
+    }
 
     env.framework_state.uikit.ui_view.views.push(this);
 
+    let key_ns_string = get_static_str(env, "UISubviews");
+    let subviews: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    let subview_count: NSUInteger = msg![env; subviews count];
+    for i in 0..subview_count {
+        let subview: id = msg![env; subviews objectAtIndex:i];
+        () = msg![env; this addSubview:subview];
+    }
+
     this
 }
-- (())show {
-    log!("TODO: [(UIAlertView*){:?} show]", this);
+
+/// Some UIKit subclasses have different defaults for properties that are
+/// stored in UIView's host object or the layer. They can't override
+/// `allocWithZone:` themselves, because they share the host object.
+fn set_subclass_defaults(env: &mut Environment, class: Class, view: id) {
+    let mut is_subclass_of = |name| {
+        let superclass = env.objc.get_known_class(name, &mut env.mem);
+        env.objc.class_is_subclass_of(class, superclass)
+    };
+    let is_label = is_subclass_of("UILabel");
+    let is_image_view = is_subclass_of("UIImageView");
+    let is_button = is_subclass_of("UIButton");
+    let is_scroll_view = is_subclass_of("UIScrollView");
+    let is_table_view = is_subclass_of("UITableView");
+
+    if is_label || is_image_view {
+        env.objc
+            .borrow_mut::<UIViewHostObject>(view)
+            .user_interaction_enabled = false;
+    }
+    let layer = env.objc.borrow::<UIViewHostObject>(view).layer;
+    if is_label || is_table_view {
+        let white: id = msg_class![env; UIColor whiteColor];
+        () = msg![env; layer setBackgroundColor:white];
+    }
+    if is_scroll_view {
+        () = msg![env; layer setMasksToBounds:true];
+    }
+    if is_button {
+        () = msg![env; layer setOpaque:false];
+    }
 }
-@end
 
-};
+/// Get the transform from a view's co-ordinate space to the screen's. A nil
+/// view is treated as being the screen.
+fn transform_to_screen(env: &mut Environment, view: id) -> CGAffineTransform {
+    if view == nil {
+        return CGAffineTransformIdentity;
+    }
+    let layer = env.objc.borrow::<UIViewHostObject>(view).layer;
+    ca_layer::transform_to_root(&env.objc, layer)
+}
+
+fn convert_point(env: &mut Environment, point: CGPoint, from_view: id, to_view: id) -> CGPoint {
+    let screen_point = transform_to_screen(env, from_view).apply_to_point(point);
+    // A view that has been scaled to nothing has no points.
+    let Some(from_screen) = transform_to_screen(env, to_view).invert() else {
+        return CGPoint {
+            x: CGFloat::NAN,
+            y: CGFloat::NAN,
+        };
+    };
+    from_screen.apply_to_point(screen_point)
+}
+
+/// Like [convert_point], but the result is the smallest rectangle containing
+/// the converted rectangle.
+fn convert_rect(env: &mut Environment, rect: CGRect, from_view: id, to_view: id) -> CGRect {
+    let CGRect { origin, size } = rect;
+    let corners = [
+        origin,
+        CGPoint {
+            x: origin.x + size.width,
+            y: origin.y,
+        },
+        CGPoint {
+            x: origin.x,
+            y: origin.y + size.height,
+        },
+        CGPoint {
+            x: origin.x + size.width,
+            y: origin.y + size.height,
+        },
+    ];
+    let (mut min_x, mut min_y) = (CGFloat::INFINITY, CGFloat::INFINITY);
+    let (mut max_x, mut max_y) = (CGFloat::NEG_INFINITY, CGFloat::NEG_INFINITY);
+    for corner in corners {
+        let CGPoint { x, y } = convert_point(env, corner, from_view, to_view);
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    CGRect {
+        origin: CGPoint { x: min_x, y: min_y },
+        size: CGSize {
+            width: max_x - min_x,
+            height: max_y - min_y,
+        },
+    }
+}
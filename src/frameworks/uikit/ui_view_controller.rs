@@ -4,8 +4,72 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `UIViewController`.
+//!
+//! Like on iPhone OS 3, the app is responsible for adding a view controller's
+//! view to a window, and for sending the appearance messages
+//! (`viewWillAppear:` etc) to it. The exceptions are view controllers inside a
+//! navigation or tab bar controller, and modal view controllers, which UIKit
+//! takes care of.
+//!
+//! Transitions are never animated.
 
-use crate::objc::{objc_classes, ClassExports};
+use super::ui_application::UIInterfaceOrientation;
+use super::ui_device::UIDeviceOrientationPortrait;
+use super::ui_navigation_controller::NavigationControllerState;
+use super::ui_nib;
+use super::ui_tab_bar_controller::TabBarControllerState;
+use super::ui_view::UIViewHostObject;
+use crate::frameworks::core_graphics::CGRect;
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::{NSInteger, NSUInteger};
+use crate::objc::{
+    id, msg, msg_class, nil, objc_classes, release, retain, AnyHostObject, ClassExports,
+    HostObject, NSZonePtr,
+};
+use crate::Environment;
+
+/// State specific to the UIKit classes that inherit from `UIViewController`,
+/// which have to share its host object.
+#[derive(Clone)]
+pub(super) enum UIViewControllerSubclass {
+    Other,
+    UINavigationController(NavigationControllerState),
+    UITabBarController(TabBarControllerState),
+}
+
+#[derive(Clone)]
+pub(super) struct UIViewControllerHostObject {
+    /// `UIView*`, nil until it's loaded. This is a strong reference.
+    view: id,
+    /// `NSString*`, possibly nil. This is a strong reference.
+    nib_name: id,
+    /// `NSBundle*`, possibly nil. This is a strong reference.
+    nib_bundle: id,
+    /// `NSString*`, possibly nil. This is a strong reference.
+    title: id,
+    /// `UINavigationItem*`, nil until it's first needed. This is a strong
+    /// reference.
+    navigation_item: id,
+    /// `UITabBarItem*`, nil until it's first needed. This is a strong
+    /// reference.
+    tab_bar_item: id,
+    /// The navigation or tab bar controller this view controller is a child
+    /// of, if any. This is a weak reference.
+    parent: id,
+    /// The view controller this one is presenting modally, if any. This is a
+    /// strong reference.
+    modal_view_controller: id,
+    /// The view controller presenting this one modally, if any. This is a weak
+    /// reference.
+    presenting_view_controller: id,
+    editing: bool,
+    pub(super) subclass: UIViewControllerSubclass,
+}
+impl HostObject for UIViewControllerHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -13,10 +77,562 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @implementation UIViewController: UIResponder
 
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UIViewControllerHostObject {
+        view: nil,
+        nib_name: nil,
+        nib_bundle: nil,
+        title: nil,
+        navigation_item: nil,
+        tab_bar_item: nil,
+        parent: nil,
+        modal_view_controller: nil,
+        presenting_view_controller: nil,
+        editing: false,
+        subclass: UIViewControllerSubclass::Other,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)init {
+    msg![env; this initWithNibName:nil bundle:nil]
+}
+
+- (id)initWithNibName:(id)nib_name // NSString*
+               bundle:(id)nib_bundle { // NSBundle*
+    let nib_name: id = msg![env; nib_name copy];
+    retain(env, nib_bundle);
+    let host_object = env.objc.borrow_mut::<UIViewControllerHostObject>(this);
+    host_object.nib_name = nib_name;
+    host_object.nib_bundle = nib_bundle;
+    this
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    init_with_coder(env, this, coder)
+}
+
+- (())dealloc {
+    let host_object = env.objc.borrow_mut::<UIViewControllerHostObject>(this);
+    let subclass = std::mem::replace(&mut host_object.subclass, UIViewControllerSubclass::Other);
+    let &mut UIViewControllerHostObject {
+        view,
+        nib_name,
+        nib_bundle,
+        title,
+        navigation_item,
+        tab_bar_item,
+        modal_view_controller,
+        ..
+    } = host_object;
+
+    match subclass {
+        UIViewControllerSubclass::Other => (),
+        UIViewControllerSubclass::UINavigationController(state) => state.release(env),
+        UIViewControllerSubclass::UITabBarController(state) => state.release(env),
+    }
+    if view != nil {
+        env.objc.borrow_mut::<UIViewHostObject>(view).view_controller = nil;
+    }
+    if modal_view_controller != nil {
+        env.objc
+            .borrow_mut::<UIViewControllerHostObject>(modal_view_controller)
+            .presenting_view_controller = nil;
+    }
+    release(env, view);
+    release(env, nib_name);
+    release(env, nib_bundle);
+    release(env, title);
+    release(env, navigation_item);
+    release(env, tab_bar_item);
+    release(env, modal_view_controller);
+
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)nibName {
+    env.objc.borrow::<UIViewControllerHostObject>(this).nib_name
+}
+- (id)nibBundle {
+    env.objc.borrow::<UIViewControllerHostObject>(this).nib_bundle
+}
+
+// The view is loaded the first time it's needed.
+- (id)view {
+    let view = env.objc.borrow::<UIViewControllerHostObject>(this).view;
+    if view != nil {
+        return view;
+    }
+    () = msg![env; this loadView];
+    let view = env.objc.borrow::<UIViewControllerHostObject>(this).view;
+    if view == nil {
+        panic!("[(UIViewController*){:?} loadView] didn't set a view", this);
+    }
+    () = msg![env; this viewDidLoad];
+    view
+}
+- (())setView:(id)view { // UIView*
+    retain(env, view);
+    let old = std::mem::replace(
+        &mut env.objc.borrow_mut::<UIViewControllerHostObject>(this).view,
+        view
+    );
+    if old != nil {
+        env.objc.borrow_mut::<UIViewHostObject>(old).view_controller = nil;
+        release(env, old);
+    }
+    if view != nil {
+        env.objc.borrow_mut::<UIViewHostObject>(view).view_controller = this;
+    }
+}
+- (bool)isViewLoaded {
+    env.objc.borrow::<UIViewControllerHostObject>(this).view != nil
+}
+
+- (())loadView {
+    let &UIViewControllerHostObject { nib_name, nib_bundle, .. } = env.objc.borrow(this);
+    // TODO: look for a nib file named after the class if there's no nib name.
+    if nib_name != nil {
+        // Loading the nib file connects its view to this controller's "view"
+        // outlet.
+        if !ui_nib::load_nib_named(env, nib_name, nib_bundle, this) {
+            panic!("Couldn't load nib file for (UIViewController*){:?}", this);
+        }
+        return;
+    }
+    let screen: id = msg_class![env; UIScreen mainScreen];
+    let frame: CGRect = msg![env; screen applicationFrame];
+    let view: id = msg_class![env; UIView alloc];
+    let view: id = msg![env; view initWithFrame:frame];
+    () = msg![env; this setView:view];
+    release(env, view);
+}
+
+// These are for subclasses to override.
+- (())viewDidLoad {}
+- (())viewDidUnload {}
+- (())viewWillAppear:(bool)_animated {}
+- (())viewDidAppear:(bool)_animated {}
+- (())viewWillDisappear:(bool)_animated {}
+- (())viewDidDisappear:(bool)_animated {}
+- (())didReceiveMemoryWarning {}
+
+- (bool)shouldAutorotateToInterfaceOrientation:(UIInterfaceOrientation)orientation {
+    orientation == UIDeviceOrientationPortrait
+}
+- (UIInterfaceOrientation)interfaceOrientation {
+    // TODO: rotation
+    UIDeviceOrientationPortrait
+}
+
+- (id)title {
+    env.objc.borrow::<UIViewControllerHostObject>(this).title
+}
+- (())setTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    let host_object = env.objc.borrow_mut::<UIViewControllerHostObject>(this);
+    let old = std::mem::replace(&mut host_object.title, title);
+    let navigation_item = host_object.navigation_item;
+    let tab_bar_item = host_object.tab_bar_item;
+    release(env, old);
+    // The items show the controller's title.
+    if navigation_item != nil {
+        () = msg![env; navigation_item setTitle:title];
+    }
+    if tab_bar_item != nil {
+        () = msg![env; tab_bar_item setTitle:title];
+    }
+}
+
+- (id)navigationItem {
+    let existing = env.objc.borrow::<UIViewControllerHostObject>(this).navigation_item;
+    if existing != nil {
+        return existing;
+    }
+    let title = env.objc.borrow::<UIViewControllerHostObject>(this).title;
+    let item: id = msg_class![env; UINavigationItem alloc];
+    let item: id = msg![env; item initWithTitle:title];
+    env.objc.borrow_mut::<UIViewControllerHostObject>(this).navigation_item = item;
+    item
+}
+
+- (id)tabBarItem {
+    let existing = env.objc.borrow::<UIViewControllerHostObject>(this).tab_bar_item;
+    if existing != nil {
+        return existing;
+    }
+    let title = env.objc.borrow::<UIViewControllerHostObject>(this).title;
+    let item: id = msg_class![env; UITabBarItem alloc];
+    let item: id = msg![env; item initWithTitle:title image:nil tag:(0 as NSInteger)];
+    env.objc.borrow_mut::<UIViewControllerHostObject>(this).tab_bar_item = item;
+    item
+}
+- (())setTabBarItem:(id)item { // UITabBarItem*
+    retain(env, item);
+    let old = std::mem::replace(
+        &mut env.objc.borrow_mut::<UIViewControllerHostObject>(this).tab_bar_item,
+        item
+    );
+    release(env, old);
+}
+
+- (id)parentViewController {
+    let host_object = env.objc.borrow::<UIViewControllerHostObject>(this);
+    if host_object.parent != nil {
+        host_object.parent
+    } else {
+        host_object.presenting_view_controller
+    }
+}
+- (id)navigationController {
+    find_ancestor(env, this, "UINavigationController")
+}
+- (id)tabBarController {
+    find_ancestor(env, this, "UITabBarController")
+}
+
+- (id)modalViewController {
+    env.objc.borrow::<UIViewControllerHostObject>(this).modal_view_controller
+}
+- (())presentModalViewController:(id)modal_view_controller // UIViewController*
+                        animated:(bool)animated {
+    present_modal(env, this, modal_view_controller, animated);
+}
+- (())dismissModalViewControllerAnimated:(bool)animated {
+    dismiss_modal(env, this, animated);
+}
+
+- (bool)isEditing {
+    env.objc.borrow::<UIViewControllerHostObject>(this).editing
+}
 - (())setEditing:(bool)editing {
-    log!("TODO: [(UIViewController*){:?} setEditing:{}]", this, editing); // TODO
+    msg![env; this setEditing:editing animated:false]
+}
+- (())setEditing:(bool)editing
+        animated:(bool)_animated {
+    env.objc.borrow_mut::<UIViewControllerHostObject>(this).editing = editing;
+}
+
+// UIResponder implementation
+- (id)nextResponder {
+    let view = env.objc.borrow::<UIViewControllerHostObject>(this).view;
+    if view != nil {
+        msg![env; view superview]
+    } else {
+        nil
+    }
 }
 
 @end
 
 };
+
+/// The body of UIViewController's `initWithCoder:`, for use by the subclasses
+/// that decode more properties.
+pub(super) fn init_with_coder(env: &mut Environment, this: id, coder: id) -> id {
+    let mut decode = |key| {
+        let key_ns_string = get_static_str(env, key);
+        let object: id = msg![env; coder decodeObjectForKey:key_ns_string];
+        retain(env, object)
+    };
+    let nib_name = decode("UINibName");
+    let title = decode("UITitle");
+    let navigation_item = decode("UINavigationItem");
+    let tab_bar_item = decode("UITabBarItem");
+
+    let host_object = env.objc.borrow_mut::<UIViewControllerHostObject>(this);
+    host_object.nib_name = nib_name;
+    host_object.title = title;
+    host_object.navigation_item = navigation_item;
+    host_object.tab_bar_item = tab_bar_item;
+
+    // The view is only in the same nib file if there's no separate one.
+    let key_ns_string = get_static_str(env, "UIView");
+    let view: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if view != nil {
+        () = msg![env; this setView:view];
+    }
+
+    this
+}
+
+/// Decode the child view controllers of a navigation or tab bar controller.
+pub(super) fn decode_child_view_controllers(env: &mut Environment, coder: id) -> Vec<id> {
+    // Nib files for iPhone OS 5 and later use a different key.
+    let mut key_ns_string = get_static_str(env, "UIViewControllers");
+    if !msg![env; coder containsValueForKey:key_ns_string] {
+        key_ns_string = get_static_str(env, "UIChildViewControllers");
+    }
+    let array: id = msg![env; coder decodeObjectForKey:key_ns_string];
+    if array == nil {
+        return Vec::new();
+    }
+    let count: NSUInteger = msg![env; array count];
+    (0..count)
+        .map(|i| msg![env; array objectAtIndex:i])
+        .collect()
+}
+
+/// For use by navigation and tab bar controllers: make a view controller
+/// their child, or not (if `parent` is nil).
+pub(super) fn set_parent(env: &mut Environment, child: id, parent: id) {
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(child)
+        .parent = parent;
+}
+
+/// For use by navigation and tab bar controllers: check if a view controller's
+/// view is loaded and in a window, i.e. whether its children should get
+/// appearance messages.
+pub(super) fn view_is_in_window(env: &mut Environment, controller: id) -> bool {
+    let view = env
+        .objc
+        .borrow::<UIViewControllerHostObject>(controller)
+        .view;
+    if view == nil {
+        return false;
+    }
+    let window_class = env.objc.get_known_class("UIWindow", &mut env.mem);
+    let mut view = view;
+    while view != nil {
+        if msg![env; view isKindOfClass:window_class] {
+            return true;
+        }
+        view = msg![env; view superview];
+    }
+    false
+}
+
+/// For use by `-[UIView addSubview:]`: on iPhone OS 3, navigation and tab bar
+/// controllers send their visible child the appearance messages when their
+/// view is added to a window.
+pub(super) fn view_added_to_superview(env: &mut Environment, controller: id, superview: id) {
+    let window_class = env.objc.get_known_class("UIWindow", &mut env.mem);
+    if !msg![env; superview isKindOfClass:window_class] {
+        return;
+    }
+    if matches!(
+        env.objc
+            .borrow::<UIViewControllerHostObject>(controller)
+            .subclass,
+        UIViewControllerSubclass::Other
+    ) {
+        return;
+    }
+    () = msg![env; controller viewWillAppear:false];
+    () = msg![env; controller viewDidAppear:false];
+}
+
+/// Find the nearest container view controller of a particular class.
+fn find_ancestor(env: &mut Environment, controller: id, class_name: &str) -> id {
+    let class = env.objc.get_known_class(class_name, &mut env.mem);
+    let mut ancestor = env
+        .objc
+        .borrow::<UIViewControllerHostObject>(controller)
+        .parent;
+    while ancestor != nil {
+        if msg![env; ancestor isKindOfClass:class] {
+            return ancestor;
+        }
+        ancestor = env
+            .objc
+            .borrow::<UIViewControllerHostObject>(ancestor)
+            .parent;
+    }
+    nil
+}
+
+/// The outermost container of a view controller, or the controller itself if
+/// it's not in a container.
+fn outermost_container(env: &mut Environment, controller: id) -> id {
+    let mut controller = controller;
+    loop {
+        let parent = env
+            .objc
+            .borrow::<UIViewControllerHostObject>(controller)
+            .parent;
+        if parent == nil {
+            return controller;
+        }
+        controller = parent;
+    }
+}
+
+/// Show a modal view controller in place of the view of the presenting view
+/// controller (or its outermost container).
+fn present_modal(env: &mut Environment, this: id, modal: id, animated: bool) {
+    let presenter = outermost_container(env, this);
+    let existing = env
+        .objc
+        .borrow::<UIViewControllerHostObject>(presenter)
+        .modal_view_controller;
+    if existing != nil {
+        // Only one modal view controller can be presented at a time, so this
+        // goes on top of the existing one.
+        return present_modal(env, existing, modal, animated);
+    }
+
+    log_dbg!(
+        "(UIViewController*){:?} presents (UIViewController*){:?} modally",
+        presenter,
+        modal
+    );
+
+    retain(env, modal);
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(presenter)
+        .modal_view_controller = modal;
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(modal)
+        .presenting_view_controller = presenter;
+
+    let presenter_view: id = msg![env; presenter view];
+    let superview: id = msg![env; presenter_view superview];
+    if superview == nil {
+        log!(
+            "Warning: (UIViewController*){:?} presented a modal view controller while its view isn't shown",
+            presenter
+        );
+        return;
+    }
+
+    let modal_view: id = msg![env; modal view];
+    let frame: CGRect = msg![env; presenter_view frame];
+    () = msg![env; modal_view setFrame:frame];
+
+    () = msg![env; presenter viewWillDisappear:animated];
+    () = msg![env; modal viewWillAppear:animated];
+    // addSubview: would send appearance messages for containers too.
+    () = msg![env; superview insertSubview:modal_view atIndex:(NSInteger::MAX)];
+    () = msg![env; modal_view layoutSubviews];
+    () = msg![env; presenter_view removeFromSuperview];
+    () = msg![env; presenter viewDidDisappear:animated];
+    () = msg![env; modal viewDidAppear:animated];
+}
+
+/// Dismiss the modal view controller that `this` is presenting, or that
+/// `this` is (or is inside).
+fn dismiss_modal(env: &mut Environment, this: id, animated: bool) {
+    let mut presenter = this;
+    loop {
+        let host_object = env.objc.borrow::<UIViewControllerHostObject>(presenter);
+        if host_object.modal_view_controller != nil {
+            break;
+        }
+        if host_object.presenting_view_controller != nil {
+            presenter = host_object.presenting_view_controller;
+            break;
+        }
+        if host_object.parent == nil {
+            log!(
+                "Warning: (UIViewController*){:?} has no modal view controller to dismiss",
+                this
+            );
+            return;
+        }
+        presenter = host_object.parent;
+    }
+
+    let modal = env
+        .objc
+        .borrow::<UIViewControllerHostObject>(presenter)
+        .modal_view_controller;
+
+    // Any modal view controller the modal view controller is presenting is
+    // dismissed too.
+    let modal_modal = env
+        .objc
+        .borrow::<UIViewControllerHostObject>(modal)
+        .modal_view_controller;
+    if modal_modal != nil {
+        dismiss_modal(env, modal, false);
+    }
+
+    log_dbg!(
+        "(UIViewController*){:?} dismisses modal (UIViewController*){:?}",
+        presenter,
+        modal
+    );
+
+    let modal_view = env.objc.borrow::<UIViewControllerHostObject>(modal).view;
+    let superview: id = if modal_view != nil {
+        msg![env; modal_view superview]
+    } else {
+        nil
+    };
+    if superview != nil {
+        let presenter_view: id = msg![env; presenter view];
+        () = msg![env; modal viewWillDisappear:animated];
+        () = msg![env; presenter viewWillAppear:animated];
+        // addSubview: would send appearance messages for containers too.
+        () = msg![env; superview insertSubview:presenter_view atIndex:(NSInteger::MAX)];
+        () = msg![env; presenter_view layoutSubviews];
+        () = msg![env; modal_view removeFromSuperview];
+        () = msg![env; modal viewDidDisappear:animated];
+        () = msg![env; presenter viewDidAppear:animated];
+    }
+
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(presenter)
+        .modal_view_controller = nil;
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(modal)
+        .presenting_view_controller = nil;
+    release(env, modal);
+}
+
+/// For use by navigation and tab bar controllers: replace the child view
+/// controller whose view is shown in the container's view (which must be
+/// loaded), sending the appearance messages if it's in a window.
+pub(super) fn show_child(
+    env: &mut Environment,
+    container: id,
+    old_child: id,
+    new_child: id,
+    frame: CGRect,
+    animated: bool,
+) {
+    let container_view = env
+        .objc
+        .borrow::<UIViewControllerHostObject>(container)
+        .view;
+    let in_window = view_is_in_window(env, container);
+
+    // The new view has to be loaded before viewWillAppear: is sent.
+    let new_view: id = if new_child != nil {
+        msg![env; new_child view]
+    } else {
+        nil
+    };
+
+    if in_window {
+        if old_child != nil {
+            () = msg![env; old_child viewWillDisappear:animated];
+        }
+        if new_child != nil {
+            () = msg![env; new_child viewWillAppear:animated];
+        }
+    }
+    if old_child != nil {
+        let old_view = env
+            .objc
+            .borrow::<UIViewControllerHostObject>(old_child)
+            .view;
+        () = msg![env; old_view removeFromSuperview];
+    }
+    if new_view != nil {
+        () = msg![env; new_view setFrame:frame];
+        // The container's bars stay in front.
+        () = msg![env; container_view insertSubview:new_view atIndex:(0 as NSInteger)];
+        () = msg![env; new_view layoutSubviews];
+    }
+    if in_window {
+        if old_child != nil {
+            () = msg![env; old_child viewDidDisappear:animated];
+        }
+        if new_child != nil {
+            () = msg![env; new_child viewDidAppear:animated];
+        }
+    }
+}
@@ -5,7 +5,16 @@
  */
 //! `UIWindow`.
 
-use crate::objc::{objc_classes, ClassExports};
+use crate::objc::{id, msg, msg_class, objc_classes, ClassExports};
+use crate::Environment;
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Visible windows in back-to-front order. These are weak references.
+    visible_windows: Vec<id>,
+    /// The key window, if any. This is a weak reference.
+    pub(super) key_window: Option<id>,
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -13,12 +22,58 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @implementation UIWindow: UIView
 
-// TODO
+// TODO: window levels
 
 - (())makeKeyAndVisible {
-  // TODO: implement support for hidden/non-key UIWindow objects
+    () = msg![env; this setHidden:false];
+    msg![env; this makeKeyWindow]
+}
+
+- (())makeKeyWindow {
+    env.framework_state.uikit.ui_window.key_window = Some(this);
+}
+- (bool)isKeyWindow {
+    env.framework_state.uikit.ui_window.key_window == Some(this)
+}
+
+// UIResponder implementation
+- (id)nextResponder {
+    msg_class![env; UIApplication sharedApplication]
+}
+
+// Windows are hidden until they're made visible, so the layer's hidden
+// property can't be used on its own.
+- (bool)isHidden {
+    !env.framework_state.uikit.ui_window.visible_windows.contains(&this)
+}
+- (())setHidden:(bool)hidden {
+    let visible_windows = &mut env.framework_state.uikit.ui_window.visible_windows;
+    if hidden {
+        visible_windows.retain(|&window| window != this);
+    } else if !visible_windows.contains(&this) {
+        visible_windows.push(this);
+    }
+    let layer: id = msg![env; this layer];
+    () = msg![env; layer setHidden:hidden];
 }
 
 @end
 
 };
+
+/// Get the visible windows, in back-to-front order. This is the starting point
+/// for drawing the screen, see
+/// [crate::frameworks::core_animation::composition].
+pub fn visible_windows(env: &mut Environment) -> Vec<id> {
+    env.framework_state.uikit.ui_window.visible_windows.clone()
+}
+
+/// For use by `-[UIView dealloc]`: make sure a window that is being
+/// deallocated is no longer referenced.
+pub(super) fn forget_window(env: &mut Environment, window: id) {
+    let state = &mut env.framework_state.uikit.ui_window;
+    state.visible_windows.retain(|&w| w != window);
+    if state.key_window == Some(window) {
+        state.key_window = None;
+    }
+}
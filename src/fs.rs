@@ -10,7 +10,7 @@
 //!
 //! Most of the filesystem is frozen at the point of creation and can't be
 //! modified. The exception is the writeable parts of the app's sandboxed home
-//! directory (`Documents` etc).
+//! directory (`Documents`, `Library` and `tmp`).
 //!
 //! All files in the guest filesystem must have a corresponding file in the host
 //! filesystem, or a corresponding file inside a `.ipa` file (ZIP archive) in
@@ -395,8 +395,8 @@ pub struct Fs {
 }
 impl Fs {
     /// Construct a filesystem containing a home directory for the app, its
-    /// bundle and documents, and the bundled shared libraries. Returns the new
-    /// filesystem and the guest path of the bundle.
+    /// bundle and writeable sandbox directories, and the bundled shared
+    /// libraries. Returns the new filesystem and the guest path of the bundle.
     ///
     /// The `bundle_dir_name` argument will be used as the name of the bundle
     /// directory in the guest filesystem, and must end in `.app`.
@@ -406,8 +406,9 @@ impl Fs {
     ///
     /// The `bundle_id` argument should be some value that uniquely identifies
     /// the app. This will be used to construct the host path for the app's
-    /// sandbox directory, where documents can be stored. A directory will be
-    /// created at that path if it does not already exist.
+    /// sandbox directory, where documents, preferences, caches and temporary
+    /// files can be stored. Directories will be created at that path if they
+    /// do not already exist. Temporary files from previous runs are deleted.
     pub fn new(
         app_bundle: BundleData,
         bundle_dir_name: String,
@@ -421,15 +422,33 @@ impl Fs {
         let bundle_guest_path = home_directory.join(&bundle_dir_name);
 
         let prefix = files_prefix();
-        let documents_host_path = Path::new(prefix)
-            .join("touchHLE_sandbox")
-            .join(bundle_id)
-            .join("Documents");
-        if let Err(e) = std::fs::create_dir_all(&documents_host_path) {
-            panic!(
-                "Could not create documents directory for app at {:?}: {:?}",
-                documents_host_path, e
-            );
+        let sandbox_host_path = Path::new(prefix).join("touchHLE_sandbox").join(bundle_id);
+        let documents_host_path = sandbox_host_path.join("Documents");
+        let library_host_path = sandbox_host_path.join("Library");
+        let tmp_host_path = sandbox_host_path.join("tmp");
+        // Apps expect tmp to be empty when launched. iPhone OS doesn't
+        // guarantee this, but it does clean it up from time to time.
+        if tmp_host_path.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&tmp_host_path) {
+                panic!(
+                    "Could not clear temporary directory for app at {:?}: {:?}",
+                    tmp_host_path, e
+                );
+            }
+        }
+        for dir in [
+            documents_host_path.clone(),
+            library_host_path.join("Caches"),
+            // Used by NSUserDefaults.
+            library_host_path.join("Preferences"),
+            tmp_host_path.clone(),
+        ] {
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                panic!(
+                    "Could not create sandbox directory for app at {:?}: {:?}",
+                    dir, e
+                );
+            }
         }
 
         // Some Free Software libraries are bundled with touchHLE.
@@ -466,6 +485,20 @@ impl Fs {
                                         /* writeable: */ true,
                                     ),
                                 ),
+                                (
+                                    "Library".to_string(),
+                                    FsNode::from_host_dir(
+                                        &library_host_path,
+                                        /* writeable: */ true,
+                                    ),
+                                ),
+                                (
+                                    "tmp".to_string(),
+                                    FsNode::from_host_dir(
+                                        &tmp_host_path,
+                                        /* writeable: */ true,
+                                    ),
+                                ),
                             ]),
                             writeable: None,
                         },
@@ -796,6 +829,80 @@ impl Fs {
         Ok(())
     }
 
+    /// Like [std::fs::rename] but for the guest filesystem. Only files can be
+    /// renamed for now. If there's already a file at the destination, it is
+    /// replaced.
+    pub fn rename<P: AsRef<GuestPath>, Q: AsRef<GuestPath>>(
+        &mut self,
+        from: P,
+        to: Q,
+    ) -> Result<(), ()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+
+        // Check the destination first, so that nothing changes if it's
+        // unsuitable.
+        let (to_parent_node, to_name) = self.lookup_parent_node(to).ok_or(())?;
+        let FsNode::Directory {
+            children,
+            writeable: to_dir_host_path,
+        } = to_parent_node else {
+            return Err(());
+        };
+        let Some(to_dir_host_path) = to_dir_host_path else {
+            log!("Warning: attempt to rename file to path {:?}, but directory is read-only", to);
+            return Err(());
+        };
+        match children.get(&to_name) {
+            None => (),
+            Some(FsNode::HostFile {
+                writeable: true, ..
+            }) => (),
+            Some(_) => return Err(()),
+        }
+        if to_name.chars().any(std::path::is_separator) {
+            return Err(());
+        }
+        let to_host_path = to_dir_host_path.join(&to_name);
+
+        let (from_parent_node, from_name) = self.lookup_parent_node(from).ok_or(())?;
+        let FsNode::Directory {
+            children,
+            writeable: Some(_),
+        } = from_parent_node else {
+            return Err(());
+        };
+        let Some(FsNode::HostFile {
+            host_path: from_host_path,
+            writeable: true,
+        }) = children.get(&from_name) else {
+            return Err(());
+        };
+        handle_open_err(
+            std::fs::rename(from_host_path, &to_host_path),
+            from_host_path,
+        );
+        children.remove(&from_name).unwrap();
+
+        let (to_parent_node, _) = self.lookup_parent_node(to).unwrap();
+        let FsNode::Directory { children, .. } = to_parent_node else {
+            unreachable!();
+        };
+        log_dbg!(
+            "Renamed file at path {:?} to {:?} (host path: {:?})",
+            from,
+            to,
+            to_host_path
+        );
+        children.insert(
+            to_name,
+            FsNode::HostFile {
+                host_path: to_host_path,
+                writeable: true,
+            },
+        );
+        Ok(())
+    }
+
     /// Like [std::fs::create_dir] but for the guest filesystem.
     pub fn create_dir<P: AsRef<GuestPath>>(&mut self, path: P) -> Result<(), ()> {
         let path = path.as_ref();
@@ -11,9 +11,13 @@
 //! - The GDB source code:
 //!   - `include/gdb/signals.def` for the meanings of signal numbers
 //!   - `gdb/arch/arm.h` for ARMv6 register numbers
+//!
+//! GDB thread IDs must be positive (0 and -1 have special meanings), so a
+//! touchHLE [ThreadID] `n` is presented to the debugger as thread `n + 1`.
 
-use crate::cpu::{Cpu, CpuError};
+use crate::cpu::{Cpu, CpuContext, CpuError};
 use crate::mem::{GuestUSize, Mem, Ptr};
+use crate::ThreadID;
 use std::fmt::Write as _;
 use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
 use std::net::TcpStream;
@@ -31,6 +35,53 @@ const TARGET_XML: &str = r##"
 pub struct GdbServer {
     reader: BufReader<TcpStream>,
     first_halt: bool,
+    /// Thread selected by the `Hg` packet, used for register access.
+    register_thread: ThreadID,
+}
+
+/// The threads the debugger can see. Since only one thread's CPU state can be
+/// in the [Cpu] at a time, the others are accessed via their saved contexts.
+pub struct DebugThreads<'a> {
+    /// The thread whose state is currently in the [Cpu].
+    pub current: ThreadID,
+    /// Indexed by [ThreadID]. This is [None] for the current thread and for
+    /// threads that have finished.
+    pub contexts: Vec<Option<&'a mut CpuContext>>,
+}
+impl DebugThreads<'_> {
+    fn is_alive(&self, thread: ThreadID) -> bool {
+        thread == self.current || matches!(self.contexts.get(thread), Some(Some(_)))
+    }
+
+    fn alive_threads(&self) -> impl Iterator<Item = ThreadID> + '_ {
+        (0..self.contexts.len()).filter(|&thread| self.is_alive(thread))
+    }
+
+    /// Run a closure with a particular thread's state loaded into the CPU.
+    fn with_cpu<R>(&mut self, cpu: &mut Cpu, thread: ThreadID, f: impl FnOnce(&mut Cpu) -> R) -> R {
+        if thread == self.current {
+            return f(cpu);
+        }
+        let context = self.contexts[thread].as_mut().unwrap();
+        cpu.swap_context(context);
+        let res = f(cpu);
+        cpu.swap_context(context);
+        res
+    }
+
+    /// Parse a thread ID sent by the debugger. `0` (any thread) and `-1` (all
+    /// threads) are treated as the current thread.
+    fn parse_id(&self, id: &str) -> Option<ThreadID> {
+        if id == "0" || id == "-1" {
+            return Some(self.current);
+        }
+        let id = ThreadID::from_str_radix(id, 16).ok()?.checked_sub(1)?;
+        self.is_alive(id).then_some(id)
+    }
+}
+
+fn format_thread_id(thread: ThreadID) -> String {
+    format!("{:x}", thread + 1)
 }
 
 impl GdbServer {
@@ -54,6 +105,7 @@ impl GdbServer {
         GdbServer {
             reader: BufReader::with_capacity(4096, connection),
             first_halt: true,
+            register_thread: 0,
         }
     }
 
@@ -126,6 +178,15 @@ impl GdbServer {
         log_dbg!("Sent packet: {:?}", body);
     }
 
+    /// Tell the debugger execution has stopped, and on which thread.
+    fn send_stop_reply(&mut self, signal: u8, thread: ThreadID) {
+        self.send_packet(&format!(
+            "T{:02x}thread:{};",
+            signal,
+            format_thread_id(thread)
+        ));
+    }
+
     /// Communciates with the debugger, returning only once it requests
     /// execution should continue. Returns [true] if the CPU should step and
     /// then resume debugging, or [false] if it should resume normal execution.
@@ -134,9 +195,13 @@ impl GdbServer {
         stop_reason: Option<CpuError>,
         cpu: &mut Cpu,
         mem: &mut Mem,
+        mut threads: DebugThreads,
     ) -> bool {
         echo!("Waiting for debugger to continue.");
 
+        // The debugger expects the thread that stopped to be selected.
+        self.register_thread = threads.current;
+
         // Send reply to continue/step packet that gdb sent earlier, so it knows
         // why execution was stopped.
         match stop_reason {
@@ -147,7 +212,7 @@ impl GdbServer {
                 } else {
                     // The debugger previously requested stepping and no errors
                     // occurred.
-                    self.send_packet("S05"); // SIGTRAP
+                    self.send_stop_reply(0x05, threads.current); // SIGTRAP
                 }
             }
             // GDB uses an undefined instruction for software breakpoints in
@@ -155,10 +220,10 @@ impl GdbServer {
             // It apparently expects SIGTRAP instead of SIGILL even in the
             // former case.
             Some(CpuError::UndefinedInstruction) | Some(CpuError::Breakpoint) => {
-                self.send_packet("S05"); // SIGTRAP
+                self.send_stop_reply(0x05, threads.current); // SIGTRAP
             }
             Some(CpuError::MemoryError) => {
-                self.send_packet("S0b"); // SIGSEGV
+                self.send_stop_reply(0x0b, threads.current); // SIGSEGV
             }
         }
 
@@ -175,12 +240,13 @@ impl GdbServer {
                 // Query for target halt reason when first connecting
                 b'?' => {
                     assert!(stop_reason.is_none());
-                    self.send_packet("S00"); // no signal
+                    self.send_stop_reply(0x00, threads.current); // no signal
                 }
                 // Read general registers
                 b'g' => {
+                    let regs = threads.with_cpu(cpu, self.register_thread, |cpu| *cpu.regs());
                     let mut packet = String::with_capacity(16 * 4 * 2);
-                    for reg in cpu.regs() {
+                    for reg in regs {
                         // Rust always prints in big-endian, but GDB expects
                         // little-endian.
                         let reg = u32::from_be_bytes(reg.to_le_bytes());
@@ -191,29 +257,33 @@ impl GdbServer {
                 // Write general registers
                 b'G' => {
                     let data = &p[1..];
-                    let regs = cpu.regs_mut();
-                    assert!(data.len() == regs.len() * 4 * 2);
-                    for (i, reg) in regs.iter_mut().enumerate() {
-                        let word = &data[i * 4 * 2..][..4 * 2];
-                        let word = u32::from_str_radix(word, 16).unwrap();
-                        // Rust decodes in big-endian, but GDB supplies
-                        // little-endian.
-                        let word = u32::from_le_bytes(word.to_be_bytes());
-                        *reg = word;
-                    }
+                    threads.with_cpu(cpu, self.register_thread, |cpu| {
+                        let regs = cpu.regs_mut();
+                        assert!(data.len() == regs.len() * 4 * 2);
+                        for (i, reg) in regs.iter_mut().enumerate() {
+                            let word = &data[i * 4 * 2..][..4 * 2];
+                            let word = u32::from_str_radix(word, 16).unwrap();
+                            // Rust decodes in big-endian, but GDB supplies
+                            // little-endian.
+                            let word = u32::from_le_bytes(word.to_be_bytes());
+                            *reg = word;
+                        }
+                    });
                     self.send_packet("OK");
                 }
                 // Read single register by number
                 b'p' => {
                     let num = usize::from_str_radix(&p[1..], 16).unwrap();
-                    let reg = if num < 16 {
-                        Some(cpu.regs()[num])
-                    } else if num == 25 {
-                        Some(cpu.cpsr())
-                    // TODO: FPSCR, VFP registers
-                    } else {
-                        None
-                    };
+                    let reg = threads.with_cpu(cpu, self.register_thread, |cpu| {
+                        if num < 16 {
+                            Some(cpu.regs()[num])
+                        } else if num == 25 {
+                            Some(cpu.cpsr())
+                        // TODO: FPSCR, VFP registers
+                        } else {
+                            None
+                        }
+                    });
                     if let Some(reg) = reg {
                         // Rust always prints in big-endian, but GDB expects
                         // little-endian.
@@ -232,13 +302,20 @@ impl GdbServer {
                     // Rust decodes in big-endian, but GDB supplies
                     // little-endian.
                     let word = u32::from_le_bytes(word.to_be_bytes());
-                    if num < 16 {
-                        cpu.regs_mut()[num] = word;
-                        self.send_packet("OK");
-                    } else if num == 25 {
-                        cpu.set_cpsr(word);
+                    let written = threads.with_cpu(cpu, self.register_thread, |cpu| {
+                        if num < 16 {
+                            cpu.regs_mut()[num] = word;
+                            true
+                        } else if num == 25 {
+                            cpu.set_cpsr(word);
+                            true
+                        // TODO: FPSCR, VFP registers
+                        } else {
+                            false
+                        }
+                    });
+                    if written {
                         self.send_packet("OK");
-                    // TODO: FPSCR, VFP registers
                     } else {
                         // Error 0
                         self.send_packet("E00");
@@ -305,6 +382,38 @@ impl GdbServer {
                     }
                     break p.as_bytes()[0] == b'S';
                 }
+                // Set thread for subsequent operations
+                b'H' => {
+                    // The packet should be an operation followed by an ID.
+                    let op = p.get(1..2);
+                    let id = p.get(2..).filter(|id| !id.is_empty());
+                    let (Some(op), Some(id)) = (op, id) else {
+                        // Error 1
+                        self.send_packet("E01");
+                        continue;
+                    };
+                    match threads.parse_id(id) {
+                        Some(thread) => {
+                            // Only register access ('g') can target a
+                            // particular thread. Continue/step ('c') always
+                            // affects the current thread, since only it can
+                            // be run while debugging.
+                            if op == "g" {
+                                self.register_thread = thread;
+                            }
+                            self.send_packet("OK");
+                        }
+                        None => self.send_packet("E00"),
+                    }
+                }
+                // Query whether thread is alive
+                b'T' => {
+                    if threads.parse_id(&p[1..]).is_some() {
+                        self.send_packet("OK");
+                    } else {
+                        self.send_packet("E00");
+                    }
+                }
                 // Kill
                 b'k' => {
                     panic!("Debugger requested kill.");
@@ -314,6 +423,45 @@ impl GdbServer {
                     if p == "qAttached" {
                         // New process
                         self.send_packet("0");
+                    // Query current thread
+                    } else if p == "qC" {
+                        self.send_packet(&format!("QC{}", format_thread_id(threads.current)));
+                    // Query list of threads. All of them fit in the first
+                    // reply, so the subsequent query gets an end-of-list.
+                    } else if p == "qfThreadInfo" {
+                        let ids: Vec<String> =
+                            threads.alive_threads().map(format_thread_id).collect();
+                        self.send_packet(&format!("m{}", ids.join(",")));
+                    } else if p == "qsThreadInfo" {
+                        self.send_packet("l");
+                    // Query supported vCont actions
+                    } else if p == "vCont?" {
+                        self.send_packet("vCont;c;C;s;S");
+                    // Continue or step, with per-thread actions
+                    } else if let Some(actions) = p.strip_prefix("vCont;") {
+                        // Only the current thread can be run while debugging,
+                        // so the other threads' actions are irrelevant.
+                        // The first action applying to it wins.
+                        let mut do_step = false;
+                        for action in actions.split(';') {
+                            let (action, thread) = match action.split_once(':') {
+                                Some((action, id)) => (action, threads.parse_id(id)),
+                                None => (action, Some(threads.current)),
+                            };
+                            if thread != Some(threads.current) {
+                                if action.starts_with(['s', 'S']) {
+                                    echo!(
+                                        "Debugger requested stepping a thread other than the current thread ({}), which is unsupported.",
+                                        threads.current
+                                    );
+                                }
+                                continue;
+                            }
+                            // Signals are ignored, like for 'C' and 'S'.
+                            do_step = action.starts_with(['s', 'S']);
+                            break;
+                        }
+                        break do_step;
                     // Query for supported features
                     } else if p == "qSupported" || p.starts_with("qSupported:") {
                         // Tell GDB we can send it an XML target description.
@@ -3,16 +3,18 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
-//! Image decoding. Currently only supports PNG files (treated as 8-bit sRGB).
+//! Image decoding and encoding. Currently only supports PNG files (treated as
+//! 8-bit sRGB).
 //!
 //! Implemented as a wrapper around the C library stb_image, since it supports
 //! "CgBI" PNG files (an Apple proprietary extension used in iPhone OS apps).
+//! Encoding uses its sibling, stb_image_write.
 //!
 //! This module also exposes decompression for Imagination Technologies' PVRTC
 //! format, implementing as a wrapper around their decoder from the PowerVR
 //! SDK.
 
-use std::ffi::{c_int, c_uchar, CStr};
+use std::ffi::{c_int, c_uchar, c_void, CStr};
 
 use touchHLE_pvrt_decompress_wrapper::*;
 use touchHLE_stb_image_wrapper::*;
@@ -103,6 +105,35 @@ impl Drop for Image {
     }
 }
 
+/// Encode image data (8 bits per channel RGBA, rows in top-to-bottom order) as
+/// a PNG file.
+pub fn encode_png(pixels: &[u8], dimensions: (u32, u32)) -> Result<Vec<u8>, String> {
+    let (width, height) = dimensions;
+    assert!(pixels.len() == width as usize * height as usize * 4);
+
+    unsafe extern "C" fn write_func(context: *mut c_void, data: *mut c_void, size: c_int) {
+        let buffer = &mut *(context as *mut Vec<u8>);
+        buffer.extend_from_slice(std::slice::from_raw_parts(data as *const u8, size as usize));
+    }
+
+    let mut buffer: Vec<u8> = Vec::new();
+    let success = unsafe {
+        stbi_write_png_to_func(
+            write_func,
+            &mut buffer as *mut Vec<u8> as *mut c_void,
+            width.try_into().unwrap(),
+            height.try_into().unwrap(),
+            4,
+            pixels.as_ptr() as *const c_void,
+            (width * 4).try_into().unwrap(),
+        )
+    };
+    if success == 0 {
+        return Err("Could not encode PNG".to_string());
+    }
+    Ok(buffer)
+}
+
 /// Approximate implementation of sRGB gamma encoding.
 pub fn gamma_encode(intensity: f32) -> f32 {
     // TODO: This doesn't implement the linear section near zero.
@@ -30,6 +30,8 @@ mod log;
 mod abi;
 mod audio;
 mod bundle;
+mod clock;
+mod compat_report;
 mod cpu;
 mod dyld;
 mod environment;
@@ -44,7 +46,9 @@ mod mach_o;
 mod mem;
 mod objc;
 mod options;
+mod snapshot;
 mod stack;
+mod time_zone;
 mod window;
 
 // These are very frequently used and used to be in this module, so they are
@@ -90,6 +94,12 @@ Special options:
 
     --info
         Print basic information about the app bundle without running the app.
+
+    --compat-report
+        Print a report of which functions, constants, Objective-C classes and
+        selectors used by the app are implemented by touchHLE, without running
+        the app. This is JSON and is printed to standard output, whereas all
+        other output goes to standard error.
 ";
 
 fn app_picker(title: &str) -> Result<PathBuf, String> {
@@ -193,6 +203,7 @@ pub fn main<T: Iterator<Item = String>>(mut args: T) -> Result<(), String> {
 
     let mut bundle_path: Option<PathBuf> = None;
     let mut just_info = false;
+    let mut compat_report = false;
     let mut option_args = Vec::new();
 
     for arg in args {
@@ -205,6 +216,8 @@ pub fn main<T: Iterator<Item = String>>(mut args: T) -> Result<(), String> {
             return Ok(());
         } else if arg == "--info" {
             just_info = true;
+        } else if arg == "--compat-report" {
+            compat_report = true;
         // Parse an option but discard the value, to test whether it's valid.
         // We don't want to apply it immediately, because then options loaded
         // from a file would take precedence over options from the command line.
@@ -275,6 +288,10 @@ pub fn main<T: Iterator<Item = String>>(mut args: T) -> Result<(), String> {
         return Ok(());
     }
 
+    if compat_report {
+        return compat_report::print_report(&bundle, &fs);
+    }
+
     let mut options = options::Options::default();
 
     // Apply options from files
@@ -21,6 +21,7 @@ pub mod mach_time;
 pub mod math;
 pub mod posix_io;
 pub mod pthread;
+pub mod semaphore;
 pub mod setjmp;
 pub mod stdio;
 pub mod stdlib;
@@ -33,10 +34,50 @@ pub mod wchar;
 /// Container for state of various child modules
 #[derive(Default)]
 pub struct State {
+    cxxabi: cxxabi::State,
+    errno: errno::State,
     keymgr: keymgr::State,
     posix_io: posix_io::State,
     pthread: pthread::State,
+    semaphore: semaphore::State,
     stdlib: stdlib::State,
     string: string::State,
     time: time::State,
 }
+impl State {
+    /// Check that the app isn't using anything that save states don't support
+    /// yet (see [crate::snapshot]).
+    pub fn check_snapshot_supported(&self) -> Result<(), String> {
+        self.posix_io.check_no_open_files()
+    }
+
+    /// Make a copy of the state for a save state.
+    pub fn snapshot(&self) -> Result<State, String> {
+        self.check_snapshot_supported()?;
+        Ok(State {
+            cxxabi: self.cxxabi.clone(),
+            errno: self.errno.clone(),
+            keymgr: self.keymgr.clone(),
+            posix_io: Default::default(),
+            pthread: self.pthread.clone(),
+            semaphore: self.semaphore.clone(),
+            stdlib: self.stdlib.clone(),
+            string: self.string.clone(),
+            time: self.time.clone(),
+        })
+    }
+
+    /// Replace the state with a copy of one from a save state. This must only
+    /// be done if [Self::check_snapshot_supported] succeeds, since there can't
+    /// be any open files to keep.
+    pub fn restore(&mut self, saved: &State) {
+        self.cxxabi = saved.cxxabi.clone();
+        self.errno = saved.errno.clone();
+        self.keymgr = saved.keymgr.clone();
+        self.pthread = saved.pthread.clone();
+        self.semaphore = saved.semaphore.clone();
+        self.stdlib = saved.stdlib.clone();
+        self.string = saved.string.clone();
+        self.time = saved.time.clone();
+    }
+}
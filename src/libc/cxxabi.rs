@@ -8,33 +8,64 @@
 //! Resources:
 //! - [Itanium C++ ABI specification](https://itanium-cxx-abi.github.io/cxx-abi/abi.html#dso-dtor-runtime-api)
 
-use crate::abi::GuestFunction;
+use crate::abi::{CallFromHost, GuestFunction};
 use crate::dyld::{export_c_func, FunctionExports};
-use crate::mem::MutVoidPtr;
+use crate::mem::{MutVoidPtr, Ptr};
 use crate::Environment;
 
-fn __cxa_atexit(
-    _env: &mut Environment,
+#[derive(Default, Clone)]
+pub struct State {
+    /// Functions registered with `__cxa_atexit` (or C `atexit`), in order of
+    /// registration, with their argument and DSO handle.
+    atexit_funcs: Vec<(GuestFunction, MutVoidPtr, MutVoidPtr)>,
+}
+
+/// Also used for C `atexit`, since a function that takes no arguments can be
+/// called as if it takes one.
+pub fn __cxa_atexit(
+    env: &mut Environment,
     func: GuestFunction, // void (*func)(void *)
     p: MutVoidPtr,
     d: MutVoidPtr,
 ) -> i32 {
-    // TODO: when this is implemented, make sure it's properly compatible with
-    // C atexit.
-    log!(
-        "TODO: __cxa_atexit({:?}, {:?}, {:?}) (unimplemented)",
-        func,
-        p,
-        d
-    );
+    log_dbg!("__cxa_atexit({:?}, {:?}, {:?})", func, p, d);
+    env.libc_state.cxxabi.atexit_funcs.push((func, p, d));
     0 // success
 }
 
-fn __cxa_finalize(_env: &mut Environment, d: MutVoidPtr) {
-    log!("TODO: __cxa_finalize({:?}) (unimplemented)", d);
+/// Calls the functions registered for the DSO handle `d`, or all functions if
+/// it is null, in reverse order of registration. This is also used by `exit`.
+pub fn __cxa_finalize(env: &mut Environment, d: MutVoidPtr) {
+    // The functions might register more functions, so the list can't be
+    // iterated over directly.
+    loop {
+        let funcs = &mut env.libc_state.cxxabi.atexit_funcs;
+        let idx = funcs
+            .iter()
+            .rposition(|&(_, _, dso)| d.is_null() || dso == d);
+        let Some(idx) = idx else {
+            break;
+        };
+        let (func, p, dso) = funcs.remove(idx);
+        log_dbg!(
+            "__cxa_finalize: calling {:?}({:?}) for DSO {:?}",
+            func,
+            p,
+            dso
+        );
+        let () = func.call_from_host(env, (p,));
+    }
+}
+
+fn atexit(
+    env: &mut Environment,
+    func: GuestFunction, // void (*func)(void)
+) -> i32 {
+    __cxa_atexit(env, func, Ptr::null(), Ptr::null())
 }
 
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(__cxa_atexit(_, _, _)),
     export_c_func!(__cxa_finalize(_)),
+    export_c_func!(atexit(_)),
 ];
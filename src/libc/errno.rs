@@ -5,6 +5,43 @@
  */
 //! `errno.h`
 
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::mem::MutPtr;
+use crate::{Environment, ThreadID};
+use std::collections::HashMap;
+
 pub const EPERM: i32 = 1;
+pub const ESRCH: i32 = 3;
 pub const EDEADLK: i32 = 11;
+pub const EBUSY: i32 = 16;
 pub const EINVAL: i32 = 22;
+pub const EAGAIN: i32 = 35;
+pub const ETIMEDOUT: i32 = 60;
+
+#[derive(Default, Clone)]
+pub struct State {
+    /// Each thread has its own `errno`, allocated when first used.
+    locations: HashMap<ThreadID, MutPtr<i32>>,
+}
+
+/// Set the current thread's `errno`.
+pub fn set_errno(env: &mut Environment, value: i32) {
+    let location = __error(env);
+    env.mem.write(location, value);
+}
+
+/// `errno` is a macro that dereferences the result of this function.
+fn __error(env: &mut Environment) -> MutPtr<i32> {
+    let current_thread = env.current_thread;
+    if let Some(&location) = env.libc_state.errno.locations.get(&current_thread) {
+        return location;
+    }
+    let location = env.mem.alloc_and_write(0i32);
+    env.libc_state
+        .errno
+        .locations
+        .insert(current_thread, location);
+    location
+}
+
+pub const FUNCTIONS: FunctionExports = &[export_c_func!(__error())];
@@ -12,7 +12,7 @@ use crate::mem::{MutPtr, MutVoidPtr, Ptr};
 use crate::{Environment, ThreadID};
 use std::collections::hash_map::{Entry, HashMap};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     processwide_ptrs: HashMap<i32, (MutVoidPtr, Option<ThreadID>)>,
 }
@@ -25,6 +25,18 @@ impl State {
             .get_mut(fd_to_file_idx(fd))
             .and_then(|file_or_none| file_or_none.as_mut())
     }
+
+    /// Save states don't support open files yet (see [crate::snapshot]), so
+    /// this returns an error if there are any.
+    pub fn check_no_open_files(&self) -> Result<(), String> {
+        match self.files.iter().position(Option::is_some) {
+            Some(idx) => Err(format!(
+                "file descriptor {} is open, and open files aren't supported yet",
+                file_idx_to_fd(idx)
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 struct PosixFileHostObject {
@@ -28,13 +28,15 @@ macro_rules! check_magic {
     }
 }
 
+pub mod cond;
 pub mod key;
 pub mod mutex;
 pub mod once;
 pub mod thread;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
+    cond: cond::State,
     key: key::State,
     mutex: mutex::State,
     thread: thread::State,
@@ -0,0 +1,225 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Condition variables.
+
+use super::mutex::{pthread_mutex_unlock, MutexPtr};
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::environment::ThreadBlock;
+use crate::libc::errno::ETIMEDOUT;
+use crate::libc::time::timespec;
+use crate::mem::{ConstPtr, MutPtr, SafeRead};
+use crate::Environment;
+use std::collections::HashSet;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Default, Clone)]
+pub struct State {
+    conds: HashSet<CondPtr>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut Self {
+        &mut env.libc_state.pthread.cond
+    }
+}
+
+/// Apple's implementation is a 4-byte magic number followed by an 4-byte
+/// opaque region. We only have to match the size theirs has.
+#[repr(C, packed)]
+struct pthread_condattr_t {
+    /// Magic number (must be [MAGIC_CONDATTR])
+    magic: u32,
+    _unused: u32,
+}
+unsafe impl SafeRead for pthread_condattr_t {}
+
+/// Apple's implementation is a 4-byte magic number followed by a 24-byte
+/// opaque region. There's no data we need to store other than which threads
+/// are waiting, which is tracked by [Environment] itself.
+#[repr(C, packed)]
+pub struct pthread_cond_t {
+    /// Magic number (must be [MAGIC_COND])
+    magic: u32,
+}
+unsafe impl SafeRead for pthread_cond_t {}
+
+pub type CondPtr = MutPtr<pthread_cond_t>;
+
+/// Arbitrarily-chosen magic number for `pthread_condattr_t` (not Apple's).
+const MAGIC_CONDATTR: u32 = u32::from_be_bytes(*b"CoAt");
+/// Arbitrarily-chosen magic number for `pthread_cond_t` (not Apple's).
+const MAGIC_COND: u32 = u32::from_be_bytes(*b"COND");
+/// Magic number used by `PTHREAD_COND_INITIALIZER`. This is part of the ABI!
+const MAGIC_COND_STATIC: u32 = 0x3CB0B1BB;
+
+fn pthread_condattr_init(env: &mut Environment, attr: MutPtr<pthread_condattr_t>) -> i32 {
+    env.mem.write(
+        attr,
+        pthread_condattr_t {
+            magic: MAGIC_CONDATTR,
+            _unused: 0,
+        },
+    );
+    0 // success
+}
+fn pthread_condattr_destroy(env: &mut Environment, attr: MutPtr<pthread_condattr_t>) -> i32 {
+    check_magic!(env, attr, MAGIC_CONDATTR);
+    env.mem.write(
+        attr,
+        pthread_condattr_t {
+            magic: 0,
+            _unused: 0,
+        },
+    );
+    0 // success
+}
+
+fn pthread_cond_init(
+    env: &mut Environment,
+    cond: CondPtr,
+    attr: ConstPtr<pthread_condattr_t>,
+) -> i32 {
+    if !attr.is_null() {
+        check_magic!(env, attr, MAGIC_CONDATTR);
+    }
+    env.mem.write(cond, pthread_cond_t { magic: MAGIC_COND });
+
+    assert!(State::get(env).conds.insert(cond));
+
+    0 // success
+}
+
+fn check_or_register_cond(env: &mut Environment, cond: CondPtr) {
+    let magic: u32 = env.mem.read(cond.cast());
+    // This is a statically-initialized condition variable, we need to register
+    // it, and change the magic number in the process.
+    if magic == MAGIC_COND_STATIC {
+        log_dbg!(
+            "Detected statically-initialized condition variable at {:?}, registering.",
+            cond
+        );
+        pthread_cond_init(env, cond, ConstPtr::null());
+    } else {
+        // We should actually return an error if the magic number doesn't match,
+        // but this almost certainly indicates a memory corruption, so panicking
+        // is more useful.
+        assert_eq!(magic, MAGIC_COND);
+    }
+}
+
+fn wait_inner(
+    env: &mut Environment,
+    cond: CondPtr,
+    mutex: MutexPtr,
+    timeout: Option<Instant>,
+) -> i32 {
+    check_or_register_cond(env, cond);
+
+    let res = pthread_mutex_unlock(env, mutex);
+    assert!(res == 0); // should be EPERM
+
+    log_dbg!(
+        "Thread {} is waiting on condition variable {:?} with mutex {:?}.",
+        env.current_thread,
+        cond,
+        mutex
+    );
+    env.block(
+        ThreadBlock::Condition(cond, mutex),
+        timeout.map(|timeout| (timeout, ETIMEDOUT as u32)),
+    );
+    0 // success (once the thread is unblocked)
+}
+
+fn pthread_cond_wait(env: &mut Environment, cond: CondPtr, mutex: MutexPtr) -> i32 {
+    wait_inner(env, cond, mutex, None)
+}
+
+fn pthread_cond_timedwait(
+    env: &mut Environment,
+    cond: CondPtr,
+    mutex: MutexPtr,
+    abstime: ConstPtr<timespec>,
+) -> i32 {
+    let timespec { tv_sec, tv_nsec } = env.mem.read(abstime);
+    assert!(tv_sec >= 0 && (0..1_000_000_000).contains(&tv_nsec)); // should be EINVAL
+    let abstime = Duration::new(tv_sec as u64, tv_nsec as u32);
+
+    // The timeout is an absolute time on the system clock, but the scheduler
+    // works with the monotonic clock.
+    let now = env
+        .clock
+        .system_time()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    let timeout = env.clock.now() + abstime.saturating_sub(now);
+
+    wait_inner(env, cond, mutex, Some(timeout))
+}
+
+/// Wake up one or all of the threads waiting on a condition variable. They
+/// will then be blocked on the mutex instead.
+fn wake(env: &mut Environment, cond: CondPtr, all: bool) {
+    check_or_register_cond(env, cond);
+
+    // Wake threads in order of ID, starting with the one after the current
+    // thread, for consistency with the scheduler.
+    let thread_count = env.threads.len();
+    for i in 0..thread_count {
+        let i = (env.current_thread + 1 + i) % thread_count;
+        let thread = &mut env.threads[i];
+        let Some(ThreadBlock::Condition(waiting_cond, mutex)) = thread.blocked_by else {
+            continue;
+        };
+        if waiting_cond != cond {
+            continue;
+        }
+
+        log_dbg!(
+            "Waking thread {} waiting on condition variable {:?}.",
+            i,
+            cond
+        );
+        thread.blocked_by = Some(ThreadBlock::Mutex(mutex));
+        thread.blocked_until = None;
+        if !all {
+            break;
+        }
+    }
+}
+
+fn pthread_cond_signal(env: &mut Environment, cond: CondPtr) -> i32 {
+    wake(env, cond, false);
+    0 // success
+}
+
+fn pthread_cond_broadcast(env: &mut Environment, cond: CondPtr) -> i32 {
+    wake(env, cond, true);
+    0 // success
+}
+
+fn pthread_cond_destroy(env: &mut Environment, cond: CondPtr) -> i32 {
+    check_or_register_cond(env, cond);
+
+    let has_waiters = env.threads.iter().any(|thread| {
+        matches!(thread.blocked_by, Some(ThreadBlock::Condition(waiting_cond, _)) if waiting_cond == cond)
+    });
+    assert!(!has_waiters); // should be EBUSY
+
+    State::get(env).conds.remove(&cond);
+    env.mem.write(cond, pthread_cond_t { magic: 0 });
+    0 // success
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(pthread_condattr_init(_)),
+    export_c_func!(pthread_condattr_destroy(_)),
+    export_c_func!(pthread_cond_init(_, _)),
+    export_c_func!(pthread_cond_wait(_, _)),
+    export_c_func!(pthread_cond_timedwait(_, _, _)),
+    export_c_func!(pthread_cond_signal(_)),
+    export_c_func!(pthread_cond_broadcast(_)),
+    export_c_func!(pthread_cond_destroy(_)),
+];
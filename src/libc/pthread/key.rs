@@ -11,7 +11,7 @@ use crate::mem::{ConstVoidPtr, MutPtr, MutVoidPtr, Ptr};
 use crate::{Environment, ThreadID};
 use std::collections::HashMap;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     /// The `pthread_key_t` value, with 1 subtracted, is the index into this
     /// vector. The tuple contains the map of thread-specific data pointers plus
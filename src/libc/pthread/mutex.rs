@@ -6,15 +6,16 @@
 //! Mutexes.
 
 use crate::dyld::{export_c_func, FunctionExports};
-use crate::libc::errno::{EDEADLK, EPERM};
+use crate::environment::ThreadBlock;
+use crate::libc::errno::{EBUSY, EDEADLK, EPERM};
 use crate::mem::{ConstPtr, MutPtr, Ptr, SafeRead};
 use crate::{Environment, ThreadID};
 use std::collections::HashMap;
 use std::num::NonZeroU32;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
-    mutexes: HashMap<MutPtr<pthread_mutex_t>, MutexHostObject>,
+    mutexes: HashMap<MutexPtr, MutexHostObject>,
 }
 impl State {
     fn get(env: &mut Environment) -> &mut Self {
@@ -37,12 +38,15 @@ unsafe impl SafeRead for pthread_mutexattr_t {}
 /// Apple's implementation is a 4-byte magic number followed by a 56-byte opaque
 /// region. We will store the actual data on the host instead.
 #[repr(C, packed)]
-struct pthread_mutex_t {
+pub struct pthread_mutex_t {
     /// Magic number (must be [MAGIC_MUTEX])
     magic: u32,
 }
 unsafe impl SafeRead for pthread_mutex_t {}
 
+pub type MutexPtr = MutPtr<pthread_mutex_t>;
+
+#[derive(Clone)]
 struct MutexHostObject {
     type_: MutexType,
     /// The `NonZeroU32` is the number of locks on this thread (if it's a
@@ -191,16 +195,54 @@ fn pthread_mutex_lock(env: &mut Environment, mutex: MutPtr<pthread_mutex_t>) ->
         }
     }
 
-    // TODO: wait for unlock on other thread
-    unimplemented!(
-        "Attempted to lock mutex {:?} for thread {}, already locked by thread {}",
+    log_dbg!(
+        "Attempted to lock mutex {:?} for thread {}, already locked by thread {}. Blocking until it is unlocked.",
         mutex,
         current_thread,
         locking_thread,
-    )
+    );
+    env.block(ThreadBlock::Mutex(mutex), None);
+    0 // success (once the thread is unblocked)
 }
 
-fn pthread_mutex_unlock(env: &mut Environment, mutex: MutPtr<pthread_mutex_t>) -> i32 {
+fn pthread_mutex_trylock(env: &mut Environment, mutex: MutPtr<pthread_mutex_t>) -> i32 {
+    check_or_register_mutex(env, mutex);
+
+    let current_thread = env.current_thread;
+    let host_object = State::get(env).mutexes.get(&mutex).unwrap();
+    match host_object.locked {
+        Some((locking_thread, _))
+            if locking_thread != current_thread || host_object.type_ != PTHREAD_MUTEX_RECURSIVE =>
+        {
+            log_dbg!(
+                "Attempted to lock mutex {:?} for thread {}, already locked by thread {}. Returning EBUSY.",
+                mutex,
+                current_thread,
+                locking_thread,
+            );
+            EBUSY
+        }
+        _ => pthread_mutex_lock(env, mutex),
+    }
+}
+
+/// For use by [Environment::try_unblock]: lock the mutex on behalf of a thread
+/// that is blocked waiting for it, if it is now unlocked.
+pub fn try_lock_for_blocked_thread(
+    env: &mut Environment,
+    mutex: MutexPtr,
+    thread: ThreadID,
+) -> bool {
+    let host_object: &mut _ = State::get(env).mutexes.get_mut(&mutex).unwrap();
+    if host_object.locked.is_some() {
+        return false;
+    }
+    log_dbg!("Locked mutex {:?} for thread {}.", mutex, thread);
+    host_object.locked = Some((thread, NonZeroU32::new(1).unwrap()));
+    true
+}
+
+pub fn pthread_mutex_unlock(env: &mut Environment, mutex: MutPtr<pthread_mutex_t>) -> i32 {
     check_or_register_mutex(env, mutex);
 
     let current_thread = env.current_thread;
@@ -264,11 +306,22 @@ fn pthread_mutex_unlock(env: &mut Environment, mutex: MutPtr<pthread_mutex_t>) -
     0 // success
 }
 
+fn pthread_mutex_destroy(env: &mut Environment, mutex: MutPtr<pthread_mutex_t>) -> i32 {
+    check_or_register_mutex(env, mutex);
+
+    let host_object = State::get(env).mutexes.remove(&mutex).unwrap();
+    assert!(host_object.locked.is_none()); // should be EBUSY
+    env.mem.write(mutex, pthread_mutex_t { magic: 0 });
+    0 // success
+}
+
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(pthread_mutexattr_init(_)),
     export_c_func!(pthread_mutexattr_settype(_, _)),
     export_c_func!(pthread_mutexattr_destroy(_)),
     export_c_func!(pthread_mutex_init(_, _)),
     export_c_func!(pthread_mutex_lock(_)),
+    export_c_func!(pthread_mutex_trylock(_)),
     export_c_func!(pthread_mutex_unlock(_)),
+    export_c_func!(pthread_mutex_destroy(_)),
 ];
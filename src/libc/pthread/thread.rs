@@ -7,11 +7,13 @@
 
 use crate::abi::GuestFunction;
 use crate::dyld::{export_c_func, FunctionExports};
+use crate::environment::ThreadBlock;
+use crate::libc::errno::{EDEADLK, ESRCH};
 use crate::mem::{ConstPtr, MutPtr, MutVoidPtr, SafeRead};
 use crate::{Environment, ThreadID};
 use std::collections::HashMap;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     threads: HashMap<pthread_t, ThreadHostObject>,
     main_thread_object_created: bool,
@@ -51,9 +53,10 @@ unsafe impl SafeRead for OpaqueThread {}
 
 type pthread_t = MutPtr<OpaqueThread>;
 
+#[derive(Clone)]
 struct ThreadHostObject {
     thread_id: ThreadID,
-    _attr: pthread_attr_t,
+    attr: pthread_attr_t,
 }
 
 /// Arbitrarily-chosen magic number for `pthread_attr_t` (not Apple's).
@@ -117,13 +120,9 @@ fn pthread_create(
     env.mem.write(thread, opaque);
 
     assert!(!State::get(env).threads.contains_key(&opaque));
-    State::get(env).threads.insert(
-        opaque,
-        ThreadHostObject {
-            thread_id,
-            _attr: attr,
-        },
-    );
+    State::get(env)
+        .threads
+        .insert(opaque, ThreadHostObject { thread_id, attr });
 
     log_dbg!("pthread_create({:?}, {:?}, {:?}, {:?}) => 0 (success), created new pthread_t {:?} (thread ID: {})", thread, attr, start_routine, user_data, opaque, thread_id);
 
@@ -147,7 +146,7 @@ fn pthread_self(env: &mut Environment) -> pthread_t {
             opaque,
             ThreadHostObject {
                 thread_id: 0,
-                _attr: DEFAULT_ATTR,
+                attr: DEFAULT_ATTR,
             },
         );
         log_dbg!(
@@ -164,6 +163,36 @@ fn pthread_self(env: &mut Environment) -> pthread_t {
     ptr
 }
 
+fn pthread_join(env: &mut Environment, thread: pthread_t, value_ptr: MutPtr<MutVoidPtr>) -> i32 {
+    let Some(host_object) = State::get(env).threads.get(&thread) else {
+        log!(
+            "Warning: pthread_join() called with unknown thread {:?}, returning ESRCH.",
+            thread
+        );
+        return ESRCH;
+    };
+    let thread_id = host_object.thread_id;
+    let detachstate = host_object.attr.detachstate;
+    assert!(detachstate == PTHREAD_CREATE_JOINABLE); // should be EINVAL
+
+    if thread_id == env.current_thread {
+        log!(
+            "Warning: thread {} tried to join itself, returning EDEADLK.",
+            thread_id
+        );
+        return EDEADLK;
+    }
+
+    log_dbg!(
+        "Thread {} is waiting for thread {} ({:?}) to finish.",
+        env.current_thread,
+        thread_id,
+        thread
+    );
+    env.block(ThreadBlock::Join(thread_id, value_ptr), None);
+    0 // success (once the thread is unblocked)
+}
+
 fn pthread_setcanceltype(_env: &mut Environment, _type: i32, _oldtype: MutPtr<i32>) -> i32 {
     // TODO
     0
@@ -183,6 +212,7 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(pthread_attr_setdetachstate(_, _)),
     export_c_func!(pthread_attr_destroy(_)),
     export_c_func!(pthread_create(_, _, _, _)),
+    export_c_func!(pthread_join(_, _)),
     export_c_func!(pthread_self()),
     export_c_func!(pthread_setcanceltype(_, _)),
     export_c_func!(pthread_mach_thread_np(_)),
@@ -0,0 +1,318 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Semaphores: both POSIX named semaphores (`semaphore.h`) and Mach semaphores
+//! (`mach/semaphore.h`), which share an implementation.
+//!
+//! Unnamed POSIX semaphores (`sem_init`) aren't supported by iPhone OS, so apps
+//! shouldn't be using them.
+
+#![allow(non_camel_case_types)]
+
+use crate::abi::DotDotDot;
+use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
+use crate::environment::ThreadBlock;
+use crate::libc::errno::{set_errno, EAGAIN, EINVAL};
+use crate::libc::posix_io::{O_CREAT, O_EXCL};
+use crate::mem::{ConstPtr, ConstVoidPtr, Mem, MutPtr, Ptr, SafeRead};
+use crate::Environment;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Identifier for a semaphore host object. For Mach semaphores, this is
+/// directly used as the `semaphore_t` value.
+pub type SemaphoreID = u32;
+
+#[derive(Default, Clone)]
+pub struct State {
+    semaphores: HashMap<SemaphoreID, SemaphoreHostObject>,
+    /// Used to allocate the next [SemaphoreID]. Zero is never used, since that
+    /// would be `MACH_PORT_NULL`.
+    last_id: SemaphoreID,
+    /// POSIX named semaphores by name. These stay open until unlinked.
+    named: HashMap<Vec<u8>, MutPtr<sem_t>>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut Self {
+        &mut env.libc_state.semaphore
+    }
+
+    fn create(&mut self, value: i32) -> SemaphoreID {
+        self.last_id = self.last_id.checked_add(1).unwrap();
+        let id = self.last_id;
+        self.semaphores.insert(id, SemaphoreHostObject { value });
+        id
+    }
+}
+
+#[derive(Clone)]
+struct SemaphoreHostObject {
+    /// The semaphore's count. This is never negative: threads waiting on the
+    /// semaphore are tracked by [Environment] instead.
+    value: i32,
+}
+
+/// Decrement the semaphore if its count is positive. Returns [Some(false)] if
+/// the count is zero and the thread would have to wait, or [None] if there's
+/// no such semaphore. This is also used by [Environment::try_unblock] for
+/// threads that are waiting.
+pub fn try_decrement(env: &mut Environment, semaphore: SemaphoreID) -> Option<bool> {
+    let host_object = State::get(env).semaphores.get_mut(&semaphore)?;
+    if host_object.value > 0 {
+        host_object.value -= 1;
+        Some(true)
+    } else {
+        Some(false)
+    }
+}
+
+/// Increment the semaphore. Returns [None] if there's no such semaphore.
+fn increment(env: &mut Environment, semaphore: SemaphoreID) -> Option<()> {
+    let host_object = State::get(env).semaphores.get_mut(&semaphore)?;
+    host_object.value = host_object.value.checked_add(1).unwrap();
+    Some(())
+}
+
+fn count_waiting_threads(env: &Environment, semaphore: SemaphoreID) -> usize {
+    env.threads
+        .iter()
+        .filter(|thread| thread.blocked_by == Some(ThreadBlock::Semaphore(semaphore)))
+        .count()
+}
+
+// POSIX semaphores (semaphore.h)
+
+/// On Apple platforms this is just an `int`. We store a [SemaphoreID] in it.
+type sem_t = SemaphoreID;
+
+/// `SEM_FAILED` is `(sem_t *)-1`.
+fn sem_failed() -> MutPtr<sem_t> {
+    Ptr::from_bits(u32::MAX)
+}
+
+fn sem_open(
+    env: &mut Environment,
+    name: ConstPtr<u8>,
+    oflag: i32,
+    args: DotDotDot,
+) -> MutPtr<sem_t> {
+    let name_bytes = env.mem.cstr_at(name).to_vec();
+
+    if let Some(&existing) = State::get(env).named.get(&name_bytes) {
+        if (oflag & O_CREAT) != 0 && (oflag & O_EXCL) != 0 {
+            log!(
+                "Warning: sem_open({:?}) failed, semaphore already exists",
+                std::str::from_utf8(&name_bytes)
+            );
+            // TODO: set errno
+            return sem_failed();
+        }
+        log_dbg!(
+            "sem_open({:?}) => {:?} (existing)",
+            std::str::from_utf8(&name_bytes),
+            existing
+        );
+        return existing;
+    }
+
+    if (oflag & O_CREAT) == 0 {
+        log!(
+            "Warning: sem_open({:?}) failed, semaphore doesn't exist",
+            std::str::from_utf8(&name_bytes)
+        );
+        // TODO: set errno
+        return sem_failed();
+    }
+
+    let mut args = args.start();
+    let _mode: u32 = args.next(env);
+    let value: u32 = args.next(env);
+
+    let id = State::get(env).create(value.try_into().unwrap());
+    let sem = env.mem.alloc_and_write(id);
+    State::get(env).named.insert(name_bytes.clone(), sem);
+    log_dbg!(
+        "sem_open({:?}) => {:?} (new, ID {}, value {})",
+        std::str::from_utf8(&name_bytes),
+        sem,
+        id,
+        value
+    );
+    sem
+}
+
+fn sem_close(env: &mut Environment, sem: MutPtr<sem_t>) -> i32 {
+    // The semaphore stays around until it's unlinked, so there's nothing to do
+    // here other than checking validity.
+    let id = env.mem.read(sem);
+    if !State::get(env).semaphores.contains_key(&id) {
+        set_errno(env, EINVAL);
+        return -1;
+    }
+    0 // success
+}
+
+fn sem_unlink(env: &mut Environment, name: ConstPtr<u8>) -> i32 {
+    let name_bytes = env.mem.cstr_at(name).to_vec();
+    // Existing references to the semaphore remain valid, so it isn't freed.
+    if State::get(env).named.remove(&name_bytes).is_some() {
+        0 // success
+    } else {
+        // TODO: set errno
+        -1
+    }
+}
+
+fn sem_wait(env: &mut Environment, sem: MutPtr<sem_t>) -> i32 {
+    let id = env.mem.read(sem);
+    let Some(decremented) = try_decrement(env, id) else {
+        set_errno(env, EINVAL);
+        return -1;
+    };
+    if !decremented {
+        env.block(ThreadBlock::Semaphore(id), None);
+    }
+    0 // success (possibly once the thread is unblocked)
+}
+
+fn sem_trywait(env: &mut Environment, sem: MutPtr<sem_t>) -> i32 {
+    let id = env.mem.read(sem);
+    let Some(decremented) = try_decrement(env, id) else {
+        set_errno(env, EINVAL);
+        return -1;
+    };
+    if decremented {
+        0 // success
+    } else {
+        set_errno(env, EAGAIN);
+        -1
+    }
+}
+
+fn sem_post(env: &mut Environment, sem: MutPtr<sem_t>) -> i32 {
+    let id = env.mem.read(sem);
+    if increment(env, id).is_none() {
+        set_errno(env, EINVAL);
+        return -1;
+    }
+    0 // success
+}
+
+// Mach semaphores (mach/semaphore.h, mach/task.h)
+
+type kern_return_t = i32;
+const KERN_SUCCESS: kern_return_t = 0;
+const KERN_INVALID_ARGUMENT: kern_return_t = 4;
+const KERN_OPERATION_TIMED_OUT: kern_return_t = 49;
+
+type mach_port_t = u32;
+type task_t = mach_port_t;
+type semaphore_t = mach_port_t;
+
+/// Arbitrarily-chosen value for `mach_task_self()`.
+const TASK_SELF: task_t = 0x103;
+
+fn get_mach_task_self(mem: &mut Mem) -> ConstVoidPtr {
+    mem.alloc_and_write(TASK_SELF).cast().cast_const()
+}
+
+fn semaphore_create(
+    env: &mut Environment,
+    task: task_t,
+    semaphore: MutPtr<semaphore_t>,
+    _policy: i32,
+    value: i32,
+) -> kern_return_t {
+    assert!(task == TASK_SELF);
+    if value < 0 {
+        return KERN_INVALID_ARGUMENT;
+    }
+    let id = State::get(env).create(value);
+    env.mem.write(semaphore, id);
+    log_dbg!("semaphore_create() => {} (value {})", id, value);
+    KERN_SUCCESS
+}
+
+fn semaphore_destroy(env: &mut Environment, task: task_t, semaphore: semaphore_t) -> kern_return_t {
+    assert!(task == TASK_SELF);
+    // Waiting threads should be woken up with KERN_TERMINATED, but an app
+    // that relies on that is probably unusual.
+    assert!(count_waiting_threads(env, semaphore) == 0);
+    if State::get(env).semaphores.remove(&semaphore).is_some() {
+        KERN_SUCCESS
+    } else {
+        KERN_INVALID_ARGUMENT
+    }
+}
+
+fn semaphore_signal(env: &mut Environment, semaphore: semaphore_t) -> kern_return_t {
+    match increment(env, semaphore) {
+        Some(()) => KERN_SUCCESS,
+        None => KERN_INVALID_ARGUMENT,
+    }
+}
+
+fn semaphore_signal_all(env: &mut Environment, semaphore: semaphore_t) -> kern_return_t {
+    if !State::get(env).semaphores.contains_key(&semaphore) {
+        return KERN_INVALID_ARGUMENT;
+    }
+    // Waiting threads decrement the count when they are unblocked, so
+    // incrementing it once for each of them wakes them all.
+    for _ in 0..count_waiting_threads(env, semaphore) {
+        increment(env, semaphore);
+    }
+    KERN_SUCCESS
+}
+
+fn semaphore_wait(env: &mut Environment, semaphore: semaphore_t) -> kern_return_t {
+    match try_decrement(env, semaphore) {
+        Some(true) => (),
+        Some(false) => env.block(ThreadBlock::Semaphore(semaphore), None),
+        None => return KERN_INVALID_ARGUMENT,
+    }
+    KERN_SUCCESS // (possibly once the thread is unblocked)
+}
+
+/// The timeout is a `mach_timespec_t` passed by value, which is equivalent to
+/// passing its two fields as separate arguments.
+fn semaphore_timedwait(
+    env: &mut Environment,
+    semaphore: semaphore_t,
+    tv_sec: u32,
+    tv_nsec: i32,
+) -> kern_return_t {
+    match try_decrement(env, semaphore) {
+        Some(true) => return KERN_SUCCESS,
+        Some(false) => (),
+        None => return KERN_INVALID_ARGUMENT,
+    }
+    let Ok(tv_nsec) = u32::try_from(tv_nsec) else {
+        return KERN_INVALID_ARGUMENT;
+    };
+    let timeout = env.clock.now() + Duration::new(tv_sec.into(), tv_nsec);
+    env.block(
+        ThreadBlock::Semaphore(semaphore),
+        Some((timeout, KERN_OPERATION_TIMED_OUT as u32)),
+    );
+    KERN_SUCCESS // (unless the timeout is reached)
+}
+
+pub const CONSTANTS: ConstantExports =
+    &[("_mach_task_self_", HostConstant::Custom(get_mach_task_self))];
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(sem_open(_, _, _)),
+    export_c_func!(sem_close(_)),
+    export_c_func!(sem_unlink(_)),
+    export_c_func!(sem_wait(_)),
+    export_c_func!(sem_trywait(_)),
+    export_c_func!(sem_post(_)),
+    export_c_func!(semaphore_create(_, _, _, _)),
+    export_c_func!(semaphore_destroy(_, _)),
+    export_c_func!(semaphore_signal(_)),
+    export_c_func!(semaphore_signal_all(_)),
+    export_c_func!(semaphore_wait(_)),
+    export_c_func!(semaphore_timedwait(_, _, _)),
+];
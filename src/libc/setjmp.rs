@@ -5,25 +5,106 @@
  */
 //! `setjmp.h`.
 //!
-//! We don't have a real implementation for this right now. It could be quite
-//! tricky to write one, considering that we would need to unwind through host
-//! code, and somehow do so selectively since we have a mix of stack frames from
-//! different guest threads. For the moment, we simply pray the app never throws
-//! exceptions.
+//! `setjmp` saves the callee-saved registers, the stack pointer and the return
+//! address, and `longjmp` restores them, so that the `bx lr` in the host
+//! function stub returns to the caller of `setjmp` a second time.
+//!
+//! If there are host functions (e.g. an Objective-C message dispatch) between
+//! the `setjmp` and `longjmp` calls, the jump leaves them behind on the host
+//! stack. [crate::Environment] detects this the next time the guest calls a
+//! host function, and unwinds the abandoned host frames then. The same
+//! mechanism handles C++ exceptions: the bundled libgcc_s uses SjLj
+//! exceptions, which use `__builtin_setjmp`/`__builtin_longjmp` and therefore
+//! don't call these functions at all, but can still jump over host frames.
 //!
 //! Note that `setjmp` and `longjmp` are defined as macros in the C standard,
 //! but it seems like the implementation of these on iPhone OS uses real
-//! functions, at least for the former.
+//! functions.
+//!
+//! TODO: save and restore the callee-saved VFP registers (d8-d15) too.
+
+#![allow(non_camel_case_types)]
 
+use crate::cpu::Cpu;
 use crate::dyld::{export_c_func, FunctionExports};
+use crate::mem::MutPtr;
 use crate::Environment;
 
-/// The signature of this is incomplete because it's a stub (see module docs).
-fn setjmp(env: &mut Environment) {
+/// `jmp_buf` is an array of 27 `int`s on iPhone OS, and `sigjmp_buf` has one
+/// more. We only need the first few.
+pub type jmp_buf = MutPtr<u32>;
+
+/// Registers that are saved in the `jmp_buf`, in order: r4-r11, SP, LR.
+const SAVED_REGS: [usize; 10] = [4, 5, 6, 7, 8, 9, 10, 11, Cpu::SP, Cpu::LR];
+
+fn setjmp(env: &mut Environment, buf: jmp_buf) -> i32 {
+    let regs = env.cpu.regs();
+    for (i, &reg) in SAVED_REGS.iter().enumerate() {
+        env.mem.write(buf + i.try_into().unwrap(), regs[reg]);
+    }
     log_dbg!(
-        "TODO: setjmp() at {:#x}",
-        env.cpu.regs()[crate::cpu::Cpu::LR]
+        "setjmp({:?}) at {}, SP {:#x}",
+        buf,
+        env.symbolicate(regs[Cpu::LR]),
+        regs[Cpu::SP]
     );
+    0 // direct return
+}
+
+/// This doesn't really return: the registers are replaced so that the host
+/// function stub returns from `setjmp` instead, and the return value is what
+/// `setjmp` appears to return.
+fn longjmp(env: &mut Environment, buf: jmp_buf, val: i32) -> i32 {
+    for (i, &reg) in SAVED_REGS.iter().enumerate() {
+        let value = env.mem.read(buf + i.try_into().unwrap());
+        env.cpu.regs_mut()[reg] = value;
+    }
+    log_dbg!(
+        "longjmp({:?}, {}) to {}, SP {:#x}",
+        buf,
+        val,
+        env.symbolicate(env.cpu.regs()[Cpu::LR]),
+        env.cpu.regs()[Cpu::SP]
+    );
+    // longjmp() can't make setjmp() return 0.
+    if val == 0 {
+        1
+    } else {
+        val
+    }
+}
+
+/// For use by host code: do a `longjmp` from within a host function, which is
+/// then abandoned (see [Environment::abandon_host_function]).
+pub fn longjmp_from_host(env: &mut Environment, buf: jmp_buf, val: i32) -> ! {
+    let val = longjmp(env, buf, val);
+    env.cpu.regs_mut()[0] = val as u32;
+    env.abandon_host_function()
+}
+
+/// We don't have signals, so there's no signal mask to save.
+fn sigsetjmp(env: &mut Environment, buf: jmp_buf, _savemask: i32) -> i32 {
+    setjmp(env, buf)
+}
+
+fn siglongjmp(env: &mut Environment, buf: jmp_buf, val: i32) -> i32 {
+    longjmp(env, buf, val)
+}
+
+/// Variant of [setjmp] that doesn't save the signal mask.
+fn _setjmp(env: &mut Environment, buf: jmp_buf) -> i32 {
+    setjmp(env, buf)
+}
+
+fn _longjmp(env: &mut Environment, buf: jmp_buf, val: i32) -> i32 {
+    longjmp(env, buf, val)
 }
 
-pub const FUNCTIONS: FunctionExports = &[export_c_func!(setjmp())];
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(setjmp(_)),
+    export_c_func!(longjmp(_, _)),
+    export_c_func!(sigsetjmp(_, _)),
+    export_c_func!(siglongjmp(_, _)),
+    export_c_func!(_setjmp(_)),
+    export_c_func!(_longjmp(_, _)),
+];
@@ -13,7 +13,7 @@ use std::collections::HashMap;
 
 pub mod qsort;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     rand: u32,
     random: u32,
@@ -36,16 +36,6 @@ fn free(env: &mut Environment, ptr: MutVoidPtr) {
     env.mem.free(ptr);
 }
 
-fn atexit(
-    _env: &mut Environment,
-    func: GuestFunction, // void (*func)(void)
-) -> i32 {
-    // TODO: when this is implemented, make sure it's properly compatible with
-    // __cxa_atexit.
-    log!("TODO: atexit({:?}) (unimplemented)", func);
-    0 // success
-}
-
 fn skip_whitespace(env: &mut Environment, s: ConstPtr<u8>) -> ConstPtr<u8> {
     let mut start = s;
     loop {
@@ -184,11 +174,19 @@ fn setenv(env: &mut Environment, name: ConstPtr<u8>, value: ConstPtr<u8>, overwr
     0 // success
 }
 
-fn exit(_env: &mut Environment, exit_code: i32) {
+fn exit(env: &mut Environment, exit_code: i32) {
     echo!("App called exit(), exiting.");
+    crate::libc::cxxabi::__cxa_finalize(env, Ptr::null());
+    crate::frameworks::foundation::ns_user_defaults::synchronize_standard_defaults(env);
     std::process::exit(exit_code);
 }
 
+fn abort(_env: &mut Environment) {
+    // TODO: SIGABRT would be more useful for debugging.
+    echo!("App called abort(), exiting.");
+    std::process::exit(1);
+}
+
 fn bsearch(
     env: &mut Environment,
     key: ConstVoidPtr,
@@ -229,7 +227,6 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(malloc(_)),
     export_c_func!(calloc(_, _)),
     export_c_func!(free(_)),
-    export_c_func!(atexit(_)),
     export_c_func!(atoi(_)),
     export_c_func!(atof(_)),
     export_c_func!(srand(_)),
@@ -239,5 +236,6 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(getenv(_)),
     export_c_func!(setenv(_, _, _)),
     export_c_func!(exit(_)),
+    export_c_func!(abort()),
     export_c_func!(bsearch(_, _, _, _, _)),
 ];
@@ -11,7 +11,7 @@ use crate::Environment;
 
 use super::generic_char::GenericChar;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     strtok: Option<MutPtr<u8>>,
 }
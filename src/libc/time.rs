@@ -6,16 +6,53 @@
 //! `time.h` (C) and `sys/time.h` (POSIX)
 
 use crate::dyld::{export_c_func, FunctionExports};
-use crate::mem::{guest_size_of, ConstPtr, MutPtr, MutVoidPtr, Ptr, SafeRead};
+use crate::mem::{guest_size_of, ConstPtr, MutPtr, MutVoidPtr, SafeRead};
+use crate::time_zone::{seconds_from_civil, TimeZone};
 use crate::Environment;
+use std::collections::HashMap;
 use std::time::SystemTime;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct State {
     y2k38_warned: bool,
     /// Temporary static storage for the return value of `gmtime` or
     /// `localtime`. The standard allows calls to either to overwrite it.
     gmtime_tmp: Option<MutPtr<tm>>,
+    /// Loaded on first use, see [local_time_zone].
+    local_time_zone: Option<TimeZone>,
+    /// Guest strings for `tm_zone`, which has to stay valid indefinitely.
+    zone_abbreviations: HashMap<String, ConstPtr<u8>>,
+}
+
+/// Get the time zone the app sees as its local time zone: the one chosen with
+/// `--time-zone=`, or the host's.
+pub fn local_time_zone(env: &mut Environment) -> &TimeZone {
+    let state = &mut env.libc_state.time;
+    state.local_time_zone.get_or_insert_with(|| {
+        let time_zone = match env.options.time_zone {
+            // The name was already checked when parsing the option.
+            Some(ref name) => TimeZone::load(name).unwrap(),
+            None => TimeZone::host(),
+        };
+        log_dbg!("Local time zone: {}", time_zone.name());
+        time_zone
+    })
+}
+
+/// Get a guest string for a time zone abbreviation, for use in `tm_zone`.
+fn zone_abbreviation(env: &mut Environment, abbreviation: &str) -> ConstPtr<u8> {
+    if let Some(&ptr) = env.libc_state.time.zone_abbreviations.get(abbreviation) {
+        return ptr;
+    }
+    let ptr = env
+        .mem
+        .alloc_and_write_cstr(abbreviation.as_bytes())
+        .cast_const();
+    env.libc_state
+        .time
+        .zone_abbreviations
+        .insert(abbreviation.to_string(), ptr);
+    ptr
 }
 
 // time.h (C)
@@ -25,7 +62,9 @@ pub struct State {
 type time_t = i32;
 
 fn time(env: &mut Environment, out: MutPtr<time_t>) -> time_t {
-    let time64 = SystemTime::now()
+    let time64 = env
+        .clock
+        .system_time()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs();
@@ -168,13 +207,41 @@ fn timestamp_to_calendar_date(timestamp: time_t) -> tm {
         tm_year: year - 1900,
         tm_wday: day_of_the_week,
         tm_yday: day_in_year,
-        // This function always returns UTC
+        // This function always returns UTC, callers can adjust it
         tm_isdst: 0,
         tm_gmtoff: 0,
-        // TODO: this probably shouldn't be NULL?
-        tm_zone: Ptr::null(),
+        tm_zone: ConstPtr::null(),
     }
 }
+
+/// Inverse of [timestamp_to_calendar_date], ignoring the time zone fields and
+/// the day of the week and year. The other fields are allowed to be out of
+/// range, e.g. `tm_mday` can be 0 for the last day of the previous month.
+/// Returns seconds since 1970-01-01 00:00:00 in whichever time zone the date
+/// is in.
+fn calendar_date_to_seconds(date: &tm) -> i64 {
+    seconds_from_civil(
+        date.tm_year as i64 + 1900,
+        date.tm_mon as i64 + 1,
+        date.tm_mday.into(),
+        date.tm_hour.into(),
+        date.tm_min.into(),
+        date.tm_sec.into(),
+    )
+}
+
+/// Like [timestamp_to_calendar_date], but in the local time zone.
+fn timestamp_to_local_calendar_date(env: &mut Environment, timestamp: time_t) -> tm {
+    let local_time_type = local_time_zone(env)
+        .local_time_type(timestamp.into())
+        .clone();
+    let mut calendar_date =
+        timestamp_to_calendar_date(timestamp.saturating_add(local_time_type.utc_offset));
+    calendar_date.tm_isdst = local_time_type.is_dst.into();
+    calendar_date.tm_gmtoff = local_time_type.utc_offset;
+    calendar_date.tm_zone = zone_abbreviation(env, &local_time_type.abbreviation);
+    calendar_date
+}
 #[cfg(test)]
 #[test]
 fn test_timestamp_to_calendar_date() {
@@ -228,32 +295,103 @@ fn test_timestamp_to_calendar_date() {
     do_test("Fri, 2005-05-27T19:45:47", 1117223147);
     do_test("Sat, 1955-03-26T20:47:45", -466053135);
 }
+#[cfg(test)]
+#[test]
+fn test_calendar_date_to_seconds() {
+    for timestamp in [1140398872, 2113022454, -1509557849, 0, -1, i32::MAX] {
+        let date = timestamp_to_calendar_date(timestamp);
+        assert_eq!(calendar_date_to_seconds(&date), timestamp.into());
+    }
+    // Out-of-range fields are normalized like mktime() does.
+    let mut date = timestamp_to_calendar_date(0);
+    date.tm_mon = 12;
+    date.tm_mday = 0;
+    date.tm_sec = -1;
+    // 1970-13-00T00:00:-1 is 1970-12-30T23:59:59
+    assert_eq!(calendar_date_to_seconds(&date), 31449599);
+}
+
+fn gmtime_tmp(env: &mut Environment) -> MutPtr<tm> {
+    // This doesn't have to be a unique temporary, gmtime and localtime are
+    // allowed to share it.
+    *env.libc_state
+        .time
+        .gmtime_tmp
+        .get_or_insert_with(|| env.mem.alloc(guest_size_of::<tm>()).cast())
+}
 
 fn gmtime_r(env: &mut Environment, timestamp: ConstPtr<time_t>, res: MutPtr<tm>) -> MutPtr<tm> {
     let timestamp = env.mem.read(timestamp);
-    let calendar_date = timestamp_to_calendar_date(timestamp);
+    let mut calendar_date = timestamp_to_calendar_date(timestamp);
+    calendar_date.tm_zone = zone_abbreviation(env, "UTC");
     env.mem.write(res, calendar_date);
     res
 }
 fn gmtime(env: &mut Environment, timestamp: ConstPtr<time_t>) -> MutPtr<tm> {
-    let tmp = *env
-        .libc_state
-        .time
-        .gmtime_tmp
-        .get_or_insert_with(|| env.mem.alloc(guest_size_of::<tm>()).cast());
+    let tmp = gmtime_tmp(env);
     gmtime_r(env, timestamp, tmp)
 }
 
 fn localtime_r(env: &mut Environment, timestamp: ConstPtr<time_t>, res: MutPtr<tm>) -> MutPtr<tm> {
-    // TODO: don't assume local time is UTC?
-    gmtime_r(env, timestamp, res)
+    let timestamp = env.mem.read(timestamp);
+    let calendar_date = timestamp_to_local_calendar_date(env, timestamp);
+    env.mem.write(res, calendar_date);
+    res
 }
 fn localtime(env: &mut Environment, timestamp: ConstPtr<time_t>) -> MutPtr<tm> {
-    // TODO: don't assume local time is UTC?
-    // This doesn't have to be a unique temporary, gmtime and localtime are
-    // allowed to share it.
-    gmtime(env, timestamp)
+    let tmp = gmtime_tmp(env);
+    localtime_r(env, timestamp, tmp)
+}
+
+fn mktime(env: &mut Environment, date: MutPtr<tm>) -> time_t {
+    let date_value = env.mem.read(date.cast_const());
+    let local_time = calendar_date_to_seconds(&date_value);
+    // A negative value means the caller doesn't know whether daylight saving
+    // time is in effect.
+    let is_dst = match date_value.tm_isdst {
+        0 => Some(false),
+        isdst if isdst > 0 => Some(true),
+        _ => None,
+    };
+    let utc_offset = local_time_zone(env).utc_offset_for_local_time(local_time, is_dst);
+    let Ok(timestamp) = time_t::try_from(local_time - i64::from(utc_offset)) else {
+        return -1;
+    };
+    // The fields are normalized and the day of the week and year filled in.
+    let calendar_date = timestamp_to_local_calendar_date(env, timestamp);
+    env.mem.write(date, calendar_date);
+    timestamp
+}
+
+fn timegm(env: &mut Environment, date: MutPtr<tm>) -> time_t {
+    let Ok(timestamp) =
+        time_t::try_from(calendar_date_to_seconds(&env.mem.read(date.cast_const())))
+    else {
+        return -1;
+    };
+    let mut calendar_date = timestamp_to_calendar_date(timestamp);
+    calendar_date.tm_zone = zone_abbreviation(env, "UTC");
+    env.mem.write(date, calendar_date);
+    timestamp
+}
+
+fn tzset(env: &mut Environment) {
+    // Make sure the time zone is loaded. Its rules can't change afterwards.
+    local_time_zone(env);
+}
+
+fn difftime(_env: &mut Environment, time1: time_t, time0: time_t) -> f64 {
+    f64::from(time1) - f64::from(time0)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug)]
+#[repr(C, packed)]
+pub struct timespec {
+    pub tv_sec: time_t,
+    pub tv_nsec: i32,
 }
+unsafe impl SafeRead for timespec {}
 
 // sys/time.h (POSIX)
 
@@ -279,7 +417,9 @@ fn gettimeofday(
         return 0; // success
     }
 
-    let time = SystemTime::now()
+    let time = env
+        .clock
+        .system_time()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
 
@@ -302,5 +442,9 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(gmtime(_)),
     export_c_func!(localtime_r(_, _)),
     export_c_func!(localtime(_)),
+    export_c_func!(mktime(_)),
+    export_c_func!(timegm(_)),
+    export_c_func!(tzset()),
+    export_c_func!(difftime(_, _)),
     export_c_func!(gettimeofday(_, _)),
 ];
@@ -40,6 +40,14 @@ pub struct MachO {
     /// can look things up quickly. Thumb function symbols always have the Thumb
     /// bit set.
     pub exported_symbols: HashMap<String, u32>,
+    /// All named symbols defined by the binary, including non-exported ones,
+    /// sorted by address. This is only used for symbolication (see
+    /// [nearest_symbol]), so the Thumb bit is never set.
+    pub symbols: Vec<(u32, String)>,
+    /// Paths of the dynamic libraries that undefined symbols are expected to
+    /// be imported from, for symbols where this is known (two-level
+    /// namespace).
+    pub symbol_libraries: HashMap<String, String>,
     /// List of addresses and names of external relocations for the dynamic
     /// linker to resolve.
     pub external_relocations: Vec<(u32, String)>,
@@ -252,6 +260,8 @@ impl MachO {
         // Info used for the result
         let mut dynamic_libraries = Vec::new();
         let mut exported_symbols = HashMap::new();
+        let mut symbols = Vec::new();
+        let mut symbol_library_ordinals: Vec<(String, u16)> = Vec::new();
         let mut indirect_undef_symbols: Vec<Option<String>> = Vec::new();
         let mut external_relocations: Vec<(u32, String)> = Vec::new();
         let mut entry_point_pc: Option<u32> = None;
@@ -328,15 +338,30 @@ impl MachO {
                             if let Symbol::Debug { .. } = symbol {
                                 continue;
                             }
+                            if let Symbol::Undefined {
+                                name: Some(name),
+                                desc,
+                                ..
+                            } = symbol
+                            {
+                                // GET_LIBRARY_ORDINAL() from mach-o/nlist.h
+                                let ordinal = desc >> 8;
+                                symbol_library_ordinals.push((name.to_string(), ordinal));
+                                continue;
+                            }
                             if let Symbol::Defined {
                                 name: Some(name),
-                                external: true,
+                                external,
                                 entry,
                                 desc,
                                 ..
                             } = symbol
                             {
                                 let entry: u32 = entry.try_into().unwrap();
+                                symbols.push((entry, name.to_string()));
+                                if !external {
+                                    continue;
+                                }
                                 let entry = if desc & N_ARM_THUMB_DEF != 0 {
                                     entry | GuestFunction::THUMB_BIT
                                 } else {
@@ -442,6 +467,20 @@ impl MachO {
             }
         }
 
+        // Library ordinals count from 1. Special values like 0 (this binary)
+        // and 0xfe (flat namespace lookup) are just ignored.
+        let symbol_libraries = symbol_library_ordinals
+            .into_iter()
+            .filter_map(|(name, ordinal)| {
+                let library = dynamic_libraries.get(usize::from(ordinal).checked_sub(1)?)?;
+                Some((name, library.clone()))
+            })
+            .collect();
+
+        // If several symbols have the same address, the first one wins.
+        symbols.sort_by_key(|&(addr, _)| addr);
+        symbols.dedup_by_key(|&mut (addr, _)| addr);
+
         let sections = all_sections
             .iter()
             .map(|section| {
@@ -498,6 +537,8 @@ impl MachO {
             dynamic_libraries,
             sections,
             exported_symbols,
+            symbols,
+            symbol_libraries,
             external_relocations,
             entry_point_pc,
         })
@@ -524,4 +565,21 @@ impl MachO {
     pub fn get_section<P: SectionPredicate>(&self, by: P) -> Option<&Section> {
         self.sections.iter().find(|section| by.test(section))
     }
+
+    /// Get the section containing an address, if there is one.
+    pub fn section_containing(&self, addr: u32) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|section| (section.addr..(section.addr + section.size)).contains(&addr))
+    }
+}
+
+/// Find the symbol in a list sorted by address (like [MachO::symbols]) with
+/// the highest address that is less than or equal to `addr`. Symbols don't
+/// have sizes, so the caller must check that the result makes sense, e.g. by
+/// checking the symbol is in the same section.
+pub fn nearest_symbol(symbols: &[(u32, String)], addr: u32) -> Option<(u32, &str)> {
+    let idx = symbols.partition_point(|&(sym_addr, _)| sym_addr <= addr);
+    let (sym_addr, ref name) = *symbols.get(idx.checked_sub(1)?)?;
+    Some((sym_addr, name))
 }
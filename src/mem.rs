@@ -213,6 +213,13 @@ pub struct Mem {
     allocator: allocator::Allocator,
 }
 
+/// Copy of the guest memory that is in use, see [Mem::snapshot].
+pub struct MemSnapshot {
+    allocator: allocator::Allocator,
+    /// Base address and contents of each chunk of memory in use.
+    chunks: Vec<(VAddr, Box<[u8]>)>,
+}
+
 impl Drop for Mem {
     fn drop(&mut self) {
         let layout = std::alloc::Layout::new::<Bytes>();
@@ -467,4 +474,41 @@ impl Mem {
     pub fn reserve(&mut self, base: VAddr, size: GuestUSize) {
         self.allocator.reserve(allocator::Chunk::new(base, size));
     }
+
+    /// Copy the memory that is in use, for a save state (see
+    /// [crate::snapshot]).
+    pub fn snapshot(&self) -> MemSnapshot {
+        let chunks = self
+            .allocator
+            .used_ranges()
+            .filter(|&(base, _)| base >= Self::NULL_PAGE_SIZE)
+            .map(|(base, size)| (base, self.bytes()[base as usize..][..size as usize].into()))
+            .collect();
+        MemSnapshot {
+            allocator: self.allocator.clone(),
+            chunks,
+        }
+    }
+
+    /// Replace the memory contents and allocations with those from a save
+    /// state. Returns the ranges of memory that were changed, since the CPU
+    /// may have cached code from them.
+    pub fn restore(&mut self, snapshot: &MemSnapshot) -> Vec<(VAddr, GuestUSize)> {
+        // Memory that isn't in use is always zeroed (see [Self::free]), so
+        // anything allocated since the state was saved has to be zeroed too.
+        let mut changed: Vec<_> = self
+            .allocator
+            .used_ranges()
+            .filter(|&(base, _)| base >= Self::NULL_PAGE_SIZE)
+            .collect();
+        for &(base, size) in &changed {
+            self.bytes_mut()[base as usize..][..size as usize].fill(0);
+        }
+        for (base, bytes) in &snapshot.chunks {
+            self.bytes_mut()[*base as usize..][..bytes.len()].copy_from_slice(bytes);
+            changed.push((*base, bytes.len() as GuestUSize));
+        }
+        self.allocator = snapshot.allocator.clone();
+        changed
+    }
 }
@@ -88,7 +88,7 @@ mod chunk_tests {
 }
 
 /// Tracks which memory is in use and (TODO:) makes allocations from it.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Allocator {
     used_chunks: Vec<Chunk>,
     unused_chunks: Vec<Chunk>,
@@ -110,6 +110,13 @@ impl Allocator {
         }
     }
 
+    /// Iterate over the base addresses and sizes of the memory in use.
+    pub fn used_ranges(&self) -> impl Iterator<Item = (VAddr, GuestUSize)> + '_ {
+        self.used_chunks
+            .iter()
+            .map(|chunk| (chunk.base, chunk.size.get()))
+    }
+
     pub fn reserve(&mut self, chunk: Chunk) {
         for i in 0..self.unused_chunks.len() {
             if let Some((before, after)) = self.unused_chunks[i].trisect_by(chunk) {
@@ -19,10 +19,13 @@
 //! categories and dynamic class editing).
 
 use crate::dyld::{export_c_func, FunctionExports};
+use crate::mem::MutVoidPtr;
+use crate::ThreadID;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 mod classes;
+mod exceptions;
 mod messages;
 mod methods;
 mod objects;
@@ -32,6 +35,7 @@ mod selectors;
 pub use classes::{
     class_getName, class_getName_inner, objc_classes, Class, ClassExports, ClassTemplate,
 };
+pub use exceptions::throw;
 pub use messages::{autorelease, msg, msg_class, msg_send, release, retain};
 pub use methods::{GuestIMP, HostIMP, IMP};
 pub use objects::{
@@ -41,6 +45,10 @@ pub use objects::{
 pub use selectors::{selector, SEL};
 
 use classes::{ClassHostObject, FakeClass, UnimplementedClass, CLASS_LISTS};
+use exceptions::{
+    objc_exception_extract, objc_exception_match, objc_exception_throw, objc_exception_try_enter,
+    objc_exception_try_exit,
+};
 use messages::{objc_msgSend, objc_msgSendSuper2, objc_msgSend_stret};
 use methods::method_list_t;
 use objects::{objc_object, HostObjectEntry};
@@ -67,6 +75,22 @@ pub struct ObjC {
     ///
     /// Look at the `isa` to get the metaclass for a class.
     classes: HashMap<String, Class>,
+
+    /// Stack of `@try` handlers for each thread. See [exceptions].
+    exception_handlers: HashMap<ThreadID, Vec<MutVoidPtr>>,
+    /// Exception most recently thrown to each `@try` handler. These are
+    /// strong references, see [exceptions].
+    caught_exceptions: HashMap<MutVoidPtr, id>,
+
+    /// Names like `-[Class selector]` for the method implementations in the
+    /// app binary, sorted by address, for symbolication. These are needed
+    /// because the binary's symbol table usually has been stripped of them.
+    /// See [crate::mach_o::nearest_symbol].
+    method_symbols: Vec<(u32, String)>,
+
+    /// Messages to unimplemented classes or methods that have been stubbed
+    /// and logged. See [crate::options::Options::stub_unimplemented].
+    stubbed_messages: HashSet<(Class, SEL)>,
 }
 
 impl ObjC {
@@ -75,6 +99,10 @@ impl ObjC {
             selectors: HashMap::new(),
             objects: HashMap::new(),
             classes: HashMap::new(),
+            exception_handlers: HashMap::new(),
+            caught_exceptions: HashMap::new(),
+            method_symbols: Vec::new(),
+            stubbed_messages: HashSet::new(),
         }
     }
 }
@@ -86,4 +114,9 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(objc_setProperty(_, _, _, _, _, _)),
     export_c_func!(objc_copyStruct(_, _, _, _, _)),
     export_c_func!(class_getName(_)),
+    export_c_func!(objc_exception_throw(_)),
+    export_c_func!(objc_exception_try_enter(_)),
+    export_c_func!(objc_exception_try_exit(_)),
+    export_c_func!(objc_exception_extract(_)),
+    export_c_func!(objc_exception_match(_, _)),
 ];
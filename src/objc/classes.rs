@@ -34,6 +34,7 @@ pub type Class = id;
 /// will look up method implementations.
 ///
 /// Note: `superclass` can be `nil`!
+#[derive(Clone)]
 pub(super) struct ClassHostObject {
     pub(super) name: String,
     pub(super) is_metaclass: bool,
@@ -47,28 +48,42 @@ pub(super) struct ClassHostObject {
     /// This is always >= the value in the superclass.
     pub(super) instance_size: GuestUSize,
 }
-impl HostObject for ClassHostObject {}
+impl HostObject for ClassHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 /// Placeholder object for classes and metaclasses referenced by the app that
 /// we don't have an implementation for.
 ///
 /// This lets us delay errors about missing implementations until the first
 /// time the app actually uses them (e.g. when a message is sent).
+#[derive(Clone)]
 pub(super) struct UnimplementedClass {
     pub(super) name: String,
     pub(super) is_metaclass: bool,
 }
-impl HostObject for UnimplementedClass {}
+impl HostObject for UnimplementedClass {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 /// Substitute object for classes and metaclasses from the guest app that we do
 /// not want to support (see [substitute_classes]).
 ///
 /// Messages sent to this class will behave as if messaging [nil].
+#[derive(Clone)]
 pub(super) struct FakeClass {
     pub(super) name: String,
     pub(super) is_metaclass: bool,
 }
-impl HostObject for FakeClass {}
+impl HostObject for FakeClass {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 /// The layout of a class in an app binary.
 ///
@@ -444,6 +459,49 @@ impl ObjC {
         crate::dyld::search_lists(CLASS_LISTS, name).map(|&(_name, ref template)| template)
     }
 
+    /// For use by [crate::compat_report]: is there a host implementation of
+    /// this class?
+    pub fn has_host_class(name: &str) -> bool {
+        Self::find_template(name).is_some()
+    }
+
+    /// For use by [crate::compat_report]: is there a method for this selector
+    /// in any of our classes, or in any of the app's classes? This only makes
+    /// sense after the app binary's classes have been registered.
+    pub fn has_method_for_selector(&self, name: &str, mem: &Mem) -> bool {
+        let in_host_classes = CLASS_LISTS
+            .iter()
+            .flat_map(|&list| list)
+            .flat_map(|(_, template)| {
+                template
+                    .class_methods
+                    .iter()
+                    .chain(template.instance_methods)
+            })
+            .any(|&(method_name, _)| method_name == name);
+        if in_host_classes {
+            return true;
+        }
+
+        let Some(sel) = self.lookup_selector(name) else {
+            return false;
+        };
+        self.classes.values().any(|&class| {
+            [class, Self::read_isa(class, mem)]
+                .into_iter()
+                .any(|class| {
+                    let host_object = self.get_host_object(class).unwrap();
+                    if let Some(ClassHostObject { methods, .. }) =
+                        host_object.as_any().downcast_ref()
+                    {
+                        methods.contains_key(&sel)
+                    } else {
+                        false
+                    }
+                })
+        })
+    }
+
     /// For use by [crate::dyld]: get the class or metaclass referenced by an
     /// external relocation in the app binary. If we don't have an
     /// implementation of the class, a placeholder is used.
@@ -457,6 +515,21 @@ impl ObjC {
         self.link_class_inner(name, /* is_metaclass: */ false, mem, false)
     }
 
+    /// For use by host functions: get a class by name if it exists, like
+    /// `NSClassFromString()`. Unlike [Self::get_known_class], this doesn't
+    /// panic if there's no such class.
+    pub fn find_class(&mut self, name: &str, mem: &mut Mem) -> Option<Class> {
+        if self
+            .get_class(name, /* is_metaclass: */ false, mem)
+            .is_some()
+            || Self::find_template(name).is_some()
+        {
+            Some(self.get_known_class(name, mem))
+        } else {
+            None
+        }
+    }
+
     fn link_class_inner(
         &mut self,
         name: &str,
@@ -656,6 +729,14 @@ impl ObjC {
         }
     }
 
+    /// Get the superclass of a class, or `nil` if it has none (or if it's a
+    /// placeholder for an unimplemented class).
+    pub fn get_superclass(&self, class: Class) -> Class {
+        self.get_host_object(class)
+            .and_then(|host_object| host_object.as_any().downcast_ref::<ClassHostObject>())
+            .map_or(nil, |&ClassHostObject { superclass, .. }| superclass)
+    }
+
     pub fn class_is_subclass_of(&self, class: Class, superclass: Class) -> bool {
         if class == superclass {
             return true;
@@ -685,7 +766,7 @@ pub fn class_getName(env: &mut Environment, class: Class) -> ConstPtr<u8> {
 }
 
 /// Internal method that gets class name as a &str.
-pub fn class_getName_inner(objc: &mut ObjC, class: Class) -> &str {
+pub fn class_getName_inner(objc: &ObjC, class: Class) -> &str {
     if class == nil {
         "nil"
     } else if let Some(host_object) = objc.get_host_object(class).map(|obj| obj.as_any()) {
@@ -18,12 +18,18 @@ pub const CLASS_LISTS: &[super::ClassExports] = &[
     foundation::ns_array::CLASSES,
     foundation::ns_autorelease_pool::CLASSES,
     foundation::ns_bundle::CLASSES,
+    foundation::ns_calendar::CLASSES,
     foundation::ns_character_set::CLASSES,
     foundation::ns_coder::CLASSES,
     foundation::ns_data::CLASSES,
+    foundation::ns_date::CLASSES,
+    foundation::ns_date_formatter::CLASSES,
     foundation::ns_dictionary::CLASSES,
     foundation::ns_enumerator::CLASSES,
+    foundation::ns_exception::CLASSES,
     foundation::ns_file_manager::CLASSES,
+    foundation::ns_index_path::CLASSES,
+    foundation::ns_keyed_archiver::CLASSES,
     foundation::ns_keyed_unarchiver::CLASSES,
     foundation::ns_locale::CLASSES,
     foundation::ns_notification::CLASSES,
@@ -31,10 +37,12 @@ pub const CLASS_LISTS: &[super::ClassExports] = &[
     foundation::ns_null::CLASSES,
     foundation::ns_object::CLASSES,
     foundation::ns_process_info::CLASSES,
+    foundation::ns_property_list_serialization::CLASSES,
     foundation::ns_run_loop::CLASSES,
     foundation::ns_set::CLASSES,
     foundation::ns_string::CLASSES,
     foundation::ns_thread::CLASSES,
+    foundation::ns_time_zone::CLASSES,
     foundation::ns_timer::CLASSES,
     foundation::ns_url::CLASSES,
     foundation::ns_user_defaults::CLASSES,
@@ -42,7 +50,11 @@ pub const CLASS_LISTS: &[super::ClassExports] = &[
     media_player::movie_player::CLASSES,
     opengles::eagl::CLASSES,
     uikit::ui_accelerometer::CLASSES,
+    uikit::ui_action_sheet::CLASSES,
+    uikit::ui_alert_view::CLASSES,
     uikit::ui_application::CLASSES,
+    uikit::ui_bar_item::CLASSES,
+    uikit::ui_button::CLASSES,
     uikit::ui_color::CLASSES,
     uikit::ui_control::CLASSES,
     uikit::ui_device::CLASSES,
@@ -51,9 +63,17 @@ pub const CLASS_LISTS: &[super::ClassExports] = &[
     uikit::ui_image::CLASSES,
     uikit::ui_image_picker_controller::CLASSES,
     uikit::ui_image_view::CLASSES,
+    uikit::ui_label::CLASSES,
+    uikit::ui_navigation_bar::CLASSES,
+    uikit::ui_navigation_controller::CLASSES,
     uikit::ui_nib::CLASSES,
     uikit::ui_responder::CLASSES,
     uikit::ui_screen::CLASSES,
+    uikit::ui_scroll_view::CLASSES,
+    uikit::ui_tab_bar::CLASSES,
+    uikit::ui_tab_bar_controller::CLASSES,
+    uikit::ui_table_view::CLASSES,
+    uikit::ui_table_view_cell::CLASSES,
     uikit::ui_text_field::CLASSES,
     uikit::ui_touch::CLASSES,
     uikit::ui_view::CLASSES,
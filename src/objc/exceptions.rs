@@ -0,0 +1,97 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Objective-C exceptions (`@throw`, `@try`, `@catch`).
+//!
+//! On iPhone OS 2 and 3, these use a setjmp-based ABI: a `@try` block
+//! registers a handler with `objc_exception_try_enter` and then calls
+//! `_setjmp` on the `jmp_buf` at the start of the handler's data, and throwing
+//! an exception `longjmp`s to the most recently registered handler. The rest
+//! of the handler's data is private to the runtime, but since its size isn't
+//! well-documented, we store everything on the host instead.
+//!
+//! The runtime holds a reference to a caught exception until the handler's
+//! `@try` block is exited, so that e.g. an autoreleased exception raised by
+//! host code stays alive even if the thrower's autorelease pool is drained.
+//!
+//! The `NSException` class is in [crate::frameworks::foundation::ns_exception].
+
+use super::{id, msg, nil, release, retain, Class};
+use crate::frameworks::foundation::ns_exception;
+use crate::libc::setjmp::longjmp_from_host;
+use crate::mem::MutVoidPtr;
+use crate::Environment;
+
+/// Pointer to the compiler-generated `_objc_exception_data` struct for a
+/// `@try` block. This starts with a `jmp_buf`.
+type ExceptionHandler = MutVoidPtr;
+
+/// Throw an exception, i.e. `@throw exception;`. This can be used by host code
+/// too, but keep in mind that the host function that calls it will be
+/// abandoned (see [Environment::abandon_host_function]).
+pub fn throw(env: &mut Environment, exception: id) -> ! {
+    let handlers = env
+        .objc
+        .exception_handlers
+        .entry(env.current_thread)
+        .or_default();
+    let Some(handler) = handlers.pop() else {
+        ns_exception::handle_uncaught_exception(env, exception);
+    };
+
+    log_dbg!(
+        "Throwing exception {:?} to handler {:?} on thread {}",
+        exception,
+        handler,
+        env.current_thread
+    );
+    retain(env, exception);
+    // A handler can catch another exception if one is thrown inside its
+    // `@catch` block.
+    if let Some(previous) = env.objc.caught_exceptions.insert(handler, exception) {
+        release(env, previous);
+    }
+    longjmp_from_host(env, handler.cast(), 1)
+}
+
+pub(super) fn objc_exception_throw(env: &mut Environment, exception: id) {
+    throw(env, exception)
+}
+
+pub(super) fn objc_exception_try_enter(env: &mut Environment, handler: ExceptionHandler) {
+    // This is also called again for the same handler when entering a
+    // `@catch` block, so the caught exception isn't released here.
+    env.objc
+        .exception_handlers
+        .entry(env.current_thread)
+        .or_default()
+        .push(handler);
+}
+
+pub(super) fn objc_exception_try_exit(env: &mut Environment, handler: ExceptionHandler) {
+    let handlers = env
+        .objc
+        .exception_handlers
+        .get_mut(&env.current_thread)
+        .unwrap();
+    let top = handlers.pop();
+    assert!(top == Some(handler));
+    if let Some(exception) = env.objc.caught_exceptions.remove(&handler) {
+        release(env, exception);
+    }
+}
+
+pub(super) fn objc_exception_extract(env: &mut Environment, handler: ExceptionHandler) -> id {
+    env.objc
+        .caught_exceptions
+        .get(&handler)
+        .copied()
+        .unwrap_or(nil)
+}
+
+pub(super) fn objc_exception_match(env: &mut Environment, class: Class, exception: id) -> i32 {
+    let matches: bool = msg![env; exception isKindOfClass:class];
+    matches.into()
+}
@@ -13,9 +13,34 @@
 
 use super::{id, nil, Class, ObjC, IMP, SEL};
 use crate::abi::{CallFromHost, GuestRet};
+use crate::cpu::Cpu;
 use crate::mem::{ConstPtr, MutVoidPtr, SafeRead};
 use crate::Environment;
 
+/// Handle a message that can't be sent because the class or method isn't
+/// implemented. Normally this panics with `message`, but if the user asked for
+/// unimplemented things to be stubbed, this behaves as if the message was sent
+/// to nil, logging the first such message for each class and selector.
+fn stub_unimplemented_message(env: &mut Environment, class: Class, selector: SEL, message: String) {
+    if !env.options.stub_unimplemented {
+        panic!("{}", message);
+    }
+
+    if env.objc.stubbed_messages.insert((class, selector)) {
+        let regs = env.cpu.regs();
+        log!(
+            "Warning: {} Called from {}, r0-r3: {:#x}, {:#x}, {:#x}, {:#x}. Behaving as if message was sent to nil.",
+            message,
+            env.symbolicate(regs[Cpu::LR]),
+            regs[0],
+            regs[1],
+            regs[2],
+            regs[3],
+        );
+    }
+    env.cpu.regs_mut()[0..2].fill(0);
+}
+
 /// The core implementation of `objc_msgSend`, the main function of Objective-C.
 ///
 /// Note that while only two parameters (usually receiver and selector) are
@@ -54,7 +79,7 @@ fn objc_msgSend_inner(env: &mut Environment, receiver: id, selector: SEL, super2
                 ..
             } = class_host_object.as_any().downcast_ref().unwrap();
 
-            panic!(
+            let message = format!(
                 "{} {:?} ({}class \"{}\", {:?}){} does not respond to selector \"{}\"!",
                 if is_metaclass { "Class" } else { "Object" },
                 receiver,
@@ -68,6 +93,8 @@ fn objc_msgSend_inner(env: &mut Environment, receiver: id, selector: SEL, super2
                 },
                 selector.as_str(&env.mem),
             );
+            stub_unimplemented_message(env, orig_class, selector, message);
+            return;
         }
 
         let host_object = env.objc.get_host_object(class).unwrap();
@@ -101,13 +128,15 @@ fn objc_msgSend_inner(env: &mut Environment, receiver: id, selector: SEL, super2
             is_metaclass,
         }) = host_object.as_any().downcast_ref()
         {
-            panic!(
+            let message = format!(
                 "Class \"{}\" ({:?}) is unimplemented. Call to {} method \"{}\".",
                 name,
                 class,
                 if is_metaclass { "class" } else { "instance" },
                 selector.as_str(&env.mem),
             );
+            stub_unimplemented_message(env, class, selector, message);
+            return;
         } else if let Some(&super::FakeClass {
             ref name,
             is_metaclass,
@@ -10,6 +10,7 @@
 
 use super::{id, nil, Class, ClassHostObject, ObjC, SEL};
 use crate::abi::{CallFromGuest, DotDotDot, GuestArg, GuestFunction, GuestRet};
+use crate::mach_o::nearest_symbol;
 use crate::mem::{guest_size_of, ConstPtr, GuestUSize, Mem, Ptr, SafeRead};
 use crate::Environment;
 
@@ -21,6 +22,7 @@ use crate::Environment;
 /// "guest methods" (functions in the guest app). Either way, the function needs
 /// to conform to the same ABI: [id] and [SEL] must be its first two parameters.
 #[allow(clippy::upper_case_acronyms)]
+#[derive(Clone)]
 pub enum IMP {
     Host(&'static dyn HostIMP),
     Guest(GuestIMP),
@@ -166,11 +168,30 @@ impl ClassHostObject {
             // We must deduplicate it like any other.
             let sel = objc.register_bin_selector(name, mem);
             self.methods.insert(sel, IMP::Guest(imp));
+
+            let symbol = format!(
+                "{}[{} {}]",
+                if self.is_metaclass { '+' } else { '-' },
+                self.name,
+                sel.as_str(mem)
+            );
+            let addr = imp.addr_without_thumb_bit();
+            let idx = objc
+                .method_symbols
+                .partition_point(|&(sym_addr, _)| sym_addr < addr);
+            objc.method_symbols.insert(idx, (addr, symbol));
         }
     }
 }
 
 impl ObjC {
+    /// For use by [crate::Environment::symbolicate]: find the method
+    /// implementation from the app binary that is nearest to `addr`. See
+    /// [nearest_symbol].
+    pub fn nearest_method_symbol(&self, addr: u32) -> Option<(u32, &str)> {
+        nearest_symbol(&self.method_symbols, addr)
+    }
+
     /// For use by NSObject's getter/setter search methods.
     pub fn class_has_method(&self, class: Class, sel: SEL) -> bool {
         let mut class = class;
@@ -189,4 +210,24 @@ impl ObjC {
             }
         }
     }
+
+    /// Check if `class` or any of its superclasses up to (but not including)
+    /// `superclass` has a method for `sel`, i.e. whether the implementation
+    /// in `superclass` has been overridden. For example, UIKit uses this to
+    /// check if a view has its own `drawRect:`.
+    pub fn class_overrides_method(&self, class: Class, superclass: Class, sel: SEL) -> bool {
+        let mut class = class;
+        while class != superclass && class != nil {
+            let &ClassHostObject {
+                superclass: next,
+                ref methods,
+                ..
+            } = self.borrow(class);
+            if methods.contains_key(&sel) {
+                return true;
+            }
+            class = next;
+        }
+        false
+    }
 }
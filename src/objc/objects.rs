@@ -20,10 +20,11 @@
 //!
 //! See also: [crate::frameworks::foundation::ns_object].
 
-use super::{Class, ClassHostObject, ObjC};
+use super::{class_getName_inner, Class, ClassHostObject, ObjC};
 use crate::environment::Environment;
 use crate::mem::{guest_size_of, GuestUSize, Mem, MutPtr, Ptr, SafeRead};
 use std::any::Any;
+use std::collections::HashMap;
 use std::num::NonZeroU32;
 
 /// Memory layout of a minimal Objective-C object. See [id].
@@ -66,7 +67,15 @@ pub(super) struct HostObjectEntry {
 }
 
 /// Type for host objects.
-pub trait HostObject: Any + 'static {}
+pub trait HostObject: Any + 'static {
+    /// Make a copy of this host object for a save state (see
+    /// [crate::snapshot]). Host objects that own host resources which can't
+    /// simply be copied, e.g. an OpenGL ES context, should keep the default
+    /// implementation, which prevents the state from being saved.
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        None
+    }
+}
 
 /// Trait wrapping [HostObject] with a blanket implementation to make
 /// downcasting work. Don't implement it yourself.
@@ -76,6 +85,7 @@ pub trait HostObject: Any + 'static {}
 pub trait AnyHostObject {
     fn as_any<'a>(&'a self) -> &'a (dyn Any + 'static);
     fn as_any_mut<'a>(&'a mut self) -> &'a mut (dyn Any + 'static);
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>>;
 }
 impl<T: HostObject> AnyHostObject for T {
     fn as_any<'a>(&'a self) -> &'a (dyn Any + 'static) {
@@ -84,11 +94,19 @@ impl<T: HostObject> AnyHostObject for T {
     fn as_any_mut<'a>(&'a mut self) -> &'a mut (dyn Any + 'static) {
         self
     }
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        HostObject::snapshot(self)
+    }
 }
 
 /// Empty host object used by `[NSObject alloc]`.
+#[derive(Clone)]
 pub struct TrivialHostObject;
-impl HostObject for TrivialHostObject {}
+impl HostObject for TrivialHostObject {
+    fn snapshot(&self) -> Option<Box<dyn AnyHostObject>> {
+        Some(Box::new(self.clone()))
+    }
+}
 
 impl super::ObjC {
     /// Read the all-important `isa`.
@@ -259,6 +277,45 @@ impl super::ObjC {
 
         mem.free(object.cast());
     }
+
+    /// Make a copy of the runtime's state for a save state (see
+    /// [crate::snapshot]). This fails if there is an object whose host object
+    /// can't be copied (see [HostObject::snapshot]).
+    pub fn snapshot(&self, mem: &Mem) -> Result<ObjC, String> {
+        let mut objects = HashMap::with_capacity(self.objects.len());
+        let mut unsupported = None;
+        for (&object, entry) in &self.objects {
+            let Some(host_object) = entry.host_object.snapshot() else {
+                unsupported = Some(object);
+                break;
+            };
+            objects.insert(
+                object,
+                HostObjectEntry {
+                    host_object,
+                    refcount: entry.refcount,
+                },
+            );
+        }
+        if let Some(object) = unsupported {
+            let class = Self::read_isa(object, mem);
+            return Err(format!(
+                "{:?} is an instance of {}, which doesn't support save states yet",
+                object,
+                class_getName_inner(self, class)
+            ));
+        }
+
+        Ok(ObjC {
+            selectors: self.selectors.clone(),
+            objects,
+            classes: self.classes.clone(),
+            exception_handlers: self.exception_handlers.clone(),
+            caught_exceptions: self.caught_exceptions.clone(),
+            method_symbols: self.method_symbols.clone(),
+            stubbed_messages: self.stubbed_messages.clone(),
+        })
+    }
 }
 
 /// Checks if an instance of the provided class responds to a selector (has a method in it's class
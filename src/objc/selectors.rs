@@ -53,6 +53,14 @@ impl SEL {
         // selectors are probably always UTF-8 but this hasn't been verified
         mem.cstr_at_utf8(self.0).unwrap()
     }
+
+    pub fn null() -> Self {
+        SEL(ConstPtr::null())
+    }
+
+    pub fn is_null(self) -> bool {
+        self.0.is_null()
+    }
 }
 
 impl ObjC {
@@ -81,6 +89,18 @@ impl ObjC {
         }
     }
 
+    /// Look up a selector by name, registering it if it's new. This is for
+    /// selector names that only become known at runtime, e.g. from a nib file.
+    pub fn register_selector(&mut self, name: &str, mem: &mut Mem) -> SEL {
+        if let Some(existing_sel) = self.lookup_selector(name) {
+            existing_sel
+        } else {
+            let sel = SEL(mem.alloc_and_write_cstr(name.as_bytes()).cast_const());
+            self.selectors.insert(name.to_string(), sel);
+            sel
+        }
+    }
+
     /// Register a selector from the application binary. Must be a
     /// static-lifetime constant string.
     pub(super) fn register_bin_selector(&mut self, sel_cstr: ConstPtr<u8>, mem: &Mem) -> SEL {
@@ -6,12 +6,13 @@
 //! Parsing and management of user-configurable options, e.g. for input methods.
 
 use crate::frameworks::opengles::GLESImplementation;
-use crate::window::DeviceOrientation;
+use crate::window::{DeviceOrientation, Key};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 
 pub const DOCUMENTATION: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/OPTIONS_HELP.txt"));
@@ -28,6 +29,7 @@ pub enum Button {
 /// Struct containing all user-configurable options.
 pub struct Options {
     pub fullscreen: bool,
+    pub headless: bool,
     pub initial_orientation: DeviceOrientation,
     pub scale_hack: NonZeroU32,
     pub deadzone: f32,
@@ -36,15 +38,25 @@ pub struct Options {
     pub x_tilt_offset: f32,
     pub y_tilt_offset: f32,
     pub button_to_touch: HashMap<Button, (f32, f32)>,
+    pub key_to_touch: HashMap<Key, (f32, f32)>,
+    pub key_to_drag: HashMap<Key, ((f32, f32), (f32, f32))>,
+    pub key_to_tilt: HashMap<Key, (f32, f32)>,
     pub gles1_implementation: Option<GLESImplementation>,
     pub direct_memory_access: bool,
     pub gdb_listen_addrs: Option<Vec<SocketAddr>>,
+    pub record_input: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub dump_frames: Option<PathBuf>,
+    pub speed: f64,
+    pub time_zone: Option<String>,
+    pub stub_unimplemented: bool,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Options {
             fullscreen: false,
+            headless: false,
             initial_orientation: DeviceOrientation::Portrait,
             scale_hack: NonZeroU32::new(1).unwrap(),
             deadzone: 0.1,
@@ -53,9 +65,18 @@ impl Default for Options {
             x_tilt_offset: 0.0,
             y_tilt_offset: 0.0,
             button_to_touch: HashMap::new(),
+            key_to_touch: HashMap::new(),
+            key_to_drag: HashMap::new(),
+            key_to_tilt: HashMap::new(),
             gles1_implementation: None,
             direct_memory_access: true,
             gdb_listen_addrs: None,
+            record_input: None,
+            replay: None,
+            dump_frames: None,
+            speed: 1.0,
+            time_zone: None,
+            stub_unimplemented: false,
         }
     }
 }
@@ -74,9 +95,32 @@ impl Options {
             }
             Ok(arg)
         }
+        /// Parse a key name followed by `N` numbers, separated by commas.
+        fn parse_key_mapping<const N: usize>(
+            value: &str,
+            name: &str,
+        ) -> Result<(Key, [f32; N]), String> {
+            let mut parts = value.split(',');
+            let key_name = parts.next().unwrap();
+            let key = Key::from_name(key_name)
+                .ok_or_else(|| format!("Unrecognized key name {:?} for {}", key_name, name))?;
+            let parts: Vec<&str> = parts.collect();
+            if parts.len() != N {
+                return Err(format!("{} requires {} values", name, N + 1));
+            }
+            let mut numbers = [0.0; N];
+            for (number, part) in numbers.iter_mut().zip(parts) {
+                *number = part
+                    .parse()
+                    .map_err(|_| format!("Invalid number {:?} for {}", part, name))?;
+            }
+            Ok((key, numbers))
+        }
 
         if arg == "--fullscreen" {
             self.fullscreen = true;
+        } else if arg == "--headless" {
+            self.headless = true;
         } else if arg == "--landscape-left" {
             self.initial_orientation = DeviceOrientation::LandscapeLeft;
         } else if arg == "--landscape-right" {
@@ -116,6 +160,18 @@ impl Options {
                 .parse()
                 .map_err(|_| "Invalid Y co-ordinate for --button-to-touch=".to_string())?;
             self.button_to_touch.insert(button, (x, y));
+        } else if let Some(values) = arg.strip_prefix("--key-to-touch=") {
+            let (key, [x, y]) = parse_key_mapping(values, "--key-to-touch=")?;
+            self.key_to_touch.insert(key, (x, y));
+        } else if let Some(values) = arg.strip_prefix("--key-to-drag=") {
+            let (key, [x1, y1, x2, y2]) = parse_key_mapping(values, "--key-to-drag=")?;
+            self.key_to_drag.insert(key, ((x1, y1), (x2, y2)));
+        } else if let Some(values) = arg.strip_prefix("--key-to-tilt=") {
+            let (key, [x, y]) = parse_key_mapping(values, "--key-to-tilt=")?;
+            if !(-1.0..=1.0).contains(&x) || !(-1.0..=1.0).contains(&y) {
+                return Err("Values for --key-to-tilt= must be between -1 and 1".to_string());
+            }
+            self.key_to_tilt.insert(key, (x, y));
         } else if let Some(value) = arg.strip_prefix("--gles1=") {
             self.gles1_implementation = Some(
                 GLESImplementation::from_short_name(value)
@@ -129,6 +185,26 @@ impl Options {
                 .map_err(|e| format!("Could not resolve GDB server listen address: {}", e))?
                 .collect();
             self.gdb_listen_addrs = Some(addrs);
+        } else if let Some(path) = arg.strip_prefix("--record-input=") {
+            self.record_input = Some(PathBuf::from(path));
+        } else if let Some(path) = arg.strip_prefix("--replay=") {
+            self.replay = Some(PathBuf::from(path));
+        } else if let Some(path) = arg.strip_prefix("--dump-frames=") {
+            self.dump_frames = Some(PathBuf::from(path));
+        } else if let Some(value) = arg.strip_prefix("--speed=") {
+            let speed: f64 = value
+                .parse()
+                .map_err(|_| "Invalid value for --speed=".to_string())?;
+            if !speed.is_finite() || speed <= 0.0 {
+                return Err("Value for --speed= must be greater than 0".to_string());
+            }
+            self.speed = speed;
+        } else if let Some(name) = arg.strip_prefix("--time-zone=") {
+            // Check the name now so mistakes are reported right away.
+            crate::time_zone::TimeZone::load(name)?;
+            self.time_zone = Some(name.to_string());
+        } else if arg == "--stub-unimplemented" {
+            self.stub_unimplemented = true;
         } else {
             return Ok(false);
         };
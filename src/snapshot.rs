@@ -0,0 +1,112 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Save states: copies of the app's state that can be returned to later.
+//!
+//! This is deliberately limited for now. There is a single slot, kept in
+//! memory ([Environment::saved_state]) rather than written to a file, and
+//! states can only be saved or loaded at the top of the main thread's outermost
+//! run loop (see `NSRunLoop`), since that's the only point where the host's own
+//! stack is known to be the same each time.
+//!
+//! Everything the app can observe is copied: guest memory, threads, guest time,
+//! Objective-C objects, and the state of libc and the frameworks. Host
+//! resources that can't be copied yet (OpenGL ES contexts, audio, open files)
+//! aren't silently lost: saving or loading is refused with an error naming
+//! what's in use. Objects opt in by implementing
+//! [crate::objc::HostObject::snapshot]. The app's files aren't rolled back.
+
+use crate::environment::Thread;
+use crate::mem::MemSnapshot;
+use crate::objc::{id, ObjC};
+use crate::window::StateRequest;
+use crate::{frameworks, libc, Environment};
+use std::time::Duration;
+
+pub struct Snapshot {
+    /// The run loop the state was saved in. It must be the one running when
+    /// the state is loaded.
+    run_loop: id,
+    mem: MemSnapshot,
+    threads: Vec<Thread>,
+    /// Guest time, see [crate::clock::Clock::elapsed].
+    elapsed: Duration,
+    objc: ObjC,
+    libc_state: libc::State,
+    framework_state: frameworks::State,
+}
+
+/// Check for anything in use that can't be copied or replaced, before copying
+/// or replacing anything.
+fn check_supported(env: &Environment) -> Result<(), String> {
+    if env.window.is_recording_or_replaying_input() {
+        return Err("input is being recorded or replayed".to_string());
+    }
+    env.libc_state.check_snapshot_supported()?;
+    env.framework_state.check_snapshot_supported()
+}
+
+fn save(env: &mut Environment, run_loop: id) -> Result<Snapshot, String> {
+    check_supported(env)?;
+    let threads = env.snapshot_threads()?;
+    let objc = env.objc.snapshot(&env.mem)?;
+    Ok(Snapshot {
+        run_loop,
+        mem: env.mem.snapshot(),
+        threads,
+        elapsed: env.clock.elapsed(),
+        objc,
+        libc_state: env.libc_state.snapshot()?,
+        framework_state: env.framework_state.snapshot()?,
+    })
+}
+
+fn load(env: &mut Environment, run_loop: id, snapshot: &Snapshot) -> Result<(), String> {
+    check_supported(env)?;
+    if run_loop != snapshot.run_loop {
+        return Err("the app is in a different run loop".to_string());
+    }
+    // Objects that can't be copied own host resources that would be leaked or
+    // destroyed while still in use if the objects were replaced.
+    env.objc.snapshot(&env.mem)?;
+
+    // Nothing can fail after this point.
+    env.restore_threads(&snapshot.threads)?;
+    for (base, size) in env.mem.restore(&snapshot.mem) {
+        env.cpu.invalidate_cache_range(base, size);
+    }
+    env.clock.set_elapsed(snapshot.elapsed);
+    // The saved copy is kept so the state can be loaded again.
+    env.objc = snapshot.objc.snapshot(&env.mem).unwrap();
+    env.libc_state.restore(&snapshot.libc_state);
+    env.framework_state.restore(&snapshot.framework_state);
+    Ok(())
+}
+
+/// Save or load the state if the hotkey for that was pressed. `NSRunLoop`
+/// calls this at the top of each iteration of the run loop `run_loop`.
+pub fn handle_request(env: &mut Environment, run_loop: id) {
+    match env.window.take_state_request() {
+        None => (),
+        Some(StateRequest::Save) => match save(env, run_loop) {
+            Ok(snapshot) => {
+                env.saved_state = Some(snapshot);
+                echo!("Saved state.");
+            }
+            Err(e) => echo!("Couldn't save state: {}.", e),
+        },
+        Some(StateRequest::Load) => {
+            let Some(snapshot) = env.saved_state.take() else {
+                echo!("Couldn't load state: no state has been saved.");
+                return;
+            };
+            match load(env, run_loop, &snapshot) {
+                Ok(()) => echo!("Loaded state."),
+                Err(e) => echo!("Couldn't load state: {}.", e),
+            }
+            env.saved_state = Some(snapshot);
+        }
+    }
+}
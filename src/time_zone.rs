@@ -0,0 +1,1159 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Time zones and Gregorian calendar arithmetic, for the guest's local time.
+//!
+//! Rust's standard library has nothing for time zones, so the rules are read
+//! from the host's copy of the IANA time zone database, which is a directory of
+//! TZif files (`/usr/share/zoneinfo` on Linux and macOS). Hosts without one
+//! (e.g. Windows) only get UTC and fixed offsets from UTC.
+//!
+//! The local time zone is the host's, unless the `--time-zone=` option is used.
+//!
+//! Useful resources:
+//! - [RFC 8536](https://www.rfc-editor.org/rfc/rfc8536), which describes TZif.
+//! - The description of the `TZ` environment variable in POSIX, whose syntax is
+//!   used for the rule at the end of a TZif file.
+
+use std::path::Path;
+
+/// Directories where the host might keep the time zone database.
+const ZONEINFO_DIRS: &[&str] = &[
+    "/usr/share/zoneinfo",
+    "/usr/lib/zoneinfo",
+    "/usr/share/lib/zoneinfo",
+];
+
+pub const MINUTE_SECONDS: i64 = 60;
+pub const HOUR_SECONDS: i64 = MINUTE_SECONDS * 60;
+pub const DAY_SECONDS: i64 = HOUR_SECONDS * 24;
+
+pub fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Number of days in a month (counting from 1) of a year.
+pub fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Number of days between 1970-01-01 and a date. The month must be from 1 to
+/// 12, but the day can be out of range (e.g. day 0 is the last day of the
+/// previous month).
+///
+/// This is the `days_from_civil` algorithm from Howard Hinnant's
+/// [chrono-Compatible Low-Level Date Algorithms](https://howardhinnant.github.io/date_algorithms.html).
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    assert!((1..=12).contains(&month));
+    // The year is considered to start in March, so that the leap day is at
+    // the end of it.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The inverse of [days_from_civil]: returns the year, month (from 1) and day
+/// (from 1).
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (year_of_era * 365 + year_of_era / 4 - year_of_era / 100);
+    let month_index = (day_of_year * 5 + 2) / 153; // 0 = March
+    let day = day_of_year - (month_index * 153 + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Day of the week (0 = Sunday) for a number of days since 1970-01-01.
+pub fn weekday_from_days(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    (days + 4).rem_euclid(7)
+}
+
+/// Number of seconds between 1970-01-01 00:00:00 and a date and time, with no
+/// time zone applied. Every field can be out of range (e.g. month 13 is January
+/// of the next year, second -1 is the last second of the previous minute).
+pub fn seconds_from_civil(
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+) -> i64 {
+    let months = year * 12 + (month - 1);
+    let days = days_from_civil(months.div_euclid(12), months.rem_euclid(12) + 1, day);
+    days * DAY_SECONDS + hour * HOUR_SECONDS + minute * MINUTE_SECONDS + second
+}
+
+/// Offset from UTC and related information that applies at some point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalTimeType {
+    /// Seconds to add to UTC to get local time (positive east of Greenwich).
+    pub utc_offset: i32,
+    pub is_dst: bool,
+    /// Abbreviation, e.g. `CEST`.
+    pub abbreviation: String,
+}
+
+/// A date and time in some time zone, in the (proleptic) Gregorian calendar.
+#[derive(Clone, Debug)]
+pub struct DateTime {
+    pub year: i64,
+    /// From 1 to 12.
+    pub month: i64,
+    /// From 1 to 31.
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+    /// Fraction of a second, from 0 up to but not including 1.
+    pub fraction: f64,
+    /// Day of the week, 0 = Sunday.
+    pub weekday: i64,
+    /// Day of the year, counting from 0.
+    pub day_of_year: i64,
+    pub local_time_type: LocalTimeType,
+}
+
+/// Rules for a time zone. Construct with [TimeZone::load] and friends.
+#[derive(Clone, Debug)]
+pub struct TimeZone {
+    name: String,
+    /// Times (seconds since the UNIX epoch) when the local time type changes,
+    /// and the index in [Self::types] of the new type. Sorted by time.
+    transitions: Vec<(i64, usize)>,
+    /// Local time types. The first one applies before the first transition.
+    types: Vec<LocalTimeType>,
+    /// Rule for times after the last transition.
+    rule: Option<Rule>,
+}
+
+/// Rule in the format of a POSIX `TZ` environment variable, e.g.
+/// `CET-1CEST,M3.5.0,M10.5.0/3`.
+#[derive(Clone, Debug)]
+struct Rule {
+    standard: LocalTimeType,
+    daylight_saving: Option<DaylightSavingRule>,
+}
+
+#[derive(Clone, Debug)]
+struct DaylightSavingRule {
+    local_time_type: LocalTimeType,
+    /// When daylight saving time starts, as a date and a time of day in
+    /// seconds, both in standard time.
+    start: (RuleDate, i64),
+    /// When daylight saving time ends, as a date and a time of day in seconds,
+    /// both in daylight saving time.
+    end: (RuleDate, i64),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum RuleDate {
+    /// `Jn`: day of the year from 1 to 365, never counting February 29.
+    JulianNoLeap(i64),
+    /// `n`: day of the year from 0 to 365, counting February 29.
+    Julian(i64),
+    /// `Mm.w.d`: day of the week `d` (0 = Sunday) of week `w` (1 to 5, where 5
+    /// means the last) of month `m`.
+    MonthWeekDay { month: i64, week: i64, weekday: i64 },
+}
+
+impl RuleDate {
+    /// Days since 1970-01-01 of this date in a particular year.
+    fn days_in_year(self, year: i64) -> i64 {
+        let january_1st = days_from_civil(year, 1, 1);
+        match self {
+            RuleDate::JulianNoLeap(day) => {
+                january_1st + day - 1 + (is_leap_year(year) && day > 59) as i64
+            }
+            RuleDate::Julian(day) => january_1st + day,
+            RuleDate::MonthWeekDay {
+                month,
+                week,
+                weekday,
+            } => {
+                let first = days_from_civil(year, month, 1);
+                let first_match = first + (weekday - weekday_from_days(first)).rem_euclid(7);
+                let mut day = first_match + (week - 1) * 7;
+                while day >= first + days_in_month(year, month) {
+                    day -= 7;
+                }
+                day
+            }
+        }
+    }
+}
+
+impl Rule {
+    fn local_time_type(&self, time: i64) -> &LocalTimeType {
+        let Some(ref dst) = self.daylight_saving else {
+            return &self.standard;
+        };
+        let (year, _, _) = civil_from_days(time.div_euclid(DAY_SECONDS));
+        let start = dst.start.0.days_in_year(year) * DAY_SECONDS + dst.start.1
+            - self.standard.utc_offset as i64;
+        let end = dst.end.0.days_in_year(year) * DAY_SECONDS + dst.end.1
+            - dst.local_time_type.utc_offset as i64;
+        // In the southern hemisphere, daylight saving time spans the new year.
+        let in_dst = if start < end {
+            start <= time && time < end
+        } else {
+            !(end <= time && time < start)
+        };
+        if in_dst {
+            &dst.local_time_type
+        } else {
+            &self.standard
+        }
+    }
+}
+
+/// Parser for the POSIX `TZ` syntax.
+struct RuleParser<'a> {
+    string: &'a [u8],
+    pos: usize,
+}
+
+impl RuleParser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.string.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, String> {
+        let start = self.pos;
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.string[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| format!("Expected number at position {}", start))
+    }
+
+    /// Time zone abbreviation, either alphabetic or quoted with `<>`.
+    fn abbreviation(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        let abbreviation = if self.eat(b'<') {
+            while self.peek().map_or(false, |c| c != b'>') {
+                self.pos += 1;
+            }
+            let abbreviation = &self.string[start + 1..self.pos];
+            if !self.eat(b'>') {
+                return Err("Unterminated <> in abbreviation".to_string());
+            }
+            abbreviation
+        } else {
+            while self.peek().map_or(false, |c| c.is_ascii_alphabetic()) {
+                self.pos += 1;
+            }
+            &self.string[start..self.pos]
+        };
+        if abbreviation.len() < 3 {
+            return Err(format!("Bad abbreviation at position {}", start));
+        }
+        Ok(String::from_utf8_lossy(abbreviation).into_owned())
+    }
+
+    /// `[+|-]hh[:mm[:ss]]`, in seconds.
+    fn time(&mut self) -> Result<i64, String> {
+        let sign = if self.eat(b'-') {
+            -1
+        } else {
+            self.eat(b'+');
+            1
+        };
+        let mut seconds = self.number()? * HOUR_SECONDS;
+        if self.eat(b':') {
+            seconds += self.number()? * MINUTE_SECONDS;
+            if self.eat(b':') {
+                seconds += self.number()?;
+            }
+        }
+        Ok(sign * seconds)
+    }
+
+    /// A date and an optional time (default 02:00:00), for the start or end of
+    /// daylight saving time.
+    fn date_and_time(&mut self) -> Result<(RuleDate, i64), String> {
+        let date = if self.eat(b'J') {
+            RuleDate::JulianNoLeap(self.number()?)
+        } else if self.eat(b'M') {
+            let month = self.number()?;
+            if !self.eat(b'.') {
+                return Err("Expected . after month".to_string());
+            }
+            let week = self.number()?;
+            if !self.eat(b'.') {
+                return Err("Expected . after week".to_string());
+            }
+            let weekday = self.number()?;
+            if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+                return Err("Month, week or day of week out of range".to_string());
+            }
+            RuleDate::MonthWeekDay {
+                month,
+                week,
+                weekday,
+            }
+        } else {
+            RuleDate::Julian(self.number()?)
+        };
+        let time = if self.eat(b'/') {
+            self.time()?
+        } else {
+            2 * HOUR_SECONDS
+        };
+        Ok((date, time))
+    }
+}
+
+impl Rule {
+    fn parse(string: &str) -> Result<Rule, String> {
+        let mut parser = RuleParser {
+            string: string.as_bytes(),
+            pos: 0,
+        };
+        let standard_abbreviation = parser.abbreviation()?;
+        // POSIX offsets are the opposite way around to everything else.
+        let standard = LocalTimeType {
+            utc_offset: -parser.time()? as i32,
+            is_dst: false,
+            abbreviation: standard_abbreviation,
+        };
+        if parser.peek().is_none() {
+            return Ok(Rule {
+                standard,
+                daylight_saving: None,
+            });
+        }
+
+        let abbreviation = parser.abbreviation()?;
+        let utc_offset = match parser.peek() {
+            Some(b',') | None => standard.utc_offset + HOUR_SECONDS as i32,
+            _ => -parser.time()? as i32,
+        };
+        let (start, end) = if parser.eat(b',') {
+            let start = parser.date_and_time()?;
+            if !parser.eat(b',') {
+                return Err("Expected end of daylight saving time".to_string());
+            }
+            (start, parser.date_and_time()?)
+        } else {
+            // Traditional US rules are the default.
+            let start = RuleDate::MonthWeekDay {
+                month: 3,
+                week: 2,
+                weekday: 0,
+            };
+            let end = RuleDate::MonthWeekDay {
+                month: 11,
+                week: 1,
+                weekday: 0,
+            };
+            ((start, 2 * HOUR_SECONDS), (end, 2 * HOUR_SECONDS))
+        };
+        if parser.peek().is_some() {
+            return Err(format!("Unexpected data at position {}", parser.pos));
+        }
+        Ok(Rule {
+            standard,
+            daylight_saving: Some(DaylightSavingRule {
+                local_time_type: LocalTimeType {
+                    utc_offset,
+                    is_dst: true,
+                    abbreviation,
+                },
+                start,
+                end,
+            }),
+        })
+    }
+}
+
+/// Reader for the big-endian fields of a TZif file.
+struct TzifReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> TzifReader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..)
+            .and_then(|rest| rest.get(..count))
+            .ok_or_else(|| "Unexpected end of file".to_string())?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn time(&mut self, size: usize) -> Result<i64, String> {
+        let bytes = self.bytes(size)?;
+        Ok(if size == 4 {
+            i32::from_be_bytes(bytes.try_into().unwrap()) as i64
+        } else {
+            i64::from_be_bytes(bytes.try_into().unwrap())
+        })
+    }
+}
+
+/// Counts from a TZif header.
+struct TzifHeader {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl TzifHeader {
+    fn read(reader: &mut TzifReader) -> Result<TzifHeader, String> {
+        if reader.bytes(4)? != b"TZif" {
+            return Err("Not a TZif file".to_string());
+        }
+        let version = reader.u8()?;
+        reader.bytes(15)?; // reserved
+        Ok(TzifHeader {
+            version,
+            isutcnt: reader.u32()? as usize,
+            isstdcnt: reader.u32()? as usize,
+            leapcnt: reader.u32()? as usize,
+            timecnt: reader.u32()? as usize,
+            typecnt: reader.u32()? as usize,
+            charcnt: reader.u32()? as usize,
+        })
+    }
+
+    fn data_block_size(&self, time_size: usize) -> usize {
+        self.timecnt * (time_size + 1)
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+impl TimeZone {
+    /// Parse the contents of a TZif file.
+    fn parse_tzif(name: String, data: &[u8]) -> Result<TimeZone, String> {
+        let mut reader = TzifReader { data, pos: 0 };
+        let mut header = TzifHeader::read(&mut reader)?;
+        let mut time_size = 4;
+        if header.version >= b'2' {
+            // Version 2 and later files have a second header and data block
+            // with 64-bit times, which is all we need.
+            reader.bytes(header.data_block_size(4))?;
+            header = TzifHeader::read(&mut reader)?;
+            time_size = 8;
+        }
+        if header.typecnt == 0 {
+            return Err("No local time types".to_string());
+        }
+
+        let mut times = Vec::with_capacity(header.timecnt);
+        for _ in 0..header.timecnt {
+            times.push(reader.time(time_size)?);
+        }
+        let mut transitions = Vec::with_capacity(header.timecnt);
+        for time in times {
+            let index = reader.u8()? as usize;
+            if index >= header.typecnt {
+                return Err("Bad local time type index".to_string());
+            }
+            transitions.push((time, index));
+        }
+        let mut records = Vec::with_capacity(header.typecnt);
+        for _ in 0..header.typecnt {
+            let utc_offset = reader.u32()? as i32;
+            let is_dst = reader.u8()? != 0;
+            let abbreviation_index = reader.u8()? as usize;
+            records.push((utc_offset, is_dst, abbreviation_index));
+        }
+        let abbreviations = reader.bytes(header.charcnt)?;
+        let types = records
+            .into_iter()
+            .map(|(utc_offset, is_dst, abbreviation_index)| {
+                let abbreviation = abbreviations.get(abbreviation_index..).unwrap_or(&[]);
+                let end = abbreviation
+                    .iter()
+                    .position(|&c| c == b'\0')
+                    .unwrap_or(abbreviation.len());
+                LocalTimeType {
+                    utc_offset,
+                    is_dst,
+                    abbreviation: String::from_utf8_lossy(&abbreviation[..end]).into_owned(),
+                }
+            })
+            .collect();
+        // Leap seconds aren't used by the usual "right"-less zones and the
+        // standard/wall and UT/local indicators only matter for the rule at
+        // the end, so the rest of the data block can be skipped.
+        reader.bytes(header.leapcnt * (time_size + 4) + header.isstdcnt + header.isutcnt)?;
+
+        let mut rule = None;
+        if header.version >= b'2' && reader.u8()? == b'\n' {
+            let footer = &data[reader.pos..];
+            let end = footer.iter().position(|&c| c == b'\n').unwrap_or(0);
+            let footer = std::str::from_utf8(&footer[..end])
+                .map_err(|_| "Footer is not valid UTF-8".to_string())?;
+            if !footer.is_empty() {
+                rule = Some(Rule::parse(footer).map_err(|e| format!("Bad footer: {}", e))?);
+            }
+        }
+
+        Ok(TimeZone {
+            name,
+            transitions,
+            types,
+            rule,
+        })
+    }
+
+    fn from_rule(name: String, rule: Rule) -> TimeZone {
+        TimeZone {
+            name,
+            transitions: Vec::new(),
+            types: vec![rule.standard.clone()],
+            rule: Some(rule),
+        }
+    }
+
+    /// Time zone with a constant offset from UTC (in seconds), named like
+    /// `GMT+0100` (or just `GMT` for an offset of 0).
+    pub fn with_offset(utc_offset: i32) -> TimeZone {
+        let name = if utc_offset == 0 {
+            "GMT".to_string()
+        } else {
+            let sign = if utc_offset < 0 { '-' } else { '+' };
+            let minutes = utc_offset.unsigned_abs() / 60;
+            format!("GMT{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+        };
+        let rule = Rule {
+            standard: LocalTimeType {
+                utc_offset,
+                is_dst: false,
+                abbreviation: name.clone(),
+            },
+            daylight_saving: None,
+        };
+        TimeZone::from_rule(name, rule)
+    }
+
+    pub fn utc() -> TimeZone {
+        let rule = Rule {
+            standard: LocalTimeType {
+                utc_offset: 0,
+                is_dst: false,
+                abbreviation: "UTC".to_string(),
+            },
+            daylight_saving: None,
+        };
+        TimeZone::from_rule("UTC".to_string(), rule)
+    }
+
+    /// Find a time zone by its IANA name (e.g. `Europe/Stockholm`), or by an
+    /// offset from UTC written like `UTC+01:00` or `GMT-5`.
+    pub fn load(name: &str) -> Result<TimeZone, String> {
+        if let Some(offset) = name
+            .strip_prefix("UTC")
+            .or_else(|| name.strip_prefix("GMT"))
+            .filter(|offset| offset.starts_with(['+', '-']))
+        {
+            let mut parser = RuleParser {
+                string: offset.as_bytes(),
+                pos: 0,
+            };
+            let utc_offset = parser.time();
+            return match utc_offset {
+                Ok(utc_offset) if parser.peek().is_none() && utc_offset.abs() <= DAY_SECONDS => {
+                    Ok(TimeZone::with_offset(utc_offset as i32))
+                }
+                _ => Err(format!("Invalid offset from UTC in {:?}", name)),
+            };
+        }
+
+        // Don't let the name escape the database directory.
+        let valid = !name.is_empty()
+            && name.split('/').all(|part| !part.is_empty() && part != "..")
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "/_+-".contains(c));
+        if valid {
+            for dir in ZONEINFO_DIRS {
+                let Ok(data) = std::fs::read(Path::new(dir).join(name)) else {
+                    continue;
+                };
+                return TimeZone::parse_tzif(name.to_string(), &data)
+                    .map_err(|e| format!("Could not parse time zone {:?}: {}", name, e));
+            }
+        }
+        match name {
+            "UTC" | "Etc/UTC" | "Universal" | "Zulu" => Ok(TimeZone::utc()),
+            "GMT" | "Etc/GMT" => Ok(TimeZone::with_offset(0)),
+            _ => Err(format!("Unknown time zone {:?}", name)),
+        }
+    }
+
+    /// Get the host's time zone, using the same sources as the C library would
+    /// on a Unix-like system. Falls back to UTC.
+    pub fn host() -> TimeZone {
+        match TimeZone::try_host() {
+            Ok(time_zone) => time_zone,
+            Err(e) => {
+                log!(
+                    "Warning: Could not determine your time zone ({}), using UTC. Use --time-zone= to choose one.",
+                    e
+                );
+                TimeZone::utc()
+            }
+        }
+    }
+
+    fn try_host() -> Result<TimeZone, String> {
+        if let Ok(tz) = std::env::var("TZ") {
+            let tz = tz.strip_prefix(':').unwrap_or(&tz);
+            if Path::new(tz).is_absolute() {
+                let data = std::fs::read(tz).map_err(|e| format!("{:?}: {}", tz, e))?;
+                return TimeZone::parse_tzif(zone_name_from_path(Path::new(tz)), &data);
+            }
+            return TimeZone::load(tz).or_else(|e| {
+                // Maybe it's a rule rather than a name, e.g. "CET-1CEST".
+                Rule::parse(tz)
+                    .map(|rule| TimeZone::from_rule(tz.to_string(), rule))
+                    .map_err(|_| e)
+            });
+        }
+
+        if let Ok(data) = std::fs::read("/etc/localtime") {
+            let name = std::fs::read_link("/etc/localtime")
+                .ok()
+                .map(|path| zone_name_from_path(&path))
+                .or_else(|| {
+                    std::fs::read_to_string("/etc/timezone")
+                        .ok()
+                        .map(|name| name.trim().to_string())
+                })
+                .unwrap_or_else(|| "Local".to_string());
+            return TimeZone::parse_tzif(name, &data);
+        }
+
+        Err("no TZ environment variable or /etc/localtime".to_string())
+    }
+
+    /// Name, e.g. `Europe/Stockholm`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the offset from UTC etc that applies at a time (seconds since the
+    /// UNIX epoch).
+    pub fn local_time_type(&self, time: i64) -> &LocalTimeType {
+        let index = self.transitions.partition_point(|&(t, _)| t <= time);
+        if index == self.transitions.len() {
+            if let Some(ref rule) = self.rule {
+                return rule.local_time_type(time);
+            }
+        }
+        match index {
+            0 => &self.types[0],
+            _ => &self.types[self.transitions[index - 1].1],
+        }
+    }
+
+    /// Get the offset from UTC to subtract from a local time (seconds since
+    /// 1970-01-01 00:00:00 local time) to get seconds since the UNIX epoch.
+    ///
+    /// Around changes of offset, a local time can happen twice or not at all.
+    /// If it happens twice, `is_dst` chooses which, if specified. If it doesn't
+    /// happen, the offset from before the change is used, so e.g. 02:30 on a
+    /// day when clocks go forward from 02:00 to 03:00 is treated as 03:30.
+    pub fn utc_offset_for_local_time(&self, local_time: i64, is_dst: Option<bool>) -> i32 {
+        let candidates = [
+            self.local_time_type(local_time - DAY_SECONDS),
+            self.local_time_type(local_time),
+            self.local_time_type(local_time + DAY_SECONDS),
+        ];
+        let valid = |candidate: &&&LocalTimeType| {
+            let offset = candidate.utc_offset;
+            self.local_time_type(local_time - offset as i64).utc_offset == offset
+        };
+        let preferred = candidates
+            .iter()
+            .filter(valid)
+            .find(|candidate| is_dst.map_or(true, |is_dst| candidate.is_dst == is_dst));
+        match preferred.or_else(|| candidates.iter().find(valid)) {
+            Some(candidate) => candidate.utc_offset,
+            None => candidates[0].utc_offset,
+        }
+    }
+
+    /// Break down a time (seconds since the UNIX epoch) into the local date and
+    /// time.
+    pub fn date_time(&self, time: f64) -> DateTime {
+        // Keep absurd dates (about three million years away) from overflowing.
+        let time = time.clamp(-1e14, 1e14);
+        let whole_seconds = time.floor();
+        let fraction = time - whole_seconds;
+        let whole_seconds = whole_seconds as i64;
+        let local_time_type = self.local_time_type(whole_seconds).clone();
+        let local = whole_seconds + local_time_type.utc_offset as i64;
+        let days = local.div_euclid(DAY_SECONDS);
+        let second_in_day = local.rem_euclid(DAY_SECONDS);
+        let (year, month, day) = civil_from_days(days);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: second_in_day / HOUR_SECONDS,
+            minute: (second_in_day % HOUR_SECONDS) / MINUTE_SECONDS,
+            second: second_in_day % MINUTE_SECONDS,
+            fraction,
+            weekday: weekday_from_days(days),
+            day_of_year: days - days_from_civil(year, 1, 1),
+            local_time_type,
+        }
+    }
+}
+
+/// Get the name of a time zone from the path of its file in the database, e.g.
+/// `/usr/share/zoneinfo/Europe/Stockholm` becomes `Europe/Stockholm`.
+fn zone_name_from_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match path.split_once("zoneinfo/") {
+        Some((_, name)) => name.to_string(),
+        None => path.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a TZif file. Version 1 files only have the 32-bit data block.
+    /// Later versions get an empty 32-bit block (so that reading it instead of
+    /// the 64-bit one would be noticed) and a footer.
+    fn tzif(
+        version: u8,
+        transitions: &[(i64, u8)],
+        types: &[(i32, bool, u8)],
+        abbreviations: &[u8],
+        footer: &str,
+    ) -> Vec<u8> {
+        fn header(data: &mut Vec<u8>, version: u8, timecnt: usize, typecnt: usize, charcnt: usize) {
+            data.extend_from_slice(b"TZif");
+            data.push(version);
+            data.extend_from_slice(&[0; 15]);
+            // isutcnt, isstdcnt, leapcnt
+            data.extend_from_slice(&[0; 12]);
+            for count in [timecnt, typecnt, charcnt] {
+                data.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+        }
+
+        let mut data = Vec::new();
+        if version == 0 {
+            header(
+                &mut data,
+                version,
+                transitions.len(),
+                types.len(),
+                abbreviations.len(),
+            );
+            for &(time, _) in transitions {
+                data.extend_from_slice(&(time as i32).to_be_bytes());
+            }
+        } else {
+            header(&mut data, version, 0, 1, 4);
+            data.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+            data.extend_from_slice(b"LMT\0");
+            header(
+                &mut data,
+                version,
+                transitions.len(),
+                types.len(),
+                abbreviations.len(),
+            );
+            for &(time, _) in transitions {
+                data.extend_from_slice(&time.to_be_bytes());
+            }
+        }
+        for &(_, index) in transitions {
+            data.push(index);
+        }
+        for &(utc_offset, is_dst, abbreviation_index) in types {
+            data.extend_from_slice(&utc_offset.to_be_bytes());
+            data.push(is_dst as u8);
+            data.push(abbreviation_index);
+        }
+        data.extend_from_slice(abbreviations);
+        if version != 0 {
+            data.push(b'\n');
+            data.extend_from_slice(footer.as_bytes());
+            data.push(b'\n');
+        }
+        data
+    }
+
+    fn rule_zone(rule: &str) -> TimeZone {
+        TimeZone::from_rule(rule.to_string(), Rule::parse(rule).unwrap())
+    }
+
+    fn utc_time(year: i64, month: i64, day: i64, hour: i64, minute: i64, second: i64) -> i64 {
+        seconds_from_civil(year, month, day, hour, minute, second)
+    }
+
+    #[test]
+    fn test_civil_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2024, 3, 0), days_from_civil(2024, 2, 29));
+        for days in [-719468, -1, 0, 11016, 11017, 19782, 2932896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(19782), (2024, 2, 29));
+        assert_eq!(weekday_from_days(0), 4);
+        assert_eq!(weekday_from_days(-1), 3);
+        // 2009-02-13 23:31:30 UTC
+        assert_eq!(utc_time(2009, 2, 13, 23, 31, 30), 1234567890);
+        assert_eq!(utc_time(2008, 14, 13, 23, 31, 30), 1234567890);
+        assert_eq!(utc_time(2009, 2, 14, 0, -28, -30), 1234567890);
+    }
+
+    #[test]
+    fn test_parse_tzif_v1() {
+        let data = tzif(
+            0,
+            &[(-1000, 1), (1000, 2)],
+            &[(0, false, 0), (3600, false, 4), (7200, true, 8)],
+            b"LMT\0ABC\0ABD\0",
+            "",
+        );
+        let time_zone = TimeZone::parse_tzif("Test/V1".to_string(), &data).unwrap();
+        assert_eq!(time_zone.name(), "Test/V1");
+        assert_eq!(time_zone.local_time_type(-1001).abbreviation, "LMT");
+        assert_eq!(time_zone.local_time_type(-1000).utc_offset, 3600);
+        assert_eq!(time_zone.local_time_type(999).abbreviation, "ABC");
+        let last = time_zone.local_time_type(1000);
+        assert_eq!(
+            last,
+            &LocalTimeType {
+                utc_offset: 7200,
+                is_dst: true,
+                abbreviation: "ABD".to_string(),
+            }
+        );
+        // Without a footer, the last type applies forever.
+        assert_eq!(time_zone.local_time_type(i64::MAX), last);
+    }
+
+    #[test]
+    fn test_parse_tzif_v2() {
+        // Europe/Stockholm, abridged: the 2023 changes, then the rule.
+        let data = tzif(
+            b'2',
+            &[(1679792400, 1), (1698541200, 0)],
+            &[(3600, false, 0), (7200, true, 4)],
+            b"CET\0CEST\0",
+            "CET-1CEST,M3.5.0,M10.5.0/3",
+        );
+        let time_zone = TimeZone::parse_tzif("Europe/Stockholm".to_string(), &data).unwrap();
+        // The 64-bit data block was used, not the empty 32-bit one.
+        assert_eq!(time_zone.local_time_type(0).abbreviation, "CET");
+        assert_eq!(time_zone.local_time_type(1679792399).utc_offset, 3600);
+        assert_eq!(time_zone.local_time_type(1679792400).abbreviation, "CEST");
+        assert_eq!(time_zone.local_time_type(1698541200).abbreviation, "CET");
+        // After the last transition, the footer applies: 2024-03-31 01:00 UTC.
+        let start = utc_time(2024, 3, 31, 1, 0, 0);
+        assert_eq!(time_zone.local_time_type(start - 1).utc_offset, 3600);
+        assert_eq!(time_zone.local_time_type(start).utc_offset, 7200);
+        assert!(time_zone.local_time_type(start).is_dst);
+
+        // A version 3 file with an empty footer has no rule.
+        let data = tzif(
+            b'3',
+            &[(0, 1)],
+            &[(0, false, 0), (3600, false, 0)],
+            b"XXX\0",
+            "",
+        );
+        let time_zone = TimeZone::parse_tzif("Test/V3".to_string(), &data).unwrap();
+        assert_eq!(time_zone.local_time_type(i64::MAX).utc_offset, 3600);
+    }
+
+    #[test]
+    fn test_parse_tzif_errors() {
+        let data = tzif(0, &[(0, 0)], &[(0, false, 0)], b"UTC\0", "");
+        assert!(TimeZone::parse_tzif(String::new(), &data).is_ok());
+        assert!(TimeZone::parse_tzif(String::new(), &data[..data.len() - 1]).is_err());
+        assert!(TimeZone::parse_tzif(String::new(), b"TZjf").is_err());
+        assert!(TimeZone::parse_tzif(String::new(), b"").is_err());
+        // Local time type index out of range.
+        let data = tzif(0, &[(0, 1)], &[(0, false, 0)], b"UTC\0", "");
+        assert!(TimeZone::parse_tzif(String::new(), &data).is_err());
+        // No local time types.
+        let data = tzif(0, &[], &[], b"", "");
+        assert!(TimeZone::parse_tzif(String::new(), &data).is_err());
+        let data = tzif(b'2', &[], &[(0, false, 0)], b"UTC\0", "UTC0,");
+        assert!(TimeZone::parse_tzif(String::new(), &data).is_err());
+    }
+
+    #[test]
+    fn test_rule_us() {
+        let time_zone = rule_zone("EST5EDT,M3.2.0/2,M11.1.0");
+        // 2024-03-10 02:00 EST and 2024-11-03 02:00 EDT.
+        let start = utc_time(2024, 3, 10, 7, 0, 0);
+        let end = utc_time(2024, 11, 3, 6, 0, 0);
+        let est = LocalTimeType {
+            utc_offset: -5 * 3600,
+            is_dst: false,
+            abbreviation: "EST".to_string(),
+        };
+        let edt = LocalTimeType {
+            utc_offset: -4 * 3600,
+            is_dst: true,
+            abbreviation: "EDT".to_string(),
+        };
+        assert_eq!(time_zone.local_time_type(start - 1), &est);
+        assert_eq!(time_zone.local_time_type(start), &edt);
+        assert_eq!(time_zone.local_time_type(end - 1), &edt);
+        assert_eq!(time_zone.local_time_type(end), &est);
+
+        // The rule without dates defaults to the same dates and times, and
+        // daylight saving time defaults to an hour ahead.
+        let default = rule_zone("EST5EDT");
+        for time in [start - 1, start, end - 1, end] {
+            assert_eq!(
+                default.local_time_type(time),
+                time_zone.local_time_type(time)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rule_southern_hemisphere() {
+        let time_zone = rule_zone("NZST-12NZDT,M9.5.0,M4.1.0/3");
+        // 2024-04-07 03:00 NZDT and 2024-09-29 02:00 NZST.
+        let end = utc_time(2024, 4, 6, 14, 0, 0);
+        let start = utc_time(2024, 9, 28, 14, 0, 0);
+        assert_eq!(
+            time_zone
+                .local_time_type(utc_time(2024, 1, 1, 0, 0, 0))
+                .utc_offset,
+            13 * 3600
+        );
+        assert_eq!(time_zone.local_time_type(end - 1).abbreviation, "NZDT");
+        assert_eq!(time_zone.local_time_type(end).abbreviation, "NZST");
+        assert_eq!(time_zone.local_time_type(start - 1).utc_offset, 12 * 3600);
+        assert_eq!(time_zone.local_time_type(start).utc_offset, 13 * 3600);
+    }
+
+    #[test]
+    fn test_rule_julian_dates() {
+        // J60 is always March 1, while 59 (counting from 0) is February 29 in a
+        // leap year.
+        for year in [2023, 2024] {
+            assert_eq!(
+                RuleDate::JulianNoLeap(60).days_in_year(year),
+                days_from_civil(year, 3, 1)
+            );
+        }
+        assert_eq!(
+            RuleDate::JulianNoLeap(59).days_in_year(2024),
+            days_from_civil(2024, 2, 28)
+        );
+        assert_eq!(
+            RuleDate::Julian(59).days_in_year(2023),
+            days_from_civil(2023, 3, 1)
+        );
+        assert_eq!(
+            RuleDate::Julian(59).days_in_year(2024),
+            days_from_civil(2024, 2, 29)
+        );
+
+        let time_zone = rule_zone("AAA3BBB,J60/0,300/-1");
+        let start = utc_time(2024, 3, 1, 3, 0, 0);
+        assert_eq!(time_zone.local_time_type(start - 1).abbreviation, "AAA");
+        assert_eq!(time_zone.local_time_type(start).abbreviation, "BBB");
+        // Day 300 counting from 0 is October 27 in a leap year, and the
+        // negative time means 23:00 the day before, in daylight saving time.
+        let end = utc_time(2024, 10, 26, 23 + 2, 0, 0);
+        assert_eq!(time_zone.local_time_type(end - 1).utc_offset, -2 * 3600);
+        assert_eq!(time_zone.local_time_type(end).utc_offset, -3 * 3600);
+    }
+
+    #[test]
+    fn test_rule_offsets() {
+        // POSIX offsets are west of Greenwich, so negative ones are east.
+        let rule = Rule::parse("<+0330>-3:30").unwrap();
+        assert_eq!(rule.standard.utc_offset, 3 * 3600 + 30 * 60);
+        assert_eq!(rule.standard.abbreviation, "+0330");
+        assert!(rule.daylight_saving.is_none());
+        let rule = Rule::parse("<-03>+3").unwrap();
+        assert_eq!(rule.standard.utc_offset, -3 * 3600);
+        assert_eq!(rule.standard.abbreviation, "-03");
+        let rule = Rule::parse("IST-1GMT0,M10.5.0,M3.5.0/1").unwrap();
+        assert_eq!(rule.standard.utc_offset, 3600);
+        assert_eq!(rule.daylight_saving.unwrap().local_time_type.utc_offset, 0);
+        let rule = Rule::parse("XXX-5:45:30").unwrap();
+        assert_eq!(rule.standard.utc_offset, 5 * 3600 + 45 * 60 + 30);
+
+        for bad in [
+            "",
+            "EST",
+            "ES5",
+            "<EST5",
+            "EST5EDT,M3.2.0",
+            "EST5EDT,M13.2.0,M11.1.0",
+            "EST5EDT,M3.6.0,M11.1.0",
+            "EST5EDT,M3.2.7,M11.1.0",
+            "EST5EDT,M3.2.0,M11.1.0,",
+        ] {
+            assert!(Rule::parse(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_utc_offset_for_local_time() {
+        let time_zone = rule_zone("EST5EDT,M3.2.0,M11.1.0");
+        let est = -5 * 3600;
+        let edt = -4 * 3600;
+
+        let local = utc_time(2024, 1, 15, 12, 0, 0);
+        assert_eq!(time_zone.utc_offset_for_local_time(local, None), est);
+        assert_eq!(time_zone.utc_offset_for_local_time(local, Some(true)), est);
+        let local = utc_time(2024, 7, 15, 12, 0, 0);
+        assert_eq!(time_zone.utc_offset_for_local_time(local, None), edt);
+        assert_eq!(time_zone.utc_offset_for_local_time(local, Some(false)), edt);
+
+        // Clocks go forward from 02:00 to 03:00, so 02:30 is skipped, and is
+        // treated as 03:30.
+        let skipped = utc_time(2024, 3, 10, 2, 30, 0);
+        assert_eq!(time_zone.utc_offset_for_local_time(skipped, None), est);
+        assert_eq!(
+            time_zone.utc_offset_for_local_time(skipped, Some(true)),
+            est
+        );
+        let date_time = time_zone.date_time((skipped - est as i64) as f64);
+        assert_eq!((date_time.hour, date_time.minute), (3, 30));
+        // Either side of the gap is unambiguous.
+        let before = utc_time(2024, 3, 10, 1, 59, 59);
+        assert_eq!(time_zone.utc_offset_for_local_time(before, None), est);
+        let after = utc_time(2024, 3, 10, 3, 0, 0);
+        assert_eq!(time_zone.utc_offset_for_local_time(after, None), edt);
+
+        // Clocks go back from 02:00 to 01:00, so 01:30 happens twice.
+        let repeated = utc_time(2024, 11, 3, 1, 30, 0);
+        assert_eq!(time_zone.utc_offset_for_local_time(repeated, None), edt);
+        assert_eq!(
+            time_zone.utc_offset_for_local_time(repeated, Some(true)),
+            edt
+        );
+        assert_eq!(
+            time_zone.utc_offset_for_local_time(repeated, Some(false)),
+            est
+        );
+        let before = utc_time(2024, 11, 3, 0, 59, 59);
+        assert_eq!(
+            time_zone.utc_offset_for_local_time(before, Some(false)),
+            edt
+        );
+        let after = utc_time(2024, 11, 3, 2, 0, 0);
+        assert_eq!(time_zone.utc_offset_for_local_time(after, Some(true)), est);
+    }
+
+    #[test]
+    fn test_date_time() {
+        let time_zone = rule_zone("EST5EDT,M3.2.0,M11.1.0");
+        let date_time = time_zone.date_time(utc_time(2024, 3, 10, 7, 0, 0) as f64 + 0.25);
+        assert_eq!(
+            (date_time.year, date_time.month, date_time.day),
+            (2024, 3, 10)
+        );
+        assert_eq!(
+            (date_time.hour, date_time.minute, date_time.second),
+            (3, 0, 0)
+        );
+        assert_eq!(date_time.fraction, 0.25);
+        assert_eq!(date_time.weekday, 0);
+        assert_eq!(date_time.day_of_year, 31 + 29 + 9);
+        assert_eq!(date_time.local_time_type.abbreviation, "EDT");
+
+        let date_time = TimeZone::utc().date_time(-0.5);
+        assert_eq!(
+            (
+                date_time.year,
+                date_time.month,
+                date_time.day,
+                date_time.second
+            ),
+            (1969, 12, 31, 59)
+        );
+        assert_eq!(date_time.fraction, 0.5);
+    }
+
+    #[test]
+    fn test_load_offsets() {
+        assert_eq!(TimeZone::with_offset(0).name(), "GMT");
+        assert_eq!(TimeZone::with_offset(3600).name(), "GMT+0100");
+        assert_eq!(
+            TimeZone::with_offset(-(9 * 3600 + 30 * 60)).name(),
+            "GMT-0930"
+        );
+        let time_zone = TimeZone::load("UTC+01:00").unwrap();
+        assert_eq!(time_zone.local_time_type(0).utc_offset, 3600);
+        let time_zone = TimeZone::load("GMT-5").unwrap();
+        assert_eq!(time_zone.local_time_type(0).utc_offset, -5 * 3600);
+        assert!(TimeZone::load("UTC+25").is_err());
+        assert!(TimeZone::load("UTC+01:00x").is_err());
+        assert!(TimeZone::load("../../../etc/passwd").is_err());
+        assert!(TimeZone::load("/etc/passwd").is_err());
+        assert_eq!(
+            TimeZone::load("Etc/UTC")
+                .unwrap()
+                .local_time_type(0)
+                .utc_offset,
+            0
+        );
+    }
+}
@@ -13,21 +13,26 @@
 //! window system interaction in general, because it is assumed only one window
 //! will be needed for the runtime of the app.
 
+mod capture;
 mod gl;
 mod matrix;
+mod replay;
 
 pub use gl::{gl21compat, gl32core, gles11, GLContext, GLVersion};
 pub use matrix::Matrix;
 
+use crate::clock::Clock;
 use crate::image::Image;
-use crate::options::Options;
+use crate::options::{Button, Options};
+use sdl2::keyboard::{Keycode, Mod};
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::surface::Surface;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
 use std::f32::consts::FRAC_PI_2;
 use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum DeviceOrientation {
@@ -71,12 +76,152 @@ fn set_sdl2_orientation(orientation: DeviceOrientation) {
     );
 }
 
-#[derive(Debug)]
+/// Identifies a finger touching the screen, so that the events for
+/// simultaneous touches can be told apart.
+pub type FingerId = u32;
+
+/// Finger used for the left mouse button.
+const MOUSE_FINGER: FingerId = 0;
+/// Finger used for the second touch that can be simulated with the mouse. See
+/// [SecondMouseFinger].
+const SECOND_MOUSE_FINGER: FingerId = 1;
+/// Fingers for touches from the host's touchscreen, from the analog
+/// stick-controlled virtual cursors, and mapped to controller buttons and
+/// keyboard keys are allocated from this number upwards, so that several can
+/// be held at once.
+const FIRST_ALLOCATED_FINGER: FingerId = 2;
+
+/// Hotkey for saving a screenshot of the app's output, see [capture].
+const SCREENSHOT_KEYCODE: Keycode = Keycode::F12;
+/// Hotkeys for controlling the emulation speed, see [Clock].
+const PAUSE_KEYCODE: Keycode = Keycode::F5;
+const ADVANCE_FRAME_KEYCODE: Keycode = Keycode::F6;
+const SLOW_MOTION_KEYCODE: Keycode = Keycode::F7;
+const FAST_FORWARD_KEYCODE: Keycode = Keycode::F8;
+/// Hotkeys for saving and loading the save state, see [crate::snapshot].
+const SAVE_STATE_KEYCODE: Keycode = Keycode::F2;
+const LOAD_STATE_KEYCODE: Keycode = Keycode::F3;
+
+/// `SDL_TOUCH_MOUSEID`: the mouse ID of mouse events SDL synthesizes from
+/// touches.
+const SDL_TOUCH_MOUSEID: u32 = u32::MAX;
+/// `SDL_MOUSE_TOUCHID`: the touch device ID of touch events SDL synthesizes
+/// from mouse input.
+const SDL_MOUSE_TOUCHID: i64 = -1;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Event {
     Quit,
-    TouchDown((f32, f32)),
-    TouchMove((f32, f32)),
-    TouchUp((f32, f32)),
+    TouchDown(FingerId, (f32, f32)),
+    TouchMove(FingerId, (f32, f32)),
+    TouchUp(FingerId, (f32, f32)),
+}
+
+/// Save state hotkey that was pressed, see [Window::take_state_request].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StateRequest {
+    Save,
+    Load,
+}
+
+/// Keyboard key, for the `--key-to-touch=` etc options.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Key(Keycode);
+impl Key {
+    /// Look up a key by its SDL name, e.g. `Space`, `Left` or `W`.
+    pub fn from_name(name: &str) -> Option<Key> {
+        Keycode::from_name(name).map(Key)
+    }
+}
+
+/// How long a `--key-to-drag=` drag takes to get from its start point to its
+/// end point.
+const KEY_DRAG_DURATION: Duration = Duration::from_millis(150);
+
+/// A drag in progress for `--key-to-drag=`. Co-ordinates are in the same space
+/// as the option's.
+struct KeyDrag {
+    finger: FingerId,
+    from: (f32, f32),
+    to: (f32, f32),
+    start: Instant,
+    finished: bool,
+}
+impl KeyDrag {
+    fn position(&self) -> (f32, f32) {
+        let progress = self.start.elapsed().as_secs_f32() / KEY_DRAG_DURATION.as_secs_f32();
+        let progress = progress.min(1.0);
+        (
+            self.from.0 + (self.to.0 - self.from.0) * progress,
+            self.from.1 + (self.to.1 - self.from.1) * progress,
+        )
+    }
+}
+
+/// A connected game controller, and the state of the virtual cursor controlled
+/// by its right analog stick.
+struct Controller {
+    controller: sdl2::controller::GameController,
+    /// On-screen position, press state and visibility of the virtual cursor as
+    /// of the last update.
+    virtual_cursor_last: Option<(f32, f32, bool, bool)>,
+    /// Finger touching the screen while the virtual cursor is pressed.
+    virtual_cursor_finger: Option<FingerId>,
+}
+
+/// Get the X and Y position and button state of the left or right analog stick
+/// of a game controller. Each axis value is in the range [-1, 1].
+fn get_stick(
+    controller: &sdl2::controller::GameController,
+    options: &Options,
+    left: bool,
+) -> (f32, f32, bool) {
+    use sdl2::controller::{Axis, Button};
+
+    fn convert_axis(axis: i16, deadzone: f32) -> f32 {
+        assert!(deadzone >= 0.0);
+        let axis = ((axis as f32) / (i16::MAX as f32)).clamp(-1.0, 1.0);
+        let abs_axis = (axis.abs().max(deadzone) - deadzone) / (1.0 - deadzone);
+        abs_axis.copysign(axis)
+    }
+
+    let (x_axis, y_axis, button1, button2) = if left {
+        (
+            Axis::LeftX,
+            Axis::LeftY,
+            Button::LeftStick,
+            Button::LeftShoulder,
+        )
+    } else {
+        (
+            Axis::RightX,
+            Axis::RightY,
+            Button::RightStick,
+            Button::RightShoulder,
+        )
+    };
+    let x = convert_axis(controller.axis(x_axis), options.deadzone);
+    let y = convert_axis(controller.axis(y_axis), options.deadzone);
+    let pressed = controller.button(button1) || controller.button(button2);
+    (x, y, pressed)
+}
+
+/// Distance, in window pixels, between the mouse cursor and the second finger
+/// when it is first put down using the mouse wheel.
+const SECOND_MOUSE_FINGER_INITIAL_DISTANCE: f32 = 50.0;
+/// How much one step of the mouse wheel changes that distance.
+const SECOND_MOUSE_FINGER_WHEEL_STEP: f32 = 10.0;
+
+/// How a second finger is being simulated with the mouse, for gestures like
+/// pinch-to-zoom.
+#[derive(Copy, Clone)]
+enum SecondMouseFinger {
+    /// The Alt key was held when the left mouse button was pressed: the
+    /// second finger is mirrored about the centre of the screen.
+    Mirrored,
+    /// The mouse wheel was used while the left mouse button was held: the
+    /// second finger is this far to the right of the mouse cursor.
+    Offset(f32),
 }
 
 fn surface_from_image(image: &Image) -> Surface {
@@ -140,7 +285,7 @@ pub fn show_message_with_options(
 }
 
 pub struct Window {
-    _sdl_ctx: sdl2::Sdl,
+    sdl_ctx: sdl2::Sdl,
     video_ctx: sdl2::VideoSubsystem,
     window: sdl2::video::Window,
     event_pump: sdl2::EventPump,
@@ -152,15 +297,42 @@ pub struct Window {
     /// Copy of `fullscreen` on [Options]. Note that this is meaningless when
     /// [Self::rotatable_fullscreen] returns [true].
     fullscreen: bool,
+    /// Copy of `headless` on [Options]. When this is [true], the window is
+    /// created with SDL's dummy video driver and OpenGL is not available.
+    headless: bool,
     scale_hack: NonZeroU32,
     splash_image_and_gl_ctx: Option<(Image, GLContext)>,
     device_orientation: DeviceOrientation,
     app_gl_ctx_no_longer_current: bool,
     controller_ctx: sdl2::GameControllerSubsystem,
-    controllers: Vec<sdl2::controller::GameController>,
+    controllers: Vec<Controller>,
     _sensor_ctx: sdl2::SensorSubsystem,
     accelerometer: Option<sdl2::sensor::Sensor>,
-    virtual_cursor_last: Option<(f32, f32, bool, bool)>,
+    /// Position of the mouse cursor in window co-ordinates, as of the last
+    /// mouse button or motion event.
+    mouse_position: (f32, f32),
+    /// Whether the left mouse button is held.
+    mouse_down: bool,
+    second_mouse_finger: Option<SecondMouseFinger>,
+    /// Fingers touching the host's touchscreen, by SDL touch device and finger
+    /// ID.
+    touch_fingers: HashMap<(i64, i64), FingerId>,
+    /// Fingers touching the screen because of `--button-to-touch=`.
+    button_fingers: HashMap<Button, FingerId>,
+    /// Fingers touching the screen because of `--key-to-touch=`.
+    key_fingers: HashMap<Key, FingerId>,
+    /// Drags in progress because of `--key-to-drag=`.
+    key_drags: HashMap<Key, KeyDrag>,
+    /// Keys that are currently held and mapped with `--key-to-tilt=`.
+    tilt_keys_held: HashSet<Key>,
+    /// Guest time as of the last call to [Self::poll_for_events]. See
+    /// [crate::cpu::Cpu::elapsed_ticks].
+    guest_ticks: u64,
+    input_recorder: Option<replay::InputRecorder>,
+    input_replay: Option<replay::InputReplay>,
+    capture: capture::FrameCapture,
+    /// Set by the save state hotkeys, see [Self::take_state_request].
+    state_request: Option<StateRequest>,
 }
 impl Window {
     /// Returns [true] if touchHLE is running on a device where we should always
@@ -176,6 +348,13 @@ impl Window {
         launch_image: Option<Image>,
         options: &Options,
     ) -> Window {
+        let headless = options.headless;
+        if headless {
+            // The dummy driver doesn't need a display server, but it also
+            // can't provide OpenGL, see Self::create_gl_context.
+            sdl2::hint::set("SDL_VIDEODRIVER", "dummy");
+        }
+
         let sdl_ctx = sdl2::init().unwrap();
         let video_ctx = sdl_ctx.video().unwrap();
 
@@ -203,9 +382,14 @@ impl Window {
         // TODO: some apps specify their orientation in Info.plist, we could use
         // that here.
         let device_orientation = options.initial_orientation;
-        let fullscreen = options.fullscreen;
+        let fullscreen = options.fullscreen && !headless;
 
-        let mut window = if Self::rotatable_fullscreen() {
+        let mut window = if headless {
+            // Requesting OpenGL would make window creation fail with the dummy
+            // driver.
+            let (width, height) = size_for_orientation(device_orientation, scale_hack);
+            video_ctx.window(title, width, height).build().unwrap()
+        } else if Self::rotatable_fullscreen() {
             // Without this, SDL will force fullscreen mode to be portrait.
             set_sdl2_orientation(device_orientation);
             let screen_size = video_ctx.display_bounds(0).unwrap().size();
@@ -250,7 +434,9 @@ impl Window {
 
         let event_pump = sdl_ctx.event_pump().unwrap();
 
-        let splash_image_and_gl_ctx = if let Some(launch_image) = launch_image {
+        let splash_image_and_gl_ctx = if headless {
+            None
+        } else if let Some(launch_image) = launch_image {
             // Splash screen must be drawn with OpenGL (or not drawn at all)
             // because otherwise we can't later use OpenGL in the same window.
             // We are not required to use the same OpenGL version as for other
@@ -287,8 +473,25 @@ impl Window {
         #[cfg(target_os = "macos")]
         let max_height = window.size().1;
 
+        let input_recorder = options.record_input.as_ref().map(|path| {
+            echo!("Recording input to {:?}.", path);
+            replay::InputRecorder::new(path).unwrap_or_else(|e| panic!("{}", e))
+        });
+        let input_replay = options.replay.as_ref().map(|path| {
+            echo!(
+                "Replaying input from {:?}. Input from the host will be ignored.",
+                path
+            );
+            replay::InputReplay::new(path).unwrap_or_else(|e| panic!("{}", e))
+        });
+        if let Some(ref path) = options.dump_frames {
+            echo!("Dumping frames to {:?}.", path);
+        }
+        let capture = capture::FrameCapture::new(options.dump_frames.clone())
+            .unwrap_or_else(|e| panic!("{}", e));
+
         let mut window = Window {
-            _sdl_ctx: sdl_ctx,
+            sdl_ctx,
             video_ctx,
             window,
             event_pump,
@@ -298,6 +501,7 @@ impl Window {
             #[cfg(target_os = "macos")]
             viewport_y_offset: 0,
             fullscreen,
+            headless,
             scale_hack,
             splash_image_and_gl_ctx,
             device_orientation,
@@ -306,7 +510,19 @@ impl Window {
             controllers: Vec::new(),
             _sensor_ctx: sensor_ctx,
             accelerometer,
-            virtual_cursor_last: None,
+            mouse_position: (0.0, 0.0),
+            mouse_down: false,
+            second_mouse_finger: None,
+            touch_fingers: HashMap::new(),
+            button_fingers: HashMap::new(),
+            key_fingers: HashMap::new(),
+            key_drags: HashMap::new(),
+            tilt_keys_held: HashSet::new(),
+            guest_ticks: 0,
+            input_recorder,
+            input_replay,
+            capture,
+            state_request: None,
         };
         if window.splash_image_and_gl_ctx.is_some() {
             window.display_splash();
@@ -318,64 +534,240 @@ impl Window {
     /// (60Hz is probably fine) so that the host OS doesn't consider touchHLE
     /// to be unresponsive. Note that events are not returned by this function,
     /// since we often need to defer actually handling them.
-    pub fn poll_for_events(&mut self, options: &Options) {
-        fn transform_input_coords(
-            window: &Window,
-            (in_x, in_y): (f32, f32),
-            independent_of_viewport: bool,
-        ) -> (f32, f32) {
-            let (vx, vy, vw, vh) = if independent_of_viewport {
-                let (width, height) =
-                    size_for_orientation(window.device_orientation, NonZeroU32::new(1).unwrap());
-                (0, 0, width, height)
-            } else {
-                window.viewport()
-            };
-            // normalize to unit square centred on origin
-            let x = (in_x - vx as f32) / vw as f32 - 0.5;
-            let y = (in_y - vy as f32) / vh as f32 - 0.5;
-            // rotate
-            let matrix = window.input_rotation_matrix();
-            let [x, y] = matrix.transform([x, y]);
-            // back to pixels
-            let (out_w, out_h) = window.size_unrotated_unscaled();
-            let out_x = (x + 0.5) * out_w as f32;
-            let out_y = (y + 0.5) * out_h as f32;
-            (out_x, out_y)
-        }
-        fn translate_button(button: sdl2::controller::Button) -> Option<crate::options::Button> {
+    ///
+    /// `guest_ticks` is the current guest time, see
+    /// [crate::cpu::Cpu::elapsed_ticks]. This is used for recording and
+    /// replaying input.
+    ///
+    /// The hotkeys for controlling the emulation speed are applied to `clock`.
+    pub fn poll_for_events(&mut self, options: &Options, clock: &mut Clock, guest_ticks: u64) {
+        fn translate_button(button: sdl2::controller::Button) -> Option<Button> {
             match button {
-                sdl2::controller::Button::A => Some(crate::options::Button::A),
-                sdl2::controller::Button::B => Some(crate::options::Button::B),
-                sdl2::controller::Button::X => Some(crate::options::Button::X),
-                sdl2::controller::Button::Y => Some(crate::options::Button::Y),
+                sdl2::controller::Button::A => Some(Button::A),
+                sdl2::controller::Button::B => Some(Button::B),
+                sdl2::controller::Button::X => Some(Button::X),
+                sdl2::controller::Button::Y => Some(Button::Y),
                 _ => None,
             }
         }
 
+        self.guest_ticks = guest_ticks;
+
         let mut controller_updated = false;
         while let Some(event) = self.event_pump.poll_event() {
             use sdl2::event::Event as E;
+            // In headless mode, or when replaying input, only requests to quit
+            // (e.g. Ctrl+C), to take screenshots and to control the emulation
+            // speed are accepted from the host.
+            if (self.headless || self.input_replay.is_some())
+                && !matches!(
+                    event,
+                    E::Quit { .. }
+                        | E::KeyDown {
+                            keycode: Some(
+                                SCREENSHOT_KEYCODE
+                                    | PAUSE_KEYCODE
+                                    | ADVANCE_FRAME_KEYCODE
+                                    | SLOW_MOTION_KEYCODE
+                                    | FAST_FORWARD_KEYCODE
+                            ),
+                            ..
+                        }
+                )
+            {
+                continue;
+            }
+            // SDL synthesizes mouse events from touches, but touches are
+            // handled directly below.
+            if matches!(
+                event,
+                E::MouseButtonDown {
+                    which: SDL_TOUCH_MOUSEID,
+                    ..
+                } | E::MouseButtonUp {
+                    which: SDL_TOUCH_MOUSEID,
+                    ..
+                } | E::MouseMotion {
+                    which: SDL_TOUCH_MOUSEID,
+                    ..
+                }
+            ) {
+                continue;
+            }
             self.event_queue.push_back(match event {
-                E::Quit { .. } => Event::Quit,
-                // TODO: support for multi-touch
+                E::Quit { .. } => {
+                    // The app can't handle the request while it is paused.
+                    clock.resume();
+                    Event::Quit
+                }
                 E::MouseButtonDown {
                     x,
                     y,
                     mouse_btn: MouseButton::Left,
                     ..
-                } => Event::TouchDown(transform_input_coords(self, (x as f32, y as f32), false)),
+                } => {
+                    self.mouse_position = (x as f32, y as f32);
+                    self.mouse_down = true;
+                    self.push_mouse_touch(Event::TouchDown, MOUSE_FINGER);
+                    let alt_held = self
+                        .sdl_ctx
+                        .keyboard()
+                        .mod_state()
+                        .intersects(Mod::LALTMOD | Mod::RALTMOD);
+                    if alt_held {
+                        self.second_mouse_finger = Some(SecondMouseFinger::Mirrored);
+                        self.push_mouse_touch(Event::TouchDown, SECOND_MOUSE_FINGER);
+                    }
+                    continue;
+                }
                 E::MouseMotion {
                     x, y, mousestate, ..
                 } if mousestate.left() => {
-                    Event::TouchMove(transform_input_coords(self, (x as f32, y as f32), false))
+                    self.mouse_position = (x as f32, y as f32);
+                    self.push_mouse_touch(Event::TouchMove, MOUSE_FINGER);
+                    if self.second_mouse_finger.is_some() {
+                        self.push_mouse_touch(Event::TouchMove, SECOND_MOUSE_FINGER);
+                    }
+                    continue;
                 }
                 E::MouseButtonUp {
                     x,
                     y,
                     mouse_btn: MouseButton::Left,
                     ..
-                } => Event::TouchUp(transform_input_coords(self, (x as f32, y as f32), false)),
+                } => {
+                    self.mouse_position = (x as f32, y as f32);
+                    self.mouse_down = false;
+                    self.push_mouse_touch(Event::TouchUp, MOUSE_FINGER);
+                    if self.second_mouse_finger.is_some() {
+                        self.push_mouse_touch(Event::TouchUp, SECOND_MOUSE_FINGER);
+                        self.second_mouse_finger = None;
+                    }
+                    continue;
+                }
+                E::MouseWheel { y, .. } if self.mouse_down => {
+                    match self.second_mouse_finger {
+                        None => {
+                            self.second_mouse_finger = Some(SecondMouseFinger::Offset(
+                                SECOND_MOUSE_FINGER_INITIAL_DISTANCE,
+                            ));
+                            self.push_mouse_touch(Event::TouchDown, SECOND_MOUSE_FINGER);
+                        }
+                        Some(SecondMouseFinger::Offset(distance)) => {
+                            let distance = (distance + y as f32 * SECOND_MOUSE_FINGER_WHEEL_STEP)
+                                .max(SECOND_MOUSE_FINGER_WHEEL_STEP);
+                            self.second_mouse_finger = Some(SecondMouseFinger::Offset(distance));
+                            self.push_mouse_touch(Event::TouchMove, SECOND_MOUSE_FINGER);
+                        }
+                        Some(SecondMouseFinger::Mirrored) => (),
+                    }
+                    continue;
+                }
+                E::FingerDown {
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    ..
+                } if touch_id != SDL_MOUSE_TOUCHID => {
+                    let finger = self.allocate_finger();
+                    self.touch_fingers.insert((touch_id, finger_id), finger);
+                    Event::TouchDown(finger, self.transform_touch_coords(x, y))
+                }
+                E::FingerMotion {
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let Some(&finger) = self.touch_fingers.get(&(touch_id, finger_id)) else {
+                        continue;
+                    };
+                    Event::TouchMove(finger, self.transform_touch_coords(x, y))
+                }
+                E::FingerUp {
+                    touch_id,
+                    finger_id,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let Some(finger) = self.touch_fingers.remove(&(touch_id, finger_id)) else {
+                        continue;
+                    };
+                    Event::TouchUp(finger, self.transform_touch_coords(x, y))
+                }
+                E::KeyDown {
+                    keycode: Some(SCREENSHOT_KEYCODE),
+                    repeat: false,
+                    ..
+                } => {
+                    self.capture.request_screenshot();
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(SAVE_STATE_KEYCODE),
+                    repeat: false,
+                    ..
+                } => {
+                    self.state_request = Some(StateRequest::Save);
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(LOAD_STATE_KEYCODE),
+                    repeat: false,
+                    ..
+                } => {
+                    self.state_request = Some(StateRequest::Load);
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(PAUSE_KEYCODE),
+                    repeat: false,
+                    ..
+                } => {
+                    clock.toggle_pause();
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(ADVANCE_FRAME_KEYCODE),
+                    ..
+                } => {
+                    clock.advance_frame();
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(SLOW_MOTION_KEYCODE),
+                    repeat: false,
+                    ..
+                } => {
+                    clock.toggle_slow_motion();
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(FAST_FORWARD_KEYCODE),
+                    repeat: false,
+                    ..
+                } => {
+                    clock.toggle_fast_forward();
+                    continue;
+                }
+                E::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => {
+                    self.key_down(options, Key(keycode));
+                    continue;
+                }
+                E::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    self.key_up(options, Key(keycode));
+                    continue;
+                }
                 E::ControllerDeviceAdded { which, .. } => {
                     self.controller_added(which);
                     continue;
@@ -394,12 +786,21 @@ impl Window {
                     let Some(&(x, y)) = options.button_to_touch.get(&button) else {
                         continue;
                     };
+                    let coords = self.transform_input_coords((x, y), true);
                     match event {
                         E::ControllerButtonUp { .. } => {
-                            Event::TouchUp(transform_input_coords(self, (x, y), true))
+                            let Some(finger) = self.button_fingers.remove(&button) else {
+                                continue;
+                            };
+                            Event::TouchUp(finger, coords)
                         }
                         E::ControllerButtonDown { .. } => {
-                            Event::TouchDown(transform_input_coords(self, (x, y), true))
+                            if self.button_fingers.contains_key(&button) {
+                                continue;
+                            }
+                            let finger = self.allocate_finger();
+                            self.button_fingers.insert(button, finger);
+                            Event::TouchDown(finger, coords)
                         }
                         _ => unreachable!(),
                     }
@@ -412,29 +813,211 @@ impl Window {
             })
         }
 
+        self.update_key_drags();
+
         if controller_updated {
-            let (new_x, new_y, new_pressed, visible) = self.get_virtual_cursor(options);
-            let (old_x, old_y, old_pressed, _) = self.virtual_cursor_last.unwrap_or_default();
-            self.virtual_cursor_last = Some((new_x, new_y, new_pressed, visible));
+            self.update_virtual_cursors(options);
+        }
+    }
+
+    /// Returns the save state hotkey pressed since the last call, if any. The
+    /// request can't be handled in [Self::poll_for_events], because states can
+    /// only be saved or loaded at certain points (see [crate::snapshot]).
+    pub fn take_state_request(&mut self) -> Option<StateRequest> {
+        self.state_request.take()
+    }
+
+    /// Returns [true] if input is being recorded (`--record-input=`) or
+    /// replayed (`--replay=`). Recordings are of a single run, so they can't
+    /// be combined with save states.
+    pub fn is_recording_or_replaying_input(&self) -> bool {
+        self.input_recorder.is_some() || self.input_replay.is_some()
+    }
+
+    /// Queue touch events for the virtual cursors of the controllers, each of
+    /// which is a separate finger.
+    fn update_virtual_cursors(&mut self, options: &Options) {
+        for i in 0..self.controllers.len() {
+            let (new_x, new_y, new_pressed, visible) =
+                self.get_virtual_cursor(options, &self.controllers[i].controller);
+            let (old_x, old_y, _, _) = self.controllers[i].virtual_cursor_last.unwrap_or_default();
+            self.controllers[i].virtual_cursor_last = Some((new_x, new_y, new_pressed, visible));
+            let coords = self.transform_input_coords((new_x, new_y), false);
+            let event = match (self.controllers[i].virtual_cursor_finger, new_pressed) {
+                (None, true) => {
+                    let finger = self.allocate_finger();
+                    self.controllers[i].virtual_cursor_finger = Some(finger);
+                    Event::TouchDown(finger, coords)
+                }
+                (Some(finger), false) => {
+                    self.controllers[i].virtual_cursor_finger = None;
+                    Event::TouchUp(finger, coords)
+                }
+                (Some(finger), true) if (new_x, new_y) != (old_x, old_y) => {
+                    Event::TouchMove(finger, coords)
+                }
+                _ => continue,
+            };
+            self.event_queue.push_back(event);
+        }
+    }
+
+    /// Convert co-ordinates from the window (or, if `independent_of_viewport`
+    /// is [true], from options like `--button-to-touch=`) to the co-ordinate
+    /// space of touch events.
+    fn transform_input_coords(
+        &self,
+        (in_x, in_y): (f32, f32),
+        independent_of_viewport: bool,
+    ) -> (f32, f32) {
+        let (vx, vy, vw, vh) = if independent_of_viewport {
+            let (width, height) =
+                size_for_orientation(self.device_orientation, NonZeroU32::new(1).unwrap());
+            (0, 0, width, height)
+        } else {
+            self.viewport()
+        };
+        // normalize to unit square centred on origin
+        let x = (in_x - vx as f32) / vw as f32 - 0.5;
+        let y = (in_y - vy as f32) / vh as f32 - 0.5;
+        // rotate
+        let matrix = self.input_rotation_matrix();
+        let [x, y] = matrix.transform([x, y]);
+        // back to pixels
+        let (out_w, out_h) = self.size_unrotated_unscaled();
+        let out_x = (x + 0.5) * out_w as f32;
+        let out_y = (y + 0.5) * out_h as f32;
+        (out_x, out_y)
+    }
+
+    /// Convert the normalized co-ordinates of an SDL touch event to the
+    /// co-ordinate space of touch events.
+    fn transform_touch_coords(&self, x: f32, y: f32) -> (f32, f32) {
+        let (width, height) = self.window.size();
+        self.transform_input_coords((x * width as f32, y * height as f32), false)
+    }
+
+    /// Queue a touch event for a mouse finger, at its current position.
+    fn push_mouse_touch(&mut self, event: fn(FingerId, (f32, f32)) -> Event, finger: FingerId) {
+        let (x, y) = self.mouse_position;
+        let coords = match (finger, self.second_mouse_finger) {
+            (MOUSE_FINGER, _) | (_, None) => (x, y),
+            (_, Some(SecondMouseFinger::Mirrored)) => {
+                let (vx, vy, vw, vh) = self.viewport();
+                let center_x = vx as f32 + vw as f32 / 2.0;
+                let center_y = vy as f32 + vh as f32 / 2.0;
+                (2.0 * center_x - x, 2.0 * center_y - y)
+            }
+            (_, Some(SecondMouseFinger::Offset(distance))) => (x + distance, y),
+        };
+        let coords = self.transform_input_coords(coords, false);
+        self.event_queue.push_back(event(finger, coords));
+    }
+
+    /// Find a finger ID for a new touch from the host's touchscreen, a virtual
+    /// cursor, a controller button or a keyboard key.
+    fn allocate_finger(&self) -> FingerId {
+        (FIRST_ALLOCATED_FINGER..)
+            .find(|&finger| {
+                !self.touch_fingers.values().any(|&other| other == finger)
+                    && !self
+                        .controllers
+                        .iter()
+                        .any(|controller| controller.virtual_cursor_finger == Some(finger))
+                    && !self.button_fingers.values().any(|&other| other == finger)
+                    && !self.key_fingers.values().any(|&other| other == finger)
+                    && !self.key_drags.values().any(|drag| drag.finger == finger)
+            })
+            .unwrap()
+    }
+
+    fn key_down(&mut self, options: &Options, key: Key) {
+        if options.key_to_tilt.contains_key(&key) {
+            self.tilt_keys_held.insert(key);
+        }
+        if let Some(&coords) = options.key_to_touch.get(&key) {
+            if !self.key_fingers.contains_key(&key) {
+                let finger = self.allocate_finger();
+                self.key_fingers.insert(key, finger);
+                let coords = self.transform_input_coords(coords, true);
+                self.event_queue.push_back(Event::TouchDown(finger, coords));
+            }
+        }
+        if let Some(&(from, to)) = options.key_to_drag.get(&key) {
+            if !self.key_drags.contains_key(&key) {
+                let finger = self.allocate_finger();
+                self.key_drags.insert(
+                    key,
+                    KeyDrag {
+                        finger,
+                        from,
+                        to,
+                        start: Instant::now(),
+                        finished: false,
+                    },
+                );
+                let coords = self.transform_input_coords(from, true);
+                self.event_queue.push_back(Event::TouchDown(finger, coords));
+            }
+        }
+    }
+
+    fn key_up(&mut self, options: &Options, key: Key) {
+        self.tilt_keys_held.remove(&key);
+        if let Some(finger) = self.key_fingers.remove(&key) {
+            let coords = self.transform_input_coords(options.key_to_touch[&key], true);
+            self.event_queue.push_back(Event::TouchUp(finger, coords));
+        }
+        if let Some(drag) = self.key_drags.remove(&key) {
+            let coords = self.transform_input_coords(drag.position(), true);
             self.event_queue
-                .push_back(match (old_pressed, new_pressed) {
-                    (false, true) => {
-                        Event::TouchDown(transform_input_coords(self, (new_x, new_y), false))
-                    }
-                    (true, false) => {
-                        Event::TouchUp(transform_input_coords(self, (new_x, new_y), false))
-                    }
-                    _ if (new_x, new_y) != (old_x, old_y) && new_pressed => {
-                        Event::TouchMove(transform_input_coords(self, (new_x, new_y), false))
-                    }
-                    _ => return,
-                });
+                .push_back(Event::TouchUp(drag.finger, coords));
+        }
+    }
+
+    /// Move the fingers of `--key-to-drag=` drags that are in progress.
+    fn update_key_drags(&mut self) {
+        let mut moves = Vec::new();
+        for drag in self.key_drags.values_mut() {
+            if drag.finished {
+                continue;
+            }
+            drag.finished = drag.start.elapsed() >= KEY_DRAG_DURATION;
+            moves.push((drag.finger, drag.position()));
+        }
+        for (finger, coords) in moves {
+            let coords = self.transform_input_coords(coords, true);
+            self.event_queue.push_back(Event::TouchMove(finger, coords));
         }
     }
 
-    /// Pop an event from the queue (in FIFO order)
+    /// For use when the app presents a frame: capture it if a screenshot was
+    /// requested or `--dump-frames=` is in use. `guest_ticks` is the current
+    /// guest time (see [crate::cpu::Cpu::elapsed_ticks]). `read_pixels` is
+    /// only called if the frame is captured, and should return the frame's
+    /// pixels as 8 bits per channel RGBA, with rows in top-to-bottom order.
+    pub fn frame_presented(
+        &mut self,
+        guest_ticks: u64,
+        dimensions: (u32, u32),
+        read_pixels: impl FnOnce() -> Vec<u8>,
+    ) {
+        self.capture
+            .frame_presented(guest_ticks, dimensions, read_pixels);
+    }
+
+    /// Pop an event from the queue (in FIFO order). When replaying input, this
+    /// instead returns the next recorded event once it is due.
     pub fn pop_event(&mut self) -> Option<Event> {
-        self.event_queue.pop_front()
+        let event = self.event_queue.pop_front().or_else(|| {
+            self.input_replay
+                .as_mut()
+                .and_then(|replay| replay.pop_event(self.guest_ticks))
+        })?;
+        if let Some(ref mut recorder) = self.input_recorder {
+            recorder.record(self.guest_ticks, &replay::Input::Event(event.clone()));
+        }
+        Some(event)
     }
 
     fn controller_added(&mut self, joystick_idx: u32) {
@@ -446,14 +1029,27 @@ impl Window {
             "New controller connected: {}. Left stick = device tilt. Right stick = touch input (press the stick or shoulder button to tap/hold).",
             controller.name()
         );
-        self.controllers.push(controller);
+        self.controllers.push(Controller {
+            controller,
+            virtual_cursor_last: None,
+            virtual_cursor_finger: None,
+        });
     }
     fn controller_removed(&mut self, instance_id: u32) {
-        let Some(idx) = self.controllers.iter().position(|controller| controller.instance_id() == instance_id) else {
+        let Some(idx) = self.controllers.iter().position(|controller| controller.controller.instance_id() == instance_id) else {
             return;
         };
-        let controller = self.controllers.remove(idx);
+        let Controller {
+            controller,
+            virtual_cursor_last,
+            virtual_cursor_finger,
+        } = self.controllers.remove(idx);
         log!("Warning: Controller disconnected: {}", controller.name());
+        // Don't leave the virtual cursor's finger stuck on the screen.
+        if let (Some(finger), Some((x, y, _, _))) = (virtual_cursor_finger, virtual_cursor_last) {
+            let coords = self.transform_input_coords((x, y), false);
+            self.event_queue.push_back(Event::TouchUp(finger, coords));
+        }
     }
     pub fn print_accelerometer_notice(&self) {
         log!("This app uses the accelerometer.");
@@ -470,10 +1066,22 @@ impl Window {
         }
     }
 
-    /// Get the real or simulated accelerometer output.
+    /// Get the real, simulated or replayed accelerometer output.
     /// See also [crate::frameworks::uikit::ui_accelerometer].
-    pub fn get_acceleration(&self, options: &Options) -> (f32, f32, f32) {
-        if self.controllers.is_empty() {
+    pub fn get_acceleration(&mut self, options: &Options) -> (f32, f32, f32) {
+        let acceleration = if let Some(ref mut replay) = self.input_replay {
+            replay.get_acceleration(self.guest_ticks)
+        } else {
+            self.get_host_acceleration(options)
+        };
+        if let Some(ref mut recorder) = self.input_recorder {
+            recorder.record(self.guest_ticks, &replay::Input::Acceleration(acceleration));
+        }
+        acceleration
+    }
+
+    fn get_host_acceleration(&self, options: &Options) -> (f32, f32, f32) {
+        if self.controllers.is_empty() && self.tilt_keys_held.is_empty() {
             if let Some(ref accelerometer) = self.accelerometer {
                 let data = accelerometer.get_data().unwrap();
                 let sdl2::sensor::SensorData::Accel(data) = data else { panic!(); };
@@ -490,7 +1098,17 @@ impl Window {
         }
 
         // Get left analog stick input. The range is [-1, 1] on each axis.
-        let (x, y, _) = self.get_controller_stick(options, true);
+        let (mut x, mut y, _) = self.get_controller_stick(options, true);
+
+        // Keys mapped with --key-to-tilt= act like pushing the stick.
+        for key in &self.tilt_keys_held {
+            let (key_x, key_y) = options.key_to_tilt[key];
+            x += key_x;
+            y += key_y;
+        }
+        // Holding a key while also pushing the stick mustn't tilt the device
+        // further than the configured range.
+        let (x, y) = (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0));
 
         // Correct for window rotation
         let [x, y] = self.input_rotation_matrix().transform([x, y]);
@@ -525,23 +1143,28 @@ impl Window {
         (x, y, z)
     }
 
-    /// For use when redrawing the screen: Get the cached on-screen position and
-    /// press state of the analog stick-controlled virtual cursor, if it is
-    /// visible.
-    pub fn virtual_cursor_visible_at(&self) -> Option<(f32, f32, bool)> {
-        let (x, y, pressed, visible) = self.virtual_cursor_last?;
-        if visible {
-            Some((x, y, pressed))
-        } else {
-            None
-        }
+    /// For use when redrawing the screen: Get the cached on-screen positions
+    /// and press states of the analog stick-controlled virtual cursors that
+    /// are visible.
+    pub fn virtual_cursors_visible_at(&self) -> Vec<(f32, f32, bool)> {
+        self.controllers
+            .iter()
+            .filter_map(|controller| {
+                let (x, y, pressed, visible) = controller.virtual_cursor_last?;
+                visible.then_some((x, y, pressed))
+            })
+            .collect()
     }
 
     /// Get the new  on-screen position, click state and visibility of the
-    /// analog stick-controlled virtual cursor.
-    fn get_virtual_cursor(&self, options: &Options) -> (f32, f32, bool, bool) {
+    /// virtual cursor controlled by a controller's right analog stick.
+    fn get_virtual_cursor(
+        &self,
+        options: &Options,
+        controller: &sdl2::controller::GameController,
+    ) -> (f32, f32, bool, bool) {
         // Get right analog stick input. The range is [-1, 1] on each axis.
-        let (x, y, pressed) = self.get_controller_stick(options, false);
+        let (x, y, pressed) = get_stick(controller, options, false);
 
         // The cursor is intended to only show up once you move the analog stick
         // out of its deadzone, or while the button is held.
@@ -576,36 +1199,14 @@ impl Window {
     /// analog stick of the game controllers. Each axis value is in the range
     /// [-1, 1].
     fn get_controller_stick(&self, options: &Options, left: bool) -> (f32, f32, bool) {
-        fn convert_axis(axis: i16, deadzone: f32) -> f32 {
-            assert!(deadzone >= 0.0);
-            let axis = ((axis as f32) / (i16::MAX as f32)).clamp(-1.0, 1.0);
-            let abs_axis = (axis.abs().max(deadzone) - deadzone) / (1.0 - deadzone);
-            abs_axis.copysign(axis)
-        }
-
         let (mut x, mut y) = (0.0, 0.0);
         let mut pressed = false;
         for controller in &self.controllers {
-            use sdl2::controller::{Axis, Button};
-            let (x_axis, y_axis, button1, button2) = if left {
-                (
-                    Axis::LeftX,
-                    Axis::LeftY,
-                    Button::LeftStick,
-                    Button::LeftShoulder,
-                )
-            } else {
-                (
-                    Axis::RightX,
-                    Axis::RightY,
-                    Button::RightStick,
-                    Button::RightShoulder,
-                )
-            };
-            x += convert_axis(controller.axis(x_axis), options.deadzone);
-            y += convert_axis(controller.axis(y_axis), options.deadzone);
-            pressed |= controller.button(button1);
-            pressed |= controller.button(button2);
+            let (stick_x, stick_y, stick_pressed) =
+                get_stick(&controller.controller, options, left);
+            x += stick_x;
+            y += stick_y;
+            pressed |= stick_pressed;
         }
         let (x, y) = (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0));
 
@@ -613,6 +1214,9 @@ impl Window {
     }
 
     pub fn create_gl_context(&mut self, version: GLVersion) -> Result<GLContext, String> {
+        if self.headless {
+            return Err("OpenGL is not available in headless mode".to_string());
+        }
         gl::create_gl_context(&self.video_ctx, &self.window, version)
     }
 
@@ -631,6 +1235,13 @@ impl Window {
         value
     }
 
+    /// Set the flag checked by [Self::is_app_gl_ctx_no_longer_current]. This
+    /// must be called by anything outside the window that makes its own OpenGL
+    /// context current, e.g. the Core Animation compositor.
+    pub fn set_app_gl_ctx_no_longer_current(&mut self) {
+        self.app_gl_ctx_no_longer_current = true;
+    }
+
     fn display_splash(&mut self) {
         let Some((image, gl_ctx)) = &self.splash_image_and_gl_ctx else {
             panic!();
@@ -654,6 +1265,9 @@ impl Window {
     /// Swap front-buffer and back-buffer so the result of OpenGL rendering is
     /// presented.
     pub fn swap_window(&mut self) {
+        if self.headless {
+            return;
+        }
         self.window.gl_swap_window();
     }
 
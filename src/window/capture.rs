@@ -0,0 +1,102 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Capture of the app's output to PNG files: screenshots (with a hotkey) and
+//! frame dumps (`--dump-frames=` option).
+//!
+//! What is captured is the app's own framebuffer, as presented by it, before
+//! touchHLE rotates and scales it for display in the window. Each file is named
+//! with the number of the frame (counting from 0 at launch) and the guest time
+//! at which it was presented (see [crate::cpu::Cpu::elapsed_ticks]), which is
+//! the same kind of time as used by input recordings. For example:
+//! `frame_000042_ticks_1234567.png`.
+
+use crate::image::encode_png;
+use std::path::{Path, PathBuf};
+
+/// Directory screenshots taken with the hotkey are saved to.
+const SCREENSHOTS_DIR: &str = "touchHLE_screenshots";
+
+pub struct FrameCapture {
+    /// Directory every frame is written to, if `--dump-frames=` is in use.
+    dump_dir: Option<PathBuf>,
+    /// Set when the screenshot hotkey is pressed, so the next frame is saved.
+    screenshot_requested: bool,
+    /// Number of frames presented so far.
+    frame_count: u64,
+}
+impl FrameCapture {
+    pub fn new(dump_dir: Option<PathBuf>) -> Result<FrameCapture, String> {
+        if let Some(ref dump_dir) = dump_dir {
+            std::fs::create_dir_all(dump_dir).map_err(|e| {
+                format!(
+                    "Could not create frame dump directory {:?}: {}",
+                    dump_dir, e
+                )
+            })?;
+        }
+        Ok(FrameCapture {
+            dump_dir,
+            screenshot_requested: false,
+            frame_count: 0,
+        })
+    }
+
+    pub fn request_screenshot(&mut self) {
+        self.screenshot_requested = true;
+    }
+
+    /// Call this each time the app presents a frame. `read_pixels` is only
+    /// called if the frame needs to be captured, and should return the frame's
+    /// pixels as 8 bits per channel RGBA, with rows in top-to-bottom order.
+    pub fn frame_presented(
+        &mut self,
+        guest_ticks: u64,
+        dimensions: (u32, u32),
+        read_pixels: impl FnOnce() -> Vec<u8>,
+    ) {
+        let frame_number = self.frame_count;
+        self.frame_count += 1;
+
+        if self.dump_dir.is_none() && !self.screenshot_requested {
+            return;
+        }
+
+        let filename = format!("frame_{:06}_ticks_{}.png", frame_number, guest_ticks);
+        let png = match encode_png(&read_pixels(), dimensions) {
+            Ok(png) => png,
+            Err(e) => {
+                log!("Warning: Could not capture frame {}: {}", frame_number, e);
+                return;
+            }
+        };
+
+        if let Some(ref dump_dir) = self.dump_dir {
+            write_file(&dump_dir.join(&filename), &png);
+        }
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let path = Path::new(SCREENSHOTS_DIR).join(&filename);
+            if let Err(e) = std::fs::create_dir_all(SCREENSHOTS_DIR) {
+                log!("Warning: Could not create {:?}: {}", SCREENSHOTS_DIR, e);
+                return;
+            }
+            if write_file(&path, &png) {
+                echo!("Saved screenshot to {:?}.", path);
+            }
+        }
+    }
+}
+
+fn write_file(path: &Path, png: &[u8]) -> bool {
+    match std::fs::write(path, png) {
+        Ok(()) => true,
+        Err(e) => {
+            log!("Warning: Could not write {:?}: {}", path, e);
+            false
+        }
+    }
+}
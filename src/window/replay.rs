@@ -0,0 +1,265 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Recording and replay of input (`--record-input=` and `--replay=` options).
+//!
+//! Every input the app receives, i.e. every [Event] returned by
+//! [super::Window::pop_event] and every result of
+//! [super::Window::get_acceleration], is timestamped with the guest time (see
+//! [crate::cpu::Cpu::elapsed_ticks]) at which it was received. On replay, each
+//! input is delivered once that guest time has been reached, so the app sees
+//! the same inputs at the same points in its execution, regardless of how fast
+//! the host is.
+//!
+//! The file format is plain text with one input per line, so recordings can
+//! be inspected and edited by hand, or written from scratch as scripts:
+//!
+//! ```text
+//! # Comments start with a hash sign.
+//! 1500000 down 160 240
+//! 1700000 move 170 240
+//! 1900000 up 170 240
+//! 2000000 accel 0 0 -1
+//! # A two-finger pinch: the second finger is number 1.
+//! 3000000 down 140 240
+//! 3000000 down 180 240 1
+//! 3200000 move 100 240
+//! 3200000 move 220 240 1
+//! 3400000 up 100 240
+//! 3400000 up 220 240 1
+//! 9000000 quit
+//! ```
+//!
+//! The first number on each line is the guest time. Touch co-ordinates are in
+//! the app's co-ordinate space (0,0 is the top-left corner of the screen in
+//! portrait orientation). They may be followed by a finger number to
+//! distinguish simultaneous touches, which is 0 if omitted. Acceleration is in
+//! units of g-force.
+
+use super::{Event, FingerId};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::Path;
+
+/// A single recorded input.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Event(Event),
+    Acceleration((f32, f32, f32)),
+}
+
+fn format_line(guest_ticks: u64, input: &Input) -> String {
+    match input {
+        Input::Event(Event::Quit) => format!("{} quit", guest_ticks),
+        Input::Event(Event::TouchDown(finger, coords)) => {
+            format_touch(guest_ticks, "down", *finger, *coords)
+        }
+        Input::Event(Event::TouchMove(finger, coords)) => {
+            format_touch(guest_ticks, "move", *finger, *coords)
+        }
+        Input::Event(Event::TouchUp(finger, coords)) => {
+            format_touch(guest_ticks, "up", *finger, *coords)
+        }
+        Input::Acceleration((x, y, z)) => format!("{} accel {} {} {}", guest_ticks, x, y, z),
+    }
+}
+
+fn format_touch(guest_ticks: u64, kind: &str, finger: FingerId, (x, y): (f32, f32)) -> String {
+    if finger == 0 {
+        format!("{} {} {} {}", guest_ticks, kind, x, y)
+    } else {
+        format!("{} {} {} {} {}", guest_ticks, kind, x, y, finger)
+    }
+}
+
+/// Parse a line of a recording. Returns [None] for empty and comment lines.
+fn parse_line(line: &str) -> Result<Option<(u64, Input)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut parts = line.split_ascii_whitespace();
+    let guest_ticks: u64 = parts
+        .next()
+        .unwrap()
+        .parse()
+        .map_err(|_| "Invalid guest time".to_string())?;
+    let kind = parts
+        .next()
+        .ok_or_else(|| "Missing input kind".to_string())?;
+    let values = parts
+        .map(|value| value.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| "Invalid number".to_string())?;
+
+    fn touch_event(kind: &str, finger: FingerId, coords: (f32, f32)) -> Event {
+        match kind {
+            "down" => Event::TouchDown(finger, coords),
+            "move" => Event::TouchMove(finger, coords),
+            "up" => Event::TouchUp(finger, coords),
+            _ => unreachable!(),
+        }
+    }
+
+    let input = match (kind, values.as_slice()) {
+        ("quit", &[]) => Input::Event(Event::Quit),
+        ("down" | "move" | "up", &[x, y]) => Input::Event(touch_event(kind, 0, (x, y))),
+        ("down" | "move" | "up", &[x, y, finger]) => {
+            if finger < 0.0 || finger.fract() != 0.0 {
+                return Err("Invalid finger number".to_string());
+            }
+            Input::Event(touch_event(kind, finger as FingerId, (x, y)))
+        }
+        ("accel", &[x, y, z]) => Input::Acceleration((x, y, z)),
+        _ => return Err(format!("Unrecognized input {:?}", kind)),
+    };
+    Ok(Some((guest_ticks, input)))
+}
+
+/// Writes inputs to a file as they happen.
+pub struct InputRecorder {
+    file: LineWriter<File>,
+}
+impl InputRecorder {
+    pub fn new(path: &Path) -> Result<InputRecorder, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create input recording {:?}: {}", path, e))?;
+        let mut file = LineWriter::new(file);
+        writeln!(file, "# touchHLE input recording").unwrap();
+        Ok(InputRecorder { file })
+    }
+
+    pub fn record(&mut self, guest_ticks: u64, input: &Input) {
+        // Each line is flushed immediately, so that the recording is still
+        // useful if touchHLE crashes.
+        if let Err(e) = writeln!(self.file, "{}", format_line(guest_ticks, input)) {
+            log!("Warning: could not write to input recording: {}", e);
+        }
+    }
+}
+
+/// Delivers inputs from a recording once they are due.
+pub struct InputReplay {
+    events: VecDeque<(u64, Event)>,
+    accelerations: VecDeque<(u64, (f32, f32, f32))>,
+    /// Most recently replayed acceleration. The default is the device lying
+    /// flat on its back.
+    current_acceleration: (f32, f32, f32),
+    finished_notice_printed: bool,
+}
+impl InputReplay {
+    pub fn new(path: &Path) -> Result<InputReplay, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read input recording {:?}: {}", path, e))?;
+
+        let mut events = VecDeque::new();
+        let mut accelerations = VecDeque::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let parsed = parse_line(line).map_err(|e| {
+                format!("Line {} of input recording {:?}: {}", line_no + 1, path, e)
+            })?;
+            let Some((guest_ticks, input)) = parsed else {
+                continue;
+            };
+            match input {
+                Input::Event(event) => events.push_back((guest_ticks, event)),
+                Input::Acceleration(accel) => accelerations.push_back((guest_ticks, accel)),
+            }
+        }
+
+        Ok(InputReplay {
+            events,
+            accelerations,
+            current_acceleration: (0.0, 0.0, -1.0),
+            finished_notice_printed: false,
+        })
+    }
+
+    /// Get the next event, if it is due.
+    pub fn pop_event(&mut self, guest_ticks: u64) -> Option<Event> {
+        if self.events.is_empty() && !self.finished_notice_printed {
+            self.finished_notice_printed = true;
+            log!("All recorded input events have been replayed.");
+        }
+        if self.events.front()?.0 > guest_ticks {
+            return None;
+        }
+        self.events.pop_front().map(|(_, event)| event)
+    }
+
+    /// Get the most recent acceleration that is due.
+    pub fn get_acceleration(&mut self, guest_ticks: u64) -> (f32, f32, f32) {
+        while let Some(&(due, accel)) = self.accelerations.front() {
+            if due > guest_ticks {
+                break;
+            }
+            self.current_acceleration = accel;
+            self.accelerations.pop_front();
+        }
+        self.current_acceleration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for input in [
+            Input::Event(Event::Quit),
+            Input::Event(Event::TouchDown(0, (160.0, 240.5))),
+            Input::Event(Event::TouchMove(0, (-1.0, 0.0))),
+            Input::Event(Event::TouchUp(0, (320.0, 480.0))),
+            Input::Event(Event::TouchDown(1, (10.0, 20.0))),
+            Input::Event(Event::TouchUp(3, (10.0, 20.0))),
+            Input::Acceleration((0.25, -0.5, -1.0)),
+        ] {
+            let line = format_line(12345, &input);
+            assert_eq!(parse_line(&line), Ok(Some((12345, input))));
+        }
+    }
+
+    #[test]
+    fn comments_and_errors() {
+        assert_eq!(parse_line(""), Ok(None));
+        assert_eq!(parse_line("  # comment"), Ok(None));
+        assert!(parse_line("100").is_err());
+        assert!(parse_line("100 down 1").is_err());
+        assert!(parse_line("100 tap 1 2").is_err());
+        assert!(parse_line("100 down 1 2 -1").is_err());
+        assert!(parse_line("100 down 1 2 0.5").is_err());
+        assert!(parse_line("x quit").is_err());
+    }
+
+    #[test]
+    fn simultaneous_fingers() {
+        let mut replay = InputReplay {
+            events: [
+                "100 down 1 2",
+                "100 down 3 4 1",
+                "200 up 3 4 1",
+                "200 up 1 2",
+            ]
+            .into_iter()
+            .map(|line| match parse_line(line) {
+                Ok(Some((guest_ticks, Input::Event(event)))) => (guest_ticks, event),
+                other => panic!("{:?}", other),
+            })
+            .collect(),
+            accelerations: VecDeque::new(),
+            current_acceleration: (0.0, 0.0, -1.0),
+            finished_notice_printed: false,
+        };
+        assert_eq!(replay.pop_event(99), None);
+        assert_eq!(replay.pop_event(100), Some(Event::TouchDown(0, (1.0, 2.0))));
+        assert_eq!(replay.pop_event(100), Some(Event::TouchDown(1, (3.0, 4.0))));
+        assert_eq!(replay.pop_event(100), None);
+        assert_eq!(replay.pop_event(250), Some(Event::TouchUp(1, (3.0, 4.0))));
+        assert_eq!(replay.pop_event(250), Some(Event::TouchUp(0, (1.0, 2.0))));
+    }
+}
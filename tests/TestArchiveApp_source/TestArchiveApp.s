@@ -0,0 +1,699 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+/*
+This is the source of TestArchiveApp, which is used by the golden tests to
+check NSKeyedArchiver and NSKeyedUnarchiver.
+
+Like TestUIApp, this is written out by hand rather than built with the iPhone
+SDK, and can be built with:
+
+  llvm-mc -triple=armv6-none-eabi -filetype=obj -I ../asm TestArchiveApp.s -o TestArchiveApp.o
+  llvm-objcopy -O binary --only-section=.data TestArchiveApp.o ../TestArchiveApp.app/TestArchiveApp
+
+The app is equivalent to this Objective-C:
+
+  static void check(BOOL ok, const char *name) {
+    printf("%s: %s\n", ok ? "ok" : "FAILED", name);
+  }
+
+  int main(int argc, char *argv[]) {
+    NSAutoreleasePool *pool = [NSAutoreleasePool new];
+
+    // An array that contains itself.
+    NSMutableArray *array = [NSMutableArray new];
+    [array addObject:@"touchHLE"];
+    [array addObject:[NSNumber numberWithInt:42]];
+    [array addObject:array];
+    NSData *data = [NSKeyedArchiver archivedDataWithRootObject:array];
+    NSArray *copy = [NSKeyedUnarchiver unarchiveObjectWithData:data];
+    check(copy != nil && copy != array, "round trip: new object");
+    check([copy count] == 3, "round trip: count");
+    check([[copy objectAtIndex:0] isEqualToString:@"touchHLE"],
+          "round trip: string");
+    check([[copy objectAtIndex:1] intValue] == 42, "round trip: number");
+    check([copy objectAtIndex:2] == copy, "round trip: cycle");
+
+    // KeyedArchive.plist is laid out the way iPhone OS's NSKeyedArchiver
+    // writes it. Its root is a dictionary equivalent to:
+    //   NSMutableArray *loop = [NSMutableArray new];
+    //   [loop addObject:@"touchHLE"];
+    //   [loop addObject:loop];
+    //   @{@"title": @"touchHLE", @"level": @3,
+    //     @"scores": @[@1, @2.5], @"loop": loop}
+    NSString *path = [[NSBundle mainBundle] pathForResource:@"KeyedArchive"
+                                                     ofType:@"plist"];
+    NSDictionary *root = [NSKeyedUnarchiver unarchiveObjectWithFile:path];
+    check([[root objectForKey:@"title"] isEqualToString:@"touchHLE"],
+          "fixture: string");
+    check([[root objectForKey:@"level"] intValue] == 3, "fixture: integer");
+    NSArray *scores = [root objectForKey:@"scores"];
+    check([scores count] == 2, "fixture: array count");
+    check([[scores objectAtIndex:0] intValue] == 1, "fixture: array integer");
+    check([[scores objectAtIndex:1] floatValue] == 2.5f, "fixture: array real");
+    NSArray *loop = [root objectForKey:@"loop"];
+    check([loop objectAtIndex:1] == loop, "fixture: cycle");
+
+    [pool release];
+    return 0;
+  }
+*/
+
+.include "mach_o.inc"
+
+// Library ordinals for two-level namespace lookup, see the LC_LOAD_DYLIB
+// commands.
+.set FOUNDATION, 1 << 8
+.set COREFOUNDATION, 2 << 8
+.set LIBOBJC, 3 << 8
+.set LIBSYSTEM, 4 << 8
+
+// Indices in the symbol table
+.set SYM_start, 0
+.set SYM_main, 1
+.set SYM_OBJC_CLASS_NSAutoreleasePool, 2
+.set SYM_OBJC_CLASS_NSBundle, 3
+.set SYM_OBJC_CLASS_NSKeyedArchiver, 4
+.set SYM_OBJC_CLASS_NSKeyedUnarchiver, 5
+.set SYM_OBJC_CLASS_NSMutableArray, 6
+.set SYM_OBJC_CLASS_NSNumber, 7
+.set SYM_CFConstantStringClassReference, 8
+.set SYM_exit, 9
+.set SYM_objc_msgSend, 10
+.set SYM_printf, 11
+
+// Report whether the last comparison had the result given by the condition
+// code, e.g. "check eq, name" after "cmp r0, #3" is check(r0 == 3, name).
+.macro check cond, name
+  mov\cond r0, #1
+  .ifc \cond, eq
+  movne r0, #0
+  .else
+  moveq r0, #0
+  .endif
+  ldr r1, .Lcheck_name\@
+  call report
+  jump .Lcheck_end\@
+.Lcheck_name\@:
+  vmaddr \name
+.Lcheck_end\@:
+.endm
+
+/* ---------------------------------------------------------------------- */
+
+mach_header:
+  .word 0xfeedface // MH_MAGIC
+  .word 12 // CPU_TYPE_ARM
+  .word 6 // CPU_SUBTYPE_ARM_V6
+  .word 2 // MH_EXECUTE
+  .word 12 // ncmds
+  .word load_commands_end - load_commands
+  .word 0x85 // MH_NOUNDEFS | MH_DYLDLINK | MH_TWOLEVEL
+
+load_commands:
+
+  .word 0x1 // LC_SEGMENT
+  .word 56
+  name16 "__PAGEZERO"
+  .word 0 // vmaddr
+  .word 0x1000 // vmsize
+  .word 0 // fileoff
+  .word 0 // filesize
+  .word 0 // maxprot
+  .word 0 // initprot
+  .word 0 // nsects
+  .word 0 // flags
+
+  .word 0x1 // LC_SEGMENT
+  .word 56 + 68 * 3
+  name16 "__TEXT"
+  vmaddr text_segment
+  .word data_segment - text_segment // vmsize
+  .word text_segment - mach_header // fileoff
+  .word data_segment - text_segment // filesize
+  .word 7 // maxprot: rwx
+  .word 5 // initprot: r-x
+  .word 3 // nsects
+  .word 0 // flags
+  section "__text", "__TEXT", text, text_end, 2, S_REGULAR | S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS
+  section "__symbol_stub4", "__TEXT", symbol_stubs, symbol_stubs_end, 2, S_SYMBOL_STUBS | S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS, 0, 12
+  section "__cstring", "__TEXT", cstrings, cstrings_end, 2, S_CSTRING_LITERALS
+
+  .word 0x1 // LC_SEGMENT
+  .word 56 + 68 * 5
+  name16 "__DATA"
+  vmaddr data_segment
+  .word linkedit_segment - data_segment // vmsize
+  .word data_segment - mach_header // fileoff
+  .word linkedit_segment - data_segment // filesize
+  .word 7 // maxprot: rwx
+  .word 3 // initprot: rw-
+  .word 5 // nsects
+  .word 0 // flags
+  section "__la_symbol_ptr", "__DATA", lazy_symbol_pointers, lazy_symbol_pointers_end, 2, S_LAZY_SYMBOL_POINTERS, 3
+  section "__objc_selrefs", "__DATA", selrefs, selrefs_end, 2, S_LITERAL_POINTERS | S_ATTR_NO_DEAD_STRIP
+  section "__objc_classrefs", "__DATA", classrefs, classrefs_end, 2, S_REGULAR | S_ATTR_NO_DEAD_STRIP
+  section "__cfstring", "__DATA", cfstrings, cfstrings_end, 2, S_REGULAR
+  section "__objc_imageinfo", "__DATA", imageinfo, imageinfo_end, 2, S_REGULAR
+
+  .word 0x1 // LC_SEGMENT
+  .word 56
+  name16 "__LINKEDIT"
+  vmaddr linkedit_segment
+  .word 0x1000 // vmsize
+  .word linkedit_segment - mach_header // fileoff
+  .word linkedit_segment_end - linkedit_segment // filesize
+  .word 7 // maxprot: rwx
+  .word 1 // initprot: r--
+  .word 0 // nsects
+  .word 0 // flags
+
+  .word 0x2 // LC_SYMTAB
+  .word 24
+  .word symbol_table - mach_header // symoff
+  .word (symbol_table_end - symbol_table) / 12 // nsyms
+  .word string_table - mach_header // stroff
+  .word string_table_end - string_table // strsize
+
+  .word 0xb // LC_DYSYMTAB
+  .word 80
+  .word SYM_start // ilocalsym
+  .word 1 // nlocalsym
+  .word SYM_main // iextdefsym
+  .word 1 // nextdefsym
+  .word SYM_OBJC_CLASS_NSAutoreleasePool // iundefsym
+  .word SYM_printf + 1 - SYM_OBJC_CLASS_NSAutoreleasePool // nundefsym
+  .word 0 // tocoff
+  .word 0 // ntoc
+  .word 0 // modtaboff
+  .word 0 // nmodtab
+  .word 0 // extrefsymoff
+  .word 0 // nextrefsyms
+  .word indirect_symbol_table - mach_header // indirectsymoff
+  .word (indirect_symbol_table_end - indirect_symbol_table) / 4 // nindirectsyms
+  .word external_relocations - mach_header // extreloff
+  .word (external_relocations_end - external_relocations) / 8 // nextrel
+  .word 0 // locreloff
+  .word 0 // nlocrel
+
+.Lload_dylinker:
+  .word 0xe // LC_LOAD_DYLINKER
+  .word .Lload_dylinker_end - .Lload_dylinker
+  .word 12 // name offset
+  .asciz "/usr/lib/dyld"
+  .p2align 2
+.Lload_dylinker_end:
+
+  .word 0x5 // LC_UNIXTHREAD
+  .word 84
+  .word 1 // ARM_THREAD_STATE
+  .word 17 // ARM_THREAD_STATE_COUNT
+  .fill 13, 4, 0 // r0-r12
+  .word 0 // sp
+  .word 0 // lr
+  vmaddr start // pc
+  .word 0 // cpsr
+
+  load_dylib "/System/Library/Frameworks/Foundation.framework/Foundation"
+  load_dylib "/System/Library/Frameworks/CoreFoundation.framework/CoreFoundation"
+  load_dylib "/usr/lib/libobjc.A.dylib"
+  load_dylib "/usr/lib/libSystem.B.dylib"
+
+load_commands_end:
+
+/* ---------------------------------------------------------------------- */
+
+// Like a normal linker, put the code at the end of the __TEXT segment, so
+// that it's close to the data it uses.
+  .p2align 11
+text_segment = mach_header
+
+text:
+
+// Entry point. The stack has argc, then argv.
+start:
+  ldr r0, [sp]
+  add r1, sp, #4
+  call _main
+  call stub_exit
+
+// The check() function: r0 is the condition, r1 is the name.
+report:
+  push {r7, lr}
+  mov r7, sp
+  mov r2, r1
+  cmp r0, #0
+  ldrne r1, .Lreport_cstring_ok
+  ldreq r1, .Lreport_cstring_failed
+  ldr r0, .Lreport_cstring_format
+  call stub_printf
+  pop {r7, pc}
+.Lreport_cstring_ok:
+  vmaddr cstring_ok
+.Lreport_cstring_failed:
+  vmaddr cstring_failed
+.Lreport_cstring_format:
+  vmaddr cstring_format
+
+_main:
+  push {r4, r5, r6, r7, lr}
+  add r7, sp, #12
+  sub sp, sp, #4
+  // pool = [NSAutoreleasePool new]
+  ldr r0, classref_NSAutoreleasePool
+  ldr r1, selref_new
+  call stub_objc_msgSend
+  mov r6, r0
+
+  // array = [NSMutableArray new]
+  ldr r0, classref_NSMutableArray
+  ldr r1, selref_new
+  call stub_objc_msgSend
+  mov r4, r0
+  // [array addObject:@"touchHLE"]
+  ldr r1, selref_addObject
+  ldr r2, .Lmain_cfstring_touchHLE
+  call stub_objc_msgSend
+  // [array addObject:[NSNumber numberWithInt:42]]
+  ldr r0, classref_NSNumber
+  ldr r1, selref_numberWithInt
+  mov r2, #42
+  call stub_objc_msgSend
+  mov r2, r0
+  mov r0, r4
+  ldr r1, selref_addObject
+  call stub_objc_msgSend
+  // [array addObject:array]
+  mov r0, r4
+  ldr r1, selref_addObject
+  mov r2, r4
+  call stub_objc_msgSend
+  // data = [NSKeyedArchiver archivedDataWithRootObject:array]
+  ldr r0, classref_NSKeyedArchiver
+  ldr r1, selref_archivedDataWithRootObject
+  mov r2, r4
+  call stub_objc_msgSend
+  // copy = [NSKeyedUnarchiver unarchiveObjectWithData:data]
+  mov r2, r0
+  ldr r0, classref_NSKeyedUnarchiver
+  ldr r1, selref_unarchiveObjectWithData
+  call stub_objc_msgSend
+  mov r5, r0
+
+  cmp r5, #0
+  cmpne r5, r4
+  check ne, cstring_round_trip_new_object
+
+  mov r0, r5
+  ldr r1, selref_count
+  call stub_objc_msgSend
+  cmp r0, #3
+  check eq, cstring_round_trip_count
+
+  mov r0, r5
+  ldr r1, selref_objectAtIndex
+  mov r2, #0
+  call stub_objc_msgSend
+  ldr r1, selref_isEqualToString
+  ldr r2, .Lmain_cfstring_touchHLE
+  call stub_objc_msgSend
+  cmp r0, #0
+  check ne, cstring_round_trip_string
+
+  mov r0, r5
+  ldr r1, selref_objectAtIndex
+  mov r2, #1
+  call stub_objc_msgSend
+  ldr r1, selref_intValue
+  call stub_objc_msgSend
+  cmp r0, #42
+  check eq, cstring_round_trip_number
+
+  mov r0, r5
+  ldr r1, selref_objectAtIndex
+  mov r2, #2
+  call stub_objc_msgSend
+  cmp r0, r5
+  check eq, cstring_round_trip_cycle
+
+  // path = [[NSBundle mainBundle] pathForResource:@"KeyedArchive" ofType:@"plist"]
+  ldr r0, classref_NSBundle
+  ldr r1, selref_mainBundle
+  call stub_objc_msgSend
+  ldr r1, selref_pathForResource_ofType
+  ldr r2, .Lmain_cfstring_KeyedArchive
+  ldr r3, .Lmain_cfstring_plist
+  call stub_objc_msgSend
+  // root = [NSKeyedUnarchiver unarchiveObjectWithFile:path]
+  mov r2, r0
+  ldr r0, classref_NSKeyedUnarchiver
+  ldr r1, selref_unarchiveObjectWithFile
+  call stub_objc_msgSend
+  mov r4, r0
+
+  mov r0, r4
+  ldr r1, selref_objectForKey
+  ldr r2, .Lmain_cfstring_title
+  call stub_objc_msgSend
+  ldr r1, selref_isEqualToString
+  ldr r2, .Lmain_cfstring_touchHLE
+  call stub_objc_msgSend
+  cmp r0, #0
+  check ne, cstring_fixture_string
+
+  mov r0, r4
+  ldr r1, selref_objectForKey
+  ldr r2, .Lmain_cfstring_level
+  call stub_objc_msgSend
+  ldr r1, selref_intValue
+  call stub_objc_msgSend
+  cmp r0, #3
+  check eq, cstring_fixture_integer
+
+  // scores = [root objectForKey:@"scores"]
+  mov r0, r4
+  ldr r1, selref_objectForKey
+  ldr r2, .Lmain_cfstring_scores
+  call stub_objc_msgSend
+  mov r5, r0
+
+  ldr r1, selref_count
+  call stub_objc_msgSend
+  cmp r0, #2
+  check eq, cstring_fixture_array_count
+
+  mov r0, r5
+  ldr r1, selref_objectAtIndex
+  mov r2, #0
+  call stub_objc_msgSend
+  ldr r1, selref_intValue
+  call stub_objc_msgSend
+  cmp r0, #1
+  check eq, cstring_fixture_array_integer
+
+  mov r0, r5
+  ldr r1, selref_objectAtIndex
+  mov r2, #1
+  call stub_objc_msgSend
+  ldr r1, selref_floatValue
+  call stub_objc_msgSend
+  ldr r1, .Lmain_float_2_5
+  cmp r0, r1
+  check eq, cstring_fixture_array_real
+
+  // loop = [root objectForKey:@"loop"]
+  mov r0, r4
+  ldr r1, selref_objectForKey
+  ldr r2, .Lmain_cfstring_loop
+  call stub_objc_msgSend
+  mov r5, r0
+
+  ldr r1, selref_objectAtIndex
+  mov r2, #1
+  call stub_objc_msgSend
+  cmp r0, r5
+  check eq, cstring_fixture_cycle
+
+  // [pool release]
+  mov r0, r6
+  ldr r1, selref_release
+  call stub_objc_msgSend
+  mov r0, #0
+  add sp, sp, #4
+  pop {r4, r5, r6, r7, pc}
+.Lmain_cfstring_touchHLE:
+  vmaddr cfstring_touchHLE
+.Lmain_cfstring_KeyedArchive:
+  vmaddr cfstring_KeyedArchive
+.Lmain_cfstring_plist:
+  vmaddr cfstring_plist
+.Lmain_cfstring_title:
+  vmaddr cfstring_title
+.Lmain_cfstring_level:
+  vmaddr cfstring_level
+.Lmain_cfstring_scores:
+  vmaddr cfstring_scores
+.Lmain_cfstring_loop:
+  vmaddr cfstring_loop
+.Lmain_float_2_5:
+  .float 2.5
+
+text_end:
+
+symbol_stubs:
+stub_objc_msgSend:
+  symbol_stub lazy_pointer_objc_msgSend
+stub_printf:
+  symbol_stub lazy_pointer_printf
+stub_exit:
+  symbol_stub lazy_pointer_exit
+symbol_stubs_end:
+
+cstrings:
+cstring_ok:
+  .asciz "ok"
+cstring_failed:
+  .asciz "FAILED"
+cstring_format:
+  .asciz "%s: %s\n"
+cstring_round_trip_new_object:
+  .asciz "round trip: new object"
+cstring_round_trip_count:
+  .asciz "round trip: count"
+cstring_round_trip_string:
+  .asciz "round trip: string"
+cstring_round_trip_number:
+  .asciz "round trip: number"
+cstring_round_trip_cycle:
+  .asciz "round trip: cycle"
+cstring_fixture_string:
+  .asciz "fixture: string"
+cstring_fixture_integer:
+  .asciz "fixture: integer"
+cstring_fixture_array_count:
+  .asciz "fixture: array count"
+cstring_fixture_array_integer:
+  .asciz "fixture: array integer"
+cstring_fixture_array_real:
+  .asciz "fixture: array real"
+cstring_fixture_cycle:
+  .asciz "fixture: cycle"
+cstring_touchHLE:
+  .asciz "touchHLE"
+cstring_KeyedArchive:
+  .asciz "KeyedArchive"
+cstring_plist:
+  .asciz "plist"
+cstring_title:
+  .asciz "title"
+cstring_level:
+  .asciz "level"
+cstring_scores:
+  .asciz "scores"
+cstring_loop:
+  .asciz "loop"
+cstring_new:
+  .asciz "new"
+cstring_release:
+  .asciz "release"
+cstring_addObject:
+  .asciz "addObject:"
+cstring_numberWithInt:
+  .asciz "numberWithInt:"
+cstring_archivedDataWithRootObject:
+  .asciz "archivedDataWithRootObject:"
+cstring_unarchiveObjectWithData:
+  .asciz "unarchiveObjectWithData:"
+cstring_unarchiveObjectWithFile:
+  .asciz "unarchiveObjectWithFile:"
+cstring_count:
+  .asciz "count"
+cstring_objectAtIndex:
+  .asciz "objectAtIndex:"
+cstring_isEqualToString:
+  .asciz "isEqualToString:"
+cstring_intValue:
+  .asciz "intValue"
+cstring_floatValue:
+  .asciz "floatValue"
+cstring_mainBundle:
+  .asciz "mainBundle"
+cstring_pathForResource_ofType:
+  .asciz "pathForResource:ofType:"
+cstring_objectForKey:
+  .asciz "objectForKey:"
+cstrings_end:
+
+/* ---------------------------------------------------------------------- */
+
+  .p2align 12
+data_segment:
+
+lazy_symbol_pointers:
+lazy_pointer_objc_msgSend:
+  .word 0
+lazy_pointer_printf:
+  .word 0
+lazy_pointer_exit:
+  .word 0
+lazy_symbol_pointers_end:
+
+// The dynamic linker replaces these with the selectors.
+selrefs:
+selref_new:
+  vmaddr cstring_new
+selref_release:
+  vmaddr cstring_release
+selref_addObject:
+  vmaddr cstring_addObject
+selref_numberWithInt:
+  vmaddr cstring_numberWithInt
+selref_archivedDataWithRootObject:
+  vmaddr cstring_archivedDataWithRootObject
+selref_unarchiveObjectWithData:
+  vmaddr cstring_unarchiveObjectWithData
+selref_unarchiveObjectWithFile:
+  vmaddr cstring_unarchiveObjectWithFile
+selref_count:
+  vmaddr cstring_count
+selref_objectAtIndex:
+  vmaddr cstring_objectAtIndex
+selref_isEqualToString:
+  vmaddr cstring_isEqualToString
+selref_intValue:
+  vmaddr cstring_intValue
+selref_floatValue:
+  vmaddr cstring_floatValue
+selref_mainBundle:
+  vmaddr cstring_mainBundle
+selref_pathForResource_ofType:
+  vmaddr cstring_pathForResource_ofType
+selref_objectForKey:
+  vmaddr cstring_objectForKey
+selrefs_end:
+
+// The dynamic linker fills these in, see the external relocations.
+classrefs:
+classref_NSAutoreleasePool:
+  .word 0
+classref_NSBundle:
+  .word 0
+classref_NSKeyedArchiver:
+  .word 0
+classref_NSKeyedUnarchiver:
+  .word 0
+classref_NSMutableArray:
+  .word 0
+classref_NSNumber:
+  .word 0
+classrefs_end:
+
+// struct __NSConstantString, with the isa filled in by the dynamic linker.
+.macro cfstring cstring, length
+  .word 0 // isa: ___CFConstantStringClassReference
+  .word 0x7c8 // flags
+  vmaddr \cstring
+  .word \length
+.endm
+
+cfstrings:
+cfstring_touchHLE:
+  cfstring cstring_touchHLE, 8
+cfstring_KeyedArchive:
+  cfstring cstring_KeyedArchive, 12
+cfstring_plist:
+  cfstring cstring_plist, 5
+cfstring_title:
+  cfstring cstring_title, 5
+cfstring_level:
+  cfstring cstring_level, 5
+cfstring_scores:
+  cfstring cstring_scores, 6
+cfstring_loop:
+  cfstring cstring_loop, 4
+cfstrings_end:
+
+imageinfo:
+  .word 0 // version
+  .word 0 // flags
+imageinfo_end:
+
+/* ---------------------------------------------------------------------- */
+
+  .p2align 12
+linkedit_segment:
+
+symbol_table:
+  nlist symbol_start, N_SECT, 1, 0, start - mach_header + VM_OFFSET
+  nlist symbol_main, N_SECT | N_EXT, 1, 0, _main - mach_header + VM_OFFSET
+  nlist symbol_OBJC_CLASS_NSAutoreleasePool, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSBundle, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSKeyedArchiver, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSKeyedUnarchiver, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSMutableArray, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSNumber, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_CFConstantStringClassReference, N_UNDF | N_EXT, 0, COREFOUNDATION, 0
+  nlist symbol_exit, N_UNDF | N_EXT, 0, LIBSYSTEM, 0
+  nlist symbol_objc_msgSend, N_UNDF | N_EXT, 0, LIBOBJC, 0
+  nlist symbol_printf, N_UNDF | N_EXT, 0, LIBSYSTEM, 0
+symbol_table_end:
+
+indirect_symbol_table:
+  // __symbol_stub4
+  .word SYM_objc_msgSend
+  .word SYM_printf
+  .word SYM_exit
+  // __la_symbol_ptr
+  .word SYM_objc_msgSend
+  .word SYM_printf
+  .word SYM_exit
+indirect_symbol_table_end:
+
+external_relocations:
+  extrel classref_NSAutoreleasePool, SYM_OBJC_CLASS_NSAutoreleasePool
+  extrel classref_NSBundle, SYM_OBJC_CLASS_NSBundle
+  extrel classref_NSKeyedArchiver, SYM_OBJC_CLASS_NSKeyedArchiver
+  extrel classref_NSKeyedUnarchiver, SYM_OBJC_CLASS_NSKeyedUnarchiver
+  extrel classref_NSMutableArray, SYM_OBJC_CLASS_NSMutableArray
+  extrel classref_NSNumber, SYM_OBJC_CLASS_NSNumber
+  extrel cfstring_touchHLE, SYM_CFConstantStringClassReference
+  extrel cfstring_KeyedArchive, SYM_CFConstantStringClassReference
+  extrel cfstring_plist, SYM_CFConstantStringClassReference
+  extrel cfstring_title, SYM_CFConstantStringClassReference
+  extrel cfstring_level, SYM_CFConstantStringClassReference
+  extrel cfstring_scores, SYM_CFConstantStringClassReference
+  extrel cfstring_loop, SYM_CFConstantStringClassReference
+external_relocations_end:
+
+string_table:
+  .asciz " "
+symbol_start:
+  .asciz "start"
+symbol_main:
+  .asciz "_main"
+symbol_OBJC_CLASS_NSAutoreleasePool:
+  .asciz "_OBJC_CLASS_$_NSAutoreleasePool"
+symbol_OBJC_CLASS_NSBundle:
+  .asciz "_OBJC_CLASS_$_NSBundle"
+symbol_OBJC_CLASS_NSKeyedArchiver:
+  .asciz "_OBJC_CLASS_$_NSKeyedArchiver"
+symbol_OBJC_CLASS_NSKeyedUnarchiver:
+  .asciz "_OBJC_CLASS_$_NSKeyedUnarchiver"
+symbol_OBJC_CLASS_NSMutableArray:
+  .asciz "_OBJC_CLASS_$_NSMutableArray"
+symbol_OBJC_CLASS_NSNumber:
+  .asciz "_OBJC_CLASS_$_NSNumber"
+symbol_CFConstantStringClassReference:
+  .asciz "___CFConstantStringClassReference"
+symbol_exit:
+  .asciz "_exit"
+symbol_objc_msgSend:
+  .asciz "_objc_msgSend"
+symbol_printf:
+  .asciz "_printf"
+  .p2align 2
+string_table_end:
+
+linkedit_segment_end:
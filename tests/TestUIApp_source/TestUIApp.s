@@ -0,0 +1,735 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+/*
+This is the source of TestUIApp, which is used by the golden tests to check
+what the compositor draws and how touch input reaches UIKit controls.
+
+Unlike TestApp, this isn't built with the iPhone SDK. The headers, load
+commands, symbol tables and Objective-C metadata are all written out by hand
+below (with the help of ../asm/mach_o.inc), so that it can be built anywhere
+that has LLVM:
+
+  llvm-mc -triple=armv6-none-eabi -filetype=obj -I ../asm TestUIApp.s -o TestUIApp.o
+  llvm-objcopy -O binary --only-section=.data TestUIApp.o ../TestUIApp.app/TestUIApp
+
+The app is equivalent to this Objective-C:
+
+  static UIView *box;
+
+  @interface AppDelegate : NSObject
+  @end
+  @implementation AppDelegate
+  - (void)applicationDidFinishLaunching:(UIApplication *)application {
+    puts("applicationDidFinishLaunching:");
+    UIWindow *window =
+        [[UIWindow alloc] initWithFrame:CGRectMake(0, 0, 320, 480)];
+    window.backgroundColor = [UIColor blueColor];
+    box = [[UIView alloc] initWithFrame:CGRectMake(40, 40, 240, 160)];
+    box.backgroundColor = [UIColor redColor];
+    [window addSubview:box];
+    UIControl *button =
+        [[UIControl alloc] initWithFrame:CGRectMake(40, 280, 240, 160)];
+    button.backgroundColor = [UIColor greenColor];
+    [button addTarget:self
+                  action:@selector(buttonPressed:)
+        forControlEvents:UIControlEventTouchUpInside];
+    [window addSubview:button];
+    [window makeKeyAndVisible];
+    // Guest time only advances while guest code runs, so something has to
+    // keep running for the replayed input to be delivered.
+    [NSTimer scheduledTimerWithTimeInterval:1.0 / 60
+                                     target:self
+                                   selector:@selector(tick:)
+                                   userInfo:nil
+                                    repeats:YES];
+  }
+  - (void)applicationDidBecomeActive:(UIApplication *)application {
+  }
+  - (void)applicationWillTerminate:(UIApplication *)application {
+    puts("applicationWillTerminate:");
+  }
+  - (void)buttonPressed:(id)sender {
+    puts("buttonPressed:");
+    box.backgroundColor = [UIColor yellowColor];
+  }
+  - (void)tick:(NSTimer *)timer {
+    for (volatile int i = 20480; i != 0; i--) {
+    }
+  }
+  @end
+
+  int main(int argc, char *argv[]) {
+    NSAutoreleasePool *pool = [NSAutoreleasePool new];
+    int result = UIApplicationMain(argc, argv, nil, @"AppDelegate");
+    [pool release];
+    return result;
+  }
+
+The segments are laid out so that file offset N is loaded at address
+0x1000 + N, like a normal executable with a 4KiB __PAGEZERO.
+*/
+
+.include "mach_o.inc"
+
+// Library ordinals for two-level namespace lookup, see the LC_LOAD_DYLIB
+// commands.
+.set FOUNDATION, 1 << 8
+.set UIKIT, 2 << 8
+.set COREFOUNDATION, 3 << 8
+.set LIBOBJC, 4 << 8
+.set LIBSYSTEM, 5 << 8
+
+// Indices in the symbol table
+.set SYM_start, 0
+.set SYM_main, 1
+.set SYM_OBJC_CLASS_NSAutoreleasePool, 2
+.set SYM_OBJC_CLASS_NSObject, 3
+.set SYM_OBJC_CLASS_NSTimer, 4
+.set SYM_OBJC_CLASS_UIColor, 5
+.set SYM_OBJC_CLASS_UIControl, 6
+.set SYM_OBJC_CLASS_UIView, 7
+.set SYM_OBJC_CLASS_UIWindow, 8
+.set SYM_OBJC_METACLASS_NSObject, 9
+.set SYM_UIApplicationMain, 10
+.set SYM_CFConstantStringClassReference, 11
+.set SYM_exit, 12
+.set SYM_objc_msgSend, 13
+.set SYM_puts, 14
+
+.set UIControlEventTouchUpInside, 1 << 6
+
+/* ---------------------------------------------------------------------- */
+
+mach_header:
+  .word 0xfeedface // MH_MAGIC
+  .word 12 // CPU_TYPE_ARM
+  .word 6 // CPU_SUBTYPE_ARM_V6
+  .word 2 // MH_EXECUTE
+  .word 13 // ncmds
+  .word load_commands_end - load_commands
+  .word 0x85 // MH_NOUNDEFS | MH_DYLDLINK | MH_TWOLEVEL
+
+load_commands:
+
+  .word 0x1 // LC_SEGMENT
+  .word 56
+  name16 "__PAGEZERO"
+  .word 0 // vmaddr
+  .word 0x1000 // vmsize
+  .word 0 // fileoff
+  .word 0 // filesize
+  .word 0 // maxprot
+  .word 0 // initprot
+  .word 0 // nsects
+  .word 0 // flags
+
+  .word 0x1 // LC_SEGMENT
+  .word 56 + 68 * 3
+  name16 "__TEXT"
+  vmaddr text_segment
+  .word data_segment - text_segment // vmsize
+  .word text_segment - mach_header // fileoff
+  .word data_segment - text_segment // filesize
+  .word 7 // maxprot: rwx
+  .word 5 // initprot: r-x
+  .word 3 // nsects
+  .word 0 // flags
+  section "__text", "__TEXT", text, text_end, 2, S_REGULAR | S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS
+  section "__symbol_stub4", "__TEXT", symbol_stubs, symbol_stubs_end, 2, S_SYMBOL_STUBS | S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS, 0, 12
+  section "__cstring", "__TEXT", cstrings, cstrings_end, 2, S_CSTRING_LITERALS
+
+  .word 0x1 // LC_SEGMENT
+  .word 56 + 68 * 9
+  name16 "__DATA"
+  vmaddr data_segment
+  .word linkedit_segment - data_segment // vmsize
+  .word data_segment - mach_header // fileoff
+  .word linkedit_segment - data_segment // filesize
+  .word 7 // maxprot: rwx
+  .word 3 // initprot: rw-
+  .word 9 // nsects
+  .word 0 // flags
+  section "__la_symbol_ptr", "__DATA", lazy_symbol_pointers, lazy_symbol_pointers_end, 2, S_LAZY_SYMBOL_POINTERS, 4
+  section "__objc_selrefs", "__DATA", selrefs, selrefs_end, 2, S_LITERAL_POINTERS | S_ATTR_NO_DEAD_STRIP
+  section "__objc_classrefs", "__DATA", classrefs, classrefs_end, 2, S_REGULAR | S_ATTR_NO_DEAD_STRIP
+  section "__data", "__DATA", data, data_end, 2, S_REGULAR
+  section "__cfstring", "__DATA", cfstrings, cfstrings_end, 2, S_REGULAR
+  section "__objc_classlist", "__DATA", classlist, classlist_end, 2, S_REGULAR | S_ATTR_NO_DEAD_STRIP
+  section "__objc_imageinfo", "__DATA", imageinfo, imageinfo_end, 2, S_REGULAR
+  section "__objc_const", "__DATA", objc_const, objc_const_end, 2, S_REGULAR
+  section "__objc_data", "__DATA", objc_data, objc_data_end, 2, S_REGULAR
+
+  .word 0x1 // LC_SEGMENT
+  .word 56
+  name16 "__LINKEDIT"
+  vmaddr linkedit_segment
+  .word 0x1000 // vmsize
+  .word linkedit_segment - mach_header // fileoff
+  .word linkedit_segment_end - linkedit_segment // filesize
+  .word 7 // maxprot: rwx
+  .word 1 // initprot: r--
+  .word 0 // nsects
+  .word 0 // flags
+
+  .word 0x2 // LC_SYMTAB
+  .word 24
+  .word symbol_table - mach_header // symoff
+  .word (symbol_table_end - symbol_table) / 12 // nsyms
+  .word string_table - mach_header // stroff
+  .word string_table_end - string_table // strsize
+
+  .word 0xb // LC_DYSYMTAB
+  .word 80
+  .word SYM_start // ilocalsym
+  .word 1 // nlocalsym
+  .word SYM_main // iextdefsym
+  .word 1 // nextdefsym
+  .word SYM_OBJC_CLASS_NSAutoreleasePool // iundefsym
+  .word SYM_puts + 1 - SYM_OBJC_CLASS_NSAutoreleasePool // nundefsym
+  .word 0 // tocoff
+  .word 0 // ntoc
+  .word 0 // modtaboff
+  .word 0 // nmodtab
+  .word 0 // extrefsymoff
+  .word 0 // nextrefsyms
+  .word indirect_symbol_table - mach_header // indirectsymoff
+  .word (indirect_symbol_table_end - indirect_symbol_table) / 4 // nindirectsyms
+  .word external_relocations - mach_header // extreloff
+  .word (external_relocations_end - external_relocations) / 8 // nextrel
+  .word 0 // locreloff
+  .word 0 // nlocrel
+
+.Lload_dylinker:
+  .word 0xe // LC_LOAD_DYLINKER
+  .word .Lload_dylinker_end - .Lload_dylinker
+  .word 12 // name offset
+  .asciz "/usr/lib/dyld"
+  .p2align 2
+.Lload_dylinker_end:
+
+  .word 0x5 // LC_UNIXTHREAD
+  .word 84
+  .word 1 // ARM_THREAD_STATE
+  .word 17 // ARM_THREAD_STATE_COUNT
+  .fill 13, 4, 0 // r0-r12
+  .word 0 // sp
+  .word 0 // lr
+  vmaddr start // pc
+  .word 0 // cpsr
+
+  load_dylib "/System/Library/Frameworks/Foundation.framework/Foundation"
+  load_dylib "/System/Library/Frameworks/UIKit.framework/UIKit"
+  load_dylib "/System/Library/Frameworks/CoreFoundation.framework/CoreFoundation"
+  load_dylib "/usr/lib/libobjc.A.dylib"
+  load_dylib "/usr/lib/libSystem.B.dylib"
+
+load_commands_end:
+
+/* ---------------------------------------------------------------------- */
+
+// Like a normal linker, put the code at the end of the __TEXT segment, so
+// that it's close to the data it uses.
+  .p2align 11
+text_segment = mach_header
+
+text:
+
+// Entry point. The stack has argc, then argv.
+start:
+  ldr r0, [sp]
+  add r1, sp, #4
+  call _main
+  call stub_exit
+
+_main:
+  push {r4, r5, r6, r7, lr}
+  add r7, sp, #12
+  sub sp, sp, #4
+  mov r4, r0
+  mov r5, r1
+  // pool = [NSAutoreleasePool new]
+  ldr r0, classref_NSAutoreleasePool
+  ldr r1, selref_new
+  call stub_objc_msgSend
+  mov r6, r0
+  // result = UIApplicationMain(argc, argv, nil, @"AppDelegate")
+  mov r0, r4
+  mov r1, r5
+  mov r2, #0
+  ldr r3, .Lmain_cfstring_AppDelegate
+  call stub_UIApplicationMain
+  mov r4, r0
+  // [pool release]
+  mov r0, r6
+  ldr r1, selref_release
+  call stub_objc_msgSend
+  mov r0, r4
+  add sp, sp, #4
+  pop {r4, r5, r6, r7, pc}
+.Lmain_cfstring_AppDelegate:
+  vmaddr cfstring_AppDelegate
+
+// -[AppDelegate applicationDidFinishLaunching:]
+AppDelegate_applicationDidFinishLaunching:
+  push {r4, r5, r6, r7, lr}
+  add r7, sp, #12
+  // Space for arguments passed on the stack.
+  sub sp, sp, #20
+  mov r6, r0 // self
+
+  ldr r0, .Llaunch_cstring_applicationDidFinishLaunching
+  call stub_puts
+
+  // window = [[UIWindow alloc] initWithFrame:...]
+  ldr r0, classref_UIWindow
+  ldr r1, selref_alloc
+  call stub_objc_msgSend
+  ldr r1, selref_initWithFrame
+  ldr r2, .Llaunch_window_frame
+  ldr r3, .Llaunch_window_frame + 4
+  ldr r12, .Llaunch_window_frame + 8
+  str r12, [sp]
+  ldr r12, .Llaunch_window_frame + 12
+  str r12, [sp, #4]
+  call stub_objc_msgSend
+  mov r4, r0
+  // window.backgroundColor = [UIColor blueColor]
+  ldr r0, classref_UIColor
+  ldr r1, selref_blueColor
+  call stub_objc_msgSend
+  mov r2, r0
+  mov r0, r4
+  ldr r1, selref_setBackgroundColor
+  call stub_objc_msgSend
+
+  // box = [[UIView alloc] initWithFrame:...]
+  ldr r0, classref_UIView
+  ldr r1, selref_alloc
+  call stub_objc_msgSend
+  ldr r1, selref_initWithFrame
+  ldr r2, .Llaunch_box_frame
+  ldr r3, .Llaunch_box_frame + 4
+  ldr r12, .Llaunch_box_frame + 8
+  str r12, [sp]
+  ldr r12, .Llaunch_box_frame + 12
+  str r12, [sp, #4]
+  call stub_objc_msgSend
+  mov r5, r0
+  ldr r12, .Llaunch_box
+  str r5, [r12]
+  // box.backgroundColor = [UIColor redColor]
+  ldr r0, classref_UIColor
+  ldr r1, selref_redColor
+  call stub_objc_msgSend
+  mov r2, r0
+  mov r0, r5
+  ldr r1, selref_setBackgroundColor
+  call stub_objc_msgSend
+  // [window addSubview:box]
+  mov r0, r4
+  ldr r1, selref_addSubview
+  mov r2, r5
+  call stub_objc_msgSend
+
+  // button = [[UIControl alloc] initWithFrame:...]
+  ldr r0, classref_UIControl
+  ldr r1, selref_alloc
+  call stub_objc_msgSend
+  ldr r1, selref_initWithFrame
+  ldr r2, .Llaunch_button_frame
+  ldr r3, .Llaunch_button_frame + 4
+  ldr r12, .Llaunch_button_frame + 8
+  str r12, [sp]
+  ldr r12, .Llaunch_button_frame + 12
+  str r12, [sp, #4]
+  call stub_objc_msgSend
+  mov r5, r0
+  // button.backgroundColor = [UIColor greenColor]
+  ldr r0, classref_UIColor
+  ldr r1, selref_greenColor
+  call stub_objc_msgSend
+  mov r2, r0
+  mov r0, r5
+  ldr r1, selref_setBackgroundColor
+  call stub_objc_msgSend
+  // [button addTarget:self action:@selector(buttonPressed:) forControlEvents:...]
+  mov r0, r5
+  ldr r1, selref_addTarget_action_forControlEvents
+  mov r2, r6
+  ldr r3, selref_buttonPressed
+  mov r12, #UIControlEventTouchUpInside
+  str r12, [sp]
+  call stub_objc_msgSend
+  // [window addSubview:button]
+  mov r0, r4
+  ldr r1, selref_addSubview
+  mov r2, r5
+  call stub_objc_msgSend
+
+  // [window makeKeyAndVisible]
+  mov r0, r4
+  ldr r1, selref_makeKeyAndVisible
+  call stub_objc_msgSend
+
+  // [NSTimer scheduledTimerWithTimeInterval:... target:self selector:@selector(tick:) userInfo:nil repeats:YES]
+  ldr r0, classref_NSTimer
+  ldr r1, selref_scheduledTimerWithTimeInterval_target_selector_userInfo_repeats
+  ldr r2, .Llaunch_timer_interval
+  ldr r3, .Llaunch_timer_interval + 4
+  str r6, [sp]
+  ldr r12, selref_tick
+  str r12, [sp, #4]
+  mov r12, #0
+  str r12, [sp, #8]
+  mov r12, #1
+  str r12, [sp, #12]
+  call stub_objc_msgSend
+
+  add sp, sp, #20
+  pop {r4, r5, r6, r7, pc}
+.Llaunch_cstring_applicationDidFinishLaunching:
+  vmaddr cstring_applicationDidFinishLaunching
+.Llaunch_box:
+  vmaddr box
+.Llaunch_window_frame:
+  .float 0, 0, 320, 480
+.Llaunch_box_frame:
+  .float 40, 40, 240, 160
+.Llaunch_button_frame:
+  .float 40, 280, 240, 160
+.Llaunch_timer_interval:
+  .double 0.016666666666666666
+
+// -[AppDelegate applicationDidBecomeActive:]
+AppDelegate_applicationDidBecomeActive:
+  bx lr
+
+// -[AppDelegate applicationWillTerminate:]
+AppDelegate_applicationWillTerminate:
+  ldr r0, .Lterminate_cstring_applicationWillTerminate
+  jump stub_puts
+.Lterminate_cstring_applicationWillTerminate:
+  vmaddr cstring_applicationWillTerminate
+
+// -[AppDelegate buttonPressed:]
+AppDelegate_buttonPressed:
+  push {r7, lr}
+  mov r7, sp
+  ldr r0, .Lpressed_cstring_buttonPressed
+  call stub_puts
+  // box.backgroundColor = [UIColor yellowColor]
+  ldr r0, classref_UIColor
+  ldr r1, selref_yellowColor
+  call stub_objc_msgSend
+  mov r2, r0
+  ldr r0, .Lpressed_box
+  ldr r0, [r0]
+  ldr r1, selref_setBackgroundColor
+  call stub_objc_msgSend
+  pop {r7, pc}
+.Lpressed_cstring_buttonPressed:
+  vmaddr cstring_buttonPressed
+.Lpressed_box:
+  vmaddr box
+
+// -[AppDelegate tick:]
+AppDelegate_tick:
+  mov r0, #20480
+.Ltick_loop:
+  subs r0, r0, #1
+  jump_if_ne .Ltick_loop
+  bx lr
+
+text_end:
+
+symbol_stubs:
+stub_objc_msgSend:
+  symbol_stub lazy_pointer_objc_msgSend
+stub_UIApplicationMain:
+  symbol_stub lazy_pointer_UIApplicationMain
+stub_puts:
+  symbol_stub lazy_pointer_puts
+stub_exit:
+  symbol_stub lazy_pointer_exit
+symbol_stubs_end:
+
+cstrings:
+cstring_AppDelegate:
+  .asciz "AppDelegate"
+cstring_applicationDidFinishLaunching:
+  .asciz "applicationDidFinishLaunching:"
+cstring_applicationDidBecomeActive:
+  .asciz "applicationDidBecomeActive:"
+cstring_applicationWillTerminate:
+  .asciz "applicationWillTerminate:"
+cstring_buttonPressed:
+  .asciz "buttonPressed:"
+cstring_tick:
+  .asciz "tick:"
+cstring_new:
+  .asciz "new"
+cstring_release:
+  .asciz "release"
+cstring_alloc:
+  .asciz "alloc"
+cstring_initWithFrame:
+  .asciz "initWithFrame:"
+cstring_setBackgroundColor:
+  .asciz "setBackgroundColor:"
+cstring_addSubview:
+  .asciz "addSubview:"
+cstring_addTarget_action_forControlEvents:
+  .asciz "addTarget:action:forControlEvents:"
+cstring_makeKeyAndVisible:
+  .asciz "makeKeyAndVisible"
+cstring_scheduledTimerWithTimeInterval_target_selector_userInfo_repeats:
+  .asciz "scheduledTimerWithTimeInterval:target:selector:userInfo:repeats:"
+cstring_blueColor:
+  .asciz "blueColor"
+cstring_redColor:
+  .asciz "redColor"
+cstring_greenColor:
+  .asciz "greenColor"
+cstring_yellowColor:
+  .asciz "yellowColor"
+cstring_method_type:
+  .asciz "v12@0:4@8"
+cstrings_end:
+
+/* ---------------------------------------------------------------------- */
+
+  .p2align 12
+data_segment:
+
+lazy_symbol_pointers:
+lazy_pointer_objc_msgSend:
+  .word 0
+lazy_pointer_UIApplicationMain:
+  .word 0
+lazy_pointer_puts:
+  .word 0
+lazy_pointer_exit:
+  .word 0
+lazy_symbol_pointers_end:
+
+// The dynamic linker replaces these with the selectors.
+selrefs:
+selref_new:
+  vmaddr cstring_new
+selref_release:
+  vmaddr cstring_release
+selref_alloc:
+  vmaddr cstring_alloc
+selref_initWithFrame:
+  vmaddr cstring_initWithFrame
+selref_setBackgroundColor:
+  vmaddr cstring_setBackgroundColor
+selref_addSubview:
+  vmaddr cstring_addSubview
+selref_addTarget_action_forControlEvents:
+  vmaddr cstring_addTarget_action_forControlEvents
+selref_buttonPressed:
+  vmaddr cstring_buttonPressed
+selref_makeKeyAndVisible:
+  vmaddr cstring_makeKeyAndVisible
+selref_scheduledTimerWithTimeInterval_target_selector_userInfo_repeats:
+  vmaddr cstring_scheduledTimerWithTimeInterval_target_selector_userInfo_repeats
+selref_tick:
+  vmaddr cstring_tick
+selref_blueColor:
+  vmaddr cstring_blueColor
+selref_redColor:
+  vmaddr cstring_redColor
+selref_greenColor:
+  vmaddr cstring_greenColor
+selref_yellowColor:
+  vmaddr cstring_yellowColor
+selrefs_end:
+
+// The dynamic linker fills these in, see the external relocations.
+classrefs:
+classref_NSAutoreleasePool:
+  .word 0
+classref_NSTimer:
+  .word 0
+classref_UIColor:
+  .word 0
+classref_UIControl:
+  .word 0
+classref_UIView:
+  .word 0
+classref_UIWindow:
+  .word 0
+classrefs_end:
+
+data:
+box:
+  .word 0
+data_end:
+
+cfstrings:
+cfstring_AppDelegate:
+  .word 0 // isa: ___CFConstantStringClassReference
+  .word 0x7c8 // flags
+  vmaddr cstring_AppDelegate
+  .word 11 // length
+cfstrings_end:
+
+classlist:
+  vmaddr class_AppDelegate
+classlist_end:
+
+imageinfo:
+  .word 0 // version
+  .word 0 // flags
+imageinfo_end:
+
+objc_const:
+// struct class_ro_t
+metaclass_ro_AppDelegate:
+  .word 1 // flags: RO_META
+  .word 20 // instanceStart
+  .word 20 // instanceSize
+  .word 0 // ivarLayout
+  vmaddr cstring_AppDelegate // name
+  .word 0 // baseMethods
+  .word 0 // baseProtocols
+  .word 0 // ivars
+  .word 0 // weakIvarLayout
+  .word 0 // baseProperties
+class_ro_AppDelegate:
+  .word 0 // flags
+  .word 4 // instanceStart
+  .word 4 // instanceSize
+  .word 0 // ivarLayout
+  vmaddr cstring_AppDelegate // name
+  vmaddr methods_AppDelegate // baseMethods
+  .word 0 // baseProtocols
+  .word 0 // ivars
+  .word 0 // weakIvarLayout
+  .word 0 // baseProperties
+// struct method_list_t
+methods_AppDelegate:
+  .word 12 // entsize
+  .word 5 // count
+  vmaddr cstring_applicationDidFinishLaunching
+  vmaddr cstring_method_type
+  vmaddr AppDelegate_applicationDidFinishLaunching
+  vmaddr cstring_applicationDidBecomeActive
+  vmaddr cstring_method_type
+  vmaddr AppDelegate_applicationDidBecomeActive
+  vmaddr cstring_applicationWillTerminate
+  vmaddr cstring_method_type
+  vmaddr AppDelegate_applicationWillTerminate
+  vmaddr cstring_buttonPressed
+  vmaddr cstring_method_type
+  vmaddr AppDelegate_buttonPressed
+  vmaddr cstring_tick
+  vmaddr cstring_method_type
+  vmaddr AppDelegate_tick
+objc_const_end:
+
+objc_data:
+// struct class_t
+class_AppDelegate:
+  vmaddr metaclass_AppDelegate // isa
+  .word 0 // superclass: _OBJC_CLASS_$_NSObject
+  .word 0 // cache
+  .word 0 // vtable
+  vmaddr class_ro_AppDelegate // data
+metaclass_AppDelegate:
+  .word 0 // isa: _OBJC_METACLASS_$_NSObject
+  .word 0 // superclass: _OBJC_METACLASS_$_NSObject
+  .word 0 // cache
+  .word 0 // vtable
+  vmaddr metaclass_ro_AppDelegate // data
+objc_data_end:
+
+/* ---------------------------------------------------------------------- */
+
+  .p2align 12
+linkedit_segment:
+
+symbol_table:
+  nlist symbol_start, N_SECT, 1, 0, start - mach_header + VM_OFFSET
+  nlist symbol_main, N_SECT | N_EXT, 1, 0, _main - mach_header + VM_OFFSET
+  nlist symbol_OBJC_CLASS_NSAutoreleasePool, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSObject, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_NSTimer, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_OBJC_CLASS_UIColor, N_UNDF | N_EXT, 0, UIKIT, 0
+  nlist symbol_OBJC_CLASS_UIControl, N_UNDF | N_EXT, 0, UIKIT, 0
+  nlist symbol_OBJC_CLASS_UIView, N_UNDF | N_EXT, 0, UIKIT, 0
+  nlist symbol_OBJC_CLASS_UIWindow, N_UNDF | N_EXT, 0, UIKIT, 0
+  nlist symbol_OBJC_METACLASS_NSObject, N_UNDF | N_EXT, 0, FOUNDATION, 0
+  nlist symbol_UIApplicationMain, N_UNDF | N_EXT, 0, UIKIT, 0
+  nlist symbol_CFConstantStringClassReference, N_UNDF | N_EXT, 0, COREFOUNDATION, 0
+  nlist symbol_exit, N_UNDF | N_EXT, 0, LIBSYSTEM, 0
+  nlist symbol_objc_msgSend, N_UNDF | N_EXT, 0, LIBOBJC, 0
+  nlist symbol_puts, N_UNDF | N_EXT, 0, LIBSYSTEM, 0
+symbol_table_end:
+
+indirect_symbol_table:
+  // __symbol_stub4
+  .word SYM_objc_msgSend
+  .word SYM_UIApplicationMain
+  .word SYM_puts
+  .word SYM_exit
+  // __la_symbol_ptr
+  .word SYM_objc_msgSend
+  .word SYM_UIApplicationMain
+  .word SYM_puts
+  .word SYM_exit
+indirect_symbol_table_end:
+
+external_relocations:
+  extrel classref_NSAutoreleasePool, SYM_OBJC_CLASS_NSAutoreleasePool
+  extrel classref_NSTimer, SYM_OBJC_CLASS_NSTimer
+  extrel classref_UIColor, SYM_OBJC_CLASS_UIColor
+  extrel classref_UIControl, SYM_OBJC_CLASS_UIControl
+  extrel classref_UIView, SYM_OBJC_CLASS_UIView
+  extrel classref_UIWindow, SYM_OBJC_CLASS_UIWindow
+  extrel cfstring_AppDelegate, SYM_CFConstantStringClassReference
+  extrel class_AppDelegate + 4, SYM_OBJC_CLASS_NSObject
+  extrel metaclass_AppDelegate, SYM_OBJC_METACLASS_NSObject
+  extrel metaclass_AppDelegate + 4, SYM_OBJC_METACLASS_NSObject
+external_relocations_end:
+
+string_table:
+  .asciz " "
+symbol_start:
+  .asciz "start"
+symbol_main:
+  .asciz "_main"
+symbol_OBJC_CLASS_NSAutoreleasePool:
+  .asciz "_OBJC_CLASS_$_NSAutoreleasePool"
+symbol_OBJC_CLASS_NSObject:
+  .asciz "_OBJC_CLASS_$_NSObject"
+symbol_OBJC_CLASS_NSTimer:
+  .asciz "_OBJC_CLASS_$_NSTimer"
+symbol_OBJC_CLASS_UIColor:
+  .asciz "_OBJC_CLASS_$_UIColor"
+symbol_OBJC_CLASS_UIControl:
+  .asciz "_OBJC_CLASS_$_UIControl"
+symbol_OBJC_CLASS_UIView:
+  .asciz "_OBJC_CLASS_$_UIView"
+symbol_OBJC_CLASS_UIWindow:
+  .asciz "_OBJC_CLASS_$_UIWindow"
+symbol_OBJC_METACLASS_NSObject:
+  .asciz "_OBJC_METACLASS_$_NSObject"
+symbol_UIApplicationMain:
+  .asciz "_UIApplicationMain"
+symbol_CFConstantStringClassReference:
+  .asciz "___CFConstantStringClassReference"
+symbol_exit:
+  .asciz "_exit"
+symbol_objc_msgSend:
+  .asciz "_objc_msgSend"
+symbol_puts:
+  .asciz "_puts"
+  .p2align 2
+string_table_end:
+
+linkedit_segment_end:
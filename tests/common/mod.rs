@@ -0,0 +1,29 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Helpers shared by the integration tests.
+
+use std::env;
+use std::path::PathBuf;
+
+// adapted from `assert_cmd` crate
+pub fn target_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .map(|mut path| {
+            path.pop();
+            if path.ends_with("deps") {
+                path.pop();
+            }
+            path
+        })
+        .unwrap()
+}
+
+/// Path of the touchHLE binary being tested.
+pub fn touchhle_binary() -> PathBuf {
+    let binary_name = "touchHLE";
+    target_dir().join(format!("{}{}", binary_name, env::consts::EXE_SUFFIX))
+}
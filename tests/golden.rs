@@ -0,0 +1,252 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Golden-output regression tests.
+//!
+//! Each test runs an app bundle, optionally with scripted input, and compares
+//! what it outputs against reference files checked in under `tests/golden/`,
+//! in a directory named after the test:
+//!
+//! - `stdout.txt`: the expected standard output, i.e. the app's `printf()` and
+//!   `NSLog()` output. This must match exactly.
+//! - `input.txt` (optional): input to replay, in the `--replay=` format.
+//! - `frames/<ticks>.png`: the expected frame on screen at each of the guest
+//!   times listed for the test (see `--dump-frames=`), i.e. the last frame
+//!   presented at or before that time. These are compared with a small
+//!   tolerance, since graphics drivers don't all round the same way.
+//!
+//! The actual output of each test is written to `golden_output/<test name>/`
+//! in the target directory. To update the reference files after an intended
+//! change, run the tests with the environment variable
+//! `TOUCHHLE_UPDATE_GOLDEN=1`, and check the differences before committing.
+
+mod common;
+
+use std::ffi::{c_int, c_void};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use touchHLE_stb_image_wrapper::{stbi_failure_reason, stbi_image_free, stbi_load_from_memory};
+
+/// Maximum difference allowed between the value of a channel in a reference
+/// frame and the corresponding value in the actual frame.
+const CHANNEL_TOLERANCE: u8 = 8;
+/// Maximum proportion of pixels allowed to differ by more than
+/// [CHANNEL_TOLERANCE].
+const MAX_DIFFERING_PIXELS: f64 = 0.001;
+
+struct GoldenTest {
+    /// Name of the test's directory in `tests/golden/`.
+    name: &'static str,
+    /// App bundle to run, relative to `tests/`.
+    bundle: &'static str,
+    /// Options passed to touchHLE in addition to the ones for input and frame
+    /// capture.
+    options: &'static [&'static str],
+    /// Guest times (see `--dump-frames=`) at which to check the frame on
+    /// screen.
+    frames_at: &'static [u64],
+}
+
+fn update_mode() -> bool {
+    std::env::var_os("TOUCHHLE_UPDATE_GOLDEN").map_or(false, |value| value == "1")
+}
+
+fn run_golden_test(test: &GoldenTest) {
+    let tests_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let reference_dir = tests_dir.join("golden").join(test.name);
+    let output_dir = common::target_dir().join("golden_output").join(test.name);
+    let frames_dir = output_dir.join("frames");
+    if output_dir.exists() {
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let mut cmd = Command::new(common::touchhle_binary());
+    cmd.arg(tests_dir.join(test.bundle));
+    cmd.args(test.options);
+    let input_path = reference_dir.join("input.txt");
+    if input_path.exists() {
+        cmd.arg(format!("--replay={}", input_path.to_str().unwrap()));
+    }
+    if !test.frames_at.is_empty() {
+        cmd.arg(format!("--dump-frames={}", frames_dir.to_str().unwrap()));
+    }
+    let output = cmd.output().expect("failed to execute process");
+
+    std::fs::write(output_dir.join("stdout.txt"), &output.stdout).unwrap();
+    std::fs::write(output_dir.join("stderr.txt"), &output.stderr).unwrap();
+    assert!(
+        output.status.success(),
+        "{} exited with {}, see {:?} for its output",
+        test.name,
+        output.status,
+        output_dir.join("stderr.txt")
+    );
+
+    let mut failures = Vec::new();
+
+    let stdout_path = reference_dir.join("stdout.txt");
+    if update_mode() {
+        std::fs::create_dir_all(&reference_dir).unwrap();
+        std::fs::write(&stdout_path, &output.stdout).unwrap();
+    } else {
+        let expected = std::fs::read(&stdout_path).unwrap_or_else(|e| {
+            panic!("Could not read {:?}: {}", stdout_path, e);
+        });
+        // Git might have changed the line endings of the reference file.
+        let expected = String::from_utf8_lossy(&expected).replace("\r\n", "\n");
+        let actual = String::from_utf8_lossy(&output.stdout);
+        if expected != actual {
+            failures.push(format!(
+                "Standard output doesn't match {:?}.\nExpected:\n{}\nActual:\n{}",
+                stdout_path, expected, actual
+            ));
+        }
+    }
+
+    let dumped_frames = list_dumped_frames(&frames_dir);
+    for &ticks in test.frames_at {
+        let reference_path = reference_dir.join("frames").join(format!("{}.png", ticks));
+        let Some((_, actual_path)) = dumped_frames.iter().rev().find(|&&(t, _)| t <= ticks) else {
+            failures.push(format!("No frame was presented by guest time {}", ticks));
+            continue;
+        };
+        if update_mode() {
+            std::fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+            std::fs::copy(actual_path, &reference_path).unwrap();
+            continue;
+        }
+        if let Err(e) = compare_frames(&reference_path, actual_path) {
+            failures.push(format!(
+                "Frame at guest time {} ({:?}) doesn't match {:?}: {}",
+                ticks, actual_path, reference_path, e
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Golden test {} failed:\n{}",
+        test.name,
+        failures.join("\n")
+    );
+}
+
+/// List the frames written by `--dump-frames=`, with their guest times, in
+/// order of presentation.
+fn list_dumped_frames(frames_dir: &Path) -> Vec<(u64, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(frames_dir) else {
+        return Vec::new();
+    };
+    let mut frames = Vec::new();
+    for entry in entries {
+        let path = entry.unwrap().path();
+        // File names look like frame_000042_ticks_1234567.png
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        let mut parts = name.split('_');
+        let (Some("frame"), Some(number), Some("ticks"), Some(ticks)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let number: u64 = number.parse().unwrap();
+        let ticks: u64 = ticks.parse().unwrap();
+        frames.push((number, ticks, path));
+    }
+    frames.sort();
+    frames
+        .into_iter()
+        .map(|(_number, ticks, path)| (ticks, path))
+        .collect()
+}
+
+/// Decoded PNG file: dimensions and 8 bits per channel RGBA pixels.
+fn load_png(path: &Path) -> Result<((u32, u32), Vec<u8>), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+    let mut x: c_int = 0;
+    let mut y: c_int = 0;
+    let mut _channels_in_file: c_int = 0;
+    unsafe {
+        let pixels = stbi_load_from_memory(
+            bytes.as_ptr(),
+            bytes.len().try_into().unwrap(),
+            &mut x,
+            &mut y,
+            &mut _channels_in_file,
+            4,
+        );
+        if pixels.is_null() {
+            let reason = std::ffi::CStr::from_ptr(stbi_failure_reason());
+            return Err(format!("Could not decode {:?}: {:?}", path, reason));
+        }
+        let len = x as usize * y as usize * 4;
+        let vec = std::slice::from_raw_parts(pixels, len).to_vec();
+        stbi_image_free(pixels as *mut c_void);
+        Ok(((x as u32, y as u32), vec))
+    }
+}
+
+fn compare_frames(reference_path: &Path, actual_path: &Path) -> Result<(), String> {
+    let (reference_size, reference) = load_png(reference_path)?;
+    let (actual_size, actual) = load_png(actual_path)?;
+    if reference_size != actual_size {
+        return Err(format!(
+            "size is {:?}, expected {:?}",
+            actual_size, reference_size
+        ));
+    }
+    let differing_pixels = reference
+        .chunks_exact(4)
+        .zip(actual.chunks_exact(4))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(&a, &b)| a.abs_diff(b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    let pixel_count = reference_size.0 as usize * reference_size.1 as usize;
+    if differing_pixels as f64 > pixel_count as f64 * MAX_DIFFERING_PIXELS {
+        return Err(format!(
+            "{} of {} pixels differ",
+            differing_pixels, pixel_count
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_app() {
+    run_golden_test(&GoldenTest {
+        name: "TestApp",
+        bundle: "TestApp.app",
+        // Headless mode means the test can run without a display server or
+        // GPU.
+        options: &["--headless"],
+        frames_at: &[],
+    });
+}
+
+#[test]
+fn test_archive_app() {
+    run_golden_test(&GoldenTest {
+        name: "TestArchiveApp",
+        bundle: "TestArchiveApp.app",
+        options: &["--headless"],
+        frames_at: &[],
+    });
+}
+
+#[test]
+fn test_ui_app() {
+    run_golden_test(&GoldenTest {
+        name: "TestUIApp",
+        bundle: "TestUIApp.app",
+        // The compositor draws in software, so frames can be captured even in
+        // headless mode.
+        options: &["--headless"],
+        // Before and after the button is tapped (see input.txt).
+        frames_at: &[500_000, 2_000_000],
+    });
+}
@@ -1,22 +1,9 @@
-use std::env;
+mod common;
+
 use std::env::current_dir;
 use std::io::Write;
 use std::process::Command;
 
-// adapted from `assert_cmd` crate
-fn target_dir() -> std::path::PathBuf {
-    env::current_exe()
-        .ok()
-        .map(|mut path| {
-            path.pop();
-            if path.ends_with("deps") {
-                path.pop();
-            }
-            path
-        })
-        .unwrap()
-}
-
 // https://stackoverflow.com/a/35907071/2241008
 fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack
@@ -26,17 +13,16 @@ fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 #[test]
 fn run_test_app() -> Result<(), Box<dyn std::error::Error>> {
-    let binary_name = "touchHLE";
-    let binary_path = target_dir().join(format!("{}{}", binary_name, env::consts::EXE_SUFFIX));
-
-    let mut cmd = Command::new(binary_path);
+    let mut cmd = Command::new(common::touchhle_binary());
 
     let mut test_app_path = current_dir()?;
     test_app_path.push("tests");
     test_app_path.push("TestApp.app");
 
+    // Headless mode means the test can run without a display server or GPU.
     let output = cmd
         .arg(test_app_path)
+        .arg("--headless")
         .output()
         .expect("failed to execute process");
 